@@ -0,0 +1,96 @@
+//! # glwfr_derive
+//!
+//! Derive macros for `glwfr`. Enable them through the `derive` feature of `glwfr`
+//! instead of depending on this crate directly.
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields};
+
+/// Derives `glwfr::graphics::gl_wrapper::Vertex` for a struct with named fields.
+///
+/// Each field becomes one vertex attribute. Indices follow the field order and offsets
+/// are computed with `std::mem::offset_of!`, so the struct should be `#[repr(C)]`.
+///
+/// Field attributes:
+/// - `#[vertex(normalized)]` - upload integer components as normalized values.
+/// - `#[vertex(skip)]` - do not generate an attribute for this field.
+#[proc_macro_derive(Vertex, attributes(vertex))]
+pub fn derive_vertex(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand_vertex(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn expand_vertex(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let name = &input.ident;
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    name,
+                    "Vertex can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                name,
+                "Vertex can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut attributes = Vec::new();
+    let mut index = 0u32;
+    for field in fields {
+        let mut normalized = false;
+        let mut skip = false;
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("vertex")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("normalized") {
+                    normalized = true;
+                    Ok(())
+                } else if meta.path.is_ident("skip") {
+                    skip = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("unknown vertex attribute, expected `normalized` or `skip`"))
+                }
+            })?;
+        }
+        if skip {
+            continue;
+        }
+
+        let ident = field.ident.as_ref().expect("named field");
+        let ty = &field.ty;
+        let normalized = if normalized {
+            quote!(::glwfr::gl::TRUE)
+        } else {
+            quote!(::glwfr::gl::FALSE)
+        };
+        attributes.push(quote! {
+            ::glwfr::graphics::gl_wrapper::VertexAttributeLayout {
+                index: #index,
+                size: <#ty as ::glwfr::graphics::gl_wrapper::VertexComponent>::SIZE,
+                r#type: <#ty as ::glwfr::graphics::gl_wrapper::VertexComponent>::GL_TYPE,
+                normalized: #normalized,
+                offset: ::std::mem::offset_of!(#name, #ident),
+            }
+        });
+        index += 1;
+    }
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::glwfr::graphics::gl_wrapper::Vertex for #name #ty_generics #where_clause {
+            fn layout() -> ::std::vec::Vec<::glwfr::graphics::gl_wrapper::VertexAttributeLayout> {
+                ::std::vec![#(#attributes),*]
+            }
+        }
+    })
+}
@@ -9,9 +9,13 @@
 //! let audio_system = AudioSystem::new().expect("Failed to initialize audio system");
 //! ```
 
-use crate::audio::Sound;
+use crate::audio::{
+    Attenuation, Caption, CaptionEvent, CaptionTrack, Listener, MusicPlayer, Sound,
+};
 use crate::custom_errors::Errors;
-use rodio::{OutputStream, Sink};
+use cgmath::Vector3;
+use rodio::cpal::traits::HostTrait;
+use rodio::{DeviceTrait, OutputStream, Sink};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 
@@ -20,19 +24,188 @@ pub struct AudioSystem {
     _stream: OutputStream,
     stream_handle: rodio::OutputStreamHandle,
     sounds: HashMap<String, Arc<Mutex<Sound>>>,
+    music: MusicPlayer,
+    listener: Listener,
+    output_channels: u16,
+    output_sample_rate: u32,
+    caption_events: Vec<(String, CaptionEvent)>,
 }
 
 impl AudioSystem {
     /// Creates a new audio system.
     pub fn new() -> Result<Self, Errors> {
         let (_stream, stream_handle) = OutputStream::try_default()?;
+        let music = MusicPlayer::new(stream_handle.clone());
+        // The stream is opened with the default configuration of the default device; if it
+        // fell back to another device, sounds prepared for this format are converted again
+        // by the mixer
+        let (output_channels, output_sample_rate) = rodio::cpal::default_host()
+            .default_output_device()
+            .and_then(|device| device.default_output_config().ok())
+            .map_or((2, 44100), |config| {
+                (config.channels(), config.sample_rate().0)
+            });
         Ok(Self {
             _stream,
             stream_handle,
             sounds: HashMap::new(),
+            music,
+            listener: Listener::default(),
+            output_channels,
+            output_sample_rate,
+            caption_events: Vec::new(),
         })  
     }
 
+    /// Updates the audio system. Call this once per frame to keep the music playlist going
+    /// and to attenuate and doppler shift spatial sounds for the current listener. Caption
+    /// events are collected for `take_caption_events`.
+    pub fn update(&mut self) -> Result<(), Errors> {
+        for (name, sound) in &self.sounds {
+            let mut sound = sound.lock().unwrap();
+            sound.apply_listener(&self.listener);
+            self.caption_events.extend(
+                sound
+                    .update_captions()
+                    .into_iter()
+                    .map(|event| (name.clone(), event)),
+            );
+        }
+        self.music.update()
+    }
+
+    /// Returns the listener spatial sounds are heard from.
+    pub fn listener(&self) -> &Listener {
+        &self.listener
+    }
+
+    /// Returns a mutable reference to the listener, e.g. to follow the camera.
+    pub fn listener_mut(&mut self) -> &mut Listener {
+        &mut self.listener
+    }
+
+    /// Places a sound in the world, or makes it non-spatial with `None`.
+    pub fn set_sound_position(
+        &self,
+        name: &str,
+        position: Option<Vector3<f32>>,
+    ) -> Result<(), Errors> {
+        if let Some(sound) = self.sounds.get(name) {
+            let mut sound = sound.lock().unwrap();
+            sound.set_position(position);
+            sound.apply_listener(&self.listener);
+            Ok(())
+        } else {
+            Err(Errors::SoundNotFoundError(name.to_string()))
+        }
+    }
+
+    /// Sets the velocity of a spatial sound in world units per second, used for the doppler
+    /// effect.
+    pub fn set_sound_velocity(&self, name: &str, velocity: Vector3<f32>) -> Result<(), Errors> {
+        if let Some(sound) = self.sounds.get(name) {
+            let mut sound = sound.lock().unwrap();
+            sound.set_velocity(velocity);
+            sound.apply_listener(&self.listener);
+            Ok(())
+        } else {
+            Err(Errors::SoundNotFoundError(name.to_string()))
+        }
+    }
+
+    /// Moves a spatial sound and derives its velocity from the distance travelled since the
+    /// previous position, see `Sound::move_to`.
+    pub fn move_sound(
+        &self,
+        name: &str,
+        position: Vector3<f32>,
+        delta_time: f32,
+    ) -> Result<(), Errors> {
+        if let Some(sound) = self.sounds.get(name) {
+            let mut sound = sound.lock().unwrap();
+            sound.move_to(position, delta_time);
+            sound.apply_listener(&self.listener);
+            Ok(())
+        } else {
+            Err(Errors::SoundNotFoundError(name.to_string()))
+        }
+    }
+
+    /// Sets how a spatial sound fades with distance to the listener.
+    pub fn set_sound_attenuation(
+        &self,
+        name: &str,
+        attenuation: Attenuation,
+    ) -> Result<(), Errors> {
+        if let Some(sound) = self.sounds.get(name) {
+            let mut sound = sound.lock().unwrap();
+            sound.set_attenuation(attenuation);
+            sound.apply_listener(&self.listener);
+            Ok(())
+        } else {
+            Err(Errors::SoundNotFoundError(name.to_string()))
+        }
+    }
+
+    /// Sets the captions shown while a sound plays, or removes them with `None`.
+    pub fn set_sound_captions(
+        &self,
+        name: &str,
+        captions: Option<CaptionTrack>,
+    ) -> Result<(), Errors> {
+        if let Some(sound) = self.sounds.get(name) {
+            sound.lock().unwrap().set_captions(captions);
+            Ok(())
+        } else {
+            Err(Errors::SoundNotFoundError(name.to_string()))
+        }
+    }
+
+    /// Returns the caption events collected by `update` since the last call, with the name
+    /// of the sound each belongs to.
+    pub fn take_caption_events(&mut self) -> Vec<(String, CaptionEvent)> {
+        std::mem::take(&mut self.caption_events)
+    }
+
+    /// Returns the captions visible at the last `update` with the name of their sound,
+    /// ordered by their start time.
+    pub fn active_captions(&self) -> Vec<(String, Caption)> {
+        let mut captions: Vec<(String, Caption)> = self
+            .sounds
+            .iter()
+            .flat_map(|(name, sound)| {
+                let sound = sound.lock().unwrap();
+                sound
+                    .captions()
+                    .map(|track| {
+                        track
+                            .active()
+                            .map(|caption| (name.clone(), caption.clone()))
+                            .collect::<Vec<_>>()
+                    })
+                    .unwrap_or_default()
+            })
+            .collect();
+        captions.sort_by_key(|(_, caption)| caption.start);
+        captions
+    }
+
+    /// Replaces the music playlist with the given tracks and starts playing the first one.
+    ///
+    /// Tracks are streamed from disk and play back to back without gaps.
+    pub fn queue_music<I, S>(&mut self, tracks: I) -> Result<(), Errors>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.music.queue(tracks)
+    }
+
+    /// Returns the music player, e.g. to skip tracks or change the repeat mode.
+    pub fn music(&mut self) -> &mut MusicPlayer {
+        &mut self.music
+    }
+
     /// Loads a sound from a file and stores it with a given name.
     pub fn load_sound(&mut self, name: &str, file_path: &str) -> Result<(), Errors> {
         let sound = Sound::new(file_path)?;
@@ -41,6 +214,32 @@ impl AudioSystem {
         Ok(())
     }
 
+    /// Loads a short sound effect and prepares it for the output stream, so triggering it
+    /// does not copy or convert its samples, see `Sound::prepare`.
+    pub fn load_sfx(&mut self, name: &str, file_path: &str) -> Result<(), Errors> {
+        let mut sound = Sound::new(file_path)?;
+        sound.prepare(self.output_channels, self.output_sample_rate);
+        self.sounds
+            .insert(name.to_string(), Arc::new(Mutex::new(sound)));
+        Ok(())
+    }
+
+    /// Prepares an already loaded sound for the output stream, see `load_sfx`.
+    pub fn prepare_sound(&self, name: &str) -> Result<(), Errors> {
+        if let Some(sound) = self.sounds.get(name) {
+            let mut sound = sound.lock().unwrap();
+            sound.prepare(self.output_channels, self.output_sample_rate);
+            Ok(())
+        } else {
+            Err(Errors::SoundNotFoundError(name.to_string()))
+        }
+    }
+
+    /// Returns the channel count and sample rate sounds are prepared for.
+    pub fn output_format(&self) -> (u16, u32) {
+        (self.output_channels, self.output_sample_rate)
+    }
+
     /// Plays a sound once by its name.
     pub fn play_sound_once(&mut self, name: &str) -> Result<(), Errors> {
         if let Some(sound) = self.sounds.get(name) {
@@ -0,0 +1,181 @@
+//! # Beat Module
+//! Tracks the beats and bars of music for rhythm-synced gameplay and audio-reactive visuals.
+//!
+//! A `BeatClock` is fed the playback position of the music every frame and reports the
+//! beats and bars that passed since the previous update, both as returned events and through
+//! optional callbacks.
+//!
+//! # Example
+//! ```rust
+//! use glwfr::audio::{AudioSystem, BeatClock, BeatEvent};
+//!
+//! fn main() -> Result<(), glwfr::custom_errors::Errors> {
+//!     let mut audio_system = AudioSystem::new()?;
+//!     audio_system.queue_music(["track_120bpm.ogg"])?;
+//!
+//!     let mut clock = BeatClock::new(120.0);
+//!     clock.on_bar(|bar| println!("Bar {}", bar));
+//!
+//!     // Once per frame
+//!     audio_system.update()?;
+//!     for event in clock.update(audio_system.music().position()) {
+//!         if let BeatEvent::Beat(_) = event {
+//!             // Pulse the lights
+//!         }
+//!     }
+//!     let until_next = clock.time_to_next_beat();
+//!     Ok(())
+//! }
+//! ```
+
+use std::time::Duration;
+
+/// A beat or bar boundary passed by a `BeatClock`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BeatEvent {
+    /// A beat started. Contains the zero-based beat index.
+    Beat(u64),
+    /// A bar started. Contains the zero-based bar index.
+    Bar(u64),
+}
+
+/// Converts a music playback position into beats and bars.
+pub struct BeatClock {
+    bpm: f64,
+    beats_per_bar: u32,
+    offset: Duration,
+    position: Duration,
+    last_beat: Option<u64>,
+    beat_callbacks: Vec<Box<dyn FnMut(u64)>>,
+    bar_callbacks: Vec<Box<dyn FnMut(u64)>>,
+}
+
+impl BeatClock {
+    /// Creates a new beat clock for music with the given tempo in 4/4 time.
+    pub fn new(bpm: f64) -> Self {
+        Self {
+            bpm: bpm.max(f64::EPSILON),
+            beats_per_bar: 4,
+            offset: Duration::ZERO,
+            position: Duration::ZERO,
+            last_beat: None,
+            beat_callbacks: Vec::new(),
+            bar_callbacks: Vec::new(),
+        }
+    }
+
+    /// Sets the tempo in beats per minute.
+    pub fn set_bpm(&mut self, bpm: f64) {
+        self.bpm = bpm.max(f64::EPSILON);
+    }
+
+    /// Returns the tempo in beats per minute.
+    pub fn bpm(&self) -> f64 {
+        self.bpm
+    }
+
+    /// Sets the number of beats per bar, e.g. `3` for a waltz.
+    pub fn set_beats_per_bar(&mut self, beats_per_bar: u32) {
+        self.beats_per_bar = beats_per_bar.max(1);
+    }
+
+    /// Returns the number of beats per bar.
+    pub fn beats_per_bar(&self) -> u32 {
+        self.beats_per_bar
+    }
+
+    /// Sets the playback position of the first beat, for music with a lead-in.
+    pub fn set_offset(&mut self, offset: Duration) {
+        self.offset = offset;
+    }
+
+    /// Returns the playback position of the first beat.
+    pub fn offset(&self) -> Duration {
+        self.offset
+    }
+
+    /// Registers a callback invoked with the beat index whenever a beat starts.
+    pub fn on_beat(&mut self, callback: impl FnMut(u64) + 'static) {
+        self.beat_callbacks.push(Box::new(callback));
+    }
+
+    /// Registers a callback invoked with the bar index whenever a bar starts.
+    pub fn on_bar(&mut self, callback: impl FnMut(u64) + 'static) {
+        self.bar_callbacks.push(Box::new(callback));
+    }
+
+    /// Returns the duration of a single beat.
+    pub fn beat_duration(&self) -> Duration {
+        Duration::from_secs_f64(60.0 / self.bpm)
+    }
+
+    /// Advances the clock to the given playback position.
+    ///
+    /// Jumping backwards, e.g. when the music loops, restarts the count without firing the
+    /// skipped beats.
+    ///
+    /// # Returns
+    ///
+    /// The beats and bars that started since the previous update, in order. At most one
+    /// bar's worth of beats is reported after a long stall.
+    pub fn update(&mut self, position: Duration) -> Vec<BeatEvent> {
+        if position < self.position {
+            self.last_beat = None;
+        }
+        self.position = position;
+
+        let Some(current) = self.current_beat() else {
+            return Vec::new();
+        };
+        let first = match self.last_beat {
+            Some(last) if current <= last => return Vec::new(),
+            Some(last) => (last + 1).max(current.saturating_sub(self.beats_per_bar as u64 - 1)),
+            None => current,
+        };
+        self.last_beat = Some(current);
+
+        let mut events = Vec::new();
+        for beat in first..=current {
+            if beat % self.beats_per_bar as u64 == 0 {
+                let bar = beat / self.beats_per_bar as u64;
+                events.push(BeatEvent::Bar(bar));
+                for callback in &mut self.bar_callbacks {
+                    callback(bar);
+                }
+            }
+            events.push(BeatEvent::Beat(beat));
+            for callback in &mut self.beat_callbacks {
+                callback(beat);
+            }
+        }
+        events
+    }
+
+    /// Returns the index of the current beat, or `None` before the first beat.
+    pub fn current_beat(&self) -> Option<u64> {
+        let elapsed = self.position.checked_sub(self.offset)?;
+        Some((elapsed.as_secs_f64() * self.bpm / 60.0) as u64)
+    }
+
+    /// Returns the index of the current bar, or `None` before the first beat.
+    pub fn current_bar(&self) -> Option<u64> {
+        self.current_beat()
+            .map(|beat| beat / self.beats_per_bar as u64)
+    }
+
+    /// Returns how far the current beat has progressed, from `0.0` at the beat to `1.0`.
+    pub fn beat_phase(&self) -> f32 {
+        match self.position.checked_sub(self.offset) {
+            Some(elapsed) => (elapsed.as_secs_f64() * self.bpm / 60.0).fract() as f32,
+            None => 0.0,
+        }
+    }
+
+    /// Returns the time until the next beat starts.
+    pub fn time_to_next_beat(&self) -> Duration {
+        match self.position.checked_sub(self.offset) {
+            Some(_) => self.beat_duration().mul_f64(1.0 - self.beat_phase() as f64),
+            None => self.offset - self.position,
+        }
+    }
+}
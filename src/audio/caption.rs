@@ -0,0 +1,196 @@
+//! # Caption Module
+//! Timed captions attached to sounds, for subtitling dialogue and describing sound effects.
+//!
+//! A `CaptionTrack` holds captions with a start time and a duration relative to the start
+//! of a sound. While the sound plays, `AudioSystem::update` reports when captions appear and
+//! disappear as `CaptionEvent`s, and `AudioSystem::active_captions` returns the ones to show
+//! right now. Captions follow the playback position, so pausing a sound keeps its caption on
+//! screen and looping sounds show their captions again on every loop.
+//!
+//! # Example
+//! ```rust
+//! use glwfr::audio::{AudioSystem, Caption, CaptionEvent, CaptionTrack};
+//! use std::time::Duration;
+//!
+//! fn main() -> Result<(), glwfr::custom_errors::Errors> {
+//!     let mut audio_system = AudioSystem::new()?;
+//!     audio_system.load_sound("greeting", "dialogue/greeting.ogg")?;
+//!     let captions = CaptionTrack::new()
+//!         .with_caption(
+//!             Caption::new(Duration::ZERO, Duration::from_secs(2), "Welcome back.")
+//!                 .with_speaker("Guard"),
+//!         )
+//!         .with_caption(Caption::new(
+//!             Duration::from_millis(2500),
+//!             Duration::from_secs(1),
+//!             "[door creaks]",
+//!         ));
+//!     audio_system.set_sound_captions("greeting", Some(captions))?;
+//!     audio_system.play_sound_once("greeting")?;
+//!
+//!     // Once per frame
+//!     audio_system.update()?;
+//!     for (sound, event) in audio_system.take_caption_events() {
+//!         if let CaptionEvent::Shown(caption) = event {
+//!             println!("{}: {}", sound, caption);
+//!         }
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use std::fmt;
+use std::time::Duration;
+
+/// A line of text shown while part of a sound plays.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Caption {
+    /// The playback position the caption appears at.
+    pub start: Duration,
+    /// How long the caption stays visible.
+    pub duration: Duration,
+    /// The text to show.
+    pub text: String,
+    /// The name of the character speaking, or `None` for narration and sound descriptions.
+    pub speaker: Option<String>,
+}
+
+impl Caption {
+    /// Creates a caption without a speaker.
+    pub fn new(start: Duration, duration: Duration, text: impl Into<String>) -> Self {
+        Self {
+            start,
+            duration,
+            text: text.into(),
+            speaker: None,
+        }
+    }
+
+    /// Returns the caption with the given speaker.
+    pub fn with_speaker(mut self, speaker: impl Into<String>) -> Self {
+        self.speaker = Some(speaker.into());
+        self
+    }
+
+    /// Returns the playback position the caption disappears at.
+    pub fn end(&self) -> Duration {
+        self.start + self.duration
+    }
+
+    /// Checks if the caption is visible at the given playback position.
+    pub fn is_active_at(&self, position: Duration) -> bool {
+        self.start <= position && position < self.end()
+    }
+}
+
+impl fmt::Display for Caption {
+    /// Formats the caption as `"Speaker: text"`, or just the text without a speaker.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.speaker {
+            Some(speaker) => write!(f, "{}: {}", speaker, self.text),
+            None => f.write_str(&self.text),
+        }
+    }
+}
+
+/// A caption appearing or disappearing during playback.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum CaptionEvent {
+    /// The caption became visible.
+    Shown(Caption),
+    /// The caption is no longer visible, because its time ran out or the sound stopped.
+    Hidden(Caption),
+}
+
+/// The captions of a sound, ordered by their start time.
+#[derive(Debug, Clone, Default)]
+pub struct CaptionTrack {
+    captions: Vec<Caption>,
+    /// Indices of the captions visible at the last update.
+    active: Vec<usize>,
+    last_position: Option<Duration>,
+}
+
+impl CaptionTrack {
+    /// Creates an empty caption track.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the track with the given caption added.
+    pub fn with_caption(mut self, caption: Caption) -> Self {
+        self.add(caption);
+        self
+    }
+
+    /// Adds a caption, keeping the captions ordered by their start time.
+    pub fn add(&mut self, caption: Caption) {
+        let index = self
+            .captions
+            .partition_point(|other| other.start <= caption.start);
+        self.captions.insert(index, caption);
+        // Indices after the new caption shifted by one
+        for active in &mut self.active {
+            if *active >= index {
+                *active += 1;
+            }
+        }
+    }
+
+    /// Returns all captions, ordered by their start time.
+    pub fn captions(&self) -> &[Caption] {
+        &self.captions
+    }
+
+    /// Returns the captions visible at the last update.
+    pub fn active(&self) -> impl Iterator<Item = &Caption> {
+        self.active.iter().map(|&index| &self.captions[index])
+    }
+
+    /// Advances the track to the given playback position.
+    ///
+    /// Jumping backwards, e.g. when the sound loops or restarts, hides the visible captions
+    /// and shows the ones at the new position.
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - The playback position, or `None` if the sound is not playing, which
+    ///   hides all captions.
+    ///
+    /// # Returns
+    ///
+    /// The captions hidden and shown since the previous update, hidden ones first.
+    pub fn update(&mut self, position: Option<Duration>) -> Vec<CaptionEvent> {
+        let restarted = match (position, self.last_position) {
+            (Some(position), Some(last)) => position < last,
+            _ => false,
+        };
+        self.last_position = position;
+
+        let active: Vec<usize> = match position {
+            Some(position) => self
+                .captions
+                .iter()
+                .enumerate()
+                .take_while(|(_, caption)| caption.start <= position)
+                .filter(|(_, caption)| caption.is_active_at(position))
+                .map(|(index, _)| index)
+                .collect(),
+            None => Vec::new(),
+        };
+
+        let mut events = Vec::new();
+        for &index in &self.active {
+            if restarted || !active.contains(&index) {
+                events.push(CaptionEvent::Hidden(self.captions[index].clone()));
+            }
+        }
+        for &index in &active {
+            if restarted || !self.active.contains(&index) {
+                events.push(CaptionEvent::Shown(self.captions[index].clone()));
+            }
+        }
+        self.active = active;
+        events
+    }
+}
@@ -4,7 +4,14 @@
 //!
 //! ## Submodules
 //! - **audio**: The main audio system for managing sounds.
+//! - **beat**: Beat and bar events synced to music playback.
+//! - **caption**: Timed captions for dialogue and sound effects.
+//! - **music**: A music playlist with gapless transitions and looping tracks.
 //! - **sound**: Represents a sound that can be played.
+//! - **spatial**: Distance attenuation and doppler shift for sounds placed in the world.
+//! - **web**: The audio system on the Web Audio API, used by the `web` backend.
+//!
+//! The music player and `Sound` are built on rodio and need the `native` feature.
 //!
 //! ## Example
 //! ```rust
@@ -24,8 +31,26 @@
 //! }
 //! ```
 
+#[cfg(feature = "native")]
 pub mod audio;
+pub mod beat;
+pub mod caption;
+#[cfg(feature = "native")]
+pub mod music;
+#[cfg(feature = "native")]
 pub mod sound;
+pub mod spatial;
+#[cfg(all(feature = "web", not(feature = "native")))]
+pub mod web;
 
+#[cfg(feature = "native")]
 pub use audio::*;
+pub use beat::*;
+pub use caption::*;
+#[cfg(feature = "native")]
+pub use music::*;
+#[cfg(feature = "native")]
 pub use sound::*;
+pub use spatial::*;
+#[cfg(all(feature = "web", not(feature = "native")))]
+pub use web::*;
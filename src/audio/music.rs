@@ -0,0 +1,466 @@
+//! # Music Module
+//! Plays a playlist of music tracks back to back.
+//!
+//! Tracks are streamed from disk instead of being decoded up front, and the next track is
+//! queued while the current one plays, so transitions are gapless. `MusicPlayer::update`
+//! has to be called regularly (e.g. once per frame) to keep the playlist going.
+//!
+//! Game music that should loop forever can be played with sample-accurate `LoopPoints`, or
+//! as an intro file followed by a loop file, so the intro is heard once and the loop section
+//! repeats without a gap.
+//!
+//! # Example
+//! ```rust
+//! use glwfr::audio::{AudioSystem, RepeatMode};
+//!
+//! fn main() -> Result<(), glwfr::custom_errors::Errors> {
+//!     let mut audio_system = AudioSystem::new()?;
+//!     audio_system.queue_music(["intro.ogg", "loop_a.ogg", "loop_b.ogg"])?;
+//!     audio_system.music().set_repeat_mode(RepeatMode::All);
+//!
+//!     // Once per frame
+//!     audio_system.update()?;
+//!     Ok(())
+//! }
+//!
+//! fn boss_music(audio_system: &mut AudioSystem) -> Result<(), glwfr::custom_errors::Errors> {
+//!     use glwfr::audio::LoopPoints;
+//!
+//!     // The intro ends at sample frame 211680; the loop section repeats until the end
+//!     audio_system.music().play_looped("boss.ogg", LoopPoints::new(211_680, None))?;
+//!     // Or with the intro and the loop section in separate files
+//!     audio_system.music().play_intro_loop("boss_intro.ogg", "boss_loop.ogg")
+//! }
+//! ```
+
+use crate::custom_errors::Errors;
+use crate::math::noise::Xorshift;
+use rodio::{Decoder, OutputStreamHandle, Sink, Source};
+use std::fs::File;
+use std::io::BufReader;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// What happens when the end of the playlist is reached.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum RepeatMode {
+    /// Stop after the last track.
+    #[default]
+    Off,
+    /// Start over with the first track.
+    All,
+    /// Repeat the current track forever.
+    One,
+}
+
+/// The section of a track that repeats, in sample frames (one sample per channel).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct LoopPoints {
+    /// The first frame of the loop section. Everything before it plays once as the intro.
+    pub start: u64,
+    /// The frame after the last one of the loop section, or `None` to loop at the end of
+    /// the track. Anything after it is never heard.
+    pub end: Option<u64>,
+}
+
+impl LoopPoints {
+    /// Creates loop points from the frame the loop starts at and the frame it jumps back at.
+    pub fn new(start: u64, end: Option<u64>) -> Self {
+        Self { start, end }
+    }
+
+    /// Creates loop points that repeat the whole track.
+    pub fn whole_track() -> Self {
+        Self::default()
+    }
+}
+
+/// A fully decoded track that plays up to its loop end, then continues at its loop start.
+struct LoopingSource {
+    samples: Vec<f32>,
+    channels: u16,
+    sample_rate: u32,
+    loop_start: usize,
+    loop_end: usize,
+    position: usize,
+}
+
+impl LoopingSource {
+    /// Decodes a track and checks the loop points against its length.
+    fn new(file_path: &str, loop_points: LoopPoints) -> Result<Self, Errors> {
+        let decoder = MusicPlayer::open(file_path)?;
+        let channels = decoder.channels();
+        let sample_rate = decoder.sample_rate();
+        let samples: Vec<f32> = decoder.convert_samples().collect();
+        let frame_count = samples.len() as u64 / channels.max(1) as u64;
+        let end = loop_points.end.unwrap_or(frame_count).min(frame_count);
+        if loop_points.start >= end {
+            return Err(Errors::SoundPlayError(format!(
+                "Loop start {} must be before the loop end {} of {}",
+                loop_points.start, end, file_path
+            )));
+        }
+        Ok(Self {
+            samples,
+            channels,
+            sample_rate,
+            loop_start: (loop_points.start * channels as u64) as usize,
+            loop_end: (end * channels as u64) as usize,
+            position: 0,
+        })
+    }
+}
+
+impl Iterator for LoopingSource {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.position >= self.loop_end {
+            self.position = self.loop_start;
+        }
+        let sample = self.samples[self.position];
+        self.position += 1;
+        Some(sample)
+    }
+}
+
+impl Source for LoopingSource {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+/// Plays a playlist of music tracks with gapless transitions.
+pub struct MusicPlayer {
+    stream_handle: OutputStreamHandle,
+    tracks: Vec<String>,
+    order: Vec<usize>,
+    current: Option<usize>,
+    queued: Option<usize>,
+    repeat_mode: RepeatMode,
+    shuffle: bool,
+    volume: f32,
+    sink: Option<Sink>,
+    /// Whether the current track loops forever, so nothing is queued after it.
+    endless: bool,
+}
+
+impl MusicPlayer {
+    /// Creates a new music player with an empty playlist.
+    pub fn new(stream_handle: OutputStreamHandle) -> Self {
+        Self {
+            stream_handle,
+            tracks: Vec::new(),
+            order: Vec::new(),
+            current: None,
+            queued: None,
+            repeat_mode: RepeatMode::Off,
+            shuffle: false,
+            volume: 1.0,
+            sink: None,
+            endless: false,
+        }
+    }
+
+    /// Replaces the playlist with the given tracks and starts playing the first one.
+    pub fn queue<I, S>(&mut self, tracks: I) -> Result<(), Errors>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.stop();
+        self.tracks = tracks.into_iter().map(Into::into).collect();
+        self.rebuild_order();
+        match self.order.first() {
+            Some(&first) => self.play_track(first),
+            None => Ok(()),
+        }
+    }
+
+    /// Replaces the playlist with a single track that loops forever between the given points.
+    ///
+    /// The part before `loop_points.start` plays once, then the loop section repeats without
+    /// a gap. The track is decoded into memory up front instead of being streamed, so it
+    /// can jump back to the exact sample.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::SoundPlayError` if the loop start is not before the loop end or
+    /// the end of the track, or an error if the file cannot be read or decoded.
+    pub fn play_looped(&mut self, file_path: &str, loop_points: LoopPoints) -> Result<(), Errors> {
+        let source = LoopingSource::new(file_path, loop_points)?;
+        self.play_endless(vec![file_path.to_string()], |sink| sink.append(source))
+    }
+
+    /// Replaces the playlist with an intro that plays once, followed by a loop that repeats
+    /// forever.
+    ///
+    /// The loop starts right after the last sample of the intro. The intro is streamed and
+    /// the loop is decoded into memory. `current_track` returns the intro until `stop` is
+    /// called.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either file cannot be read or decoded.
+    pub fn play_intro_loop(&mut self, intro_path: &str, loop_path: &str) -> Result<(), Errors> {
+        let intro = Self::open(intro_path)?;
+        let looped = LoopingSource::new(loop_path, LoopPoints::whole_track())?;
+        self.play_endless(
+            vec![intro_path.to_string(), loop_path.to_string()],
+            |sink| {
+                sink.append(intro);
+                sink.append(looped);
+            },
+        )
+    }
+
+    /// Checks if the current track loops forever, see `play_looped` and `play_intro_loop`.
+    pub fn is_endless(&self) -> bool {
+        self.endless
+    }
+
+    /// Appends a track to the end of the playlist.
+    pub fn add_track(&mut self, file_path: &str) -> Result<(), Errors> {
+        self.tracks.push(file_path.to_string());
+        self.order.push(self.tracks.len() - 1);
+        if self.is_playing() {
+            self.preload()
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Advances the playlist when the current track has finished and queues the next one.
+    ///
+    /// Call this regularly, e.g. once per frame.
+    pub fn update(&mut self) -> Result<(), Errors> {
+        let Some(sink) = &self.sink else {
+            return Ok(());
+        };
+        if sink.empty() {
+            self.sink = None;
+            self.current = None;
+            self.queued = None;
+        } else if self.queued.is_some() && sink.len() <= 1 {
+            // The current track finished and the queued one is now playing
+            self.current = self.queued.take();
+            self.preload()?;
+        }
+        Ok(())
+    }
+
+    /// Skips to the next track in the playlist.
+    pub fn skip(&mut self) -> Result<(), Errors> {
+        match self.current.and_then(|track| self.next_track(track, true)) {
+            Some(track) => self.play_track(track),
+            None => {
+                self.stop();
+                Ok(())
+            }
+        }
+    }
+
+    /// Goes back to the previous track in the playlist, or restarts the first track.
+    pub fn previous(&mut self) -> Result<(), Errors> {
+        let Some(position) = self.current.and_then(|track| self.position_of(track)) else {
+            return Ok(());
+        };
+        let position = match (position, self.repeat_mode) {
+            (0, RepeatMode::All) => self.order.len() - 1,
+            (0, _) => 0,
+            (position, _) => position - 1,
+        };
+        self.play_track(self.order[position])
+    }
+
+    /// Pauses the music.
+    pub fn pause(&self) {
+        if let Some(sink) = &self.sink {
+            sink.pause();
+        }
+    }
+
+    /// Resumes the music after `pause`.
+    pub fn resume(&self) {
+        if let Some(sink) = &self.sink {
+            sink.play();
+        }
+    }
+
+    /// Stops the music. The playlist is kept.
+    pub fn stop(&mut self) {
+        self.sink = None;
+        self.current = None;
+        self.queued = None;
+        self.endless = false;
+    }
+
+    /// Checks if a track is currently playing or paused.
+    pub fn is_playing(&self) -> bool {
+        self.sink.is_some()
+    }
+
+    /// Returns the playback position within the current track.
+    ///
+    /// For endless tracks this is the time since playback started, including all loops.
+    pub fn position(&self) -> Duration {
+        match &self.sink {
+            Some(sink) => sink.get_pos(),
+            None => Duration::ZERO,
+        }
+    }
+
+    /// Returns the file path of the current track, if any.
+    pub fn current_track(&self) -> Option<&str> {
+        self.current.map(|track| self.tracks[track].as_str())
+    }
+
+    /// Returns the tracks of the playlist in the order they were added.
+    pub fn tracks(&self) -> &[String] {
+        &self.tracks
+    }
+
+    /// Sets what happens when the end of the playlist is reached.
+    pub fn set_repeat_mode(&mut self, repeat_mode: RepeatMode) {
+        self.repeat_mode = repeat_mode;
+    }
+
+    /// Returns what happens when the end of the playlist is reached.
+    pub fn repeat_mode(&self) -> RepeatMode {
+        self.repeat_mode
+    }
+
+    /// Enables or disables shuffling. The new order applies after the queued track.
+    pub fn set_shuffle(&mut self, shuffle: bool) {
+        self.shuffle = shuffle;
+        self.rebuild_order();
+    }
+
+    /// Checks if the playlist is shuffled.
+    pub fn is_shuffled(&self) -> bool {
+        self.shuffle
+    }
+
+    /// Sets the music volume (0.0 to 1.0).
+    pub fn set_volume(&mut self, volume: f32) -> Result<(), Errors> {
+        if !(0.0..=1.0).contains(&volume) {
+            return Err(Errors::AudioVolumeError(
+                "Volume must be between 0.0 and 1.0".to_string(),
+            ));
+        }
+        self.volume = volume;
+        if let Some(sink) = &self.sink {
+            sink.set_volume(volume);
+        }
+        Ok(())
+    }
+
+    /// Returns the music volume.
+    pub fn volume(&self) -> f32 {
+        self.volume
+    }
+
+    /// Replaces the playlist with the given tracks and plays the sources added by `append`,
+    /// without queueing anything after them.
+    fn play_endless(
+        &mut self,
+        tracks: Vec<String>,
+        append: impl FnOnce(&Sink),
+    ) -> Result<(), Errors> {
+        self.stop();
+        let sink = Sink::try_new(&self.stream_handle)?;
+        sink.set_volume(self.volume);
+        append(&sink);
+        self.tracks = tracks;
+        self.rebuild_order();
+        self.sink = Some(sink);
+        self.current = Some(0);
+        self.endless = true;
+        Ok(())
+    }
+
+    /// Starts playing the given track immediately and queues the one after it.
+    fn play_track(&mut self, track: usize) -> Result<(), Errors> {
+        let sink = Sink::try_new(&self.stream_handle)?;
+        sink.set_volume(self.volume);
+        sink.append(Self::open(&self.tracks[track])?);
+        self.sink = Some(sink);
+        self.current = Some(track);
+        self.queued = None;
+        self.endless = false;
+        self.preload()
+    }
+
+    /// Appends the track after the current one to the sink, so it starts without a gap.
+    fn preload(&mut self) -> Result<(), Errors> {
+        if self.queued.is_some() || self.endless {
+            return Ok(());
+        }
+        let Some(next) = self.current.and_then(|track| self.next_track(track, false)) else {
+            return Ok(());
+        };
+        if let Some(sink) = &self.sink {
+            sink.append(Self::open(&self.tracks[next])?);
+            self.queued = Some(next);
+        }
+        Ok(())
+    }
+
+    /// Returns the track following the given one, honoring the repeat mode.
+    ///
+    /// `RepeatMode::One` is ignored when `skip` is `true`, so skipping moves on.
+    fn next_track(&self, track: usize, skip: bool) -> Option<usize> {
+        if self.repeat_mode == RepeatMode::One && !skip {
+            return Some(track);
+        }
+        let position = self.position_of(track)?;
+        match self.order.get(position + 1) {
+            Some(&next) => Some(next),
+            None if self.repeat_mode != RepeatMode::Off => self.order.first().copied(),
+            None => None,
+        }
+    }
+
+    /// Returns the position of a track in the play order.
+    fn position_of(&self, track: usize) -> Option<usize> {
+        self.order.iter().position(|&t| t == track)
+    }
+
+    /// Recomputes the play order, keeping the current track first when shuffling.
+    fn rebuild_order(&mut self) {
+        self.order = (0..self.tracks.len()).collect();
+        if !self.shuffle {
+            return;
+        }
+        // Fisher-Yates shuffle with a time-seeded generator
+        let mut random = Xorshift::new(
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_nanos() as u64)
+                .unwrap_or(0),
+        );
+        for i in (1..self.order.len()).rev() {
+            self.order.swap(i, random.below(i as u64 + 1) as usize);
+        }
+        if let Some(position) = self.current.and_then(|track| self.position_of(track)) {
+            self.order.swap(0, position);
+        }
+    }
+
+    /// Opens a track for streaming playback.
+    fn open(file_path: &str) -> Result<Decoder<BufReader<File>>, Errors> {
+        let file = File::open(file_path)?;
+        Ok(Decoder::new(BufReader::new(file))?)
+    }
+}
@@ -4,6 +4,11 @@
 //! Sounds are loaded from audio files and can be played once or in a loop.
 //! The audio data is stored in a buffer for efficient playback.
 //!
+//! Short effects that are triggered often can be prepared for the output stream with
+//! `Sound::prepare`, which converts them to the stream's channel count and sample rate once.
+//! Playing a prepared sound shares its samples instead of copying and converting them on
+//! every trigger, see `AudioSystem::load_sfx`.
+//!
 //! # Supported Formats
 //! - WAV
 //! - MP3
@@ -16,19 +21,83 @@
 //! let sound = Sound::new("path/to/sound.wav").expect("Failed to load sound");
 //! ```
 
+use crate::audio::{Attenuation, CaptionEvent, CaptionTrack, Listener};
 use crate::custom_errors::Errors;
+use cgmath::Vector3;
+use rodio::source::UniformSourceIterator;
 use rodio::{Decoder, Sink, Source};
 use std::fs::File;
 use std::io::BufReader;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// The decoded samples of a sound.
+enum SampleData {
+    /// Samples as decoded from the file, copied into a new buffer for every playback.
+    Raw(Vec<u16>),
+    /// Samples converted to the output format, shared by all playbacks.
+    Prepared(Arc<[f32]>),
+}
+
+/// Plays prepared samples without copying them.
+struct PreparedSource {
+    samples: Arc<[f32]>,
+    channels: u16,
+    sample_rate: u32,
+    position: usize,
+    looping: bool,
+}
+
+impl Iterator for PreparedSource {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.position >= self.samples.len() {
+            if !self.looping || self.samples.is_empty() {
+                return None;
+            }
+            self.position = 0;
+        }
+        let sample = self.samples[self.position];
+        self.position += 1;
+        Some(sample)
+    }
+}
+
+impl Source for PreparedSource {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
 
 /// Represents a sound that can be played.
 pub struct Sound {
-    data: Vec<u16>,
+    data: SampleData,
+    channels: u16,
+    sample_rate: u32,
     volume: f32,
     is_playing: bool,
     is_paused: bool,
     sink: Option<Arc<Mutex<Sink>>>, // Храним Arc<Mutex<Sink>>, а не MutexGuard
+    position: Option<Vector3<f32>>,
+    attenuation: Attenuation,
+    distance_gain: f32,
+    velocity: Vector3<f32>,
+    doppler_pitch: f32,
+    looping: bool,
+    captions: Option<CaptionTrack>,
 }
 
 impl Sound {
@@ -37,16 +106,67 @@ impl Sound {
         let file = File::open(file_path)?;
         let reader = BufReader::new(file);
         let decoder = Decoder::new(reader)?;
+        let channels = decoder.channels();
+        let sample_rate = decoder.sample_rate();
         let data: Vec<u16> = decoder.convert_samples().collect();
         Ok(Self {
-            data,
+            data: SampleData::Raw(data),
+            channels,
+            sample_rate,
             volume: 1.0, // Default volume
             is_playing: false,
             is_paused: false,
             sink: None,
+            position: None,
+            attenuation: Attenuation::default(),
+            distance_gain: 1.0,
+            velocity: Vector3::new(0.0, 0.0, 0.0),
+            doppler_pitch: 1.0,
+            looping: false,
+            captions: None,
         })
     }
 
+    /// Converts the sound to the given output format once, so playing it no longer copies or
+    /// converts its samples.
+    ///
+    /// The sound is resampled and its channels are mixed up or down as needed; the samples
+    /// in the file's own format are released afterwards. Preparing again for a different
+    /// format converts the prepared samples.
+    ///
+    /// # Arguments
+    /// * `channels` - The channel count of the output stream.
+    /// * `sample_rate` - The sample rate of the output stream in Hz.
+    pub fn prepare(&mut self, channels: u16, sample_rate: u32) {
+        let samples: Vec<f32> = match &self.data {
+            SampleData::Prepared(_)
+                if self.channels == channels && self.sample_rate == sample_rate =>
+            {
+                return;
+            }
+            SampleData::Raw(data) => UniformSourceIterator::new(
+                rodio::buffer::SamplesBuffer::new(self.channels, self.sample_rate, data.as_slice()),
+                channels,
+                sample_rate,
+            )
+            .collect(),
+            SampleData::Prepared(samples) => UniformSourceIterator::new(
+                rodio::buffer::SamplesBuffer::new(self.channels, self.sample_rate, &samples[..]),
+                channels,
+                sample_rate,
+            )
+            .collect(),
+        };
+        self.data = SampleData::Prepared(samples.into());
+        self.channels = channels;
+        self.sample_rate = sample_rate;
+    }
+
+    /// Checks if the sound was converted to an output format with `prepare`.
+    pub fn is_prepared(&self) -> bool {
+        matches!(self.data, SampleData::Prepared(_))
+    }
+
     /// Sets the volume of the sound (0.0 to 1.0).
     pub fn set_volume(&mut self, volume: f32) -> Result<(), Errors> {
         if volume < 0.0 || volume > 1.0 {
@@ -55,11 +175,75 @@ impl Sound {
             ));
         }
         self.volume = volume;
+        self.update_sink_volume();
+        Ok(())
+    }
+
+    /// Places the sound in the world, or `None` for a non-spatial sound heard at full volume.
+    pub fn set_position(&mut self, position: Option<Vector3<f32>>) {
+        self.position = position;
+        if position.is_none() {
+            self.distance_gain = 1.0;
+            self.doppler_pitch = 1.0;
+            self.update_sink_volume();
+        }
+    }
+
+    /// Moves a spatial sound and derives its velocity from the distance travelled.
+    ///
+    /// The first call only places the sound, since there is no previous position to
+    /// compare with. The velocity is left unchanged if `delta_time` is not positive.
+    pub fn move_to(&mut self, position: Vector3<f32>, delta_time: f32) {
+        if let Some(previous) = self.position {
+            if delta_time > 0.0 {
+                self.velocity = (position - previous) / delta_time;
+            }
+        }
+        self.position = Some(position);
+    }
+
+    /// Sets the velocity of the sound in world units per second, used for the doppler effect.
+    pub fn set_velocity(&mut self, velocity: Vector3<f32>) {
+        self.velocity = velocity;
+    }
+
+    /// Returns the velocity of the sound in world units per second.
+    pub fn velocity(&self) -> Vector3<f32> {
+        self.velocity
+    }
+
+    /// Returns the position of the sound in the world, if it is spatial.
+    pub fn position(&self) -> Option<Vector3<f32>> {
+        self.position
+    }
+
+    /// Sets how the sound fades with distance to the listener.
+    pub fn set_attenuation(&mut self, attenuation: Attenuation) {
+        self.attenuation = attenuation;
+    }
+
+    /// Returns how the sound fades with distance to the listener.
+    pub fn attenuation(&self) -> Attenuation {
+        self.attenuation
+    }
+
+    /// Updates the volume and doppler pitch of a spatial sound for the listener's position
+    /// and velocity.
+    pub fn apply_listener(&mut self, listener: &Listener) {
+        if let Some(position) = self.position {
+            self.distance_gain = self.attenuation.gain(listener.distance_to(position));
+            self.doppler_pitch = listener.doppler_pitch(position, self.velocity);
+            self.update_sink_volume();
+        }
+    }
+
+    /// Applies the volume, distance attenuation and doppler pitch to the playing sink.
+    fn update_sink_volume(&self) {
         if let Some(sink) = &self.sink {
             let sink = sink.lock().unwrap();
-            sink.set_volume(volume);
+            sink.set_volume(self.volume * self.distance_gain);
+            sink.set_speed(self.doppler_pitch);
         }
-        Ok(())
     }
 
     /// Checks if the sound is currently playing.
@@ -78,12 +262,95 @@ impl Sound {
         self.is_paused
     }
 
+    /// Returns the length of the sound.
+    pub fn duration(&self) -> Duration {
+        let samples = match &self.data {
+            SampleData::Raw(data) => data.len(),
+            SampleData::Prepared(samples) => samples.len(),
+        };
+        let samples_per_second = self.channels.max(1) as u64 * self.sample_rate.max(1) as u64;
+        Duration::from_secs_f64(samples as f64 / samples_per_second as f64)
+    }
+
+    /// Returns the playback position within the sound, or `None` if it is stopped.
+    ///
+    /// For looping sounds this is the position within the current loop.
+    pub fn playback_position(&mut self) -> Option<Duration> {
+        let playing = self.is_playing();
+        if !playing && !self.is_paused {
+            return None;
+        }
+        let position = self.sink.as_ref()?.lock().unwrap().get_pos();
+        let duration = self.duration();
+        if self.looping && !duration.is_zero() {
+            Some(Duration::from_secs_f64(
+                position.as_secs_f64() % duration.as_secs_f64(),
+            ))
+        } else {
+            Some(position)
+        }
+    }
+
+    /// Sets the captions shown while the sound plays, or removes them with `None`.
+    pub fn set_captions(&mut self, captions: Option<CaptionTrack>) {
+        self.captions = captions;
+    }
+
+    /// Returns the captions of the sound, if any.
+    pub fn captions(&self) -> Option<&CaptionTrack> {
+        self.captions.as_ref()
+    }
+
+    /// Advances the captions to the current playback position.
+    ///
+    /// # Returns
+    ///
+    /// The captions shown and hidden since the previous call, see `CaptionTrack::update`.
+    pub fn update_captions(&mut self) -> Vec<CaptionEvent> {
+        if self.captions.is_none() {
+            return Vec::new();
+        }
+        let position = self.playback_position();
+        match &mut self.captions {
+            Some(captions) => captions.update(position),
+            None => Vec::new(),
+        }
+    }
+
+    /// Appends the samples to a sink, sharing them if the sound is prepared.
+    fn append_to(&self, sink: &Sink, looping: bool) {
+        match &self.data {
+            SampleData::Raw(data) => {
+                let source = rodio::buffer::SamplesBuffer::new(
+                    self.channels,
+                    self.sample_rate,
+                    data.clone(),
+                );
+                if looping {
+                    sink.append(source.repeat_infinite());
+                } else {
+                    sink.append(source);
+                }
+            }
+            SampleData::Prepared(samples) => sink.append(PreparedSource {
+                samples: Arc::clone(samples),
+                channels: self.channels,
+                sample_rate: self.sample_rate,
+                position: 0,
+                looping,
+            }),
+        }
+    }
+
     /// Plays the sound once using the provided sink.
     pub fn play_once(&mut self, sink: &Arc<Mutex<Sink>>) -> Result<(), Errors> {
-        let source = rodio::buffer::SamplesBuffer::new(1, 44100, self.data.clone());
         let sink = sink;
-        sink.lock().unwrap().set_volume(self.volume);
-        sink.lock().unwrap().append(source);
+        sink.lock()
+            .unwrap()
+            .set_volume(self.volume * self.distance_gain);
+        sink.lock().unwrap().set_speed(self.doppler_pitch);
+        self.append_to(&sink.lock().unwrap(), false);
+        self.looping = false;
         self.is_playing = true;
         self.is_paused = false;
         self.sink = Some(Arc::clone(sink)); // Сохраняем Arc<Mutex<Sink>>, а не MutexGuard
@@ -92,10 +359,13 @@ impl Sound {
 
     /// Plays the sound in a loop using the provided sink.
     pub fn play_loop(&mut self, sink: &Arc<Mutex<Sink>>) -> Result<(), Errors> {
-        let source = rodio::buffer::SamplesBuffer::new(1, 44100, self.data.clone());
         let sink = sink;
-        sink.lock().unwrap().set_volume(self.volume);
-        sink.lock().unwrap().append(source.repeat_infinite());
+        sink.lock()
+            .unwrap()
+            .set_volume(self.volume * self.distance_gain);
+        sink.lock().unwrap().set_speed(self.doppler_pitch);
+        self.append_to(&sink.lock().unwrap(), true);
+        self.looping = true;
         self.is_playing = true;
         self.is_paused = false;
         self.sink = Some(Arc::clone(sink)); // Сохраняем Arc<Mutex<Sink>>, а не MutexGuard
@@ -0,0 +1,184 @@
+//! # Spatial Module
+//! Distance attenuation and doppler shift for sounds placed in the world.
+//!
+//! Each positioned sound fades with its distance to the `Listener` according to its
+//! `Attenuation`. The listener's `distance_scale` converts world units to the units the
+//! attenuation distances are given in.
+//!
+//! Sounds and the listener can also have a velocity, set explicitly or derived from their
+//! movement with `move_to`. The pitch of a sound then rises while it approaches the
+//! listener and falls while it moves away, scaled by the listener's `doppler_factor`.
+//!
+//! # Example
+//! ```rust
+//! use glwfr::audio::{Attenuation, AudioSystem, Rolloff};
+//! use glwfr::cgmath::Vector3;
+//!
+//! fn main() -> Result<(), glwfr::custom_errors::Errors> {
+//!     let mut audio_system = AudioSystem::new()?;
+//!     audio_system.load_sound("waterfall", "waterfall.ogg")?;
+//!     audio_system.set_sound_position("waterfall", Some(Vector3::new(10.0, 0.0, 0.0)))?;
+//!     audio_system.set_sound_attenuation(
+//!         "waterfall",
+//!         Attenuation {
+//!             rolloff: Rolloff::Linear,
+//!             min_distance: 2.0,
+//!             max_distance: 40.0,
+//!             rolloff_factor: 1.0,
+//!         },
+//!     )?;
+//!     audio_system.play_sound_loop("waterfall")?;
+//!
+//!     // Once per frame, deriving the velocities from the movement
+//!     let delta_time = 1.0 / 60.0;
+//!     audio_system.listener_mut().move_to(Vector3::new(0.0, 0.0, 0.0), delta_time);
+//!     audio_system.move_sound("waterfall", Vector3::new(10.0, 0.0, 0.0), delta_time)?;
+//!     audio_system.update()?;
+//!     Ok(())
+//! }
+//! ```
+
+use cgmath::*;
+
+/// How the volume of a sound decreases between its minimum and maximum distance.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Rolloff {
+    /// The volume decreases linearly and reaches zero at the maximum distance.
+    Linear,
+    /// The volume decreases inversely with distance, like sound in the real world.
+    #[default]
+    Inverse,
+    /// The volume decreases with `(distance / min_distance)^-rolloff_factor`.
+    Exponential,
+}
+
+/// Distance attenuation settings of a sound.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Attenuation {
+    /// The curve the volume follows with increasing distance.
+    pub rolloff: Rolloff,
+    /// The distance up to which the sound plays at full volume.
+    pub min_distance: f32,
+    /// The distance beyond which the volume no longer decreases.
+    pub max_distance: f32,
+    /// How quickly the volume decreases for the inverse and exponential curves.
+    pub rolloff_factor: f32,
+}
+
+impl Attenuation {
+    /// Returns the volume factor (0.0 to 1.0) at the given distance from the listener.
+    pub fn gain(&self, distance: f32) -> f32 {
+        let min_distance = self.min_distance.max(f32::EPSILON);
+        let max_distance = self.max_distance.max(min_distance);
+        let distance = distance.clamp(min_distance, max_distance);
+        let gain = match self.rolloff {
+            Rolloff::Linear => {
+                if max_distance > min_distance {
+                    1.0 - self.rolloff_factor * (distance - min_distance)
+                        / (max_distance - min_distance)
+                } else {
+                    1.0
+                }
+            }
+            Rolloff::Inverse => {
+                min_distance / (min_distance + self.rolloff_factor * (distance - min_distance))
+            }
+            Rolloff::Exponential => (distance / min_distance).powf(-self.rolloff_factor),
+        };
+        gain.clamp(0.0, 1.0)
+    }
+}
+
+impl Default for Attenuation {
+    fn default() -> Self {
+        Self {
+            rolloff: Rolloff::Inverse,
+            min_distance: 1.0,
+            max_distance: 100.0,
+            rolloff_factor: 1.0,
+        }
+    }
+}
+
+/// The point in the world sounds are heard from, usually the camera.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Listener {
+    /// The position of the listener in world space.
+    pub position: Vector3<f32>,
+    /// A factor distances are multiplied by before attenuation, e.g. to convert world
+    /// units to meters.
+    pub distance_scale: f32,
+    /// The velocity of the listener in world units per second.
+    pub velocity: Vector3<f32>,
+    /// How strongly velocities shift the pitch of sounds. `0.0` disables the doppler effect,
+    /// values above `1.0` exaggerate it.
+    pub doppler_factor: f32,
+    /// The speed of sound in scaled units per second, `343.3` for meters.
+    pub speed_of_sound: f32,
+}
+
+impl Listener {
+    /// Returns the scaled distance from the listener to the given position.
+    pub fn distance_to(&self, position: Vector3<f32>) -> f32 {
+        (position - self.position).magnitude() * self.distance_scale
+    }
+
+    /// Moves the listener and derives its velocity from the distance travelled.
+    ///
+    /// # Arguments
+    /// * `position` - The new position in world space.
+    /// * `delta_time` - The time since the previous position in seconds. The velocity is
+    ///   left unchanged if it is not positive.
+    pub fn move_to(&mut self, position: Vector3<f32>, delta_time: f32) {
+        if delta_time > 0.0 {
+            self.velocity = (position - self.position) / delta_time;
+        }
+        self.position = position;
+    }
+
+    /// Returns the pitch factor of a sound at the given position and velocity.
+    ///
+    /// Uses the same model as OpenAL: only the velocity components along the line between
+    /// the sound and the listener count, and they are clamped below the speed of sound.
+    ///
+    /// # Arguments
+    /// * `position` - The position of the sound in world space.
+    /// * `velocity` - The velocity of the sound in world units per second.
+    ///
+    /// # Returns
+    /// A factor above `1.0` while the distance shrinks, below `1.0` while it grows, and
+    /// `1.0` if the doppler effect is disabled.
+    pub fn doppler_pitch(&self, position: Vector3<f32>, velocity: Vector3<f32>) -> f32 {
+        if self.doppler_factor <= 0.0 || self.speed_of_sound <= 0.0 {
+            return 1.0;
+        }
+        let to_listener = self.position - position;
+        let distance = to_listener.magnitude();
+        if distance <= f32::EPSILON {
+            return 1.0;
+        }
+        let direction = to_listener / distance;
+        let max_speed = self.speed_of_sound / self.doppler_factor;
+        let listener_speed = (direction.dot(self.velocity) * self.distance_scale).min(max_speed);
+        let source_speed = (direction.dot(velocity) * self.distance_scale).min(max_speed);
+        let pitch = (self.speed_of_sound - self.doppler_factor * listener_speed)
+            / (self.speed_of_sound - self.doppler_factor * source_speed);
+        if pitch.is_finite() {
+            pitch.max(0.0)
+        } else {
+            1.0
+        }
+    }
+}
+
+impl Default for Listener {
+    fn default() -> Self {
+        Self {
+            position: Vector3::new(0.0, 0.0, 0.0),
+            distance_scale: 1.0,
+            velocity: Vector3::new(0.0, 0.0, 0.0),
+            doppler_factor: 1.0,
+            speed_of_sound: 343.3,
+        }
+    }
+}
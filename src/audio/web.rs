@@ -0,0 +1,471 @@
+//! # Web Audio Module
+//! The browser version of `AudioSystem`, playing sounds with the Web Audio API. It is
+//! available with the `web` feature and replaces the rodio audio system when the `native`
+//! feature is disabled.
+//!
+//! Sounds are fetched from URLs relative to the page and decoded in the background, so
+//! `load_sound` returns before the sound is ready. Sounds played while still loading start
+//! once they are decoded; `is_loaded` tells whether that has happened. Browsers only allow
+//! audio after the user has interacted with the page, so nothing is heard before the first
+//! click or key press.
+//!
+//! Music playlists are not available in the browser.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::audio::AudioSystem;
+//!
+//! fn main() -> Result<(), glwfr::custom_errors::Errors> {
+//!     let mut audio_system = AudioSystem::new()?;
+//!     audio_system.load_sound("explosion", "sounds/explosion.ogg")?;
+//!     // Starts as soon as the sound is decoded
+//!     audio_system.play_sound_once("explosion")?;
+//!     Ok(())
+//! }
+//! ```
+
+use crate::audio::{Attenuation, Caption, CaptionEvent, CaptionTrack, Listener};
+use crate::custom_errors::Errors;
+use cgmath::Vector3;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::time::Duration;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    AudioBuffer, AudioBufferSourceNode, AudioContext, AudioScheduledSourceNode, GainNode, Response,
+};
+
+/// The decoding state of a sound.
+enum Data {
+    Loading,
+    Loaded(AudioBuffer),
+    Failed,
+}
+
+/// A sound of the audio system and its playback state.
+struct WebSound {
+    data: Data,
+    gain: GainNode,
+    source: Option<AudioBufferSourceNode>,
+    /// Whether the sound was played while still loading, and whether to loop it.
+    pending: Option<bool>,
+    looping: bool,
+    /// The context time playback started at, in seconds.
+    started_at: f64,
+    volume: f32,
+    position: Option<Vector3<f32>>,
+    velocity: Vector3<f32>,
+    attenuation: Attenuation,
+    distance_gain: f32,
+    doppler_pitch: f32,
+    captions: Option<CaptionTrack>,
+}
+
+impl WebSound {
+    fn duration(&self) -> f64 {
+        match &self.data {
+            Data::Loaded(buffer) => buffer.duration(),
+            _ => 0.0,
+        }
+    }
+
+    /// Returns the playback position, or `None` if the sound is not playing.
+    fn playback_position(&self, context: &AudioContext) -> Option<f64> {
+        self.source.as_ref()?;
+        let elapsed = (context.current_time() - self.started_at).max(0.0);
+        let duration = self.duration();
+        if self.looping && duration > 0.0 {
+            Some(elapsed % duration)
+        } else if elapsed < duration {
+            Some(elapsed)
+        } else {
+            None
+        }
+    }
+
+    /// Applies the volume, distance attenuation and doppler shift to the playing sound.
+    fn apply(&self) {
+        self.gain.gain().set_value(self.volume * self.distance_gain);
+        if let Some(source) = &self.source {
+            source.playback_rate().set_value(self.doppler_pitch);
+        }
+    }
+
+    fn apply_listener(&mut self, listener: &Listener) {
+        if let Some(position) = self.position {
+            self.distance_gain = self.attenuation.gain(listener.distance_to(position));
+            self.doppler_pitch = listener.doppler_pitch(position, self.velocity);
+        } else {
+            self.distance_gain = 1.0;
+            self.doppler_pitch = 1.0;
+        }
+        self.apply();
+    }
+
+    /// Starts playing the sound from the beginning, replacing a previous playback.
+    fn start(&mut self, context: &AudioContext, looping: bool) -> Result<(), Errors> {
+        let Data::Loaded(buffer) = &self.data else {
+            self.pending = Some(looping);
+            return Ok(());
+        };
+        let buffer = buffer.clone();
+        self.stop();
+        let source = context.create_buffer_source().map_err(play_error)?;
+        source.set_buffer(Some(&buffer));
+        source.set_loop(looping);
+        source
+            .connect_with_audio_node(&self.gain)
+            .map_err(play_error)?;
+        source.start().map_err(play_error)?;
+        self.source = Some(source);
+        self.pending = None;
+        self.looping = looping;
+        self.started_at = context.current_time();
+        self.apply();
+        Ok(())
+    }
+
+    fn stop(&mut self) {
+        if let Some(source) = self.source.take() {
+            // `AudioBufferSourceNode::stop` is deprecated in favour of the base class method
+            let _ = source.unchecked_ref::<AudioScheduledSourceNode>().stop();
+            let _ = source.disconnect();
+        }
+    }
+}
+
+/// Represents the audio system.
+pub struct AudioSystem {
+    context: AudioContext,
+    sounds: HashMap<String, Rc<RefCell<WebSound>>>,
+    listener: Listener,
+    caption_events: Vec<(String, CaptionEvent)>,
+}
+
+impl AudioSystem {
+    /// Creates a new audio system.
+    pub fn new() -> Result<Self, Errors> {
+        let context = AudioContext::new()
+            .map_err(|error| Errors::AudioInitializationError(format!("{:?}", error)))?;
+        Ok(Self {
+            context,
+            sounds: HashMap::new(),
+            listener: Listener::default(),
+            caption_events: Vec::new(),
+        })
+    }
+
+    /// Updates the audio system. Call this once per frame to start sounds that finished
+    /// loading and to attenuate and doppler shift spatial sounds for the current listener.
+    /// Caption events are collected for `take_caption_events`.
+    pub fn update(&mut self) -> Result<(), Errors> {
+        for (name, sound) in &self.sounds {
+            let mut sound = sound.borrow_mut();
+            if let (Some(looping), Data::Loaded(_)) = (sound.pending, &sound.data) {
+                sound.start(&self.context, looping)?;
+            }
+            if sound.source.is_some() && sound.playback_position(&self.context).is_none() {
+                sound.stop();
+            }
+            sound.apply_listener(&self.listener);
+            let position = sound
+                .playback_position(&self.context)
+                .map(Duration::from_secs_f64);
+            if let Some(captions) = &mut sound.captions {
+                self.caption_events.extend(
+                    captions
+                        .update(position)
+                        .into_iter()
+                        .map(|event| (name.clone(), event)),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the listener spatial sounds are heard from.
+    pub fn listener(&self) -> &Listener {
+        &self.listener
+    }
+
+    /// Returns a mutable reference to the listener, e.g. to follow the camera.
+    pub fn listener_mut(&mut self) -> &mut Listener {
+        &mut self.listener
+    }
+
+    /// Runs a function with a sound, or returns a `SoundNotFoundError`.
+    fn with_sound<R>(
+        &self,
+        name: &str,
+        f: impl FnOnce(&mut WebSound) -> Result<R, Errors>,
+    ) -> Result<R, Errors> {
+        match self.sounds.get(name) {
+            Some(sound) => f(&mut sound.borrow_mut()),
+            None => Err(Errors::SoundNotFoundError(name.to_string())),
+        }
+    }
+
+    /// Places a sound in the world, or makes it non-spatial with `None`.
+    pub fn set_sound_position(
+        &self,
+        name: &str,
+        position: Option<Vector3<f32>>,
+    ) -> Result<(), Errors> {
+        self.with_sound(name, |sound| {
+            sound.position = position;
+            sound.apply_listener(&self.listener);
+            Ok(())
+        })
+    }
+
+    /// Sets the velocity of a spatial sound in world units per second, used for the doppler
+    /// effect.
+    pub fn set_sound_velocity(&self, name: &str, velocity: Vector3<f32>) -> Result<(), Errors> {
+        self.with_sound(name, |sound| {
+            sound.velocity = velocity;
+            sound.apply_listener(&self.listener);
+            Ok(())
+        })
+    }
+
+    /// Moves a spatial sound and derives its velocity from the distance travelled since the
+    /// previous position.
+    pub fn move_sound(
+        &self,
+        name: &str,
+        position: Vector3<f32>,
+        delta_time: f32,
+    ) -> Result<(), Errors> {
+        self.with_sound(name, |sound| {
+            if let Some(previous) = sound.position {
+                if delta_time > 0.0 {
+                    sound.velocity = (position - previous) / delta_time;
+                }
+            }
+            sound.position = Some(position);
+            sound.apply_listener(&self.listener);
+            Ok(())
+        })
+    }
+
+    /// Sets how a spatial sound fades with distance to the listener.
+    pub fn set_sound_attenuation(
+        &self,
+        name: &str,
+        attenuation: Attenuation,
+    ) -> Result<(), Errors> {
+        self.with_sound(name, |sound| {
+            sound.attenuation = attenuation;
+            sound.apply_listener(&self.listener);
+            Ok(())
+        })
+    }
+
+    /// Sets the captions shown while a sound plays, or removes them with `None`.
+    pub fn set_sound_captions(
+        &self,
+        name: &str,
+        captions: Option<CaptionTrack>,
+    ) -> Result<(), Errors> {
+        self.with_sound(name, |sound| {
+            sound.captions = captions;
+            Ok(())
+        })
+    }
+
+    /// Returns the caption events collected by `update` since the last call, with the name
+    /// of the sound each belongs to.
+    pub fn take_caption_events(&mut self) -> Vec<(String, CaptionEvent)> {
+        std::mem::take(&mut self.caption_events)
+    }
+
+    /// Returns the captions visible at the last `update` with the name of their sound,
+    /// ordered by their start time.
+    pub fn active_captions(&self) -> Vec<(String, Caption)> {
+        let mut captions: Vec<(String, Caption)> = self
+            .sounds
+            .iter()
+            .flat_map(|(name, sound)| {
+                let sound = sound.borrow();
+                sound
+                    .captions
+                    .as_ref()
+                    .map(|track| {
+                        track
+                            .active()
+                            .map(|caption| (name.clone(), caption.clone()))
+                            .collect::<Vec<_>>()
+                    })
+                    .unwrap_or_default()
+            })
+            .collect();
+        captions.sort_by_key(|(_, caption)| caption.start);
+        captions
+    }
+
+    /// Starts loading a sound from a URL and stores it with a given name.
+    ///
+    /// The sound is fetched and decoded in the background; errors are logged, and the
+    /// sound then never plays.
+    pub fn load_sound(&mut self, name: &str, file_path: &str) -> Result<(), Errors> {
+        let gain = self.context.create_gain().map_err(load_error)?;
+        gain.connect_with_audio_node(&self.context.destination())
+            .map_err(load_error)?;
+        let sound = Rc::new(RefCell::new(WebSound {
+            data: Data::Loading,
+            gain,
+            source: None,
+            pending: None,
+            looping: false,
+            started_at: 0.0,
+            volume: 1.0,
+            position: None,
+            velocity: Vector3::new(0.0, 0.0, 0.0),
+            attenuation: Attenuation::default(),
+            distance_gain: 1.0,
+            doppler_pitch: 1.0,
+            captions: None,
+        }));
+        if let Some(previous) = self.sounds.insert(name.to_string(), sound.clone()) {
+            previous.borrow_mut().stop();
+        }
+
+        let context = self.context.clone();
+        let url = file_path.to_string();
+        wasm_bindgen_futures::spawn_local(async move {
+            let decoded = fetch_and_decode(&context, &url).await;
+            let mut sound = sound.borrow_mut();
+            match decoded {
+                Ok(buffer) => {
+                    sound.data = Data::Loaded(buffer);
+                    if let Some(looping) = sound.pending {
+                        if let Err(error) = sound.start(&context, looping) {
+                            log::error!("Failed to play {}: {}", url, error);
+                        }
+                    }
+                }
+                Err(error) => {
+                    log::error!("{}", error);
+                    sound.data = Data::Failed;
+                    sound.pending = None;
+                }
+            }
+        });
+        Ok(())
+    }
+
+    /// Same as `load_sound`. Web Audio decodes sounds for the output format when loading.
+    pub fn load_sfx(&mut self, name: &str, file_path: &str) -> Result<(), Errors> {
+        self.load_sound(name, file_path)
+    }
+
+    /// Does nothing besides checking the sound exists, see `load_sfx`.
+    pub fn prepare_sound(&self, name: &str) -> Result<(), Errors> {
+        self.with_sound(name, |_| Ok(()))
+    }
+
+    /// Returns the channel count and sample rate of the output.
+    pub fn output_format(&self) -> (u16, u32) {
+        let channels = self.context.destination().channel_count();
+        (channels as u16, self.context.sample_rate() as u32)
+    }
+
+    /// Checks if a sound has finished loading.
+    pub fn is_loaded(&self, name: &str) -> Result<bool, Errors> {
+        self.with_sound(name, |sound| Ok(matches!(sound.data, Data::Loaded(_))))
+    }
+
+    /// Plays a sound once by its name, or once it is loaded.
+    pub fn play_sound_once(&mut self, name: &str) -> Result<(), Errors> {
+        self.resume();
+        self.with_sound(name, |sound| sound.start(&self.context, false))
+    }
+
+    /// Plays a sound in a loop by its name, or once it is loaded.
+    pub fn play_sound_loop(&mut self, name: &str) -> Result<(), Errors> {
+        self.resume();
+        self.with_sound(name, |sound| sound.start(&self.context, true))
+    }
+
+    /// Sets the volume of a specific sound (0.0 to 1.0).
+    pub fn set_volume(&self, name: &str, volume: f32) -> Result<(), Errors> {
+        if !(0.0..=1.0).contains(&volume) {
+            return Err(Errors::AudioVolumeError(
+                "Volume must be between 0.0 and 1.0".to_string(),
+            ));
+        }
+        self.with_sound(name, |sound| {
+            sound.volume = volume;
+            sound.apply();
+            Ok(())
+        })
+    }
+
+    /// Checks if a specific sound is currently playing. Sounds waiting to be loaded count
+    /// as playing.
+    pub fn is_playing(&self, name: &str) -> Result<bool, Errors> {
+        self.with_sound(name, |sound| {
+            Ok(sound.pending.is_some() || sound.playback_position(&self.context).is_some())
+        })
+    }
+
+    /// Checks if a specific sound is paused. Sounds cannot be paused in the browser, so this
+    /// is always `false` for existing sounds.
+    pub fn is_paused(&self, name: &str) -> Result<bool, Errors> {
+        self.with_sound(name, |_| Ok(false))
+    }
+
+    /// Resumes the audio context, which browsers suspend until the user interacts with the
+    /// page.
+    fn resume(&self) {
+        let _ = self.context.resume();
+    }
+}
+
+impl Drop for AudioSystem {
+    fn drop(&mut self) {
+        let _ = self.context.close();
+    }
+}
+
+/// Fetches a sound file and decodes it.
+async fn fetch_and_decode(context: &AudioContext, url: &str) -> Result<AudioBuffer, Errors> {
+    let fetch_error = |error: JsValue| Errors::SoundLoadError(format!("{}: {:?}", url, error));
+    let page = web_sys::window()
+        .ok_or_else(|| Errors::SoundLoadError(format!("{}: no page to fetch from", url)))?;
+    let response: Response = JsFuture::from(page.fetch_with_str(url))
+        .await
+        .map_err(fetch_error)?
+        .dyn_into()
+        .map_err(fetch_error)?;
+    if !response.ok() {
+        return Err(Errors::SoundLoadError(format!(
+            "{}: HTTP status {}",
+            url,
+            response.status()
+        )));
+    }
+    let data = JsFuture::from(response.array_buffer().map_err(fetch_error)?)
+        .await
+        .map_err(fetch_error)?;
+    let decode_error = |error: JsValue| Errors::AudioDecodeError(format!("{}: {:?}", url, error));
+    let decoding = context
+        .decode_audio_data(data.unchecked_ref())
+        .map_err(decode_error)?;
+    JsFuture::from(decoding)
+        .await
+        .map_err(decode_error)?
+        .dyn_into()
+        .map_err(decode_error)
+}
+
+fn load_error(error: JsValue) -> Errors {
+    Errors::SoundLoadError(format!("{:?}", error))
+}
+
+fn play_error(error: JsValue) -> Errors {
+    Errors::SoundPlayError(format!("{:?}", error))
+}
@@ -8,18 +8,33 @@
 //! - **TextureLoadError**: Failed to load a texture.
 //! - **ShaderCompilationError**: Failed to compile a shader.
 //! - **ShaderLinkError**: Failed to link a shader program.
+//! - **ShaderIncludeError**: Failed to resolve an `#include` in a shader file.
 //! - **FileLoadError**: Failed to load a file.
-//! - **OpenGlError**: OpenGL-related errors.
+//! - **VertexDataError**: Vertex data is inconsistent, e.g. streams of different lengths.
+//! - **OpenGlError**: OpenGL-related errors, classified by a `GlErrorKind`.
 //! - **AudioInitializationError**: Failed to initialize the audio system.
 //! - **SoundLoadError**: Failed to load a sound file.
 //! - **SoundPlayError**: Failed to play a sound.
 //! - **SoundNotFoundError**: Sound not found in the audio system.
 //! - **AudioDecodeError**: Failed to decode an audio file.
 //! - **AudioVolumeError**: Failed to set audio volume.
+//! - **RenderDocError**: Failed to connect to RenderDoc.
+//! - **MapLoadError**: Failed to parse a tilemap file.
+//! - **AnimationLoadError**: Failed to import animations, e.g. from a glTF file.
+//! - **FontLoadError**: Failed to parse a bitmap font file.
+//! - **RenderGraphError**: A render graph's passes cannot be scheduled.
+//! - **CommandListError**: A command list refers to unknown resources or draws without state.
+//! - **SceneNotFoundError**: Scene not found in the scene manager.
+//! - **PrefabNotFoundError**: Prefab not registered in the scene.
+//! - **PrefabLoadError**: Failed to parse saved prefab placements.
+//! - **PoolNotFoundError**: Object pool not registered in the scene.
+//! - **StreamingError**: The background loader of a level streamer stopped.
+//! - **Context**: Another error annotated with what was being done when it occurred.
 //!
 //! ## Example
 //! ```rust
-//! use glwfr::custom_errors::Errors;
+//! use glwfr::custom_errors::{ErrorContext, Errors, GlwfrResult};
+//! use glwfr::graphics::texture::Texture;
 //!
 //! fn load_texture(path: &str) -> Result<(), Errors> {
 //!     if path.is_empty() {
@@ -28,6 +43,15 @@
 //!     // Load texture logic...
 //!     Ok(())
 //! }
+//!
+//! fn load_player() -> GlwfrResult<Texture> {
+//!     let texture = Texture::new();
+//!     // Fails with "loading player skin: Failed to load texture: ..."
+//!     texture
+//!         .load_from_file("player.png")
+//!         .context("loading player skin")?;
+//!     Ok(texture)
+//! }
 //! ```
 
 use thiserror::Error;
@@ -50,11 +74,17 @@ pub enum Errors {
     #[error("Failed to link shader program: {0}")]
     ShaderLinkError(String),
 
+    #[error("Failed to resolve shader include: {0}")]
+    ShaderIncludeError(String),
+
     #[error("Failed to load file: {0}")]
     FileLoadError(String),
 
-    #[error("OpenGL error (code: {1}): {0}")]
-    OpenGlError(String, u32),
+    #[error("Invalid vertex data: {0}")]
+    VertexDataError(String),
+
+    #[error("OpenGL error ({1}): {0}")]
+    OpenGlError(String, GlErrorKind),
 
     #[error("Failed to initialize audio system: {0}")]
     AudioInitializationError(String),
@@ -73,6 +103,172 @@ pub enum Errors {
 
     #[error("Failed to set audio volume: {0}")]
     AudioVolumeError(String),
+
+    #[error("Failed to connect to RenderDoc: {0}")]
+    RenderDocError(String),
+
+    #[error("Failed to load map: {0}")]
+    MapLoadError(String),
+
+    #[error("Failed to load animation: {0}")]
+    AnimationLoadError(String),
+
+    #[error("Failed to load font: {0}")]
+    FontLoadError(String),
+
+    #[error("Invalid render graph: {0}")]
+    RenderGraphError(String),
+
+    #[error("Invalid command list: {0}")]
+    CommandListError(String),
+
+    #[error("Scene not found: {0}")]
+    SceneNotFoundError(String),
+
+    #[error("Prefab not found: {0}")]
+    PrefabNotFoundError(String),
+
+    #[error("Failed to load prefab placements: {0}")]
+    PrefabLoadError(String),
+
+    #[error("Object pool not found: {0}")]
+    PoolNotFoundError(String),
+
+    #[error("Level streaming failed: {0}")]
+    StreamingError(String),
+
+    #[error("{context}: {source}")]
+    Context {
+        context: String,
+        #[source]
+        source: Box<Errors>,
+    },
+}
+
+/// A `Result` with `Errors` as the error type.
+pub type GlwfrResult<T> = Result<T, Errors>;
+
+impl Errors {
+    /// Returns the innermost error, skipping all `Errors::Context` layers.
+    pub fn root_cause(&self) -> &Errors {
+        match self {
+            Errors::Context { source, .. } => source.root_cause(),
+            error => error,
+        }
+    }
+}
+
+/// Adds a description of what was being done to the error of a `Result`.
+pub trait ErrorContext<T> {
+    /// Wraps the error in an `Errors::Context` with the given message.
+    fn context(self, context: impl Into<String>) -> GlwfrResult<T>;
+
+    /// Wraps the error in an `Errors::Context` with a lazily built message.
+    fn with_context<C, F>(self, f: F) -> GlwfrResult<T>
+    where
+        C: Into<String>,
+        F: FnOnce() -> C;
+}
+
+impl<T, E: Into<Errors>> ErrorContext<T> for Result<T, E> {
+    fn context(self, context: impl Into<String>) -> GlwfrResult<T> {
+        self.map_err(|error| Errors::Context {
+            context: context.into(),
+            source: Box::new(error.into()),
+        })
+    }
+
+    fn with_context<C, F>(self, f: F) -> GlwfrResult<T>
+    where
+        C: Into<String>,
+        F: FnOnce() -> C,
+    {
+        self.map_err(|error| Errors::Context {
+            context: f().into(),
+            source: Box::new(error.into()),
+        })
+    }
+}
+
+/// The kind of an `Errors::OpenGlError`, for matching on specific failures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GlErrorKind {
+    /// `GL_INVALID_ENUM`: An unacceptable value was given for an enumerated argument.
+    InvalidEnum,
+    /// `GL_INVALID_VALUE`: A numeric or string argument is out of range.
+    InvalidValue,
+    /// `GL_INVALID_OPERATION`: The operation is not allowed in the current state.
+    InvalidOperation,
+    /// `GL_INVALID_FRAMEBUFFER_OPERATION`: The bound framebuffer is not complete.
+    InvalidFramebufferOperation,
+    /// `GL_OUT_OF_MEMORY`: Not enough memory is left to execute the command.
+    OutOfMemory,
+    /// An OpenGL object could not be generated.
+    ObjectCreationFailed,
+    /// A framebuffer is incomplete. Contains the status from `glCheckFramebufferStatus`.
+    FramebufferIncomplete(u32),
+    /// A uniform or uniform block is not declared by the shader program.
+    UniformNotFound,
+    /// No OpenGL context is current.
+    NoContext,
+    /// OpenGL was used from a thread other than the one owning the context.
+    WrongThread,
+    /// A limited resource, such as texture units, is used up.
+    ResourceExhausted,
+    /// An error code not covered by the other kinds.
+    Unknown(u32),
+}
+
+impl GlErrorKind {
+    /// Returns the kind matching an error code from `glGetError`.
+    pub fn from_code(code: u32) -> Self {
+        match code {
+            gl::INVALID_ENUM => GlErrorKind::InvalidEnum,
+            gl::INVALID_VALUE => GlErrorKind::InvalidValue,
+            gl::INVALID_OPERATION => GlErrorKind::InvalidOperation,
+            gl::INVALID_FRAMEBUFFER_OPERATION => GlErrorKind::InvalidFramebufferOperation,
+            gl::OUT_OF_MEMORY => GlErrorKind::OutOfMemory,
+            _ => GlErrorKind::Unknown(code),
+        }
+    }
+}
+
+impl std::fmt::Display for GlErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GlErrorKind::InvalidEnum => write!(f, "invalid enum"),
+            GlErrorKind::InvalidValue => write!(f, "invalid value"),
+            GlErrorKind::InvalidOperation => write!(f, "invalid operation"),
+            GlErrorKind::InvalidFramebufferOperation => {
+                write!(f, "invalid framebuffer operation")
+            }
+            GlErrorKind::OutOfMemory => write!(f, "out of memory"),
+            GlErrorKind::ObjectCreationFailed => write!(f, "object creation failed"),
+            GlErrorKind::FramebufferIncomplete(status) => {
+                let reason = match *status {
+                    gl::FRAMEBUFFER_UNDEFINED => "undefined",
+                    gl::FRAMEBUFFER_INCOMPLETE_ATTACHMENT => "incomplete attachment",
+                    gl::FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT => "missing attachment",
+                    gl::FRAMEBUFFER_INCOMPLETE_DRAW_BUFFER => "incomplete draw buffer",
+                    gl::FRAMEBUFFER_INCOMPLETE_READ_BUFFER => "incomplete read buffer",
+                    gl::FRAMEBUFFER_UNSUPPORTED => "unsupported format combination",
+                    gl::FRAMEBUFFER_INCOMPLETE_MULTISAMPLE => "mismatched sample counts",
+                    gl::FRAMEBUFFER_INCOMPLETE_LAYER_TARGETS => "mismatched layer targets",
+                    _ => "unknown reason",
+                };
+                write!(
+                    f,
+                    "framebuffer incomplete, {}, status: {:#x}",
+                    reason, status
+                )
+            }
+            GlErrorKind::UniformNotFound => write!(f, "uniform not found"),
+            GlErrorKind::NoContext => write!(f, "no context"),
+            GlErrorKind::WrongThread => write!(f, "wrong thread"),
+            GlErrorKind::ResourceExhausted => write!(f, "resource exhausted"),
+            GlErrorKind::Unknown(code) => write!(f, "unknown error, code: {:#x}", code),
+        }
+    }
 }
 
 impl From<std::io::Error> for Errors {
@@ -81,24 +277,28 @@ impl From<std::io::Error> for Errors {
     }
 }
 
+#[cfg(feature = "native")]
 impl From<rodio::decoder::DecoderError> for Errors {
     fn from(err: rodio::decoder::DecoderError) -> Self {
         Errors::AudioDecodeError(err.to_string())
     }
 }
 
+#[cfg(feature = "native")]
 impl From<rodio::PlayError> for Errors {
     fn from(err: rodio::PlayError) -> Self {
         Errors::SoundPlayError(err.to_string())
     }
 }
 
+#[cfg(feature = "native")]
 impl From<rodio::StreamError> for Errors {
     fn from(err: rodio::StreamError) -> Self {
         Errors::AudioInitializationError(err.to_string())
     }
 }
 
+#[cfg(feature = "native")]
 impl From<rodio::DevicesError> for Errors {
     fn from(err: rodio::DevicesError) -> Self {
         Errors::AudioInitializationError(err.to_string())
@@ -109,14 +309,10 @@ impl From<rodio::DevicesError> for Errors {
 pub fn check_opengl_error() -> Result<(), Errors> {
     let error_code = unsafe { gl::GetError() };
     if error_code != gl::NO_ERROR {
-        let error_message = match error_code {
-            gl::INVALID_ENUM => "Invalid enum".to_string(),
-            gl::INVALID_VALUE => "Invalid value".to_string(),
-            gl::INVALID_OPERATION => "Invalid operation".to_string(),
-            gl::OUT_OF_MEMORY => "Out of memory".to_string(),
-            _ => format!("Unknown OpenGL error (code: {})", error_code),
-        };
-        return Err(Errors::OpenGlError(error_message, error_code));
+        return Err(Errors::OpenGlError(
+            format!("Error code {:#x}", error_code),
+            GlErrorKind::from_code(error_code),
+        ));
     }
     Ok(())
 }
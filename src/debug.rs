@@ -0,0 +1,121 @@
+//! # Debug Module
+//!
+//! This module integrates the [RenderDoc](https://renderdoc.org) graphics debugger. It is only
+//! available with the `renderdoc` feature enabled.
+//!
+//! The application has to be launched from RenderDoc (or with its library injected) for
+//! captures to work; otherwise `init_renderdoc` returns an error and all other functions
+//! do nothing.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::debug;
+//! use glwfr::input::Key;
+//!
+//! // Must be called before the window is created
+//! if let Err(e) = debug::init_renderdoc() {
+//!     log::warn!("RenderDoc not available: {}", e);
+//! }
+//!
+//! // Capture the next frame whenever F11 is pressed
+//! debug::set_capture_key(Some(Key::F11));
+//!
+//! // Or trigger a capture from code
+//! debug::trigger_capture();
+//! ```
+
+use crate::custom_errors::Errors;
+use crate::input::Key;
+use lazy_static::lazy_static;
+use renderdoc::{RenderDoc, V141};
+use std::os::raw::c_void;
+use std::sync::Mutex;
+
+lazy_static! {
+    static ref RENDERDOC: Mutex<Option<RenderDoc<V141>>> = Mutex::new(None);
+    static ref CAPTURE_KEY: Mutex<Option<Key>> = Mutex::new(None);
+}
+
+/// Connects to the RenderDoc in-application API.
+///
+/// Call this before creating the window, so RenderDoc can hook the OpenGL context.
+///
+/// # Errors
+///
+/// Returns an `Errors::RenderDocError` if the application was not launched from RenderDoc.
+pub fn init_renderdoc() -> Result<(), Errors> {
+    let renderdoc = RenderDoc::<V141>::new().map_err(|e| Errors::RenderDocError(e.to_string()))?;
+    *RENDERDOC.lock().unwrap() = Some(renderdoc);
+    Ok(())
+}
+
+/// Checks if the RenderDoc API has been loaded with `init_renderdoc`.
+pub fn is_renderdoc_available() -> bool {
+    RENDERDOC.lock().unwrap().is_some()
+}
+
+/// Captures the next frame presented by the window.
+///
+/// Does nothing if RenderDoc is not available.
+pub fn trigger_capture() {
+    if let Some(renderdoc) = RENDERDOC.lock().unwrap().as_mut() {
+        renderdoc.trigger_capture();
+    }
+}
+
+/// Captures the given number of consecutive frames, starting with the next one.
+///
+/// Does nothing if RenderDoc is not available.
+pub fn trigger_multi_frame_capture(frames: u32) {
+    if let Some(renderdoc) = RENDERDOC.lock().unwrap().as_mut() {
+        renderdoc.trigger_multi_frame_capture(frames);
+    }
+}
+
+/// Starts capturing all OpenGL calls until `end_frame_capture` is called.
+///
+/// Useful for capturing work that is not tied to a presented frame, such as a compute pass.
+/// Does nothing if RenderDoc is not available.
+pub fn start_frame_capture() {
+    if let Some(renderdoc) = RENDERDOC.lock().unwrap().as_mut() {
+        renderdoc.start_frame_capture(std::ptr::null::<c_void>(), std::ptr::null());
+    }
+}
+
+/// Ends a capture started with `start_frame_capture`.
+///
+/// Does nothing if RenderDoc is not available.
+pub fn end_frame_capture() {
+    if let Some(renderdoc) = RENDERDOC.lock().unwrap().as_mut() {
+        renderdoc.end_frame_capture(std::ptr::null::<c_void>(), std::ptr::null());
+    }
+}
+
+/// Checks if a frame capture is currently in progress.
+pub fn is_frame_capturing() -> bool {
+    RENDERDOC
+        .lock()
+        .unwrap()
+        .as_ref()
+        .is_some_and(|renderdoc| renderdoc.is_frame_capturing())
+}
+
+/// Sets a key that triggers a capture of the next frame when pressed, or `None` to disable it.
+///
+/// The key is checked by `Window::update`.
+pub fn set_capture_key(key: Option<Key>) {
+    *CAPTURE_KEY.lock().unwrap() = key;
+}
+
+/// Returns the key that triggers a capture, if any.
+pub fn capture_key() -> Option<Key> {
+    *CAPTURE_KEY.lock().unwrap()
+}
+
+/// Triggers a capture if `key` is the capture key. Called by the window for each key press.
+pub(crate) fn handle_key_press(key: Key) {
+    if capture_key() == Some(key) {
+        trigger_capture();
+    }
+}
@@ -0,0 +1,166 @@
+//! # Alpha Mode Module
+//!
+//! This module describes how the alpha of a material is used. Opaque materials ignore it,
+//! blended materials mix with what is behind them and have to be drawn back to front, and
+//! cutout materials, such as foliage and fences, discard pixels below a threshold. Cutouts
+//! stay in the opaque queue since they write depth like opaque geometry.
+//!
+//! With multisampling active, cutout materials can use alpha-to-coverage instead of
+//! discarding: the alpha is turned into a sample mask, which smooths the hard edges of the
+//! alpha test.
+//!
+//! Shaders can paste `CUTOUT_GLSL` into their fragment shader and pass their alpha through
+//! `applyCutout(alpha)`. `Object::render` uploads the uniforms it needs.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::graphics::alpha_mode::{AlphaMode, CUTOUT_GLSL};
+//! use glwfr::graphics::gl_wrapper::ShaderProgram;
+//! use glwfr::scene::Object;
+//!
+//! fn make_fence(mut object: Object) -> Object {
+//!     object.alpha_mode = AlphaMode::Cutout {
+//!         cutoff: 0.5,
+//!         alpha_to_coverage: true,
+//!     };
+//!     object
+//! }
+//!
+//! let fragment_source = format!(
+//!     "#version 330 core\n{}\nin vec2 uv;\nout vec4 fragColor;\nuniform sampler2D albedo;\n\
+//!      void main() {{\n    vec4 color = texture(albedo, uv);\n\
+//!      fragColor = vec4(color.rgb, applyCutout(color.a));\n}}",
+//!     CUTOUT_GLSL
+//! );
+//! ```
+
+use crate::graphics::gl_wrapper::ShaderProgram;
+
+/// GLSL helper applying the alpha test of cutout materials.
+///
+/// Declares the `alphaCutoff` and `alphaToCoverage` uniforms and
+/// `float applyCutout(float alpha)`, which discards pixels below the cutoff, or with
+/// alpha-to-coverage sharpens the alpha around the cutoff, and returns the alpha to write.
+/// Materials that are not cutouts have a cutoff of `0.0` and keep their alpha.
+pub const CUTOUT_GLSL: &str = r#"
+uniform float alphaCutoff;
+uniform int alphaToCoverage;
+
+float applyCutout(float alpha) {
+    if (alphaCutoff <= 0.0) {
+        return alpha;
+    }
+    if (alphaToCoverage != 0) {
+        // Keep the edge one pixel wide, so coverage gives a crisp but antialiased border
+        return clamp((alpha - alphaCutoff) / max(fwidth(alpha), 0.0001) + 0.5, 0.0, 1.0);
+    }
+    if (alpha < alphaCutoff) {
+        discard;
+    }
+    return 1.0;
+}
+"#;
+
+/// The queue a material is drawn in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum RenderQueue {
+    /// Geometry writing depth, drawn first.
+    #[default]
+    Opaque,
+    /// Blended geometry, drawn after the opaque queue from back to front.
+    Transparent,
+}
+
+/// How the alpha of a material is used.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum AlphaMode {
+    /// The alpha is ignored.
+    #[default]
+    Opaque,
+    /// Pixels with an alpha below `cutoff` are discarded.
+    Cutout {
+        /// The alpha below which pixels are discarded, e.g. `0.5`.
+        cutoff: f32,
+        /// Whether the alpha is turned into MSAA coverage instead when multisampling is active.
+        alpha_to_coverage: bool,
+    },
+    /// The material is blended with what is behind it. The render state of the object should
+    /// enable blending, e.g. `RenderState::transparent()`.
+    Blend,
+}
+
+impl AlphaMode {
+    /// Returns the queue the material is drawn in. Cutouts are drawn with opaque geometry.
+    pub fn render_queue(&self) -> RenderQueue {
+        match self {
+            AlphaMode::Opaque | AlphaMode::Cutout { .. } => RenderQueue::Opaque,
+            AlphaMode::Blend => RenderQueue::Transparent,
+        }
+    }
+
+    /// Returns `true` for cutout materials.
+    pub fn is_cutout(&self) -> bool {
+        matches!(self, AlphaMode::Cutout { .. })
+    }
+
+    /// Checks if alpha-to-coverage is used, which requires multisampling to be active.
+    pub fn uses_alpha_to_coverage(&self) -> bool {
+        matches!(
+            self,
+            AlphaMode::Cutout {
+                alpha_to_coverage: true,
+                ..
+            }
+        ) && multisampling_active()
+    }
+
+    /// Uploads the `alphaCutoff` and `alphaToCoverage` uniforms used by `CUTOUT_GLSL`.
+    ///
+    /// The shader program must be bound before calling this function.
+    ///
+    /// # Returns
+    ///
+    /// `true` if alpha-to-coverage is used, in which case the caller enables it with
+    /// `set_alpha_to_coverage` for the draw.
+    pub fn apply(&self, shader_program: &ShaderProgram) -> bool {
+        let cutoff = match self {
+            AlphaMode::Cutout { cutoff, .. } => cutoff.max(0.0),
+            _ => 0.0,
+        };
+        let alpha_to_coverage = self.uses_alpha_to_coverage();
+        shader_program.try_set_uniform("alphaCutoff", cutoff);
+        shader_program.try_set_uniform("alphaToCoverage", alpha_to_coverage as i32);
+        alpha_to_coverage
+    }
+}
+
+/// Checks if the bound draw framebuffer is multisampled and multisampling is enabled.
+///
+/// # OpenGL Functions
+///
+/// This function is a wrapper around `glGetIntegerv(GL_SAMPLE_BUFFERS)` and
+/// `glIsEnabled(GL_MULTISAMPLE)`.
+pub fn multisampling_active() -> bool {
+    let mut sample_buffers = 0;
+    unsafe {
+        gl::GetIntegerv(gl::SAMPLE_BUFFERS, &mut sample_buffers);
+        sample_buffers > 0 && gl::IsEnabled(gl::MULTISAMPLE) == gl::TRUE
+    }
+}
+
+/// Enables or disables alpha-to-coverage.
+///
+/// # OpenGL Functions
+///
+/// This function is a wrapper around `glEnable(GL_SAMPLE_ALPHA_TO_COVERAGE)` and
+/// `glDisable(GL_SAMPLE_ALPHA_TO_COVERAGE)`.
+pub fn set_alpha_to_coverage(enabled: bool) {
+    unsafe {
+        if enabled {
+            gl::Enable(gl::SAMPLE_ALPHA_TO_COVERAGE);
+        } else {
+            gl::Disable(gl::SAMPLE_ALPHA_TO_COVERAGE);
+        }
+    }
+}
@@ -0,0 +1,331 @@
+//! # Command List Module
+//!
+//! This module provides a `CommandList` that records high-level draw commands without
+//! touching OpenGL. GL objects can only be used on the context thread, but command lists
+//! only refer to materials and meshes by handle, so they can be filled on worker threads,
+//! e.g. one list per culling job, and replayed on the context thread afterwards.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::graphics::command_list::{CommandList, DrawResources};
+//! use glwfr::graphics::gl_wrapper::{ShaderProgram, Vao};
+//! use glwfr::cgmath::{Matrix4, Vector3};
+//! use std::thread;
+//!
+//! fn main() -> Result<(), glwfr::custom_errors::Errors> {
+//!     let mut resources = DrawResources::new();
+//!     let material = resources.add_material(ShaderProgram::new("vertex.glsl", "fragment.glsl")?);
+//!     let mesh = resources.add_mesh(Vao::new()?);
+//!
+//!     // Record on worker threads
+//!     let workers: Vec<_> = (0..4)
+//!         .map(|worker| {
+//!             thread::spawn(move || {
+//!                 let mut list = CommandList::new();
+//!                 list.set_material(material);
+//!                 list.set_mesh(mesh);
+//!                 let offset = Vector3::new(worker as f32 * 2.0, 0.0, 0.0);
+//!                 list.set_uniform("model", Matrix4::from_translation(offset));
+//!                 list.draw();
+//!                 list
+//!             })
+//!         })
+//!         .collect();
+//!
+//!     // Replay on the context thread, in a deterministic order
+//!     let lists: Vec<CommandList> = workers.into_iter().map(|w| w.join().unwrap()).collect();
+//!     CommandList::concat(lists).execute(&resources)?;
+//!     Ok(())
+//! }
+//! ```
+
+use crate::custom_errors::Errors;
+use crate::graphics::gl_wrapper::{self, ShaderProgram, UniformValue, Vao};
+use crate::graphics::mesh::Mesh;
+use crate::graphics::render_state::RenderState;
+use crate::graphics::stats;
+use cgmath::*;
+use gl::types::*;
+use std::rc::Rc;
+
+/// A handle to a shader program registered with `DrawResources`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MaterialHandle(u32);
+
+/// A handle to a mesh registered with `DrawResources`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MeshHandle(u32);
+
+/// A uniform value that can be recorded on any thread.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UniformData {
+    Float(f32),
+    Int(i32),
+    Vec3(Vector3<f32>),
+    Mat4(Matrix4<f32>),
+}
+
+impl From<f32> for UniformData {
+    fn from(value: f32) -> Self {
+        UniformData::Float(value)
+    }
+}
+
+impl From<i32> for UniformData {
+    fn from(value: i32) -> Self {
+        UniformData::Int(value)
+    }
+}
+
+impl From<Vector3<f32>> for UniformData {
+    fn from(value: Vector3<f32>) -> Self {
+        UniformData::Vec3(value)
+    }
+}
+
+impl From<Matrix4<f32>> for UniformData {
+    fn from(value: Matrix4<f32>) -> Self {
+        UniformData::Mat4(value)
+    }
+}
+
+impl UniformValue for UniformData {
+    fn set_uniform(&self, location: GLint) {
+        match self {
+            UniformData::Float(value) => value.set_uniform(location),
+            UniformData::Int(value) => value.set_uniform(location),
+            UniformData::Vec3(value) => value.set_uniform(location),
+            UniformData::Mat4(value) => value.set_uniform(location),
+        }
+    }
+}
+
+/// A recorded draw command.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    /// Binds the shader program of a material.
+    SetMaterial(MaterialHandle),
+    /// Binds a mesh for the following draws.
+    SetMesh(MeshHandle),
+    /// Applies a render state.
+    SetRenderState(RenderState),
+    /// Sets a uniform of the current material. Missing uniforms are skipped.
+    SetUniform { name: String, value: UniformData },
+    /// Draws the current mesh once.
+    Draw,
+    /// Draws the current mesh several times, e.g. with per-instance attributes.
+    DrawInstanced { instances: u32 },
+}
+
+/// The shader programs and meshes command lists refer to. Lives on the context thread.
+#[derive(Default)]
+pub struct DrawResources {
+    materials: Vec<Rc<ShaderProgram>>,
+    meshes: Vec<Rc<Mesh>>,
+}
+
+impl DrawResources {
+    /// Creates an empty set of resources.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a shader program and returns its handle.
+    pub fn add_material(&mut self, shader_program: impl Into<Rc<ShaderProgram>>) -> MaterialHandle {
+        self.materials.push(shader_program.into());
+        MaterialHandle(self.materials.len() as u32 - 1)
+    }
+
+    /// Registers a mesh and returns its handle. The mesh may be shared with objects.
+    pub fn add_mesh(&mut self, mesh: impl Into<Rc<Mesh>>) -> MeshHandle {
+        self.meshes.push(mesh.into());
+        MeshHandle(self.meshes.len() as u32 - 1)
+    }
+
+    /// Returns the shader program of a material.
+    pub fn material(&self, handle: MaterialHandle) -> Option<&Rc<ShaderProgram>> {
+        self.materials.get(handle.0 as usize)
+    }
+
+    /// Returns a mesh.
+    pub fn mesh(&self, handle: MeshHandle) -> Option<&Rc<Mesh>> {
+        self.meshes.get(handle.0 as usize)
+    }
+}
+
+/// A list of draw commands recorded without an OpenGL context.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CommandList {
+    commands: Vec<Command>,
+}
+
+impl CommandList {
+    /// Creates an empty command list.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty command list with room for `capacity` commands.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            commands: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Joins lists, e.g. from several worker threads, into one list replaying them in order.
+    pub fn concat(lists: impl IntoIterator<Item = CommandList>) -> Self {
+        let mut result = CommandList::new();
+        for mut list in lists {
+            result.append(&mut list);
+        }
+        result
+    }
+
+    /// Moves the commands of another list to the end of this list.
+    pub fn append(&mut self, other: &mut CommandList) {
+        self.commands.append(&mut other.commands);
+    }
+
+    /// Records a command.
+    pub fn push(&mut self, command: Command) {
+        self.commands.push(command);
+    }
+
+    /// Records binding the shader program of a material.
+    pub fn set_material(&mut self, material: MaterialHandle) {
+        self.push(Command::SetMaterial(material));
+    }
+
+    /// Records binding a mesh.
+    pub fn set_mesh(&mut self, mesh: MeshHandle) {
+        self.push(Command::SetMesh(mesh));
+    }
+
+    /// Records applying a render state.
+    pub fn set_render_state(&mut self, render_state: RenderState) {
+        self.push(Command::SetRenderState(render_state));
+    }
+
+    /// Records setting a uniform of the current material.
+    pub fn set_uniform(&mut self, name: &str, value: impl Into<UniformData>) {
+        self.push(Command::SetUniform {
+            name: name.to_string(),
+            value: value.into(),
+        });
+    }
+
+    /// Records drawing the current mesh.
+    pub fn draw(&mut self) {
+        self.push(Command::Draw);
+    }
+
+    /// Records drawing the current mesh `instances` times.
+    pub fn draw_instanced(&mut self, instances: u32) {
+        self.push(Command::DrawInstanced { instances });
+    }
+
+    /// Returns the recorded commands.
+    pub fn commands(&self) -> &[Command] {
+        &self.commands
+    }
+
+    /// Returns the number of recorded commands.
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    /// Returns `true` if no commands were recorded.
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// Removes all commands, keeping the allocated memory for the next frame.
+    pub fn clear(&mut self) {
+        self.commands.clear();
+    }
+
+    /// Replays the commands. Must be called on the context thread.
+    ///
+    /// Binding the same material or mesh again is skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `resources` - The materials and meshes the handles refer to.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::CommandListError` if a handle is not registered with `resources`,
+    /// or if a uniform is set or a mesh drawn before a material and mesh were set, and an
+    /// `Errors::OpenGlError` of kind `GlErrorKind::WrongThread` if called on another thread.
+    pub fn execute(&self, resources: &DrawResources) -> Result<(), Errors> {
+        gl_wrapper::check_context_thread()?;
+        let mut material: Option<&ShaderProgram> = None;
+        let mut mesh: Option<&Vao> = None;
+        for command in &self.commands {
+            match command {
+                Command::SetMaterial(handle) => {
+                    let next = resources
+                        .material(*handle)
+                        .ok_or_else(|| invalid_handle("material", handle.0))?;
+                    if !material.is_some_and(|current| std::ptr::eq(current, next.as_ref())) {
+                        next.bind();
+                        material = Some(next);
+                    }
+                }
+                Command::SetMesh(handle) => {
+                    let next = resources
+                        .mesh(*handle)
+                        .ok_or_else(|| invalid_handle("mesh", handle.0))?;
+                    let next = next.vao();
+                    if !mesh.is_some_and(|current| std::ptr::eq(current, next)) {
+                        next.bind();
+                        mesh = Some(next);
+                    }
+                }
+                Command::SetRenderState(render_state) => render_state.apply(),
+                Command::SetUniform { name, value } => {
+                    let material = material.ok_or_else(|| missing_state("set a uniform"))?;
+                    material.try_set_uniform(name, *value);
+                }
+                Command::Draw => {
+                    draw_mesh(mesh.ok_or_else(|| missing_state("draw"))?, 1);
+                }
+                Command::DrawInstanced { instances } => {
+                    draw_mesh(mesh.ok_or_else(|| missing_state("draw"))?, *instances);
+                }
+            }
+        }
+        if mesh.is_some() {
+            Vao::unbind_all();
+        }
+        Ok(())
+    }
+}
+
+/// Draws the bound mesh with its primitive and index types.
+fn draw_mesh(mesh: &Vao, instances: u32) {
+    let primitive_type = mesh.primitive_type();
+    primitive_type.apply(mesh.line_width(), mesh.point_size());
+    unsafe {
+        gl::DrawElementsInstanced(
+            primitive_type.gl_mode(),
+            mesh.index_count() as GLsizei,
+            mesh.index_type().gl_type(),
+            std::ptr::null(),
+            instances as GLsizei,
+        );
+    }
+    stats::record_draw(primitive_type, mesh.index_count(), instances as usize);
+}
+
+fn invalid_handle(kind: &str, index: u32) -> Errors {
+    Errors::CommandListError(format!("refers to unknown {} {}", kind, index))
+}
+
+fn missing_state(action: &str) -> Errors {
+    Errors::CommandListError(format!(
+        "tries to {} before setting a material and mesh",
+        action
+    ))
+}
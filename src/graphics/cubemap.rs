@@ -0,0 +1,566 @@
+//! # Cubemap Module
+//!
+//! This module provides a wrapper for OpenGL cubemap textures, used for skyboxes and
+//! environment reflections. A cubemap can be loaded from six face images, from a single image
+//! with the faces laid out as a cross, or from an equirectangular panorama, which is
+//! resampled into faces on the CPU.
+//!
+//! Faces are in the order of `GL_TEXTURE_CUBE_MAP_POSITIVE_X` to
+//! `GL_TEXTURE_CUBE_MAP_NEGATIVE_Z`, i.e. right, left, top, bottom, front and back, with the
+//! first row of each image at the top as in the usual skybox exports.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::graphics::cubemap::Cubemap;
+//! use glwfr::graphics::texture_unit::TextureUnit;
+//!
+//! fn main() -> Result<(), glwfr::custom_errors::Errors> {
+//!     let skybox = Cubemap::new();
+//!     skybox.load_faces([
+//!         "sky/right.png",
+//!         "sky/left.png",
+//!         "sky/top.png",
+//!         "sky/bottom.png",
+//!         "sky/front.png",
+//!         "sky/back.png",
+//!     ])?;
+//!
+//!     // Or from a single panorama, resampled to 512x512 faces
+//!     let environment = Cubemap::new();
+//!     environment.load_equirectangular("sky/panorama.hdr", 512)?;
+//!
+//!     skybox.bind_to_unit(TextureUnit::new(0));
+//!     Ok(())
+//! }
+//! ```
+
+use crate::custom_errors::Errors;
+use crate::graphics::gl_wrapper::{set_object_label, ContextBound};
+use crate::graphics::stats::{record_texture_bind, track_allocation, MemoryCategory};
+use crate::graphics::texture::{TextureFormat, WrapMode};
+use crate::graphics::texture_unit::TextureUnit;
+use crate::graphics::vertex_compression::f32_to_f16;
+use gl::types::*;
+use image::{DynamicImage, ImageError, RgbaImage};
+use std::cell::Cell;
+use std::f32::consts::PI;
+use std::path::Path;
+
+/// A face of a cubemap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CubeFace {
+    /// The `+X` (right) face.
+    PositiveX,
+    /// The `-X` (left) face.
+    NegativeX,
+    /// The `+Y` (top) face.
+    PositiveY,
+    /// The `-Y` (bottom) face.
+    NegativeY,
+    /// The `+Z` (front) face.
+    PositiveZ,
+    /// The `-Z` (back) face.
+    NegativeZ,
+}
+
+impl CubeFace {
+    /// All faces in the order OpenGL numbers them.
+    pub const ALL: [CubeFace; 6] = [
+        CubeFace::PositiveX,
+        CubeFace::NegativeX,
+        CubeFace::PositiveY,
+        CubeFace::NegativeY,
+        CubeFace::PositiveZ,
+        CubeFace::NegativeZ,
+    ];
+
+    /// Returns the OpenGL texture target of the face, e.g. `GL_TEXTURE_CUBE_MAP_POSITIVE_X`.
+    pub fn gl_target(&self) -> GLenum {
+        gl::TEXTURE_CUBE_MAP_POSITIVE_X + *self as GLenum
+    }
+
+    /// Returns the direction through a point of the face.
+    ///
+    /// `s` and `t` range from `-1.0` to `1.0`, with `t` pointing down the image rows.
+    fn direction(&self, s: f32, t: f32) -> [f32; 3] {
+        match self {
+            CubeFace::PositiveX => [1.0, -t, -s],
+            CubeFace::NegativeX => [-1.0, -t, s],
+            CubeFace::PositiveY => [s, 1.0, t],
+            CubeFace::NegativeY => [s, -1.0, -t],
+            CubeFace::PositiveZ => [s, -t, 1.0],
+            CubeFace::NegativeZ => [-s, -t, -1.0],
+        }
+    }
+}
+
+/// Represents an OpenGL cubemap texture.
+///
+/// All faces are square and share the same size and format.
+pub struct Cubemap {
+    id: GLuint,
+    size: Cell<u32>,
+    memory_size: Cell<usize>,
+    generate_mipmaps: Cell<bool>,
+    format: Cell<TextureFormat>,
+    _context: ContextBound,
+}
+
+impl Cubemap {
+    /// Generate a new OpenGL texture handle and create a `Cubemap` instance wrapping it.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glGenTextures(1, &mut id)`.
+    ///
+    /// # Returns
+    ///
+    /// A new `Cubemap` instance with a valid OpenGL texture ID.
+    pub fn new() -> Self {
+        let mut id = 0;
+        unsafe {
+            gl::GenTextures(1, &mut id);
+        }
+        Self {
+            id,
+            size: Cell::new(0),
+            memory_size: Cell::new(0),
+            generate_mipmaps: Cell::new(true),
+            format: Cell::new(TextureFormat::Rgba8),
+            _context: ContextBound::default(),
+        }
+    }
+
+    /// Returns the OpenGL texture handle.
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
+    /// Attach a debug label to the cubemap, shown by tools like RenderDoc and Nsight.
+    ///
+    /// Does nothing if `GL_KHR_debug` is not available.
+    ///
+    /// # Arguments
+    ///
+    /// * `label` - The label to attach, e.g. `"skybox"`.
+    pub fn set_debug_label(&self, label: &str) {
+        set_object_label(gl::TEXTURE, self.id, label);
+    }
+
+    /// Returns the width and height of each face in pixels, or `0` if nothing has been
+    /// uploaded yet.
+    pub fn size(&self) -> u32 {
+        self.size.get()
+    }
+
+    /// Returns the estimated GPU memory used by all faces in bytes, including mipmaps.
+    pub fn memory_size(&self) -> usize {
+        self.memory_size.get()
+    }
+
+    /// Returns the storage format of the faces.
+    pub fn format(&self) -> TextureFormat {
+        self.format.get()
+    }
+
+    /// Set whether loading faces generates mipmaps with `glGenerateMipmap`.
+    ///
+    /// Defaults to `true`, which is needed for sampling blurrier levels for rough reflections.
+    ///
+    /// # Arguments
+    ///
+    /// * `generate` - Whether to generate mipmaps after uploading the faces.
+    pub fn set_generate_mipmaps(&self, generate: bool) {
+        self.generate_mipmaps.set(generate);
+    }
+
+    /// Returns whether mipmaps are generated automatically after uploading the faces.
+    pub fn generates_mipmaps(&self) -> bool {
+        self.generate_mipmaps.get()
+    }
+
+    /// Generate the mip chain of every face from its base level.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glGenerateMipmap(GL_TEXTURE_CUBE_MAP)`.
+    pub fn generate_mipmaps(&self) {
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, self.id);
+            gl::GenerateMipmap(gl::TEXTURE_CUBE_MAP);
+            gl::TexParameteri(
+                gl::TEXTURE_CUBE_MAP,
+                gl::TEXTURE_MIN_FILTER,
+                gl::LINEAR_MIPMAP_LINEAR as GLint,
+            );
+        }
+        self.set_storage_info(self.size(), self.format(), true);
+    }
+
+    /// It binds the cubemap to the given active texture unit.
+    ///
+    /// # Arguments
+    ///
+    /// * `unit` - The active texture unit to bind the cubemap to.
+    pub fn bind(&self, unit: GLenum) {
+        unsafe {
+            gl::ActiveTexture(unit);
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, self.id);
+        }
+        record_texture_bind();
+    }
+
+    /// Bind the cubemap to the given typed texture unit.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glActiveTexture(unit)` and
+    /// `glBindTexture(GL_TEXTURE_CUBE_MAP, id)`.
+    ///
+    /// # Arguments
+    ///
+    /// * `unit` - The texture unit to bind the cubemap to.
+    pub fn bind_to_unit(&self, unit: TextureUnit) {
+        self.bind(unit.gl_enum());
+    }
+
+    /// Set a parameter of the cubemap.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glTexParameteri(GL_TEXTURE_CUBE_MAP, param, value)`.
+    ///
+    /// # Arguments
+    ///
+    /// * `param` - The parameter to set. For example, `GL_TEXTURE_MIN_FILTER`.
+    /// * `value` - The value to set the parameter to. For example, `GL_LINEAR`.
+    pub fn set_parameteri(&self, param: GLenum, value: GLint) {
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, self.id);
+            gl::TexParameteri(gl::TEXTURE_CUBE_MAP, param, value);
+        }
+    }
+
+    /// Set the wrap mode for all texture coordinate axes.
+    ///
+    /// Cubemaps default to `WrapMode::ClampToEdge`, which avoids seams between faces.
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - The wrap mode to use.
+    pub fn set_wrap(&self, mode: WrapMode) {
+        for param in [gl::TEXTURE_WRAP_S, gl::TEXTURE_WRAP_T, gl::TEXTURE_WRAP_R] {
+            self.set_parameteri(param, mode.to_gl() as GLint);
+        }
+    }
+
+    /// Load the six faces from image files.
+    ///
+    /// # Arguments
+    ///
+    /// * `paths` - The face images in the order `+X`, `-X`, `+Y`, `-Y`, `+Z`, `-Z`. All
+    ///   images must be square and of the same size.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::TextureLoadError` if an image cannot be opened, is not square or
+    /// differs in size from the others.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function uploads the faces with `glTexImage2D(GL_TEXTURE_CUBE_MAP_*, ...)` and,
+    /// unless disabled with `set_generate_mipmaps`, generates mipmaps with
+    /// `glGenerateMipmap(GL_TEXTURE_CUBE_MAP)`.
+    pub fn load_faces<P: AsRef<Path>>(&self, paths: [P; 6]) -> Result<(), Errors> {
+        let mut faces = Vec::with_capacity(6);
+        for path in &paths {
+            faces.push(open_image(path.as_ref())?.to_rgba8());
+        }
+        let size = faces[0].width();
+        if faces
+            .iter()
+            .any(|face| face.width() != size || face.height() != size)
+        {
+            return Err(Errors::TextureLoadError(
+                "Cubemap faces must be square and of the same size".to_string(),
+            ));
+        }
+        let faces: Vec<&[u8]> = faces.iter().map(|face| face.as_raw().as_slice()).collect();
+        self.upload(size, TextureFormat::Rgba8, &faces);
+        Ok(())
+    }
+
+    /// Load the six faces from a single image with the faces laid out as a cross.
+    ///
+    /// Both the horizontal cross, four faces wide and three high, and the vertical cross,
+    /// three faces wide and four high with the back face upside down at the bottom, are
+    /// supported:
+    ///
+    /// ```text
+    ///      +Y                  +Y
+    ///  -X  +Z  +X  -Z      -X  +Z  +X
+    ///      -Y                  -Y
+    ///                          -Z
+    /// ```
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - A path to the cross image.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::TextureLoadError` if the image cannot be opened or its size is
+    /// not that of a horizontal or vertical cross.
+    pub fn load_cross<P: AsRef<Path>>(&self, path: P) -> Result<(), Errors> {
+        let image = open_image(path.as_ref())?.to_rgba8();
+        let (width, height) = image.dimensions();
+        let (size, vertical) = if width * 3 == height * 4 {
+            (width / 4, false)
+        } else if width * 4 == height * 3 {
+            (width / 3, true)
+        } else {
+            return Err(Errors::TextureLoadError(format!(
+                "A {}x{} image is not a cubemap cross",
+                width, height
+            )));
+        };
+
+        let faces: Vec<Vec<u8>> = CubeFace::ALL
+            .iter()
+            .map(|face| {
+                // The cell of the face in the cross, in face sizes
+                let (column, row, flipped) = match (face, vertical) {
+                    (CubeFace::PositiveX, _) => (2, 1, false),
+                    (CubeFace::NegativeX, _) => (0, 1, false),
+                    (CubeFace::PositiveY, _) => (1, 0, false),
+                    (CubeFace::NegativeY, _) => (1, 2, false),
+                    (CubeFace::PositiveZ, _) => (1, 1, false),
+                    (CubeFace::NegativeZ, false) => (3, 1, false),
+                    (CubeFace::NegativeZ, true) => (1, 3, true),
+                };
+                crop_face(&image, column * size, row * size, size, flipped)
+            })
+            .collect();
+        let faces: Vec<&[u8]> = faces.iter().map(Vec::as_slice).collect();
+        self.upload(size, TextureFormat::Rgba8, &faces);
+        Ok(())
+    }
+
+    /// Load the faces from an equirectangular (latitude-longitude) panorama.
+    ///
+    /// The panorama is resampled into faces on the CPU with bilinear filtering and stored as
+    /// `TextureFormat::Rgba16F`, so HDR panoramas keep their range.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - A path to the panorama, twice as wide as it is high, with the sky at the
+    ///   top.
+    /// * `face_size` - The width and height of each face in pixels, e.g. a quarter of the
+    ///   panorama width.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::TextureLoadError` if the image cannot be opened or `face_size` is
+    /// `0`.
+    pub fn load_equirectangular<P: AsRef<Path>>(
+        &self,
+        path: P,
+        face_size: u32,
+    ) -> Result<(), Errors> {
+        if face_size == 0 {
+            return Err(Errors::TextureLoadError(
+                "Cubemap faces must be at least one pixel".to_string(),
+            ));
+        }
+        let panorama = open_image(path.as_ref())?.to_rgba32f();
+        let (width, height) = panorama.dimensions();
+        let pixels = panorama.as_raw();
+        let texel = |x: u32, y: u32| {
+            let start = (y * width + x) as usize * 4;
+            &pixels[start..start + 4]
+        };
+
+        let faces: Vec<Vec<u8>> = CubeFace::ALL
+            .iter()
+            .map(|face| {
+                let mut data = Vec::with_capacity(face_size as usize * face_size as usize * 8);
+                for y in 0..face_size {
+                    for x in 0..face_size {
+                        let s = 2.0 * (x as f32 + 0.5) / face_size as f32 - 1.0;
+                        let t = 2.0 * (y as f32 + 0.5) / face_size as f32 - 1.0;
+                        let [dx, dy, dz] = face.direction(s, t);
+                        let length = (dx * dx + dy * dy + dz * dz).sqrt();
+                        let u = 0.5 + dz.atan2(dx) / (2.0 * PI);
+                        let v = (dy / length).clamp(-1.0, 1.0).acos() / PI;
+
+                        // Bilinear filtering, wrapping around horizontally
+                        let px = u * width as f32 - 0.5;
+                        let py = (v * height as f32 - 0.5).clamp(0.0, (height - 1) as f32);
+                        let (fx, fy) = (px - px.floor(), py - py.floor());
+                        let x0 = (px.floor() as i64).rem_euclid(width as i64) as u32;
+                        let x1 = (x0 + 1) % width;
+                        let y0 = py.floor() as u32;
+                        let y1 = (y0 + 1).min(height - 1);
+                        for channel in 0..4 {
+                            let top =
+                                texel(x0, y0)[channel] * (1.0 - fx) + texel(x1, y0)[channel] * fx;
+                            let bottom =
+                                texel(x0, y1)[channel] * (1.0 - fx) + texel(x1, y1)[channel] * fx;
+                            let value = top * (1.0 - fy) + bottom * fy;
+                            data.extend_from_slice(&f32_to_f16(value).to_ne_bytes());
+                        }
+                    }
+                }
+                data
+            })
+            .collect();
+        let faces: Vec<&[u8]> = faces.iter().map(Vec::as_slice).collect();
+        self.upload(face_size, TextureFormat::Rgba16F, &faces);
+        Ok(())
+    }
+
+    /// Loads the six faces from raw data in the given format.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - The width and height of each face.
+    /// * `format` - The storage format of the faces, which also defines the layout of the
+    ///   data.
+    /// * `faces` - The raw data of the faces in the order `+X`, `-X`, `+Y`, `-Y`, `+Z`, `-Z`,
+    ///   `format.bytes_per_pixel()` bytes per pixel with tightly packed rows.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::TextureLoadError` if the data size of a face is invalid or the
+    /// format is a depth format.
+    pub fn load_from_data_with_format(
+        &self,
+        size: u32,
+        format: TextureFormat,
+        faces: [&[u8]; 6],
+    ) -> Result<(), Errors> {
+        if format.is_depth() {
+            return Err(Errors::TextureLoadError(
+                "Cubemaps cannot be loaded with a depth format".to_string(),
+            ));
+        }
+        let face_size = size as usize * size as usize * format.bytes_per_pixel();
+        if faces.iter().any(|face| face.len() != face_size) {
+            return Err(Errors::TextureLoadError(
+                "Invalid data size for cubemap face".to_string(),
+            ));
+        }
+        self.upload(size, format, &faces);
+        Ok(())
+    }
+
+    /// Records the size of the uploaded faces for `size`, `format` and `memory_size`.
+    fn set_storage_info(&self, size: u32, format: TextureFormat, mipmapped: bool) {
+        self.size.set(size);
+        self.format.set(format);
+        let base = 6 * size as usize * size as usize * format.bytes_per_pixel();
+        // A full mip chain adds roughly a third of the base level.
+        let memory_size = if mipmapped { base + base / 3 } else { base };
+        track_allocation(MemoryCategory::Texture, self.memory_size.get(), memory_size);
+        self.memory_size.set(memory_size);
+    }
+
+    /// Uploads the base level of every face, generating mipmaps if enabled.
+    fn upload(&self, size: u32, format: TextureFormat, faces: &[&[u8]]) {
+        let generate_mipmaps = self.generate_mipmaps.get();
+        // Rows of 1, 2 and 3 byte pixels are not necessarily 4-byte aligned.
+        let tightly_packed = !format.bytes_per_pixel().is_multiple_of(4);
+        let min_filter = if generate_mipmaps {
+            gl::LINEAR_MIPMAP_LINEAR
+        } else {
+            gl::LINEAR
+        };
+
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, self.id);
+            if tightly_packed {
+                gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+            }
+            for (face, data) in CubeFace::ALL.iter().zip(faces) {
+                gl::TexImage2D(
+                    face.gl_target(),
+                    0,
+                    format.internal_format() as GLint,
+                    size as GLsizei,
+                    size as GLsizei,
+                    0,
+                    format.format(),
+                    format.data_type(),
+                    data.as_ptr() as *const _,
+                );
+            }
+            if tightly_packed {
+                gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+            }
+            let parameters = [
+                (gl::TEXTURE_MIN_FILTER, min_filter),
+                (gl::TEXTURE_MAG_FILTER, gl::LINEAR),
+                (gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE),
+                (gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE),
+                (gl::TEXTURE_WRAP_R, gl::CLAMP_TO_EDGE),
+            ];
+            for (parameter, value) in parameters {
+                gl::TexParameteri(gl::TEXTURE_CUBE_MAP, parameter, value as GLint);
+            }
+            if generate_mipmaps {
+                gl::GenerateMipmap(gl::TEXTURE_CUBE_MAP);
+            }
+            // Filter across face edges instead of clamping each face separately
+            gl::Enable(gl::TEXTURE_CUBE_MAP_SEAMLESS);
+        }
+        self.set_storage_info(size, format, generate_mipmaps);
+    }
+}
+
+impl Default for Cubemap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Cubemap {
+    /// Automatically deletes the OpenGL texture when the `Cubemap` instance is dropped.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glDeleteTextures(1, &self.id)`.
+    fn drop(&mut self) {
+        track_allocation(MemoryCategory::Texture, self.memory_size.get(), 0);
+        unsafe {
+            gl::DeleteTextures(1, &self.id);
+        }
+    }
+}
+
+/// Opens an image, mapping failures to `Errors::TextureLoadError`.
+fn open_image(path: &Path) -> Result<DynamicImage, Errors> {
+    image::open(path).map_err(|e: ImageError| {
+        Errors::TextureLoadError(format!(
+            "Failed to load cubemap face {}: {}",
+            path.display(),
+            e
+        ))
+    })
+}
+
+/// Copies a square face out of a cross image, rotating it by 180 degrees if `flipped`.
+fn crop_face(image: &RgbaImage, x: u32, y: u32, size: u32, flipped: bool) -> Vec<u8> {
+    let mut data = Vec::with_capacity(size as usize * size as usize * 4);
+    for row in 0..size {
+        for column in 0..size {
+            let (column, row) = if flipped {
+                (size - 1 - column, size - 1 - row)
+            } else {
+                (column, row)
+            };
+            data.extend_from_slice(&image.get_pixel(x + column, y + row).0);
+        }
+    }
+    data
+}
@@ -0,0 +1,271 @@
+//! # Depth of Field Module
+//!
+//! This module provides a depth-of-field post pass. The circle of confusion of every pixel
+//! is computed from its depth, the focus distance and the aperture, and the image is blurred
+//! by gathering samples on a spiral disc. Pixels in front of the focus plane bleed over
+//! sharper pixels behind them, while sharp foreground pixels are not smeared by the
+//! blurred background.
+//!
+//! The blur is gathered in a single field: the near and far blur are not rendered into
+//! separate layers and composited, so a blurred foreground edge fades into the pixels
+//! behind it instead of showing the background through its bokeh.
+//!
+//! `scene::AutoFocus` animates the focus distance towards an object or a raycast hit.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::graphics::depth_of_field::DepthOfField;
+//!
+//! fn main() -> Result<(), glwfr::custom_errors::Errors> {
+//!     let mut dof = DepthOfField::new(800, 600)?;
+//!     dof.focus_distance = 4.0;
+//!     dof.aperture = 0.3;
+//!
+//!     dof.begin();
+//!     // scene.render();
+//!     // dof.end(camera.projection_matrix());
+//!
+//!     Ok(())
+//! }
+//! ```
+
+use crate::custom_errors::Errors;
+use crate::graphics::gl_wrapper::{Framebuffer, PrimitiveType, ShaderProgram, Vao};
+use crate::graphics::stats;
+use crate::graphics::texture::{Texture, TextureFormat, WrapMode};
+use cgmath::{Matrix4, SquareMatrix};
+use gl::types::*;
+
+const DOF_VERTEX_SHADER: &str = r#"#version 330 core
+out vec2 uv;
+
+void main() {
+    // A single triangle covering the screen
+    vec2 position = vec2((gl_VertexID << 1) & 2, gl_VertexID & 2);
+    uv = position;
+    gl_Position = vec4(position * 2.0 - 1.0, 0.0, 1.0);
+}
+"#;
+
+const DOF_FRAGMENT_SHADER: &str = r#"#version 330 core
+in vec2 uv;
+out vec4 fragColor;
+
+uniform sampler2D colorTexture;
+uniform sampler2D depthTexture;
+uniform mat4 inverseProjection;
+uniform float focusDistance;
+uniform float focusRange;
+uniform float aperture;
+uniform float maxBlur;
+uniform int sampleCount;
+
+const float GOLDEN_ANGLE = 2.39996323;
+
+float viewDistance(vec2 coords) {
+    float depth = texture(depthTexture, coords).r;
+    vec4 view = inverseProjection * vec4(vec3(coords, depth) * 2.0 - 1.0, 1.0);
+    return -view.z / view.w;
+}
+
+// The blur radius in pixels, the same in front of and behind the focus plane
+float circleOfConfusion(float distance) {
+    float offset = abs(distance - focusDistance);
+    float blur = max(offset - focusRange * 0.5, 0.0) * aperture / max(distance, 0.0001);
+    return min(blur, 1.0) * maxBlur;
+}
+
+void main() {
+    float centerDistance = viewDistance(uv);
+    float centerSize = circleOfConfusion(centerDistance);
+    vec3 color = texture(colorTexture, uv).rgb;
+    float total = 1.0;
+    vec2 texelSize = 1.0 / vec2(textureSize(colorTexture, 0));
+
+    for (int i = 0; i < sampleCount; i++) {
+        float radius = maxBlur * sqrt((float(i) + 0.5) / float(sampleCount));
+        float angle = float(i) * GOLDEN_ANGLE;
+        vec2 coords = uv + vec2(cos(angle), sin(angle)) * radius * texelSize;
+        vec3 sampleColor = texture(colorTexture, coords).rgb;
+        float sampleDistance = viewDistance(coords);
+        float sampleSize = circleOfConfusion(sampleDistance);
+        // Blurred background must not spread over a sharper foreground
+        if (sampleDistance > centerDistance) {
+            sampleSize = clamp(sampleSize, 0.0, centerSize * 2.0);
+        }
+        float coverage = smoothstep(radius - 0.5, radius + 0.5, sampleSize);
+        color += mix(color / total, sampleColor, coverage);
+        total += 1.0;
+    }
+    fragColor = vec4(color / total, 1.0);
+}
+"#;
+
+/// Renders the main pass into a color and depth target and blurs it by depth onto the
+/// window.
+pub struct DepthOfField {
+    framebuffer: Framebuffer,
+    color: Texture,
+    depth: Texture,
+    shader_program: ShaderProgram,
+    vao: Vao,
+    width: u32,
+    height: u32,
+    /// The distance from the camera that is in perfect focus.
+    pub focus_distance: f32,
+    /// The depth of the band around `focus_distance` that stays sharp.
+    pub focus_range: f32,
+    /// How quickly the blur grows away from the focus band. `0.0` disables the effect.
+    pub aperture: f32,
+    /// The largest blur radius in pixels.
+    pub max_blur: f32,
+    /// The number of samples gathered per pixel.
+    pub samples: u32,
+}
+
+impl DepthOfField {
+    /// Creates a depth-of-field target of the given size.
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - The width of the window framebuffer in pixels.
+    /// * `height` - The height of the window framebuffer in pixels.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::OpenGlError` if the target cannot be created or is incomplete,
+    /// or an `Errors::ShaderCompilationError` if the blur shader fails to compile.
+    pub fn new(width: u32, height: u32) -> Result<Self, Errors> {
+        let color = Texture::new();
+        let depth = Texture::new();
+        for (texture, filter) in [(&color, gl::LINEAR), (&depth, gl::NEAREST)] {
+            texture.set_generate_mipmaps(false);
+            texture.set_parameteri(gl::TEXTURE_MIN_FILTER, filter as GLint);
+            texture.set_parameteri(gl::TEXTURE_MAG_FILTER, filter as GLint);
+            texture.set_wrap(WrapMode::ClampToEdge);
+        }
+        let shader_program = ShaderProgram::from_source(DOF_VERTEX_SHADER, DOF_FRAGMENT_SHADER)?;
+
+        let mut depth_of_field = Self {
+            framebuffer: Framebuffer::new()?,
+            color,
+            depth,
+            shader_program,
+            vao: Vao::new()?,
+            width: 0,
+            height: 0,
+            focus_distance: 10.0,
+            focus_range: 2.0,
+            aperture: 0.5,
+            max_blur: 8.0,
+            samples: 32,
+        };
+        depth_of_field.resize(width, height)?;
+        Ok(depth_of_field)
+    }
+
+    /// Updates the size of the target, for example after the window was resized.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::OpenGlError` if the resized target is incomplete.
+    pub fn resize(&mut self, width: u32, height: u32) -> Result<(), Errors> {
+        self.width = width.max(1);
+        self.height = height.max(1);
+        self.color
+            .allocate(self.width, self.height, TextureFormat::Rgba16F);
+        self.depth
+            .allocate(self.width, self.height, TextureFormat::Depth24);
+
+        self.framebuffer.bind();
+        unsafe {
+            gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0,
+                gl::TEXTURE_2D,
+                self.color.id(),
+                0,
+            );
+            gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::DEPTH_ATTACHMENT,
+                gl::TEXTURE_2D,
+                self.depth.id(),
+                0,
+            );
+        }
+        let status = self.framebuffer.check_status();
+        self.framebuffer.unbind();
+        status
+    }
+
+    /// Returns the size of the target as `(width, height)`.
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Returns the depth buffer written during the main pass.
+    pub fn depth_texture(&self) -> &Texture {
+        &self.depth
+    }
+
+    /// Start rendering the main pass into the color and depth target.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glBindFramebuffer` and `glViewport`.
+    pub fn begin(&self) {
+        self.framebuffer.bind();
+        unsafe {
+            gl::Viewport(0, 0, self.width as GLsizei, self.height as GLsizei);
+        }
+    }
+
+    /// Blur the main pass onto the window and make the window the active framebuffer again.
+    ///
+    /// Depth testing is disabled while the blur is drawn and enabled again afterwards.
+    ///
+    /// # Arguments
+    ///
+    /// * `projection_matrix` - The projection matrix the main pass was rendered with, used
+    ///   to turn depth back into distances from the camera.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glBindFramebuffer(GL_FRAMEBUFFER, 0)` and
+    /// `glDrawArrays` with a screen-covering triangle.
+    pub fn end(&self, projection_matrix: Matrix4<f32>) {
+        self.framebuffer.unbind();
+        unsafe {
+            gl::Viewport(0, 0, self.width as GLsizei, self.height as GLsizei);
+            gl::Disable(gl::DEPTH_TEST);
+        }
+        let inverse_projection = projection_matrix.invert().unwrap_or_else(Matrix4::identity);
+
+        self.shader_program.bind();
+        self.color.bind(gl::TEXTURE0);
+        self.depth.bind(gl::TEXTURE1);
+        self.shader_program.try_set_uniform("colorTexture", 0);
+        self.shader_program.try_set_uniform("depthTexture", 1);
+        self.shader_program
+            .try_set_uniform("inverseProjection", inverse_projection);
+        self.shader_program
+            .try_set_uniform("focusDistance", self.focus_distance);
+        self.shader_program
+            .try_set_uniform("focusRange", self.focus_range.max(0.0));
+        self.shader_program
+            .try_set_uniform("aperture", self.aperture.max(0.0));
+        self.shader_program
+            .try_set_uniform("maxBlur", self.max_blur.max(0.0));
+        self.shader_program
+            .try_set_uniform("sampleCount", self.samples as i32);
+        self.vao.bind();
+        unsafe {
+            gl::DrawArrays(gl::TRIANGLES, 0, 3);
+            gl::Enable(gl::DEPTH_TEST);
+        }
+        stats::record_draw(PrimitiveType::Triangles, 3, 1);
+        self.vao.unbind();
+    }
+}
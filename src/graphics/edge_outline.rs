@@ -0,0 +1,334 @@
+//! # Edge Outline Module
+//!
+//! This module provides a screen-space outline pass for stylized and toon looks. During the
+//! main pass, shaders write their view-space normal into a third color attachment. A post
+//! pass then draws lines wherever the distance from the camera or the normal changes
+//! sharply between neighbouring pixels, so silhouettes and creases get outlined without any
+//! per-object setup.
+//!
+//! Unlike stencil outlines, which draw an enlarged copy of a single object, this pass
+//! outlines everything on screen, including edges inside an object. Fragment shaders paste
+//! `OUTLINE_NORMAL_GLSL` and call `writeOutlineNormal(viewNormal)`. Shaders that do not
+//! write a normal still get outlines from depth alone.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::graphics::edge_outline::EdgeOutline;
+//! use glwfr::cgmath::Vector3;
+//!
+//! fn main() -> Result<(), glwfr::custom_errors::Errors> {
+//!     let mut outline = EdgeOutline::new(800, 600)?;
+//!     outline.color = Vector3::new(0.05, 0.05, 0.1);
+//!     outline.thickness = 2.0;
+//!
+//!     outline.begin();
+//!     // scene.render();
+//!     // outline.end(camera.projection_matrix());
+//!
+//!     Ok(())
+//! }
+//! ```
+
+use crate::custom_errors::Errors;
+use crate::graphics::gl_wrapper::{Framebuffer, PrimitiveType, ShaderProgram, Vao};
+use crate::graphics::stats;
+use crate::graphics::texture::{Texture, TextureFormat, WrapMode};
+use cgmath::{Matrix4, SquareMatrix, Vector3};
+use gl::types::*;
+
+/// The color attachment the normal buffer is bound to during the main pass.
+///
+/// Attachment `1` is left to `motion_blur::VELOCITY_ATTACHMENT`, so a shader can write both
+/// outputs.
+pub const NORMAL_ATTACHMENT: u32 = 2;
+
+/// GLSL helpers for the fragment shader of the main pass.
+///
+/// Declares the normal output at `NORMAL_ATTACHMENT` and a `writeOutlineNormal(viewNormal)`
+/// function storing the normalized view-space normal of the fragment. Pixels that never
+/// receive a normal are only outlined by depth.
+pub const OUTLINE_NORMAL_GLSL: &str = r#"
+layout(location = 2) out vec4 fragOutlineNormal;
+
+void writeOutlineNormal(vec3 viewNormal) {
+    fragOutlineNormal = vec4(normalize(viewNormal) * 0.5 + 0.5, 1.0);
+}
+"#;
+
+const OUTLINE_VERTEX_SHADER: &str = r#"#version 330 core
+out vec2 uv;
+
+void main() {
+    // A single triangle covering the screen
+    vec2 position = vec2((gl_VertexID << 1) & 2, gl_VertexID & 2);
+    uv = position;
+    gl_Position = vec4(position * 2.0 - 1.0, 0.0, 1.0);
+}
+"#;
+
+const OUTLINE_FRAGMENT_SHADER: &str = r#"#version 330 core
+in vec2 uv;
+out vec4 fragColor;
+
+uniform sampler2D colorTexture;
+uniform sampler2D normalTexture;
+uniform sampler2D depthTexture;
+uniform mat4 inverseProjection;
+uniform vec3 outlineColor;
+uniform float outlineOpacity;
+uniform float thickness;
+uniform float depthThreshold;
+uniform float normalThreshold;
+
+float viewDistance(vec2 coords) {
+    float depth = texture(depthTexture, coords).r;
+    vec4 view = inverseProjection * vec4(vec3(coords, depth) * 2.0 - 1.0, 1.0);
+    return -view.z / view.w;
+}
+
+// The view-space normal in xyz and whether one was written in w
+vec4 viewNormal(vec2 coords) {
+    vec4 normal = texture(normalTexture, coords);
+    return vec4(normal.xyz * 2.0 - 1.0, normal.w);
+}
+
+void main() {
+    vec2 offset = thickness / vec2(textureSize(depthTexture, 0));
+    // Roberts cross: compare the two diagonals around the pixel
+    vec2 coords[4] = vec2[](
+        uv + vec2(-offset.x, -offset.y),
+        uv + vec2(offset.x, offset.y),
+        uv + vec2(offset.x, -offset.y),
+        uv + vec2(-offset.x, offset.y)
+    );
+
+    float distances[4];
+    vec4 normals[4];
+    for (int i = 0; i < 4; i++) {
+        distances[i] = viewDistance(coords[i]);
+        normals[i] = viewNormal(coords[i]);
+    }
+
+    // Depth edges are relative to the distance so far surfaces are not all outlined
+    float nearest = max(min(min(distances[0], distances[1]), min(distances[2], distances[3])), 0.0001);
+    float depthEdge = max(abs(distances[0] - distances[1]), abs(distances[2] - distances[3])) / nearest;
+    float edge = step(depthThreshold, depthEdge);
+
+    float normalEdge = 0.0;
+    for (int i = 0; i < 4; i += 2) {
+        if (normals[i].w > 0.5 && normals[i + 1].w > 0.5) {
+            normalEdge = max(normalEdge, 1.0 - dot(normals[i].xyz, normals[i + 1].xyz));
+        }
+    }
+    edge = max(edge, step(normalThreshold, normalEdge));
+
+    vec4 color = texture(colorTexture, uv);
+    fragColor = vec4(mix(color.rgb, outlineColor, edge * outlineOpacity), color.a);
+}
+"#;
+
+/// Renders the main pass into a color, normal and depth target and draws outlines along
+/// its edges onto the window.
+pub struct EdgeOutline {
+    framebuffer: Framebuffer,
+    color_texture: Texture,
+    normal: Texture,
+    depth: Texture,
+    shader_program: ShaderProgram,
+    vao: Vao,
+    width: u32,
+    height: u32,
+    /// The color of the outlines.
+    pub color: Vector3<f32>,
+    /// How strongly the outlines cover the image. `0.0` disables the effect.
+    pub opacity: f32,
+    /// The distance in pixels between the compared samples, which sets the line width.
+    pub thickness: f32,
+    /// The relative change in distance from the camera that counts as an edge, e.g. `0.1`
+    /// for a 10% jump.
+    pub depth_threshold: f32,
+    /// The change in normal direction that counts as an edge, from `0.0` (any change) to
+    /// `2.0` (opposite normals).
+    pub normal_threshold: f32,
+}
+
+impl EdgeOutline {
+    /// Creates an outline target of the given size.
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - The width of the window framebuffer in pixels.
+    /// * `height` - The height of the window framebuffer in pixels.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::OpenGlError` if the target cannot be created or is incomplete,
+    /// or an `Errors::ShaderCompilationError` if the outline shader fails to compile.
+    pub fn new(width: u32, height: u32) -> Result<Self, Errors> {
+        let color_texture = Texture::new();
+        let normal = Texture::new();
+        let depth = Texture::new();
+        for texture in [&color_texture, &normal, &depth] {
+            texture.set_generate_mipmaps(false);
+            texture.set_parameteri(gl::TEXTURE_MIN_FILTER, gl::NEAREST as GLint);
+            texture.set_parameteri(gl::TEXTURE_MAG_FILTER, gl::NEAREST as GLint);
+            texture.set_wrap(WrapMode::ClampToEdge);
+        }
+        let shader_program =
+            ShaderProgram::from_source(OUTLINE_VERTEX_SHADER, OUTLINE_FRAGMENT_SHADER)?;
+
+        let mut outline = Self {
+            framebuffer: Framebuffer::new()?,
+            color_texture,
+            normal,
+            depth,
+            shader_program,
+            vao: Vao::new()?,
+            width: 0,
+            height: 0,
+            color: Vector3::new(0.0, 0.0, 0.0),
+            opacity: 1.0,
+            thickness: 1.0,
+            depth_threshold: 0.1,
+            normal_threshold: 0.4,
+        };
+        outline.resize(width, height)?;
+        Ok(outline)
+    }
+
+    /// Updates the size of the target, for example after the window was resized.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::OpenGlError` if the resized target is incomplete.
+    pub fn resize(&mut self, width: u32, height: u32) -> Result<(), Errors> {
+        self.width = width.max(1);
+        self.height = height.max(1);
+        self.color_texture
+            .allocate(self.width, self.height, TextureFormat::Rgba16F);
+        self.normal
+            .allocate(self.width, self.height, TextureFormat::Rgba8);
+        self.depth
+            .allocate(self.width, self.height, TextureFormat::Depth24);
+
+        self.framebuffer.bind();
+        unsafe {
+            gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0,
+                gl::TEXTURE_2D,
+                self.color_texture.id(),
+                0,
+            );
+            gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0 + NORMAL_ATTACHMENT,
+                gl::TEXTURE_2D,
+                self.normal.id(),
+                0,
+            );
+            gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::DEPTH_ATTACHMENT,
+                gl::TEXTURE_2D,
+                self.depth.id(),
+                0,
+            );
+        }
+        let status = self.framebuffer.check_status();
+        self.framebuffer.unbind();
+        status
+    }
+
+    /// Returns the size of the target as `(width, height)`.
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Returns the normal buffer written during the main pass.
+    pub fn normal_texture(&self) -> &Texture {
+        &self.normal
+    }
+
+    /// Returns the depth buffer written during the main pass.
+    pub fn depth_texture(&self) -> &Texture {
+        &self.depth
+    }
+
+    /// Start rendering the main pass into the color, normal and depth target.
+    ///
+    /// Clears the color and depth buffers, and the normal buffer to zero so pixels without
+    /// a normal are only outlined by depth.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glBindFramebuffer`, `glDrawBuffers`, `glViewport`
+    /// and `glClearBufferfv`.
+    pub fn begin(&self) {
+        self.framebuffer.bind();
+        // Fragment output locations index the draw buffers, so location 1 writes nowhere
+        let draw_buffers = [
+            gl::COLOR_ATTACHMENT0,
+            gl::NONE,
+            gl::COLOR_ATTACHMENT0 + NORMAL_ATTACHMENT,
+        ];
+        let zero = [0.0f32; 4];
+        unsafe {
+            gl::DrawBuffers(draw_buffers.len() as GLsizei, draw_buffers.as_ptr());
+            gl::Viewport(0, 0, self.width as GLsizei, self.height as GLsizei);
+            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+            gl::ClearBufferfv(gl::COLOR, NORMAL_ATTACHMENT as GLint, zero.as_ptr());
+        }
+    }
+
+    /// Draw the main pass with outlines onto the window and make the window the active
+    /// framebuffer again.
+    ///
+    /// Depth testing is disabled while the outlines are drawn and enabled again afterwards.
+    ///
+    /// # Arguments
+    ///
+    /// * `projection_matrix` - The projection matrix the main pass was rendered with, used
+    ///   to turn depth back into distances from the camera.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glBindFramebuffer(GL_FRAMEBUFFER, 0)` and
+    /// `glDrawArrays` with a screen-covering triangle.
+    pub fn end(&self, projection_matrix: Matrix4<f32>) {
+        self.framebuffer.unbind();
+        unsafe {
+            gl::Viewport(0, 0, self.width as GLsizei, self.height as GLsizei);
+            gl::Disable(gl::DEPTH_TEST);
+        }
+        let inverse_projection = projection_matrix.invert().unwrap_or_else(Matrix4::identity);
+
+        self.shader_program.bind();
+        self.color_texture.bind(gl::TEXTURE0);
+        self.normal.bind(gl::TEXTURE1);
+        self.depth.bind(gl::TEXTURE2);
+        self.shader_program.try_set_uniform("colorTexture", 0);
+        self.shader_program.try_set_uniform("normalTexture", 1);
+        self.shader_program.try_set_uniform("depthTexture", 2);
+        self.shader_program
+            .try_set_uniform("inverseProjection", inverse_projection);
+        self.shader_program
+            .try_set_uniform("outlineColor", self.color);
+        self.shader_program
+            .try_set_uniform("outlineOpacity", self.opacity.clamp(0.0, 1.0));
+        self.shader_program
+            .try_set_uniform("thickness", self.thickness.max(0.0));
+        self.shader_program
+            .try_set_uniform("depthThreshold", self.depth_threshold.max(0.0));
+        self.shader_program
+            .try_set_uniform("normalThreshold", self.normal_threshold.max(0.0));
+        self.vao.bind();
+        unsafe {
+            gl::DrawArrays(gl::TRIANGLES, 0, 3);
+            gl::Enable(gl::DEPTH_TEST);
+        }
+        stats::record_draw(PrimitiveType::Triangles, 3, 1);
+        self.vao.unbind();
+    }
+}
@@ -0,0 +1,204 @@
+//! # GL Info Module
+//!
+//! This module queries the capabilities of the current OpenGL context, such as its version,
+//! driver strings, supported extensions and implementation limits.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::graphics::gl_info::{Extension, GlInfo};
+//! use glwfr::graphics::window::Window;
+//!
+//! fn main() -> Result<(), glwfr::custom_errors::Errors> {
+//!     let mut window = Window::new(800, 600, "GL info")?;
+//!     window.init_gl()?;
+//!
+//!     let info = GlInfo::query()?;
+//!     println!("{} on {}", info.version_string, info.renderer);
+//!     if !info.supports(Extension::ComputeShader) {
+//!         println!("Falling back to the CPU path");
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use crate::custom_errors::{Errors, GlErrorKind};
+use gl::types::*;
+use std::collections::HashSet;
+use std::ffi::CStr;
+
+/// Optional OpenGL features that can be checked with `GlInfo::supports`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Extension {
+    /// Compute shaders (`GL_ARB_compute_shader`, core in 4.3).
+    ComputeShader,
+    /// Shader storage buffers (`GL_ARB_shader_storage_buffer_object`, core in 4.3).
+    ShaderStorageBufferObject,
+    /// Debug output callbacks (`GL_KHR_debug`, core in 4.3).
+    DebugOutput,
+    /// `glMultiDrawElementsIndirect` (`GL_ARB_multi_draw_indirect`, core in 4.3).
+    MultiDrawIndirect,
+    /// Tessellation shaders (`GL_ARB_tessellation_shader`, core in 4.0).
+    TessellationShader,
+    /// Immutable texture storage (`GL_ARB_texture_storage`, core in 4.2).
+    TextureStorage,
+    /// Direct state access (`GL_ARB_direct_state_access`, core in 4.5).
+    DirectStateAccess,
+    /// Anisotropic texture filtering (`GL_EXT_texture_filter_anisotropic`, core in 4.6).
+    TextureFilterAnisotropic,
+    /// Bindless textures (`GL_ARB_bindless_texture`, extension only).
+    BindlessTexture,
+}
+
+impl Extension {
+    /// Returns the name of the extension string advertising the feature.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Extension::ComputeShader => "GL_ARB_compute_shader",
+            Extension::ShaderStorageBufferObject => "GL_ARB_shader_storage_buffer_object",
+            Extension::DebugOutput => "GL_KHR_debug",
+            Extension::MultiDrawIndirect => "GL_ARB_multi_draw_indirect",
+            Extension::TessellationShader => "GL_ARB_tessellation_shader",
+            Extension::TextureStorage => "GL_ARB_texture_storage",
+            Extension::DirectStateAccess => "GL_ARB_direct_state_access",
+            Extension::TextureFilterAnisotropic => "GL_EXT_texture_filter_anisotropic",
+            Extension::BindlessTexture => "GL_ARB_bindless_texture",
+        }
+    }
+
+    /// Returns the OpenGL version in which the feature became core, if any.
+    pub fn core_version(&self) -> Option<(i32, i32)> {
+        match self {
+            Extension::ComputeShader
+            | Extension::ShaderStorageBufferObject
+            | Extension::DebugOutput
+            | Extension::MultiDrawIndirect => Some((4, 3)),
+            Extension::TessellationShader => Some((4, 0)),
+            Extension::TextureStorage => Some((4, 2)),
+            Extension::DirectStateAccess => Some((4, 5)),
+            Extension::TextureFilterAnisotropic => Some((4, 6)),
+            Extension::BindlessTexture => None,
+        }
+    }
+}
+
+/// Capabilities of the current OpenGL context.
+#[derive(Debug, Clone)]
+pub struct GlInfo {
+    /// The context version as `(major, minor)`.
+    pub version: (i32, i32),
+    /// The full version string reported by the driver.
+    pub version_string: String,
+    /// The company responsible for the implementation.
+    pub vendor: String,
+    /// The name of the renderer, usually the GPU model.
+    pub renderer: String,
+    /// The supported GLSL version string.
+    pub glsl_version: String,
+    /// All supported extension strings.
+    pub extensions: HashSet<String>,
+    /// The largest supported texture width or height.
+    pub max_texture_size: i32,
+    /// The number of texture units available to all shader stages combined.
+    pub max_texture_units: i32,
+    /// The largest supported uniform block in bytes.
+    pub max_uniform_block_size: i32,
+    /// The number of vertex attributes available to the vertex shader.
+    pub max_vertex_attribs: i32,
+    /// The largest supported MSAA sample count.
+    pub max_samples: i32,
+}
+
+impl GlInfo {
+    /// Queries the capabilities of the current OpenGL context.
+    ///
+    /// Must be called after `Window::init_gl`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::OpenGlError` if no OpenGL context is current.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glGetString`, `glGetStringi` and `glGetIntegerv`.
+    pub fn query() -> Result<Self, Errors> {
+        let version_string = Self::get_string(gl::VERSION).ok_or_else(|| {
+            Errors::OpenGlError(
+                "No current OpenGL context".to_string(),
+                GlErrorKind::NoContext,
+            )
+        })?;
+
+        let extension_count = Self::get_integer(gl::NUM_EXTENSIONS);
+        let extensions = (0..extension_count.max(0) as u32)
+            .filter_map(|index| unsafe {
+                let ptr = gl::GetStringi(gl::EXTENSIONS, index);
+                (!ptr.is_null()).then(|| {
+                    CStr::from_ptr(ptr as *const _)
+                        .to_string_lossy()
+                        .into_owned()
+                })
+            })
+            .collect();
+
+        Ok(Self {
+            version: (
+                Self::get_integer(gl::MAJOR_VERSION),
+                Self::get_integer(gl::MINOR_VERSION),
+            ),
+            version_string,
+            vendor: Self::get_string(gl::VENDOR).unwrap_or_default(),
+            renderer: Self::get_string(gl::RENDERER).unwrap_or_default(),
+            glsl_version: Self::get_string(gl::SHADING_LANGUAGE_VERSION).unwrap_or_default(),
+            extensions,
+            max_texture_size: Self::get_integer(gl::MAX_TEXTURE_SIZE),
+            max_texture_units: Self::get_integer(gl::MAX_COMBINED_TEXTURE_IMAGE_UNITS),
+            max_uniform_block_size: Self::get_integer(gl::MAX_UNIFORM_BLOCK_SIZE),
+            max_vertex_attribs: Self::get_integer(gl::MAX_VERTEX_ATTRIBS),
+            max_samples: Self::get_integer(gl::MAX_SAMPLES),
+        })
+    }
+
+    /// Checks if the context version is at least `major.minor`.
+    pub fn version_at_least(&self, major: i32, minor: i32) -> bool {
+        self.version >= (major, minor)
+    }
+
+    /// Checks if the given extension string is advertised by the driver.
+    pub fn has_extension(&self, name: &str) -> bool {
+        self.extensions.contains(name)
+    }
+
+    /// Checks if a feature is available, either through the core version or as an extension.
+    pub fn supports(&self, extension: Extension) -> bool {
+        extension
+            .core_version()
+            .is_some_and(|(major, minor)| self.version_at_least(major, minor))
+            || self.has_extension(extension.name())
+    }
+
+    /// Reads a driver string, returning `None` if no context is current.
+    fn get_string(name: GLenum) -> Option<String> {
+        unsafe {
+            let ptr = gl::GetString(name);
+            if ptr.is_null() {
+                None
+            } else {
+                Some(
+                    CStr::from_ptr(ptr as *const _)
+                        .to_string_lossy()
+                        .into_owned(),
+                )
+            }
+        }
+    }
+
+    /// Reads a single integer limit.
+    fn get_integer(name: GLenum) -> i32 {
+        let mut value = 0;
+        unsafe {
+            gl::GetIntegerv(name, &mut value);
+        }
+        value
+    }
+}
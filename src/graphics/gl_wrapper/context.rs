@@ -0,0 +1,58 @@
+//! # Context Module
+//!
+//! OpenGL contexts are bound to the thread they were made current on. The GL wrapper types
+//! are `!Send` and `!Sync`, so they cannot be moved to another thread, and their constructors
+//! return an error when called on a thread other than the context thread.
+
+use crate::custom_errors::{Errors, GlErrorKind};
+use lazy_static::lazy_static;
+use std::marker::PhantomData;
+use std::sync::Mutex;
+use std::thread::{self, ThreadId};
+
+lazy_static! {
+    static ref CONTEXT_THREAD: Mutex<Option<ThreadId>> = Mutex::new(None);
+}
+
+/// Marker field that makes a GL wrapper type `!Send` and `!Sync`.
+pub(crate) type ContextBound = PhantomData<*const ()>;
+
+/// Record the current thread as the thread owning the OpenGL context.
+///
+/// Called by `Window::init_gl`; only needed when the context is created by other means.
+pub fn mark_context_thread() {
+    *CONTEXT_THREAD.lock().unwrap() = Some(thread::current().id());
+}
+
+/// Checks if the current thread owns the OpenGL context.
+///
+/// # Returns
+///
+/// `true` if the current thread is the context thread or no context thread has been recorded
+/// yet, otherwise `false`.
+pub fn is_context_thread() -> bool {
+    CONTEXT_THREAD
+        .lock()
+        .unwrap()
+        .is_none_or(|id| id == thread::current().id())
+}
+
+/// Make sure the current thread owns the OpenGL context.
+///
+/// # Errors
+///
+/// Returns an `Errors::OpenGlError` of kind `GlErrorKind::WrongThread` if the context was made
+/// current on another thread.
+pub fn check_context_thread() -> Result<(), Errors> {
+    if is_context_thread() {
+        Ok(())
+    } else {
+        Err(Errors::OpenGlError(
+            format!(
+                "OpenGL used from thread {:?}, but the context belongs to another thread",
+                thread::current().id()
+            ),
+            GlErrorKind::WrongThread,
+        ))
+    }
+}
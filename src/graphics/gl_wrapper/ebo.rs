@@ -1,11 +1,67 @@
 //! # EBO Module
 
-use crate::custom_errors::Errors;
+use crate::custom_errors::{Errors, GlErrorKind};
+use crate::graphics::gl_wrapper::context::{check_context_thread, ContextBound};
+use crate::graphics::gl_wrapper::label::set_object_label;
+use crate::graphics::stats::{track_allocation, MemoryCategory};
+use std::cell::Cell;
 use std::mem;
 use std::os::raw::*;
 
+/// The type of the indices stored in an EBO.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum IndexType {
+    /// 8-bit unsigned indices, up to 256 vertices.
+    U8,
+    /// 16-bit unsigned indices, up to 65536 vertices.
+    U16,
+    /// 32-bit unsigned indices.
+    #[default]
+    U32,
+}
+
+impl IndexType {
+    /// Returns the OpenGL enum for the index type, as expected by `glDrawElements`.
+    pub fn gl_type(&self) -> gl::types::GLenum {
+        match self {
+            IndexType::U8 => gl::UNSIGNED_BYTE,
+            IndexType::U16 => gl::UNSIGNED_SHORT,
+            IndexType::U32 => gl::UNSIGNED_INT,
+        }
+    }
+
+    /// Returns the size of a single index in bytes.
+    pub fn size(&self) -> usize {
+        match self {
+            IndexType::U8 => mem::size_of::<u8>(),
+            IndexType::U16 => mem::size_of::<u16>(),
+            IndexType::U32 => mem::size_of::<u32>(),
+        }
+    }
+}
+
+/// An integer type that can be stored in an EBO.
+pub trait Index: Copy {
+    /// The `IndexType` matching this integer type.
+    const INDEX_TYPE: IndexType;
+}
+
+impl Index for u8 {
+    const INDEX_TYPE: IndexType = IndexType::U8;
+}
+
+impl Index for u16 {
+    const INDEX_TYPE: IndexType = IndexType::U16;
+}
+
+impl Index for u32 {
+    const INDEX_TYPE: IndexType = IndexType::U32;
+}
+
 pub struct Ebo {
     id: gl::types::GLuint,
+    size: Cell<usize>,
+    _context: ContextBound,
 }
 
 impl Ebo {
@@ -18,9 +74,10 @@ impl Ebo {
     ///
     /// # Errors
     ///
-    /// Returns an `Errors::OpenGlError` if the EBO cannot be generated.
-
+    /// Returns an `Errors::OpenGlError` if the EBO cannot be generated or if called from a
+    /// thread other than the context thread.
     pub fn new() -> Result<Self, Errors> {
+        check_context_thread()?;
         let mut id = 0;
         unsafe {
             gl::GenBuffers(1, &mut id);
@@ -28,10 +85,34 @@ impl Ebo {
         if id == 0 {
             return Err(Errors::OpenGlError(
                 "Failed to generate EBO".to_string(),
-                gl::INVALID_OPERATION,
+                GlErrorKind::ObjectCreationFailed,
             ));
         }
-        Ok(Self { id })
+        Ok(Self {
+            id,
+            size: Cell::new(0),
+            _context: ContextBound::default(),
+        })
+    }
+
+    /// Attach a debug label to the buffer, shown by tools like RenderDoc and Nsight.
+    ///
+    /// Does nothing if `GL_KHR_debug` is not available.
+    ///
+    /// # Arguments
+    ///
+    /// * `label` - The label to attach, e.g. `"player_mesh_ebo"`.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glObjectLabel`.
+    pub fn set_debug_label(&self, label: &str) {
+        set_object_label(gl::BUFFER, self.id, label);
+    }
+
+    /// Returns the size of the stored indices in bytes, or `0` if nothing has been stored yet.
+    pub fn size(&self) -> usize {
+        self.size.get()
     }
 
     /// Bind the Element Buffer Object (EBO) to the current OpenGL context, making it the active EBO.
@@ -57,25 +138,33 @@ impl Ebo {
         }
     }
 
-    /// Store the given u32 slice in the Element Buffer Object (EBO).
+    /// Store the given index slice in the Element Buffer Object (EBO).
     ///
     /// # OpenGL Functions
     ///
     /// This function is a wrapper around `glBufferData(gl::ELEMENT_ARRAY_BUFFER, size, data, usage)`.
-    /// It stores the given u32 slice in the EBO.
+    /// It stores the given u8, u16 or u32 slice in the EBO.
     ///
     /// # Arguments
     ///
-    /// * `indices` - The u32 slice to store in the EBO.
-    pub fn store_indices(&self, indices: &[u32]) {
+    /// * `indices` - The index slice to store in the EBO.
+    ///
+    /// # Returns
+    ///
+    /// The `IndexType` of the stored indices, to be passed to `Vao::set_index_type`.
+    pub fn store_indices<T: Index>(&self, indices: &[T]) -> IndexType {
+        let size = mem::size_of_val(indices);
+        track_allocation(MemoryCategory::Buffer, self.size.get(), size);
+        self.size.set(size);
         unsafe {
             gl::BufferData(
                 gl::ELEMENT_ARRAY_BUFFER,
-                (indices.len() * mem::size_of::<u32>()) as gl::types::GLsizeiptr,
+                size as gl::types::GLsizeiptr,
                 indices.as_ptr() as *const c_void,
                 gl::STATIC_DRAW,
             );
         }
+        T::INDEX_TYPE
     }
 
     /// Update the indices in the Element Buffer Object (EBO).
@@ -88,14 +177,30 @@ impl Ebo {
     /// # OpenGL Functions
     ///
     /// This function is a wrapper around `glBufferSubData`.
-    pub fn update_indices(&self, offset: usize, indices: &[u32]) {
+    pub fn update_indices<T: Index>(&self, offset: usize, indices: &[T]) {
         unsafe {
             gl::BufferSubData(
                 gl::ELEMENT_ARRAY_BUFFER,
                 offset as isize,
-                (indices.len() * mem::size_of::<u32>()) as isize,
+                mem::size_of_val(indices) as isize,
                 indices.as_ptr() as *const c_void,
             );
         }
     }
 }
+
+impl Drop for Ebo {
+    /// Automatically deletes the OpenGL buffer when the `Ebo` instance is dropped.
+    ///
+    /// VAOs referencing the buffer keep its data store alive until they are deleted.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glDeleteBuffers(1, &self.id)`.
+    fn drop(&mut self) {
+        track_allocation(MemoryCategory::Buffer, self.size.get(), 0);
+        unsafe {
+            gl::DeleteBuffers(1, &self.id);
+        }
+    }
+}
@@ -0,0 +1,400 @@
+//! # Framebuffer Module
+//!
+//! This module provides a `Framebuffer` for rendering off-screen, e.g. for post-processing,
+//! mirrors or shadow maps. Textures attached to it can be sampled afterwards; renderbuffers
+//! are cheaper for attachments that are only rendered to.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::graphics::gl_wrapper::Framebuffer;
+//! use glwfr::graphics::render_scale::ScaleFilter;
+//! use glwfr::graphics::texture::{Texture, TextureFormat};
+//!
+//! fn main() -> Result<(), glwfr::custom_errors::Errors> {
+//!     let color = Texture::new();
+//!     color.allocate(1280, 720, TextureFormat::Rgba8);
+//!     let depth = Texture::new();
+//!     depth.allocate(1280, 720, TextureFormat::Depth24);
+//!
+//!     let framebuffer = Framebuffer::new()?;
+//!     framebuffer.attach_texture(gl::COLOR_ATTACHMENT0, &color);
+//!     framebuffer.attach_texture(depth.format().default_attachment(), &depth);
+//!     framebuffer.check_status()?;
+//!
+//!     framebuffer.clear();
+//!     // Draw the scene, then sample `color` or show it in the window
+//!     framebuffer.unbind();
+//!     framebuffer.blit_to_default(framebuffer.rect(), (1280, 720), ScaleFilter::Linear)?;
+//!     Ok(())
+//! }
+//! ```
+
+use crate::custom_errors::{Errors, GlErrorKind};
+use crate::graphics::gl_wrapper::context::{check_context_thread, ContextBound};
+use crate::graphics::gl_wrapper::label::set_object_label;
+use crate::graphics::gl_wrapper::renderbuffer::Renderbuffer;
+use crate::graphics::render_scale::ScaleFilter;
+use crate::graphics::render_state::ClearState;
+use crate::graphics::renderer::Rect;
+use crate::graphics::texture::Texture;
+use gl::types::*;
+use std::cell::Cell;
+
+/// An off-screen render target.
+pub struct Framebuffer {
+    id: GLuint,
+    width: Cell<u32>,
+    height: Cell<u32>,
+    clear_state: Cell<ClearState>,
+    _context: ContextBound,
+}
+
+impl Framebuffer {
+    /// Generate a new framebuffer object without any attachments.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::OpenGlError` if the framebuffer cannot be generated or if called
+    /// from a thread other than the context thread.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glGenFramebuffers`.
+    pub fn new() -> Result<Self, Errors> {
+        check_context_thread()?;
+        let mut id = 0;
+        unsafe {
+            gl::GenFramebuffers(1, &mut id);
+        }
+        if id == 0 {
+            return Err(Errors::OpenGlError(
+                "Failed to generate framebuffer".to_string(),
+                GlErrorKind::ObjectCreationFailed,
+            ));
+        }
+        Ok(Self {
+            id,
+            width: Cell::new(0),
+            height: Cell::new(0),
+            clear_state: Cell::new(ClearState::default()),
+            _context: ContextBound::default(),
+        })
+    }
+
+    /// Returns the OpenGL framebuffer handle.
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
+    /// Returns the width of the framebuffer in pixels, taken from the last attachment.
+    pub fn width(&self) -> u32 {
+        self.width.get()
+    }
+
+    /// Returns the height of the framebuffer in pixels, taken from the last attachment.
+    pub fn height(&self) -> u32 {
+        self.height.get()
+    }
+
+    /// Returns a rectangle covering the whole framebuffer.
+    pub fn rect(&self) -> Rect {
+        Rect::new(0, 0, self.width() as i32, self.height() as i32)
+    }
+
+    /// Bind the framebuffer, so subsequent draw calls render into it.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glBindFramebuffer(GL_FRAMEBUFFER, id)`.
+    pub fn bind(&self) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.id);
+        }
+    }
+
+    /// Set how `clear` clears the framebuffer.
+    ///
+    /// Defaults to `ClearState::default()`, clearing the color to opaque black and the
+    /// depth to `1.0`.
+    ///
+    /// # Arguments
+    ///
+    /// * `clear_state` - The buffers to clear and their values, or `ClearState::LOAD` to
+    ///   keep the previous contents, e.g. for effects accumulating over several frames.
+    pub fn set_clear_state(&self, clear_state: ClearState) {
+        self.clear_state.set(clear_state);
+    }
+
+    /// Returns how `clear` clears the framebuffer.
+    pub fn clear_state(&self) -> ClearState {
+        self.clear_state.get()
+    }
+
+    /// Bind the framebuffer and clear it with its clear state.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glBindFramebuffer` and `ClearState::apply`.
+    pub fn clear(&self) {
+        self.bind();
+        self.clear_state.get().apply();
+    }
+
+    /// Bind the default framebuffer, so subsequent draw calls render to the window.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glBindFramebuffer(GL_FRAMEBUFFER, 0)`.
+    pub fn unbind(&self) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+    }
+
+    /// Attach a renderbuffer to the framebuffer.
+    ///
+    /// The framebuffer is bound afterwards.
+    ///
+    /// # Arguments
+    ///
+    /// * `attachment` - The attachment point, e.g. `gl::DEPTH_STENCIL_ATTACHMENT` or
+    ///   `renderbuffer.default_attachment()`.
+    /// * `renderbuffer` - The renderbuffer to attach.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glFramebufferRenderbuffer`.
+    pub fn attach_renderbuffer(&self, attachment: GLenum, renderbuffer: &Renderbuffer) {
+        self.width.set(renderbuffer.width());
+        self.height.set(renderbuffer.height());
+        self.bind();
+        unsafe {
+            gl::FramebufferRenderbuffer(
+                gl::FRAMEBUFFER,
+                attachment,
+                gl::RENDERBUFFER,
+                renderbuffer.id(),
+            );
+        }
+    }
+
+    /// Attach the base level of a 2D texture to the framebuffer.
+    ///
+    /// The texture must have been allocated, e.g. with `Texture::allocate`. The framebuffer
+    /// is bound afterwards.
+    ///
+    /// # Arguments
+    ///
+    /// * `attachment` - The attachment point, e.g. `gl::COLOR_ATTACHMENT0` or
+    ///   `texture.format().default_attachment()`.
+    /// * `texture` - The texture to attach.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glFramebufferTexture2D`.
+    pub fn attach_texture(&self, attachment: GLenum, texture: &Texture) {
+        self.width.set(texture.width());
+        self.height.set(texture.height());
+        self.bind();
+        unsafe {
+            gl::FramebufferTexture2D(gl::FRAMEBUFFER, attachment, gl::TEXTURE_2D, texture.id(), 0);
+        }
+    }
+
+    /// Detach whatever is attached at the given attachment point.
+    ///
+    /// The framebuffer is bound afterwards.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glFramebufferTexture2D` with texture `0`.
+    pub fn detach(&self, attachment: GLenum) {
+        self.bind();
+        unsafe {
+            gl::FramebufferTexture2D(gl::FRAMEBUFFER, attachment, gl::TEXTURE_2D, 0, 0);
+        }
+    }
+
+    /// Select the color attachments fragment shader outputs are written to, in the order of
+    /// their output locations.
+    ///
+    /// Only `gl::COLOR_ATTACHMENT0` is written by default. Pass an empty slice for depth-only
+    /// framebuffers. The framebuffer is bound afterwards.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glDrawBuffers`.
+    pub fn set_draw_buffers(&self, attachments: &[GLenum]) {
+        self.bind();
+        unsafe {
+            if attachments.is_empty() {
+                gl::DrawBuffer(gl::NONE);
+            } else {
+                gl::DrawBuffers(attachments.len() as GLsizei, attachments.as_ptr());
+            }
+        }
+    }
+
+    /// Check that the framebuffer is complete and can be rendered to.
+    ///
+    /// The framebuffer is bound afterwards.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::OpenGlError` of kind `GlErrorKind::FramebufferIncomplete` if the
+    /// attachments do not form a complete framebuffer.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glCheckFramebufferStatus`.
+    pub fn check_status(&self) -> Result<(), Errors> {
+        self.bind();
+        let status = unsafe { gl::CheckFramebufferStatus(gl::FRAMEBUFFER) };
+        if status != gl::FRAMEBUFFER_COMPLETE {
+            return Err(Errors::OpenGlError(
+                "Framebuffer is incomplete".to_string(),
+                GlErrorKind::FramebufferIncomplete(status),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Copy a region of this framebuffer into another framebuffer.
+    ///
+    /// Used to resolve multisampled targets, build downsampling chains and copy depth between
+    /// passes. The rectangles use the top-left origin of `Rect` and are flipped to OpenGL's
+    /// bottom-left origin using each framebuffer's height. The previous framebuffer binding
+    /// is restored afterwards.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The destination framebuffer.
+    /// * `src_rect` - The region to read from this framebuffer.
+    /// * `dst_rect` - The region to write in `other`. The image is scaled if the sizes differ.
+    /// * `mask` - The buffers to copy, a combination of `gl::COLOR_BUFFER_BIT`,
+    ///   `gl::DEPTH_BUFFER_BIT` and `gl::STENCIL_BUFFER_BIT`.
+    /// * `filter` - The filter used when the image is scaled.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::OpenGlError` of kind `GlErrorKind::InvalidOperation` if depth or
+    /// stencil are copied with `ScaleFilter::Linear`, which OpenGL does not allow.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glBlitFramebuffer`.
+    pub fn blit_to(
+        &self,
+        other: &Framebuffer,
+        src_rect: Rect,
+        dst_rect: Rect,
+        mask: GLbitfield,
+        filter: ScaleFilter,
+    ) -> Result<(), Errors> {
+        self.blit(other.id, other.height(), src_rect, dst_rect, mask, filter)
+    }
+
+    /// Copy the color of a region of this framebuffer onto the whole window.
+    ///
+    /// Used to show the result of off-screen rendering without a full-screen draw. The
+    /// image is stretched if the sizes differ. The previous framebuffer binding is restored
+    /// afterwards.
+    ///
+    /// # Arguments
+    ///
+    /// * `src_rect` - The region to read from this framebuffer, e.g. `self.rect()`.
+    /// * `window_size` - The framebuffer size of the window in pixels, see
+    ///   `Window::framebuffer_size`.
+    /// * `filter` - The filter used when the image is scaled.
+    ///
+    /// # Errors
+    ///
+    /// Never fails for color copies; the `Result` matches `blit_to`.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glBlitFramebuffer`.
+    pub fn blit_to_default(
+        &self,
+        src_rect: Rect,
+        window_size: (u32, u32),
+        filter: ScaleFilter,
+    ) -> Result<(), Errors> {
+        let (width, height) = window_size;
+        self.blit(
+            0,
+            height,
+            src_rect,
+            Rect::new(0, 0, width as i32, height as i32),
+            gl::COLOR_BUFFER_BIT,
+            filter,
+        )
+    }
+
+    /// Copies a region into the framebuffer with the given handle and height.
+    fn blit(
+        &self,
+        dst_id: GLuint,
+        dst_height: u32,
+        src_rect: Rect,
+        dst_rect: Rect,
+        mask: GLbitfield,
+        filter: ScaleFilter,
+    ) -> Result<(), Errors> {
+        if filter == ScaleFilter::Linear
+            && mask & (gl::DEPTH_BUFFER_BIT | gl::STENCIL_BUFFER_BIT) != 0
+        {
+            return Err(Errors::OpenGlError(
+                "Depth and stencil can only be blitted with nearest filtering".to_string(),
+                GlErrorKind::InvalidOperation,
+            ));
+        }
+
+        let src_y = self.height() as i32 - src_rect.y - src_rect.height;
+        let dst_y = dst_height as i32 - dst_rect.y - dst_rect.height;
+        let mut previous_fbo = 0;
+        unsafe {
+            gl::GetIntegerv(gl::FRAMEBUFFER_BINDING, &mut previous_fbo);
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.id);
+            gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, dst_id);
+            gl::BlitFramebuffer(
+                src_rect.x,
+                src_y,
+                src_rect.x + src_rect.width,
+                src_y + src_rect.height,
+                dst_rect.x,
+                dst_y,
+                dst_rect.x + dst_rect.width,
+                dst_y + dst_rect.height,
+                mask,
+                filter.to_gl(),
+            );
+            gl::BindFramebuffer(gl::FRAMEBUFFER, previous_fbo as GLuint);
+        }
+        Ok(())
+    }
+
+    /// Attach a debug label to the framebuffer, shown by tools like RenderDoc and Nsight.
+    ///
+    /// Does nothing if `GL_KHR_debug` is not available.
+    ///
+    /// # Arguments
+    ///
+    /// * `label` - The label to attach, e.g. `"gbuffer"`.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glObjectLabel`.
+    pub fn set_debug_label(&self, label: &str) {
+        set_object_label(gl::FRAMEBUFFER, self.id, label);
+    }
+}
+
+impl Drop for Framebuffer {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteFramebuffers(1, &self.id);
+        }
+    }
+}
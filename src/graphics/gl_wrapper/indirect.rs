@@ -0,0 +1,275 @@
+//! # Indirect Draw Module
+
+use crate::custom_errors::{Errors, GlErrorKind};
+use crate::graphics::gl_wrapper::context::{check_context_thread, ContextBound};
+use crate::graphics::gl_wrapper::{IndexType, PrimitiveType};
+use crate::graphics::stats;
+use gl::types::*;
+use std::mem;
+use std::os::raw::*;
+
+/// A single indexed draw command as read by `glDrawElementsIndirect`.
+///
+/// The layout matches the `DrawElementsIndirectCommand` structure expected by OpenGL,
+/// so a slice of these can be uploaded directly into a `DrawIndirectBuffer` or written
+/// by a compute shader.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DrawElementsIndirectCommand {
+    /// The number of indices to draw.
+    pub count: u32,
+    /// The number of instances to draw.
+    pub instance_count: u32,
+    /// The first index to read from the bound EBO.
+    pub first_index: u32,
+    /// The value added to each index before fetching the vertex.
+    pub base_vertex: i32,
+    /// The first instance ID passed to the shader.
+    pub base_instance: u32,
+}
+
+/// A buffer of `DrawElementsIndirectCommand`s, letting a single call issue many indexed
+/// draws whose parameters live on the GPU.
+///
+/// # OpenGL Functions
+///
+/// The buffer is created with `glGenBuffers`, filled with `glBufferData` and
+/// `glBufferSubData` on `GL_DRAW_INDIRECT_BUFFER`, drawn with `glDrawElementsIndirect` or
+/// `glMultiDrawElementsIndirect` and deleted with `glDeleteBuffers` when dropped.
+pub struct DrawIndirectBuffer {
+    id: GLuint,
+    usage: GLenum,
+    _context: ContextBound,
+}
+
+impl DrawIndirectBuffer {
+    /// Generate a new draw indirect buffer with the given usage.
+    ///
+    /// # Arguments
+    ///
+    /// * `usage` - The usage of the buffer. For example, `gl::STATIC_DRAW` or `gl::DYNAMIC_DRAW`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a `DrawIndirectBuffer` instance if successful, or an error of type
+    /// `Errors::OpenGlError` otherwise.
+    pub fn new(usage: GLenum) -> Result<Self, Errors> {
+        check_context_thread()?;
+        let mut id = 0;
+        unsafe {
+            gl::GenBuffers(1, &mut id);
+        }
+        if id == 0 {
+            return Err(Errors::OpenGlError(
+                "Failed to generate draw indirect buffer".to_string(),
+                GlErrorKind::ObjectCreationFailed,
+            ));
+        }
+        Ok(Self {
+            id,
+            usage,
+            _context: ContextBound::default(),
+        })
+    }
+
+    /// Bind the buffer to the draw indirect binding point.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glBindBuffer(gl::DRAW_INDIRECT_BUFFER, id)`.
+    pub fn bind(&self) {
+        unsafe {
+            gl::BindBuffer(gl::DRAW_INDIRECT_BUFFER, self.id);
+        }
+    }
+
+    /// Unbind any buffer from the draw indirect binding point.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glBindBuffer(gl::DRAW_INDIRECT_BUFFER, 0)`.
+    pub fn unbind(&self) {
+        unsafe {
+            gl::BindBuffer(gl::DRAW_INDIRECT_BUFFER, 0);
+        }
+    }
+
+    /// Bind the buffer as a shader storage buffer so a compute pass can fill the draw commands.
+    ///
+    /// # Arguments
+    ///
+    /// * `binding_point` - The shader storage binding point to bind the buffer to.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glBindBufferBase(gl::SHADER_STORAGE_BUFFER, binding_point, id)`.
+    pub fn bind_as_storage(&self, binding_point: u32) {
+        unsafe {
+            gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, binding_point, self.id);
+        }
+    }
+
+    /// Store the given draw commands in the buffer.
+    ///
+    /// The buffer must be bound before calling this function.
+    ///
+    /// # Arguments
+    ///
+    /// * `commands` - The draw commands to store in the buffer.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glBufferData(gl::DRAW_INDIRECT_BUFFER, size, data, usage)`.
+    pub fn store_commands(&self, commands: &[DrawElementsIndirectCommand]) {
+        unsafe {
+            gl::BufferData(
+                gl::DRAW_INDIRECT_BUFFER,
+                mem::size_of_val(commands) as GLsizeiptr,
+                commands.as_ptr() as *const c_void,
+                self.usage,
+            );
+        }
+    }
+
+    /// Update draw commands in the buffer, starting at the given command index.
+    ///
+    /// The buffer must be bound before calling this function.
+    ///
+    /// # Arguments
+    ///
+    /// * `first` - The index of the first command to overwrite.
+    /// * `commands` - The draw commands to store in the buffer.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glBufferSubData`.
+    pub fn update_commands(&self, first: usize, commands: &[DrawElementsIndirectCommand]) {
+        unsafe {
+            gl::BufferSubData(
+                gl::DRAW_INDIRECT_BUFFER,
+                (first * mem::size_of::<DrawElementsIndirectCommand>()) as isize,
+                mem::size_of_val(commands) as isize,
+                commands.as_ptr() as *const c_void,
+            );
+        }
+    }
+
+    /// Issue a single indexed draw whose parameters are read from the buffer.
+    ///
+    /// The buffer, the VAO and its EBO must be bound before calling this function.
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - The primitive type to draw.
+    /// * `index_type` - The type of the indices in the EBO.
+    /// * `command` - The index of the command to read from the buffer.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glDrawElementsIndirect(mode, type, offset)`.
+    pub fn draw_elements_indirect(
+        &self,
+        mode: PrimitiveType,
+        index_type: IndexType,
+        command: usize,
+    ) {
+        unsafe {
+            gl::DrawElementsIndirect(
+                mode.gl_mode(),
+                index_type.gl_type(),
+                (command * mem::size_of::<DrawElementsIndirectCommand>()) as *const c_void,
+            );
+        }
+        stats::record_indirect_draw();
+    }
+
+    /// Issue several indexed draws whose parameters are read from the buffer.
+    ///
+    /// The buffer, the VAO and its EBO must be bound before calling this function.
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - The primitive type to draw.
+    /// * `index_type` - The type of the indices in the EBO.
+    /// * `first` - The index of the first command to read from the buffer.
+    /// * `draw_count` - The number of commands to execute.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glMultiDrawElementsIndirect(mode, type, offset, drawcount, 0)`.
+    pub fn multi_draw_elements_indirect(
+        &self,
+        mode: PrimitiveType,
+        index_type: IndexType,
+        first: usize,
+        draw_count: usize,
+    ) {
+        unsafe {
+            gl::MultiDrawElementsIndirect(
+                mode.gl_mode(),
+                index_type.gl_type(),
+                (first * mem::size_of::<DrawElementsIndirectCommand>()) as *const c_void,
+                draw_count as GLsizei,
+                0,
+            );
+        }
+        stats::record_indirect_draw();
+    }
+}
+
+impl Drop for DrawIndirectBuffer {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteBuffers(1, &self.id);
+        }
+    }
+}
+
+/// Draw several ranges of the bound EBO with a single call.
+///
+/// The VAO and its EBO must be bound before calling this function.
+///
+/// # Arguments
+///
+/// * `mode` - The primitive type to draw.
+/// * `counts` - The number of indices to draw for each range.
+/// * `index_type` - The type of the indices in the EBO.
+/// * `offsets` - The byte offset into the EBO at which each range starts.
+///
+/// # Errors
+///
+/// Returns an `Errors::OpenGlError` if `counts` and `offsets` have different lengths.
+///
+/// # OpenGL Functions
+///
+/// This function is a wrapper around `glMultiDrawElements(mode, counts, type, indices, drawcount)`.
+pub fn multi_draw_elements(
+    mode: PrimitiveType,
+    counts: &[i32],
+    index_type: IndexType,
+    offsets: &[usize],
+) -> Result<(), Errors> {
+    if counts.len() != offsets.len() {
+        return Err(Errors::OpenGlError(
+            "Counts and offsets must have the same length".to_string(),
+            GlErrorKind::InvalidValue,
+        ));
+    }
+
+    let indices: Vec<*const c_void> = offsets
+        .iter()
+        .map(|&offset| offset as *const c_void)
+        .collect();
+
+    unsafe {
+        gl::MultiDrawElements(
+            mode.gl_mode(),
+            counts.as_ptr(),
+            index_type.gl_type(),
+            indices.as_ptr(),
+            counts.len() as GLsizei,
+        );
+    }
+    stats::record_multi_draw(mode, counts);
+    Ok(())
+}
@@ -0,0 +1,35 @@
+//! # Label Module
+
+use gl::types::*;
+
+/// Checks if debug labels are supported by the current context (`GL_KHR_debug`, core in 4.3).
+pub fn debug_labels_supported() -> bool {
+    gl::ObjectLabel::is_loaded()
+}
+
+/// Attach a human-readable label to an OpenGL object, shown by tools like RenderDoc and Nsight.
+///
+/// Does nothing if `debug_labels_supported` returns `false`.
+///
+/// # Arguments
+///
+/// * `identifier` - The namespace of the object, e.g. `gl::BUFFER` or `gl::TEXTURE`.
+/// * `name` - The OpenGL handle of the object.
+/// * `label` - The label to attach.
+///
+/// # OpenGL Functions
+///
+/// This function is a wrapper around `glObjectLabel`.
+pub fn set_object_label(identifier: GLenum, name: GLuint, label: &str) {
+    if !debug_labels_supported() {
+        return;
+    }
+    unsafe {
+        gl::ObjectLabel(
+            identifier,
+            name,
+            label.len() as GLsizei,
+            label.as_ptr() as *const GLchar,
+        );
+    }
+}
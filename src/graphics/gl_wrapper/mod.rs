@@ -1,6 +1,9 @@
 //! # GL Wrapper Module
 //!
-//! This module provides wrappers for OpenGL objects such as VAO, VBO, EBO, and shader programs.
+//! This module provides wrappers for OpenGL objects such as VAO, VBO, EBO, draw indirect buffers, shader programs, framebuffers, renderbuffers and queries.
+//! These types are tied to the thread owning the OpenGL context and cannot be sent to other threads.
+//! Objects can be given debug labels with `set_debug_label` to identify them in graphics debuggers.
+//! Shader files can share code with `#include` directives, resolved against `add_include_path`.
 //!
 //! ## Usage
 //!
@@ -23,14 +26,30 @@
 //! }
 //! ```
 
+pub mod context;
 pub mod ebo;
+pub mod framebuffer;
+pub mod indirect;
+pub mod label;
+pub mod primitive;
+pub mod query;
+pub mod renderbuffer;
 pub mod shader;
+pub mod shader_include;
 pub mod vao;
 pub mod vbo;
 pub mod vertex_attribute;
 
+pub use context::*;
 pub use ebo::*;
+pub use framebuffer::*;
+pub use indirect::*;
+pub use label::*;
+pub use primitive::*;
+pub use query::*;
+pub use renderbuffer::*;
 pub use shader::*;
+pub use shader_include::*;
 pub use vao::*;
 pub use vbo::*;
 pub use vertex_attribute::*;
@@ -0,0 +1,147 @@
+//! # Primitive Module
+
+use gl::types::*;
+
+/// The primitive topology used to assemble vertices when drawing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum PrimitiveType {
+    /// Every three indices form a separate triangle.
+    #[default]
+    Triangles,
+    /// Every index after the first two forms a triangle with the previous two.
+    TriangleStrip,
+    /// Every index after the first two forms a triangle with the first and the previous index.
+    TriangleFan,
+    /// Every two indices form a separate line.
+    Lines,
+    /// Every index after the first forms a line with the previous one.
+    LineStrip,
+    /// Like `LineStrip`, but the last index is also connected to the first one.
+    LineLoop,
+    /// Every index is drawn as a single point.
+    Points,
+    /// Every `vertices` indices form a patch for the tessellation stages of the shader
+    /// program.
+    Patches { vertices: u32 },
+}
+
+impl PrimitiveType {
+    /// Returns the OpenGL enum for the primitive type, as expected by `glDrawElements`.
+    pub fn gl_mode(&self) -> GLenum {
+        match self {
+            PrimitiveType::Triangles => gl::TRIANGLES,
+            PrimitiveType::TriangleStrip => gl::TRIANGLE_STRIP,
+            PrimitiveType::TriangleFan => gl::TRIANGLE_FAN,
+            PrimitiveType::Lines => gl::LINES,
+            PrimitiveType::LineStrip => gl::LINE_STRIP,
+            PrimitiveType::LineLoop => gl::LINE_LOOP,
+            PrimitiveType::Points => gl::POINTS,
+            PrimitiveType::Patches { .. } => gl::PATCHES,
+        }
+    }
+
+    /// Returns `true` if the primitive type draws lines.
+    pub fn is_line(&self) -> bool {
+        matches!(
+            self,
+            PrimitiveType::Lines | PrimitiveType::LineStrip | PrimitiveType::LineLoop
+        )
+    }
+
+    /// Returns `true` if the primitive type draws points.
+    pub fn is_point(&self) -> bool {
+        matches!(self, PrimitiveType::Points)
+    }
+
+    /// Sets the line width, point size or patch size needed before drawing with the
+    /// primitive type.
+    ///
+    /// # Arguments
+    ///
+    /// * `line_width` - The width of lines in pixels, used by line primitives.
+    /// * `point_size` - The diameter of points in pixels, used by `Points`.
+    pub(crate) fn apply(&self, line_width: f32, point_size: f32) {
+        if self.is_line() {
+            set_line_width(line_width);
+        } else if self.is_point() {
+            set_point_size(point_size);
+        } else if let PrimitiveType::Patches { vertices } = self {
+            set_patch_vertices(*vertices);
+        }
+    }
+
+    /// Returns the number of triangles assembled from the given number of indices, or `0`
+    /// for lines and points.
+    pub fn triangle_count(&self, index_count: usize) -> usize {
+        match self {
+            PrimitiveType::Triangles => index_count / 3,
+            PrimitiveType::TriangleStrip | PrimitiveType::TriangleFan => {
+                index_count.saturating_sub(2)
+            }
+            _ => 0,
+        }
+    }
+}
+
+/// Set the width of rasterized lines.
+///
+/// # OpenGL Functions
+///
+/// This function is a wrapper around `glLineWidth(width)`.
+///
+/// # Arguments
+///
+/// * `width` - The width of lines in pixels.
+pub fn set_line_width(width: f32) {
+    unsafe {
+        gl::LineWidth(width);
+    }
+}
+
+/// Set the diameter of rasterized points.
+///
+/// # OpenGL Functions
+///
+/// This function is a wrapper around `glPointSize(size)`.
+///
+/// # Arguments
+///
+/// * `size` - The diameter of points in pixels.
+pub fn set_point_size(size: f32) {
+    unsafe {
+        gl::PointSize(size);
+    }
+}
+
+/// Set the number of vertices forming a patch for tessellation.
+///
+/// # OpenGL Functions
+///
+/// This function is a wrapper around `glPatchParameteri(GL_PATCH_VERTICES, vertices)`.
+///
+/// # Arguments
+///
+/// * `vertices` - The number of vertices per patch, e.g. `3` for triangles or `4` for quads.
+pub fn set_patch_vertices(vertices: u32) {
+    unsafe {
+        gl::PatchParameteri(gl::PATCH_VERTICES, vertices as GLint);
+    }
+}
+
+/// Set the tessellation levels used by programs without a tessellation control shader.
+///
+/// # OpenGL Functions
+///
+/// This function is a wrapper around `glPatchParameterfv` with
+/// `GL_PATCH_DEFAULT_OUTER_LEVEL` and `GL_PATCH_DEFAULT_INNER_LEVEL`.
+///
+/// # Arguments
+///
+/// * `outer` - The outer tessellation levels of the patch edges.
+/// * `inner` - The inner tessellation levels of the patch interior.
+pub fn set_default_tessellation_levels(outer: [f32; 4], inner: [f32; 2]) {
+    unsafe {
+        gl::PatchParameterfv(gl::PATCH_DEFAULT_OUTER_LEVEL, outer.as_ptr());
+        gl::PatchParameterfv(gl::PATCH_DEFAULT_INNER_LEVEL, inner.as_ptr());
+    }
+}
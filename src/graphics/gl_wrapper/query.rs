@@ -0,0 +1,163 @@
+//! # Query Module
+
+use crate::custom_errors::{Errors, GlErrorKind};
+use crate::graphics::gl_wrapper::context::{check_context_thread, ContextBound};
+use crate::graphics::gl_wrapper::label::set_object_label;
+use gl::types::*;
+
+/// What a `Query` measures between `begin` and `end`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum QueryTarget {
+    /// The number of samples that passed the depth and stencil tests.
+    SamplesPassed,
+    /// Whether any sample passed the depth and stencil tests. Usually cheaper than counting.
+    AnySamplesPassed,
+    /// The number of primitives that left the vertex processing stages.
+    PrimitivesGenerated,
+    /// The GPU time in nanoseconds spent on the commands between `begin` and `end`.
+    TimeElapsed,
+}
+
+impl QueryTarget {
+    /// Returns the matching OpenGL enum, e.g. `gl::ANY_SAMPLES_PASSED`.
+    pub fn gl_target(&self) -> GLenum {
+        match self {
+            QueryTarget::SamplesPassed => gl::SAMPLES_PASSED,
+            QueryTarget::AnySamplesPassed => gl::ANY_SAMPLES_PASSED,
+            QueryTarget::PrimitivesGenerated => gl::PRIMITIVES_GENERATED,
+            QueryTarget::TimeElapsed => gl::TIME_ELAPSED,
+        }
+    }
+}
+
+/// An OpenGL query object, e.g. an occlusion query.
+///
+/// Query results are computed by the GPU some time after `end` is called. Reading them with
+/// `result` waits for the GPU, so renderers usually poll with `try_result` and use the
+/// answer a frame later.
+pub struct Query {
+    id: GLuint,
+    target: QueryTarget,
+    _context: ContextBound,
+}
+
+impl Query {
+    /// Generate a new query object.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - What the query measures.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::OpenGlError` if the query cannot be generated or if called from a
+    /// thread other than the context thread.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glGenQueries`.
+    pub fn new(target: QueryTarget) -> Result<Self, Errors> {
+        check_context_thread()?;
+        let mut id = 0;
+        unsafe {
+            gl::GenQueries(1, &mut id);
+        }
+        if id == 0 {
+            return Err(Errors::OpenGlError(
+                "Failed to generate query".to_string(),
+                GlErrorKind::ObjectCreationFailed,
+            ));
+        }
+        Ok(Self {
+            id,
+            target,
+            _context: ContextBound::default(),
+        })
+    }
+
+    /// Returns the OpenGL handle of the query.
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
+    /// Returns what the query measures.
+    pub fn target(&self) -> QueryTarget {
+        self.target
+    }
+
+    /// Start measuring. Only one query per target can be active at a time.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glBeginQuery`.
+    pub fn begin(&self) {
+        unsafe {
+            gl::BeginQuery(self.target.gl_target(), self.id);
+        }
+    }
+
+    /// Stop measuring. The result becomes available once the GPU finished the commands.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glEndQuery`.
+    pub fn end(&self) {
+        unsafe {
+            gl::EndQuery(self.target.gl_target());
+        }
+    }
+
+    /// Checks if the result of the last measurement can be read without waiting.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glGetQueryObjectuiv(id, GL_QUERY_RESULT_AVAILABLE)`.
+    pub fn is_result_available(&self) -> bool {
+        let mut available = 0;
+        unsafe {
+            gl::GetQueryObjectuiv(self.id, gl::QUERY_RESULT_AVAILABLE, &mut available);
+        }
+        available != 0
+    }
+
+    /// Reads the result of the last measurement, waiting for the GPU if needed.
+    ///
+    /// For `QueryTarget::AnySamplesPassed` the result is `0` or `1`.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glGetQueryObjectui64v(id, GL_QUERY_RESULT)`.
+    pub fn result(&self) -> u64 {
+        let mut result = 0;
+        unsafe {
+            gl::GetQueryObjectui64v(self.id, gl::QUERY_RESULT, &mut result);
+        }
+        result
+    }
+
+    /// Reads the result of the last measurement if it is available.
+    ///
+    /// # Returns
+    ///
+    /// The result, or `None` if the GPU has not finished the measurement yet.
+    pub fn try_result(&self) -> Option<u64> {
+        self.is_result_available().then(|| self.result())
+    }
+
+    /// Attach a label to the query, shown by graphics debuggers.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glObjectLabel`.
+    pub fn set_debug_label(&self, label: &str) {
+        set_object_label(gl::QUERY, self.id, label);
+    }
+}
+
+impl Drop for Query {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteQueries(1, &self.id);
+        }
+    }
+}
@@ -0,0 +1,185 @@
+//! # Renderbuffer Module
+
+use crate::custom_errors::{Errors, GlErrorKind};
+use crate::graphics::gl_wrapper::context::{check_context_thread, ContextBound};
+use crate::graphics::gl_wrapper::label::set_object_label;
+use crate::graphics::stats::{track_allocation, MemoryCategory};
+use crate::graphics::texture::TextureFormat;
+use gl::types::*;
+
+/// Framebuffer storage that can be rendered to but not sampled.
+///
+/// Renderbuffers are cheaper than textures for depth and stencil targets that are only used
+/// during rendering, and are the only way to store multisampled images besides multisample
+/// textures.
+pub struct Renderbuffer {
+    id: GLuint,
+    format: TextureFormat,
+    width: u32,
+    height: u32,
+    samples: u32,
+    _context: ContextBound,
+}
+
+impl Renderbuffer {
+    /// Generate a new renderbuffer and allocate its storage.
+    ///
+    /// # Arguments
+    ///
+    /// * `format` - The storage format, e.g. `TextureFormat::Depth24Stencil8`.
+    /// * `width` - The width of the renderbuffer in pixels.
+    /// * `height` - The height of the renderbuffer in pixels.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::OpenGlError` if the renderbuffer cannot be generated or if called
+    /// from a thread other than the context thread.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glGenRenderbuffers` and `glRenderbufferStorage`.
+    pub fn new(format: TextureFormat, width: u32, height: u32) -> Result<Self, Errors> {
+        Self::new_multisample(format, width, height, 0)
+    }
+
+    /// Generate a new multisampled renderbuffer and allocate its storage.
+    ///
+    /// Multisampled renderbuffers have to be resolved with `Framebuffer::blit_to` before
+    /// their contents can be displayed or sampled.
+    ///
+    /// # Arguments
+    ///
+    /// * `format` - The storage format, e.g. `TextureFormat::Rgba8`.
+    /// * `width` - The width of the renderbuffer in pixels.
+    /// * `height` - The height of the renderbuffer in pixels.
+    /// * `samples` - The number of samples per pixel, or `0` for no multisampling.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::OpenGlError` if the renderbuffer cannot be generated or if called
+    /// from a thread other than the context thread.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glGenRenderbuffers` and
+    /// `glRenderbufferStorageMultisample`.
+    pub fn new_multisample(
+        format: TextureFormat,
+        width: u32,
+        height: u32,
+        samples: u32,
+    ) -> Result<Self, Errors> {
+        check_context_thread()?;
+        let mut id = 0;
+        unsafe {
+            gl::GenRenderbuffers(1, &mut id);
+        }
+        if id == 0 {
+            return Err(Errors::OpenGlError(
+                "Failed to generate renderbuffer".to_string(),
+                GlErrorKind::ObjectCreationFailed,
+            ));
+        }
+        let mut renderbuffer = Self {
+            id,
+            format,
+            width: 0,
+            height: 0,
+            samples,
+            _context: ContextBound::default(),
+        };
+        renderbuffer.resize(width, height);
+        Ok(renderbuffer)
+    }
+
+    /// Reallocate the storage with a new size, e.g. after the window was resized.
+    ///
+    /// The contents become undefined. Framebuffers the renderbuffer is attached to keep
+    /// referencing it.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glRenderbufferStorageMultisample`.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        let old_size = self.memory_size();
+        self.width = width;
+        self.height = height;
+        track_allocation(MemoryCategory::Renderbuffer, old_size, self.memory_size());
+        unsafe {
+            gl::BindRenderbuffer(gl::RENDERBUFFER, self.id);
+            gl::RenderbufferStorageMultisample(
+                gl::RENDERBUFFER,
+                self.samples as GLsizei,
+                self.format.internal_format(),
+                width as GLsizei,
+                height as GLsizei,
+            );
+            gl::BindRenderbuffer(gl::RENDERBUFFER, 0);
+        }
+    }
+
+    /// Returns the OpenGL renderbuffer handle.
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
+    /// Returns the storage format.
+    pub fn format(&self) -> TextureFormat {
+        self.format
+    }
+
+    /// Returns the width of the renderbuffer in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns the height of the renderbuffer in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns the number of samples per pixel, or `0` if the renderbuffer is not multisampled.
+    pub fn samples(&self) -> u32 {
+        self.samples
+    }
+
+    /// Returns the estimated GPU memory used by the renderbuffer in bytes, including samples.
+    pub fn memory_size(&self) -> usize {
+        self.width as usize
+            * self.height as usize
+            * self.format.bytes_per_pixel()
+            * self.samples.max(1) as usize
+    }
+
+    /// Returns the framebuffer attachment point matching the format, e.g.
+    /// `gl::DEPTH_STENCIL_ATTACHMENT` for `TextureFormat::Depth24Stencil8`.
+    ///
+    /// Color formats map to `gl::COLOR_ATTACHMENT0`.
+    pub fn default_attachment(&self) -> GLenum {
+        self.format.default_attachment()
+    }
+
+    /// Attach a debug label to the renderbuffer, shown by tools like RenderDoc and Nsight.
+    ///
+    /// Does nothing if `GL_KHR_debug` is not available.
+    ///
+    /// # Arguments
+    ///
+    /// * `label` - The label to attach, e.g. `"shadow_pass_depth"`.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glObjectLabel`.
+    pub fn set_debug_label(&self, label: &str) {
+        set_object_label(gl::RENDERBUFFER, self.id, label);
+    }
+}
+
+impl Drop for Renderbuffer {
+    fn drop(&mut self) {
+        track_allocation(MemoryCategory::Renderbuffer, self.memory_size(), 0);
+        unsafe {
+            gl::DeleteRenderbuffers(1, &self.id);
+        }
+    }
+}
@@ -1,18 +1,156 @@
 //! # Shader Module
 
-use crate::custom_errors::Errors;
+use crate::custom_errors::{Errors, GlErrorKind};
+use crate::graphics::gl_wrapper::context::{check_context_thread, ContextBound};
+use crate::graphics::gl_wrapper::label::set_object_label;
+use crate::graphics::gl_wrapper::shader_include::ShaderSource;
+use crate::graphics::stats;
 use cgmath::*;
 use gl::types::*;
-use std::collections::HashMap;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
 use std::ffi::CString;
-use std::fs::File;
-use std::io::Read;
 use std::mem;
 use std::os::raw::*;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+/// What a `ShaderProgram` does when a uniform is not found, e.g. because the shader compiler
+/// optimized it away.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum MissingUniformPolicy {
+    /// Return an `Errors::OpenGlError` of kind `GlErrorKind::UniformNotFound` from the setter.
+    #[default]
+    Error,
+    /// Log a warning the first time each missing uniform is set, then ignore it.
+    WarnOnce,
+    /// Silently ignore the missing uniform.
+    Ignore,
+}
+
+/// A value that can be uploaded to a uniform variable.
+pub trait UniformValue {
+    /// Upload the value to the uniform at the given location of the bound shader program.
+    fn set_uniform(&self, location: GLint);
+}
+
+impl UniformValue for f32 {
+    fn set_uniform(&self, location: GLint) {
+        unsafe {
+            gl::Uniform1f(location, *self);
+        }
+    }
+}
+
+impl UniformValue for i32 {
+    fn set_uniform(&self, location: GLint) {
+        unsafe {
+            gl::Uniform1i(location, *self);
+        }
+    }
+}
+
+impl UniformValue for u32 {
+    fn set_uniform(&self, location: GLint) {
+        unsafe {
+            gl::Uniform1ui(location, *self);
+        }
+    }
+}
+
+impl UniformValue for bool {
+    fn set_uniform(&self, location: GLint) {
+        unsafe {
+            gl::Uniform1i(location, *self as GLint);
+        }
+    }
+}
+
+impl UniformValue for Vector3<f32> {
+    fn set_uniform(&self, location: GLint) {
+        unsafe {
+            gl::Uniform3f(location, self.x, self.y, self.z);
+        }
+    }
+}
+
+impl UniformValue for Vector2<f32> {
+    fn set_uniform(&self, location: GLint) {
+        unsafe {
+            gl::Uniform2f(location, self.x, self.y);
+        }
+    }
+}
+
+impl UniformValue for Vector4<f32> {
+    fn set_uniform(&self, location: GLint) {
+        unsafe {
+            gl::Uniform4f(location, self.x, self.y, self.z, self.w);
+        }
+    }
+}
+
+impl UniformValue for Point3<f32> {
+    fn set_uniform(&self, location: GLint) {
+        unsafe {
+            gl::Uniform3f(location, self.x, self.y, self.z);
+        }
+    }
+}
+
+impl UniformValue for Matrix3<f32> {
+    fn set_uniform(&self, location: GLint) {
+        unsafe {
+            gl::UniformMatrix3fv(location, 1, gl::FALSE, self.as_ptr());
+        }
+    }
+}
+
+impl UniformValue for Matrix4<f32> {
+    fn set_uniform(&self, location: GLint) {
+        unsafe {
+            gl::UniformMatrix4fv(location, 1, gl::FALSE, self.as_ptr());
+        }
+    }
+}
+
+/// The source files of a shader program watched for changes by
+/// `ShaderProgram::enable_hot_reload`.
+struct HotReload {
+    files: Vec<PathBuf>,
+    modified: Vec<Option<SystemTime>>,
+}
+
+impl HotReload {
+    /// Starts watching the given files from their current modification times.
+    fn new(files: Vec<PathBuf>) -> Self {
+        Self {
+            modified: Self::modification_times(&files),
+            files,
+        }
+    }
+
+    /// Returns the modification times of the files, or `None` for files that cannot be
+    /// read, e.g. while an editor is saving them.
+    fn modification_times(files: &[PathBuf]) -> Vec<Option<SystemTime>> {
+        files
+            .iter()
+            .map(|path| std::fs::metadata(path).and_then(|m| m.modified()).ok())
+            .collect()
+    }
+}
 
 pub struct ShaderProgram {
-    program_handle: u32,
-    uniform_ids: HashMap<String, GLint>,
+    program_handle: Cell<u32>,
+    uniform_ids: RefCell<HashMap<String, GLint>>,
+    uniform_blocks: RefCell<Vec<(String, u32)>>,
+    missing_uniform_policy: Cell<MissingUniformPolicy>,
+    warned_uniforms: RefCell<HashSet<String>>,
+    has_tessellation: bool,
+    stages: Vec<(String, GLenum)>,
+    source_files: RefCell<Vec<PathBuf>>,
+    hot_reload: RefCell<Option<HotReload>>,
+    _context: ContextBound,
 }
 
 #[allow(temporary_cstring_as_ptr)]
@@ -33,16 +171,192 @@ impl ShaderProgram {
     /// A `Result` containing a `ShaderProgram` instance if successful, or an error of type
     /// `Errors::ShaderCompilationError` or `Errors::ShaderLinkError` otherwise.
     pub fn new(vertex_path: &str, fragment_path: &str) -> Result<Self, Errors> {
-        let vertex_shader = Self::compile_shader(vertex_path, gl::VERTEX_SHADER)?;
-        let fragment_shader = Self::compile_shader(fragment_path, gl::FRAGMENT_SHADER)?;
+        Self::from_files(&[
+            (vertex_path, gl::VERTEX_SHADER),
+            (fragment_path, gl::FRAGMENT_SHADER),
+        ])
+    }
 
+    /// Compile a vertex, tessellation and fragment shader and link them into a shader program.
+    ///
+    /// Tessellation needs OpenGL 4.0 or `GL_ARB_tessellation_shader`. Meshes drawn with the
+    /// program must use `PrimitiveType::Patches`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the shaders cannot be compiled or linked.
+    ///
+    /// # Arguments
+    ///
+    /// * `vertex_path` - The path to the vertex shader source file.
+    /// * `control_path` - The path to the tessellation control shader source file, or `None`
+    ///   to use the default tessellation levels set with `set_default_tessellation_levels`.
+    /// * `evaluation_path` - The path to the tessellation evaluation shader source file.
+    /// * `fragment_path` - The path to the fragment shader source file.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a `ShaderProgram` instance if successful, or an error of type
+    /// `Errors::ShaderCompilationError` or `Errors::ShaderLinkError` otherwise.
+    pub fn with_tessellation(
+        vertex_path: &str,
+        control_path: Option<&str>,
+        evaluation_path: &str,
+        fragment_path: &str,
+    ) -> Result<Self, Errors> {
+        let mut stages = vec![(vertex_path, gl::VERTEX_SHADER)];
+        if let Some(control_path) = control_path {
+            stages.push((control_path, gl::TESS_CONTROL_SHADER));
+        }
+        stages.push((evaluation_path, gl::TESS_EVALUATION_SHADER));
+        stages.push((fragment_path, gl::FRAGMENT_SHADER));
+        Self::from_files(&stages)
+    }
+
+    /// Compile the given shader files and link them into a program that remembers its
+    /// source files for hot reloading.
+    fn from_files(stages: &[(&str, GLenum)]) -> Result<Self, Errors> {
+        check_context_thread()?;
+        let stages: Vec<(String, GLenum)> = stages
+            .iter()
+            .map(|&(path, shader_type)| (path.to_string(), shader_type))
+            .collect();
+        let (shaders, source_files) = Self::compile_files(&stages)?;
+        let mut program = Self::link(&shaders)?;
+        program.stages = stages;
+        program.source_files = RefCell::new(source_files);
+        Ok(program)
+    }
+
+    /// Compile every shader file, deleting the already compiled shaders if one fails.
+    ///
+    /// Returns the shaders and all files they were built from, including their includes.
+    fn compile_files(stages: &[(String, GLenum)]) -> Result<(Vec<GLuint>, Vec<PathBuf>), Errors> {
+        let mut shaders = Vec::with_capacity(stages.len());
+        let mut source_files = Vec::new();
+        for (path, shader_type) in stages {
+            match Self::compile_shader(path, *shader_type) {
+                Ok((shader, files)) => {
+                    shaders.push(shader);
+                    for file in files {
+                        if !source_files.contains(&file) {
+                            source_files.push(file);
+                        }
+                    }
+                }
+                Err(error) => {
+                    Self::delete_shaders(&shaders);
+                    return Err(error);
+                }
+            }
+        }
+        Ok((shaders, source_files))
+    }
+
+    /// Compile every shader source, deleting the already compiled shaders if one fails.
+    fn compile_sources(stages: &[(&str, GLenum)]) -> Result<Vec<GLuint>, Errors> {
+        let mut shaders = Vec::with_capacity(stages.len());
+        for &(source, shader_type) in stages {
+            match Self::compile_source(source, shader_type) {
+                Ok(shader) => shaders.push(shader),
+                Err(error) => {
+                    Self::delete_shaders(&shaders);
+                    return Err(error);
+                }
+            }
+        }
+        Ok(shaders)
+    }
+
+    /// Delete compiled shaders that will not be linked.
+    fn delete_shaders(shaders: &[GLuint]) {
+        for &shader in shaders {
+            unsafe {
+                gl::DeleteShader(shader);
+            }
+        }
+    }
+
+    /// Compile two shaders from source strings and link them into a shader program.
+    ///
+    /// # Arguments
+    ///
+    /// * `vertex_source` - The GLSL source of the vertex shader.
+    /// * `fragment_source` - The GLSL source of the fragment shader.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a `ShaderProgram` instance if successful, or an error of type
+    /// `Errors::ShaderCompilationError` or `Errors::ShaderLinkError` otherwise.
+    pub fn from_source(vertex_source: &str, fragment_source: &str) -> Result<Self, Errors> {
+        check_context_thread()?;
+        let shaders = Self::compile_sources(&[
+            (vertex_source, gl::VERTEX_SHADER),
+            (fragment_source, gl::FRAGMENT_SHADER),
+        ])?;
+        Self::link(&shaders)
+    }
+
+    /// Compile a vertex, tessellation and fragment shader from source strings and link them
+    /// into a shader program.
+    ///
+    /// # Arguments
+    ///
+    /// * `vertex_source` - The GLSL source of the vertex shader.
+    /// * `control_source` - The GLSL source of the tessellation control shader, or `None` to
+    ///   use the default tessellation levels set with `set_default_tessellation_levels`.
+    /// * `evaluation_source` - The GLSL source of the tessellation evaluation shader.
+    /// * `fragment_source` - The GLSL source of the fragment shader.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a `ShaderProgram` instance if successful, or an error of type
+    /// `Errors::ShaderCompilationError` or `Errors::ShaderLinkError` otherwise.
+    pub fn from_source_with_tessellation(
+        vertex_source: &str,
+        control_source: Option<&str>,
+        evaluation_source: &str,
+        fragment_source: &str,
+    ) -> Result<Self, Errors> {
+        check_context_thread()?;
+        let mut stages = vec![(vertex_source, gl::VERTEX_SHADER)];
+        if let Some(control_source) = control_source {
+            stages.push((control_source, gl::TESS_CONTROL_SHADER));
+        }
+        stages.push((evaluation_source, gl::TESS_EVALUATION_SHADER));
+        stages.push((fragment_source, gl::FRAGMENT_SHADER));
+        let shaders = Self::compile_sources(&stages)?;
+        Self::link(&shaders)
+    }
+
+    /// Link compiled shaders into a program and delete the shaders.
+    fn link(shaders: &[GLuint]) -> Result<Self, Errors> {
+        let program_handle = Self::link_handle(shaders)?;
+        Ok(Self {
+            program_handle: Cell::new(program_handle),
+            uniform_ids: RefCell::new(HashMap::new()),
+            uniform_blocks: RefCell::new(Vec::new()),
+            missing_uniform_policy: Cell::new(MissingUniformPolicy::Error),
+            warned_uniforms: RefCell::new(HashSet::new()),
+            has_tessellation: shaders.len() > 2,
+            stages: Vec::new(),
+            source_files: RefCell::new(Vec::new()),
+            hot_reload: RefCell::new(None),
+            _context: ContextBound::default(),
+        })
+    }
+
+    /// Link compiled shaders into a program handle and delete the shaders.
+    fn link_handle(shaders: &[GLuint]) -> Result<GLuint, Errors> {
         let program_handle = unsafe { gl::CreateProgram() };
         unsafe {
-            gl::AttachShader(program_handle, vertex_shader);
-            gl::AttachShader(program_handle, fragment_shader);
+            for &shader in shaders {
+                gl::AttachShader(program_handle, shader);
+            }
             gl::LinkProgram(program_handle);
-            gl::DeleteShader(vertex_shader);
-            gl::DeleteShader(fragment_shader);
+            for &shader in shaders {
+                gl::DeleteShader(shader);
+            }
         }
 
         let mut success = 0;
@@ -62,24 +376,22 @@ impl ShaderProgram {
                     std::ptr::null_mut(),
                     log.as_mut_ptr() as *mut i8,
                 );
+                gl::DeleteProgram(program_handle);
             }
             return Err(Errors::ShaderLinkError(
                 String::from_utf8_lossy(&log).to_string(),
             ));
         }
 
-        Ok(Self {
-            program_handle,
-            uniform_ids: HashMap::new(),
-        })
+        Ok(program_handle)
     }
 
-    /// Compile a shader from a file.
+    /// Compile a shader from a file, resolving its `#include` directives.
     ///
     /// # Errors
     ///
-    /// This function will return an error if the shader source file cannot be read or if the shader
-    /// cannot be compiled.
+    /// This function will return an error if the shader source file cannot be read, an include
+    /// cannot be resolved or if the shader cannot be compiled.
     ///
     /// # Arguments
     ///
@@ -88,19 +400,34 @@ impl ShaderProgram {
     ///
     /// # Returns
     ///
+    /// A `Result` containing the OpenGL shader handle and the files it was built from if
+    /// successful, or an error of type `Errors::ShaderCompilationError` otherwise.
+    fn compile_shader(path: &str, shader_type: GLenum) -> Result<(GLuint, Vec<PathBuf>), Errors> {
+        let shader_source = ShaderSource::load(path)?;
+        let shader = Self::compile_source(&shader_source.source, shader_type).map_err(|error| {
+            match error {
+                // Name the files behind the source string numbers in the compiler log
+                Errors::ShaderCompilationError(message, log) => Errors::ShaderCompilationError(
+                    format!("{} in {}", message, path),
+                    format!("{}\nSource strings:\n{}", log, shader_source.file_legend()),
+                ),
+                error => error,
+            }
+        })?;
+        Ok((shader, shader_source.files))
+    }
+
+    /// Compile a shader from a source string.
+    ///
+    /// # Returns
+    ///
     /// A `Result` containing the OpenGL shader handle if successful, or an error of type
     /// `Errors::ShaderCompilationError` otherwise.
-    fn compile_shader(path: &str, shader_type: GLenum) -> Result<GLuint, Errors> {
-        let mut shader_file = File::open(path).map_err(|e| Errors::FileLoadError(e.to_string()))?;
-        let mut shader_source = String::new();
-        shader_file
-            .read_to_string(&mut shader_source)
-            .map_err(|e| Errors::FileLoadError(e.to_string()))?;
-
-        let shader = unsafe { gl::CreateShader(shader_type) };
+    fn compile_source(shader_source: &str, shader_type: GLenum) -> Result<GLuint, Errors> {
         let c_str = CString::new(shader_source.as_bytes()).map_err(|e| {
             Errors::ShaderCompilationError("Failed to create CString".to_string(), e.to_string())
         })?;
+        let shader = unsafe { gl::CreateShader(shader_type) };
 
         unsafe {
             gl::ShaderSource(shader, 1, &c_str.as_ptr(), std::ptr::null());
@@ -124,6 +451,7 @@ impl ShaderProgram {
                     std::ptr::null_mut(),
                     log.as_mut_ptr() as *mut i8,
                 );
+                gl::DeleteShader(shader);
             }
             return Err(Errors::ShaderCompilationError(
                 "Shader compilation failed".to_string(),
@@ -134,6 +462,136 @@ impl ShaderProgram {
         Ok(shader)
     }
 
+    /// Returns `true` if the program has tessellation stages, so meshes drawn with it must
+    /// use `PrimitiveType::Patches`.
+    pub fn has_tessellation(&self) -> bool {
+        self.has_tessellation
+    }
+
+    /// Returns the source files the program was compiled from, or an empty list if it was
+    /// compiled from source strings.
+    pub fn source_paths(&self) -> Vec<&str> {
+        self.stages.iter().map(|(path, _)| path.as_str()).collect()
+    }
+
+    /// Start watching the source files of the program and the files they include for changes.
+    ///
+    /// `reload_if_changed` then recompiles the program whenever one of the files was saved.
+    ///
+    /// # Returns
+    ///
+    /// `false` if the program was compiled from source strings and has no files to watch.
+    pub fn enable_hot_reload(&self) -> bool {
+        if self.stages.is_empty() {
+            return false;
+        }
+        *self.hot_reload.borrow_mut() = Some(HotReload::new(self.source_files.borrow().clone()));
+        true
+    }
+
+    /// Stop watching the source files of the program.
+    pub fn disable_hot_reload(&self) {
+        *self.hot_reload.borrow_mut() = None;
+    }
+
+    /// Returns `true` if the source files of the program are watched for changes.
+    pub fn is_hot_reload_enabled(&self) -> bool {
+        self.hot_reload.borrow().is_some()
+    }
+
+    /// Recompile the program if hot reloading is enabled and one of its source files changed
+    /// since it was last compiled.
+    ///
+    /// Call this at a safe point, e.g. at the start of a frame before anything is drawn.
+    /// A file that fails to compile is not retried until it changes again.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing `true` if the program was reloaded, or the error of `reload`.
+    /// The previous program stays in use if reloading fails.
+    pub fn reload_if_changed(&self) -> Result<bool, Errors> {
+        {
+            let mut hot_reload = self.hot_reload.borrow_mut();
+            let Some(hot_reload) = hot_reload.as_mut() else {
+                return Ok(false);
+            };
+            let modified = HotReload::modification_times(&hot_reload.files);
+            // Wait until every file can be read again, editors may replace files on save
+            if modified.iter().any(Option::is_none) || modified == hot_reload.modified {
+                return Ok(false);
+            }
+            hot_reload.modified = modified;
+        }
+        self.reload()?;
+        Ok(true)
+    }
+
+    /// Recompile the program from its source files and swap it in place.
+    ///
+    /// Objects sharing the program through an `Rc` use the new program from their next draw.
+    /// Cached uniform locations are cleared and uniform block bindings made with
+    /// `create_uniform_block` are restored, but uniform values have to be set again. Bind
+    /// the program again before drawing with it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::FileLoadError` if the program was compiled from source strings or
+    /// a file cannot be read, or an `Errors::ShaderCompilationError` or
+    /// `Errors::ShaderLinkError` if the new sources are invalid. The previous program stays
+    /// in use in that case.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glCompileShader`, `glLinkProgram` and
+    /// `glDeleteProgram`.
+    pub fn reload(&self) -> Result<(), Errors> {
+        check_context_thread()?;
+        if self.stages.is_empty() {
+            return Err(Errors::FileLoadError(
+                "Shader program was not compiled from files".to_string(),
+            ));
+        }
+        let (shaders, source_files) = Self::compile_files(&self.stages)?;
+        let program_handle = Self::link_handle(&shaders)?;
+        let previous = self.program_handle.replace(program_handle);
+        unsafe {
+            gl::DeleteProgram(previous);
+        }
+        // Includes may have been added or removed
+        if let Some(hot_reload) = self.hot_reload.borrow_mut().as_mut() {
+            if hot_reload.files != source_files {
+                *hot_reload = HotReload::new(source_files.clone());
+            }
+        }
+        *self.source_files.borrow_mut() = source_files;
+        self.uniform_ids.borrow_mut().clear();
+        self.warned_uniforms.borrow_mut().clear();
+
+        let uniform_blocks = self.uniform_blocks.take();
+        for (block_name, binding_point) in &uniform_blocks {
+            if let Err(error) = self.create_uniform_block(block_name, *binding_point) {
+                log::warn!("{} after reloading shader program", error);
+            }
+        }
+        log::info!("Reloaded shader program {}", self.source_paths().join(", "));
+        Ok(())
+    }
+
+    /// Attach a debug label to the shader program, shown by tools like RenderDoc and Nsight.
+    ///
+    /// Does nothing if `GL_KHR_debug` is not available.
+    ///
+    /// # Arguments
+    ///
+    /// * `label` - The label to attach, e.g. `"lit_shader"`.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glObjectLabel`.
+    pub fn set_debug_label(&self, label: &str) {
+        set_object_label(gl::PROGRAM, self.program_handle.get(), label);
+    }
+
     /// Bind the shader program to the current OpenGL context.
     ///
     /// # OpenGL Functions
@@ -142,8 +600,9 @@ impl ShaderProgram {
     /// It binds the shader program to the current OpenGL context.
     pub fn bind(&self) {
         unsafe {
-            gl::UseProgram(self.program_handle);
+            gl::UseProgram(self.program_handle.get());
         }
+        stats::record_program_bind(self.program_handle.get());
     }
 
     /// Unbind any shader program from the current OpenGL context, making no shader program active.
@@ -156,6 +615,23 @@ impl ShaderProgram {
         unsafe {
             gl::UseProgram(0);
         }
+        stats::record_program_bind(0);
+    }
+
+    /// Set what the uniform setters do when a uniform is not found.
+    ///
+    /// Defaults to `MissingUniformPolicy::Error`.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - The policy to apply to missing uniforms.
+    pub fn set_missing_uniform_policy(&self, policy: MissingUniformPolicy) {
+        self.missing_uniform_policy.set(policy);
+    }
+
+    /// Returns what the uniform setters do when a uniform is not found.
+    pub fn missing_uniform_policy(&self) -> MissingUniformPolicy {
+        self.missing_uniform_policy.get()
     }
 
     /// Retrieve the location of a uniform variable within the shader program.
@@ -164,6 +640,10 @@ impl ShaderProgram {
     /// If the location is not cached, it queries OpenGL for the location of the uniform
     /// variable with the given `name` and caches the result.
     ///
+    /// If the uniform is not found, the program's `MissingUniformPolicy` decides the outcome.
+    /// With `WarnOnce` and `Ignore` the location `-1` is returned, which OpenGL silently
+    /// ignores in `glUniform*` calls.
+    ///
     /// # Arguments
     ///
     /// * `name` - The name of the uniform variable whose location is to be retrieved.
@@ -171,25 +651,87 @@ impl ShaderProgram {
     /// # Returns
     ///
     /// A `Result` containing the location of the uniform variable as a `GLint` if successful,
-    /// or an error of type `Errors::OpenGlError` if the uniform variable is not found or if
-    /// there is an error converting the name to a `CString`.
-
-    pub fn get_uniform_location(&mut self, name: &str) -> Result<GLint, Errors> {
-        if let Some(&location) = self.uniform_ids.get(name) {
-            Ok(location)
-        } else {
-            let c_name = CString::new(name)
-                .map_err(|e| Errors::OpenGlError(e.to_string(), gl::INVALID_VALUE))?;
-            let location = unsafe { gl::GetUniformLocation(self.program_handle, c_name.as_ptr()) };
-            if location < 0 {
-                Err(Errors::OpenGlError(
-                    format!("Uniform '{}' not found", name,),
-                    gl::UNIFORM,
-                ))
-            } else {
-                self.uniform_ids.insert(name.to_string(), location);
+    /// or an error of type `Errors::OpenGlError` if the uniform variable is not found with
+    /// `MissingUniformPolicy::Error` or if there is an error converting the name to a `CString`.
+    pub fn get_uniform_location(&self, name: &str) -> Result<GLint, Errors> {
+        let location = self.query_uniform_location(name)?;
+        if location >= 0 {
+            return Ok(location);
+        }
+
+        match self.missing_uniform_policy.get() {
+            MissingUniformPolicy::Error => Err(Errors::OpenGlError(
+                format!("Uniform '{}' not found", name,),
+                GlErrorKind::UniformNotFound,
+            )),
+            MissingUniformPolicy::WarnOnce => {
+                if self.warned_uniforms.borrow_mut().insert(name.to_string()) {
+                    log::warn!(
+                        "Uniform '{}' not found in shader program {}",
+                        name,
+                        self.program_handle.get()
+                    );
+                }
                 Ok(location)
             }
+            MissingUniformPolicy::Ignore => Ok(location),
+        }
+    }
+
+    /// Looks up and caches the location of a uniform, returning `-1` if it does not exist.
+    fn query_uniform_location(&self, name: &str) -> Result<GLint, Errors> {
+        if let Some(&location) = self.uniform_ids.borrow().get(name) {
+            return Ok(location);
+        }
+        let c_name = CString::new(name)
+            .map_err(|e| Errors::OpenGlError(e.to_string(), GlErrorKind::InvalidValue))?;
+        let location =
+            unsafe { gl::GetUniformLocation(self.program_handle.get(), c_name.as_ptr()) };
+        self.uniform_ids
+            .borrow_mut()
+            .insert(name.to_string(), location);
+        Ok(location)
+    }
+
+    /// Set the value of a uniform variable of any type implementing `UniformValue`.
+    ///
+    /// Implemented for `f32`, `i32`, `u32`, `bool` and the cgmath types `Vector2<f32>`,
+    /// `Vector3<f32>`, `Vector4<f32>`, `Point3<f32>`, `Matrix3<f32>` and `Matrix4<f32>`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the uniform variable to set.
+    /// * `value` - The value to set the uniform variable to.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a value of type `()` if successful, or an error of type
+    /// `Errors::OpenGlError` if there is an error setting the uniform variable.
+    pub fn set_uniform<T: UniformValue>(&self, name: &str, value: T) -> Result<(), Errors> {
+        let location = self.get_uniform_location(name)?;
+        value.set_uniform(location);
+        Ok(())
+    }
+
+    /// Set the value of a uniform variable if it exists, ignoring the `MissingUniformPolicy`.
+    ///
+    /// Use this for optional uniforms that only some shaders declare.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the uniform variable to set.
+    /// * `value` - The value to set the uniform variable to.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the uniform exists and was set, otherwise `false`.
+    pub fn try_set_uniform<T: UniformValue>(&self, name: &str, value: T) -> bool {
+        match self.query_uniform_location(name) {
+            Ok(location) if location >= 0 => {
+                value.set_uniform(location);
+                true
+            }
+            _ => false,
         }
     }
 
@@ -205,20 +747,24 @@ impl ShaderProgram {
     /// A `Result` containing `()` if successful, or an error of type `Errors::OpenGlError` otherwise.
     pub fn create_uniform_block(&self, block_name: &str, binding_point: u32) -> Result<(), Errors> {
         let c_name = CString::new(block_name)
-            .map_err(|e| Errors::OpenGlError(e.to_string(), gl::INVALID_VALUE))?;
+            .map_err(|e| Errors::OpenGlError(e.to_string(), GlErrorKind::InvalidValue))?;
 
-        let block_index = unsafe { gl::GetUniformBlockIndex(self.program_handle, c_name.as_ptr()) };
+        let block_index =
+            unsafe { gl::GetUniformBlockIndex(self.program_handle.get(), c_name.as_ptr()) };
         if block_index == gl::INVALID_INDEX {
             return Err(Errors::OpenGlError(
                 format!("Uniform block '{}' not found", block_name),
-                gl::INVALID_VALUE,
+                GlErrorKind::UniformNotFound,
             ));
         }
 
         unsafe {
-            gl::UniformBlockBinding(self.program_handle, block_index, binding_point);
+            gl::UniformBlockBinding(self.program_handle.get(), block_index, binding_point);
         }
 
+        let mut uniform_blocks = self.uniform_blocks.borrow_mut();
+        uniform_blocks.retain(|(name, _)| name != block_name);
+        uniform_blocks.push((block_name.to_string(), binding_point));
         Ok(())
     }
 
@@ -238,12 +784,8 @@ impl ShaderProgram {
     ///
     /// A `Result` containing a value of type `()` if successful, or an error of type
     /// `Errors::OpenGlError` if there is an error setting the uniform variable.
-    pub fn set_uniform_1f(&mut self, name: &str, value: f32) -> Result<(), Errors> {
-        let location = self.get_uniform_location(name)?;
-        unsafe {
-            gl::Uniform1f(location, value);
-        }
-        Ok(())
+    pub fn set_uniform_1f(&self, name: &str, value: f32) -> Result<(), Errors> {
+        self.set_uniform(name, value)
     }
 
     /// Set the value of a uniform variable of type `i32`.
@@ -262,12 +804,8 @@ impl ShaderProgram {
     ///
     /// A `Result` containing a value of type `()` if successful, or an error of type
     /// `Errors::OpenGlError` if there is an error setting the uniform variable.
-    pub fn set_uniform_1i(&mut self, name: &str, value: i32) -> Result<(), Errors> {
-        let location = self.get_uniform_location(name)?;
-        unsafe {
-            gl::Uniform1i(location, value);
-        }
-        Ok(())
+    pub fn set_uniform_1i(&self, name: &str, value: i32) -> Result<(), Errors> {
+        self.set_uniform(name, value)
     }
 
     /// Set the value of a uniform variable of type `vec3` (three f32 components).
@@ -289,12 +827,8 @@ impl ShaderProgram {
     /// A `Result` containing a value of type `()` if successful, or an error of type
     /// `Errors::OpenGlError` if there is an error setting the uniform variable.
 
-    pub fn set_uniform_3f(&mut self, name: &str, x: f32, y: f32, z: f32) -> Result<(), Errors> {
-        let location = self.get_uniform_location(name)?;
-        unsafe {
-            gl::Uniform3f(location, x, y, z);
-        }
-        Ok(())
+    pub fn set_uniform_3f(&self, name: &str, x: f32, y: f32, z: f32) -> Result<(), Errors> {
+        self.set_uniform(name, vec3(x, y, z))
     }
 
     /// Set the value of a uniform variable of type `cgmath::Matrix4<f32>`.
@@ -314,21 +848,377 @@ impl ShaderProgram {
     /// A `Result` containing a value of type `()` if successful, or an error of type
     /// `Errors::OpenGlError` if there is an error setting the uniform variable.
     pub fn set_uniform_matrix4fv(
-        &mut self,
+        &self,
         name: &str,
         matrix: &cgmath::Matrix4<f32>,
     ) -> Result<(), Errors> {
+        self.set_uniform(name, *matrix)
+    }
+
+    /// Set the value of a uniform variable of type `vec2`.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glUniform2f(location, x, y)`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the uniform variable to set.
+    /// * `x` - The x component of the vector.
+    /// * `y` - The y component of the vector.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a value of type `()` if successful, or an error of type
+    /// `Errors::OpenGlError` if there is an error setting the uniform variable.
+    pub fn set_uniform_2f(&self, name: &str, x: f32, y: f32) -> Result<(), Errors> {
+        self.set_uniform(name, vec2(x, y))
+    }
+
+    /// Set the value of a uniform variable of type `vec4`.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glUniform4f(location, x, y, z, w)`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the uniform variable to set.
+    /// * `x` - The x component of the vector.
+    /// * `y` - The y component of the vector.
+    /// * `z` - The z component of the vector.
+    /// * `w` - The w component of the vector.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a value of type `()` if successful, or an error of type
+    /// `Errors::OpenGlError` if there is an error setting the uniform variable.
+    pub fn set_uniform_4f(&self, name: &str, x: f32, y: f32, z: f32, w: f32) -> Result<(), Errors> {
+        self.set_uniform(name, vec4(x, y, z, w))
+    }
+
+    /// Set the value of a uniform variable of type `ivec2`.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glUniform2i(location, x, y)`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the uniform variable to set.
+    /// * `x` - The x component of the vector.
+    /// * `y` - The y component of the vector.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a value of type `()` if successful, or an error of type
+    /// `Errors::OpenGlError` if there is an error setting the uniform variable.
+    pub fn set_uniform_2i(&self, name: &str, x: i32, y: i32) -> Result<(), Errors> {
+        let location = self.get_uniform_location(name)?;
+        unsafe {
+            gl::Uniform2i(location, x, y);
+        }
+        Ok(())
+    }
+
+    /// Set the value of a uniform variable of type `ivec3`.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glUniform3i(location, x, y, z)`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the uniform variable to set.
+    /// * `x` - The x component of the vector.
+    /// * `y` - The y component of the vector.
+    /// * `z` - The z component of the vector.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a value of type `()` if successful, or an error of type
+    /// `Errors::OpenGlError` if there is an error setting the uniform variable.
+    pub fn set_uniform_3i(&self, name: &str, x: i32, y: i32, z: i32) -> Result<(), Errors> {
+        let location = self.get_uniform_location(name)?;
+        unsafe {
+            gl::Uniform3i(location, x, y, z);
+        }
+        Ok(())
+    }
+
+    /// Set the value of a uniform variable of type `ivec4`.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glUniform4i(location, x, y, z, w)`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the uniform variable to set.
+    /// * `x` - The x component of the vector.
+    /// * `y` - The y component of the vector.
+    /// * `z` - The z component of the vector.
+    /// * `w` - The w component of the vector.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a value of type `()` if successful, or an error of type
+    /// `Errors::OpenGlError` if there is an error setting the uniform variable.
+    pub fn set_uniform_4i(&self, name: &str, x: i32, y: i32, z: i32, w: i32) -> Result<(), Errors> {
+        let location = self.get_uniform_location(name)?;
+        unsafe {
+            gl::Uniform4i(location, x, y, z, w);
+        }
+        Ok(())
+    }
+
+    /// Set the value of a uniform variable of type `uint`.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glUniform1ui(location, value)`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the uniform variable to set.
+    /// * `value` - The value to set the uniform variable to.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a value of type `()` if successful, or an error of type
+    /// `Errors::OpenGlError` if there is an error setting the uniform variable.
+    pub fn set_uniform_1ui(&self, name: &str, value: u32) -> Result<(), Errors> {
+        self.set_uniform(name, value)
+    }
+
+    /// Set the value of a uniform variable of type `bool`.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glUniform1i(location, value)`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the uniform variable to set.
+    /// * `value` - The value to set the uniform variable to, uploaded as `0` or `1`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a value of type `()` if successful, or an error of type
+    /// `Errors::OpenGlError` if there is an error setting the uniform variable.
+    pub fn set_uniform_bool(&self, name: &str, value: bool) -> Result<(), Errors> {
+        self.set_uniform(name, value)
+    }
+
+    /// Set the value of a uniform variable of type `cgmath::Matrix3<f32>`, e.g. a normal
+    /// matrix.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glUniformMatrix3fv(location, 1, GL_FALSE, matrix.as_ptr())`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the uniform variable to set.
+    /// * `matrix` - The value to set the uniform variable to.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a value of type `()` if successful, or an error of type
+    /// `Errors::OpenGlError` if there is an error setting the uniform variable.
+    pub fn set_uniform_matrix3fv(
+        &self,
+        name: &str,
+        matrix: &cgmath::Matrix3<f32>,
+    ) -> Result<(), Errors> {
+        self.set_uniform(name, *matrix)
+    }
+
+    /// Set the values of a uniform array of type `float[]`, starting at its first element.
+    ///
+    /// Only as many elements as given are set; the rest keep their values.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glUniform1fv(location, count, values)`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the uniform array to set, e.g. `"lightRanges"`.
+    /// * `values` - The values of the array elements.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a value of type `()` if successful, or an error of type
+    /// `Errors::OpenGlError` if there is an error setting the uniform variable.
+    pub fn set_uniform_1fv(&self, name: &str, values: &[f32]) -> Result<(), Errors> {
+        let location = self.get_uniform_location(name)?;
+        unsafe {
+            gl::Uniform1fv(location, values.len() as GLsizei, values.as_ptr());
+        }
+        Ok(())
+    }
+
+    /// Set the values of a uniform array of type `int[]`, starting at its first element.
+    ///
+    /// Only as many elements as given are set; the rest keep their values.
+    /// Also used for arrays of samplers, with one texture unit per element.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glUniform1iv(location, count, values)`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the uniform array to set, e.g. `"shadowMaps"`.
+    /// * `values` - The values of the array elements.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a value of type `()` if successful, or an error of type
+    /// `Errors::OpenGlError` if there is an error setting the uniform variable.
+    pub fn set_uniform_1iv(&self, name: &str, values: &[i32]) -> Result<(), Errors> {
+        let location = self.get_uniform_location(name)?;
+        unsafe {
+            gl::Uniform1iv(location, values.len() as GLsizei, values.as_ptr());
+        }
+        Ok(())
+    }
+
+    /// Set the values of a uniform array of type `vec2[]`, starting at its first element.
+    ///
+    /// Only as many elements as given are set; the rest keep their values.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glUniform2fv(location, count, values)`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the uniform array to set, e.g. `"sampleOffsets"`.
+    /// * `values` - The values of the array elements.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a value of type `()` if successful, or an error of type
+    /// `Errors::OpenGlError` if there is an error setting the uniform variable.
+    pub fn set_uniform_2fv_array(&self, name: &str, values: &[[f32; 2]]) -> Result<(), Errors> {
+        let location = self.get_uniform_location(name)?;
+        unsafe {
+            gl::Uniform2fv(
+                location,
+                values.len() as GLsizei,
+                values.as_ptr() as *const GLfloat,
+            );
+        }
+        Ok(())
+    }
+
+    /// Set the values of a uniform array of type `vec3[]`, starting at its first element.
+    ///
+    /// Only as many elements as given are set; the rest keep their values.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glUniform3fv(location, count, values)`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the uniform array to set, e.g. `"lightPositions"`.
+    /// * `values` - The values of the array elements.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a value of type `()` if successful, or an error of type
+    /// `Errors::OpenGlError` if there is an error setting the uniform variable.
+    pub fn set_uniform_3fv_array(&self, name: &str, values: &[[f32; 3]]) -> Result<(), Errors> {
+        let location = self.get_uniform_location(name)?;
+        unsafe {
+            gl::Uniform3fv(
+                location,
+                values.len() as GLsizei,
+                values.as_ptr() as *const GLfloat,
+            );
+        }
+        Ok(())
+    }
+
+    /// Set the values of a uniform array of type `vec4[]`, starting at its first element.
+    ///
+    /// Only as many elements as given are set; the rest keep their values.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glUniform4fv(location, count, values)`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the uniform array to set, e.g. `"lightColors"`.
+    /// * `values` - The values of the array elements.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a value of type `()` if successful, or an error of type
+    /// `Errors::OpenGlError` if there is an error setting the uniform variable.
+    pub fn set_uniform_4fv_array(&self, name: &str, values: &[[f32; 4]]) -> Result<(), Errors> {
         let location = self.get_uniform_location(name)?;
         unsafe {
-            gl::UniformMatrix4fv(location, 1, gl::FALSE, matrix.as_ptr());
+            gl::Uniform4fv(
+                location,
+                values.len() as GLsizei,
+                values.as_ptr() as *const GLfloat,
+            );
+        }
+        Ok(())
+    }
+
+    /// Set the values of a uniform array of type `mat4[]`, starting at its first element.
+    ///
+    /// Only as many elements as given are set; the rest keep their values.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glUniformMatrix4fv(location, count, GL_FALSE, values)`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the uniform array to set, e.g. `"boneMatrices"`.
+    /// * `values` - The values of the array elements.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a value of type `()` if successful, or an error of type
+    /// `Errors::OpenGlError` if there is an error setting the uniform variable.
+    pub fn set_uniform_matrix4fv_array(
+        &self,
+        name: &str,
+        values: &[cgmath::Matrix4<f32>],
+    ) -> Result<(), Errors> {
+        let location = self.get_uniform_location(name)?;
+        unsafe {
+            gl::UniformMatrix4fv(
+                location,
+                values.len() as GLsizei,
+                gl::FALSE,
+                values.as_ptr() as *const GLfloat,
+            );
         }
         Ok(())
     }
 }
 
+impl Drop for ShaderProgram {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteProgram(self.program_handle.get());
+        }
+    }
+}
+
 pub struct UniformBuffer {
     id: GLuint,
     binding_point: u32,
+    _context: ContextBound,
 }
 
 impl UniformBuffer {
@@ -342,6 +1232,7 @@ impl UniformBuffer {
     ///
     /// A `Result` containing a `UniformBuffer` instance if successful, or an error of type `Errors::OpenGlError` otherwise.
     pub fn new(binding_point: u32) -> Result<Self, Errors> {
+        check_context_thread()?;
         let mut id = 0;
         unsafe {
             gl::GenBuffers(1, &mut id);
@@ -349,11 +1240,15 @@ impl UniformBuffer {
         if id == 0 {
             return Err(Errors::OpenGlError(
                 "Failed to generate uniform buffer".to_string(),
-                gl::INVALID_OPERATION,
+                GlErrorKind::ObjectCreationFailed,
             ));
         }
 
-        Ok(Self { id, binding_point })
+        Ok(Self {
+            id,
+            binding_point,
+            _context: ContextBound::default(),
+        })
     }
 
     /// Bind the uniform buffer to its binding point.
@@ -0,0 +1,207 @@
+//! # Shader Include Module
+//!
+//! Shader files loaded by `ShaderProgram` may include other files with
+//! `#include "common/lighting.glsl"`. Quoted paths are looked up next to the including file
+//! first and then in the include search paths; `#include <common/lighting.glsl>` only uses
+//! the search paths. Every file is included at most once per shader, like with
+//! `#pragma once`, and include cycles are reported as errors.
+//!
+//! `#line` directives are inserted around included files, so compiler messages refer to the
+//! line in the original file. The source string number of a file is its index in
+//! `ShaderSource::files`.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::graphics::gl_wrapper::{add_include_path, ShaderProgram};
+//!
+//! fn main() -> Result<(), glwfr::custom_errors::Errors> {
+//!     add_include_path("assets/shaders");
+//!     // "lit.frag" may now contain `#include "common/lighting.glsl"`
+//!     let shader_program = ShaderProgram::new("assets/shaders/lit.vert", "assets/shaders/lit.frag")?;
+//!     shader_program.bind();
+//!     Ok(())
+//! }
+//! ```
+
+use crate::custom_errors::Errors;
+use lazy_static::lazy_static;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+lazy_static! {
+    static ref INCLUDE_PATHS: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+}
+
+/// Add a directory to the search paths for `#include` directives.
+///
+/// Directories are searched in the order they were added.
+pub fn add_include_path(path: impl AsRef<Path>) {
+    let path = path.as_ref().to_path_buf();
+    let mut paths = INCLUDE_PATHS.lock().unwrap();
+    if !paths.contains(&path) {
+        paths.push(path);
+    }
+}
+
+/// Remove all search paths for `#include` directives.
+pub fn clear_include_paths() {
+    INCLUDE_PATHS.lock().unwrap().clear();
+}
+
+/// Returns the search paths for `#include` directives.
+pub fn include_paths() -> Vec<PathBuf> {
+    INCLUDE_PATHS.lock().unwrap().clone()
+}
+
+/// A shader file with all of its includes resolved.
+#[derive(Debug, Clone)]
+pub struct ShaderSource {
+    /// The GLSL source with the includes pasted in.
+    pub source: String,
+    /// The files the source was built from. The first one is the loaded file, the index of a
+    /// file is its source string number in compiler messages.
+    pub files: Vec<PathBuf>,
+}
+
+impl ShaderSource {
+    /// Load a shader file and resolve its `#include` directives.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the shader source file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::FileLoadError` if the shader file cannot be read, or an
+    /// `Errors::ShaderIncludeError` naming the file and line of an include that is malformed,
+    /// cannot be found or forms a cycle.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Errors> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)
+            .map_err(|e| Errors::FileLoadError(format!("{}: {}", path.display(), e)))?;
+        let mut preprocessor = Preprocessor {
+            search_paths: include_paths(),
+            files: Vec::new(),
+            canonical: Vec::new(),
+            stack: Vec::new(),
+            output: String::with_capacity(source.len()),
+        };
+        preprocessor.process(path, &source)?;
+        Ok(Self {
+            source: preprocessor.output,
+            files: preprocessor.files,
+        })
+    }
+
+    /// Returns a list of the source string numbers and their files, for reading compiler
+    /// messages.
+    pub fn file_legend(&self) -> String {
+        self.files
+            .iter()
+            .enumerate()
+            .map(|(index, file)| format!("{}: {}", index, file.display()))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Pastes included files into a single source.
+struct Preprocessor {
+    search_paths: Vec<PathBuf>,
+    files: Vec<PathBuf>,
+    canonical: Vec<PathBuf>,
+    /// Indices of the files currently being included, outermost first.
+    stack: Vec<usize>,
+    output: String,
+}
+
+impl Preprocessor {
+    /// Append a file and its includes to the output.
+    fn process(&mut self, path: &Path, source: &str) -> Result<(), Errors> {
+        let index = self.files.len();
+        self.files.push(path.to_path_buf());
+        self.canonical
+            .push(fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf()));
+        self.stack.push(index);
+
+        for (number, line) in source.lines().enumerate() {
+            let Some(target) = parse_include(line) else {
+                self.output.push_str(line);
+                self.output.push('\n');
+                continue;
+            };
+            let error = |message: String| {
+                Errors::ShaderIncludeError(format!(
+                    "{}:{}: {}",
+                    path.display(),
+                    number + 1,
+                    message
+                ))
+            };
+            let (name, quoted) = target.map_err(|_| error(format!("malformed {}", line.trim())))?;
+            let file = self
+                .find(path, name, quoted)
+                .ok_or_else(|| error(format!("cannot find include \"{}\"", name)))?;
+            let canonical = fs::canonicalize(&file).unwrap_or_else(|_| file.clone());
+
+            if let Some(included) = self.canonical.iter().position(|c| *c == canonical) {
+                if let Some(start) = self.stack.iter().position(|&i| i == included) {
+                    let mut cycle: Vec<String> = self.stack[start..]
+                        .iter()
+                        .map(|&i| self.files[i].display().to_string())
+                        .collect();
+                    cycle.push(file.display().to_string());
+                    return Err(error(format!("include cycle: {}", cycle.join(" -> "))));
+                }
+                // Already pasted earlier in the shader, keep the line numbers intact
+                self.output.push('\n');
+                continue;
+            }
+
+            let included_source = fs::read_to_string(&file)
+                .map_err(|e| error(format!("cannot read {}: {}", file.display(), e)))?;
+            self.output
+                .push_str(&format!("#line 1 {}\n", self.files.len()));
+            self.process(&file, &included_source)?;
+            self.output
+                .push_str(&format!("#line {} {}\n", number + 2, index));
+        }
+
+        self.stack.pop();
+        Ok(())
+    }
+
+    /// Look up an included file, next to the including file first for quoted paths.
+    fn find(&self, including: &Path, name: &str, quoted: bool) -> Option<PathBuf> {
+        let local = including
+            .parent()
+            .filter(|_| quoted)
+            .map(|directory| directory.join(name));
+        local
+            .into_iter()
+            .chain(
+                self.search_paths
+                    .iter()
+                    .map(|directory| directory.join(name)),
+            )
+            .find(|candidate| candidate.is_file())
+    }
+}
+
+/// Parses an `#include` directive into the included path and whether it was quoted.
+///
+/// Returns `None` for other lines and `Some(Err(()))` for a malformed directive.
+fn parse_include(line: &str) -> Option<Result<(&str, bool), ()>> {
+    let directive = line.trim_start().strip_prefix('#')?.trim_start();
+    let rest = directive.strip_prefix("include")?.trim();
+    let target = if let Some(name) = rest.strip_prefix('"') {
+        name.strip_suffix('"').map(|name| (name, true))
+    } else if let Some(name) = rest.strip_prefix('<') {
+        name.strip_suffix('>').map(|name| (name, false))
+    } else {
+        None
+    };
+    Some(target.filter(|(name, _)| !name.is_empty()).ok_or(()))
+}
@@ -1,9 +1,18 @@
 //! # VAO Module
 
-use crate::custom_errors::Errors;
+use crate::custom_errors::{Errors, GlErrorKind};
+use crate::graphics::gl_wrapper::context::{check_context_thread, ContextBound};
+use crate::graphics::gl_wrapper::label::set_object_label;
+use crate::graphics::gl_wrapper::{BufferObject, IndexType, PrimitiveType, Vertex};
+
 pub struct Vao {
     id: gl::types::GLuint,
     index_count: Option<usize>,
+    index_type: IndexType,
+    primitive_type: PrimitiveType,
+    line_width: f32,
+    point_size: f32,
+    _context: ContextBound,
 }
 
 impl Vao {
@@ -16,9 +25,10 @@ impl Vao {
     ///
     /// # Errors
     ///
-    /// Returns an `Errors::OpenGlError` if the VAO cannot be generated.
-
+    /// Returns an `Errors::OpenGlError` if the VAO cannot be generated or if called from a
+    /// thread other than the context thread.
     pub fn new() -> Result<Self, Errors> {
+        check_context_thread()?;
         let mut id = 0;
         unsafe {
             gl::GenVertexArrays(1, &mut id);
@@ -26,15 +36,35 @@ impl Vao {
         if id == 0 {
             return Err(Errors::OpenGlError(
                 "VAO creation failed".to_string(),
-                gl::INVALID_OPERATION,
+                GlErrorKind::ObjectCreationFailed,
             ));
         }
         Ok(Self {
             id,
             index_count: None,
+            index_type: IndexType::U32,
+            primitive_type: PrimitiveType::Triangles,
+            line_width: 1.0,
+            point_size: 1.0,
+            _context: ContextBound::default(),
         })
     }
 
+    /// Attach a debug label to the vertex array object, shown by tools like RenderDoc and Nsight.
+    ///
+    /// Does nothing if `GL_KHR_debug` is not available.
+    ///
+    /// # Arguments
+    ///
+    /// * `label` - The label to attach, e.g. `"player_mesh_vao"`.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glObjectLabel`.
+    pub fn set_debug_label(&self, label: &str) {
+        set_object_label(gl::VERTEX_ARRAY, self.id, label);
+    }
+
     /// Set the index count for the vertex array object (VAO).
     ///
     /// # Parameters
@@ -57,6 +87,88 @@ impl Vao {
         self.index_count.expect("Index count not set for VAO")
     }
 
+    /// Set the type of the indices stored in the EBO bound to this VAO.
+    ///
+    /// Defaults to `IndexType::U32`.
+    ///
+    /// # Parameters
+    ///
+    /// * `index_type` - The type of the indices used for drawing the VAO.
+    pub fn set_index_type(&mut self, index_type: IndexType) {
+        self.index_type = index_type;
+    }
+
+    /// Returns the type of the indices used for drawing the VAO.
+    pub fn index_type(&self) -> IndexType {
+        self.index_type
+    }
+
+    /// Set the primitive type used for drawing the VAO.
+    ///
+    /// Defaults to `PrimitiveType::Triangles`.
+    ///
+    /// # Parameters
+    ///
+    /// * `primitive_type` - The primitive topology used to assemble the indices.
+    pub fn set_primitive_type(&mut self, primitive_type: PrimitiveType) {
+        self.primitive_type = primitive_type;
+    }
+
+    /// Returns the primitive type used for drawing the VAO.
+    pub fn primitive_type(&self) -> PrimitiveType {
+        self.primitive_type
+    }
+
+    /// Set the width of lines when the VAO is drawn with a line primitive type.
+    ///
+    /// # Parameters
+    ///
+    /// * `width` - The width of lines in pixels. Defaults to `1.0`.
+    pub fn set_line_width(&mut self, width: f32) {
+        self.line_width = width;
+    }
+
+    /// Returns the width of lines used for drawing the VAO.
+    pub fn line_width(&self) -> f32 {
+        self.line_width
+    }
+
+    /// Set the diameter of points when the VAO is drawn with `PrimitiveType::Points`.
+    ///
+    /// # Parameters
+    ///
+    /// * `size` - The diameter of points in pixels. Defaults to `1.0`.
+    pub fn set_point_size(&mut self, size: f32) {
+        self.point_size = size;
+    }
+
+    /// Returns the diameter of points used for drawing the VAO.
+    pub fn point_size(&self) -> f32 {
+        self.point_size
+    }
+
+    /// Attach a buffer of per-instance data to the vertex array object (VAO).
+    ///
+    /// The attributes of `I` advance once per instance, so every instance drawn with
+    /// `glDrawElementsInstanced` reads its own element of the buffer. The VAO is unbound
+    /// afterwards.
+    ///
+    /// # Arguments
+    ///
+    /// * `buffer` - A `gl::ARRAY_BUFFER` holding elements of type `I`.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glVertexAttribPointer` and `glVertexAttribDivisor`
+    /// for every attribute of `I`, see `Vertex::setup_instance_attributes`.
+    pub fn attach_instance_buffer<I: Vertex>(&self, buffer: &BufferObject) {
+        self.bind();
+        buffer.bind();
+        I::setup_instance_attributes();
+        self.unbind();
+        buffer.unbind();
+    }
+
     /// Bind the Vertex Array Object (VAO).
     ///
     /// # OpenGL Functions
@@ -1,7 +1,11 @@
 //! # VBO Module
 
-use crate::custom_errors::Errors;
+use crate::custom_errors::{Errors, GlErrorKind};
+use crate::graphics::gl_wrapper::context::{check_context_thread, ContextBound};
+use crate::graphics::gl_wrapper::label::set_object_label;
+use crate::graphics::stats::{track_allocation, MemoryCategory};
 use gl::types::*;
+use std::cell::Cell;
 use std::mem;
 use std::os::raw::*;
 
@@ -9,6 +13,8 @@ pub struct BufferObject {
     id: gl::types::GLuint,
     r#type: gl::types::GLenum,
     usage: gl::types::GLenum,
+    size: Cell<usize>,
+    _context: ContextBound,
 }
 
 impl BufferObject {
@@ -29,6 +35,7 @@ impl BufferObject {
     /// This function is a wrapper around `glGenBuffers(1, &mut id)` and `glBindBuffer(r#type, id)`.
     /// It generates a new buffer object of the given type with the given usage.
     pub fn new(r#type: GLenum, usage: GLenum) -> Result<Self, Errors> {
+        check_context_thread()?;
         let mut id = 0;
         unsafe {
             gl::GenBuffers(1, &mut id);
@@ -36,10 +43,31 @@ impl BufferObject {
         if id == 0 {
             return Err(Errors::OpenGlError(
                 "Failed to generate buffer".to_string(),
-                gl::INVALID_OPERATION,
+                GlErrorKind::ObjectCreationFailed,
             ));
         }
-        Ok(Self { id, r#type, usage })
+        Ok(Self {
+            id,
+            r#type,
+            usage,
+            size: Cell::new(0),
+            _context: ContextBound::default(),
+        })
+    }
+
+    /// Attach a debug label to the buffer object, shown by tools like RenderDoc and Nsight.
+    ///
+    /// Does nothing if `GL_KHR_debug` is not available.
+    ///
+    /// # Arguments
+    ///
+    /// * `label` - The label to attach, e.g. `"player_mesh_vbo"`.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glObjectLabel`.
+    pub fn set_debug_label(&self, label: &str) {
+        set_object_label(gl::BUFFER, self.id, label);
     }
 
     /// Bind the buffer object to the given OpenGL buffer binding point.
@@ -66,6 +94,63 @@ impl BufferObject {
         }
     }
 
+    /// Returns the buffer type, e.g. `gl::ARRAY_BUFFER`.
+    pub fn buffer_type(&self) -> GLenum {
+        self.r#type
+    }
+
+    /// Returns the usage hint the buffer object was created with, e.g. `gl::STATIC_DRAW`.
+    pub fn usage(&self) -> GLenum {
+        self.usage
+    }
+
+    /// Returns the size of the buffer's data store in bytes, or `0` if nothing has been stored yet.
+    pub fn size(&self) -> usize {
+        self.size.get()
+    }
+
+    /// Updates `size` and the memory tracked in `RendererStats`.
+    fn set_size(&self, size: usize) {
+        track_allocation(MemoryCategory::Buffer, self.size.get(), size);
+        self.size.set(size);
+    }
+
+    /// Allocate an uninitialized data store of the given size, replacing the previous one.
+    ///
+    /// The buffer object must be bound before calling this function.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - The size of the data store in bytes.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glBufferData(r#type, size, null, usage)`.
+    pub fn allocate(&self, size: usize) {
+        self.set_size(size);
+        unsafe {
+            gl::BufferData(
+                self.r#type,
+                size as GLsizeiptr,
+                std::ptr::null(),
+                self.usage,
+            );
+        }
+    }
+
+    /// Orphan the data store, so the driver can hand out fresh memory while draws still
+    /// reading the old contents finish. The size is kept, but the contents become undefined.
+    ///
+    /// Call this before rewriting a dynamic buffer every frame to avoid stalling on the GPU.
+    /// The buffer object must be bound before calling this function.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glBufferData(r#type, size, null, usage)`.
+    pub fn orphan(&self) {
+        self.allocate(self.size.get());
+    }
+
     /// Store the given i32 slice in the buffer object.
     ///
     /// # OpenGL Functions
@@ -77,10 +162,12 @@ impl BufferObject {
     ///
     /// * `data` - The i32 slice to store in the buffer object.
     pub fn store_i32_data(&self, data: &[i32]) {
+        let size = mem::size_of_val(data);
+        self.set_size(size);
         unsafe {
             gl::BufferData(
                 self.r#type,
-                (data.len() * mem::size_of::<gl::types::GLint>()) as gl::types::GLsizeiptr,
+                size as gl::types::GLsizeiptr,
                 &data[0] as *const i32 as *const c_void,
                 self.usage,
             )
@@ -98,10 +185,12 @@ impl BufferObject {
     ///
     /// * `data` - The f32 slice to store in the buffer object.
     pub fn store_f32_data(&self, data: &[f32]) {
+        let size = mem::size_of_val(data);
+        self.set_size(size);
         unsafe {
             gl::BufferData(
                 self.r#type,
-                (data.len() * mem::size_of::<gl::types::GLfloat>()) as gl::types::GLsizeiptr,
+                size as gl::types::GLsizeiptr,
                 &data[0] as *const f32 as *const c_void,
                 self.usage,
             )
@@ -119,10 +208,12 @@ impl BufferObject {
     ///
     /// * `data` - The u32 slice to store in the buffer object.
     pub fn store_u32_data(&self, data: &[u32]) {
+        let size = mem::size_of_val(data);
+        self.set_size(size);
         unsafe {
             gl::BufferData(
                 self.r#type,
-                (data.len() * mem::size_of::<gl::types::GLuint>()) as gl::types::GLsizeiptr,
+                size as gl::types::GLsizeiptr,
                 &data[0] as *const u32 as *const c_void,
                 self.usage,
             )
@@ -150,3 +241,19 @@ impl BufferObject {
         }
     }
 }
+
+impl Drop for BufferObject {
+    /// Automatically deletes the OpenGL buffer when the `BufferObject` instance is dropped.
+    ///
+    /// VAOs referencing the buffer keep its data store alive until they are deleted.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glDeleteBuffers(1, &self.id)`.
+    fn drop(&mut self) {
+        track_allocation(MemoryCategory::Buffer, self.size.get(), 0);
+        unsafe {
+            gl::DeleteBuffers(1, &self.id);
+        }
+    }
+}
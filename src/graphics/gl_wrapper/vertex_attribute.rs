@@ -42,6 +42,37 @@ impl VertexAttribute {
         VertexAttribute { index }
     }
 
+    /// Create a new VertexAttribute read as integers, e.g. for bone indices.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glVertexAttribIPointer(index, size, type, stride, offset)`.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index of the vertex attribute.
+    /// * `size` - The number of components of the vertex attribute.
+    /// * `r#type` - The integer type of the vertex attribute, e.g. `gl::UNSIGNED_SHORT`.
+    /// * `stride` - The stride of the vertex attribute.
+    /// * `offset` - The offset of the attribute in bytes from the start of the vertex.
+    ///
+    /// # Returns
+    ///
+    /// A `VertexAttribute` instance with the given index.
+    pub fn new_integer(
+        index: u32,
+        size: i32,
+        r#type: GLenum,
+        stride: GLsizei,
+        offset: usize,
+    ) -> VertexAttribute {
+        unsafe {
+            gl::VertexAttribIPointer(index, size, r#type, stride, offset as *const c_void);
+        }
+
+        VertexAttribute { index }
+    }
+
     /// Enable the vertex attribute at the given index.
     ///
     /// # OpenGL Functions
@@ -54,6 +85,22 @@ impl VertexAttribute {
         }
     }
 
+    /// Set how often the attribute advances during instanced draws.
+    ///
+    /// # Arguments
+    ///
+    /// * `divisor` - `0` to advance once per vertex, or `n` to advance once every `n`
+    ///   instances, e.g. `1` for per-instance data.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glVertexAttribDivisor(index, divisor)`.
+    pub fn set_divisor(&self, divisor: u32) {
+        unsafe {
+            gl::VertexAttribDivisor(self.index, divisor);
+        }
+    }
+
     /// Disable the vertex attribute at the given index.
     ///
     /// # OpenGL Functions
@@ -66,3 +113,136 @@ impl VertexAttribute {
         }
     }
 }
+
+/// Describes a single attribute of a vertex layout.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VertexAttributeLayout {
+    /// The index of the vertex attribute.
+    pub index: u32,
+    /// The number of components of the vertex attribute.
+    pub size: i32,
+    /// The type of the vertex attribute. For example, `gl::FLOAT`.
+    pub r#type: GLenum,
+    /// Whether the vertex attribute is normalized.
+    pub normalized: GLboolean,
+    /// The offset of the attribute in bytes from the start of the vertex.
+    pub offset: usize,
+}
+
+/// A type that can be used as a field of a vertex struct.
+///
+/// Implemented for `f32`, `i32`, `u32`, `i16`, `u16`, `i8`, `u8` and arrays of up to four of them.
+pub trait VertexComponent {
+    /// The number of components of the field.
+    const SIZE: i32;
+    /// The OpenGL type of a single component. For example, `gl::FLOAT`.
+    const GL_TYPE: GLenum;
+}
+
+macro_rules! impl_vertex_component {
+    ($($ty:ty => $gl_type:expr),* $(,)?) => {
+        $(
+            impl VertexComponent for $ty {
+                const SIZE: i32 = 1;
+                const GL_TYPE: GLenum = $gl_type;
+            }
+            impl VertexComponent for [$ty; 1] {
+                const SIZE: i32 = 1;
+                const GL_TYPE: GLenum = $gl_type;
+            }
+            impl VertexComponent for [$ty; 2] {
+                const SIZE: i32 = 2;
+                const GL_TYPE: GLenum = $gl_type;
+            }
+            impl VertexComponent for [$ty; 3] {
+                const SIZE: i32 = 3;
+                const GL_TYPE: GLenum = $gl_type;
+            }
+            impl VertexComponent for [$ty; 4] {
+                const SIZE: i32 = 4;
+                const GL_TYPE: GLenum = $gl_type;
+            }
+        )*
+    };
+}
+
+impl_vertex_component! {
+    f32 => gl::FLOAT,
+    i32 => gl::INT,
+    u32 => gl::UNSIGNED_INT,
+    i16 => gl::SHORT,
+    u16 => gl::UNSIGNED_SHORT,
+    i8 => gl::BYTE,
+    u8 => gl::UNSIGNED_BYTE,
+}
+
+/// A vertex type with a known attribute layout.
+///
+/// With the `derive` feature enabled this trait can be derived for plain structs, which
+/// generates one attribute per field in declaration order:
+///
+#[cfg_attr(feature = "derive", doc = "```rust")]
+#[cfg_attr(not(feature = "derive"), doc = "```ignore")]
+/// use glwfr::graphics::gl_wrapper::Vertex;
+///
+/// #[derive(Vertex)]
+/// #[repr(C)]
+/// struct MyVertex {
+///     pos: [f32; 3],
+///     #[vertex(normalized)]
+///     color: [u8; 4],
+///     uv: [f32; 2],
+/// }
+/// ```
+pub trait Vertex: Sized {
+    /// Returns the attribute layout of the vertex.
+    fn layout() -> Vec<VertexAttributeLayout>;
+
+    /// Create and enable a `VertexAttribute` for every attribute of the layout.
+    ///
+    /// The VAO and the VBO holding the vertices must be bound before calling this function.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glVertexAttribPointer` and `glEnableVertexAttribArray`
+    /// for each attribute, using `size_of::<Self>()` as the stride.
+    fn setup_attributes() -> Vec<VertexAttribute> {
+        let stride = std::mem::size_of::<Self>() as GLsizei;
+        Self::layout()
+            .into_iter()
+            .map(|layout| {
+                let attribute = VertexAttribute::new(
+                    layout.index,
+                    layout.size,
+                    layout.r#type,
+                    layout.normalized,
+                    stride,
+                    layout.offset as *const c_void,
+                );
+                attribute.enable();
+                attribute
+            })
+            .collect()
+    }
+
+    /// Create and enable a `VertexAttribute` for every attribute of the layout that advances
+    /// once per instance instead of once per vertex.
+    ///
+    /// The VAO and the buffer holding the per-instance data must be bound before calling this
+    /// function. The attribute locations must not overlap those of the per-vertex layout.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glVertexAttribPointer`, `glEnableVertexAttribArray`
+    /// and `glVertexAttribDivisor(index, 1)` for each attribute.
+    fn setup_instance_attributes() -> Vec<VertexAttribute> {
+        let attributes = Self::setup_attributes();
+        for attribute in &attributes {
+            attribute.set_divisor(1);
+        }
+        attributes
+    }
+}
+
+#[cfg(feature = "derive")]
+pub use glwfr_derive::Vertex;
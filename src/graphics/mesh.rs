@@ -0,0 +1,353 @@
+//! # Mesh Module
+//!
+//! This module provides a `Mesh` that owns the VAO, vertex buffer and index buffer of a piece
+//! of geometry. Creating a mesh from a vertex slice and an index slice uploads both, sets up
+//! the attributes of the vertex type and records the index count and type, so the mesh can
+//! be drawn with a single call.
+//!
+//! Per-instance data, e.g. an `InstanceTransform` for every copy of the mesh, is uploaded with
+//! `set_instances` and drawn with `draw_instanced`.
+//!
+//! `Mesh::from_streams` builds a mesh from separate attribute arrays, optionally storing them
+//! in compressed formats, see the `vertex_compression` module.
+//!
+//! Meshes built by hand can still be wrapped with `Mesh::from_vao`, or with `Mesh::from_parts`
+//! to keep their buffers alive together with the VAO.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::graphics::mesh::Mesh;
+//! use glwfr::graphics::standard_vertex::StandardVertex;
+//! use glwfr::scene::Object;
+//! use glwfr::graphics::gl_wrapper::ShaderProgram;
+//!
+//! fn main() -> Result<(), glwfr::custom_errors::Errors> {
+//!     let vertices = [
+//!         StandardVertex::new([0.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 0.0]),
+//!         StandardVertex::new([1.0, 0.0, 0.0], [0.0, 0.0, 1.0], [1.0, 0.0]),
+//!         StandardVertex::new([0.0, 1.0, 0.0], [0.0, 0.0, 1.0], [0.0, 1.0]),
+//!     ];
+//!     let mesh = Mesh::new(&vertices, &[0u16, 1, 2])?;
+//!     mesh.set_debug_label("triangle");
+//!
+//!     let shader_program = ShaderProgram::new("vertex.glsl", "fragment.glsl")?;
+//!     let object = Object::new(mesh, shader_program);
+//!     Ok(())
+//! }
+//! ```
+
+use crate::custom_errors::Errors;
+use crate::graphics::gl_wrapper::{BufferObject, Ebo, Index, PrimitiveType, Vao, Vertex};
+use crate::graphics::stats;
+use crate::graphics::vertex_compression::{VertexCompression, VertexStreams};
+use std::cell::{Cell, OnceCell};
+use std::rc::Rc;
+
+/// A VAO together with the buffers holding its vertices and indices.
+pub struct Mesh {
+    vao: Vao,
+    vertex_buffer: Option<BufferObject>,
+    index_buffer: Option<Ebo>,
+    /// The buffer holding per-instance data, created by the first `set_instances` call.
+    instance_buffer: OnceCell<BufferObject>,
+    instance_count: Cell<usize>,
+}
+
+impl Mesh {
+    /// Uploads vertices and indices into new buffers and creates a triangle mesh drawing them.
+    ///
+    /// The buffers are created with `gl::STATIC_DRAW`; use `update_vertices` for occasional
+    /// changes.
+    ///
+    /// # Arguments
+    ///
+    /// * `vertices` - The vertices, whose attributes are set up from `V::layout`.
+    /// * `indices` - The `u8`, `u16` or `u32` indices into `vertices`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::OpenGlError` if the VAO or a buffer cannot be created or if called
+    /// from a thread other than the context thread.
+    pub fn new<V: Vertex, I: Index>(vertices: &[V], indices: &[I]) -> Result<Self, Errors> {
+        Self::with_primitive_type(vertices, indices, PrimitiveType::Triangles)
+    }
+
+    /// Uploads vertices and indices into new buffers and creates a mesh drawing them with the
+    /// given primitive type.
+    ///
+    /// # Arguments
+    ///
+    /// * `vertices` - The vertices, whose attributes are set up from `V::layout`.
+    /// * `indices` - The `u8`, `u16` or `u32` indices into `vertices`.
+    /// * `primitive_type` - The primitive topology used to assemble the indices.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::OpenGlError` if the VAO or a buffer cannot be created or if called
+    /// from a thread other than the context thread.
+    pub fn with_primitive_type<V: Vertex, I: Index>(
+        vertices: &[V],
+        indices: &[I],
+        primitive_type: PrimitiveType,
+    ) -> Result<Self, Errors> {
+        let mut vao = Vao::new()?;
+        vao.bind();
+        let vertex_buffer = BufferObject::new(gl::ARRAY_BUFFER, gl::STATIC_DRAW)?;
+        vertex_buffer.bind();
+        vertex_buffer.allocate(std::mem::size_of_val(vertices));
+        vertex_buffer.update_data(0, vertices);
+        V::setup_attributes();
+        let index_buffer = Ebo::new()?;
+        index_buffer.bind();
+        let index_type = index_buffer.store_indices(indices);
+        vao.unbind();
+
+        vao.set_index_count(indices.len());
+        vao.set_index_type(index_type);
+        vao.set_primitive_type(primitive_type);
+        Ok(Self {
+            vao,
+            vertex_buffer: Some(vertex_buffer),
+            index_buffer: Some(index_buffer),
+            instance_buffer: OnceCell::new(),
+            instance_count: Cell::new(0),
+        })
+    }
+
+    /// Encodes vertex streams with the given compression and creates a triangle mesh drawing
+    /// them.
+    ///
+    /// # Arguments
+    ///
+    /// * `streams` - The vertex attributes, see `VertexStreams`.
+    /// * `indices` - The `u8`, `u16` or `u32` indices into the vertices.
+    /// * `compression` - Which attributes are stored in compressed formats.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::VertexDataError` if the streams have different lengths, or an
+    /// `Errors::OpenGlError` if the VAO or a buffer cannot be created or if called from a
+    /// thread other than the context thread.
+    pub fn from_streams<I: Index>(
+        streams: &VertexStreams,
+        indices: &[I],
+        compression: VertexCompression,
+    ) -> Result<Self, Errors> {
+        let encoded = streams.encode(compression)?;
+        let mut vao = Vao::new()?;
+        vao.bind();
+        let vertex_buffer = BufferObject::new(gl::ARRAY_BUFFER, gl::STATIC_DRAW)?;
+        vertex_buffer.bind();
+        vertex_buffer.allocate(encoded.data.len());
+        vertex_buffer.update_data(0, &encoded.data);
+        encoded.setup_attributes();
+        let index_buffer = Ebo::new()?;
+        index_buffer.bind();
+        let index_type = index_buffer.store_indices(indices);
+        vao.unbind();
+
+        vao.set_index_count(indices.len());
+        vao.set_index_type(index_type);
+        Ok(Self::from_parts(
+            vao,
+            Some(vertex_buffer),
+            Some(index_buffer),
+        ))
+    }
+
+    /// Wraps a VAO whose buffers are owned elsewhere.
+    ///
+    /// The index count of the VAO must be set before the mesh is drawn.
+    pub fn from_vao(vao: Vao) -> Self {
+        Self::from_parts(vao, None, None)
+    }
+
+    /// Wraps a VAO together with the buffers it reads, so they are deleted with the mesh.
+    ///
+    /// The index count of the VAO must be set before the mesh is drawn.
+    pub fn from_parts(
+        vao: Vao,
+        vertex_buffer: Option<BufferObject>,
+        index_buffer: Option<Ebo>,
+    ) -> Self {
+        Self {
+            vao,
+            vertex_buffer,
+            index_buffer,
+            instance_buffer: OnceCell::new(),
+            instance_count: Cell::new(0),
+        }
+    }
+
+    /// Attach debug labels to the VAO and buffers, shown by tools like RenderDoc and Nsight.
+    ///
+    /// The buffers are labelled `"<label>_vbo"` and `"<label>_ebo"`. Does nothing if
+    /// `GL_KHR_debug` is not available.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glObjectLabel`.
+    pub fn set_debug_label(&self, label: &str) {
+        self.vao.set_debug_label(label);
+        if let Some(vertex_buffer) = &self.vertex_buffer {
+            vertex_buffer.set_debug_label(&format!("{}_vbo", label));
+        }
+        if let Some(index_buffer) = &self.index_buffer {
+            index_buffer.set_debug_label(&format!("{}_ebo", label));
+        }
+    }
+
+    /// Returns the VAO of the mesh.
+    pub fn vao(&self) -> &Vao {
+        &self.vao
+    }
+
+    /// Returns the VAO of the mesh mutably, e.g. to change the primitive type or line width.
+    pub fn vao_mut(&mut self) -> &mut Vao {
+        &mut self.vao
+    }
+
+    /// Returns the buffer holding the vertices, if the mesh owns it.
+    pub fn vertex_buffer(&self) -> Option<&BufferObject> {
+        self.vertex_buffer.as_ref()
+    }
+
+    /// Returns the buffer holding the indices, if the mesh owns it.
+    pub fn index_buffer(&self) -> Option<&Ebo> {
+        self.index_buffer.as_ref()
+    }
+
+    /// Returns the number of indices drawn.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index count of the VAO has not been set.
+    pub fn index_count(&self) -> usize {
+        self.vao.index_count()
+    }
+
+    /// Overwrites vertices in the vertex buffer, starting at the vertex with the given index.
+    ///
+    /// Does nothing if the mesh does not own its vertex buffer. The vertices must fit into
+    /// the buffer and use the layout the mesh was created with.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glBufferSubData`.
+    pub fn update_vertices<V: Vertex>(&self, first_vertex: usize, vertices: &[V]) {
+        if let Some(vertex_buffer) = &self.vertex_buffer {
+            vertex_buffer.bind();
+            vertex_buffer.update_data(first_vertex * std::mem::size_of::<V>(), vertices);
+            vertex_buffer.unbind();
+        }
+    }
+
+    /// Uploads per-instance data, replacing the previous instances.
+    ///
+    /// The first call creates a `gl::DYNAMIC_DRAW` buffer and attaches it to the VAO with
+    /// `Vao::attach_instance_buffer`, so every later call must pass the same instance type. The
+    /// buffer grows as needed and is orphaned before being rewritten, so the instances can be
+    /// replaced every frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `instances` - One element per instance, whose attribute locations must not overlap
+    ///   those of the vertices.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::OpenGlError` if the buffer cannot be created or if called from a
+    /// thread other than the context thread.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glBufferData` and `glBufferSubData`.
+    pub fn set_instances<I: Vertex>(&self, instances: &[I]) -> Result<(), Errors> {
+        let instance_buffer = match self.instance_buffer.get() {
+            Some(instance_buffer) => instance_buffer,
+            None => {
+                let instance_buffer = BufferObject::new(gl::ARRAY_BUFFER, gl::DYNAMIC_DRAW)?;
+                self.vao.attach_instance_buffer::<I>(&instance_buffer);
+                self.instance_buffer.get_or_init(|| instance_buffer)
+            }
+        };
+        let size = std::mem::size_of_val(instances);
+        instance_buffer.bind();
+        if size > instance_buffer.size() {
+            instance_buffer.allocate(size);
+        } else {
+            instance_buffer.orphan();
+        }
+        instance_buffer.update_data(0, instances);
+        instance_buffer.unbind();
+        self.instance_count.set(instances.len());
+        Ok(())
+    }
+
+    /// Returns the number of instances uploaded by the last `set_instances` call.
+    pub fn instance_count(&self) -> usize {
+        self.instance_count.get()
+    }
+
+    /// Returns the buffer holding the per-instance data, if `set_instances` has been called.
+    pub fn instance_buffer(&self) -> Option<&BufferObject> {
+        self.instance_buffer.get()
+    }
+
+    /// Binds the mesh and draws it with its primitive and index types.
+    ///
+    /// For line and point primitives the VAO's line width or point size is applied first, and
+    /// for patches the number of patch vertices. The mesh stays bound afterwards.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glBindVertexArray` and `glDrawElements`.
+    pub fn draw(&self) {
+        self.draw_instanced(1);
+    }
+
+    /// Binds the mesh and draws it `instances` times, e.g. with per-instance attributes.
+    ///
+    /// `instances` must not exceed `instance_count` if the shader reads instance attributes.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glBindVertexArray` and `glDrawElementsInstanced`.
+    pub fn draw_instanced(&self, instances: u32) {
+        self.vao.bind();
+        let primitive_type = self.vao.primitive_type();
+        primitive_type.apply(self.vao.line_width(), self.vao.point_size());
+        unsafe {
+            if instances == 1 {
+                gl::DrawElements(
+                    primitive_type.gl_mode(),
+                    self.vao.index_count() as i32,
+                    self.vao.index_type().gl_type(),
+                    std::ptr::null(),
+                );
+            } else {
+                gl::DrawElementsInstanced(
+                    primitive_type.gl_mode(),
+                    self.vao.index_count() as i32,
+                    self.vao.index_type().gl_type(),
+                    std::ptr::null(),
+                    instances as i32,
+                );
+            }
+        }
+        stats::record_draw(primitive_type, self.vao.index_count(), instances as usize);
+    }
+}
+
+impl From<Vao> for Mesh {
+    fn from(vao: Vao) -> Self {
+        Self::from_vao(vao)
+    }
+}
+
+impl From<Vao> for Rc<Mesh> {
+    fn from(vao: Vao) -> Self {
+        Rc::new(Mesh::from_vao(vao))
+    }
+}
@@ -5,26 +5,76 @@
 //!
 //! ## Submodules
 //! - **window**: Window creation and management.
+//! - **web**: The WebGL2 browser backend (`web` feature).
 //! - **texture**: Utilities for loading and managing textures.
+//! - **cubemap**: Cubemap textures loaded from six faces, a cross or a panorama.
+//! - **texture_manager**: A cache of textures keyed by file path.
+//! - **shader_manager**: A cache of shader programs shared between objects.
+//! - **texture_unit**: Typed texture units and automatic unit assignment.
+//! - **renderer**: Render state shared between draw calls, such as scissor clipping.
+//! - **render_state**: Typed depth, blend and cull configuration.
+//! - **alpha_mode**: Opaque, cutout and blended materials and their render queues.
+//! - **viewport**: Fixed virtual resolution with letterboxing.
+//! - **render_scale**: Rendering at a scaled internal resolution.
+//! - **render_graph**: Pass scheduling and transient render target allocation.
+//! - **command_list**: Draw commands recorded on worker threads and replayed on the GL thread.
+//! - **motion_blur**: Camera and per-object motion blur with a velocity buffer.
+//! - **depth_of_field**: Depth-of-field blur with focus distance and aperture controls.
+//! - **edge_outline**: Screen-space outlines from depth and normal edges.
+//! - **mesh**: Meshes owning their VAO, vertex buffer and index buffer.
+//! - **standard_vertex**: The standard vertex layout with normals, UVs and vertex colors.
+//! - **vertex_compression**: Half-float and packed 10-10-10-2 vertex attribute formats.
+//! - **nine_slice**: Nine-slice meshes for scalable UI panels.
+//! - **tilemap**: Tile maps with Tiled import and chunked rendering.
+//! - **text**: Bitmap font text rendering with fallback fonts.
 //! - **gl_wrapper**: A wrapper for OpenGL functions.
+//! - **gl_info**: Queries for the OpenGL version, extensions and limits.
+//! - **stats**: GPU memory usage and per-frame draw call, triangle and state change counts.
 //!
 //! ## Example
 //! ```rust
-//! use glwfr::graphics::{window::Window, gl_wrapper::*};
+//! use glwfr::graphics::{window::Window, gl_wrapper::*, render_state::{ClearState, RenderState}};
 //!
 //! fn main() -> Result<(), glwfr::custom_errors::Errors> {
 //!     let mut window = Window::new(800, 600, "My Window")?;
 //!     window.init_gl()?;
-//!     window.enable_depth_test();
+//!     RenderState::default().apply();
 //!
 //!     while !window.should_close() {
-//!         window.clear(0.0, 0.0, 0.0, 1.0);
+//!         window.clear_with(&ClearState::default());
 //!         window.update();
 //!     }
 //!     Ok(())
 //! }
 //! ```
 
+pub mod alpha_mode;
+pub mod command_list;
+pub mod cubemap;
+pub mod depth_of_field;
+pub mod edge_outline;
+pub mod gl_info;
 pub mod gl_wrapper;
+pub mod mesh;
+pub mod motion_blur;
+pub mod nine_slice;
+pub mod render_graph;
+pub mod render_scale;
+pub mod render_state;
+pub mod renderer;
+pub mod shader_manager;
+pub mod standard_vertex;
+pub mod stats;
+pub mod text;
 pub mod texture;
+pub mod texture_manager;
+pub mod texture_unit;
+pub mod tilemap;
+pub mod vertex_compression;
+pub mod viewport;
+#[cfg(feature = "web")]
+pub mod web;
+#[cfg(feature = "native")]
 pub mod window;
+#[cfg(all(feature = "web", not(feature = "native")))]
+pub use web::window;
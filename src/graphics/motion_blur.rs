@@ -0,0 +1,284 @@
+//! # Motion Blur Module
+//!
+//! This module provides camera and per-object motion blur. During the main pass, shaders
+//! write the screen-space velocity of every fragment into a second color attachment,
+//! computed from the current and previous frame's model-view-projection matrices. A post
+//! pass then blurs the image along those velocities.
+//!
+//! Vertex shaders paste `VELOCITY_VERTEX_GLSL` and call `writeVelocity(localPos)` after
+//! setting `gl_Position`. Fragment shaders paste `VELOCITY_FRAGMENT_GLSL` and call
+//! `writeVelocityOutput()`. `Object::render` and `Scene::render` upload the previous-frame
+//! matrices.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::graphics::motion_blur::MotionBlur;
+//!
+//! fn main() -> Result<(), glwfr::custom_errors::Errors> {
+//!     let mut motion_blur = MotionBlur::new(800, 600)?;
+//!     motion_blur.shutter_strength = 0.5;
+//!
+//!     motion_blur.begin();
+//!     // scene.render();
+//!     motion_blur.end();
+//!
+//!     Ok(())
+//! }
+//! ```
+
+use crate::custom_errors::Errors;
+use crate::graphics::gl_wrapper::{Framebuffer, PrimitiveType, Renderbuffer, ShaderProgram, Vao};
+use crate::graphics::stats;
+use crate::graphics::texture::{Texture, TextureFormat, WrapMode};
+use gl::types::*;
+
+/// The color attachment the velocity buffer is bound to during the main pass.
+pub const VELOCITY_ATTACHMENT: u32 = 1;
+
+/// GLSL helpers for the vertex shader of the main pass.
+///
+/// Declares the `previousModel` and `previousViewProjection` uniforms and a
+/// `writeVelocity(localPos)` function that passes the current and previous clip positions
+/// to the fragment shader. Call it after `gl_Position` has been written.
+pub const VELOCITY_VERTEX_GLSL: &str = r#"
+uniform mat4 previousModel;
+uniform mat4 previousViewProjection;
+
+out vec4 velocityCurrentClip;
+out vec4 velocityPreviousClip;
+
+void writeVelocity(vec3 localPos) {
+    velocityCurrentClip = gl_Position;
+    velocityPreviousClip = previousViewProjection * previousModel * vec4(localPos, 1.0);
+}
+"#;
+
+/// GLSL helpers for the fragment shader of the main pass.
+///
+/// Declares the velocity output at `VELOCITY_ATTACHMENT` and a `writeVelocityOutput()`
+/// function storing the screen-space motion of the fragment since the previous frame, in
+/// texture coordinates.
+pub const VELOCITY_FRAGMENT_GLSL: &str = r#"
+in vec4 velocityCurrentClip;
+in vec4 velocityPreviousClip;
+
+layout(location = 1) out vec2 fragVelocity;
+
+void writeVelocityOutput() {
+    vec2 current = velocityCurrentClip.xy / velocityCurrentClip.w;
+    vec2 previous = velocityPreviousClip.xy / velocityPreviousClip.w;
+    fragVelocity = (current - previous) * 0.5;
+}
+"#;
+
+const BLUR_VERTEX_SHADER: &str = r#"#version 330 core
+out vec2 uv;
+
+void main() {
+    // A single triangle covering the screen
+    vec2 position = vec2((gl_VertexID << 1) & 2, gl_VertexID & 2);
+    uv = position;
+    gl_Position = vec4(position * 2.0 - 1.0, 0.0, 1.0);
+}
+"#;
+
+const BLUR_FRAGMENT_SHADER: &str = r#"#version 330 core
+in vec2 uv;
+out vec4 fragColor;
+
+uniform sampler2D colorTexture;
+uniform sampler2D velocityTexture;
+uniform float shutterStrength;
+uniform float maxBlur;
+uniform int sampleCount;
+
+void main() {
+    vec2 velocity = texture(velocityTexture, uv).xy * shutterStrength;
+    float blurLength = length(velocity);
+    if (blurLength > maxBlur) {
+        velocity *= maxBlur / blurLength;
+    }
+
+    int samples = max(sampleCount, 1);
+    if (samples == 1) {
+        fragColor = texture(colorTexture, uv);
+        return;
+    }
+    vec4 color = vec4(0.0);
+    for (int i = 0; i < samples; i++) {
+        // Spread the samples evenly over the motion of the frame, from -0.5 to 0.5 around
+        // the pixel
+        float offset = float(i) / float(samples - 1) - 0.5;
+        color += texture(colorTexture, uv - velocity * offset);
+    }
+    fragColor = color / float(samples);
+}
+"#;
+
+/// Renders the main pass into a color and velocity target and blurs it onto the window.
+pub struct MotionBlur {
+    framebuffer: Framebuffer,
+    color: Texture,
+    velocity: Texture,
+    depth: Renderbuffer,
+    shader_program: ShaderProgram,
+    vao: Vao,
+    width: u32,
+    height: u32,
+    /// The fraction of the frame the virtual shutter stays open. `0.0` disables the blur,
+    /// `1.0` blurs along the full motion since the previous frame.
+    pub shutter_strength: f32,
+    /// The number of color samples taken along the velocity of each pixel.
+    pub samples: u32,
+    /// The longest blur, as a fraction of the screen size.
+    pub max_blur: f32,
+}
+
+impl MotionBlur {
+    /// Creates a motion blur target of the given size.
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - The width of the window framebuffer in pixels.
+    /// * `height` - The height of the window framebuffer in pixels.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::OpenGlError` if the target cannot be created or is incomplete,
+    /// or an `Errors::ShaderCompilationError` if the blur shader fails to compile.
+    pub fn new(width: u32, height: u32) -> Result<Self, Errors> {
+        let color = Texture::new();
+        let velocity = Texture::new();
+        for texture in [&color, &velocity] {
+            texture.set_generate_mipmaps(false);
+            texture.set_parameteri(gl::TEXTURE_MIN_FILTER, gl::LINEAR as GLint);
+            texture.set_parameteri(gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
+            texture.set_wrap(WrapMode::ClampToEdge);
+        }
+        let depth = Renderbuffer::new(TextureFormat::Depth24Stencil8, width, height)?;
+        let shader_program = ShaderProgram::from_source(BLUR_VERTEX_SHADER, BLUR_FRAGMENT_SHADER)?;
+
+        let mut motion_blur = Self {
+            framebuffer: Framebuffer::new()?,
+            color,
+            velocity,
+            depth,
+            shader_program,
+            vao: Vao::new()?,
+            width: 0,
+            height: 0,
+            shutter_strength: 0.5,
+            samples: 8,
+            max_blur: 0.05,
+        };
+        motion_blur.resize(width, height)?;
+        Ok(motion_blur)
+    }
+
+    /// Updates the size of the target, for example after the window was resized.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::OpenGlError` if the resized target is incomplete.
+    pub fn resize(&mut self, width: u32, height: u32) -> Result<(), Errors> {
+        self.width = width.max(1);
+        self.height = height.max(1);
+        self.color
+            .allocate(self.width, self.height, TextureFormat::Rgba16F);
+        self.velocity
+            .allocate(self.width, self.height, TextureFormat::Rg16F);
+        self.depth.resize(self.width, self.height);
+
+        self.framebuffer.bind();
+        unsafe {
+            gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0,
+                gl::TEXTURE_2D,
+                self.color.id(),
+                0,
+            );
+            gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0 + VELOCITY_ATTACHMENT,
+                gl::TEXTURE_2D,
+                self.velocity.id(),
+                0,
+            );
+        }
+        self.framebuffer
+            .attach_renderbuffer(gl::DEPTH_STENCIL_ATTACHMENT, &self.depth);
+        let status = self.framebuffer.check_status();
+        self.framebuffer.unbind();
+        status
+    }
+
+    /// Returns the size of the target as `(width, height)`.
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Returns the velocity buffer written during the main pass.
+    pub fn velocity_texture(&self) -> &Texture {
+        &self.velocity
+    }
+
+    /// Start rendering the main pass into the color and velocity target.
+    ///
+    /// Clears the color and depth buffers, and the velocity buffer to zero so the background
+    /// stays sharp.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glBindFramebuffer`, `glDrawBuffers`, `glViewport`
+    /// and `glClearBufferfv`.
+    pub fn begin(&self) {
+        self.framebuffer.bind();
+        let draw_buffers = [
+            gl::COLOR_ATTACHMENT0,
+            gl::COLOR_ATTACHMENT0 + VELOCITY_ATTACHMENT,
+        ];
+        let zero = [0.0f32; 4];
+        unsafe {
+            gl::DrawBuffers(draw_buffers.len() as GLsizei, draw_buffers.as_ptr());
+            gl::Viewport(0, 0, self.width as GLsizei, self.height as GLsizei);
+            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT | gl::STENCIL_BUFFER_BIT);
+            gl::ClearBufferfv(gl::COLOR, VELOCITY_ATTACHMENT as GLint, zero.as_ptr());
+        }
+    }
+
+    /// Blur the main pass onto the window and make the window the active framebuffer again.
+    ///
+    /// Depth testing is disabled while the blur is drawn and enabled again afterwards.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glBindFramebuffer(GL_FRAMEBUFFER, 0)` and
+    /// `glDrawArrays` with a screen-covering triangle.
+    pub fn end(&self) {
+        self.framebuffer.unbind();
+        unsafe {
+            gl::Viewport(0, 0, self.width as GLsizei, self.height as GLsizei);
+            gl::Disable(gl::DEPTH_TEST);
+        }
+        self.shader_program.bind();
+        self.color.bind(gl::TEXTURE0);
+        self.velocity.bind(gl::TEXTURE1);
+        self.shader_program.try_set_uniform("colorTexture", 0);
+        self.shader_program.try_set_uniform("velocityTexture", 1);
+        self.shader_program
+            .try_set_uniform("shutterStrength", self.shutter_strength.max(0.0));
+        self.shader_program
+            .try_set_uniform("maxBlur", self.max_blur.max(0.0));
+        self.shader_program
+            .try_set_uniform("sampleCount", self.samples.max(1) as i32);
+        self.vao.bind();
+        unsafe {
+            gl::DrawArrays(gl::TRIANGLES, 0, 3);
+            gl::Enable(gl::DEPTH_TEST);
+        }
+        stats::record_draw(PrimitiveType::Triangles, 3, 1);
+        self.vao.unbind();
+    }
+}
@@ -0,0 +1,273 @@
+//! # Nine Slice Module
+//!
+//! This module provides nine-slice (nine-patch) meshes for scalable UI panels and buttons. The
+//! region of a texture atlas is split into a 3x3 grid by its borders; the corners keep their
+//! size, the edges stretch along one axis and the center stretches along both.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::graphics::nine_slice::{NineSlice, NineSliceMesh, SliceBorders};
+//! use glwfr::graphics::renderer::Rect;
+//!
+//! fn main() -> Result<(), glwfr::custom_errors::Errors> {
+//!     // A 48x48 panel frame at (64, 0) in a 256x256 atlas with 16 pixel borders
+//!     let panel = NineSlice::new((256, 256), Rect::new(64, 0, 48, 48), SliceBorders::uniform(16.0));
+//!
+//!     // A 300x120 panel with its top-left corner at (20, 20)
+//!     let mut mesh = NineSliceMesh::new(&panel, 20.0, 20.0, 300.0, 120.0)?;
+//!     mesh.draw();
+//!
+//!     // After the window was resized
+//!     mesh.set_rect(&panel, 20.0, 20.0, 400.0, 120.0);
+//!     Ok(())
+//! }
+//! ```
+
+use crate::custom_errors::Errors;
+use crate::graphics::gl_wrapper::{
+    BufferObject, Ebo, PrimitiveType, Vao, Vertex, VertexAttributeLayout,
+};
+use crate::graphics::renderer::Rect;
+use crate::graphics::stats;
+use gl::types::*;
+
+/// The attribute location of the position of a `SliceVertex`.
+pub const SLICE_POSITION_ATTRIBUTE: u32 = 0;
+
+/// The attribute location of the texture coordinates of a `SliceVertex`.
+pub const SLICE_UV_ATTRIBUTE: u32 = 2;
+
+/// The size of the borders of a nine-slice region, in texture pixels.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SliceBorders {
+    pub left: f32,
+    pub right: f32,
+    pub top: f32,
+    pub bottom: f32,
+}
+
+impl SliceBorders {
+    /// Creates borders with the same size on all four sides.
+    pub fn uniform(size: f32) -> Self {
+        Self {
+            left: size,
+            right: size,
+            top: size,
+            bottom: size,
+        }
+    }
+}
+
+/// A vertex of a nine-slice mesh with a 2D position and texture coordinates.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[repr(C)]
+pub struct SliceVertex {
+    pub position: [f32; 2],
+    pub uv: [f32; 2],
+}
+
+impl Vertex for SliceVertex {
+    fn layout() -> Vec<VertexAttributeLayout> {
+        vec![
+            VertexAttributeLayout {
+                index: SLICE_POSITION_ATTRIBUTE,
+                size: 2,
+                r#type: gl::FLOAT,
+                normalized: gl::FALSE,
+                offset: 0,
+            },
+            VertexAttributeLayout {
+                index: SLICE_UV_ATTRIBUTE,
+                size: 2,
+                r#type: gl::FLOAT,
+                normalized: gl::FALSE,
+                offset: std::mem::size_of::<[f32; 2]>(),
+            },
+        ]
+    }
+}
+
+/// The atlas metadata of a nine-slice region.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NineSlice {
+    /// The size of the atlas texture in pixels.
+    pub texture_size: (u32, u32),
+    /// The region of the atlas in pixels, with the origin in the top-left corner.
+    pub region: Rect,
+    /// The borders of the region that are not stretched.
+    pub borders: SliceBorders,
+}
+
+impl NineSlice {
+    /// Creates a nine-slice region of an atlas texture.
+    ///
+    /// # Arguments
+    ///
+    /// * `texture_size` - The size of the atlas texture in pixels.
+    /// * `region` - The region of the atlas in pixels, with the origin in the top-left corner.
+    /// * `borders` - The borders of the region that are not stretched.
+    pub fn new(texture_size: (u32, u32), region: Rect, borders: SliceBorders) -> Self {
+        Self {
+            texture_size,
+            region,
+            borders,
+        }
+    }
+
+    /// Builds the 16 vertices and 54 indices of a nine-slice quad.
+    ///
+    /// Positions use the same top-left origin as the atlas region, with `y` growing
+    /// downwards. One texture pixel of border maps to one unit; if the rectangle is smaller
+    /// than the borders, they are scaled down to fit.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - The left edge of the rectangle.
+    /// * `y` - The top edge of the rectangle.
+    /// * `width` - The width of the rectangle.
+    /// * `height` - The height of the rectangle.
+    pub fn vertices(
+        &self,
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+    ) -> (Vec<SliceVertex>, Vec<u32>) {
+        let b = self.borders;
+        let horizontal = fit_scale(b.left + b.right, width);
+        let vertical = fit_scale(b.top + b.bottom, height);
+
+        let xs = [
+            x,
+            x + b.left * horizontal,
+            x + width - b.right * horizontal,
+            x + width,
+        ];
+        let ys = [
+            y,
+            y + b.top * vertical,
+            y + height - b.bottom * vertical,
+            y + height,
+        ];
+
+        let (texture_width, texture_height) = (
+            self.texture_size.0.max(1) as f32,
+            self.texture_size.1.max(1) as f32,
+        );
+        let left = self.region.x as f32;
+        let top = self.region.y as f32;
+        let right = left + self.region.width as f32;
+        let bottom = top + self.region.height as f32;
+        let us = [left, left + b.left, right - b.right, right].map(|u| u / texture_width);
+        let vs = [top, top + b.top, bottom - b.bottom, bottom].map(|v| v / texture_height);
+
+        let mut vertices = Vec::with_capacity(16);
+        for row in 0..4 {
+            for column in 0..4 {
+                vertices.push(SliceVertex {
+                    position: [xs[column], ys[row]],
+                    uv: [us[column], vs[row]],
+                });
+            }
+        }
+
+        let mut indices = Vec::with_capacity(54);
+        for row in 0..3 {
+            for column in 0..3 {
+                let top_left = row * 4 + column;
+                let bottom_left = top_left + 4;
+                indices.extend_from_slice(&[
+                    top_left,
+                    bottom_left,
+                    top_left + 1,
+                    top_left + 1,
+                    bottom_left,
+                    bottom_left + 1,
+                ]);
+            }
+        }
+        (vertices, indices)
+    }
+}
+
+/// Returns the factor that shrinks `border` so it fits into `size`.
+fn fit_scale(border: f32, size: f32) -> f32 {
+    if border > size.abs() && border > 0.0 {
+        size.abs() / border
+    } else {
+        1.0
+    }
+}
+
+/// A nine-slice quad uploaded to the GPU.
+pub struct NineSliceMesh {
+    vao: Vao,
+    vbo: BufferObject,
+    _ebo: Ebo,
+}
+
+impl NineSliceMesh {
+    /// Creates the mesh of a nine-slice quad.
+    ///
+    /// See `NineSlice::vertices` for the arguments.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::OpenGlError` if the buffers cannot be created.
+    pub fn new(slice: &NineSlice, x: f32, y: f32, width: f32, height: f32) -> Result<Self, Errors> {
+        let (vertices, indices) = slice.vertices(x, y, width, height);
+
+        let mut vao = Vao::new()?;
+        vao.bind();
+        let vbo = BufferObject::new(gl::ARRAY_BUFFER, gl::DYNAMIC_DRAW)?;
+        vbo.bind();
+        vbo.allocate(std::mem::size_of_val(vertices.as_slice()));
+        vbo.update_data(0, &vertices);
+        let ebo = Ebo::new()?;
+        ebo.bind();
+        let index_type = ebo.store_indices(&indices);
+        SliceVertex::setup_attributes();
+        vao.unbind();
+
+        vao.set_index_count(indices.len());
+        vao.set_index_type(index_type);
+        Ok(Self {
+            vao,
+            vbo,
+            _ebo: ebo,
+        })
+    }
+
+    /// Moves or resizes the quad, or switches it to another region with the same borders.
+    ///
+    /// See `NineSlice::vertices` for the arguments.
+    pub fn set_rect(&mut self, slice: &NineSlice, x: f32, y: f32, width: f32, height: f32) {
+        let (vertices, _) = slice.vertices(x, y, width, height);
+        self.vbo.bind();
+        self.vbo.update_data(0, &vertices);
+    }
+
+    /// Returns the vertex array object of the quad, e.g. to wrap it in an `Object`.
+    pub fn vao(&self) -> &Vao {
+        &self.vao
+    }
+
+    /// Draws the quad with the currently bound shader program and texture.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glDrawElements`.
+    pub fn draw(&self) {
+        self.vao.bind();
+        unsafe {
+            gl::DrawElements(
+                gl::TRIANGLES,
+                self.vao.index_count() as GLsizei,
+                self.vao.index_type().gl_type(),
+                std::ptr::null(),
+            );
+        }
+        stats::record_draw(PrimitiveType::Triangles, self.vao.index_count(), 1);
+    }
+}
@@ -0,0 +1,721 @@
+//! # Render Graph Module
+//!
+//! This module provides a render graph. Instead of wiring framebuffers by hand, every pass
+//! declares the textures it reads and writes. The graph then orders the passes by their
+//! dependencies, skips passes whose results are never used, allocates short-lived render
+//! targets from a pool, reusing their memory once no later pass needs them, and binds and
+//! clears each pass's framebuffer before running it.
+//!
+//! Passes reading a texture may be added before the passes writing it. Passes writing the
+//! same texture, e.g. an opaque and a transparent pass drawing into one color target, run in
+//! the order they were added, and a pass overwriting a texture runs after the earlier
+//! passes reading it.
+//!
+//! The graph is rebuilt every frame; the `RenderTargetPool` it allocates from is kept
+//! between frames.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::graphics::render_graph::{RenderGraph, RenderTargetDesc, RenderTargetPool};
+//! use glwfr::graphics::texture::TextureFormat;
+//!
+//! fn render_frame(pool: &mut RenderTargetPool) -> Result<(), glwfr::custom_errors::Errors> {
+//!     let mut graph = RenderGraph::new();
+//!     let color = graph.create_texture("hdr_color", RenderTargetDesc::full(TextureFormat::Rgba16F));
+//!     let depth = graph.create_texture("depth", RenderTargetDesc::full(TextureFormat::Depth24));
+//!     let backbuffer = graph.backbuffer();
+//!
+//!     // Readers can be added before the writers they depend on
+//!     graph.add_pass(
+//!         "tonemap",
+//!         |pass| {
+//!             pass.read(color).write(backbuffer);
+//!         },
+//!         |context| {
+//!             context.texture(color).bind(gl::TEXTURE0);
+//!             // draw a fullscreen triangle
+//!         },
+//!     );
+//!     graph.add_pass(
+//!         "opaque",
+//!         |pass| {
+//!             pass.write(color)
+//!                 .write(depth)
+//!                 .clear_color([0.0, 0.0, 0.0, 1.0])
+//!                 .clear_depth(1.0);
+//!         },
+//!         |_| {
+//!             // scene.render();
+//!         },
+//!     );
+//!
+//!     graph.execute(pool)
+//! }
+//! ```
+
+use crate::custom_errors::Errors;
+use crate::graphics::gl_wrapper::Framebuffer;
+use crate::graphics::render_state::ClearState;
+use crate::graphics::texture::{Texture, TextureFormat, WrapMode};
+use gl::types::*;
+use std::collections::HashMap;
+
+/// A handle to a texture declared in a `RenderGraph`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ResourceId(usize);
+
+/// The size of a render target.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TargetSize {
+    /// The size of the window, as set on the `RenderTargetPool`.
+    Full,
+    /// The size of the window multiplied by a factor, e.g. `0.5` for half resolution.
+    Scaled(f32),
+    /// A fixed size in pixels, e.g. for shadow maps.
+    Fixed(u32, u32),
+}
+
+impl TargetSize {
+    /// Returns the size in pixels for a window of the given size.
+    pub fn resolve(&self, width: u32, height: u32) -> (u32, u32) {
+        match *self {
+            TargetSize::Full => (width.max(1), height.max(1)),
+            TargetSize::Scaled(scale) => (
+                ((width as f32 * scale).round() as u32).max(1),
+                ((height as f32 * scale).round() as u32).max(1),
+            ),
+            TargetSize::Fixed(width, height) => (width.max(1), height.max(1)),
+        }
+    }
+}
+
+/// The size and format of a render target created by the graph.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RenderTargetDesc {
+    /// The size of the target.
+    pub size: TargetSize,
+    /// The storage format of the target.
+    pub format: TextureFormat,
+}
+
+impl RenderTargetDesc {
+    /// Describes a target with the size of the window.
+    pub fn full(format: TextureFormat) -> Self {
+        Self {
+            size: TargetSize::Full,
+            format,
+        }
+    }
+
+    /// Describes a target with the size of the window multiplied by `scale`.
+    pub fn scaled(scale: f32, format: TextureFormat) -> Self {
+        Self {
+            size: TargetSize::Scaled(scale),
+            format,
+        }
+    }
+
+    /// Describes a target with a fixed size in pixels.
+    pub fn fixed(width: u32, height: u32, format: TextureFormat) -> Self {
+        Self {
+            size: TargetSize::Fixed(width, height),
+            format,
+        }
+    }
+}
+
+/// Where the contents of a graph resource live.
+enum ResourceKind<'a> {
+    /// A render target allocated from the pool for the duration of the frame.
+    Transient(RenderTargetDesc),
+    /// A texture owned outside of the graph, e.g. a shadow map kept between frames.
+    Imported(&'a Texture),
+    /// The default framebuffer of the window.
+    Backbuffer,
+}
+
+struct Resource<'a> {
+    name: String,
+    kind: ResourceKind<'a>,
+}
+
+/// The declaration of the textures a pass reads and writes.
+#[derive(Debug, Clone)]
+pub struct PassBuilder {
+    reads: Vec<ResourceId>,
+    writes: Vec<ResourceId>,
+    clear: ClearState,
+    side_effects: bool,
+}
+
+impl Default for PassBuilder {
+    fn default() -> Self {
+        Self {
+            reads: Vec::new(),
+            writes: Vec::new(),
+            clear: ClearState::LOAD,
+            side_effects: false,
+        }
+    }
+}
+
+impl PassBuilder {
+    /// Declares a texture the pass samples. The pass runs after every pass writing it.
+    pub fn read(&mut self, resource: ResourceId) -> &mut Self {
+        if !self.reads.contains(&resource) {
+            self.reads.push(resource);
+        }
+        self
+    }
+
+    /// Declares a texture the pass renders into. Color targets are bound as draw buffers in
+    /// the order they are declared.
+    pub fn write(&mut self, resource: ResourceId) -> &mut Self {
+        if !self.writes.contains(&resource) {
+            self.writes.push(resource);
+        }
+        self
+    }
+
+    /// Clears the color targets of the pass before it runs.
+    pub fn clear_color(&mut self, color: [f32; 4]) -> &mut Self {
+        self.clear.color = Some(color);
+        self
+    }
+
+    /// Clears the depth target of the pass before it runs.
+    pub fn clear_depth(&mut self, depth: f32) -> &mut Self {
+        self.clear.depth = Some(depth);
+        self
+    }
+
+    /// Clears the stencil buffer of the pass before it runs.
+    pub fn clear_stencil(&mut self, value: i32) -> &mut Self {
+        self.clear.stencil = Some(value);
+        self
+    }
+
+    /// Replaces the clear configuration of the pass. Passes start with `ClearState::LOAD`,
+    /// keeping the contents their targets had after the previous pass writing them.
+    pub fn clear(&mut self, clear: ClearState) -> &mut Self {
+        self.clear = clear;
+        self
+    }
+
+    /// Keeps the pass even if nothing reads its results, e.g. for a pass reading back
+    /// pixels to the CPU.
+    pub fn has_side_effects(&mut self) -> &mut Self {
+        self.side_effects = true;
+        self
+    }
+}
+
+/// The textures available to a pass while it runs.
+pub struct PassContext<'p> {
+    textures: &'p HashMap<ResourceId, &'p Texture>,
+    width: u32,
+    height: u32,
+}
+
+impl PassContext<'_> {
+    /// Returns the texture of a resource declared by the graph.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the resource is the backbuffer or is not used by any running pass.
+    pub fn texture(&self, resource: ResourceId) -> &Texture {
+        self.textures
+            .get(&resource)
+            .expect("Resource has no texture in this frame")
+    }
+
+    /// Returns the size of the pass's render targets as `(width, height)`.
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+}
+
+type PassFn<'a> = Box<dyn FnMut(&PassContext) + 'a>;
+
+struct Pass<'a> {
+    name: String,
+    builder: PassBuilder,
+    execute: PassFn<'a>,
+}
+
+/// A texture in the pool, with the pass index after which it can be reused.
+struct PooledTarget {
+    width: u32,
+    height: u32,
+    format: TextureFormat,
+    texture: Texture,
+    free_after: Option<usize>,
+}
+
+/// Render targets and framebuffers kept between frames for `RenderGraph::execute`.
+pub struct RenderTargetPool {
+    width: u32,
+    height: u32,
+    targets: Vec<PooledTarget>,
+    framebuffers: HashMap<Vec<GLuint>, Framebuffer>,
+}
+
+impl RenderTargetPool {
+    /// Creates an empty pool for a window of the given framebuffer size.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            targets: Vec::new(),
+            framebuffers: HashMap::new(),
+        }
+    }
+
+    /// Updates the window size, for example after a resize. Targets of the old size are
+    /// released.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        if (width, height) != (self.width, self.height) {
+            self.width = width;
+            self.height = height;
+            self.clear();
+        }
+    }
+
+    /// Returns the window size as `(width, height)`.
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Returns the number of textures allocated by the pool.
+    pub fn target_count(&self) -> usize {
+        self.targets.len()
+    }
+
+    /// Returns the estimated GPU memory used by the pool's textures in bytes.
+    pub fn memory_size(&self) -> usize {
+        self.targets
+            .iter()
+            .map(|target| target.texture.memory_size())
+            .sum()
+    }
+
+    /// Releases all textures and framebuffers.
+    pub fn clear(&mut self) {
+        self.framebuffers.clear();
+        self.targets.clear();
+    }
+
+    /// Returns the index of a free target matching the description, allocating one if
+    /// needed, and reserves it until the pass `last_use`.
+    fn acquire(&mut self, desc: &RenderTargetDesc, first_use: usize, last_use: usize) -> usize {
+        let (width, height) = desc.size.resolve(self.width, self.height);
+        let free = self.targets.iter().position(|target| {
+            target.width == width
+                && target.height == height
+                && target.format == desc.format
+                && target
+                    .free_after
+                    .is_none_or(|free_after| free_after < first_use)
+        });
+        let index = free.unwrap_or_else(|| {
+            let texture = Texture::new();
+            texture.set_generate_mipmaps(false);
+            let filter = if desc.format.is_depth() {
+                gl::NEAREST
+            } else {
+                gl::LINEAR
+            };
+            texture.set_parameteri(gl::TEXTURE_MIN_FILTER, filter as GLint);
+            texture.set_parameteri(gl::TEXTURE_MAG_FILTER, filter as GLint);
+            texture.set_wrap(WrapMode::ClampToEdge);
+            texture.allocate(width, height, desc.format);
+            self.targets.push(PooledTarget {
+                width,
+                height,
+                format: desc.format,
+                texture,
+                free_after: None,
+            });
+            self.targets.len() - 1
+        });
+        self.targets[index].free_after = Some(last_use);
+        index
+    }
+
+    /// Returns a complete framebuffer with the given textures attached, creating it once.
+    ///
+    /// Takes the framebuffer cache instead of the pool, so the attachments can be borrowed
+    /// from the pool's targets.
+    fn framebuffer(
+        framebuffers: &mut HashMap<Vec<GLuint>, Framebuffer>,
+        attachments: &[&Texture],
+    ) -> Result<GLuint, Errors> {
+        let key: Vec<GLuint> = attachments.iter().map(|texture| texture.id()).collect();
+        if let Some(framebuffer) = framebuffers.get(&key) {
+            return Ok(framebuffer.id());
+        }
+
+        let framebuffer = Framebuffer::new()?;
+        framebuffer.bind();
+        let mut color_index = 0;
+        for texture in attachments {
+            let attachment = match texture.format() {
+                TextureFormat::Depth24Stencil8 => gl::DEPTH_STENCIL_ATTACHMENT,
+                format if format.is_depth() => gl::DEPTH_ATTACHMENT,
+                _ => {
+                    color_index += 1;
+                    gl::COLOR_ATTACHMENT0 + color_index - 1
+                }
+            };
+            unsafe {
+                gl::FramebufferTexture2D(
+                    gl::FRAMEBUFFER,
+                    attachment,
+                    gl::TEXTURE_2D,
+                    texture.id(),
+                    0,
+                );
+            }
+        }
+        framebuffer.check_status()?;
+        framebuffer.unbind();
+        let id = framebuffer.id();
+        framebuffers.insert(key, framebuffer);
+        Ok(id)
+    }
+}
+
+/// A frame's passes and the textures flowing between them.
+#[derive(Default)]
+pub struct RenderGraph<'a> {
+    resources: Vec<Resource<'a>>,
+    passes: Vec<Pass<'a>>,
+}
+
+impl<'a> RenderGraph<'a> {
+    /// Creates an empty graph.
+    pub fn new() -> Self {
+        Self {
+            resources: Vec::new(),
+            passes: Vec::new(),
+        }
+    }
+
+    /// Declares a render target that only lives during this frame. Its memory is taken from
+    /// the pool and may be shared with other targets that are not used at the same time.
+    pub fn create_texture(&mut self, name: &str, desc: RenderTargetDesc) -> ResourceId {
+        self.add_resource(name, ResourceKind::Transient(desc))
+    }
+
+    /// Declares a texture owned outside of the graph, e.g. a shadow map or a texture read
+    /// back in the next frame. Passes writing imported textures are never skipped.
+    pub fn import_texture(&mut self, name: &str, texture: &'a Texture) -> ResourceId {
+        self.add_resource(name, ResourceKind::Imported(texture))
+    }
+
+    /// Returns the resource of the window's default framebuffer. Passes writing it are
+    /// never skipped.
+    pub fn backbuffer(&mut self) -> ResourceId {
+        let existing = self
+            .resources
+            .iter()
+            .position(|resource| matches!(resource.kind, ResourceKind::Backbuffer));
+        match existing {
+            Some(index) => ResourceId(index),
+            None => self.add_resource("backbuffer", ResourceKind::Backbuffer),
+        }
+    }
+
+    /// Returns the name a resource was declared with.
+    pub fn resource_name(&self, resource: ResourceId) -> &str {
+        &self.resources[resource.0].name
+    }
+
+    /// Adds a pass to the graph.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the pass, used in error messages.
+    /// * `setup` - Declares the textures the pass reads and writes.
+    /// * `execute` - Issues the pass's draw calls. Its framebuffer is bound, cleared and
+    ///   sized when it is called.
+    pub fn add_pass(
+        &mut self,
+        name: &str,
+        setup: impl FnOnce(&mut PassBuilder),
+        execute: impl FnMut(&PassContext) + 'a,
+    ) {
+        let mut builder = PassBuilder::default();
+        setup(&mut builder);
+        self.passes.push(Pass {
+            name: name.to_string(),
+            builder,
+            execute: Box::new(execute),
+        });
+    }
+
+    /// Returns the names of the passes that would run, in execution order.
+    ///
+    /// # Errors
+    ///
+    /// See `execute`.
+    pub fn compile(&self) -> Result<Vec<&str>, Errors> {
+        Ok(self
+            .schedule()?
+            .into_iter()
+            .map(|index| self.passes[index].name.as_str())
+            .collect())
+    }
+
+    /// Orders the passes, allocates their render targets and runs them.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::RenderGraphError` if a pass reads and writes the same texture,
+    /// reads a texture no pass writes, mixes the backbuffer with other targets, or if the
+    /// passes depend on each other in a cycle. Returns an `Errors::OpenGlError` if a
+    /// framebuffer is incomplete.
+    pub fn execute(mut self, pool: &mut RenderTargetPool) -> Result<(), Errors> {
+        let order = self.schedule()?;
+
+        // Find the first and last pass using every transient target, then assign pool
+        // textures so targets with disjoint lifetimes share memory
+        let mut lifetimes: HashMap<ResourceId, (usize, usize)> = HashMap::new();
+        for (position, &index) in order.iter().enumerate() {
+            let builder = &self.passes[index].builder;
+            for &resource in builder.reads.iter().chain(&builder.writes) {
+                let lifetime = lifetimes.entry(resource).or_insert((position, position));
+                lifetime.1 = position;
+            }
+        }
+        let mut transients: Vec<(ResourceId, (usize, usize))> = lifetimes
+            .into_iter()
+            .filter(|(resource, _)| {
+                matches!(self.resources[resource.0].kind, ResourceKind::Transient(_))
+            })
+            .collect();
+        transients.sort_by_key(|&(resource, (first, _))| (first, resource));
+        for target in &mut pool.targets {
+            target.free_after = None;
+        }
+        let mut slots = HashMap::new();
+        for (resource, (first, last)) in transients {
+            if let ResourceKind::Transient(desc) = &self.resources[resource.0].kind {
+                slots.insert(resource, pool.acquire(desc, first, last));
+            }
+        }
+
+        // Create the framebuffers before borrowing the pool's textures for the passes
+        let mut framebuffers = Vec::with_capacity(order.len());
+        for &index in &order {
+            let writes = &self.passes[index].builder.writes;
+            let attachments: Vec<&Texture> = writes
+                .iter()
+                .filter_map(|resource| match &self.resources[resource.0].kind {
+                    ResourceKind::Transient(_) => Some(&pool.targets[slots[resource]].texture),
+                    ResourceKind::Imported(texture) => Some(*texture),
+                    ResourceKind::Backbuffer => None,
+                })
+                .collect();
+            let framebuffer = if attachments.is_empty() {
+                0
+            } else {
+                RenderTargetPool::framebuffer(&mut pool.framebuffers, &attachments)?
+            };
+            let size = attachments
+                .first()
+                .map_or((pool.width, pool.height), |texture| {
+                    (texture.width(), texture.height())
+                });
+            let color_count = attachments
+                .iter()
+                .filter(|texture| !texture.format().is_depth())
+                .count();
+            framebuffers.push((framebuffer, size, color_count));
+        }
+
+        let mut textures: HashMap<ResourceId, &Texture> = HashMap::new();
+        for (index, resource) in self.resources.iter().enumerate() {
+            let id = ResourceId(index);
+            match &resource.kind {
+                ResourceKind::Transient(_) => {
+                    if let Some(&slot) = slots.get(&id) {
+                        textures.insert(id, &pool.targets[slot].texture);
+                    }
+                }
+                ResourceKind::Imported(texture) => {
+                    textures.insert(id, *texture);
+                }
+                ResourceKind::Backbuffer => {}
+            }
+        }
+
+        let memory_barrier = gl::MemoryBarrier::is_loaded();
+        for (position, &index) in order.iter().enumerate() {
+            let (framebuffer, (width, height), color_count) = framebuffers[position];
+            let pass = &mut self.passes[index];
+            unsafe {
+                // Make writes of earlier passes visible to passes that sample them
+                if memory_barrier && position > 0 && !pass.builder.reads.is_empty() {
+                    gl::MemoryBarrier(gl::TEXTURE_FETCH_BARRIER_BIT | gl::FRAMEBUFFER_BARRIER_BIT);
+                }
+                gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer);
+                if framebuffer != 0 {
+                    let draw_buffers: Vec<GLenum> = (0..color_count as GLenum)
+                        .map(|attachment| gl::COLOR_ATTACHMENT0 + attachment)
+                        .collect();
+                    if draw_buffers.is_empty() {
+                        gl::DrawBuffer(gl::NONE);
+                    } else {
+                        gl::DrawBuffers(draw_buffers.len() as GLsizei, draw_buffers.as_ptr());
+                    }
+                }
+                gl::Viewport(0, 0, width as GLsizei, height as GLsizei);
+            }
+            pass.builder.clear.apply();
+            let context = PassContext {
+                textures: &textures,
+                width,
+                height,
+            };
+            (pass.execute)(&context);
+        }
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            let (width, height) = pool.size();
+            gl::Viewport(0, 0, width as GLsizei, height as GLsizei);
+        }
+        Ok(())
+    }
+
+    fn add_resource(&mut self, name: &str, kind: ResourceKind<'a>) -> ResourceId {
+        self.resources.push(Resource {
+            name: name.to_string(),
+            kind,
+        });
+        ResourceId(self.resources.len() - 1)
+    }
+
+    /// Validates the passes, drops those whose results are unused and sorts the rest so
+    /// every pass runs after the passes writing what it reads, writers of the same resource
+    /// run in the order they were added and a writer runs after the earlier readers of the
+    /// resource.
+    fn schedule(&self) -> Result<Vec<usize>, Errors> {
+        let error = |message: String| Err(Errors::RenderGraphError(message));
+        let mut writers: HashMap<ResourceId, Vec<usize>> = HashMap::new();
+        for (index, pass) in self.passes.iter().enumerate() {
+            let builder = &pass.builder;
+            if let Some(resource) = builder.reads.iter().find(|r| builder.writes.contains(r)) {
+                return error(format!(
+                    "pass '{}' reads and writes '{}'",
+                    pass.name,
+                    self.resource_name(*resource)
+                ));
+            }
+            let writes_backbuffer = builder.writes.iter().any(|resource| {
+                matches!(self.resources[resource.0].kind, ResourceKind::Backbuffer)
+            });
+            if writes_backbuffer && builder.writes.len() > 1 {
+                return error(format!(
+                    "pass '{}' writes the backbuffer together with other targets",
+                    pass.name
+                ));
+            }
+            for &resource in &builder.writes {
+                writers.entry(resource).or_default().push(index);
+            }
+        }
+
+        // A writer builds on the result of the previous writer of the same resource
+        let mut dependencies: Vec<Vec<usize>> = vec![Vec::new(); self.passes.len()];
+        for resource_writers in writers.values() {
+            for pair in resource_writers.windows(2) {
+                dependencies[pair[1]].push(pair[0]);
+            }
+        }
+
+        // A read depends on the writers declared before it, or on all writers if the
+        // resource is only written by passes added later. Writers added after the reader
+        // must not overwrite the resource before it is read, which only constrains the
+        // order and does not keep the reader alive.
+        let mut run_after: Vec<Vec<usize>> = vec![Vec::new(); self.passes.len()];
+        for (index, pass) in self.passes.iter().enumerate() {
+            for resource in &pass.builder.reads {
+                let Some(resource_writers) = writers.get(resource) else {
+                    if matches!(self.resources[resource.0].kind, ResourceKind::Imported(_)) {
+                        continue;
+                    }
+                    return error(format!(
+                        "pass '{}' reads '{}', which no pass writes",
+                        pass.name,
+                        self.resource_name(*resource)
+                    ));
+                };
+                let earlier: Vec<usize> = resource_writers
+                    .iter()
+                    .copied()
+                    .filter(|&writer| writer < index)
+                    .collect();
+                if earlier.is_empty() {
+                    dependencies[index].extend(resource_writers);
+                } else {
+                    dependencies[index].extend(earlier);
+                    for &writer in resource_writers.iter().filter(|&&writer| writer > index) {
+                        run_after[writer].push(index);
+                    }
+                }
+            }
+        }
+
+        // Keep the passes producing outputs and everything they depend on
+        let mut needed = vec![false; self.passes.len()];
+        let mut stack: Vec<usize> = self
+            .passes
+            .iter()
+            .enumerate()
+            .filter(|(_, pass)| {
+                pass.builder.side_effects
+                    || pass.builder.writes.iter().any(|resource| {
+                        !matches!(self.resources[resource.0].kind, ResourceKind::Transient(_))
+                    })
+            })
+            .map(|(index, _)| index)
+            .collect();
+        while let Some(index) = stack.pop() {
+            if !std::mem::replace(&mut needed[index], true) {
+                stack.extend(&dependencies[index]);
+            }
+        }
+
+        // Topological sort, preferring the order the passes were added in
+        let mut order = Vec::new();
+        let mut done = vec![false; self.passes.len()];
+        while order.len() < needed.iter().filter(|&&needed| needed).count() {
+            let next = (0..self.passes.len()).find(|&index| {
+                needed[index]
+                    && !done[index]
+                    && dependencies[index]
+                        .iter()
+                        .all(|&dependency| done[dependency])
+                    && run_after[index]
+                        .iter()
+                        .all(|&reader| done[reader] || !needed[reader])
+            });
+            match next {
+                Some(index) => {
+                    done[index] = true;
+                    order.push(index);
+                }
+                None => {
+                    let cycle: Vec<&str> = (0..self.passes.len())
+                        .filter(|&index| needed[index] && !done[index])
+                        .map(|index| self.passes[index].name.as_str())
+                        .collect();
+                    return error(format!("passes depend on each other: {}", cycle.join(", ")));
+                }
+            }
+        }
+        Ok(order)
+    }
+}
@@ -0,0 +1,238 @@
+//! # Render Scale Module
+//!
+//! This module provides a `RenderScaler` that renders into an off-screen target at a scaled
+//! internal resolution and then upscales or downsamples the result to the window.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::graphics::render_scale::RenderScaler;
+//!
+//! fn main() -> Result<(), glwfr::custom_errors::Errors> {
+//!     // Render at 75% of an 800x600 window
+//!     let mut scaler = RenderScaler::new(800, 600, 0.75)?;
+//!
+//!     scaler.begin();
+//!     // scene.render();
+//!     scaler.end();
+//!
+//!     Ok(())
+//! }
+//! ```
+
+use crate::custom_errors::{Errors, GlErrorKind};
+use gl::types::*;
+
+/// The filter used when copying the internal image to the window.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ScaleFilter {
+    /// Bilinear filtering. Smooth, suited for most 3D content.
+    #[default]
+    Linear,
+    /// Nearest-neighbour filtering. Keeps hard pixel edges.
+    Nearest,
+}
+
+impl ScaleFilter {
+    /// Returns the OpenGL enum for the filter, as expected by `glBlitFramebuffer`.
+    pub fn to_gl(&self) -> GLenum {
+        match self {
+            ScaleFilter::Linear => gl::LINEAR,
+            ScaleFilter::Nearest => gl::NEAREST,
+        }
+    }
+}
+
+/// Renders into an off-screen target at a scaled resolution and copies it to the window.
+pub struct RenderScaler {
+    fbo: GLuint,
+    color_buffer: GLuint,
+    depth_buffer: GLuint,
+    scale: f32,
+    filter: ScaleFilter,
+    window_width: i32,
+    window_height: i32,
+    internal_width: i32,
+    internal_height: i32,
+}
+
+impl RenderScaler {
+    /// Creates a new render scaler for a window of the given framebuffer size.
+    ///
+    /// # Arguments
+    ///
+    /// * `window_width` - The width of the window framebuffer in pixels.
+    /// * `window_height` - The height of the window framebuffer in pixels.
+    /// * `scale` - The internal resolution relative to the window, e.g. `0.75` or `2.0`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::OpenGlError` if the off-screen target cannot be created or is incomplete.
+    pub fn new(window_width: i32, window_height: i32, scale: f32) -> Result<Self, Errors> {
+        let mut fbo = 0;
+        let mut renderbuffers = [0; 2];
+        unsafe {
+            gl::GenFramebuffers(1, &mut fbo);
+            gl::GenRenderbuffers(2, renderbuffers.as_mut_ptr());
+        }
+        if fbo == 0 || renderbuffers.contains(&0) {
+            return Err(Errors::OpenGlError(
+                "Failed to generate render scale target".to_string(),
+                GlErrorKind::ObjectCreationFailed,
+            ));
+        }
+
+        let mut scaler = Self {
+            fbo,
+            color_buffer: renderbuffers[0],
+            depth_buffer: renderbuffers[1],
+            scale: scale.max(0.01),
+            filter: ScaleFilter::Linear,
+            window_width,
+            window_height,
+            internal_width: 0,
+            internal_height: 0,
+        };
+        scaler.allocate()?;
+        Ok(scaler)
+    }
+
+    /// Changes the internal resolution scale and reallocates the off-screen target.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::OpenGlError` if the resized target is incomplete.
+    pub fn set_scale(&mut self, scale: f32) -> Result<(), Errors> {
+        self.scale = scale.max(0.01);
+        self.allocate()
+    }
+
+    /// Returns the internal resolution scale.
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Sets the filter used when copying the internal image to the window.
+    pub fn set_filter(&mut self, filter: ScaleFilter) {
+        self.filter = filter;
+    }
+
+    /// Returns the filter used when copying the internal image to the window.
+    pub fn filter(&self) -> ScaleFilter {
+        self.filter
+    }
+
+    /// Updates the window size, for example after a resize, and reallocates the off-screen target.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::OpenGlError` if the resized target is incomplete.
+    pub fn resize(&mut self, window_width: i32, window_height: i32) -> Result<(), Errors> {
+        self.window_width = window_width;
+        self.window_height = window_height;
+        self.allocate()
+    }
+
+    /// Returns the internal resolution as `(width, height)`.
+    pub fn internal_size(&self) -> (i32, i32) {
+        (self.internal_width, self.internal_height)
+    }
+
+    /// Start rendering into the off-screen target.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glBindFramebuffer(GL_FRAMEBUFFER, fbo)` and `glViewport`.
+    pub fn begin(&self) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+            gl::Viewport(0, 0, self.internal_width, self.internal_height);
+        }
+    }
+
+    /// Copy the off-screen target to the window and make the window the active framebuffer again.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glBlitFramebuffer` with the configured filter,
+    /// followed by `glBindFramebuffer(GL_FRAMEBUFFER, 0)` and `glViewport`.
+    pub fn end(&self) {
+        unsafe {
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.fbo);
+            gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, 0);
+            gl::BlitFramebuffer(
+                0,
+                0,
+                self.internal_width,
+                self.internal_height,
+                0,
+                0,
+                self.window_width,
+                self.window_height,
+                gl::COLOR_BUFFER_BIT,
+                self.filter.to_gl(),
+            );
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            gl::Viewport(0, 0, self.window_width, self.window_height);
+        }
+    }
+
+    /// Allocates the color and depth storage for the current window size and scale.
+    fn allocate(&mut self) -> Result<(), Errors> {
+        self.internal_width = ((self.window_width as f32 * self.scale).round() as i32).max(1);
+        self.internal_height = ((self.window_height as f32 * self.scale).round() as i32).max(1);
+
+        let status = unsafe {
+            gl::BindRenderbuffer(gl::RENDERBUFFER, self.color_buffer);
+            gl::RenderbufferStorage(
+                gl::RENDERBUFFER,
+                gl::RGBA8,
+                self.internal_width,
+                self.internal_height,
+            );
+            gl::BindRenderbuffer(gl::RENDERBUFFER, self.depth_buffer);
+            gl::RenderbufferStorage(
+                gl::RENDERBUFFER,
+                gl::DEPTH24_STENCIL8,
+                self.internal_width,
+                self.internal_height,
+            );
+            gl::BindRenderbuffer(gl::RENDERBUFFER, 0);
+
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+            gl::FramebufferRenderbuffer(
+                gl::FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0,
+                gl::RENDERBUFFER,
+                self.color_buffer,
+            );
+            gl::FramebufferRenderbuffer(
+                gl::FRAMEBUFFER,
+                gl::DEPTH_STENCIL_ATTACHMENT,
+                gl::RENDERBUFFER,
+                self.depth_buffer,
+            );
+            let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            status
+        };
+
+        if status != gl::FRAMEBUFFER_COMPLETE {
+            return Err(Errors::OpenGlError(
+                "Render scale target is incomplete".to_string(),
+                GlErrorKind::FramebufferIncomplete(status),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for RenderScaler {
+    fn drop(&mut self) {
+        let renderbuffers = [self.color_buffer, self.depth_buffer];
+        unsafe {
+            gl::DeleteFramebuffers(1, &self.fbo);
+            gl::DeleteRenderbuffers(2, renderbuffers.as_ptr());
+        }
+    }
+}
@@ -0,0 +1,619 @@
+//! # Render State Module
+//!
+//! This module provides typed configuration for the fixed-function parts of the pipeline:
+//! depth testing, depth bias, blending, face culling and clearing.
+//!
+//! Materials pick the faces they hide with a `CullMode`. Two-sided materials, such as
+//! leaves and cloth, show their back faces, which lit shaders should shade with a flipped
+//! normal: paste `TWO_SIDED_GLSL` into the fragment shader and pass the interpolated normal
+//! through `faceNormal(normal)`.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::graphics::render_state::{BlendState, DepthBias, DepthState, RenderState};
+//!
+//! // Opaque geometry: depth test and write, no blending
+//! RenderState::default().apply();
+//!
+//! // Transparent geometry: depth test without writing, alpha blending
+//! let transparent = RenderState {
+//!     depth: Some(DepthState {
+//!         write: false,
+//!         ..DepthState::default()
+//!     }),
+//!     blend: Some(BlendState::ALPHA),
+//!     cull: None,
+//!     depth_bias: None,
+//! };
+//! transparent.apply();
+//!
+//! // Decals: pulled towards the camera to avoid z-fighting with the surface below
+//! let decal = RenderState {
+//!     depth_bias: Some(DepthBias::DECAL),
+//!     ..RenderState::transparent()
+//! };
+//! decal.apply();
+//! ```
+
+use gl::types::*;
+use std::hash::{Hash, Hasher};
+
+/// The comparison function used by the depth test.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum CompareFunc {
+    Never,
+    #[default]
+    Less,
+    Equal,
+    LessEqual,
+    Greater,
+    NotEqual,
+    GreaterEqual,
+    Always,
+}
+
+impl CompareFunc {
+    /// Returns the OpenGL enum for the comparison function.
+    pub fn to_gl(&self) -> GLenum {
+        match self {
+            CompareFunc::Never => gl::NEVER,
+            CompareFunc::Less => gl::LESS,
+            CompareFunc::Equal => gl::EQUAL,
+            CompareFunc::LessEqual => gl::LEQUAL,
+            CompareFunc::Greater => gl::GREATER,
+            CompareFunc::NotEqual => gl::NOTEQUAL,
+            CompareFunc::GreaterEqual => gl::GEQUAL,
+            CompareFunc::Always => gl::ALWAYS,
+        }
+    }
+}
+
+/// The equation used to combine source and destination colors when blending.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum BlendEquation {
+    #[default]
+    Add,
+    Subtract,
+    ReverseSubtract,
+    Min,
+    Max,
+}
+
+impl BlendEquation {
+    /// Returns the OpenGL enum for the blend equation.
+    pub fn to_gl(&self) -> GLenum {
+        match self {
+            BlendEquation::Add => gl::FUNC_ADD,
+            BlendEquation::Subtract => gl::FUNC_SUBTRACT,
+            BlendEquation::ReverseSubtract => gl::FUNC_REVERSE_SUBTRACT,
+            BlendEquation::Min => gl::MIN,
+            BlendEquation::Max => gl::MAX,
+        }
+    }
+}
+
+/// The factor a source or destination color is multiplied by when blending.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BlendFactor {
+    Zero,
+    One,
+    SrcColor,
+    OneMinusSrcColor,
+    DstColor,
+    OneMinusDstColor,
+    SrcAlpha,
+    OneMinusSrcAlpha,
+    DstAlpha,
+    OneMinusDstAlpha,
+    ConstantColor,
+    OneMinusConstantColor,
+    ConstantAlpha,
+    OneMinusConstantAlpha,
+    SrcAlphaSaturate,
+}
+
+impl BlendFactor {
+    /// Returns the OpenGL enum for the blend factor.
+    pub fn to_gl(&self) -> GLenum {
+        match self {
+            BlendFactor::Zero => gl::ZERO,
+            BlendFactor::One => gl::ONE,
+            BlendFactor::SrcColor => gl::SRC_COLOR,
+            BlendFactor::OneMinusSrcColor => gl::ONE_MINUS_SRC_COLOR,
+            BlendFactor::DstColor => gl::DST_COLOR,
+            BlendFactor::OneMinusDstColor => gl::ONE_MINUS_DST_COLOR,
+            BlendFactor::SrcAlpha => gl::SRC_ALPHA,
+            BlendFactor::OneMinusSrcAlpha => gl::ONE_MINUS_SRC_ALPHA,
+            BlendFactor::DstAlpha => gl::DST_ALPHA,
+            BlendFactor::OneMinusDstAlpha => gl::ONE_MINUS_DST_ALPHA,
+            BlendFactor::ConstantColor => gl::CONSTANT_COLOR,
+            BlendFactor::OneMinusConstantColor => gl::ONE_MINUS_CONSTANT_COLOR,
+            BlendFactor::ConstantAlpha => gl::CONSTANT_ALPHA,
+            BlendFactor::OneMinusConstantAlpha => gl::ONE_MINUS_CONSTANT_ALPHA,
+            BlendFactor::SrcAlphaSaturate => gl::SRC_ALPHA_SATURATE,
+        }
+    }
+}
+
+/// The faces discarded by face culling.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum CullFace {
+    Front,
+    #[default]
+    Back,
+    FrontAndBack,
+}
+
+impl CullFace {
+    /// Returns the OpenGL enum for the culled faces.
+    pub fn to_gl(&self) -> GLenum {
+        match self {
+            CullFace::Front => gl::FRONT,
+            CullFace::Back => gl::BACK,
+            CullFace::FrontAndBack => gl::FRONT_AND_BACK,
+        }
+    }
+}
+
+/// The faces a material hides, see `Object::cull_mode`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum CullMode {
+    /// Back faces are culled, the usual setting for closed meshes.
+    #[default]
+    Back,
+    /// Front faces are culled, e.g. for the inside of a sky dome.
+    Front,
+    /// No faces are culled, for thin geometry such as leaves, cloth and paper.
+    TwoSided,
+}
+
+impl CullMode {
+    /// Returns the mode matching the cull part of a render state.
+    pub fn from_state(cull: Option<&CullState>) -> Self {
+        match cull.map(|cull| cull.face) {
+            Some(CullFace::Back) => CullMode::Back,
+            Some(CullFace::Front) => CullMode::Front,
+            Some(CullFace::FrontAndBack) | None => CullMode::TwoSided,
+        }
+    }
+
+    /// Returns `true` if back faces are visible, so their normals have to be flipped.
+    pub fn shows_back_faces(&self) -> bool {
+        *self != CullMode::Back
+    }
+
+    /// Returns the cull configuration for the mode, or `None` if culling is disabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `front_face` - The winding order of front-facing triangles.
+    pub fn cull_state(&self, front_face: FrontFace) -> Option<CullState> {
+        let face = match self {
+            CullMode::Back => CullFace::Back,
+            CullMode::Front => CullFace::Front,
+            CullMode::TwoSided => return None,
+        };
+        Some(CullState { face, front_face })
+    }
+
+    /// Enable or disable face culling for the mode.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glEnable(GL_CULL_FACE)` and `glCullFace`, or
+    /// `glDisable(GL_CULL_FACE)` for two-sided materials.
+    pub fn apply(&self, front_face: FrontFace) {
+        RenderState::apply_cull(self.cull_state(front_face).as_ref());
+    }
+}
+
+/// GLSL helper for two-sided lighting.
+///
+/// Declares the `twoSided` uniform, uploaded by `Object::render`, and
+/// `vec3 faceNormal(vec3 normal)`, which normalizes the normal and flips it on back faces of
+/// two-sided materials.
+pub const TWO_SIDED_GLSL: &str = r#"
+uniform int twoSided;
+
+vec3 faceNormal(vec3 normal) {
+    normal = normalize(normal);
+    return (twoSided != 0 && !gl_FrontFacing) ? -normal : normal;
+}
+"#;
+
+/// The winding order of front-facing triangles.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum FrontFace {
+    #[default]
+    CounterClockwise,
+    Clockwise,
+}
+
+impl FrontFace {
+    /// Returns the OpenGL enum for the winding order.
+    pub fn to_gl(&self) -> GLenum {
+        match self {
+            FrontFace::CounterClockwise => gl::CCW,
+            FrontFace::Clockwise => gl::CW,
+        }
+    }
+}
+
+/// Depth test configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DepthState {
+    /// The comparison function used by the depth test.
+    pub func: CompareFunc,
+    /// Whether fragments that pass the test write their depth.
+    pub write: bool,
+}
+
+impl Default for DepthState {
+    fn default() -> Self {
+        Self {
+            func: CompareFunc::Less,
+            write: true,
+        }
+    }
+}
+
+impl DepthState {
+    /// Enable depth testing with this configuration.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glEnable(GL_DEPTH_TEST)`, `glDepthFunc` and `glDepthMask`.
+    pub fn apply(&self) {
+        unsafe {
+            gl::Enable(gl::DEPTH_TEST);
+            gl::DepthFunc(self.func.to_gl());
+            gl::DepthMask(if self.write { gl::TRUE } else { gl::FALSE });
+        }
+    }
+}
+
+/// Depth bias configuration, offsetting the depth of polygons to avoid z-fighting.
+///
+/// The offset is `factor * slope + units * r`, where `slope` is the depth slope of the
+/// polygon and `r` the smallest resolvable depth difference. Negative values pull polygons
+/// towards the camera, e.g. for decals and outlines; positive values push them away, e.g.
+/// for shadow casters.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DepthBias {
+    /// The offset scaled by the depth slope of the polygon.
+    pub factor: f32,
+    /// The offset in multiples of the smallest resolvable depth difference.
+    pub units: f32,
+}
+
+// The bias is compared and hashed by bit pattern, so states can be used as map keys
+impl Eq for DepthBias {}
+
+impl Hash for DepthBias {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.factor.to_bits().hash(state);
+        self.units.to_bits().hash(state);
+    }
+}
+
+impl DepthBias {
+    /// A bias pulling decals in front of the surface they are placed on.
+    pub const DECAL: DepthBias = DepthBias {
+        factor: -1.0,
+        units: -1.0,
+    };
+
+    /// A bias pushing shadow casters away from the light to reduce shadow acne.
+    pub const SHADOW: DepthBias = DepthBias {
+        factor: 2.0,
+        units: 4.0,
+    };
+
+    /// Enable polygon offset with this configuration for filled polygons.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glEnable(GL_POLYGON_OFFSET_FILL)` and
+    /// `glPolygonOffset`.
+    pub fn apply(&self) {
+        unsafe {
+            gl::Enable(gl::POLYGON_OFFSET_FILL);
+            gl::PolygonOffset(self.factor, self.units);
+        }
+    }
+}
+
+/// Blending configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BlendState {
+    /// The equation used to combine source and destination colors.
+    pub equation: BlendEquation,
+    /// The factor the source color is multiplied by.
+    pub src_factor: BlendFactor,
+    /// The factor the destination color is multiplied by.
+    pub dst_factor: BlendFactor,
+}
+
+impl BlendState {
+    /// Standard alpha blending: `src * src_alpha + dst * (1 - src_alpha)`.
+    pub const ALPHA: BlendState = BlendState {
+        equation: BlendEquation::Add,
+        src_factor: BlendFactor::SrcAlpha,
+        dst_factor: BlendFactor::OneMinusSrcAlpha,
+    };
+
+    /// Blending for colors with premultiplied alpha: `src + dst * (1 - src_alpha)`.
+    pub const PREMULTIPLIED_ALPHA: BlendState = BlendState {
+        equation: BlendEquation::Add,
+        src_factor: BlendFactor::One,
+        dst_factor: BlendFactor::OneMinusSrcAlpha,
+    };
+
+    /// Additive blending: `src * src_alpha + dst`.
+    pub const ADDITIVE: BlendState = BlendState {
+        equation: BlendEquation::Add,
+        src_factor: BlendFactor::SrcAlpha,
+        dst_factor: BlendFactor::One,
+    };
+
+    /// Enable blending with this configuration.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glEnable(GL_BLEND)`, `glBlendEquation` and `glBlendFunc`.
+    pub fn apply(&self) {
+        unsafe {
+            gl::Enable(gl::BLEND);
+            gl::BlendEquation(self.equation.to_gl());
+            gl::BlendFunc(self.src_factor.to_gl(), self.dst_factor.to_gl());
+        }
+    }
+}
+
+impl Default for BlendState {
+    fn default() -> Self {
+        Self::ALPHA
+    }
+}
+
+/// Face culling configuration.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct CullState {
+    /// The faces to discard.
+    pub face: CullFace,
+    /// The winding order of front-facing triangles.
+    pub front_face: FrontFace,
+}
+
+impl CullState {
+    /// Enable face culling with this configuration.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glEnable(GL_CULL_FACE)`, `glCullFace` and `glFrontFace`.
+    pub fn apply(&self) {
+        unsafe {
+            gl::Enable(gl::CULL_FACE);
+            gl::CullFace(self.face.to_gl());
+            gl::FrontFace(self.front_face.to_gl());
+        }
+    }
+}
+
+/// The buffers cleared at the start of a pass and the values they are cleared to.
+///
+/// A `None` field keeps the previous contents of that buffer, so `ClearState::LOAD` draws
+/// on top of what is already there, e.g. a HUD over the 3D view or effects accumulating
+/// over several frames.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClearState {
+    /// The color to clear the color buffers to, or `None` to keep them.
+    pub color: Option<[f32; 4]>,
+    /// The depth to clear the depth buffer to, usually `1.0`, or `None` to keep it.
+    pub depth: Option<f32>,
+    /// The value to clear the stencil buffer to, or `None` to keep it.
+    pub stencil: Option<i32>,
+}
+
+impl Default for ClearState {
+    /// Returns the state clearing the color to opaque black and the depth to `1.0`.
+    fn default() -> Self {
+        Self::color_and_depth([0.0, 0.0, 0.0, 1.0])
+    }
+}
+
+impl ClearState {
+    /// Keeps the contents of every buffer.
+    pub const LOAD: ClearState = ClearState {
+        color: None,
+        depth: None,
+        stencil: None,
+    };
+
+    /// Returns a state clearing the color to `color` and the depth to `1.0`.
+    pub fn color_and_depth(color: [f32; 4]) -> Self {
+        Self {
+            color: Some(color),
+            depth: Some(1.0),
+            stencil: None,
+        }
+    }
+
+    /// Returns a state clearing only the depth to `1.0`, e.g. for a view model or HUD drawn
+    /// over the scene without intersecting it.
+    pub fn depth_only() -> Self {
+        Self {
+            color: None,
+            depth: Some(1.0),
+            stencil: None,
+        }
+    }
+
+    /// Returns the state also clearing the stencil buffer to `value`.
+    pub fn with_stencil(mut self, value: i32) -> Self {
+        self.stencil = Some(value);
+        self
+    }
+
+    /// Returns the `glClear` mask of the buffers that are cleared.
+    pub fn mask(&self) -> GLbitfield {
+        let mut mask = 0;
+        if self.color.is_some() {
+            mask |= gl::COLOR_BUFFER_BIT;
+        }
+        if self.depth.is_some() {
+            mask |= gl::DEPTH_BUFFER_BIT;
+        }
+        if self.stencil.is_some() {
+            mask |= gl::STENCIL_BUFFER_BIT;
+        }
+        mask
+    }
+
+    /// Returns `true` if no buffer is cleared.
+    pub fn is_load(&self) -> bool {
+        self.mask() == 0
+    }
+
+    /// Clear the buffers of the bound framebuffer. Does nothing for `ClearState::LOAD`.
+    ///
+    /// The write masks of the cleared buffers are enabled first, since they also apply to
+    /// clearing. The scissor test is respected.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glClearColor`, `glClearDepth`, `glClearStencil`,
+    /// the matching write masks and `glClear`.
+    pub fn apply(&self) {
+        let mask = self.mask();
+        if mask == 0 {
+            return;
+        }
+        unsafe {
+            if let Some([r, g, b, a]) = self.color {
+                gl::ColorMask(gl::TRUE, gl::TRUE, gl::TRUE, gl::TRUE);
+                gl::ClearColor(r, g, b, a);
+            }
+            if let Some(depth) = self.depth {
+                gl::DepthMask(gl::TRUE);
+                gl::ClearDepth(depth as GLdouble);
+            }
+            if let Some(stencil) = self.stencil {
+                gl::StencilMask(!0);
+                gl::ClearStencil(stencil);
+            }
+            gl::Clear(mask);
+        }
+    }
+}
+
+/// The complete depth, blend and cull configuration used for a draw call.
+///
+/// A `None` field disables the corresponding OpenGL capability.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RenderState {
+    /// Depth test configuration, or `None` to disable depth testing.
+    pub depth: Option<DepthState>,
+    /// Blending configuration, or `None` to disable blending.
+    pub blend: Option<BlendState>,
+    /// Face culling configuration, or `None` to disable face culling.
+    pub cull: Option<CullState>,
+    /// Depth bias configuration, or `None` to disable polygon offset.
+    pub depth_bias: Option<DepthBias>,
+}
+
+impl Default for RenderState {
+    /// Returns the state for opaque geometry: depth test and write enabled, blending and
+    /// culling disabled.
+    fn default() -> Self {
+        Self {
+            depth: Some(DepthState::default()),
+            blend: None,
+            cull: None,
+            depth_bias: None,
+        }
+    }
+}
+
+impl RenderState {
+    /// Returns a state with depth testing, blending and culling all disabled.
+    pub fn disabled() -> Self {
+        Self {
+            depth: None,
+            blend: None,
+            cull: None,
+            depth_bias: None,
+        }
+    }
+
+    /// Returns the state for transparent geometry: depth test without depth write and
+    /// standard alpha blending.
+    pub fn transparent() -> Self {
+        Self {
+            depth: Some(DepthState {
+                func: CompareFunc::Less,
+                write: false,
+            }),
+            blend: Some(BlendState::ALPHA),
+            cull: None,
+            depth_bias: None,
+        }
+    }
+
+    /// Apply the whole render state to the current OpenGL context.
+    pub fn apply(&self) {
+        Self::apply_depth(self.depth.as_ref());
+        Self::apply_blend(self.blend.as_ref());
+        Self::apply_cull(self.cull.as_ref());
+        Self::apply_depth_bias(self.depth_bias.as_ref());
+    }
+
+    /// Apply only the parts of the state that differ from `previous`.
+    ///
+    /// # Arguments
+    ///
+    /// * `previous` - The state that is currently active in the OpenGL context.
+    pub fn apply_changes(&self, previous: &RenderState) {
+        if self.depth != previous.depth {
+            Self::apply_depth(self.depth.as_ref());
+        }
+        if self.blend != previous.blend {
+            Self::apply_blend(self.blend.as_ref());
+        }
+        if self.cull != previous.cull {
+            Self::apply_cull(self.cull.as_ref());
+        }
+        if self.depth_bias != previous.depth_bias {
+            Self::apply_depth_bias(self.depth_bias.as_ref());
+        }
+    }
+
+    fn apply_depth(depth: Option<&DepthState>) {
+        match depth {
+            Some(depth) => depth.apply(),
+            None => unsafe { gl::Disable(gl::DEPTH_TEST) },
+        }
+    }
+
+    fn apply_blend(blend: Option<&BlendState>) {
+        match blend {
+            Some(blend) => blend.apply(),
+            None => unsafe { gl::Disable(gl::BLEND) },
+        }
+    }
+
+    fn apply_cull(cull: Option<&CullState>) {
+        match cull {
+            Some(cull) => cull.apply(),
+            None => unsafe { gl::Disable(gl::CULL_FACE) },
+        }
+    }
+
+    fn apply_depth_bias(depth_bias: Option<&DepthBias>) {
+        match depth_bias {
+            Some(depth_bias) => depth_bias.apply(),
+            None => unsafe { gl::Disable(gl::POLYGON_OFFSET_FILL) },
+        }
+    }
+}
@@ -0,0 +1,268 @@
+//! # Renderer Module
+//!
+//! This module provides a `Renderer` that tracks render state shared between draw calls,
+//! such as the active `RenderState`, the stack of scissor rectangles used to clip UI
+//! and sprites, and a user clip plane.
+//!
+//! The clip plane discards geometry on its negative side, e.g. everything below the water
+//! surface while rendering a planar reflection, or half of a model for a cross-section view.
+//! Vertex shaders paste `CLIP_PLANE_GLSL` and call `applyClipPlane(worldPos)`.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::graphics::renderer::{Rect, Renderer};
+//! use glwfr::math::Plane;
+//! use glwfr::cgmath::Vector3;
+//!
+//! let mut renderer = Renderer::new(800, 600);
+//!
+//! // Clip everything drawn to the top-left quarter of the framebuffer
+//! renderer.push_scissor(Rect::new(0, 0, 400, 300));
+//! // ... draw ...
+//! renderer.pop_scissor();
+//!
+//! // Only keep geometry above the water surface at y = 0
+//! renderer.set_clip_plane(Some(Plane::new(Vector3::unit_y(), 0.0)));
+//! // shader_program.bind();
+//! // renderer.apply_clip_plane(&shader_program);
+//! // ... draw the reflection ...
+//! renderer.set_clip_plane(None);
+//! ```
+
+use crate::graphics::gl_wrapper::ShaderProgram;
+use crate::graphics::render_state::RenderState;
+use crate::math::Plane;
+use cgmath::Vector3;
+
+/// GLSL helper for vertex shaders clipping geometry against a user clip plane.
+///
+/// Declares the `clipPlaneNormal` and `clipPlaneDistance` uniforms and
+/// `void applyClipPlane(vec4 worldPos)`, which writes `gl_ClipDistance[0]`. Points with a
+/// negative distance to the plane are clipped while `GL_CLIP_DISTANCE0` is enabled.
+pub const CLIP_PLANE_GLSL: &str = r#"
+uniform vec3 clipPlaneNormal;
+uniform float clipPlaneDistance;
+
+void applyClipPlane(vec4 worldPos) {
+    gl_ClipDistance[0] = dot(worldPos.xyz, clipPlaneNormal) + clipPlaneDistance * worldPos.w;
+}
+"#;
+
+/// A rectangle in framebuffer pixels with the origin in the top-left corner.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Rect {
+    /// The x coordinate of the left edge.
+    pub x: i32,
+    /// The y coordinate of the top edge, measured downwards.
+    pub y: i32,
+    /// The width of the rectangle.
+    pub width: i32,
+    /// The height of the rectangle.
+    pub height: i32,
+}
+
+impl Rect {
+    /// Creates a new rectangle from its top-left corner and size.
+    pub fn new(x: i32, y: i32, width: i32, height: i32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Returns the overlapping area of two rectangles.
+    ///
+    /// If the rectangles do not overlap, the returned rectangle has zero width or height.
+    pub fn intersect(&self, other: &Rect) -> Rect {
+        let left = self.x.max(other.x);
+        let top = self.y.max(other.y);
+        let right = (self.x + self.width).min(other.x + other.width);
+        let bottom = (self.y + self.height).min(other.y + other.height);
+        Rect::new(left, top, (right - left).max(0), (bottom - top).max(0))
+    }
+
+    /// Returns `true` if the point lies inside the rectangle.
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+}
+
+/// Tracks render state shared between draw calls.
+pub struct Renderer {
+    framebuffer_width: i32,
+    framebuffer_height: i32,
+    scissor_stack: Vec<Rect>,
+    render_state: Option<RenderState>,
+    clip_plane: Option<Plane>,
+}
+
+impl Renderer {
+    /// Creates a new renderer for a framebuffer of the given size.
+    ///
+    /// # Arguments
+    ///
+    /// * `framebuffer_width` - The width of the framebuffer in pixels.
+    /// * `framebuffer_height` - The height of the framebuffer in pixels.
+    pub fn new(framebuffer_width: i32, framebuffer_height: i32) -> Self {
+        Self {
+            framebuffer_width,
+            framebuffer_height,
+            scissor_stack: Vec::new(),
+            render_state: None,
+            clip_plane: None,
+        }
+    }
+
+    /// Updates the framebuffer size, for example after the window has been resized.
+    ///
+    /// The active scissor rectangle is re-applied, since its OpenGL coordinates depend on
+    /// the framebuffer height.
+    pub fn set_framebuffer_size(&mut self, width: i32, height: i32) {
+        self.framebuffer_width = width;
+        self.framebuffer_height = height;
+        self.apply_scissor();
+    }
+
+    /// Returns the framebuffer size as `(width, height)`.
+    pub fn framebuffer_size(&self) -> (i32, i32) {
+        (self.framebuffer_width, self.framebuffer_height)
+    }
+
+    /// Makes the given render state active.
+    ///
+    /// Only the parts that differ from the previously set state are sent to OpenGL. The
+    /// first call applies the whole state.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The render state to use for the following draw calls.
+    pub fn set_render_state(&mut self, state: RenderState) {
+        match &self.render_state {
+            Some(previous) => state.apply_changes(previous),
+            None => state.apply(),
+        }
+        self.render_state = Some(state);
+    }
+
+    /// Returns the render state set by the last call to `set_render_state`, if any.
+    pub fn render_state(&self) -> Option<RenderState> {
+        self.render_state
+    }
+
+    /// Forgets the cached render state, so the next `set_render_state` applies it in full.
+    ///
+    /// Call this after changing depth, blend or cull state directly through OpenGL.
+    pub fn invalidate_render_state(&mut self) {
+        self.render_state = None;
+    }
+
+    /// Sets the user clip plane. Geometry on the negative side of the plane is discarded by
+    /// shaders using `CLIP_PLANE_GLSL`.
+    ///
+    /// # Arguments
+    ///
+    /// * `plane` - The clip plane in world space, or `None` to disable clipping.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glEnable(GL_CLIP_DISTANCE0)` and
+    /// `glDisable(GL_CLIP_DISTANCE0)`.
+    pub fn set_clip_plane(&mut self, plane: Option<Plane>) {
+        set_clip_distance_enabled(plane.is_some());
+        self.clip_plane = plane;
+    }
+
+    /// Returns the active user clip plane, if any.
+    pub fn clip_plane(&self) -> Option<Plane> {
+        self.clip_plane
+    }
+
+    /// Uploads the active clip plane to a shader program using `CLIP_PLANE_GLSL`.
+    ///
+    /// The shader program must be bound before calling this function.
+    pub fn apply_clip_plane(&self, shader_program: &ShaderProgram) {
+        apply_clip_plane_uniforms(shader_program, self.clip_plane.as_ref());
+    }
+
+    /// Pushes a scissor rectangle, clipping all following draw calls to it.
+    ///
+    /// The rectangle is intersected with the currently active one, so nested UI elements
+    /// never draw outside of their parents.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glEnable(GL_SCISSOR_TEST)` and `glScissor`.
+    ///
+    /// # Arguments
+    ///
+    /// * `rect` - The clipping rectangle in framebuffer pixels, with the origin in the top-left corner.
+    pub fn push_scissor(&mut self, rect: Rect) {
+        let rect = match self.scissor_stack.last() {
+            Some(current) => current.intersect(&rect),
+            None => rect,
+        };
+        self.scissor_stack.push(rect);
+        self.apply_scissor();
+    }
+
+    /// Pops the most recently pushed scissor rectangle and restores the previous one.
+    ///
+    /// When the stack becomes empty the scissor test is disabled.
+    ///
+    /// # Returns
+    ///
+    /// The popped rectangle, or `None` if the stack was empty.
+    pub fn pop_scissor(&mut self) -> Option<Rect> {
+        let rect = self.scissor_stack.pop();
+        self.apply_scissor();
+        rect
+    }
+
+    /// Returns the currently active scissor rectangle, if any.
+    pub fn current_scissor(&self) -> Option<Rect> {
+        self.scissor_stack.last().copied()
+    }
+
+    /// Applies the top of the scissor stack to the OpenGL state, converting it to
+    /// OpenGL's bottom-left origin.
+    fn apply_scissor(&self) {
+        match self.scissor_stack.last() {
+            Some(rect) => unsafe {
+                gl::Enable(gl::SCISSOR_TEST);
+                gl::Scissor(
+                    rect.x,
+                    self.framebuffer_height - rect.y - rect.height,
+                    rect.width,
+                    rect.height,
+                );
+            },
+            None => unsafe {
+                gl::Disable(gl::SCISSOR_TEST);
+            },
+        }
+    }
+}
+
+/// Enables or disables clipping against `gl_ClipDistance[0]`.
+pub(crate) fn set_clip_distance_enabled(enabled: bool) {
+    unsafe {
+        if enabled {
+            gl::Enable(gl::CLIP_DISTANCE0);
+        } else {
+            gl::Disable(gl::CLIP_DISTANCE0);
+        }
+    }
+}
+
+/// Sets the uniforms declared by `CLIP_PLANE_GLSL`. Without a plane, every point is kept.
+pub(crate) fn apply_clip_plane_uniforms(shader_program: &ShaderProgram, plane: Option<&Plane>) {
+    let (normal, distance) = match plane {
+        Some(plane) => (plane.normal, plane.distance),
+        None => (Vector3::new(0.0, 0.0, 0.0), 1.0),
+    };
+    shader_program.try_set_uniform("clipPlaneNormal", normal);
+    shader_program.try_set_uniform("clipPlaneDistance", distance);
+}
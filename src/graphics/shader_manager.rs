@@ -0,0 +1,170 @@
+//! # Shader Manager Module
+//!
+//! This module provides a `ShaderManager` that caches shader programs by their source paths,
+//! so objects using the same shaders share a single compiled program and uniform cache.
+//!
+//! With hot reloading enabled, `reload_changed` recompiles every program whose source files
+//! were saved since the last call, so GLSL can be edited while the application is running.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::graphics::shader_manager::ShaderManager;
+//! use glwfr::graphics::gl_wrapper::Vao;
+//! use glwfr::scene::Object;
+//!
+//! fn main() -> Result<(), glwfr::custom_errors::Errors> {
+//!     let mut shaders = ShaderManager::new();
+//!
+//!     // Both objects share one program, which is compiled only once
+//!     let a = Object::new(Vao::new()?, shaders.load("vertex.glsl", "fragment.glsl")?);
+//!     let b = Object::new(Vao::new()?, shaders.load("vertex.glsl", "fragment.glsl")?);
+//!     assert!(std::rc::Rc::ptr_eq(&a.shader_program, &b.shader_program));
+//!
+//!     // Once per frame, before rendering
+//!     shaders.set_hot_reload(true);
+//!     shaders.reload_changed();
+//!     Ok(())
+//! }
+//! ```
+
+use crate::custom_errors::Errors;
+use crate::graphics::gl_wrapper::ShaderProgram;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Caches shader programs by the paths of their vertex and fragment shaders.
+pub struct ShaderManager {
+    programs: HashMap<(String, String), Rc<ShaderProgram>>,
+    hot_reload: bool,
+}
+
+impl ShaderManager {
+    /// Creates a new, empty shader manager.
+    pub fn new() -> Self {
+        Self {
+            programs: HashMap::new(),
+            hot_reload: false,
+        }
+    }
+
+    /// Returns the shader program for the given shader files, compiling it on first use.
+    ///
+    /// # Arguments
+    ///
+    /// * `vertex_path` - The path to the vertex shader source file.
+    /// * `fragment_path` - The path to the fragment shader source file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::ShaderCompilationError` or `Errors::ShaderLinkError` if the program
+    /// cannot be built.
+    pub fn load(
+        &mut self,
+        vertex_path: &str,
+        fragment_path: &str,
+    ) -> Result<Rc<ShaderProgram>, Errors> {
+        let key = (vertex_path.to_string(), fragment_path.to_string());
+        if let Some(program) = self.programs.get(&key) {
+            return Ok(Rc::clone(program));
+        }
+
+        let program = Rc::new(ShaderProgram::new(vertex_path, fragment_path)?);
+        if self.hot_reload {
+            program.enable_hot_reload();
+        }
+        self.programs.insert(key, Rc::clone(&program));
+        Ok(program)
+    }
+
+    /// Returns the cached shader program for the given shader files without compiling it.
+    pub fn get(&self, vertex_path: &str, fragment_path: &str) -> Option<Rc<ShaderProgram>> {
+        self.programs
+            .get(&(vertex_path.to_string(), fragment_path.to_string()))
+            .cloned()
+    }
+
+    /// Removes the shader program for the given shader files from the cache.
+    ///
+    /// The program is deleted once all objects using it are dropped.
+    pub fn remove(&mut self, vertex_path: &str, fragment_path: &str) -> Option<Rc<ShaderProgram>> {
+        self.programs
+            .remove(&(vertex_path.to_string(), fragment_path.to_string()))
+    }
+
+    /// Removes all shader programs that are not used outside of the manager.
+    ///
+    /// # Returns
+    ///
+    /// The number of programs removed.
+    pub fn remove_unused(&mut self) -> usize {
+        let before = self.programs.len();
+        self.programs
+            .retain(|_, program| Rc::strong_count(program) > 1);
+        before - self.programs.len()
+    }
+
+    /// Enables or disables hot reloading for all cached programs and programs loaded later.
+    pub fn set_hot_reload(&mut self, enabled: bool) {
+        self.hot_reload = enabled;
+        for program in self.programs.values() {
+            if enabled {
+                program.enable_hot_reload();
+            } else {
+                program.disable_hot_reload();
+            }
+        }
+    }
+
+    /// Returns `true` if hot reloading is enabled.
+    pub fn hot_reload(&self) -> bool {
+        self.hot_reload
+    }
+
+    /// Recompiles every cached program whose source files changed since the last call.
+    ///
+    /// Call this at a safe point, e.g. at the start of a frame before anything is drawn.
+    /// Programs that fail to compile keep their previous version and the error is logged,
+    /// so a typo in a shader does not stop the application.
+    ///
+    /// # Returns
+    ///
+    /// The number of programs reloaded.
+    pub fn reload_changed(&self) -> usize {
+        let mut reloaded = 0;
+        for ((vertex_path, fragment_path), program) in &self.programs {
+            match program.reload_if_changed() {
+                Ok(true) => reloaded += 1,
+                Ok(false) => {}
+                Err(error) => log::error!(
+                    "Failed to reload shaders {} and {}: {}",
+                    vertex_path,
+                    fragment_path,
+                    error
+                ),
+            }
+        }
+        reloaded
+    }
+
+    /// Removes all shader programs from the cache.
+    pub fn clear(&mut self) {
+        self.programs.clear();
+    }
+
+    /// Returns the number of cached shader programs.
+    pub fn len(&self) -> usize {
+        self.programs.len()
+    }
+
+    /// Checks if the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.programs.is_empty()
+    }
+}
+
+impl Default for ShaderManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
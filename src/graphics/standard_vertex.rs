@@ -0,0 +1,203 @@
+//! # Standard Vertex Module
+//!
+//! This module defines the attribute locations shared by the built-in 3D shaders and a
+//! `StandardVertex` using them: position, normal, texture coordinates and an RGBA vertex
+//! color. Vertex colors are multiplied with the albedo, which is a cheap way to add
+//! variation or paint stylized meshes without textures.
+//!
+//! Meshes without a color attribute read the constant vertex color instead, which
+//! `Window::init_gl` sets to white, so shaders can always multiply with it.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::graphics::standard_vertex::{StandardVertex, VERTEX_COLOR_GLSL};
+//! use glwfr::graphics::gl_wrapper::{BufferObject, Vao, Vertex};
+//!
+//! fn main() -> Result<(), glwfr::custom_errors::Errors> {
+//!     let vertices = [
+//!         StandardVertex::new([0.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 0.0]).with_color([255, 0, 0, 255]),
+//!         StandardVertex::new([1.0, 0.0, 0.0], [0.0, 0.0, 1.0], [1.0, 0.0]).with_color([0, 255, 0, 255]),
+//!         StandardVertex::new([0.0, 1.0, 0.0], [0.0, 0.0, 1.0], [0.0, 1.0]).with_color([0, 0, 255, 255]),
+//!     ];
+//!     let vao = Vao::new()?;
+//!     vao.bind();
+//!     let vbo = BufferObject::new(gl::ARRAY_BUFFER, gl::STATIC_DRAW)?;
+//!     vbo.bind();
+//!     vbo.allocate(std::mem::size_of_val(&vertices));
+//!     vbo.update_data(0, &vertices);
+//!     StandardVertex::setup_attributes();
+//!
+//!     // The vertex shader declares the color input and passes it on
+//!     let vertex_header = format!("#version 330 core\n{}", VERTEX_COLOR_GLSL);
+//!     Ok(())
+//! }
+//! ```
+
+use crate::graphics::gl_wrapper::{Vertex, VertexAttributeLayout};
+use std::mem;
+
+/// The attribute location of the vertex position.
+pub const POSITION_ATTRIBUTE: u32 = 0;
+
+/// The attribute location of the vertex normal.
+pub const NORMAL_ATTRIBUTE: u32 = 1;
+
+/// The attribute location of the texture coordinates.
+pub const UV_ATTRIBUTE: u32 = 2;
+
+/// The attribute location of the vertex color.
+///
+/// Locations `3` to `5` are used by lightmap coordinates and skinning.
+pub const COLOR_ATTRIBUTE: u32 = 6;
+
+/// GLSL helper for vertex shaders declaring the vertex color input.
+///
+/// Declares `aColor` at `COLOR_ATTRIBUTE` and the `vertexColor` output; call
+/// `passVertexColor()` in `main` and multiply the albedo with `vertexColor` in the fragment
+/// shader.
+pub const VERTEX_COLOR_GLSL: &str = r#"
+layout (location = 6) in vec4 aColor;
+out vec4 vertexColor;
+
+void passVertexColor() {
+    vertexColor = aColor;
+}
+"#;
+
+/// The first attribute location of the per-instance model matrix.
+///
+/// The matrix takes one location per column, so locations `7` to `10` are used.
+pub const INSTANCE_MODEL_ATTRIBUTE: u32 = 7;
+
+/// The attribute location of the vertex tangent, with the bitangent handedness in `w`.
+pub const TANGENT_ATTRIBUTE: u32 = 11;
+
+/// GLSL helper for vertex shaders drawn with per-instance model matrices.
+///
+/// Declares `aInstanceModel` at `INSTANCE_MODEL_ATTRIBUTE` and the `useInstanceModel` uniform
+/// set by `Object::render_instanced` and `Scene::render`. Use `instanceModel(model)` in place of
+/// the model matrix; it returns `model * aInstanceModel` for instanced draws and `model`
+/// otherwise.
+pub const INSTANCING_GLSL: &str = r#"
+layout (location = 7) in mat4 aInstanceModel;
+uniform int useInstanceModel;
+
+mat4 instanceModel(mat4 model) {
+    return useInstanceModel != 0 ? model * aInstanceModel : model;
+}
+"#;
+
+/// The per-instance data read by `INSTANCING_GLSL`: a model matrix applied before the model
+/// matrix of the object.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[repr(C)]
+pub struct InstanceTransform {
+    /// The columns of the model matrix.
+    pub model: [[f32; 4]; 4],
+}
+
+impl From<cgmath::Matrix4<f32>> for InstanceTransform {
+    fn from(model: cgmath::Matrix4<f32>) -> Self {
+        Self {
+            model: model.into(),
+        }
+    }
+}
+
+impl Vertex for InstanceTransform {
+    fn layout() -> Vec<VertexAttributeLayout> {
+        (0..4)
+            .map(|column| VertexAttributeLayout {
+                index: INSTANCE_MODEL_ATTRIBUTE + column as u32,
+                size: 4,
+                r#type: gl::FLOAT,
+                normalized: gl::FALSE,
+                offset: column * mem::size_of::<[f32; 4]>(),
+            })
+            .collect()
+    }
+}
+
+/// A vertex with a position, normal, texture coordinates and color.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[repr(C)]
+pub struct StandardVertex {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+    pub uv: [f32; 2],
+    /// The color of the vertex, normalized to `0.0..=1.0` in the shader.
+    pub color: [u8; 4],
+}
+
+impl Default for StandardVertex {
+    fn default() -> Self {
+        Self::new([0.0; 3], [0.0, 1.0, 0.0], [0.0; 2])
+    }
+}
+
+impl StandardVertex {
+    /// Creates a white vertex.
+    pub fn new(position: [f32; 3], normal: [f32; 3], uv: [f32; 2]) -> Self {
+        Self {
+            position,
+            normal,
+            uv,
+            color: [255; 4],
+        }
+    }
+
+    /// Returns the vertex with the given color.
+    pub fn with_color(mut self, color: [u8; 4]) -> Self {
+        self.color = color;
+        self
+    }
+}
+
+impl Vertex for StandardVertex {
+    fn layout() -> Vec<VertexAttributeLayout> {
+        vec![
+            VertexAttributeLayout {
+                index: POSITION_ATTRIBUTE,
+                size: 3,
+                r#type: gl::FLOAT,
+                normalized: gl::FALSE,
+                offset: mem::offset_of!(StandardVertex, position),
+            },
+            VertexAttributeLayout {
+                index: NORMAL_ATTRIBUTE,
+                size: 3,
+                r#type: gl::FLOAT,
+                normalized: gl::FALSE,
+                offset: mem::offset_of!(StandardVertex, normal),
+            },
+            VertexAttributeLayout {
+                index: UV_ATTRIBUTE,
+                size: 2,
+                r#type: gl::FLOAT,
+                normalized: gl::FALSE,
+                offset: mem::offset_of!(StandardVertex, uv),
+            },
+            VertexAttributeLayout {
+                index: COLOR_ATTRIBUTE,
+                size: 4,
+                r#type: gl::UNSIGNED_BYTE,
+                normalized: gl::TRUE,
+                offset: mem::offset_of!(StandardVertex, color),
+            },
+        ]
+    }
+}
+
+/// Set the color read by meshes without a color attribute.
+///
+/// The value is part of the context state, not of a VAO, so it only has to be set once.
+///
+/// # OpenGL Functions
+///
+/// This function is a wrapper around `glVertexAttrib4f(COLOR_ATTRIBUTE, ...)`.
+pub fn set_default_vertex_color(color: [f32; 4]) {
+    unsafe {
+        gl::VertexAttrib4f(COLOR_ATTRIBUTE, color[0], color[1], color[2], color[3]);
+    }
+}
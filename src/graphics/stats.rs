@@ -0,0 +1,294 @@
+//! # Stats Module
+//!
+//! This module keeps track of the GPU memory allocated through the crate's wrappers, split
+//! into textures, buffers and renderbuffers, and reads the driver's estimate of the free
+//! video memory where `GL_NVX_gpu_memory_info` or `GL_ATI_meminfo` is available.
+//!
+//! The crate-side numbers are estimates from the sizes and formats of the uploaded data; the
+//! driver may pad, compress or duplicate allocations. Memory allocated with raw `gl` calls is
+//! not included.
+//!
+//! It also counts the draw calls, instances, triangles, texture binds and shader switches
+//! issued by the crate, which `Scene::render` collects into `FrameStats` to quantify the
+//! effect of batching and culling.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::graphics::gl_info::GlInfo;
+//! use glwfr::graphics::stats::RendererStats;
+//!
+//! fn report(info: &GlInfo) {
+//!     let stats = RendererStats::query(info);
+//!     log::info!("{}", stats);
+//!     if let Some(available) = stats.vram_available {
+//!         if available < 256 * 1024 * 1024 {
+//!             log::warn!("Running low on video memory");
+//!         }
+//!     }
+//! }
+//!
+//! fn report_frame(scene: &glwfr::scene::Scene) {
+//!     let frame = scene.last_frame_stats();
+//!     log::debug!("{} draw calls, {} triangles", frame.draw_calls, frame.triangles);
+//! }
+//! ```
+
+use crate::graphics::gl_info::GlInfo;
+use crate::graphics::gl_wrapper::PrimitiveType;
+use gl::types::*;
+use std::cell::Cell;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// `GL_GPU_MEMORY_INFO_TOTAL_AVAILABLE_MEMORY_NVX`, in kilobytes.
+const GPU_MEMORY_INFO_TOTAL_AVAILABLE_MEMORY_NVX: GLenum = 0x9048;
+/// `GL_GPU_MEMORY_INFO_CURRENT_AVAILABLE_VIDMEM_NVX`, in kilobytes.
+const GPU_MEMORY_INFO_CURRENT_AVAILABLE_VIDMEM_NVX: GLenum = 0x9049;
+/// `GL_TEXTURE_FREE_MEMORY_ATI`, four values of which the first is the free pool in kilobytes.
+const TEXTURE_FREE_MEMORY_ATI: GLenum = 0x87FC;
+
+static TEXTURE_BYTES: AtomicUsize = AtomicUsize::new(0);
+static BUFFER_BYTES: AtomicUsize = AtomicUsize::new(0);
+static RENDERBUFFER_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    /// The draw counts accumulated on the context thread.
+    static FRAME_COUNTERS: Cell<FrameStats> = Cell::new(FrameStats::default());
+    /// The shader program bound last, to tell switches from rebinding the same program.
+    static BOUND_PROGRAM: Cell<GLuint> = const { Cell::new(0) };
+}
+
+/// The kind of GPU allocation a tracked size belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum MemoryCategory {
+    Texture,
+    Buffer,
+    Renderbuffer,
+}
+
+impl MemoryCategory {
+    fn counter(&self) -> &'static AtomicUsize {
+        match self {
+            MemoryCategory::Texture => &TEXTURE_BYTES,
+            MemoryCategory::Buffer => &BUFFER_BYTES,
+            MemoryCategory::Renderbuffer => &RENDERBUFFER_BYTES,
+        }
+    }
+}
+
+/// Records that an allocation changed its size from `old_size` to `new_size` bytes.
+///
+/// Wrappers call this whenever they reallocate storage, and with a `new_size` of `0` when
+/// they are dropped.
+pub(crate) fn track_allocation(category: MemoryCategory, old_size: usize, new_size: usize) {
+    let counter = category.counter();
+    if new_size >= old_size {
+        counter.fetch_add(new_size - old_size, Ordering::Relaxed);
+    } else {
+        // Saturate, so a missed allocation cannot wrap the counter around
+        let _ = counter.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bytes| {
+            Some(bytes.saturating_sub(old_size - new_size))
+        });
+    }
+}
+
+/// GPU memory usage of the renderer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RendererStats {
+    /// The estimated bytes used by all live `Texture`s, including mipmaps.
+    pub texture_bytes: usize,
+    /// The bytes used by all live `BufferObject`s and `Ebo`s.
+    pub buffer_bytes: usize,
+    /// The estimated bytes used by all live `Renderbuffer`s, including their samples.
+    pub renderbuffer_bytes: usize,
+    /// The total video memory reported by the driver in bytes, if it can be queried.
+    pub vram_total: Option<usize>,
+    /// The free video memory reported by the driver in bytes, if it can be queried.
+    pub vram_available: Option<usize>,
+}
+
+impl RendererStats {
+    /// Collects the tracked allocations and queries the driver for its memory usage.
+    ///
+    /// The driver is only asked if `info` advertises `GL_NVX_gpu_memory_info` or
+    /// `GL_ATI_meminfo`; otherwise `vram_total` and `vram_available` are `None`. The ATI
+    /// extension only reports the free memory.
+    ///
+    /// # Arguments
+    ///
+    /// * `info` - The capabilities of the current context, see `GlInfo::query`.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glGetIntegerv`.
+    pub fn query(info: &GlInfo) -> Self {
+        let mut stats = Self::tracked();
+        if info.has_extension("GL_NVX_gpu_memory_info") {
+            stats.vram_total = Some(Self::get_kilobytes(
+                GPU_MEMORY_INFO_TOTAL_AVAILABLE_MEMORY_NVX,
+            ));
+            stats.vram_available = Some(Self::get_kilobytes(
+                GPU_MEMORY_INFO_CURRENT_AVAILABLE_VIDMEM_NVX,
+            ));
+        } else if info.has_extension("GL_ATI_meminfo") {
+            stats.vram_available = Some(Self::get_kilobytes(TEXTURE_FREE_MEMORY_ATI));
+        }
+        stats
+    }
+
+    /// Returns the tracked allocations without asking the driver.
+    pub fn tracked() -> Self {
+        Self {
+            texture_bytes: TEXTURE_BYTES.load(Ordering::Relaxed),
+            buffer_bytes: BUFFER_BYTES.load(Ordering::Relaxed),
+            renderbuffer_bytes: RENDERBUFFER_BYTES.load(Ordering::Relaxed),
+            vram_total: None,
+            vram_available: None,
+        }
+    }
+
+    /// Returns the bytes allocated through the crate's wrappers.
+    pub fn tracked_bytes(&self) -> usize {
+        self.texture_bytes + self.buffer_bytes + self.renderbuffer_bytes
+    }
+
+    /// Returns the video memory in use by all applications, if the driver reports both the
+    /// total and the free memory.
+    pub fn vram_used(&self) -> Option<usize> {
+        Some(self.vram_total?.saturating_sub(self.vram_available?))
+    }
+
+    /// Reads a memory size the driver reports in kilobytes and converts it to bytes.
+    fn get_kilobytes(name: GLenum) -> usize {
+        // The ATI query writes four values
+        let mut values = [0; 4];
+        unsafe {
+            gl::GetIntegerv(name, values.as_mut_ptr());
+        }
+        values[0].max(0) as usize * 1024
+    }
+}
+
+impl fmt::Display for RendererStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const MIB: f64 = 1024.0 * 1024.0;
+        write!(
+            f,
+            "textures {:.1} MiB, buffers {:.1} MiB, renderbuffers {:.1} MiB",
+            self.texture_bytes as f64 / MIB,
+            self.buffer_bytes as f64 / MIB,
+            self.renderbuffer_bytes as f64 / MIB
+        )?;
+        match (self.vram_used(), self.vram_total, self.vram_available) {
+            (Some(used), Some(total), _) => write!(
+                f,
+                ", VRAM {:.0}/{:.0} MiB",
+                used as f64 / MIB,
+                total as f64 / MIB
+            ),
+            (_, _, Some(available)) => {
+                write!(f, ", VRAM {:.0} MiB free", available as f64 / MIB)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Draw counts of a frame or of any other span of rendering.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct FrameStats {
+    /// The number of draw calls, with every multi-draw counting once.
+    pub draw_calls: usize,
+    /// The number of instances drawn; non-instanced draws count as one instance. Indirect
+    /// draws are not included, since their parameters are stored on the GPU.
+    pub instances: usize,
+    /// The number of triangles drawn, over all instances. Indirect draws are not included.
+    pub triangles: usize,
+    /// The number of textures bound for sampling.
+    pub texture_binds: usize,
+    /// The number of times a different shader program was bound.
+    pub shader_switches: usize,
+}
+
+impl FrameStats {
+    /// Returns the counts accumulated on the current thread since the context was created.
+    ///
+    /// Subtract an earlier snapshot with `since` to measure a span of rendering.
+    pub fn counters() -> Self {
+        FRAME_COUNTERS.with(Cell::get)
+    }
+
+    /// Returns the counts accumulated between the `start` snapshot and `self`.
+    pub fn since(&self, start: &FrameStats) -> Self {
+        Self {
+            draw_calls: self.draw_calls.saturating_sub(start.draw_calls),
+            instances: self.instances.saturating_sub(start.instances),
+            triangles: self.triangles.saturating_sub(start.triangles),
+            texture_binds: self.texture_binds.saturating_sub(start.texture_binds),
+            shader_switches: self.shader_switches.saturating_sub(start.shader_switches),
+        }
+    }
+}
+
+impl fmt::Display for FrameStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} draw calls, {} instances, {} triangles, {} texture binds, {} shader switches",
+            self.draw_calls,
+            self.instances,
+            self.triangles,
+            self.texture_binds,
+            self.shader_switches
+        )
+    }
+}
+
+/// Applies a change to the draw counts of the current thread.
+fn update_counters(update: impl FnOnce(&mut FrameStats)) {
+    FRAME_COUNTERS.with(|counters| {
+        let mut stats = counters.get();
+        update(&mut stats);
+        counters.set(stats);
+    });
+}
+
+/// Records a draw call of `index_count` indices or vertices for each of `instances`.
+pub(crate) fn record_draw(primitive_type: PrimitiveType, index_count: usize, instances: usize) {
+    update_counters(|stats| {
+        stats.draw_calls += 1;
+        stats.instances += instances;
+        stats.triangles += primitive_type.triangle_count(index_count) * instances;
+    });
+}
+
+/// Records a multi-draw of several index ranges with a single call.
+pub(crate) fn record_multi_draw(primitive_type: PrimitiveType, index_counts: &[i32]) {
+    update_counters(|stats| {
+        stats.draw_calls += 1;
+        stats.instances += index_counts.len();
+        stats.triangles += index_counts
+            .iter()
+            .map(|&count| primitive_type.triangle_count(count.max(0) as usize))
+            .sum::<usize>();
+    });
+}
+
+/// Records an indirect draw, whose instance and triangle counts are unknown on the CPU.
+pub(crate) fn record_indirect_draw() {
+    update_counters(|stats| stats.draw_calls += 1);
+}
+
+/// Records a texture bound for sampling.
+pub(crate) fn record_texture_bind() {
+    update_counters(|stats| stats.texture_binds += 1);
+}
+
+/// Records that a shader program was bound, counting a switch if it differs from the last one.
+pub(crate) fn record_program_bind(program: GLuint) {
+    let previous = BOUND_PROGRAM.with(|bound| bound.replace(program));
+    if previous != program && program != 0 {
+        update_counters(|stats| stats.shader_switches += 1);
+    }
+}
@@ -0,0 +1,186 @@
+//! # Font Module
+//!
+//! Bitmap fonts in the AngelCode BMFont text format (`.fnt`), as exported by BMFont,
+//! Hiero, msdf-bmfont and most other glyph atlas generators.
+
+use crate::custom_errors::Errors;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// The metrics and atlas location of a single glyph, in font pixels.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Glyph {
+    /// The left edge of the glyph in the atlas page.
+    pub x: f32,
+    /// The top edge of the glyph in the atlas page.
+    pub y: f32,
+    /// The width of the glyph image.
+    pub width: f32,
+    /// The height of the glyph image.
+    pub height: f32,
+    /// The horizontal offset from the pen position to the left edge of the image.
+    pub x_offset: f32,
+    /// The vertical offset from the top of the line to the top edge of the image.
+    pub y_offset: f32,
+    /// How far the pen moves after the glyph.
+    pub x_advance: f32,
+    /// The index of the atlas page holding the glyph.
+    pub page: usize,
+}
+
+/// A font made of pre-rendered glyphs in one or more atlas pages.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BitmapFont {
+    /// The size the glyphs were rendered at, in pixels.
+    pub size: f32,
+    /// The distance between two lines, in font pixels.
+    pub line_height: f32,
+    /// The distance from the top of a line to the baseline, in font pixels.
+    pub base: f32,
+    /// The size of the atlas pages in pixels.
+    pub page_size: (u32, u32),
+    /// The file names of the atlas pages, as referenced by the font file.
+    pub pages: Vec<String>,
+    glyphs: HashMap<char, Glyph>,
+    kerning: HashMap<(char, char), f32>,
+}
+
+impl BitmapFont {
+    /// Loads a font from a BMFont text file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::FileLoadError` if the file cannot be read, or an
+    /// `Errors::FontLoadError` if it is malformed.
+    pub fn from_fnt<P: AsRef<Path>>(path: P) -> Result<Self, Errors> {
+        Self::from_fnt_str(&fs::read_to_string(path)?)
+    }
+
+    /// Parses a font from the contents of a BMFont text file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::FontLoadError` if the font is malformed.
+    pub fn from_fnt_str(source: &str) -> Result<Self, Errors> {
+        let mut font = Self::default();
+        for line in source.lines() {
+            let (kind, rest) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+            let fields = parse_fields(rest);
+            let number = |key: &str| -> Result<f32, Errors> {
+                fields
+                    .get(key)
+                    .ok_or_else(|| Errors::FontLoadError(format!("{} line without {}", kind, key)))?
+                    .parse()
+                    .map_err(|_| Errors::FontLoadError(format!("invalid {} in {} line", key, kind)))
+            };
+            let character = |key: &str| -> Result<char, Errors> {
+                let code = number(key)? as u32;
+                char::from_u32(code)
+                    .ok_or_else(|| Errors::FontLoadError(format!("invalid character {}", code)))
+            };
+
+            match kind {
+                "info" => font.size = number("size")?.abs(),
+                "common" => {
+                    font.line_height = number("lineHeight")?;
+                    font.base = number("base")?;
+                    font.page_size = (number("scaleW")? as u32, number("scaleH")? as u32);
+                }
+                "page" => {
+                    let id = number("id")? as usize;
+                    if font.pages.len() <= id {
+                        font.pages.resize(id + 1, String::new());
+                    }
+                    font.pages[id] = fields.get("file").cloned().unwrap_or_default();
+                }
+                "char" => {
+                    font.glyphs.insert(
+                        character("id")?,
+                        Glyph {
+                            x: number("x")?,
+                            y: number("y")?,
+                            width: number("width")?,
+                            height: number("height")?,
+                            x_offset: number("xoffset")?,
+                            y_offset: number("yoffset")?,
+                            x_advance: number("xadvance")?,
+                            page: number("page").unwrap_or(0.0) as usize,
+                        },
+                    );
+                }
+                "kerning" => {
+                    font.kerning.insert(
+                        (character("first")?, character("second")?),
+                        number("amount")?,
+                    );
+                }
+                _ => {}
+            }
+        }
+
+        if font.glyphs.is_empty() {
+            return Err(Errors::FontLoadError("font has no glyphs".to_string()));
+        }
+        if font.size == 0.0 {
+            font.size = font.line_height;
+        }
+        Ok(font)
+    }
+
+    /// Returns the glyph of a character, if the font has one.
+    pub fn glyph(&self, character: char) -> Option<&Glyph> {
+        self.glyphs.get(&character)
+    }
+
+    /// Returns the characters the font has glyphs for, in no particular order.
+    pub fn characters(&self) -> impl Iterator<Item = char> + '_ {
+        self.glyphs.keys().copied()
+    }
+
+    /// Returns `true` if the font has a glyph for the character.
+    pub fn has_glyph(&self, character: char) -> bool {
+        self.glyphs.contains_key(&character)
+    }
+
+    /// Returns the kerning adjustment between two characters, in font pixels.
+    pub fn kerning(&self, first: char, second: char) -> f32 {
+        self.kerning.get(&(first, second)).copied().unwrap_or(0.0)
+    }
+
+    /// Returns the texture coordinates of a glyph as `[left, top, right, bottom]`.
+    pub fn glyph_uvs(&self, glyph: &Glyph) -> [f32; 4] {
+        let (width, height) = (
+            self.page_size.0.max(1) as f32,
+            self.page_size.1.max(1) as f32,
+        );
+        [
+            glyph.x / width,
+            glyph.y / height,
+            (glyph.x + glyph.width) / width,
+            (glyph.y + glyph.height) / height,
+        ]
+    }
+}
+
+/// Splits the `key=value` fields of a BMFont line. Quoted values may contain spaces.
+fn parse_fields(mut line: &str) -> HashMap<&str, String> {
+    let mut fields = HashMap::new();
+    loop {
+        line = line.trim_start();
+        let Some(equals) = line.find('=') else {
+            return fields;
+        };
+        let key = line[..equals].trim();
+        let value = &line[equals + 1..];
+        let (value, rest) = match value.strip_prefix('"') {
+            Some(quoted) => match quoted.find('"') {
+                Some(end) => (&quoted[..end], &quoted[end + 1..]),
+                None => (quoted, ""),
+            },
+            None => value.split_once(' ').unwrap_or((value, "")),
+        };
+        fields.insert(key, value.to_string());
+        line = rest;
+    }
+}
@@ -0,0 +1,235 @@
+//! # HarfBuzz Module
+//!
+//! Complex script shaping with rustybuzz, a Rust port of HarfBuzz. It is available with the
+//! `harfbuzz` feature.
+//!
+//! Bitmap fonts only know characters, so a font is shaped with the OpenType file its atlas
+//! was rendered from. The glyphs HarfBuzz picks are looked up in the atlas through the
+//! characters the OpenType font maps to them. Contextual forms and ligatures therefore have
+//! to be in the atlas under the character of a presentation form, e.g. the Arabic
+//! Presentation Forms-B block (`U+FE70`-`U+FEFC`) for Arabic or `U+FB01` for the `fi`
+//! ligature. Glyphs without such a character fall back to the glyph of their source character.
+//!
+//! A line is split into runs of one font and one direction, which are shaped separately and
+//! placed in text order. Right-to-left runs are reversed within the run, but a line starting
+//! with right-to-left text is not reordered as a whole.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::graphics::text::{shape, BitmapFont, FontChain};
+//!
+//! fn main() -> Result<(), glwfr::custom_errors::Errors> {
+//!     let mut chain = FontChain::new(BitmapFont::from_fnt("fonts/latin.fnt")?)
+//!         .with_fallback(BitmapFont::from_fnt("fonts/arabic.fnt")?);
+//!     // The font file the Arabic atlas was generated from
+//!     chain.load_face(1, "fonts/NotoSansArabic-Regular.ttf")?;
+//!
+//!     let line = shape(&chain, "Player: مرحبا", 24.0);
+//!     println!("{} glyphs, {} pixels wide", line.glyphs.len(), line.width);
+//!     Ok(())
+//! }
+//! ```
+
+use crate::custom_errors::Errors;
+use crate::graphics::text::font::BitmapFont;
+use crate::graphics::text::shaping::{
+    baseline, is_combining_mark, is_invisible, shape_simple, FontChain, ShapedGlyph, ShapedLine,
+};
+use rustybuzz::{Face, UnicodeBuffer};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+/// The OpenType font a bitmap font was rendered from, used to shape its text.
+#[derive(Debug, Clone)]
+pub struct ShapingFace {
+    data: Arc<Vec<u8>>,
+    units_per_em: f32,
+    /// The atlas character of each OpenType glyph the bitmap font has an image for.
+    characters: HashMap<u32, char>,
+}
+
+impl ShapingFace {
+    /// Parses an OpenType face and maps its glyphs to the characters of a bitmap font.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::FontLoadError` if the data is not a TrueType or OpenType font.
+    pub fn new(data: Vec<u8>, font: &BitmapFont) -> Result<Self, Errors> {
+        let face = Face::from_slice(&data, 0)
+            .ok_or_else(|| Errors::FontLoadError("not a TrueType or OpenType font".into()))?;
+        let mut atlas_characters: Vec<char> = font.characters().collect();
+        // Prefer the lowest character when several map to the same glyph
+        atlas_characters.sort_unstable();
+        let mut characters = HashMap::new();
+        for character in atlas_characters {
+            if let Some(glyph) = face.glyph_index(character) {
+                characters.entry(u32::from(glyph.0)).or_insert(character);
+            }
+        }
+        let units_per_em = face.units_per_em().max(1) as f32;
+        Ok(Self {
+            data: Arc::new(data),
+            units_per_em,
+            characters,
+        })
+    }
+
+    /// Returns the atlas character of an OpenType glyph, if the bitmap font has one.
+    pub fn character(&self, glyph_id: u32) -> Option<char> {
+        self.characters.get(&glyph_id).copied()
+    }
+}
+
+impl FontChain {
+    /// Shapes the text of a font of the chain with HarfBuzz, using the OpenType font the
+    /// bitmap font was rendered from.
+    ///
+    /// # Arguments
+    ///
+    /// * `font` - The index of the font in the chain, `0` for the primary font.
+    /// * `data` - The contents of the `.ttf` or `.otf` file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::FontLoadError` if there is no font with the index or the data is
+    /// not a TrueType or OpenType font.
+    pub fn set_face(&mut self, font: usize, data: Vec<u8>) -> Result<(), Errors> {
+        let bitmap = self
+            .fonts
+            .get(font)
+            .ok_or_else(|| Errors::FontLoadError(format!("no font with index {}", font)))?;
+        let face = ShapingFace::new(data, bitmap)?;
+        self.faces.insert(font, face);
+        Ok(())
+    }
+
+    /// Like `set_face`, but reads the OpenType font from a file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::FileLoadError` if the file cannot be read, or an
+    /// `Errors::FontLoadError` as described for `set_face`.
+    pub fn load_face<P: AsRef<Path>>(&mut self, font: usize, path: P) -> Result<(), Errors> {
+        self.set_face(font, fs::read(path)?)
+    }
+
+    /// Stops shaping a font with HarfBuzz.
+    pub fn remove_face(&mut self, font: usize) {
+        self.faces.remove(&font);
+    }
+
+    /// Returns the shaping face of a font, if it has one.
+    pub fn face(&self, font: usize) -> Option<&ShapingFace> {
+        self.faces.get(&font)
+    }
+}
+
+/// Returns `true` for characters of right-to-left scripts: Hebrew, Arabic, Syriac, Thaana,
+/// N'Ko, Samaritan, Mandaic and their presentation forms.
+fn is_right_to_left(character: char) -> bool {
+    matches!(
+        character as u32,
+        0x0590..=0x08FF | 0xFB1D..=0xFDFF | 0xFE70..=0xFEFC
+    )
+}
+
+/// Returns `true` for characters that take the direction of the surrounding text.
+fn is_neutral(character: char) -> bool {
+    character.is_whitespace() || character.is_ascii_punctuation() || character.is_ascii_digit()
+}
+
+/// Splits a line into runs of one font and direction and appends their glyphs to the line.
+/// Runs in fonts without a shaping face are shaped character by character.
+pub(crate) fn shape_runs(chain: &FontChain, text: &str, size: f32, line: &mut ShapedLine) {
+    // The start, font and direction of the current run
+    let mut run: Option<(usize, usize, bool)> = None;
+    for (index, character) in text.char_indices() {
+        let attached = is_invisible(character) || is_combining_mark(character);
+        let font = match run {
+            // Keep spaces and punctuation in the run if its font has them
+            Some((_, font, _))
+                if attached
+                    || (is_neutral(character) && chain.fonts[font].has_glyph(character)) =>
+            {
+                font
+            }
+            _ => chain
+                .resolve_or_replace(character)
+                .map_or(0, |(font, _, _)| font),
+        };
+        let right_to_left = match run {
+            Some((_, _, right_to_left)) if attached || is_neutral(character) => right_to_left,
+            _ => is_right_to_left(character),
+        };
+        match run {
+            Some((start, run_font, run_right_to_left))
+                if run_font != font || run_right_to_left != right_to_left =>
+            {
+                shape_run(chain, run_font, &text[start..index], size, line);
+                run = Some((index, font, right_to_left));
+            }
+            None => run = Some((index, font, right_to_left)),
+            _ => {}
+        }
+    }
+    if let Some((start, font, _)) = run {
+        shape_run(chain, font, &text[start..], size, line);
+    }
+}
+
+/// Shapes a run of text in a single font and appends its glyphs to the line.
+fn shape_run(chain: &FontChain, font_index: usize, text: &str, size: f32, line: &mut ShapedLine) {
+    let Some((shaping_face, face)) = chain
+        .faces
+        .get(&font_index)
+        .and_then(|shaping| Some((shaping, Face::from_slice(&shaping.data, 0)?)))
+    else {
+        shape_simple(chain, text, size, line);
+        return;
+    };
+    let font = &chain.fonts[font_index];
+    let scale = size / font.size.max(1.0);
+    let units = size / shaping_face.units_per_em;
+    let baseline = baseline(chain, size);
+
+    let mut buffer = UnicodeBuffer::new();
+    buffer.push_str(text);
+    buffer.guess_segment_properties();
+    let glyphs = rustybuzz::shape(&face, &[], buffer);
+
+    let mut pen = line.width;
+    for (info, position) in glyphs.glyph_infos().iter().zip(glyphs.glyph_positions()) {
+        let source = text[info.cluster as usize..].chars().next().unwrap_or(' ');
+        let atlas_glyph = shaping_face
+            .character(info.glyph_id)
+            .and_then(|character| font.glyph(character).map(|glyph| (character, glyph)))
+            .or_else(|| font.glyph(source).map(|glyph| (source, glyph)))
+            .or_else(|| {
+                chain
+                    .resolve_or_replace(source)
+                    .filter(|&(index, _, _)| index == font_index)
+                    .map(|(_, character, glyph)| (character, glyph))
+            });
+        if let Some((character, glyph)) = atlas_glyph {
+            if !is_invisible(character) {
+                line.glyphs.push(ShapedGlyph {
+                    font: font_index,
+                    page: glyph.page,
+                    character,
+                    x: pen + position.x_offset as f32 * units + glyph.x_offset * scale,
+                    y: baseline
+                        - (font.base - glyph.y_offset) * scale
+                        - position.y_offset as f32 * units,
+                    width: glyph.width * scale,
+                    height: glyph.height * scale,
+                    uvs: font.glyph_uvs(glyph),
+                });
+            }
+        }
+        pen += position.x_advance as f32 * units;
+    }
+    line.width = pen;
+}
@@ -0,0 +1,235 @@
+//! # Text Layout Module
+//!
+//! Word wrapping, alignment, line spacing and ellipsis truncation of shaped text.
+
+use crate::graphics::text::shaping::{shape, FontChain, ShapedGlyph};
+
+/// The horizontal alignment of the lines of a text layout.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+/// Options controlling how text is broken into lines and placed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LayoutOptions {
+    /// The width lines are wrapped at, or `None` to only break at line breaks.
+    pub max_width: Option<f32>,
+    /// The maximum number of lines, or `None` for no limit.
+    pub max_lines: Option<usize>,
+    /// The alignment of the lines within `max_width`, or within the widest line without it.
+    pub align: TextAlign,
+    /// The distance between lines as a multiple of the font's line height.
+    pub line_spacing: f32,
+    /// Whether text cut off by `max_lines`, or a single word wider than `max_width`, ends in
+    /// an ellipsis.
+    pub ellipsis: bool,
+}
+
+impl Default for LayoutOptions {
+    fn default() -> Self {
+        Self {
+            max_width: None,
+            max_lines: None,
+            align: TextAlign::Left,
+            line_spacing: 1.0,
+            ellipsis: false,
+        }
+    }
+}
+
+impl LayoutOptions {
+    /// Creates options that wrap text at the given width.
+    pub fn wrapped(max_width: f32) -> Self {
+        Self {
+            max_width: Some(max_width),
+            ..Self::default()
+        }
+    }
+}
+
+/// The bounds of one line of a `TextLayout`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayoutLine {
+    /// The range of the line's glyphs in `TextLayout::glyphs`.
+    pub glyphs: std::ops::Range<usize>,
+    /// The left edge of the line after alignment.
+    pub x: f32,
+    /// The top edge of the line.
+    pub y: f32,
+    /// The advance width of the line.
+    pub width: f32,
+    /// The height of the line.
+    pub height: f32,
+}
+
+/// Text broken into lines, with every glyph placed relative to the top-left corner.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TextLayout {
+    /// The placed glyphs of all lines.
+    pub glyphs: Vec<ShapedGlyph>,
+    /// The lines, from top to bottom.
+    pub lines: Vec<LayoutLine>,
+    /// The width of the widest line.
+    pub width: f32,
+    /// The distance from the top of the first line to the bottom of the last line.
+    pub height: f32,
+    /// Whether text was cut off by `max_lines`.
+    pub truncated: bool,
+}
+
+/// Breaks text into lines and places its glyphs.
+///
+/// # Arguments
+///
+/// * `chain` - The fonts to draw the text with.
+/// * `text` - The text to lay out. Line breaks always start a new line.
+/// * `size` - The font size in pixels.
+/// * `options` - How the text is wrapped and aligned.
+pub fn layout(chain: &FontChain, text: &str, size: f32, options: &LayoutOptions) -> TextLayout {
+    let primary = chain.primary();
+    let line_height = primary.line_height * size / primary.size.max(1.0);
+    let line_advance = line_height * options.line_spacing;
+    let width_of = |line: &str| shape(chain, line, size).width;
+    let ellipsis = match chain.resolve('\u{2026}') {
+        Some(_) => "\u{2026}",
+        None => "...",
+    };
+
+    let mut lines: Vec<String> = Vec::new();
+    let mut truncated = false;
+    'paragraphs: for paragraph in text.split('\n') {
+        let paragraph = paragraph.trim_end_matches('\r');
+        let wrapped = match options.max_width {
+            Some(max_width) => {
+                let ellipsis = options.ellipsis.then_some(ellipsis);
+                wrap(paragraph, max_width, ellipsis, &width_of)
+            }
+            None => vec![paragraph.to_string()],
+        };
+        for line in wrapped {
+            if options.max_lines.is_some_and(|max| lines.len() >= max) {
+                truncated = true;
+                break 'paragraphs;
+            }
+            lines.push(line);
+        }
+    }
+    if truncated && options.ellipsis {
+        if let Some(last) = lines.last_mut() {
+            let max_width = options.max_width.unwrap_or(f32::INFINITY);
+            *last = with_ellipsis(last.trim_end(), ellipsis, max_width, &width_of);
+        }
+    }
+
+    let shaped: Vec<_> = lines.iter().map(|line| shape(chain, line, size)).collect();
+    let width = shaped.iter().map(|line| line.width).fold(0.0, f32::max);
+    let area = options.max_width.unwrap_or(width);
+
+    let mut result = TextLayout {
+        width,
+        truncated,
+        ..TextLayout::default()
+    };
+    for (index, line) in shaped.into_iter().enumerate() {
+        let x = match options.align {
+            TextAlign::Left => 0.0,
+            TextAlign::Center => (area - line.width) / 2.0,
+            TextAlign::Right => area - line.width,
+        };
+        let y = index as f32 * line_advance;
+        let start = result.glyphs.len();
+        result
+            .glyphs
+            .extend(line.glyphs.into_iter().map(|mut glyph| {
+                glyph.x += x;
+                glyph.y += y;
+                glyph
+            }));
+        result.lines.push(LayoutLine {
+            glyphs: start..result.glyphs.len(),
+            x,
+            y,
+            width: line.width,
+            height: line_height,
+        });
+    }
+    result.height = match result.lines.len() {
+        0 => 0.0,
+        count => (count - 1) as f32 * line_advance + line_height,
+    };
+    result
+}
+
+/// Greedily breaks a paragraph at spaces so each line fits into `max_width`. Words wider
+/// than a whole line are broken between characters, or cut with an ellipsis.
+fn wrap(
+    paragraph: &str,
+    max_width: f32,
+    ellipsis: Option<&str>,
+    width_of: &impl Fn(&str) -> f32,
+) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in paragraph.split(' ') {
+        let candidate = if line.is_empty() {
+            word.to_string()
+        } else {
+            format!("{} {}", line, word)
+        };
+        if width_of(&candidate) <= max_width || candidate.is_empty() {
+            line = candidate;
+            continue;
+        }
+        if !line.is_empty() {
+            lines.push(std::mem::take(&mut line));
+        }
+        if width_of(word) <= max_width {
+            line = word.to_string();
+        } else if let Some(ellipsis) = ellipsis {
+            lines.push(with_ellipsis(word, ellipsis, max_width, width_of));
+        } else {
+            // Break the word between characters
+            for character in word.chars() {
+                let mut candidate = line.clone();
+                candidate.push(character);
+                if width_of(&candidate) > max_width && !line.is_empty() {
+                    lines.push(std::mem::take(&mut line));
+                    line.push(character);
+                } else {
+                    line = candidate;
+                }
+            }
+        }
+    }
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// Shortens a line until it fits into `max_width` with an ellipsis appended.
+fn with_ellipsis(
+    line: &str,
+    ellipsis: &str,
+    max_width: f32,
+    width_of: &impl Fn(&str) -> f32,
+) -> String {
+    let mut characters: Vec<char> = line.chars().collect();
+    loop {
+        let candidate: String = characters.iter().collect::<String>() + ellipsis;
+        if characters.is_empty() || width_of(&candidate) <= max_width {
+            return candidate;
+        }
+        characters.pop();
+        while characters
+            .last()
+            .is_some_and(|character| character.is_whitespace())
+        {
+            characters.pop();
+        }
+    }
+}
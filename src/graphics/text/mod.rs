@@ -0,0 +1,54 @@
+//! # Text Module
+//!
+//! This module provides text rendering with bitmap fonts. Characters missing from the
+//! primary font are looked up in a chain of fallback fonts, so player names and localized
+//! strings in other scripts still render.
+//!
+//! ## Submodules
+//! - **font**: Bitmap fonts in the BMFont text format.
+//! - **shaping**: Font fallback chains, kerning and combining marks.
+//! - **harfbuzz**: Complex script shaping with HarfBuzz (`harfbuzz` feature).
+//! - **layout**: Word wrapping, alignment and truncation.
+//! - **renderer**: Drawing of shaped text.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::graphics::text::{BitmapFont, FontChain, LayoutOptions, TextAlign, TextRenderer};
+//!
+//! fn main() -> Result<(), glwfr::custom_errors::Errors> {
+//!     let chain = FontChain::new(BitmapFont::from_fnt("fonts/latin.fnt")?)
+//!         .with_fallback(BitmapFont::from_fnt("fonts/cyrillic.fnt")?)
+//!         .with_fallback(BitmapFont::from_fnt("fonts/cjk.fnt")?);
+//!     let mut text = TextRenderer::from_directory(chain, "fonts")?;
+//!
+//!     // With a 2D text shader bound
+//!     text.draw("Player: Дмитрий 山田", 16.0, 16.0, 24.0);
+//!
+//!     // A centered dialog box, 300 pixels wide and at most four lines high
+//!     let options = LayoutOptions {
+//!         max_width: Some(300.0),
+//!         max_lines: Some(4),
+//!         align: TextAlign::Center,
+//!         ellipsis: true,
+//!         ..LayoutOptions::default()
+//!     };
+//!     let dialog = text.layout("A long line of dialog...", 20.0, &options);
+//!     text.draw_layout(&dialog, 250.0, 400.0);
+//!     Ok(())
+//! }
+//! ```
+
+pub mod font;
+#[cfg(feature = "harfbuzz")]
+pub mod harfbuzz;
+pub mod layout;
+pub mod renderer;
+pub mod shaping;
+
+pub use font::*;
+#[cfg(feature = "harfbuzz")]
+pub use harfbuzz::*;
+pub use layout::*;
+pub use renderer::*;
+pub use shaping::*;
@@ -0,0 +1,270 @@
+//! # Text Renderer Module
+//!
+//! Draws shaped text as textured quads, batched by atlas page.
+
+use crate::custom_errors::Errors;
+use crate::graphics::gl_wrapper::{
+    BufferObject, Ebo, PrimitiveType, Vao, Vertex, VertexAttributeLayout,
+};
+use crate::graphics::stats;
+use crate::graphics::text::layout::{layout, LayoutOptions, TextLayout};
+use crate::graphics::text::shaping::{shape, FontChain, ShapedGlyph};
+use crate::graphics::texture::Texture;
+use gl::types::*;
+use std::path::Path;
+
+/// The attribute location of the position of a `TextVertex`.
+pub const TEXT_POSITION_ATTRIBUTE: u32 = 0;
+
+/// The attribute location of the texture coordinates of a `TextVertex`.
+pub const TEXT_UV_ATTRIBUTE: u32 = 2;
+
+/// A vertex of a glyph quad with a 2D position and texture coordinates.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[repr(C)]
+pub struct TextVertex {
+    pub position: [f32; 2],
+    pub uv: [f32; 2],
+}
+
+impl Vertex for TextVertex {
+    fn layout() -> Vec<VertexAttributeLayout> {
+        vec![
+            VertexAttributeLayout {
+                index: TEXT_POSITION_ATTRIBUTE,
+                size: 2,
+                r#type: gl::FLOAT,
+                normalized: gl::FALSE,
+                offset: 0,
+            },
+            VertexAttributeLayout {
+                index: TEXT_UV_ATTRIBUTE,
+                size: 2,
+                r#type: gl::FLOAT,
+                normalized: gl::FALSE,
+                offset: std::mem::size_of::<[f32; 2]>(),
+            },
+        ]
+    }
+}
+
+/// Draws text with a `FontChain`.
+///
+/// Positions are in pixels with the origin in the top-left corner and `y` growing downwards.
+/// The shader program must be bound before drawing and sample the glyph atlas from texture
+/// unit 0.
+pub struct TextRenderer {
+    chain: FontChain,
+    pages: Vec<Vec<Texture>>,
+    vao: Vao,
+    vbo: BufferObject,
+    ebo: Ebo,
+    capacity: usize,
+}
+
+impl TextRenderer {
+    /// Creates a text renderer from a font chain and the atlas pages of each of its fonts.
+    ///
+    /// # Arguments
+    ///
+    /// * `chain` - The fonts to draw text with.
+    /// * `pages` - The atlas page textures of each font, in the order of the chain.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::OpenGlError` if the buffers cannot be created.
+    pub fn new(chain: FontChain, pages: Vec<Vec<Texture>>) -> Result<Self, Errors> {
+        let vao = Vao::new()?;
+        vao.bind();
+        let vbo = BufferObject::new(gl::ARRAY_BUFFER, gl::DYNAMIC_DRAW)?;
+        vbo.bind();
+        let ebo = Ebo::new()?;
+        ebo.bind();
+        TextVertex::setup_attributes();
+        vao.unbind();
+        Ok(Self {
+            chain,
+            pages,
+            vao,
+            vbo,
+            ebo,
+            capacity: 0,
+        })
+    }
+
+    /// Creates a text renderer and loads the atlas pages of every font of the chain from a
+    /// directory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::TextureLoadError` if a page cannot be loaded, or an
+    /// `Errors::OpenGlError` if the buffers cannot be created.
+    pub fn from_directory<P: AsRef<Path>>(chain: FontChain, directory: P) -> Result<Self, Errors> {
+        let mut pages = Vec::new();
+        for font in chain.fonts() {
+            let mut font_pages = Vec::new();
+            for file in &font.pages {
+                let texture = Texture::new();
+                texture.load_from_file(directory.as_ref().join(file))?;
+                font_pages.push(texture);
+            }
+            pages.push(font_pages);
+        }
+        Self::new(chain, pages)
+    }
+
+    /// Returns the font chain.
+    pub fn chain(&self) -> &FontChain {
+        &self.chain
+    }
+
+    /// Returns the distance between two lines at the given font size.
+    pub fn line_height(&self, size: f32) -> f32 {
+        let primary = self.chain.primary();
+        primary.line_height * size / primary.size.max(1.0)
+    }
+
+    /// Returns the size of text as `(width, height)`, e.g. to size a dialog box around it.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to measure.
+    /// * `size` - The font size in pixels.
+    /// * `max_width` - The width the text is wrapped at, or `None` to only break at line
+    ///   breaks.
+    pub fn measure(&self, text: &str, size: f32, max_width: Option<f32>) -> (f32, f32) {
+        let options = LayoutOptions {
+            max_width,
+            ..LayoutOptions::default()
+        };
+        let layout = layout(&self.chain, text, size, &options);
+        (layout.width, layout.height)
+    }
+
+    /// Breaks text into lines and places its glyphs, without drawing it.
+    ///
+    /// The returned layout holds the bounds of every glyph and line, for hit testing, cursors
+    /// and selections, and can be drawn with `draw_layout`.
+    pub fn layout(&self, text: &str, size: f32, options: &LayoutOptions) -> TextLayout {
+        layout(&self.chain, text, size, options)
+    }
+
+    /// Draws a layout with its top-left corner at the given position.
+    pub fn draw_layout(&mut self, layout: &TextLayout, x: f32, y: f32) {
+        let glyphs: Vec<ShapedGlyph> = layout
+            .glyphs
+            .iter()
+            .map(|glyph| ShapedGlyph {
+                x: glyph.x + x,
+                y: glyph.y + y,
+                ..*glyph
+            })
+            .collect();
+        self.draw_glyphs(&glyphs);
+    }
+
+    /// Draws text with its top-left corner at the given position. Line breaks start a new
+    /// line.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to draw.
+    /// * `x` - The left edge of the text.
+    /// * `y` - The top edge of the first line.
+    /// * `size` - The font size in pixels.
+    pub fn draw(&mut self, text: &str, x: f32, y: f32, size: f32) {
+        let line_height = self.line_height(size);
+        let mut glyphs = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            let top = y + index as f32 * line_height;
+            glyphs.extend(
+                shape(&self.chain, line, size)
+                    .glyphs
+                    .into_iter()
+                    .map(|mut glyph| {
+                        glyph.x += x;
+                        glyph.y += top;
+                        glyph
+                    }),
+            );
+        }
+        self.draw_glyphs(&glyphs);
+    }
+
+    /// Draws glyphs that were already placed, e.g. by a text layout.
+    pub fn draw_glyphs(&mut self, glyphs: &[ShapedGlyph]) {
+        if glyphs.is_empty() {
+            return;
+        }
+
+        // Group the quads by atlas page so each page is drawn with one call
+        let mut sorted: Vec<&ShapedGlyph> = glyphs.iter().collect();
+        sorted.sort_by_key(|glyph| (glyph.font, glyph.page));
+        let mut vertices = Vec::with_capacity(sorted.len() * 4);
+        for glyph in &sorted {
+            let [left, top, right, bottom] = glyph.uvs;
+            let (x0, y0) = (glyph.x, glyph.y);
+            let (x1, y1) = (glyph.x + glyph.width, glyph.y + glyph.height);
+            vertices.extend_from_slice(&[
+                TextVertex {
+                    position: [x0, y0],
+                    uv: [left, top],
+                },
+                TextVertex {
+                    position: [x1, y0],
+                    uv: [right, top],
+                },
+                TextVertex {
+                    position: [x1, y1],
+                    uv: [right, bottom],
+                },
+                TextVertex {
+                    position: [x0, y1],
+                    uv: [left, bottom],
+                },
+            ]);
+        }
+
+        self.vao.bind();
+        self.vbo.bind();
+        if sorted.len() > self.capacity {
+            self.capacity = sorted.len().next_power_of_two();
+            self.vbo
+                .allocate(self.capacity * 4 * std::mem::size_of::<TextVertex>());
+            let indices: Vec<u32> = (0..self.capacity as u32)
+                .flat_map(|quad| {
+                    let first = quad * 4;
+                    [first, first + 3, first + 1, first + 1, first + 3, first + 2]
+                })
+                .collect();
+            self.ebo.bind();
+            self.ebo.store_indices(&indices);
+        } else {
+            self.vbo.orphan();
+        }
+        self.vbo.update_data(0, &vertices);
+
+        let mut start = 0;
+        while start < sorted.len() {
+            let key = (sorted[start].font, sorted[start].page);
+            let count = sorted[start..]
+                .iter()
+                .take_while(|glyph| (glyph.font, glyph.page) == key)
+                .count();
+            if let Some(texture) = self.pages.get(key.0).and_then(|pages| pages.get(key.1)) {
+                texture.bind(gl::TEXTURE0);
+                unsafe {
+                    gl::DrawElements(
+                        gl::TRIANGLES,
+                        (count * 6) as GLsizei,
+                        gl::UNSIGNED_INT,
+                        (start * 6 * std::mem::size_of::<u32>()) as *const _,
+                    );
+                }
+                stats::record_draw(PrimitiveType::Triangles, count * 6, 1);
+            }
+            start += count;
+        }
+        self.vao.unbind();
+    }
+}
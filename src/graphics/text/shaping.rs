@@ -0,0 +1,227 @@
+//! # Shaping Module
+//!
+//! Font fallback chains and basic text shaping: glyph selection across fonts, kerning and
+//! placement of combining marks over their base character.
+//!
+//! By default shaping works character by character, which covers Latin, Cyrillic, Greek, CJK
+//! and other scripts without contextual forms. Scripts that need ligatures or reordering, such
+//! as Arabic or Devanagari, are rendered with their isolated forms unless the `harfbuzz`
+//! feature is enabled and the fonts have a shaping face (see `FontChain::set_face`).
+
+use crate::graphics::text::font::{BitmapFont, Glyph};
+#[cfg(feature = "harfbuzz")]
+use crate::graphics::text::harfbuzz::{self, ShapingFace};
+#[cfg(feature = "harfbuzz")]
+use std::collections::HashMap;
+
+/// The characters tried, in order, when no font of a chain has a glyph for a character.
+const REPLACEMENT_CHARACTERS: [char; 2] = ['\u{FFFD}', '?'];
+
+/// Returns `true` for combining marks, which are drawn over the preceding character instead
+/// of after it, such as the accents of decomposed `e\u{301}`.
+pub fn is_combining_mark(character: char) -> bool {
+    matches!(
+        character as u32,
+        0x0300..=0x036F
+            | 0x0483..=0x0489
+            | 0x0591..=0x05BD
+            | 0x0610..=0x061A
+            | 0x064B..=0x065F
+            | 0x0E31
+            | 0x0E34..=0x0E3A
+            | 0x0E47..=0x0E4E
+            | 0x1AB0..=0x1AFF
+            | 0x1DC0..=0x1DFF
+            | 0x20D0..=0x20FF
+            | 0x3099..=0x309A
+            | 0xFE20..=0xFE2F
+    )
+}
+
+/// Returns `true` for characters that take no space and have no glyph, such as zero-width
+/// joiners and variation selectors.
+pub(crate) fn is_invisible(character: char) -> bool {
+    matches!(
+        character as u32,
+        0x200B..=0x200F | 0x2060..=0x2064 | 0xFE00..=0xFE0F | 0xFEFF
+    )
+}
+
+/// A list of fonts searched in order for each character.
+///
+/// The first font is the primary font. Fallback fonts provide glyphs the primary font lacks,
+/// e.g. CJK or Cyrillic characters in player names.
+#[derive(Debug, Clone)]
+pub struct FontChain {
+    pub(crate) fonts: Vec<BitmapFont>,
+    /// The OpenType faces of the fonts that are shaped with HarfBuzz, by font index.
+    #[cfg(feature = "harfbuzz")]
+    pub(crate) faces: HashMap<usize, ShapingFace>,
+}
+
+impl FontChain {
+    /// Creates a chain with only a primary font.
+    pub fn new(primary: BitmapFont) -> Self {
+        Self {
+            fonts: vec![primary],
+            #[cfg(feature = "harfbuzz")]
+            faces: HashMap::new(),
+        }
+    }
+
+    /// Adds a fallback font after the fonts already in the chain.
+    pub fn with_fallback(mut self, font: BitmapFont) -> Self {
+        self.fonts.push(font);
+        self
+    }
+
+    /// Adds a fallback font after the fonts already in the chain.
+    pub fn push_fallback(&mut self, font: BitmapFont) {
+        self.fonts.push(font);
+    }
+
+    /// Returns the fonts of the chain, the primary font first.
+    pub fn fonts(&self) -> &[BitmapFont] {
+        &self.fonts
+    }
+
+    /// Returns the primary font.
+    pub fn primary(&self) -> &BitmapFont {
+        &self.fonts[0]
+    }
+
+    /// Returns the index of the first font with a glyph for the character, and the glyph.
+    pub fn resolve(&self, character: char) -> Option<(usize, &Glyph)> {
+        self.fonts
+            .iter()
+            .enumerate()
+            .find_map(|(index, font)| font.glyph(character).map(|glyph| (index, glyph)))
+    }
+
+    /// Like `resolve`, but falls back to a replacement character for missing glyphs.
+    pub(crate) fn resolve_or_replace(&self, character: char) -> Option<(usize, char, &Glyph)> {
+        std::iter::once(character)
+            .chain(REPLACEMENT_CHARACTERS)
+            .find_map(|candidate| {
+                self.resolve(candidate)
+                    .map(|(font, glyph)| (font, candidate, glyph))
+            })
+    }
+}
+
+/// A glyph placed by `shape`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShapedGlyph {
+    /// The index of the font in the chain.
+    pub font: usize,
+    /// The atlas page of the font holding the glyph.
+    pub page: usize,
+    /// The character the glyph was chosen for, or the replacement character. Glyphs substituted
+    /// by HarfBuzz, such as ligatures, hold a character the font maps to the same glyph.
+    pub character: char,
+    /// The left edge of the glyph image, relative to the start of the line.
+    pub x: f32,
+    /// The top edge of the glyph image, relative to the top of the line.
+    pub y: f32,
+    /// The width of the glyph image.
+    pub width: f32,
+    /// The height of the glyph image.
+    pub height: f32,
+    /// The texture coordinates as `[left, top, right, bottom]`.
+    pub uvs: [f32; 4],
+}
+
+/// A single line of shaped text.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ShapedLine {
+    /// The placed glyphs, in text order.
+    pub glyphs: Vec<ShapedGlyph>,
+    /// The advance width of the line.
+    pub width: f32,
+}
+
+/// Places the glyphs of a single line of text.
+///
+/// Fonts with a different rendering size than the primary font are scaled to match it, and
+/// glyphs of fallback fonts are aligned on the primary font's baseline. With the `harfbuzz`
+/// feature, runs of text in fonts with a shaping face are shaped with HarfBuzz.
+///
+/// # Arguments
+///
+/// * `chain` - The fonts to draw the text with.
+/// * `text` - The text to shape. Line breaks are treated like any other character.
+/// * `size` - The font size in pixels.
+pub fn shape(chain: &FontChain, text: &str, size: f32) -> ShapedLine {
+    let mut line = ShapedLine::default();
+    #[cfg(feature = "harfbuzz")]
+    if !chain.faces.is_empty() {
+        harfbuzz::shape_runs(chain, text, size, &mut line);
+        return line;
+    }
+    shape_simple(chain, text, size, &mut line);
+    line
+}
+
+/// Returns the baseline of the primary font at the given size, relative to the top of the line.
+pub(crate) fn baseline(chain: &FontChain, size: f32) -> f32 {
+    let primary = chain.primary();
+    primary.base * size / primary.size.max(1.0)
+}
+
+/// Shapes text character by character and appends the glyphs to the line, starting at its
+/// current width.
+pub(crate) fn shape_simple(chain: &FontChain, text: &str, size: f32, line: &mut ShapedLine) {
+    let baseline = baseline(chain, size);
+    let mut pen = line.width;
+    // The font, character, start and advance of the last base glyph
+    let mut previous: Option<(usize, char, f32, f32)> = None;
+
+    for character in text.chars() {
+        if is_invisible(character) {
+            continue;
+        }
+        let Some((font_index, resolved, glyph)) = chain.resolve_or_replace(character) else {
+            continue;
+        };
+        let font = &chain.fonts[font_index];
+        let scale = size / font.size.max(1.0);
+        let y = baseline - (font.base - glyph.y_offset) * scale;
+
+        if is_combining_mark(character) {
+            // Center the mark over the preceding base glyph without advancing
+            if let Some((_, _, start, advance)) = previous {
+                line.glyphs.push(ShapedGlyph {
+                    font: font_index,
+                    page: glyph.page,
+                    character: resolved,
+                    x: start + (advance - glyph.width * scale) / 2.0,
+                    y,
+                    width: glyph.width * scale,
+                    height: glyph.height * scale,
+                    uvs: font.glyph_uvs(glyph),
+                });
+                continue;
+            }
+        }
+
+        if let Some((previous_font, previous_character, _, _)) = previous {
+            if previous_font == font_index {
+                pen += font.kerning(previous_character, resolved) * scale;
+            }
+        }
+        line.glyphs.push(ShapedGlyph {
+            font: font_index,
+            page: glyph.page,
+            character: resolved,
+            x: pen + glyph.x_offset * scale,
+            y,
+            width: glyph.width * scale,
+            height: glyph.height * scale,
+            uvs: font.glyph_uvs(glyph),
+        });
+        let advance = glyph.x_advance * scale;
+        previous = Some((font_index, resolved, pen, advance));
+        pen += advance;
+    }
+    line.width = pen;
+}
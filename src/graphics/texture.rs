@@ -5,7 +5,7 @@
 //! ## Usage
 //!
 //! ```rust
-//! use glwfr::graphics::texture::Texture;
+//! use glwfr::graphics::texture::{Texture, WrapMode};
 //! use glwfr::custom_errors::Errors;
 //!
 //! fn main() -> Result<(), Errors> {
@@ -21,22 +21,225 @@
 //!     // Set texture parameters
 //!     texture.set_parameteri(gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
 //!     texture.set_parameteri(gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+//!     texture.set_wrap(WrapMode::ClampToEdge);
 //!
 //!     Ok(())
 //! }
 //! ```
 
-use crate::custom_errors::Errors;
+use crate::custom_errors::{Errors, GlErrorKind};
+use crate::graphics::gl_wrapper::{set_object_label, ContextBound};
+use crate::graphics::stats::{record_texture_bind, track_allocation, MemoryCategory};
+use crate::graphics::texture_unit::TextureUnit;
 use gl::types::*;
 use image::ImageError;
+use std::cell::Cell;
 use std::path::Path;
 
+/// The storage format of a texture, mapping to an OpenGL internal format, pixel format and
+/// component type.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum TextureFormat {
+    /// One 8-bit normalized channel.
+    R8,
+    /// Two 8-bit normalized channels.
+    Rg8,
+    /// Three 8-bit normalized channels.
+    Rgb8,
+    /// Four 8-bit normalized channels.
+    #[default]
+    Rgba8,
+    /// Four 8-bit channels with sRGB-encoded color.
+    Srgb8Alpha8,
+    /// One 16-bit float channel.
+    R16F,
+    /// Two 16-bit float channels.
+    Rg16F,
+    /// Four 16-bit float channels.
+    Rgba16F,
+    /// One 32-bit float channel.
+    R32F,
+    /// Four 32-bit float channels.
+    Rgba32F,
+    /// 16-bit depth.
+    Depth16,
+    /// 24-bit depth.
+    Depth24,
+    /// 32-bit float depth.
+    Depth32F,
+    /// 24-bit depth with an 8-bit stencil.
+    Depth24Stencil8,
+}
+
+impl TextureFormat {
+    /// Returns the sized internal format, such as `GL_RGBA8`.
+    pub fn internal_format(&self) -> GLenum {
+        match self {
+            TextureFormat::R8 => gl::R8,
+            TextureFormat::Rg8 => gl::RG8,
+            TextureFormat::Rgb8 => gl::RGB8,
+            TextureFormat::Rgba8 => gl::RGBA8,
+            TextureFormat::Srgb8Alpha8 => gl::SRGB8_ALPHA8,
+            TextureFormat::R16F => gl::R16F,
+            TextureFormat::Rg16F => gl::RG16F,
+            TextureFormat::Rgba16F => gl::RGBA16F,
+            TextureFormat::R32F => gl::R32F,
+            TextureFormat::Rgba32F => gl::RGBA32F,
+            TextureFormat::Depth16 => gl::DEPTH_COMPONENT16,
+            TextureFormat::Depth24 => gl::DEPTH_COMPONENT24,
+            TextureFormat::Depth32F => gl::DEPTH_COMPONENT32F,
+            TextureFormat::Depth24Stencil8 => gl::DEPTH24_STENCIL8,
+        }
+    }
+
+    /// Returns the pixel format of uploaded data, such as `GL_RGBA`.
+    pub fn format(&self) -> GLenum {
+        match self {
+            TextureFormat::R8 | TextureFormat::R16F | TextureFormat::R32F => gl::RED,
+            TextureFormat::Rg8 | TextureFormat::Rg16F => gl::RG,
+            TextureFormat::Rgb8 => gl::RGB,
+            TextureFormat::Rgba8
+            | TextureFormat::Srgb8Alpha8
+            | TextureFormat::Rgba16F
+            | TextureFormat::Rgba32F => gl::RGBA,
+            TextureFormat::Depth16 | TextureFormat::Depth24 | TextureFormat::Depth32F => {
+                gl::DEPTH_COMPONENT
+            }
+            TextureFormat::Depth24Stencil8 => gl::DEPTH_STENCIL,
+        }
+    }
+
+    /// Returns the component type of uploaded data, such as `GL_UNSIGNED_BYTE`.
+    pub fn data_type(&self) -> GLenum {
+        match self {
+            TextureFormat::R8
+            | TextureFormat::Rg8
+            | TextureFormat::Rgb8
+            | TextureFormat::Rgba8
+            | TextureFormat::Srgb8Alpha8 => gl::UNSIGNED_BYTE,
+            TextureFormat::R16F | TextureFormat::Rg16F | TextureFormat::Rgba16F => gl::HALF_FLOAT,
+            TextureFormat::R32F | TextureFormat::Rgba32F | TextureFormat::Depth32F => gl::FLOAT,
+            TextureFormat::Depth16 => gl::UNSIGNED_SHORT,
+            TextureFormat::Depth24 => gl::UNSIGNED_INT,
+            TextureFormat::Depth24Stencil8 => gl::UNSIGNED_INT_24_8,
+        }
+    }
+
+    /// Returns the size of a single pixel of uploaded data in bytes.
+    pub fn bytes_per_pixel(&self) -> usize {
+        match self {
+            TextureFormat::R8 => 1,
+            TextureFormat::Rg8 | TextureFormat::R16F | TextureFormat::Depth16 => 2,
+            TextureFormat::Rgb8 => 3,
+            TextureFormat::Rgba8
+            | TextureFormat::Srgb8Alpha8
+            | TextureFormat::Rg16F
+            | TextureFormat::R32F
+            | TextureFormat::Depth24
+            | TextureFormat::Depth32F
+            | TextureFormat::Depth24Stencil8 => 4,
+            TextureFormat::Rgba16F => 8,
+            TextureFormat::Rgba32F => 16,
+        }
+    }
+
+    /// Returns `true` for depth and depth-stencil formats.
+    pub fn is_depth(&self) -> bool {
+        matches!(
+            self,
+            TextureFormat::Depth16
+                | TextureFormat::Depth24
+                | TextureFormat::Depth32F
+                | TextureFormat::Depth24Stencil8
+        )
+    }
+
+    /// Returns the framebuffer attachment point matching the format, e.g.
+    /// `gl::DEPTH_STENCIL_ATTACHMENT` for `TextureFormat::Depth24Stencil8`.
+    ///
+    /// Color formats map to `gl::COLOR_ATTACHMENT0`.
+    pub fn default_attachment(&self) -> GLenum {
+        match self {
+            TextureFormat::Depth24Stencil8 => gl::DEPTH_STENCIL_ATTACHMENT,
+            format if format.is_depth() => gl::DEPTH_ATTACHMENT,
+            _ => gl::COLOR_ATTACHMENT0,
+        }
+    }
+}
+
+/// How texture coordinates outside of `[0, 1]` are resolved.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum WrapMode {
+    /// Tile the texture.
+    #[default]
+    Repeat,
+    /// Tile the texture, mirroring every other repetition.
+    MirroredRepeat,
+    /// Use the color of the nearest edge texel.
+    ClampToEdge,
+    /// Use the border color set with `Texture::set_border_color`.
+    ClampToBorder,
+    /// Mirror the texture once, then use the color of the nearest edge texel.
+    MirrorClampToEdge,
+}
+
+impl WrapMode {
+    /// Returns the OpenGL enum for the wrap mode.
+    pub fn to_gl(&self) -> GLenum {
+        match self {
+            WrapMode::Repeat => gl::REPEAT,
+            WrapMode::MirroredRepeat => gl::MIRRORED_REPEAT,
+            WrapMode::ClampToEdge => gl::CLAMP_TO_EDGE,
+            WrapMode::ClampToBorder => gl::CLAMP_TO_BORDER,
+            WrapMode::MirrorClampToEdge => gl::MIRROR_CLAMP_TO_EDGE,
+        }
+    }
+}
+
+/// The axis a gradient created with `Texture::gradient` runs along.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum GradientDirection {
+    /// From the left edge to the right edge.
+    #[default]
+    Horizontal,
+    /// From the bottom edge to the top edge.
+    Vertical,
+}
+
+/// A texture coordinate axis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TextureAxis {
+    /// The horizontal axis (`u`).
+    S,
+    /// The vertical axis (`v`).
+    T,
+    /// The depth axis, used by 3D textures and cubemaps.
+    R,
+}
+
+impl TextureAxis {
+    /// Returns the OpenGL wrap parameter for the axis, such as `GL_TEXTURE_WRAP_S`.
+    pub fn wrap_parameter(&self) -> GLenum {
+        match self {
+            TextureAxis::S => gl::TEXTURE_WRAP_S,
+            TextureAxis::T => gl::TEXTURE_WRAP_T,
+            TextureAxis::R => gl::TEXTURE_WRAP_R,
+        }
+    }
+}
+
 /// Represents an OpenGL texture.
 ///
 /// This struct encapsulates an OpenGL texture object, providing methods to load,
 /// bind, and configure textures.
 pub struct Texture {
     id: GLuint,
+    width: Cell<u32>,
+    height: Cell<u32>,
+    memory_size: Cell<usize>,
+    generate_mipmaps: Cell<bool>,
+    format: Cell<TextureFormat>,
+    _context: ContextBound,
 }
 
 impl Texture {
@@ -54,7 +257,180 @@ impl Texture {
         unsafe {
             gl::GenTextures(1, &mut id);
         }
-        Self { id }
+        Self {
+            id,
+            width: Cell::new(0),
+            height: Cell::new(0),
+            memory_size: Cell::new(0),
+            generate_mipmaps: Cell::new(true),
+            format: Cell::new(TextureFormat::Rgba8),
+            _context: ContextBound::default(),
+        }
+    }
+
+    /// Returns the OpenGL texture handle, e.g. for attaching the texture to a framebuffer.
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
+    /// Attach a debug label to the texture, shown by tools like RenderDoc and Nsight.
+    ///
+    /// Does nothing if `GL_KHR_debug` is not available.
+    ///
+    /// # Arguments
+    ///
+    /// * `label` - The label to attach, e.g. `"player_albedo"`.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glObjectLabel`.
+    pub fn set_debug_label(&self, label: &str) {
+        set_object_label(gl::TEXTURE, self.id, label);
+    }
+
+    /// Returns the width of the texture in pixels, or `0` if nothing has been uploaded yet.
+    pub fn width(&self) -> u32 {
+        self.width.get()
+    }
+
+    /// Returns the height of the texture in pixels, or `0` if nothing has been uploaded yet.
+    pub fn height(&self) -> u32 {
+        self.height.get()
+    }
+
+    /// Returns the estimated GPU memory used by the texture in bytes, including mipmaps.
+    pub fn memory_size(&self) -> usize {
+        self.memory_size.get()
+    }
+
+    /// Returns the storage format of the texture.
+    pub fn format(&self) -> TextureFormat {
+        self.format.get()
+    }
+
+    /// Records the size of the uploaded image for `width`, `height`, `format` and `memory_size`.
+    fn set_storage_info(&self, width: u32, height: u32, format: TextureFormat, mipmapped: bool) {
+        self.width.set(width);
+        self.height.set(height);
+        self.format.set(format);
+        let base = width as usize * height as usize * format.bytes_per_pixel();
+        // A full mip chain adds roughly a third of the base level.
+        self.set_memory_size(if mipmapped { base + base / 3 } else { base });
+    }
+
+    /// Updates `memory_size` and the memory tracked in `RendererStats`.
+    fn set_memory_size(&self, size: usize) {
+        track_allocation(MemoryCategory::Texture, self.memory_size.get(), size);
+        self.memory_size.set(size);
+    }
+
+    /// Returns the number of levels in a full mip chain for the given size.
+    fn mip_level_count(width: u32, height: u32) -> i32 {
+        (32 - width.max(height).max(1).leading_zeros()) as i32
+    }
+
+    /// Set whether `load_from_file` and `load_from_data` generate mipmaps with `glGenerateMipmap`.
+    ///
+    /// Defaults to `true`. Disable it when uploading pre-computed mips with `upload_mip_level`.
+    ///
+    /// # Arguments
+    ///
+    /// * `generate` - Whether to generate mipmaps after uploading the base level.
+    pub fn set_generate_mipmaps(&self, generate: bool) {
+        self.generate_mipmaps.set(generate);
+    }
+
+    /// Returns whether mipmaps are generated automatically after uploading the base level.
+    pub fn generates_mipmaps(&self) -> bool {
+        self.generate_mipmaps.get()
+    }
+
+    /// Generate the mip chain from the base level.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glGenerateMipmap(GL_TEXTURE_2D)`.
+    pub fn generate_mipmaps(&self) {
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, self.id);
+            gl::GenerateMipmap(gl::TEXTURE_2D);
+        }
+        self.set_storage_info(self.width(), self.height(), self.format(), true);
+    }
+
+    /// Upload a single mip level from raw RGBA data.
+    ///
+    /// Use this to load pre-computed mip chains, e.g. from KTX or DDS files. Level `0` defines the
+    /// size of the texture. The texture must not have been created by `load_from_file`, which
+    /// allocates immutable storage.
+    ///
+    /// # Arguments
+    ///
+    /// * `level` - The mip level to upload, `0` being the base level.
+    /// * `width` - The width of the level.
+    /// * `height` - The height of the level.
+    /// * `data` - The raw data of the level. Must be in RGBA format with 8 bits per channel.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::TextureLoadError` if the data size is invalid.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glTexImage2D(GL_TEXTURE_2D, level, ...)`.
+    pub fn upload_mip_level(
+        &self,
+        level: u32,
+        width: u32,
+        height: u32,
+        data: &[u8],
+    ) -> Result<(), Errors> {
+        if data.len() != (width * height * 4) as usize {
+            return Err(Errors::TextureLoadError(
+                "Invalid data size for mip level".to_string(),
+            ));
+        }
+
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, self.id);
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                level as i32,
+                gl::RGBA as i32,
+                width as i32,
+                height as i32,
+                0,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                data.as_ptr() as *const _,
+            );
+        }
+        if level == 0 {
+            self.set_storage_info(width, height, TextureFormat::Rgba8, false);
+        } else {
+            self.set_memory_size(self.memory_size.get() + data.len());
+        }
+
+        Ok(())
+    }
+
+    /// Limit the mip levels used for sampling.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glTexParameteri` with `GL_TEXTURE_BASE_LEVEL` and
+    /// `GL_TEXTURE_MAX_LEVEL`.
+    ///
+    /// # Arguments
+    ///
+    /// * `base` - The first mip level that may be sampled.
+    /// * `max` - The last mip level that may be sampled.
+    pub fn set_mip_range(&self, base: u32, max: u32) {
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, self.id);
+        }
+        self.set_parameteri(gl::TEXTURE_BASE_LEVEL, base as i32);
+        self.set_parameteri(gl::TEXTURE_MAX_LEVEL, max as i32);
     }
     /// Bind the texture to the given active texture unit.
     ///
@@ -71,6 +447,20 @@ impl Texture {
             gl::ActiveTexture(unit);
             gl::BindTexture(gl::TEXTURE_2D, self.id);
         }
+        record_texture_bind();
+    }
+
+    /// Bind the texture to the given typed texture unit.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glActiveTexture(unit)` and `glBindTexture(GL_TEXTURE_2D, id)`.
+    ///
+    /// # Arguments
+    ///
+    /// * `unit` - The texture unit to bind the texture to.
+    pub fn bind_to_unit(&self, unit: TextureUnit) {
+        self.bind(unit.gl_enum());
     }
 
     /// Set a parameter of the texture.
@@ -107,6 +497,55 @@ impl Texture {
         }
     }
 
+    /// Set the wrap mode for all texture coordinate axes.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glTexParameteri` with `GL_TEXTURE_WRAP_S`,
+    /// `GL_TEXTURE_WRAP_T` and `GL_TEXTURE_WRAP_R`.
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - The wrap mode to use.
+    pub fn set_wrap(&self, mode: WrapMode) {
+        for axis in [TextureAxis::S, TextureAxis::T, TextureAxis::R] {
+            self.set_wrap_axis(axis, mode);
+        }
+    }
+
+    /// Set the wrap mode for a single texture coordinate axis.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_*, mode)`.
+    ///
+    /// # Arguments
+    ///
+    /// * `axis` - The axis to configure.
+    /// * `mode` - The wrap mode to use.
+    pub fn set_wrap_axis(&self, axis: TextureAxis, mode: WrapMode) {
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, self.id);
+        }
+        self.set_parameteri(axis.wrap_parameter(), mode.to_gl() as i32);
+    }
+
+    /// Set the color returned for lookups outside the texture with `WrapMode::ClampToBorder`.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glTexParameterfv(GL_TEXTURE_2D, GL_TEXTURE_BORDER_COLOR, color)`.
+    ///
+    /// # Arguments
+    ///
+    /// * `color` - The border color as RGBA. For shadow maps this is usually `[1.0; 4]`.
+    pub fn set_border_color(&self, color: [f32; 4]) {
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, self.id);
+            gl::TexParameterfv(gl::TEXTURE_2D, gl::TEXTURE_BORDER_COLOR, color.as_ptr());
+        }
+    }
+
     /// Load a texture from a file and bind it to the texture object.
     ///
     /// # Arguments
@@ -120,9 +559,9 @@ impl Texture {
     /// # OpenGL Functions
     ///
     /// This function binds the texture and uploads its data to the GPU using
-    /// `glTexImage2D(GL_TEXTURE_2D, 0, gl::RGBA, width, height, 0, gl::RGBA, gl::UNSIGNED_BYTE, img.as_ptr() as *const _)`.
-    /// It also generates mipmaps for the texture using `glGenerateMipmap(GL_TEXTURE_2D)`.
-
+    /// `glTexStorage2D` and `glTexSubImage2D(GL_TEXTURE_2D, 0, 0, 0, width, height, gl::RGBA, gl::UNSIGNED_BYTE, img.as_ptr() as *const _)`.
+    /// Unless disabled with `set_generate_mipmaps`, it allocates a full mip chain and generates
+    /// mipmaps for the texture using `glGenerateMipmap(GL_TEXTURE_2D)`.
     pub fn load_from_file<P: AsRef<Path>>(&self, path: P) -> Result<(), Errors> {
         let img = image::open(path).map_err(|e: ImageError| {
             Errors::TextureLoadError(format!("Failed to load texture: {}", e))
@@ -130,12 +569,18 @@ impl Texture {
         let img = img.to_rgba8();
 
         let (width, height) = img.dimensions();
+        let generate_mipmaps = self.generate_mipmaps.get();
+        let levels = if generate_mipmaps {
+            Self::mip_level_count(width, height)
+        } else {
+            1
+        };
 
         unsafe {
             gl::BindTexture(gl::TEXTURE_2D, self.id);
             gl::TexStorage2D(
                 gl::TEXTURE_2D,
-                1, // Уровни мипмапов
+                levels,
                 gl::RGBA8,
                 width as i32,
                 height as i32,
@@ -151,8 +596,11 @@ impl Texture {
                 gl::UNSIGNED_BYTE,
                 img.as_ptr() as *const _,
             );
-            gl::GenerateMipmap(gl::TEXTURE_2D);
+            if generate_mipmaps {
+                gl::GenerateMipmap(gl::TEXTURE_2D);
+            }
         }
+        self.set_storage_info(width, height, TextureFormat::Rgba8, generate_mipmaps);
 
         Ok(())
     }
@@ -172,32 +620,373 @@ impl Texture {
     /// # OpenGL Functions
     ///
     /// This function binds the texture and uploads its data to the GPU using
-    /// `glTexImage2D(GL_TEXTURE_2D, 0, gl::RGBA, width, height, 0, gl::RGBA, gl::UNSIGNED_BYTE, data.as_ptr() as *const _)`.
-    /// It also generates mipmaps for the texture using `glGenerateMipmap(GL_TEXTURE_2D)`.
+    /// `glTexImage2D(GL_TEXTURE_2D, 0, gl::RGBA8, width, height, 0, gl::RGBA, gl::UNSIGNED_BYTE, data.as_ptr() as *const _)`.
+    /// Unless disabled with `set_generate_mipmaps`, it also generates mipmaps for the texture
+    /// using `glGenerateMipmap(GL_TEXTURE_2D)`.
     pub fn load_from_data(&self, width: u32, height: u32, data: &[u8]) -> Result<(), Errors> {
-        if data.len() != (width * height * 4) as usize {
+        self.load_from_data_with_format(width, height, TextureFormat::Rgba8, data)
+    }
+
+    /// Loads a texture from raw data in the given format and uploads it to the GPU.
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - The width of the texture.
+    /// * `height` - The height of the texture.
+    /// * `format` - The storage format of the texture, which also defines the layout of `data`.
+    /// * `data` - The raw data of the texture, `format.bytes_per_pixel()` bytes per pixel
+    ///   with tightly packed rows.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::TextureLoadError` if the data size is invalid.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function binds the texture and uploads its data to the GPU using `glTexImage2D`
+    /// with the internal format, format and type of `format`. Unless disabled with
+    /// `set_generate_mipmaps`, it also generates mipmaps for color formats using
+    /// `glGenerateMipmap(GL_TEXTURE_2D)`.
+    pub fn load_from_data_with_format(
+        &self,
+        width: u32,
+        height: u32,
+        format: TextureFormat,
+        data: &[u8],
+    ) -> Result<(), Errors> {
+        if data.len() != width as usize * height as usize * format.bytes_per_pixel() {
             return Err(Errors::TextureLoadError(
                 "Invalid data size for texture".to_string(),
             ));
         }
 
+        self.upload(width, height, format, data.as_ptr() as *const _);
+        Ok(())
+    }
+
+    /// Creates a checkerboard texture, e.g. as a placeholder for missing assets.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - The width and height of the texture in pixels.
+    /// * `cells` - The number of cells along each side.
+    /// * `colors` - The two alternating RGBA colors, with components from `0.0` to `1.0`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::TextureLoadError` if the texture cannot be uploaded.
+    pub fn checkerboard(size: u32, cells: u32, colors: [[f32; 4]; 2]) -> Result<Self, Errors> {
+        let colors = colors.map(Self::color_to_rgba8);
+        let cell_size = (size / cells.max(1)).max(1);
+        let data: Vec<u8> = (0..size)
+            .flat_map(|y| (0..size).map(move |x| (x / cell_size + y / cell_size) % 2))
+            .flat_map(|cell| colors[cell as usize])
+            .collect();
+        Self::from_rgba8(size, size, &data)
+    }
+
+    /// Creates a 1x1 texture of a single color, e.g. as a default for unused material slots.
+    ///
+    /// # Arguments
+    ///
+    /// * `color` - The RGBA color, with components from `0.0` to `1.0`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::TextureLoadError` if the texture cannot be uploaded.
+    pub fn solid_color(color: [f32; 4]) -> Result<Self, Errors> {
+        Self::from_rgba8(1, 1, &Self::color_to_rgba8(color))
+    }
+
+    /// Creates a texture with a linear gradient between two colors.
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - The width of the texture in pixels.
+    /// * `height` - The height of the texture in pixels.
+    /// * `from` - The RGBA color at the left or bottom edge.
+    /// * `to` - The RGBA color at the right or top edge.
+    /// * `direction` - The axis the gradient runs along.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::TextureLoadError` if the texture cannot be uploaded.
+    pub fn gradient(
+        width: u32,
+        height: u32,
+        from: [f32; 4],
+        to: [f32; 4],
+        direction: GradientDirection,
+    ) -> Result<Self, Errors> {
+        let data: Vec<u8> = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .flat_map(|(x, y)| {
+                let t = match direction {
+                    GradientDirection::Horizontal => x as f32 / (width.max(2) - 1) as f32,
+                    GradientDirection::Vertical => y as f32 / (height.max(2) - 1) as f32,
+                };
+                let mut color = [0.0; 4];
+                for (i, component) in color.iter_mut().enumerate() {
+                    *component = from[i] + (to[i] - from[i]) * t;
+                }
+                Self::color_to_rgba8(color)
+            })
+            .collect();
+        Self::from_rgba8(width, height, &data)
+    }
+
+    /// Creates a tangent-space normal map from the red channel of a height map.
+    ///
+    /// The height map is read back from the GPU, so this is meant for load time rather than
+    /// every frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `height_map` - A color texture storing heights in its red channel.
+    /// * `strength` - How pronounced the bumps are; `1.0` treats a full height step between
+    ///   neighbouring pixels as a 45 degree slope.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::TextureLoadError` if the height map is empty or uses a depth format.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function reads the height map with `glGetTexImage` and uploads the result with
+    /// `glTexImage2D`.
+    pub fn normal_from_height(height_map: &Texture, strength: f32) -> Result<Self, Errors> {
+        let (width, height) = (height_map.width(), height_map.height());
+        if width == 0 || height == 0 || height_map.format().is_depth() {
+            return Err(Errors::TextureLoadError(
+                "Height map must be a non-empty color texture".to_string(),
+            ));
+        }
+
+        let mut heights = vec![0u8; width as usize * height as usize];
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, height_map.id);
+            gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+            gl::GetTexImage(
+                gl::TEXTURE_2D,
+                0,
+                gl::RED,
+                gl::UNSIGNED_BYTE,
+                heights.as_mut_ptr() as *mut _,
+            );
+            gl::PixelStorei(gl::PACK_ALIGNMENT, 4);
+        }
+
+        // Wrap around the edges, so tiling height maps produce tiling normal maps.
+        let sample = |x: i64, y: i64| {
+            let x = x.rem_euclid(width as i64) as usize;
+            let y = y.rem_euclid(height as i64) as usize;
+            heights[y * width as usize + x] as f32 / 255.0
+        };
+        let mut data = Vec::with_capacity(heights.len() * 4);
+        for y in 0..height as i64 {
+            for x in 0..width as i64 {
+                let dx = (sample(x + 1, y) - sample(x - 1, y)) * 0.5 * strength;
+                let dy = (sample(x, y + 1) - sample(x, y - 1)) * 0.5 * strength;
+                let length = (dx * dx + dy * dy + 1.0).sqrt();
+                let normal = [-dx / length, -dy / length, 1.0 / length, 1.0];
+                data.extend(Self::color_to_rgba8(normal.map(|n| n * 0.5 + 0.5)));
+            }
+        }
+        Self::from_rgba8(width, height, &data)
+    }
+
+    /// Creates a texture from tightly packed RGBA8 pixels.
+    fn from_rgba8(width: u32, height: u32, data: &[u8]) -> Result<Self, Errors> {
+        let texture = Self::new();
+        texture.load_from_data(width, height, data)?;
+        Ok(texture)
+    }
+
+    /// Converts a color with components from `0.0` to `1.0` to RGBA8.
+    fn color_to_rgba8(color: [f32; 4]) -> [u8; 4] {
+        color.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8)
+    }
+
+    /// Allocates storage for a texture of the given size and format without uploading data.
+    ///
+    /// Use this for render targets such as color buffers or shadow maps.
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - The width of the texture.
+    /// * `height` - The height of the texture.
+    /// * `format` - The storage format of the texture.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glTexImage2D` with a null data pointer.
+    pub fn allocate(&self, width: u32, height: u32, format: TextureFormat) {
+        let generate_mipmaps = self.generate_mipmaps.replace(false);
+        self.upload(width, height, format, std::ptr::null());
+        self.generate_mipmaps.set(generate_mipmaps);
+    }
+
+    /// Copy the base level of this texture into another texture without a CPU round trip.
+    ///
+    /// Useful for ping-pong post-processing. Mipmaps of `dst` are regenerated if enabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `dst` - The destination texture. It must be at least as large as this texture and
+    ///   have a compatible format.
+    ///
+    /// # Errors
+    ///
+    /// See `copy_to_offset`.
+    pub fn copy_to(&self, dst: &Texture) -> Result<(), Errors> {
+        self.copy_to_offset(dst, 0, 0)
+    }
+
+    /// Copy the base level of this texture into a region of another texture, e.g. to place
+    /// it in a texture atlas.
+    ///
+    /// Mipmaps of `dst` are regenerated if enabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `dst` - The destination texture with a compatible format.
+    /// * `x` - The x offset in `dst` in texels.
+    /// * `y` - The y offset in `dst` in texels, measured from the bottom.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::OpenGlError` of kind `GlErrorKind::InvalidValue` if the copy does not
+    /// fit into `dst`, or of kind `GlErrorKind::FramebufferIncomplete` if the fallback path
+    /// cannot read from this texture.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glCopyImageSubData` (OpenGL 4.3). On older contexts
+    /// it falls back to `glCopyTexSubImage2D` through a temporary framebuffer.
+    pub fn copy_to_offset(&self, dst: &Texture, x: u32, y: u32) -> Result<(), Errors> {
+        let (width, height) = (self.width(), self.height());
+        let fits = |offset: u32, size: u32, limit: u32| {
+            offset.checked_add(size).is_some_and(|end| end <= limit)
+        };
+        if !fits(x, width, dst.width()) || !fits(y, height, dst.height()) {
+            return Err(Errors::OpenGlError(
+                format!(
+                    "Cannot copy a {}x{} texture to ({}, {}) of a {}x{} texture",
+                    width,
+                    height,
+                    x,
+                    y,
+                    dst.width(),
+                    dst.height()
+                ),
+                GlErrorKind::InvalidValue,
+            ));
+        }
+
+        if gl::CopyImageSubData::is_loaded() {
+            unsafe {
+                gl::CopyImageSubData(
+                    self.id,
+                    gl::TEXTURE_2D,
+                    0,
+                    0,
+                    0,
+                    0,
+                    dst.id,
+                    gl::TEXTURE_2D,
+                    0,
+                    x as i32,
+                    y as i32,
+                    0,
+                    width as i32,
+                    height as i32,
+                    1,
+                );
+            }
+        } else {
+            self.copy_through_framebuffer(dst, x, y)?;
+        }
+
+        if dst.generates_mipmaps() {
+            dst.generate_mipmaps();
+        }
+        Ok(())
+    }
+
+    /// Copies the base level with `glCopyTexSubImage2D`, reading through a temporary framebuffer.
+    fn copy_through_framebuffer(&self, dst: &Texture, x: u32, y: u32) -> Result<(), Errors> {
+        let attachment = if self.format().is_depth() {
+            gl::DEPTH_ATTACHMENT
+        } else {
+            gl::COLOR_ATTACHMENT0
+        };
+        let mut fbo = 0;
+        let mut previous_fbo = 0;
+        let mut previous_texture = 0;
+        unsafe {
+            gl::GetIntegerv(gl::READ_FRAMEBUFFER_BINDING, &mut previous_fbo);
+            gl::GetIntegerv(gl::TEXTURE_BINDING_2D, &mut previous_texture);
+            gl::GenFramebuffers(1, &mut fbo);
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, fbo);
+            gl::FramebufferTexture2D(gl::READ_FRAMEBUFFER, attachment, gl::TEXTURE_2D, self.id, 0);
+            let status = gl::CheckFramebufferStatus(gl::READ_FRAMEBUFFER);
+            if status == gl::FRAMEBUFFER_COMPLETE {
+                gl::BindTexture(gl::TEXTURE_2D, dst.id);
+                gl::CopyTexSubImage2D(
+                    gl::TEXTURE_2D,
+                    0,
+                    x as i32,
+                    y as i32,
+                    0,
+                    0,
+                    self.width() as i32,
+                    self.height() as i32,
+                );
+                gl::BindTexture(gl::TEXTURE_2D, previous_texture as GLuint);
+            }
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, previous_fbo as GLuint);
+            gl::DeleteFramebuffers(1, &fbo);
+
+            if status != gl::FRAMEBUFFER_COMPLETE {
+                return Err(Errors::OpenGlError(
+                    "Cannot read from the source texture".to_string(),
+                    GlErrorKind::FramebufferIncomplete(status),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Uploads the base level with the given format, generating mipmaps if enabled.
+    fn upload(&self, width: u32, height: u32, format: TextureFormat, data: *const GLvoid) {
+        let generate_mipmaps = self.generate_mipmaps.get() && !format.is_depth();
+        // Rows of 1, 2 and 3 byte pixels are not necessarily 4-byte aligned.
+        let tightly_packed = !format.bytes_per_pixel().is_multiple_of(4);
+
         unsafe {
             gl::BindTexture(gl::TEXTURE_2D, self.id);
+            if tightly_packed {
+                gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+            }
             gl::TexImage2D(
                 gl::TEXTURE_2D,
                 0,
-                gl::RGBA as i32,
+                format.internal_format() as i32,
                 width as i32,
                 height as i32,
                 0,
-                gl::RGBA,
-                gl::UNSIGNED_BYTE,
-                data.as_ptr() as *const _,
+                format.format(),
+                format.data_type(),
+                data,
             );
-            gl::GenerateMipmap(gl::TEXTURE_2D);
+            if tightly_packed {
+                gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+            }
+            if generate_mipmaps {
+                gl::GenerateMipmap(gl::TEXTURE_2D);
+            }
         }
-
-        Ok(())
+        self.set_storage_info(width, height, format, generate_mipmaps);
     }
 }
 
@@ -208,6 +997,7 @@ impl Drop for Texture {
     ///
     /// This function is a wrapper around `glDeleteTextures(1, &self.id)`.
     fn drop(&mut self) {
+        track_allocation(MemoryCategory::Texture, self.memory_size.get(), 0);
         unsafe {
             gl::DeleteTextures(1, &self.id);
         }
@@ -0,0 +1,142 @@
+//! # Texture Manager Module
+//!
+//! This module provides a `TextureManager` that caches textures by file path, so repeated
+//! loads of the same file share a single GPU texture.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::graphics::texture_manager::TextureManager;
+//!
+//! fn main() -> Result<(), glwfr::custom_errors::Errors> {
+//!     let mut textures = TextureManager::new();
+//!
+//!     let a = textures.load("assets/crate.png")?;
+//!     let b = textures.load("assets/crate.png")?; // Cache hit, same texture
+//!     assert!(std::rc::Rc::ptr_eq(&a, &b));
+//!
+//!     let stats = textures.stats();
+//!     println!("{} textures, {} bytes", stats.texture_count, stats.memory_bytes);
+//!     Ok(())
+//! }
+//! ```
+
+use crate::custom_errors::Errors;
+use crate::graphics::texture::Texture;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// Statistics about the textures held by a `TextureManager`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TextureStats {
+    /// The number of cached textures.
+    pub texture_count: usize,
+    /// The estimated GPU memory used by all cached textures in bytes.
+    pub memory_bytes: usize,
+    /// The number of `load` calls served from the cache.
+    pub cache_hits: usize,
+    /// The number of `load` calls that read a file.
+    pub cache_misses: usize,
+}
+
+/// Caches textures by file path and tracks their GPU memory usage.
+pub struct TextureManager {
+    textures: HashMap<PathBuf, Rc<Texture>>,
+    cache_hits: usize,
+    cache_misses: usize,
+}
+
+impl TextureManager {
+    /// Creates a new, empty texture manager.
+    pub fn new() -> Self {
+        Self {
+            textures: HashMap::new(),
+            cache_hits: 0,
+            cache_misses: 0,
+        }
+    }
+
+    /// Returns the texture for the given file, loading it on first use.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - A path to the image file to be loaded.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::TextureLoadError` if the image cannot be opened or processed.
+    pub fn load<P: AsRef<Path>>(&mut self, path: P) -> Result<Rc<Texture>, Errors> {
+        let path = path.as_ref();
+        if let Some(texture) = self.textures.get(path) {
+            self.cache_hits += 1;
+            return Ok(Rc::clone(texture));
+        }
+
+        let texture = Texture::new();
+        texture.load_from_file(path)?;
+        let texture = Rc::new(texture);
+        self.cache_misses += 1;
+        self.textures
+            .insert(path.to_path_buf(), Rc::clone(&texture));
+        Ok(texture)
+    }
+
+    /// Returns the cached texture for the given file without loading it.
+    pub fn get<P: AsRef<Path>>(&self, path: P) -> Option<Rc<Texture>> {
+        self.textures.get(path.as_ref()).cloned()
+    }
+
+    /// Checks if a texture for the given file is cached.
+    pub fn contains<P: AsRef<Path>>(&self, path: P) -> bool {
+        self.textures.contains_key(path.as_ref())
+    }
+
+    /// Removes the texture for the given file from the cache.
+    ///
+    /// The GPU texture is deleted once all other handles to it are dropped.
+    pub fn remove<P: AsRef<Path>>(&mut self, path: P) -> Option<Rc<Texture>> {
+        self.textures.remove(path.as_ref())
+    }
+
+    /// Removes all textures that are not used outside of the manager.
+    ///
+    /// # Returns
+    ///
+    /// The number of textures removed.
+    pub fn remove_unused(&mut self) -> usize {
+        let before = self.textures.len();
+        self.textures
+            .retain(|_, texture| Rc::strong_count(texture) > 1);
+        before - self.textures.len()
+    }
+
+    /// Removes all textures from the cache.
+    pub fn clear(&mut self) {
+        self.textures.clear();
+    }
+
+    /// Returns the estimated GPU memory used by all cached textures in bytes.
+    pub fn memory_usage(&self) -> usize {
+        self.textures
+            .values()
+            .map(|texture| texture.memory_size())
+            .sum()
+    }
+
+    /// Returns statistics about the cached textures, e.g. for a debug overlay.
+    pub fn stats(&self) -> TextureStats {
+        TextureStats {
+            texture_count: self.textures.len(),
+            memory_bytes: self.memory_usage(),
+            cache_hits: self.cache_hits,
+            cache_misses: self.cache_misses,
+        }
+    }
+}
+
+impl Default for TextureManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
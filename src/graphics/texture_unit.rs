@@ -0,0 +1,165 @@
+//! # Texture Unit Module
+//!
+//! This module provides a typed `TextureUnit` and a `TextureBinder` that hands out units
+//! automatically and sets the matching sampler uniforms.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::graphics::texture::Texture;
+//! use glwfr::graphics::texture_unit::TextureBinder;
+//! use glwfr::graphics::gl_wrapper::ShaderProgram;
+//!
+//! fn main() -> Result<(), glwfr::custom_errors::Errors> {
+//!     let shader_program = ShaderProgram::new("vertex.glsl", "fragment.glsl")?;
+//!     let albedo = Texture::new();
+//!     let normal = Texture::new();
+//!
+//!     shader_program.bind();
+//!     let mut binder = TextureBinder::new();
+//!     binder.bind(&shader_program, "albedoMap", &albedo)?; // unit 0
+//!     binder.bind(&shader_program, "normalMap", &normal)?; // unit 1
+//!
+//!     // Start over for the next material
+//!     binder.reset();
+//!     Ok(())
+//! }
+//! ```
+
+use crate::custom_errors::{Errors, GlErrorKind};
+use crate::graphics::gl_wrapper::ShaderProgram;
+use crate::graphics::texture::Texture;
+use gl::types::*;
+
+/// A texture image unit, such as `GL_TEXTURE0`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TextureUnit(u32);
+
+impl TextureUnit {
+    /// Creates a texture unit from its zero-based index.
+    pub const fn new(index: u32) -> Self {
+        Self(index)
+    }
+
+    /// Returns the zero-based index of the unit, as expected by sampler uniforms.
+    pub fn index(&self) -> u32 {
+        self.0
+    }
+
+    /// Returns the OpenGL enum for the unit, as expected by `glActiveTexture`.
+    pub fn gl_enum(&self) -> GLenum {
+        gl::TEXTURE0 + self.0
+    }
+
+    /// Make this unit the active texture unit.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glActiveTexture(GL_TEXTURE0 + index)`.
+    pub fn activate(&self) {
+        unsafe {
+            gl::ActiveTexture(self.gl_enum());
+        }
+    }
+
+    /// Returns the number of texture units available to all shader stages combined.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glGetIntegerv(GL_MAX_COMBINED_TEXTURE_IMAGE_UNITS)`.
+    pub fn max_units() -> u32 {
+        let mut max = 0;
+        unsafe {
+            gl::GetIntegerv(gl::MAX_COMBINED_TEXTURE_IMAGE_UNITS, &mut max);
+        }
+        max.max(0) as u32
+    }
+}
+
+/// Assigns textures to consecutive texture units and sets their sampler uniforms.
+pub struct TextureBinder {
+    next_unit: u32,
+    max_units: u32,
+}
+
+impl TextureBinder {
+    /// Creates a new binder that starts assigning from unit 0.
+    ///
+    /// The number of available units is queried from the current OpenGL context.
+    pub fn new() -> Self {
+        Self {
+            next_unit: 0,
+            max_units: TextureUnit::max_units(),
+        }
+    }
+
+    /// Creates a new binder that starts assigning from the given unit.
+    ///
+    /// Units below `first` are left alone, e.g. for a shadow map bound once per frame.
+    pub fn starting_at(first: TextureUnit) -> Self {
+        Self {
+            next_unit: first.index(),
+            max_units: TextureUnit::max_units(),
+        }
+    }
+
+    /// Binds the texture to the next free unit and points the sampler uniform at it.
+    ///
+    /// The shader program must be bound before calling this function.
+    ///
+    /// # Arguments
+    ///
+    /// * `shader_program` - The shader program containing the sampler uniform.
+    /// * `sampler_name` - The name of the sampler uniform.
+    /// * `texture` - The texture to bind.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the assigned `TextureUnit`, or an error of type `Errors::OpenGlError`
+    /// if all units are in use or the uniform is not found.
+    pub fn bind(
+        &mut self,
+        shader_program: &ShaderProgram,
+        sampler_name: &str,
+        texture: &Texture,
+    ) -> Result<TextureUnit, Errors> {
+        let unit = self.allocate()?;
+        texture.bind_to_unit(unit);
+        shader_program.set_uniform_1i(sampler_name, unit.index() as i32)?;
+        Ok(unit)
+    }
+
+    /// Reserves the next free unit without binding anything to it.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the reserved `TextureUnit`, or an error of type `Errors::OpenGlError`
+    /// if all units are in use.
+    pub fn allocate(&mut self) -> Result<TextureUnit, Errors> {
+        if self.next_unit >= self.max_units {
+            return Err(Errors::OpenGlError(
+                format!("All {} texture units are in use", self.max_units),
+                GlErrorKind::ResourceExhausted,
+            ));
+        }
+        let unit = TextureUnit::new(self.next_unit);
+        self.next_unit += 1;
+        Ok(unit)
+    }
+
+    /// Returns the number of units assigned since the last reset.
+    pub fn used_units(&self) -> u32 {
+        self.next_unit
+    }
+
+    /// Makes all units available again, e.g. before binding the textures of the next material.
+    pub fn reset(&mut self) {
+        self.next_unit = 0;
+    }
+}
+
+impl Default for TextureBinder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,307 @@
+//! # Tile Map Data Module
+//!
+//! Tilesets, tile layers and object layers of a `TileMap`.
+
+use crate::custom_errors::Errors;
+use std::collections::HashMap;
+
+/// The bit of a global tile id that flips the tile horizontally, as stored by Tiled.
+pub const FLIPPED_HORIZONTALLY: u32 = 0x8000_0000;
+
+/// The bit of a global tile id that flips the tile vertically, as stored by Tiled.
+pub const FLIPPED_VERTICALLY: u32 = 0x4000_0000;
+
+/// The bit of a global tile id that flips the tile along its diagonal, as stored by Tiled.
+pub const FLIPPED_DIAGONALLY: u32 = 0x2000_0000;
+
+/// The bits of a global tile id that hold the flip flags.
+const FLIP_MASK: u32 = FLIPPED_HORIZONTALLY | FLIPPED_VERTICALLY | FLIPPED_DIAGONALLY;
+
+/// A single frame of an animated tile.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TileFrame {
+    /// The local id of the tile shown during this frame.
+    pub tile: u32,
+    /// How long the frame is shown, in seconds.
+    pub duration: f32,
+}
+
+/// A grid of equally sized tiles in an atlas texture.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tileset {
+    /// The global id of the first tile of the tileset.
+    pub first_gid: u32,
+    /// The width of a tile in pixels.
+    pub tile_width: u32,
+    /// The height of a tile in pixels.
+    pub tile_height: u32,
+    /// The number of tile columns in the atlas.
+    pub columns: u32,
+    /// The number of tiles in the atlas.
+    pub tile_count: u32,
+    /// The pixels around the tiles at the edges of the atlas.
+    pub margin: u32,
+    /// The pixels between neighbouring tiles.
+    pub spacing: u32,
+    /// The size of the atlas texture in pixels.
+    pub texture_size: (u32, u32),
+    /// The path of the atlas image, as referenced by the map file.
+    pub image: String,
+    /// The animations of animated tiles, keyed by local tile id.
+    pub animations: HashMap<u32, Vec<TileFrame>>,
+}
+
+impl Tileset {
+    /// Creates a tileset for an atlas without margins or spacing.
+    ///
+    /// # Arguments
+    ///
+    /// * `texture_size` - The size of the atlas texture in pixels.
+    /// * `tile_width` - The width of a tile in pixels.
+    /// * `tile_height` - The height of a tile in pixels.
+    pub fn new(texture_size: (u32, u32), tile_width: u32, tile_height: u32) -> Self {
+        let columns = texture_size.0 / tile_width.max(1);
+        let rows = texture_size.1 / tile_height.max(1);
+        Self {
+            first_gid: 1,
+            tile_width,
+            tile_height,
+            columns,
+            tile_count: columns * rows,
+            margin: 0,
+            spacing: 0,
+            texture_size,
+            image: String::new(),
+            animations: HashMap::new(),
+        }
+    }
+
+    /// Returns `true` if the global tile id, without flip flags, belongs to this tileset.
+    pub fn contains(&self, gid: u32) -> bool {
+        let gid = gid & !FLIP_MASK;
+        gid >= self.first_gid && gid < self.first_gid + self.tile_count
+    }
+
+    /// Returns the local tile id shown at `time` seconds, following the tile's animation.
+    pub fn animated_tile(&self, tile: u32, time: f32) -> u32 {
+        let Some(frames) = self.animations.get(&tile) else {
+            return tile;
+        };
+        let total: f32 = frames.iter().map(|frame| frame.duration).sum();
+        if total <= 0.0 {
+            return tile;
+        }
+        let mut time = time.rem_euclid(total);
+        for frame in frames {
+            if time < frame.duration {
+                return frame.tile;
+            }
+            time -= frame.duration;
+        }
+        frames.last().map_or(tile, |frame| frame.tile)
+    }
+
+    /// Returns the texture coordinates of the four corners of a tile, in the order top-left,
+    /// top-right, bottom-right, bottom-left, with the flip flags of the global id applied.
+    ///
+    /// # Arguments
+    ///
+    /// * `tile` - The local id of the tile.
+    /// * `flags` - The global id or flip flags of the tile.
+    pub fn tile_uvs(&self, tile: u32, flags: u32) -> [[f32; 2]; 4] {
+        let columns = self.columns.max(1);
+        let x = self.margin + (tile % columns) * (self.tile_width + self.spacing);
+        let y = self.margin + (tile / columns) * (self.tile_height + self.spacing);
+        let (width, height) = (
+            self.texture_size.0.max(1) as f32,
+            self.texture_size.1.max(1) as f32,
+        );
+        let left = x as f32 / width;
+        let right = (x + self.tile_width) as f32 / width;
+        let top = y as f32 / height;
+        let bottom = (y + self.tile_height) as f32 / height;
+
+        let mut uvs = [[left, top], [right, top], [right, bottom], [left, bottom]];
+        if flags & FLIPPED_DIAGONALLY != 0 {
+            uvs.swap(1, 3);
+        }
+        if flags & FLIPPED_HORIZONTALLY != 0 {
+            uvs.swap(0, 1);
+            uvs.swap(2, 3);
+        }
+        if flags & FLIPPED_VERTICALLY != 0 {
+            uvs.swap(0, 3);
+            uvs.swap(1, 2);
+        }
+        uvs
+    }
+}
+
+/// A layer of tiles, stored row by row from the top-left corner.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TileLayer {
+    /// The name of the layer.
+    pub name: String,
+    /// The width of the layer in tiles.
+    pub width: u32,
+    /// The height of the layer in tiles.
+    pub height: u32,
+    /// The global tile ids, including flip flags. `0` marks an empty cell.
+    pub tiles: Vec<u32>,
+    /// Whether the layer is drawn.
+    pub visible: bool,
+}
+
+impl TileLayer {
+    /// Creates an empty layer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::MapLoadError` if the layer has more cells than fit in a `u32`.
+    pub fn new(name: &str, width: u32, height: u32) -> Result<Self, Errors> {
+        let cells = width.checked_mul(height).ok_or_else(|| {
+            Errors::MapLoadError(format!(
+                "layer {} of {}x{} tiles is too large",
+                name, width, height
+            ))
+        })?;
+        Ok(Self {
+            name: name.to_string(),
+            width,
+            height,
+            tiles: vec![0; cells as usize],
+            visible: true,
+        })
+    }
+
+    /// Returns the global tile id at a cell, or `0` if the cell is empty or out of bounds.
+    pub fn tile(&self, x: u32, y: u32) -> u32 {
+        if x >= self.width || y >= self.height {
+            return 0;
+        }
+        self.tiles[(y * self.width + x) as usize]
+    }
+
+    /// Sets the global tile id at a cell. Cells out of bounds are ignored.
+    pub fn set_tile(&mut self, x: u32, y: u32, gid: u32) {
+        if x < self.width && y < self.height {
+            self.tiles[(y * self.width + x) as usize] = gid;
+        }
+    }
+}
+
+/// An object placed in an object layer, such as a spawn point or a trigger area.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MapObject {
+    /// The unique id of the object.
+    pub id: u32,
+    /// The name of the object.
+    pub name: String,
+    /// The class or type of the object.
+    pub kind: String,
+    /// The left edge of the object in pixels.
+    pub x: f32,
+    /// The top edge of the object in pixels.
+    pub y: f32,
+    /// The width of the object in pixels. `0.0` for points.
+    pub width: f32,
+    /// The height of the object in pixels. `0.0` for points.
+    pub height: f32,
+    /// The global tile id of tile objects, `0` otherwise.
+    pub gid: u32,
+    /// The custom properties of the object.
+    pub properties: HashMap<String, String>,
+}
+
+/// A layer of objects.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ObjectLayer {
+    /// The name of the layer.
+    pub name: String,
+    /// The objects of the layer.
+    pub objects: Vec<MapObject>,
+}
+
+/// A tile map made of tile layers drawn from one or more tilesets and object layers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TileMap {
+    /// The width of the map in tiles.
+    pub width: u32,
+    /// The height of the map in tiles.
+    pub height: u32,
+    /// The width of a map cell in pixels.
+    pub tile_width: u32,
+    /// The height of a map cell in pixels.
+    pub tile_height: u32,
+    /// The tilesets the tiles are drawn from, sorted by their first global id.
+    pub tilesets: Vec<Tileset>,
+    /// The tile layers, from back to front.
+    pub layers: Vec<TileLayer>,
+    /// The object layers.
+    pub object_layers: Vec<ObjectLayer>,
+}
+
+impl TileMap {
+    /// Creates a map without layers. The cell size is taken from the tileset.
+    pub fn new(width: u32, height: u32, tileset: Tileset) -> Self {
+        Self {
+            width,
+            height,
+            tile_width: tileset.tile_width,
+            tile_height: tileset.tile_height,
+            tilesets: vec![tileset],
+            layers: Vec::new(),
+            object_layers: Vec::new(),
+        }
+    }
+
+    /// Adds a tileset, keeping the tilesets sorted by their first global id.
+    pub fn add_tileset(&mut self, tileset: Tileset) {
+        let index = self
+            .tilesets
+            .partition_point(|other| other.first_gid <= tileset.first_gid);
+        self.tilesets.insert(index, tileset);
+    }
+
+    /// Returns the index of the tileset a global tile id belongs to: the tileset with the
+    /// largest first global id not above the id, as in Tiled. Returns `None` for empty cells.
+    pub fn tileset_index(&self, gid: u32) -> Option<usize> {
+        let gid = gid & !FLIP_MASK;
+        if gid == 0 {
+            return None;
+        }
+        self.tilesets
+            .partition_point(|tileset| tileset.first_gid <= gid)
+            .checked_sub(1)
+    }
+
+    /// Returns the tileset a global tile id belongs to, see `tileset_index`.
+    pub fn tileset_for(&self, gid: u32) -> Option<&Tileset> {
+        self.tileset_index(gid).map(|index| &self.tilesets[index])
+    }
+
+    /// Returns the tile layer with the given name.
+    pub fn layer(&self, name: &str) -> Option<&TileLayer> {
+        self.layers.iter().find(|layer| layer.name == name)
+    }
+
+    /// Returns the tile layer with the given name mutably.
+    pub fn layer_mut(&mut self, name: &str) -> Option<&mut TileLayer> {
+        self.layers.iter_mut().find(|layer| layer.name == name)
+    }
+
+    /// Returns the object layer with the given name.
+    pub fn object_layer(&self, name: &str) -> Option<&ObjectLayer> {
+        self.object_layers.iter().find(|layer| layer.name == name)
+    }
+}
+
+/// Splits a global tile id into the local id within `tileset` and the flip flags.
+///
+/// Returns `None` for empty cells and tiles of other tilesets.
+pub(crate) fn split_gid(tileset: &Tileset, gid: u32) -> Option<(u32, u32)> {
+    if gid == 0 || !tileset.contains(gid) {
+        return None;
+    }
+    Some(((gid & !FLIP_MASK) - tileset.first_gid, gid & FLIP_MASK))
+}
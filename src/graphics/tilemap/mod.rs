@@ -0,0 +1,49 @@
+//! # Tilemap Module
+//!
+//! This module provides 2D tile maps: tileset atlases, tile and object layers, animated
+//! tiles, loading of maps made with the Tiled editor, and a renderer that batches the tiles
+//! into one static mesh per chunk, layer and tileset.
+//!
+//! ## Submodules
+//! - **map**: Tilesets, tile layers and object layers.
+//! - **tmx**: Loading of Tiled `.tmx` maps.
+//! - **tmj**: Loading of Tiled `.tmj` maps.
+//! - **renderer**: Chunked meshes for drawing the tile layers.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::graphics::tilemap::{TileMap, TileMapRenderer};
+//! use glwfr::graphics::texture::Texture;
+//!
+//! fn main() -> Result<(), glwfr::custom_errors::Errors> {
+//!     let map = TileMap::from_tmx("levels/forest.tmx")?;
+//!     let atlases = map
+//!         .tilesets
+//!         .iter()
+//!         .map(|tileset| {
+//!             let atlas = Texture::new();
+//!             atlas.load_from_file(format!("levels/{}", tileset.image))?;
+//!             Ok(atlas)
+//!         })
+//!         .collect::<Result<Vec<_>, glwfr::custom_errors::Errors>>()?;
+//!     let mut renderer = TileMapRenderer::new(&map, 32)?;
+//!
+//!     for spawn in &map.object_layer("spawns").unwrap().objects {
+//!         println!("{} at ({}, {})", spawn.name, spawn.x, spawn.y);
+//!     }
+//!
+//!     // Once per frame, with a 2D shader bound
+//!     renderer.update(&map, 1.0 / 60.0);
+//!     renderer.draw_region(&map, &atlases, [0.0, 0.0], [800.0, 600.0]);
+//!     Ok(())
+//! }
+//! ```
+
+pub mod map;
+pub mod renderer;
+pub mod tmj;
+pub mod tmx;
+
+pub use map::*;
+pub use renderer::*;
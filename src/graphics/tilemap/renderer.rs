@@ -0,0 +1,350 @@
+//! # Tile Map Renderer Module
+//!
+//! Chunked static meshes for the tile layers of a `TileMap`.
+
+use crate::custom_errors::Errors;
+use crate::graphics::gl_wrapper::{
+    BufferObject, Ebo, PrimitiveType, Vao, Vertex, VertexAttributeLayout,
+};
+use crate::graphics::stats;
+use crate::graphics::texture::Texture;
+use crate::graphics::tilemap::map::{split_gid, TileMap};
+use gl::types::*;
+use std::collections::BTreeMap;
+
+/// The attribute location of the position of a `TileVertex`.
+pub const TILE_POSITION_ATTRIBUTE: u32 = 0;
+
+/// The attribute location of the texture coordinates of a `TileVertex`.
+pub const TILE_UV_ATTRIBUTE: u32 = 2;
+
+/// A vertex of a tile map chunk with a 2D position and texture coordinates.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[repr(C)]
+pub struct TileVertex {
+    pub position: [f32; 2],
+    pub uv: [f32; 2],
+}
+
+impl Vertex for TileVertex {
+    fn layout() -> Vec<VertexAttributeLayout> {
+        vec![
+            VertexAttributeLayout {
+                index: TILE_POSITION_ATTRIBUTE,
+                size: 2,
+                r#type: gl::FLOAT,
+                normalized: gl::FALSE,
+                offset: 0,
+            },
+            VertexAttributeLayout {
+                index: TILE_UV_ATTRIBUTE,
+                size: 2,
+                r#type: gl::FLOAT,
+                normalized: gl::FALSE,
+                offset: std::mem::size_of::<[f32; 2]>(),
+            },
+        ]
+    }
+}
+
+/// An animated tile of a chunk.
+struct AnimatedTile {
+    /// The index of the first of the tile's four vertices.
+    vertex: usize,
+    /// The local id of the tile that owns the animation.
+    tile: u32,
+    /// The flip flags of the tile.
+    flags: u32,
+    /// The local id of the frame currently stored in the vertices.
+    shown: u32,
+}
+
+/// The vertices and indices of the tiles of one tileset within a chunk.
+#[derive(Default)]
+struct ChunkGeometry {
+    vertices: Vec<TileVertex>,
+    indices: Vec<u32>,
+    animated: Vec<AnimatedTile>,
+}
+
+/// The mesh of the tiles of one tileset in one layer within a square block of cells.
+struct Chunk {
+    layer: usize,
+    /// The index of the tileset in `TileMap::tilesets`.
+    tileset: usize,
+    /// The covered cells as `(x, y, width, height)`.
+    cells: (u32, u32, u32, u32),
+    vao: Vao,
+    vbo: BufferObject,
+    _ebo: Ebo,
+    animated: Vec<AnimatedTile>,
+}
+
+/// Draws the tile layers of a `TileMap`, split into chunks of static meshes.
+///
+/// Each chunk is drawn with one draw call per tileset it uses, and chunks outside the
+/// visible area can be skipped with `draw_region`. Map positions are in pixels with the origin in the top-left
+/// corner and `y` growing downwards, matching Tiled.
+pub struct TileMapRenderer {
+    chunks: Vec<Chunk>,
+    chunk_size: u32,
+    tile_width: f32,
+    tile_height: f32,
+    time: f32,
+}
+
+impl TileMapRenderer {
+    /// Builds the chunk meshes of all tile layers of a map.
+    ///
+    /// # Arguments
+    ///
+    /// * `map` - The map to draw.
+    /// * `chunk_size` - The width and height of a chunk in cells, e.g. `32`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::OpenGlError` if the buffers cannot be created.
+    pub fn new(map: &TileMap, chunk_size: u32) -> Result<Self, Errors> {
+        let mut renderer = Self {
+            chunks: Vec::new(),
+            chunk_size: chunk_size.max(1),
+            tile_width: map.tile_width as f32,
+            tile_height: map.tile_height as f32,
+            time: 0.0,
+        };
+        renderer.rebuild(map)?;
+        Ok(renderer)
+    }
+
+    /// Rebuilds all chunk meshes, e.g. after tiles were changed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::OpenGlError` if the buffers cannot be created.
+    pub fn rebuild(&mut self, map: &TileMap) -> Result<(), Errors> {
+        self.chunks.clear();
+        self.tile_width = map.tile_width as f32;
+        self.tile_height = map.tile_height as f32;
+        for (layer_index, layer) in map.layers.iter().enumerate() {
+            for chunk_y in (0..layer.height).step_by(self.chunk_size as usize) {
+                for chunk_x in (0..layer.width).step_by(self.chunk_size as usize) {
+                    let cells = (
+                        chunk_x,
+                        chunk_y,
+                        self.chunk_size.min(layer.width - chunk_x),
+                        self.chunk_size.min(layer.height - chunk_y),
+                    );
+                    let chunks = self.build_chunk(map, layer_index, cells)?;
+                    self.chunks.extend(chunks);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Builds the meshes of one chunk, one per tileset used by its tiles, in tileset order.
+    fn build_chunk(
+        &self,
+        map: &TileMap,
+        layer_index: usize,
+        cells: (u32, u32, u32, u32),
+    ) -> Result<Vec<Chunk>, Errors> {
+        let layer = &map.layers[layer_index];
+        let mut geometries: BTreeMap<usize, ChunkGeometry> = BTreeMap::new();
+
+        for y in cells.1..cells.1 + cells.3 {
+            for x in cells.0..cells.0 + cells.2 {
+                let gid = layer.tile(x, y);
+                let Some(tileset_index) = map.tileset_index(gid) else {
+                    continue;
+                };
+                let tileset = &map.tilesets[tileset_index];
+                let Some((tile, flags)) = split_gid(tileset, gid) else {
+                    continue;
+                };
+                let ChunkGeometry {
+                    vertices,
+                    indices,
+                    animated,
+                } = geometries.entry(tileset_index).or_default();
+                let shown = tileset.animated_tile(tile, self.time);
+                if tileset.animations.contains_key(&tile) {
+                    animated.push(AnimatedTile {
+                        vertex: vertices.len(),
+                        tile,
+                        flags,
+                        shown,
+                    });
+                }
+
+                // Tiles taller than a cell stick out at the top, as in Tiled
+                let left = x as f32 * self.tile_width;
+                let bottom = (y + 1) as f32 * self.tile_height;
+                let right = left + tileset.tile_width as f32;
+                let top = bottom - tileset.tile_height as f32;
+                let uvs = tileset.tile_uvs(shown, flags);
+                let first = vertices.len() as u32;
+                for (position, uv) in [[left, top], [right, top], [right, bottom], [left, bottom]]
+                    .into_iter()
+                    .zip(uvs)
+                {
+                    vertices.push(TileVertex { position, uv });
+                }
+                indices.extend_from_slice(&[
+                    first,
+                    first + 3,
+                    first + 1,
+                    first + 1,
+                    first + 3,
+                    first + 2,
+                ]);
+            }
+        }
+        geometries
+            .into_iter()
+            .map(|(tileset, geometry)| Self::upload_chunk(layer_index, tileset, cells, geometry))
+            .collect()
+    }
+
+    /// Uploads the geometry of one tileset within a chunk.
+    fn upload_chunk(
+        layer: usize,
+        tileset: usize,
+        cells: (u32, u32, u32, u32),
+        geometry: ChunkGeometry,
+    ) -> Result<Chunk, Errors> {
+        let ChunkGeometry {
+            vertices,
+            indices,
+            animated,
+        } = geometry;
+        let mut vao = Vao::new()?;
+        vao.bind();
+        let usage = if animated.is_empty() {
+            gl::STATIC_DRAW
+        } else {
+            gl::DYNAMIC_DRAW
+        };
+        let vbo = BufferObject::new(gl::ARRAY_BUFFER, usage)?;
+        vbo.bind();
+        vbo.allocate(std::mem::size_of_val(vertices.as_slice()));
+        vbo.update_data(0, &vertices);
+        let ebo = Ebo::new()?;
+        ebo.bind();
+        let index_type = ebo.store_indices(&indices);
+        TileVertex::setup_attributes();
+        vao.unbind();
+        vao.set_index_count(indices.len());
+        vao.set_index_type(index_type);
+
+        Ok(Chunk {
+            layer,
+            tileset,
+            cells,
+            vao,
+            vbo,
+            _ebo: ebo,
+            animated,
+        })
+    }
+
+    /// Advances tile animations. Call this once per frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `map` - The map the renderer was built from.
+    /// * `delta_time` - The time since the previous update in seconds.
+    pub fn update(&mut self, map: &TileMap, delta_time: f32) {
+        self.time += delta_time.max(0.0);
+        for chunk in &mut self.chunks {
+            let tileset = &map.tilesets[chunk.tileset];
+            let mut bound = false;
+            for tile in &mut chunk.animated {
+                let shown = tileset.animated_tile(tile.tile, self.time);
+                if shown == tile.shown {
+                    continue;
+                }
+                tile.shown = shown;
+                if !bound {
+                    chunk.vbo.bind();
+                    bound = true;
+                }
+                let uvs = tileset.tile_uvs(shown, tile.flags);
+                for (corner, uv) in uvs.into_iter().enumerate() {
+                    let offset = (tile.vertex + corner) * std::mem::size_of::<TileVertex>()
+                        + std::mem::size_of::<[f32; 2]>();
+                    chunk.vbo.update_data(offset, &uv);
+                }
+            }
+        }
+    }
+
+    /// Draws all visible layers of the map, from back to front, with the currently bound
+    /// shader program.
+    ///
+    /// # Arguments
+    ///
+    /// * `map` - The map the renderer was built from.
+    /// * `atlases` - The atlas textures of the tilesets, in the order of `map.tilesets`. Each
+    ///   is bound to texture unit 0 before the tiles of its tileset are drawn.
+    pub fn draw(&self, map: &TileMap, atlases: &[Texture]) {
+        let mut bound = None;
+        for chunk in &self.chunks {
+            if map.layers[chunk.layer].visible {
+                Self::draw_chunk(chunk, atlases, &mut bound);
+            }
+        }
+    }
+
+    /// Draws the chunks of the visible layers that overlap an area of the map, e.g. the part
+    /// seen by the camera.
+    ///
+    /// # Arguments
+    ///
+    /// * `map` - The map the renderer was built from.
+    /// * `atlases` - The atlas textures of the tilesets, see `draw`.
+    /// * `min` - The top-left corner of the area in pixels.
+    /// * `max` - The bottom-right corner of the area in pixels.
+    pub fn draw_region(&self, map: &TileMap, atlases: &[Texture], min: [f32; 2], max: [f32; 2]) {
+        let mut bound = None;
+        for chunk in &self.chunks {
+            if !map.layers[chunk.layer].visible {
+                continue;
+            }
+            let (x, y, width, height) = chunk.cells;
+            let left = x as f32 * self.tile_width;
+            let right = (x + width) as f32 * self.tile_width;
+            // Tiles may stick out above their cell by up to one tileset tile
+            let top = y as f32 * self.tile_height - map.tilesets[chunk.tileset].tile_height as f32;
+            let bottom = (y + height) as f32 * self.tile_height;
+            if right >= min[0] && left <= max[0] && bottom >= min[1] && top <= max[1] {
+                Self::draw_chunk(chunk, atlases, &mut bound);
+            }
+        }
+    }
+
+    /// Returns the number of chunk meshes.
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Draws a chunk, binding the atlas of its tileset unless it is bound already.
+    fn draw_chunk(chunk: &Chunk, atlases: &[Texture], bound: &mut Option<usize>) {
+        if *bound != Some(chunk.tileset) {
+            if let Some(atlas) = atlases.get(chunk.tileset) {
+                atlas.bind(gl::TEXTURE0);
+            }
+            *bound = Some(chunk.tileset);
+        }
+        chunk.vao.bind();
+        unsafe {
+            gl::DrawElements(
+                gl::TRIANGLES,
+                chunk.vao.index_count() as GLsizei,
+                chunk.vao.index_type().gl_type(),
+                std::ptr::null(),
+            );
+        }
+        stats::record_draw(PrimitiveType::Triangles, chunk.vao.index_count(), 1);
+    }
+}
@@ -0,0 +1,458 @@
+//! # TMJ Module
+//!
+//! Loading of orthogonal maps saved by the Tiled editor in its JSON (`.tmj`) format.
+//!
+//! The resulting `TileMap` is the same as for the equivalent `.tmx` map. Tile layer data
+//! must be stored as an array of global tile ids, not base64. External tilesets may be
+//! JSON (`.tsj`) or XML (`.tsx`) files.
+
+use crate::custom_errors::Errors;
+use crate::graphics::tilemap::map::*;
+use crate::graphics::tilemap::tmx::parse_tsx;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+impl TileMap {
+    /// Loads a map from a Tiled `.tmj` file. External tilesets are resolved relative to the
+    /// map file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::FileLoadError` if a file cannot be read, or an
+    /// `Errors::MapLoadError` if the map is malformed or uses an unsupported feature.
+    pub fn from_tmj(path: &str) -> Result<Self, Errors> {
+        let source = fs::read_to_string(path)?;
+        let directory = Path::new(path).parent().unwrap_or(Path::new(""));
+        parse_map(&source, Some(directory))
+    }
+
+    /// Loads a map from the contents of a Tiled `.tmj` file. The map may not reference
+    /// external tilesets.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::MapLoadError` if the map is malformed or uses an unsupported
+    /// feature.
+    pub fn from_tmj_str(source: &str) -> Result<Self, Errors> {
+        parse_map(source, None)
+    }
+}
+
+/// A parsed JSON value. Numbers keep their source text, so property values are stored
+/// exactly as written, like the attributes of a `.tmx` file.
+enum Json<'a> {
+    Null,
+    Bool(bool),
+    Number(&'a str),
+    String(String),
+    Array(Vec<Json<'a>>),
+    Object(Vec<(String, Json<'a>)>),
+}
+
+impl<'a> Json<'a> {
+    /// Returns the member with the given name of an object.
+    fn get(&self, name: &str) -> Option<&Json<'a>> {
+        match self {
+            Json::Object(members) => members
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    fn as_array(&self) -> &[Json<'a>] {
+        match self {
+            Json::Array(values) => values,
+            _ => &[],
+        }
+    }
+
+    /// Returns the text of a string member, or an empty string.
+    fn text(&self, name: &str) -> &str {
+        self.get(name).and_then(Json::as_str).unwrap_or_default()
+    }
+
+    /// Returns a boolean member, or `default` if it is missing.
+    fn flag(&self, name: &str, default: bool) -> Result<bool, Errors> {
+        match self.get(name) {
+            Some(Json::Bool(value)) => Ok(*value),
+            None | Some(Json::Null) => Ok(default),
+            Some(_) => Err(Errors::MapLoadError(format!("{} is not a boolean", name))),
+        }
+    }
+
+    fn number<T: std::str::FromStr>(&self, name: &str) -> Result<Option<T>, Errors> {
+        match self.get(name) {
+            Some(Json::Number(value)) => value
+                .parse()
+                .map(Some)
+                .map_err(|_| Errors::MapLoadError(format!("invalid value {} for {}", value, name))),
+            None | Some(Json::Null) => Ok(None),
+            Some(_) => Err(Errors::MapLoadError(format!("{} is not a number", name))),
+        }
+    }
+
+    fn required<T: std::str::FromStr>(&self, name: &str) -> Result<T, Errors> {
+        self.number(name)?
+            .ok_or_else(|| Errors::MapLoadError(format!("missing {}", name)))
+    }
+}
+
+/// A recursive descent parser for JSON documents.
+struct Parser<'a> {
+    source: &'a str,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, message: &str) -> Errors {
+        Errors::MapLoadError(format!("{} at byte {}", message, self.position))
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.source[self.position..];
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_whitespace();
+        self.source.as_bytes().get(self.position).copied()
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), Errors> {
+        if self.peek() != Some(byte) {
+            return Err(self.error(&format!("expected '{}'", byte as char)));
+        }
+        self.position += 1;
+        Ok(())
+    }
+
+    fn keyword(&mut self, keyword: &str, value: Json<'a>) -> Result<Json<'a>, Errors> {
+        if !self.source[self.position..].starts_with(keyword) {
+            return Err(self.error("unexpected character"));
+        }
+        self.position += keyword.len();
+        Ok(value)
+    }
+
+    fn value(&mut self) -> Result<Json<'a>, Errors> {
+        match self.peek() {
+            Some(b'{') => self.object(),
+            Some(b'[') => self.array(),
+            Some(b'"') => self.string().map(Json::String),
+            Some(b't') => self.keyword("true", Json::Bool(true)),
+            Some(b'f') => self.keyword("false", Json::Bool(false)),
+            Some(b'n') => self.keyword("null", Json::Null),
+            Some(b'-' | b'0'..=b'9') => {
+                let start = self.position;
+                let length = self.source[start..]
+                    .find(|c: char| !matches!(c, '0'..='9' | '-' | '+' | '.' | 'e' | 'E'))
+                    .unwrap_or(self.source.len() - start);
+                self.position += length;
+                Ok(Json::Number(&self.source[start..self.position]))
+            }
+            Some(_) => Err(self.error("unexpected character")),
+            None => Err(self.error("unexpected end of document")),
+        }
+    }
+
+    fn object(&mut self) -> Result<Json<'a>, Errors> {
+        self.expect(b'{')?;
+        let mut members = Vec::new();
+        if self.peek() == Some(b'}') {
+            self.position += 1;
+            return Ok(Json::Object(members));
+        }
+        loop {
+            if self.peek() != Some(b'"') {
+                return Err(self.error("expected a member name"));
+            }
+            let name = self.string()?;
+            self.expect(b':')?;
+            members.push((name, self.value()?));
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(b'}') => {
+                    self.position += 1;
+                    return Ok(Json::Object(members));
+                }
+                _ => return Err(self.error("expected ',' or '}'")),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Json<'a>, Errors> {
+        self.expect(b'[')?;
+        let mut values = Vec::new();
+        if self.peek() == Some(b']') {
+            self.position += 1;
+            return Ok(Json::Array(values));
+        }
+        loop {
+            values.push(self.value()?);
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(b']') => {
+                    self.position += 1;
+                    return Ok(Json::Array(values));
+                }
+                _ => return Err(self.error("expected ',' or ']'")),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, Errors> {
+        self.expect(b'"')?;
+        let mut value = String::new();
+        let mut characters = self.source[self.position..].char_indices();
+        while let Some((offset, character)) = characters.next() {
+            match character {
+                '"' => {
+                    self.position += offset + 1;
+                    return Ok(value);
+                }
+                '\\' => {
+                    let escaped = match characters.next().map(|(_, escaped)| escaped) {
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some('r') => '\r',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('u') => {
+                            let mut code = 0;
+                            for _ in 0..4 {
+                                let digit = characters
+                                    .next()
+                                    .and_then(|(_, digit)| digit.to_digit(16))
+                                    .ok_or_else(|| self.error("invalid unicode escape"))?;
+                                code = code * 16 + digit;
+                            }
+                            // Surrogate pairs are not combined and become replacement characters
+                            char::from_u32(code).unwrap_or('\u{FFFD}')
+                        }
+                        Some(other) => other,
+                        None => break,
+                    };
+                    value.push(escaped);
+                }
+                _ => value.push(character),
+            }
+        }
+        Err(self.error("unterminated string"))
+    }
+}
+
+/// Parses a complete JSON document.
+fn parse_json(source: &str) -> Result<Json<'_>, Errors> {
+    let mut parser = Parser {
+        source,
+        position: 0,
+    };
+    let value = parser.value()?;
+    if parser.peek().is_some() {
+        return Err(parser.error("trailing characters"));
+    }
+    Ok(value)
+}
+
+fn parse_map(source: &str, directory: Option<&Path>) -> Result<TileMap, Errors> {
+    let map = parse_json(source)?;
+    if !matches!(map, Json::Object(_)) {
+        return Err(Errors::MapLoadError("map is not a JSON object".to_string()));
+    }
+    let orientation = map.text("orientation");
+    if !orientation.is_empty() && orientation != "orthogonal" {
+        return Err(Errors::MapLoadError(format!(
+            "unsupported orientation {}",
+            orientation
+        )));
+    }
+    if map.flag("infinite", false)? {
+        return Err(Errors::MapLoadError(
+            "infinite maps are not supported".to_string(),
+        ));
+    }
+
+    let mut tilesets = Vec::new();
+    for tileset in map.get("tilesets").map_or(&[][..], Json::as_array) {
+        let first_gid = tileset.required("firstgid")?;
+        let mut tileset = match tileset.get("source").and_then(Json::as_str) {
+            Some(path) => {
+                let directory = directory.ok_or_else(|| {
+                    Errors::MapLoadError(format!("external tileset {} cannot be resolved", path))
+                })?;
+                let tileset_source = fs::read_to_string(directory.join(path))?;
+                if path.ends_with(".tsx") {
+                    parse_tsx(&tileset_source)?
+                } else {
+                    parse_tileset(&parse_json(&tileset_source)?)?
+                }
+            }
+            None => parse_tileset(tileset)?,
+        };
+        tileset.first_gid = first_gid;
+        tilesets.push(tileset);
+    }
+    if tilesets.is_empty() {
+        return Err(Errors::MapLoadError("map has no tileset".to_string()));
+    }
+    tilesets.sort_by_key(|tileset| tileset.first_gid);
+
+    let mut layers = Vec::new();
+    let mut object_layers = Vec::new();
+    for layer in map.get("layers").map_or(&[][..], Json::as_array) {
+        match layer.text("type") {
+            "tilelayer" => layers.push(parse_tile_layer(layer)?),
+            "objectgroup" => object_layers.push(parse_object_layer(layer)?),
+            _ => {}
+        }
+    }
+
+    Ok(TileMap {
+        width: map.required("width")?,
+        height: map.required("height")?,
+        tile_width: map.required("tilewidth")?,
+        tile_height: map.required("tileheight")?,
+        tilesets,
+        layers,
+        object_layers,
+    })
+}
+
+fn parse_tile_layer(layer: &Json<'_>) -> Result<TileLayer, Errors> {
+    let name = layer.text("name");
+    if layer.get("chunks").is_some() {
+        return Err(Errors::MapLoadError(
+            "infinite maps are not supported".to_string(),
+        ));
+    }
+    if layer.get("compression").and_then(Json::as_str).is_some() {
+        return Err(Errors::MapLoadError(
+            "compressed tile layers are not supported".to_string(),
+        ));
+    }
+    match layer.get("encoding").and_then(Json::as_str) {
+        None | Some("csv") => {}
+        Some(encoding) => {
+            return Err(Errors::MapLoadError(format!(
+                "unsupported tile layer encoding {}",
+                encoding
+            )))
+        }
+    }
+
+    let mut tile_layer = TileLayer::new(name, layer.required("width")?, layer.required("height")?)?;
+    tile_layer.visible = layer.flag("visible", true)?;
+    tile_layer.tiles = layer
+        .get("data")
+        .map_or(&[][..], Json::as_array)
+        .iter()
+        .map(|gid| match gid {
+            Json::Number(value) => value
+                .parse()
+                .map_err(|_| Errors::MapLoadError(format!("invalid tile id \"{}\"", value))),
+            _ => Err(Errors::MapLoadError(format!(
+                "layer {} has a tile id that is not a number",
+                name
+            ))),
+        })
+        .collect::<Result<_, _>>()?;
+    if tile_layer.tiles.len() != (tile_layer.width * tile_layer.height) as usize {
+        return Err(Errors::MapLoadError(format!(
+            "layer {} has {} tiles instead of {}",
+            name,
+            tile_layer.tiles.len(),
+            tile_layer.width * tile_layer.height
+        )));
+    }
+    Ok(tile_layer)
+}
+
+fn parse_object_layer(layer: &Json<'_>) -> Result<ObjectLayer, Errors> {
+    let mut objects = Vec::new();
+    for object in layer.get("objects").map_or(&[][..], Json::as_array) {
+        let kind = match object.text("type") {
+            "" => object.text("class"),
+            kind => kind,
+        };
+        let mut properties = HashMap::new();
+        for property in object.get("properties").map_or(&[][..], Json::as_array) {
+            let value = match property.get("value") {
+                Some(Json::String(value)) => value.clone(),
+                Some(Json::Number(value)) => value.to_string(),
+                Some(Json::Bool(value)) => value.to_string(),
+                _ => String::new(),
+            };
+            properties.insert(property.text("name").to_string(), value);
+        }
+        objects.push(MapObject {
+            id: object.number("id")?.unwrap_or(0),
+            name: object.text("name").to_string(),
+            kind: kind.to_string(),
+            x: object.number("x")?.unwrap_or(0.0),
+            y: object.number("y")?.unwrap_or(0.0),
+            width: object.number("width")?.unwrap_or(0.0),
+            height: object.number("height")?.unwrap_or(0.0),
+            gid: object.number("gid")?.unwrap_or(0),
+            properties,
+        });
+    }
+    Ok(ObjectLayer {
+        name: layer.text("name").to_string(),
+        objects,
+    })
+}
+
+/// Parses an embedded tileset or the contents of a `.tsj` file.
+fn parse_tileset(tileset_json: &Json<'_>) -> Result<Tileset, Errors> {
+    let tile_width = tileset_json.required("tilewidth")?;
+    let tile_height = tileset_json.required("tileheight")?;
+    let mut tileset = Tileset::new(
+        (
+            tileset_json.required("imagewidth")?,
+            tileset_json.required("imageheight")?,
+        ),
+        tile_width,
+        tile_height,
+    );
+    tileset.image = tileset_json.text("image").to_string();
+    tileset.margin = tileset_json.number("margin")?.unwrap_or(0);
+    tileset.spacing = tileset_json.number("spacing")?.unwrap_or(0);
+
+    for tile in tileset_json.get("tiles").map_or(&[][..], Json::as_array) {
+        let Some(animation) = tile.get("animation") else {
+            continue;
+        };
+        let id = tile.required("id")?;
+        for frame in animation.as_array() {
+            let duration: u32 = frame.required("duration")?;
+            tileset.animations.entry(id).or_default().push(TileFrame {
+                tile: frame.required("tileid")?,
+                duration: duration as f32 / 1000.0,
+            });
+        }
+    }
+
+    let stride_x = tile_width + tileset.spacing;
+    let stride_y = tile_height + tileset.spacing;
+    let usable_width =
+        (tileset.texture_size.0 + tileset.spacing).saturating_sub(2 * tileset.margin);
+    let usable_height =
+        (tileset.texture_size.1 + tileset.spacing).saturating_sub(2 * tileset.margin);
+    tileset.columns = tileset_json
+        .number("columns")?
+        .unwrap_or(usable_width / stride_x.max(1));
+    tileset.tile_count = tileset_json
+        .number("tilecount")?
+        .unwrap_or(tileset.columns * (usable_height / stride_y.max(1)));
+    Ok(tileset)
+}
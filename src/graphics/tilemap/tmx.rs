@@ -0,0 +1,415 @@
+//! # TMX Module
+//!
+//! Loading of orthogonal maps saved by the Tiled editor in its XML (`.tmx`) format.
+//!
+//! Tile layer data must be CSV or plain XML encoded. The tileset parser is shared with the
+//! JSON loader for external `.tsx` tilesets.
+
+use crate::custom_errors::Errors;
+use crate::graphics::tilemap::map::*;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+impl TileMap {
+    /// Loads a map from a Tiled `.tmx` file. External tilesets (`.tsx`) are resolved relative
+    /// to the map file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::FileLoadError` if a file cannot be read, or an
+    /// `Errors::MapLoadError` if the map is malformed or uses an unsupported feature.
+    pub fn from_tmx(path: &str) -> Result<Self, Errors> {
+        let source = fs::read_to_string(path)?;
+        let directory = Path::new(path).parent().unwrap_or(Path::new(""));
+        parse_map(&source, Some(directory))
+    }
+
+    /// Loads a map from the contents of a Tiled `.tmx` file. The map may not reference
+    /// external tilesets.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::MapLoadError` if the map is malformed or uses an unsupported
+    /// feature.
+    pub fn from_tmx_str(source: &str) -> Result<Self, Errors> {
+        parse_map(source, None)
+    }
+}
+
+/// A start, end or empty element tag, with the text following it.
+struct Tag<'a> {
+    name: &'a str,
+    attributes: HashMap<&'a str, String>,
+    closing: bool,
+    self_closing: bool,
+    text: &'a str,
+}
+
+impl Tag<'_> {
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.get(name).map(String::as_str)
+    }
+
+    fn number<T: std::str::FromStr>(&self, name: &str) -> Result<Option<T>, Errors> {
+        match self.attribute(name) {
+            Some(value) => value.trim().parse().map(Some).map_err(|_| {
+                Errors::MapLoadError(format!(
+                    "invalid value \"{}\" for attribute {} of <{}>",
+                    value, name, self.name
+                ))
+            }),
+            None => Ok(None),
+        }
+    }
+
+    fn required<T: std::str::FromStr>(&self, name: &str) -> Result<T, Errors> {
+        self.number(name)?.ok_or_else(|| {
+            Errors::MapLoadError(format!("missing attribute {} of <{}>", name, self.name))
+        })
+    }
+}
+
+/// Splits an XML document into its tags. Comments, processing instructions and doctype
+/// declarations are skipped.
+fn tokenize(source: &str) -> Result<Vec<Tag<'_>>, Errors> {
+    let mut tags = Vec::new();
+    let mut rest = source;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start..];
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            let end = comment
+                .find("-->")
+                .ok_or_else(|| Errors::MapLoadError("unterminated comment".to_string()))?;
+            rest = &comment[end + 3..];
+            continue;
+        }
+        let end = rest
+            .find('>')
+            .ok_or_else(|| Errors::MapLoadError("unterminated tag".to_string()))?;
+        let inner = &rest[1..end];
+        rest = &rest[end + 1..];
+        if inner.starts_with('?') || inner.starts_with('!') {
+            continue;
+        }
+
+        let closing = inner.starts_with('/');
+        let self_closing = inner.ends_with('/');
+        let inner = inner.trim_start_matches('/').trim_end_matches('/').trim();
+        let name_end = inner.find(char::is_whitespace).unwrap_or(inner.len());
+        let text = &rest[..rest.find('<').unwrap_or(rest.len())];
+        tags.push(Tag {
+            name: &inner[..name_end],
+            attributes: parse_attributes(&inner[name_end..])?,
+            closing,
+            self_closing,
+            text,
+        });
+    }
+    Ok(tags)
+}
+
+/// Parses the `name="value"` pairs of a tag.
+fn parse_attributes(mut source: &str) -> Result<HashMap<&str, String>, Errors> {
+    let mut attributes = HashMap::new();
+    loop {
+        source = source.trim_start();
+        let Some(equals) = source.find('=') else {
+            return Ok(attributes);
+        };
+        let name = source[..equals].trim();
+        let value = source[equals + 1..].trim_start();
+        let quote = value
+            .chars()
+            .next()
+            .filter(|quote| *quote == '"' || *quote == '\'')
+            .ok_or_else(|| Errors::MapLoadError(format!("unquoted attribute {}", name)))?;
+        let end = value[1..]
+            .find(quote)
+            .ok_or_else(|| Errors::MapLoadError(format!("unterminated attribute {}", name)))?;
+        attributes.insert(name, unescape(&value[1..end + 1]));
+        source = &value[end + 2..];
+    }
+}
+
+/// Replaces the predefined XML entities.
+fn unescape(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Parses a CSV encoded tile layer.
+fn parse_csv(data: &str) -> Result<Vec<u32>, Errors> {
+    data.split(',')
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|_| Errors::MapLoadError(format!("invalid tile id \"{}\"", value)))
+        })
+        .collect()
+}
+
+/// The element whose children are currently being parsed.
+#[derive(Clone, Copy, PartialEq)]
+enum Scope {
+    Map,
+    Tileset,
+    Layer,
+    ObjectGroup,
+    Object,
+}
+
+fn parse_map(source: &str, directory: Option<&Path>) -> Result<TileMap, Errors> {
+    let tags = tokenize(source)?;
+    let map_tag = tags
+        .iter()
+        .find(|tag| tag.name == "map" && !tag.closing)
+        .ok_or_else(|| Errors::MapLoadError("missing <map> element".to_string()))?;
+    if let Some(orientation) = map_tag.attribute("orientation") {
+        if orientation != "orthogonal" {
+            return Err(Errors::MapLoadError(format!(
+                "unsupported orientation {}",
+                orientation
+            )));
+        }
+    }
+
+    let mut tilesets = Vec::new();
+    let mut layers = Vec::new();
+    let mut object_layers = Vec::new();
+    let mut scope = Vec::new();
+
+    let mut index = 0;
+    while index < tags.len() {
+        let tag = &tags[index];
+        index += 1;
+        if tag.closing {
+            if matches!(
+                tag.name,
+                "map" | "tileset" | "layer" | "objectgroup" | "object"
+            ) {
+                scope.pop();
+            }
+            continue;
+        }
+
+        let opens = !tag.self_closing;
+        match (scope.last().copied(), tag.name) {
+            (_, "map") => scope.push(Scope::Map),
+            (Some(Scope::Map), "tileset") => {
+                let first_gid = tag.required("firstgid")?;
+                match tag.attribute("source") {
+                    Some(path) => {
+                        let directory = directory.ok_or_else(|| {
+                            Errors::MapLoadError(format!(
+                                "external tileset {} cannot be resolved",
+                                path
+                            ))
+                        })?;
+                        let tileset_source = fs::read_to_string(directory.join(path))?;
+                        let mut tileset = parse_tsx(&tileset_source)?;
+                        tileset.first_gid = first_gid;
+                        tilesets.push(tileset);
+                    }
+                    None => {
+                        let end = if opens {
+                            tags[index..]
+                                .iter()
+                                .position(|tag| tag.closing && tag.name == "tileset")
+                                .map_or(tags.len(), |end| index + end)
+                        } else {
+                            index
+                        };
+                        let mut tileset = parse_tileset(&tags[index - 1..end])?;
+                        tileset.first_gid = first_gid;
+                        tilesets.push(tileset);
+                        if opens {
+                            index = end + 1;
+                        }
+                        continue;
+                    }
+                }
+                if opens {
+                    scope.push(Scope::Tileset);
+                }
+            }
+            (Some(Scope::Map), "layer") => {
+                let mut layer = TileLayer::new(
+                    tag.attribute("name").unwrap_or_default(),
+                    tag.required("width")?,
+                    tag.required("height")?,
+                )?;
+                layer.visible = tag.attribute("visible") != Some("0");
+                layer.tiles.clear();
+                layers.push(layer);
+                if opens {
+                    scope.push(Scope::Layer);
+                }
+            }
+            (Some(Scope::Layer), "data") => {
+                if tag.attribute("compression").is_some() {
+                    return Err(Errors::MapLoadError(
+                        "compressed tile layers are not supported".to_string(),
+                    ));
+                }
+                match tag.attribute("encoding") {
+                    Some("csv") => {
+                        let layer = layers.last_mut().expect("layer scope without layer");
+                        layer.tiles = parse_csv(tag.text)?;
+                    }
+                    None => {}
+                    Some(encoding) => {
+                        return Err(Errors::MapLoadError(format!(
+                            "unsupported tile layer encoding {}",
+                            encoding
+                        )))
+                    }
+                }
+            }
+            (Some(Scope::Layer), "tile") => {
+                let layer = layers.last_mut().expect("layer scope without layer");
+                layer.tiles.push(tag.number("gid")?.unwrap_or(0));
+            }
+            (Some(Scope::Layer), "chunk") => {
+                return Err(Errors::MapLoadError(
+                    "infinite maps are not supported".to_string(),
+                ));
+            }
+            (Some(Scope::Map), "objectgroup") => {
+                object_layers.push(ObjectLayer {
+                    name: tag.attribute("name").unwrap_or_default().to_string(),
+                    objects: Vec::new(),
+                });
+                if opens {
+                    scope.push(Scope::ObjectGroup);
+                }
+            }
+            (Some(Scope::ObjectGroup), "object") => {
+                let object = MapObject {
+                    id: tag.number("id")?.unwrap_or(0),
+                    name: tag.attribute("name").unwrap_or_default().to_string(),
+                    kind: tag
+                        .attribute("type")
+                        .or(tag.attribute("class"))
+                        .unwrap_or_default()
+                        .to_string(),
+                    x: tag.number("x")?.unwrap_or(0.0),
+                    y: tag.number("y")?.unwrap_or(0.0),
+                    width: tag.number("width")?.unwrap_or(0.0),
+                    height: tag.number("height")?.unwrap_or(0.0),
+                    gid: tag.number("gid")?.unwrap_or(0),
+                    properties: HashMap::new(),
+                };
+                let layer = object_layers
+                    .last_mut()
+                    .expect("object scope without layer");
+                layer.objects.push(object);
+                if opens {
+                    scope.push(Scope::Object);
+                }
+            }
+            (Some(Scope::Object), "property") => {
+                let layer = object_layers
+                    .last_mut()
+                    .expect("object scope without layer");
+                let object = layer
+                    .objects
+                    .last_mut()
+                    .expect("object scope without object");
+                let value = tag
+                    .attribute("value")
+                    .map_or_else(|| tag.text.trim().to_string(), str::to_string);
+                object
+                    .properties
+                    .insert(tag.attribute("name").unwrap_or_default().to_string(), value);
+            }
+            _ => {}
+        }
+    }
+
+    for layer in &layers {
+        if layer.tiles.len() != (layer.width * layer.height) as usize {
+            return Err(Errors::MapLoadError(format!(
+                "layer {} has {} tiles instead of {}",
+                layer.name,
+                layer.tiles.len(),
+                layer.width * layer.height
+            )));
+        }
+    }
+
+    if tilesets.is_empty() {
+        return Err(Errors::MapLoadError("map has no tileset".to_string()));
+    }
+    tilesets.sort_by_key(|tileset| tileset.first_gid);
+
+    Ok(TileMap {
+        width: map_tag.required("width")?,
+        height: map_tag.required("height")?,
+        tile_width: map_tag.required("tilewidth")?,
+        tile_height: map_tag.required("tileheight")?,
+        tilesets,
+        layers,
+        object_layers,
+    })
+}
+
+/// Parses the contents of an external `.tsx` tileset. Its first global id is left at `1`.
+pub(crate) fn parse_tsx(source: &str) -> Result<Tileset, Errors> {
+    parse_tileset(&tokenize(source)?)
+}
+
+/// Parses the tags of a `<tileset>` element, starting with the element itself.
+fn parse_tileset(tags: &[Tag<'_>]) -> Result<Tileset, Errors> {
+    let tileset_tag = tags
+        .iter()
+        .find(|tag| tag.name == "tileset" && !tag.closing)
+        .ok_or_else(|| Errors::MapLoadError("missing <tileset> element".to_string()))?;
+    let tile_width = tileset_tag.required("tilewidth")?;
+    let tile_height = tileset_tag.required("tileheight")?;
+    let mut tileset = Tileset::new((0, 0), tile_width, tile_height);
+    tileset.margin = tileset_tag.number("margin")?.unwrap_or(0);
+    tileset.spacing = tileset_tag.number("spacing")?.unwrap_or(0);
+
+    let mut current_tile = None;
+    for tag in tags {
+        match (tag.name, tag.closing) {
+            ("image", false) => {
+                tileset.image = tag.attribute("source").unwrap_or_default().to_string();
+                tileset.texture_size = (tag.required("width")?, tag.required("height")?);
+            }
+            ("tile", false) => current_tile = tag.number("id")?,
+            ("tile", true) => current_tile = None,
+            ("frame", false) => {
+                let tile = current_tile
+                    .ok_or_else(|| Errors::MapLoadError("<frame> outside of <tile>".to_string()))?;
+                let duration: u32 = tag.required("duration")?;
+                tileset.animations.entry(tile).or_default().push(TileFrame {
+                    tile: tag.required("tileid")?,
+                    duration: duration as f32 / 1000.0,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    let stride_x = tile_width + tileset.spacing;
+    let stride_y = tile_height + tileset.spacing;
+    let usable_width =
+        (tileset.texture_size.0 + tileset.spacing).saturating_sub(2 * tileset.margin);
+    let usable_height =
+        (tileset.texture_size.1 + tileset.spacing).saturating_sub(2 * tileset.margin);
+    tileset.columns = tileset_tag
+        .number("columns")?
+        .unwrap_or(usable_width / stride_x.max(1));
+    tileset.tile_count = tileset_tag
+        .number("tilecount")?
+        .unwrap_or(tileset.columns * (usable_height / stride_y.max(1)));
+    Ok(tileset)
+}
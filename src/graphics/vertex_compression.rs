@@ -0,0 +1,402 @@
+//! # Vertex Compression Module
+//!
+//! This module stores mesh vertices in compact attribute formats to save vertex buffer
+//! memory and bandwidth. Positions and texture coordinates can be stored as half floats, and
+//! normals and tangents packed into a single signed normalized 10-10-10-2 integer. A static
+//! vertex with a position, normal and UV shrinks from 32 to 16 bytes.
+//!
+//! OpenGL converts the compressed attributes back to floats when reading them, so shaders
+//! declare their inputs as `vec3`, `vec2` and `vec4` as usual. Half floats keep about three
+//! significant digits, which suits meshes modelled around their own origin; large level
+//! geometry in world coordinates should keep 32-bit positions.
+//!
+//! Vertices are given as separate `VertexStreams` and encoded into one interleaved buffer by
+//! `Mesh::from_streams`, using the attribute locations of the `standard_vertex` module and
+//! the skinning attributes for skinned meshes.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::graphics::mesh::Mesh;
+//! use glwfr::graphics::vertex_compression::{VertexCompression, VertexStreams};
+//!
+//! fn main() -> Result<(), glwfr::custom_errors::Errors> {
+//!     let streams = VertexStreams::new(vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]])
+//!         .with_normals(vec![[0.0, 0.0, 1.0]; 3])
+//!         .with_uvs(vec![[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]]);
+//!     println!("{} bytes per vertex", streams.vertex_size(VertexCompression::ALL));
+//!
+//!     let mesh = Mesh::from_streams(&streams, &[0u16, 1, 2], VertexCompression::ALL)?;
+//!     Ok(())
+//! }
+//! ```
+
+use crate::custom_errors::Errors;
+use crate::graphics::gl_wrapper::{VertexAttribute, VertexAttributeLayout};
+use crate::graphics::standard_vertex::{
+    StandardVertex, COLOR_ATTRIBUTE, NORMAL_ATTRIBUTE, POSITION_ATTRIBUTE, TANGENT_ATTRIBUTE,
+    UV_ATTRIBUTE,
+};
+use crate::scene::{JOINTS_ATTRIBUTE, WEIGHTS_ATTRIBUTE};
+use gl::types::*;
+use std::os::raw::c_void;
+
+/// Which vertex attributes are stored in compressed formats.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct VertexCompression {
+    /// Store positions as three half floats, padded to 8 bytes.
+    pub half_positions: bool,
+    /// Store texture coordinates as two half floats.
+    pub half_uvs: bool,
+    /// Store normals and tangents as signed normalized 10-10-10-2 integers. The 2-bit
+    /// component holds the handedness of tangents.
+    pub packed_normals: bool,
+}
+
+impl VertexCompression {
+    /// All attributes are stored as 32-bit floats.
+    pub const NONE: VertexCompression = VertexCompression {
+        half_positions: false,
+        half_uvs: false,
+        packed_normals: false,
+    };
+
+    /// All supported attributes are compressed.
+    pub const ALL: VertexCompression = VertexCompression {
+        half_positions: true,
+        half_uvs: true,
+        packed_normals: true,
+    };
+}
+
+/// The attributes of a mesh's vertices as separate arrays.
+///
+/// Every array except `positions` is optional; empty arrays are left out of the vertex
+/// buffer. Non-empty arrays must have one element per position.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VertexStreams {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+    /// Tangents with the handedness of the bitangent in `w`, `1.0` or `-1.0`.
+    pub tangents: Vec<[f32; 4]>,
+    pub uvs: Vec<[f32; 2]>,
+    pub colors: Vec<[u8; 4]>,
+    /// The indices of up to four bones influencing each vertex.
+    pub joints: Vec<[u16; 4]>,
+    /// The weights of the bones in `joints`.
+    pub weights: Vec<[f32; 4]>,
+}
+
+/// One attribute of an encoded vertex buffer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EncodedAttribute {
+    /// The location, format and offset of the attribute.
+    pub layout: VertexAttributeLayout,
+    /// Whether the attribute is read as integers with `glVertexAttribIPointer`.
+    pub integer: bool,
+}
+
+/// Interleaved vertex data produced by `VertexStreams::encode`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EncodedVertices {
+    /// The bytes of all vertices.
+    pub data: Vec<u8>,
+    /// The size of one vertex in bytes.
+    pub stride: usize,
+    /// The attributes of a vertex.
+    pub attributes: Vec<EncodedAttribute>,
+}
+
+impl EncodedVertices {
+    /// Create and enable a `VertexAttribute` for every attribute.
+    ///
+    /// The VAO and the buffer holding `data` must be bound before calling this function.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glVertexAttribPointer` or `glVertexAttribIPointer`
+    /// and `glEnableVertexAttribArray` for each attribute.
+    pub fn setup_attributes(&self) -> Vec<VertexAttribute> {
+        self.attributes
+            .iter()
+            .map(|attribute| {
+                let layout = attribute.layout;
+                let vertex_attribute = if attribute.integer {
+                    VertexAttribute::new_integer(
+                        layout.index,
+                        layout.size,
+                        layout.r#type,
+                        self.stride as GLsizei,
+                        layout.offset,
+                    )
+                } else {
+                    VertexAttribute::new(
+                        layout.index,
+                        layout.size,
+                        layout.r#type,
+                        layout.normalized,
+                        self.stride as GLsizei,
+                        layout.offset as *const c_void,
+                    )
+                };
+                vertex_attribute.enable();
+                vertex_attribute
+            })
+            .collect()
+    }
+}
+
+impl VertexStreams {
+    /// Creates streams holding only positions.
+    pub fn new(positions: Vec<[f32; 3]>) -> Self {
+        Self {
+            positions,
+            ..Self::default()
+        }
+    }
+
+    /// Copies the positions, normals, UVs and colors of standard vertices.
+    pub fn from_standard_vertices(vertices: &[StandardVertex]) -> Self {
+        Self {
+            positions: vertices.iter().map(|vertex| vertex.position).collect(),
+            normals: vertices.iter().map(|vertex| vertex.normal).collect(),
+            uvs: vertices.iter().map(|vertex| vertex.uv).collect(),
+            colors: vertices.iter().map(|vertex| vertex.color).collect(),
+            ..Self::default()
+        }
+    }
+
+    /// Returns the streams with the given normals.
+    pub fn with_normals(mut self, normals: Vec<[f32; 3]>) -> Self {
+        self.normals = normals;
+        self
+    }
+
+    /// Returns the streams with the given tangents.
+    pub fn with_tangents(mut self, tangents: Vec<[f32; 4]>) -> Self {
+        self.tangents = tangents;
+        self
+    }
+
+    /// Returns the streams with the given texture coordinates.
+    pub fn with_uvs(mut self, uvs: Vec<[f32; 2]>) -> Self {
+        self.uvs = uvs;
+        self
+    }
+
+    /// Returns the streams with the given vertex colors.
+    pub fn with_colors(mut self, colors: Vec<[u8; 4]>) -> Self {
+        self.colors = colors;
+        self
+    }
+
+    /// Returns the streams with the given bone indices and weights for skinning.
+    pub fn with_skin(mut self, joints: Vec<[u16; 4]>, weights: Vec<[f32; 4]>) -> Self {
+        self.joints = joints;
+        self.weights = weights;
+        self
+    }
+
+    /// Returns the number of vertices.
+    pub fn vertex_count(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Returns the size of one encoded vertex in bytes.
+    pub fn vertex_size(&self, compression: VertexCompression) -> usize {
+        self.layout(compression).1
+    }
+
+    /// Computes the attributes and the stride of an encoded vertex.
+    fn layout(&self, compression: VertexCompression) -> (Vec<EncodedAttribute>, usize) {
+        let mut attributes = Vec::new();
+        let mut offset = 0;
+        let mut add = |index, size, r#type, normalized, integer, bytes| {
+            attributes.push(EncodedAttribute {
+                layout: VertexAttributeLayout {
+                    index,
+                    size,
+                    r#type,
+                    normalized: if normalized { gl::TRUE } else { gl::FALSE },
+                    offset,
+                },
+                integer,
+            });
+            offset += bytes;
+        };
+
+        if compression.half_positions {
+            add(POSITION_ATTRIBUTE, 3, gl::HALF_FLOAT, false, false, 8);
+        } else {
+            add(POSITION_ATTRIBUTE, 3, gl::FLOAT, false, false, 12);
+        }
+        if !self.normals.is_empty() {
+            if compression.packed_normals {
+                add(NORMAL_ATTRIBUTE, 4, gl::INT_2_10_10_10_REV, true, false, 4);
+            } else {
+                add(NORMAL_ATTRIBUTE, 3, gl::FLOAT, false, false, 12);
+            }
+        }
+        if !self.tangents.is_empty() {
+            if compression.packed_normals {
+                add(TANGENT_ATTRIBUTE, 4, gl::INT_2_10_10_10_REV, true, false, 4);
+            } else {
+                add(TANGENT_ATTRIBUTE, 4, gl::FLOAT, false, false, 16);
+            }
+        }
+        if !self.uvs.is_empty() {
+            if compression.half_uvs {
+                add(UV_ATTRIBUTE, 2, gl::HALF_FLOAT, false, false, 4);
+            } else {
+                add(UV_ATTRIBUTE, 2, gl::FLOAT, false, false, 8);
+            }
+        }
+        if !self.colors.is_empty() {
+            add(COLOR_ATTRIBUTE, 4, gl::UNSIGNED_BYTE, true, false, 4);
+        }
+        if !self.joints.is_empty() {
+            add(JOINTS_ATTRIBUTE, 4, gl::UNSIGNED_SHORT, false, true, 8);
+        }
+        if !self.weights.is_empty() {
+            add(WEIGHTS_ATTRIBUTE, 4, gl::FLOAT, false, false, 16);
+        }
+        (attributes, offset)
+    }
+
+    /// Encodes the streams into one interleaved buffer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::VertexDataError` if a non-empty stream does not have one element
+    /// per position.
+    pub fn encode(&self, compression: VertexCompression) -> Result<EncodedVertices, Errors> {
+        let count = self.vertex_count();
+        let lengths = [
+            ("normals", self.normals.len()),
+            ("tangents", self.tangents.len()),
+            ("uvs", self.uvs.len()),
+            ("colors", self.colors.len()),
+            ("joints", self.joints.len()),
+            ("weights", self.weights.len()),
+        ];
+        if let Some((name, length)) = lengths
+            .iter()
+            .find(|(_, length)| *length != 0 && *length != count)
+        {
+            return Err(Errors::VertexDataError(format!(
+                "{} {} for {} vertices",
+                length, name, count
+            )));
+        }
+
+        let (attributes, stride) = self.layout(compression);
+        let mut data = Vec::with_capacity(stride * count);
+        for vertex in 0..count {
+            let position = self.positions[vertex];
+            if compression.half_positions {
+                for component in position {
+                    data.extend_from_slice(&f32_to_f16(component).to_ne_bytes());
+                }
+                data.extend_from_slice(&[0; 2]);
+            } else {
+                extend_f32(&mut data, &position);
+            }
+            if let Some(&[x, y, z]) = self.normals.get(vertex) {
+                if compression.packed_normals {
+                    data.extend_from_slice(&pack_snorm_2_10_10_10([x, y, z, 0.0]).to_ne_bytes());
+                } else {
+                    extend_f32(&mut data, &[x, y, z]);
+                }
+            }
+            if let Some(tangent) = self.tangents.get(vertex) {
+                if compression.packed_normals {
+                    data.extend_from_slice(&pack_snorm_2_10_10_10(*tangent).to_ne_bytes());
+                } else {
+                    extend_f32(&mut data, tangent);
+                }
+            }
+            if let Some(uv) = self.uvs.get(vertex) {
+                if compression.half_uvs {
+                    for component in uv {
+                        data.extend_from_slice(&f32_to_f16(*component).to_ne_bytes());
+                    }
+                } else {
+                    extend_f32(&mut data, uv);
+                }
+            }
+            if let Some(color) = self.colors.get(vertex) {
+                data.extend_from_slice(color);
+            }
+            if let Some(joints) = self.joints.get(vertex) {
+                for joint in joints {
+                    data.extend_from_slice(&joint.to_ne_bytes());
+                }
+            }
+            if let Some(weights) = self.weights.get(vertex) {
+                extend_f32(&mut data, weights);
+            }
+        }
+        Ok(EncodedVertices {
+            data,
+            stride,
+            attributes,
+        })
+    }
+}
+
+/// Appends floats to a byte buffer in native byte order.
+fn extend_f32(data: &mut Vec<u8>, values: &[f32]) {
+    for value in values {
+        data.extend_from_slice(&value.to_ne_bytes());
+    }
+}
+
+/// Converts a float to the bits of the nearest half float.
+///
+/// Values beyond the half float range become infinity, and values too small for a half
+/// float subnormal become zero.
+pub fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+    if exponent == 0xff {
+        // Infinity stays infinity, NaN stays NaN
+        let nan = if mantissa != 0 { 0x200 } else { 0 };
+        return sign | 0x7c00 | nan;
+    }
+    let half_exponent = exponent - 127 + 15;
+    if half_exponent >= 0x1f {
+        return sign | 0x7c00;
+    }
+    if half_exponent <= 0 {
+        if half_exponent < -10 {
+            return sign;
+        }
+        // Subnormal half float, including the implicit leading one
+        let mantissa = mantissa | 0x80_0000;
+        let shift = (14 - half_exponent) as u32;
+        let round = (mantissa >> (shift - 1)) & 1;
+        return sign | ((mantissa >> shift) + round) as u16;
+    }
+    let half = ((half_exponent as u32) << 10) | (mantissa >> 13);
+    let round = (mantissa >> 12) & 1;
+    // A carry out of the mantissa correctly rounds up to the next exponent
+    sign | (half + round) as u16
+}
+
+/// Packs a vector with components in `-1.0..=1.0` into a signed normalized 10-10-10-2
+/// integer, as read by `gl::INT_2_10_10_10_REV` attributes.
+///
+/// `x`, `y` and `z` keep 10 bits each; `w` only holds `-1`, `0` or `1`.
+pub fn pack_snorm_2_10_10_10(vector: [f32; 4]) -> u32 {
+    let component = |value: f32, bits: u32| {
+        let max = ((1 << (bits - 1)) - 1) as f32;
+        let value = (value.clamp(-1.0, 1.0) * max).round() as i32;
+        (value as u32) & ((1 << bits) - 1)
+    };
+    component(vector[0], 10)
+        | (component(vector[1], 10) << 10)
+        | (component(vector[2], 10) << 20)
+        | (component(vector[3], 2) << 30)
+}
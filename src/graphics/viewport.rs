@@ -0,0 +1,226 @@
+//! # Viewport Module
+//!
+//! This module provides a `Viewport` that keeps a fixed virtual resolution inside a window of
+//! any size, adding letterbox or pillarbox bars when the aspect ratios differ.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::graphics::viewport::{ScalingMode, Viewport};
+//!
+//! // A 320x180 pixel-art canvas scaled by whole multiples only
+//! let mut viewport = Viewport::new(320, 180);
+//! viewport.set_scaling_mode(ScalingMode::IntegerFit);
+//!
+//! // Call on every framebuffer resize
+//! viewport.resize(1920, 1200);
+//! viewport.apply();
+//!
+//! // Translate the cursor into canvas coordinates
+//! if let Some((x, y)) = viewport.window_to_virtual(960.0, 600.0) {
+//!     println!("Cursor at ({}, {}) in virtual space", x, y);
+//! }
+//! ```
+
+use crate::graphics::renderer::Rect;
+
+/// How the virtual resolution is scaled to fit the window.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ScalingMode {
+    /// Scale by the largest factor that fits, keeping the aspect ratio and adding bars.
+    #[default]
+    Fit,
+    /// Like `Fit`, but only scale by whole multiples. Useful for pixel art.
+    IntegerFit,
+    /// Fill the whole window, distorting the aspect ratio if needed.
+    Stretch,
+}
+
+/// Maps a fixed virtual resolution onto a window of any size.
+///
+/// All window-side coordinates are in framebuffer pixels with the origin in the top-left corner.
+#[derive(Debug, Clone)]
+pub struct Viewport {
+    virtual_width: u32,
+    virtual_height: u32,
+    window_width: i32,
+    window_height: i32,
+    scaling_mode: ScalingMode,
+    rect: Rect,
+}
+
+impl Viewport {
+    /// Creates a new viewport with the given virtual resolution.
+    ///
+    /// The window size starts out equal to the virtual resolution; call `resize` with the
+    /// actual framebuffer size before using it.
+    ///
+    /// # Arguments
+    ///
+    /// * `virtual_width` - The width of the virtual canvas.
+    /// * `virtual_height` - The height of the virtual canvas.
+    pub fn new(virtual_width: u32, virtual_height: u32) -> Self {
+        let mut viewport = Self {
+            virtual_width,
+            virtual_height,
+            window_width: virtual_width as i32,
+            window_height: virtual_height as i32,
+            scaling_mode: ScalingMode::Fit,
+            rect: Rect::default(),
+        };
+        viewport.update_rect();
+        viewport
+    }
+
+    /// Sets how the virtual resolution is scaled to fit the window.
+    pub fn set_scaling_mode(&mut self, scaling_mode: ScalingMode) {
+        self.scaling_mode = scaling_mode;
+        self.update_rect();
+    }
+
+    /// Returns how the virtual resolution is scaled to fit the window.
+    pub fn scaling_mode(&self) -> ScalingMode {
+        self.scaling_mode
+    }
+
+    /// Changes the virtual resolution.
+    pub fn set_virtual_size(&mut self, width: u32, height: u32) {
+        self.virtual_width = width;
+        self.virtual_height = height;
+        self.update_rect();
+    }
+
+    /// Returns the virtual resolution as `(width, height)`.
+    pub fn virtual_size(&self) -> (u32, u32) {
+        (self.virtual_width, self.virtual_height)
+    }
+
+    /// Recomputes the viewport for a new window framebuffer size.
+    ///
+    /// # Arguments
+    ///
+    /// * `window_width` - The width of the framebuffer in pixels.
+    /// * `window_height` - The height of the framebuffer in pixels.
+    pub fn resize(&mut self, window_width: i32, window_height: i32) {
+        self.window_width = window_width;
+        self.window_height = window_height;
+        self.update_rect();
+    }
+
+    /// Returns the area of the window covered by the virtual canvas.
+    pub fn rect(&self) -> Rect {
+        self.rect
+    }
+
+    /// Returns the scale factors from virtual pixels to window pixels as `(x, y)`.
+    ///
+    /// Both factors are equal unless the scaling mode is `ScalingMode::Stretch`.
+    pub fn scale(&self) -> (f32, f32) {
+        (
+            self.rect.width as f32 / self.virtual_width.max(1) as f32,
+            self.rect.height as f32 / self.virtual_height.max(1) as f32,
+        )
+    }
+
+    /// Returns the areas of the window not covered by the virtual canvas.
+    ///
+    /// These are the letterbox bars (top and bottom) and pillarbox bars (left and right).
+    /// Empty bars are omitted.
+    pub fn bars(&self) -> Vec<Rect> {
+        let rect = self.rect;
+        let right = rect.x + rect.width;
+        let bottom = rect.y + rect.height;
+        [
+            Rect::new(0, 0, self.window_width, rect.y),
+            Rect::new(0, bottom, self.window_width, self.window_height - bottom),
+            Rect::new(0, rect.y, rect.x, rect.height),
+            Rect::new(right, rect.y, self.window_width - right, rect.height),
+        ]
+        .into_iter()
+        .filter(|bar| bar.width > 0 && bar.height > 0)
+        .collect()
+    }
+
+    /// Make the virtual canvas the active OpenGL viewport.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glViewport`, converting the canvas area to OpenGL's
+    /// bottom-left origin.
+    pub fn apply(&self) {
+        unsafe {
+            gl::Viewport(
+                self.rect.x,
+                self.window_height - self.rect.y - self.rect.height,
+                self.rect.width,
+                self.rect.height,
+            );
+        }
+    }
+
+    /// Translates a window position into virtual coordinates.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - The x coordinate in framebuffer pixels.
+    /// * `y` - The y coordinate in framebuffer pixels, measured from the top.
+    ///
+    /// # Returns
+    ///
+    /// The position in virtual pixels, or `None` if it lies in one of the bars.
+    pub fn window_to_virtual(&self, x: f64, y: f64) -> Option<(f32, f32)> {
+        if self.rect.width == 0 || self.rect.height == 0 {
+            return None;
+        }
+        let (scale_x, scale_y) = self.scale();
+        let vx = (x as f32 - self.rect.x as f32) / scale_x;
+        let vy = (y as f32 - self.rect.y as f32) / scale_y;
+        if vx < 0.0
+            || vy < 0.0
+            || vx >= self.virtual_width as f32
+            || vy >= self.virtual_height as f32
+        {
+            return None;
+        }
+        Some((vx, vy))
+    }
+
+    /// Translates a virtual position into window coordinates in framebuffer pixels.
+    pub fn virtual_to_window(&self, x: f32, y: f32) -> (f32, f32) {
+        let (scale_x, scale_y) = self.scale();
+        (
+            self.rect.x as f32 + x * scale_x,
+            self.rect.y as f32 + y * scale_y,
+        )
+    }
+
+    /// Recomputes the canvas rectangle from the window size, virtual size and scaling mode.
+    fn update_rect(&mut self) {
+        let window_width = self.window_width.max(0);
+        let window_height = self.window_height.max(0);
+        let virtual_width = self.virtual_width.max(1) as f32;
+        let virtual_height = self.virtual_height.max(1) as f32;
+
+        let (width, height) = match self.scaling_mode {
+            ScalingMode::Stretch => (window_width, window_height),
+            ScalingMode::Fit | ScalingMode::IntegerFit => {
+                let mut scale = (window_width as f32 / virtual_width)
+                    .min(window_height as f32 / virtual_height);
+                if self.scaling_mode == ScalingMode::IntegerFit && scale >= 1.0 {
+                    scale = scale.floor();
+                }
+                (
+                    (virtual_width * scale).round() as i32,
+                    (virtual_height * scale).round() as i32,
+                )
+            }
+        };
+
+        self.rect = Rect::new(
+            (window_width - width) / 2,
+            (window_height - height) / 2,
+            width,
+            height,
+        );
+    }
+}
@@ -0,0 +1,514 @@
+//! # Web Events Module
+//!
+//! The window events of the browser backend. They mirror the `glfw` types the native
+//! backend reports, so `input` handles events from both backends the same way.
+//!
+//! Keys are named after their position on a US keyboard layout, as in GLFW, and are
+//! derived from `KeyboardEvent.code`. Browsers do not expose platform scancodes, so the
+//! scancode of a key is the number of the key at its physical position.
+
+use web_sys::{KeyboardEvent, MouseEvent, WheelEvent};
+
+/// A platform scancode. In the browser, this is the `Key` at the physical key position.
+pub type Scancode = i32;
+
+macro_rules! keys {
+    ($($name:ident = $value:literal,)*) => {
+        /// A keyboard key, with the same values as GLFW's key tokens.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        #[repr(i32)]
+        pub enum Key {
+            $($name = $value,)*
+        }
+
+        impl Key {
+            /// All keys, in order of their values.
+            pub const ALL: &'static [Key] = &[$(Key::$name,)*];
+        }
+    };
+}
+
+keys! {
+    Space = 32,
+    Apostrophe = 39,
+    Comma = 44,
+    Minus = 45,
+    Period = 46,
+    Slash = 47,
+    Num0 = 48,
+    Num1 = 49,
+    Num2 = 50,
+    Num3 = 51,
+    Num4 = 52,
+    Num5 = 53,
+    Num6 = 54,
+    Num7 = 55,
+    Num8 = 56,
+    Num9 = 57,
+    Semicolon = 59,
+    Equal = 61,
+    A = 65,
+    B = 66,
+    C = 67,
+    D = 68,
+    E = 69,
+    F = 70,
+    G = 71,
+    H = 72,
+    I = 73,
+    J = 74,
+    K = 75,
+    L = 76,
+    M = 77,
+    N = 78,
+    O = 79,
+    P = 80,
+    Q = 81,
+    R = 82,
+    S = 83,
+    T = 84,
+    U = 85,
+    V = 86,
+    W = 87,
+    X = 88,
+    Y = 89,
+    Z = 90,
+    LeftBracket = 91,
+    Backslash = 92,
+    RightBracket = 93,
+    GraveAccent = 96,
+    World1 = 161,
+    World2 = 162,
+    Escape = 256,
+    Enter = 257,
+    Tab = 258,
+    Backspace = 259,
+    Insert = 260,
+    Delete = 261,
+    Right = 262,
+    Left = 263,
+    Down = 264,
+    Up = 265,
+    PageUp = 266,
+    PageDown = 267,
+    Home = 268,
+    End = 269,
+    CapsLock = 280,
+    ScrollLock = 281,
+    NumLock = 282,
+    PrintScreen = 283,
+    Pause = 284,
+    F1 = 290,
+    F2 = 291,
+    F3 = 292,
+    F4 = 293,
+    F5 = 294,
+    F6 = 295,
+    F7 = 296,
+    F8 = 297,
+    F9 = 298,
+    F10 = 299,
+    F11 = 300,
+    F12 = 301,
+    F13 = 302,
+    F14 = 303,
+    F15 = 304,
+    F16 = 305,
+    F17 = 306,
+    F18 = 307,
+    F19 = 308,
+    F20 = 309,
+    F21 = 310,
+    F22 = 311,
+    F23 = 312,
+    F24 = 313,
+    F25 = 314,
+    Kp0 = 320,
+    Kp1 = 321,
+    Kp2 = 322,
+    Kp3 = 323,
+    Kp4 = 324,
+    Kp5 = 325,
+    Kp6 = 326,
+    Kp7 = 327,
+    Kp8 = 328,
+    Kp9 = 329,
+    KpDecimal = 330,
+    KpDivide = 331,
+    KpMultiply = 332,
+    KpSubtract = 333,
+    KpAdd = 334,
+    KpEnter = 335,
+    KpEqual = 336,
+    LeftShift = 340,
+    LeftControl = 341,
+    LeftAlt = 342,
+    LeftSuper = 343,
+    RightShift = 344,
+    RightControl = 345,
+    RightAlt = 346,
+    RightSuper = 347,
+    Menu = 348,
+}
+
+impl Key {
+    /// Returns the key with the given scancode, see `Scancode`.
+    pub fn from_scancode(scancode: Scancode) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|&key| key as Scancode == scancode)
+    }
+
+    /// Returns the key at the position a `KeyboardEvent.code` names, or `None` for keys
+    /// GLFW has no token for.
+    pub fn from_code(code: &str) -> Option<Self> {
+        const LETTERS: [Key; 26] = [
+            Key::A,
+            Key::B,
+            Key::C,
+            Key::D,
+            Key::E,
+            Key::F,
+            Key::G,
+            Key::H,
+            Key::I,
+            Key::J,
+            Key::K,
+            Key::L,
+            Key::M,
+            Key::N,
+            Key::O,
+            Key::P,
+            Key::Q,
+            Key::R,
+            Key::S,
+            Key::T,
+            Key::U,
+            Key::V,
+            Key::W,
+            Key::X,
+            Key::Y,
+            Key::Z,
+        ];
+        const DIGITS: [Key; 10] = [
+            Key::Num0,
+            Key::Num1,
+            Key::Num2,
+            Key::Num3,
+            Key::Num4,
+            Key::Num5,
+            Key::Num6,
+            Key::Num7,
+            Key::Num8,
+            Key::Num9,
+        ];
+        const KEYPAD: [Key; 10] = [
+            Key::Kp0,
+            Key::Kp1,
+            Key::Kp2,
+            Key::Kp3,
+            Key::Kp4,
+            Key::Kp5,
+            Key::Kp6,
+            Key::Kp7,
+            Key::Kp8,
+            Key::Kp9,
+        ];
+        const FUNCTION: [Key; 24] = [
+            Key::F1,
+            Key::F2,
+            Key::F3,
+            Key::F4,
+            Key::F5,
+            Key::F6,
+            Key::F7,
+            Key::F8,
+            Key::F9,
+            Key::F10,
+            Key::F11,
+            Key::F12,
+            Key::F13,
+            Key::F14,
+            Key::F15,
+            Key::F16,
+            Key::F17,
+            Key::F18,
+            Key::F19,
+            Key::F20,
+            Key::F21,
+            Key::F22,
+            Key::F23,
+            Key::F24,
+        ];
+        let single = |rest: &str| -> Option<u8> {
+            match rest.as_bytes() {
+                [byte] => Some(*byte),
+                _ => None,
+            }
+        };
+        if let Some(letter) = code.strip_prefix("Key").and_then(single) {
+            return letter
+                .checked_sub(b'A')
+                .and_then(|index| LETTERS.get(index as usize).copied());
+        }
+        if let Some(digit) = code.strip_prefix("Digit").and_then(single) {
+            return digit
+                .checked_sub(b'0')
+                .and_then(|index| DIGITS.get(index as usize).copied());
+        }
+        if let Some(digit) = code.strip_prefix("Numpad").and_then(single) {
+            return digit
+                .checked_sub(b'0')
+                .and_then(|index| KEYPAD.get(index as usize).copied());
+        }
+        if let Some(number) = code
+            .strip_prefix('F')
+            .and_then(|rest| rest.parse::<usize>().ok())
+        {
+            return number
+                .checked_sub(1)
+                .and_then(|index| FUNCTION.get(index).copied());
+        }
+        Some(match code {
+            "Space" => Key::Space,
+            "Quote" => Key::Apostrophe,
+            "Comma" => Key::Comma,
+            "Minus" => Key::Minus,
+            "Period" => Key::Period,
+            "Slash" => Key::Slash,
+            "Semicolon" => Key::Semicolon,
+            "Equal" => Key::Equal,
+            "BracketLeft" => Key::LeftBracket,
+            "Backslash" => Key::Backslash,
+            "BracketRight" => Key::RightBracket,
+            "Backquote" => Key::GraveAccent,
+            "IntlBackslash" => Key::World1,
+            "IntlRo" | "IntlYen" => Key::World2,
+            "Escape" => Key::Escape,
+            "Enter" => Key::Enter,
+            "Tab" => Key::Tab,
+            "Backspace" => Key::Backspace,
+            "Insert" => Key::Insert,
+            "Delete" => Key::Delete,
+            "ArrowRight" => Key::Right,
+            "ArrowLeft" => Key::Left,
+            "ArrowDown" => Key::Down,
+            "ArrowUp" => Key::Up,
+            "PageUp" => Key::PageUp,
+            "PageDown" => Key::PageDown,
+            "Home" => Key::Home,
+            "End" => Key::End,
+            "CapsLock" => Key::CapsLock,
+            "ScrollLock" => Key::ScrollLock,
+            "NumLock" => Key::NumLock,
+            "PrintScreen" => Key::PrintScreen,
+            "Pause" => Key::Pause,
+            "NumpadDecimal" => Key::KpDecimal,
+            "NumpadDivide" => Key::KpDivide,
+            "NumpadMultiply" => Key::KpMultiply,
+            "NumpadSubtract" => Key::KpSubtract,
+            "NumpadAdd" => Key::KpAdd,
+            "NumpadEnter" => Key::KpEnter,
+            "NumpadEqual" => Key::KpEqual,
+            "ShiftLeft" => Key::LeftShift,
+            "ControlLeft" => Key::LeftControl,
+            "AltLeft" => Key::LeftAlt,
+            "MetaLeft" | "OSLeft" => Key::LeftSuper,
+            "ShiftRight" => Key::RightShift,
+            "ControlRight" => Key::RightControl,
+            "AltRight" => Key::RightAlt,
+            "MetaRight" | "OSRight" => Key::RightSuper,
+            "ContextMenu" => Key::Menu,
+            _ => return None,
+        })
+    }
+}
+
+/// A mouse button, with the same values as GLFW's button tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[repr(i32)]
+pub enum MouseButton {
+    Button1 = 0,
+    Button2 = 1,
+    Button3 = 2,
+    Button4 = 3,
+    Button5 = 4,
+    Button6 = 5,
+    Button7 = 6,
+    Button8 = 7,
+}
+
+#[allow(non_upper_case_globals)]
+impl MouseButton {
+    /// The left mouse button.
+    pub const Left: MouseButton = MouseButton::Button1;
+    /// The right mouse button.
+    pub const Right: MouseButton = MouseButton::Button2;
+    /// The middle mouse button.
+    pub const Middle: MouseButton = MouseButton::Button3;
+
+    /// Returns the button a `MouseEvent.button` number refers to.
+    pub fn from_dom(button: i16) -> Option<Self> {
+        Some(match button {
+            0 => MouseButton::Left,
+            1 => MouseButton::Middle,
+            2 => MouseButton::Right,
+            3 => MouseButton::Button4,
+            4 => MouseButton::Button5,
+            _ => return None,
+        })
+    }
+}
+
+/// The modifier keys held during an event, a set of flags like GLFW's `Modifiers`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Modifiers {
+    bits: u8,
+}
+
+#[allow(non_upper_case_globals)]
+impl Modifiers {
+    /// Either shift key.
+    pub const Shift: Modifiers = Modifiers { bits: 0x01 };
+    /// Either control key.
+    pub const Control: Modifiers = Modifiers { bits: 0x02 };
+    /// Either alt key.
+    pub const Alt: Modifiers = Modifiers { bits: 0x04 };
+    /// Either super key, the Windows or Command key.
+    pub const Super: Modifiers = Modifiers { bits: 0x08 };
+
+    /// Returns an empty set of modifiers.
+    pub const fn empty() -> Self {
+        Modifiers { bits: 0 }
+    }
+
+    /// Returns `true` if no modifier is set.
+    pub const fn is_empty(&self) -> bool {
+        self.bits == 0
+    }
+
+    /// Returns `true` if all modifiers of `other` are set.
+    pub const fn contains(&self, other: Modifiers) -> bool {
+        self.bits & other.bits == other.bits
+    }
+
+    /// Adds the modifiers of `other`.
+    pub fn insert(&mut self, other: Modifiers) {
+        self.bits |= other.bits;
+    }
+
+    /// Removes the modifiers of `other`.
+    pub fn remove(&mut self, other: Modifiers) {
+        self.bits &= !other.bits;
+    }
+
+    fn from_flags(shift: bool, control: bool, alt: bool, meta: bool) -> Self {
+        let mut modifiers = Modifiers::empty();
+        for (held, modifier) in [
+            (shift, Modifiers::Shift),
+            (control, Modifiers::Control),
+            (alt, Modifiers::Alt),
+            (meta, Modifiers::Super),
+        ] {
+            if held {
+                modifiers.insert(modifier);
+            }
+        }
+        modifiers
+    }
+
+    /// Returns the modifiers held during a keyboard event.
+    pub fn from_keyboard_event(event: &KeyboardEvent) -> Self {
+        Self::from_flags(
+            event.shift_key(),
+            event.ctrl_key(),
+            event.alt_key(),
+            event.meta_key(),
+        )
+    }
+
+    /// Returns the modifiers held during a mouse event.
+    pub fn from_mouse_event(event: &MouseEvent) -> Self {
+        Self::from_flags(
+            event.shift_key(),
+            event.ctrl_key(),
+            event.alt_key(),
+            event.meta_key(),
+        )
+    }
+}
+
+/// Whether a key or button was pressed, released or repeated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    /// The key or button was released.
+    Release,
+    /// The key or button was pressed.
+    Press,
+    /// The key was held down until it repeated.
+    Repeat,
+}
+
+/// An event reported by the browser window, with the variants of `glfw::WindowEvent` the
+/// crate handles.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WindowEvent {
+    /// The canvas was resized to the given size in pixels.
+    FramebufferSize(i32, i32),
+    /// A key was pressed, released or repeated.
+    Key(Key, Scancode, Action, Modifiers),
+    /// A character was typed.
+    Char(char),
+    /// A mouse button was pressed or released.
+    MouseButton(MouseButton, Action, Modifiers),
+    /// The mouse moved to the given position in CSS pixels from the top left corner.
+    CursorPos(f64, f64),
+    /// The mouse wheel or touchpad scrolled by the given amount of lines.
+    Scroll(f64, f64),
+}
+
+impl WindowEvent {
+    /// Converts a `keydown` or `keyup` event, returning the key event and, for key presses
+    /// that type a character, the `Char` event.
+    pub fn from_keyboard_event(event: &KeyboardEvent, action: Action) -> Vec<Self> {
+        let mut events = Vec::new();
+        let Some(key) = Key::from_code(&event.code()) else {
+            return events;
+        };
+        let action = if action == Action::Press && event.repeat() {
+            Action::Repeat
+        } else {
+            action
+        };
+        let modifiers = Modifiers::from_keyboard_event(event);
+        events.push(WindowEvent::Key(key, key as Scancode, action, modifiers));
+
+        // Named keys such as "Enter" have a `key` longer than one character
+        let typed = event.key();
+        let mut characters = typed.chars();
+        if let (Some(character), None) = (characters.next(), characters.next()) {
+            if action != Action::Release && !event.ctrl_key() && !event.meta_key() {
+                events.push(WindowEvent::Char(character));
+            }
+        }
+        events
+    }
+
+    /// Converts a `mousedown` or `mouseup` event.
+    pub fn from_mouse_button_event(event: &MouseEvent, action: Action) -> Option<Self> {
+        MouseButton::from_dom(event.button()).map(|button| {
+            WindowEvent::MouseButton(button, action, Modifiers::from_mouse_event(event))
+        })
+    }
+
+    /// Converts a `wheel` event. Scrolling up or to the left is positive, as in GLFW.
+    pub fn from_wheel_event(event: &WheelEvent) -> Self {
+        let lines = match event.delta_mode() {
+            WheelEvent::DOM_DELTA_PIXEL => 1.0 / 100.0,
+            WheelEvent::DOM_DELTA_LINE => 1.0 / 3.0,
+            _ => 1.0,
+        };
+        WindowEvent::Scroll(-event.delta_x() * lines, -event.delta_y() * lines)
+    }
+}
@@ -0,0 +1,16 @@
+//! # Web Module
+//!
+//! The browser backend, available with the `web` feature and built for
+//! `wasm32-unknown-unknown`. Without the `native` feature, its `window` replaces the GLFW
+//! window as `graphics::window`, so the rest of the crate and applications use the same API
+//! on both backends.
+//!
+//! ## Submodules
+//! - **webgl**: Loads the `gl` crate's functions for a WebGL2 context.
+//! - **window**: A window drawing into an HTML canvas, built without the `native` feature.
+//! - **events**: Keyboard and mouse events mirroring GLFW's.
+
+pub mod events;
+pub mod webgl;
+#[cfg(not(feature = "native"))]
+pub mod window;
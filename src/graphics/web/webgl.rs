@@ -0,0 +1,1672 @@
+//! # WebGL Module
+//!
+//! Loads the `gl` crate's function pointers with functions that forward to a WebGL2 context,
+//! so the renderer runs unchanged in the browser.
+//!
+//! WebGL identifies objects by JavaScript handles instead of integer names. The loader keeps
+//! a table per object type that maps the names handed out by `glGen*`/`glCreate*` to those
+//! handles, and a table of uniform locations per program.
+//!
+//! Desktop GLSL shaders are compiled as GLSL ES 3.00: a `#version 3xx`/`4xx` line is replaced
+//! by `#version 300 es` with default precisions, so shaders limited to the GLSL 3.30 feature
+//! set that WebGL2 shares compile in both places.
+//!
+//! Functions WebGL2 does not have, such as `glCopyImageSubData`, `glObjectLabel`,
+//! `glMemoryBarrier`, indirect draws and tessellation, are not loaded; their `is_loaded()`
+//! returns `false`, as on an OpenGL 3.3 driver. `glGetTexImage` is emulated with a
+//! framebuffer and `glReadPixels`, `glMultiDrawElements` with one draw per range.
+
+use crate::custom_errors::{Errors, GlErrorKind};
+use gl::types::*;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ffi::{c_void, CStr, CString};
+use std::ptr;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{
+    WebGl2RenderingContext as Gl, WebGlBuffer, WebGlFramebuffer, WebGlProgram, WebGlQuery,
+    WebGlRenderbuffer, WebGlShader, WebGlTexture, WebGlUniformLocation, WebGlVertexArrayObject,
+};
+
+/// The extensions enabled when the context is loaded, if the browser supports them.
+const EXTENSIONS: [&str; 4] = [
+    "EXT_color_buffer_float",
+    "EXT_color_buffer_half_float",
+    "EXT_disjoint_timer_query_webgl2",
+    "OES_texture_float_linear",
+];
+
+thread_local! {
+    static STATE: RefCell<Option<WebGlState>> = const { RefCell::new(None) };
+}
+
+/// Maps the integer names of one kind of GL object to WebGL handles.
+struct Names<T> {
+    objects: Vec<Option<T>>,
+}
+
+impl<T: AsRef<JsValue>> Names<T> {
+    fn new() -> Self {
+        Self {
+            objects: Vec::new(),
+        }
+    }
+
+    /// Stores a handle and returns its name. Names are never reused, so a stale name cannot
+    /// refer to a newer object.
+    fn insert(&mut self, object: T) -> GLuint {
+        self.objects.push(Some(object));
+        self.objects.len() as GLuint
+    }
+
+    fn get(&self, name: GLuint) -> Option<&T> {
+        let index = (name as usize).checked_sub(1)?;
+        self.objects.get(index)?.as_ref()
+    }
+
+    fn get_mut(&mut self, name: GLuint) -> Option<&mut T> {
+        let index = (name as usize).checked_sub(1)?;
+        self.objects.get_mut(index)?.as_mut()
+    }
+
+    fn remove(&mut self, name: GLuint) -> Option<T> {
+        let index = (name as usize).checked_sub(1)?;
+        self.objects.get_mut(index)?.take()
+    }
+
+    /// Returns the name of a handle returned by WebGL, or `0` for `null` and unknown handles.
+    fn name_of(&self, object: &JsValue) -> GLuint {
+        if object.is_null() || object.is_undefined() {
+            return 0;
+        }
+        self.objects
+            .iter()
+            .position(|entry| entry.as_ref().is_some_and(|entry| entry.as_ref() == object))
+            .map_or(0, |index| index as GLuint + 1)
+    }
+}
+
+/// A linked program and the uniform locations handed out for it.
+struct Program {
+    program: WebGlProgram,
+    uniforms: Vec<WebGlUniformLocation>,
+    uniform_names: HashMap<String, GLint>,
+}
+
+/// Shaders and programs share one namespace in OpenGL.
+enum ShaderObject {
+    Shader(WebGlShader),
+    Program(Box<Program>),
+}
+
+impl AsRef<JsValue> for ShaderObject {
+    fn as_ref(&self) -> &JsValue {
+        match self {
+            ShaderObject::Shader(shader) => shader.as_ref(),
+            ShaderObject::Program(program) => program.program.as_ref(),
+        }
+    }
+}
+
+/// The context and the name tables of the loaded functions.
+struct WebGlState {
+    gl: Gl,
+    buffers: Names<WebGlBuffer>,
+    textures: Names<WebGlTexture>,
+    framebuffers: Names<WebGlFramebuffer>,
+    renderbuffers: Names<WebGlRenderbuffer>,
+    vertex_arrays: Names<WebGlVertexArrayObject>,
+    queries: Names<WebGlQuery>,
+    shader_objects: Names<ShaderObject>,
+    current_program: GLuint,
+    /// The size of each level of each texture, for `glGetTexImage`.
+    texture_sizes: HashMap<(GLuint, GLint), (GLsizei, GLsizei)>,
+    unpack_alignment: usize,
+    pack_alignment: usize,
+    /// An error raised by the loader itself, reported before WebGL's own errors.
+    error: GLenum,
+    strings: HashMap<GLenum, CString>,
+    extensions: Vec<CString>,
+}
+
+impl WebGlState {
+    fn set_error(&mut self, error: GLenum) {
+        if self.error == gl::NO_ERROR {
+            self.error = error;
+        }
+    }
+
+    fn program(&self, name: GLuint) -> Option<&Program> {
+        match self.shader_objects.get(name)? {
+            ShaderObject::Program(program) => Some(program),
+            ShaderObject::Shader(_) => None,
+        }
+    }
+
+    fn shader(&self, name: GLuint) -> Option<&WebGlShader> {
+        match self.shader_objects.get(name)? {
+            ShaderObject::Shader(shader) => Some(shader),
+            ShaderObject::Program(_) => None,
+        }
+    }
+
+    /// Returns the location of the bound program with the given GL location.
+    fn uniform(&self, location: GLint) -> Option<&WebGlUniformLocation> {
+        let program = self.program(self.current_program)?;
+        program.uniforms.get(usize::try_from(location).ok()?)
+    }
+
+    /// Returns the name of the texture bound to a target of the active unit.
+    fn bound_texture(&self, target: GLenum) -> GLuint {
+        let binding = match target {
+            gl::TEXTURE_2D => gl::TEXTURE_BINDING_2D,
+            gl::TEXTURE_CUBE_MAP_POSITIVE_X..=gl::TEXTURE_CUBE_MAP_NEGATIVE_Z => {
+                gl::TEXTURE_BINDING_CUBE_MAP
+            }
+            _ => return 0,
+        };
+        self.gl
+            .get_parameter(binding)
+            .map_or(0, |texture| self.textures.name_of(&texture))
+    }
+
+    /// Returns the name of the object a binding query refers to, or `None` for other queries.
+    fn binding_name(&self, pname: GLenum, value: &JsValue) -> Option<GLuint> {
+        Some(match pname {
+            gl::ARRAY_BUFFER_BINDING
+            | gl::ELEMENT_ARRAY_BUFFER_BINDING
+            | gl::UNIFORM_BUFFER_BINDING
+            | gl::COPY_READ_BUFFER_BINDING
+            | gl::COPY_WRITE_BUFFER_BINDING
+            | gl::PIXEL_PACK_BUFFER_BINDING
+            | gl::PIXEL_UNPACK_BUFFER_BINDING => self.buffers.name_of(value),
+            gl::DRAW_FRAMEBUFFER_BINDING | gl::READ_FRAMEBUFFER_BINDING => {
+                self.framebuffers.name_of(value)
+            }
+            gl::RENDERBUFFER_BINDING => self.renderbuffers.name_of(value),
+            gl::TEXTURE_BINDING_2D
+            | gl::TEXTURE_BINDING_3D
+            | gl::TEXTURE_BINDING_2D_ARRAY
+            | gl::TEXTURE_BINDING_CUBE_MAP => self.textures.name_of(value),
+            gl::VERTEX_ARRAY_BINDING => self.vertex_arrays.name_of(value),
+            gl::CURRENT_PROGRAM => self.shader_objects.name_of(value),
+            _ => return None,
+        })
+    }
+}
+
+/// Runs a function with the loaded context. Calls made before `load` are ignored.
+fn with<R: Default>(f: impl FnOnce(&mut WebGlState) -> R) -> R {
+    STATE.with(|state| state.borrow_mut().as_mut().map(f).unwrap_or_default())
+}
+
+/// Loads the `gl` crate's functions for a WebGL2 context. Called by `Window::init_gl`.
+///
+/// # Errors
+///
+/// Returns an `Errors::OpenGlError` if the context has already been lost.
+pub fn load(context: Gl) -> Result<(), Errors> {
+    if context.is_context_lost() {
+        return Err(Errors::OpenGlError(
+            "The WebGL2 context was lost".to_string(),
+            GlErrorKind::NoContext,
+        ));
+    }
+    for extension in EXTENSIONS {
+        // Extensions are only enabled once requested
+        let _ = context.get_extension(extension);
+    }
+    let extensions = context
+        .get_supported_extensions()
+        .map(|names| {
+            names
+                .iter()
+                .filter_map(|name| name.as_string())
+                .filter_map(|name| CString::new(name).ok())
+                .collect()
+        })
+        .unwrap_or_default();
+    STATE.with(|state| {
+        *state.borrow_mut() = Some(WebGlState {
+            gl: context,
+            buffers: Names::new(),
+            textures: Names::new(),
+            framebuffers: Names::new(),
+            renderbuffers: Names::new(),
+            vertex_arrays: Names::new(),
+            queries: Names::new(),
+            shader_objects: Names::new(),
+            current_program: 0,
+            texture_sizes: HashMap::new(),
+            unpack_alignment: 4,
+            pack_alignment: 4,
+            error: gl::NO_ERROR,
+            strings: HashMap::new(),
+            extensions,
+        });
+    });
+    gl::load_with(proc_address);
+    Ok(())
+}
+
+/// Returns the WebGL2 context loaded by `load`, e.g. to call WebGL-only functions.
+pub fn context() -> Option<Gl> {
+    STATE.with(|state| state.borrow().as_ref().map(|state| state.gl.clone()))
+}
+
+/// Rewrites a desktop GLSL shader as GLSL ES 3.00.
+fn to_glsl_es(source: &str) -> String {
+    let trimmed = source.trim_start();
+    let Some(rest) = trimmed.strip_prefix("#version") else {
+        return source.to_string();
+    };
+    let (version, body) = rest.split_once('\n').unwrap_or((rest, ""));
+    if version.contains("es") {
+        return source.to_string();
+    }
+    format!(
+        "#version 300 es\n\
+         precision highp float;\n\
+         precision highp int;\n\
+         precision highp sampler3D;\n\
+         precision highp sampler2DArray;\n\
+         precision highp sampler2DShadow;\n\
+         precision highp samplerCubeShadow;\n\
+         {}",
+        body
+    )
+}
+
+/// Returns the number of bytes per pixel of a format and type, or `None` if either is not
+/// supported by WebGL2.
+fn pixel_size(format: GLenum, kind: GLenum) -> Option<usize> {
+    let components = match format {
+        gl::RED | gl::RED_INTEGER | gl::ALPHA | gl::DEPTH_COMPONENT => 1,
+        gl::RG | gl::RG_INTEGER | gl::DEPTH_STENCIL => 2,
+        gl::RGB | gl::RGB_INTEGER => 3,
+        gl::RGBA | gl::RGBA_INTEGER => 4,
+        _ => return None,
+    };
+    Some(match kind {
+        gl::UNSIGNED_BYTE | gl::BYTE => components,
+        gl::UNSIGNED_SHORT | gl::SHORT | gl::HALF_FLOAT => components * 2,
+        gl::UNSIGNED_INT | gl::INT | gl::FLOAT => components * 4,
+        gl::UNSIGNED_SHORT_5_6_5 | gl::UNSIGNED_SHORT_4_4_4_4 | gl::UNSIGNED_SHORT_5_5_5_1 => 2,
+        gl::UNSIGNED_INT_2_10_10_10_REV
+        | gl::UNSIGNED_INT_10F_11F_11F_REV
+        | gl::UNSIGNED_INT_5_9_9_9_REV
+        | gl::UNSIGNED_INT_24_8 => 4,
+        gl::FLOAT_32_UNSIGNED_INT_24_8_REV => 8,
+        _ => return None,
+    })
+}
+
+/// Returns the size of an image in client memory, with rows padded to the alignment.
+fn image_size(width: GLsizei, height: GLsizei, pixel: usize, alignment: usize) -> usize {
+    let (width, height) = (width.max(0) as usize, height.max(0) as usize);
+    if height == 0 {
+        return 0;
+    }
+    let row = width * pixel;
+    let padded = row.div_ceil(alignment) * alignment;
+    padded * (height - 1) + row
+}
+
+/// Creates a typed array over client memory, of the kind WebGL expects for a pixel or
+/// buffer type.
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes aligned for the type. The view must be used
+/// before the wasm memory can grow, i.e. before anything is allocated.
+unsafe fn array_view(kind: GLenum, data: *const c_void, len: usize) -> js_sys::Object {
+    match kind {
+        gl::FLOAT => {
+            js_sys::Float32Array::view(std::slice::from_raw_parts(data as *const f32, len / 4))
+                .into()
+        }
+        gl::INT => {
+            js_sys::Int32Array::view(std::slice::from_raw_parts(data as *const i32, len / 4)).into()
+        }
+        gl::UNSIGNED_INT
+        | gl::UNSIGNED_INT_2_10_10_10_REV
+        | gl::UNSIGNED_INT_10F_11F_11F_REV
+        | gl::UNSIGNED_INT_5_9_9_9_REV
+        | gl::UNSIGNED_INT_24_8 => {
+            js_sys::Uint32Array::view(std::slice::from_raw_parts(data as *const u32, len / 4))
+                .into()
+        }
+        gl::SHORT => {
+            js_sys::Int16Array::view(std::slice::from_raw_parts(data as *const i16, len / 2)).into()
+        }
+        gl::UNSIGNED_SHORT
+        | gl::HALF_FLOAT
+        | gl::UNSIGNED_SHORT_5_6_5
+        | gl::UNSIGNED_SHORT_4_4_4_4
+        | gl::UNSIGNED_SHORT_5_5_5_1 => {
+            js_sys::Uint16Array::view(std::slice::from_raw_parts(data as *const u16, len / 2))
+                .into()
+        }
+        gl::BYTE => {
+            js_sys::Int8Array::view(std::slice::from_raw_parts(data as *const i8, len)).into()
+        }
+        _ => js_sys::Uint8Array::view(std::slice::from_raw_parts(data as *const u8, len)).into(),
+    }
+}
+
+/// Converts a WebGL parameter, a number, boolean or array of them, to integers.
+fn integers(value: &JsValue) -> Vec<GLint> {
+    if let Some(number) = value.as_f64() {
+        vec![number as GLint]
+    } else if let Some(boolean) = value.as_bool() {
+        vec![boolean as GLint]
+    } else if let Some(array) = value.dyn_ref::<js_sys::Array>() {
+        array.iter().flat_map(|value| integers(&value)).collect()
+    } else if value.is_object() && js_sys::ArrayBuffer::is_view(value) {
+        js_sys::Array::from(value)
+            .iter()
+            .flat_map(|value| integers(&value))
+            .collect()
+    } else {
+        Vec::new()
+    }
+}
+
+/// Writes a string into a buffer of the given size, always terminating it.
+unsafe fn write_string(text: &str, size: GLsizei, length: *mut GLsizei, out: *mut GLchar) {
+    let count = text.len().min((size.max(1) - 1) as usize);
+    if !out.is_null() && size > 0 {
+        ptr::copy_nonoverlapping(text.as_ptr(), out as *mut u8, count);
+        *out.add(count) = 0;
+    }
+    if !length.is_null() {
+        *length = count as GLsizei;
+    }
+}
+
+/// Generates names with a WebGL create function.
+unsafe fn generate<T: AsRef<JsValue>>(
+    count: GLsizei,
+    out: *mut GLuint,
+    table: impl Fn(&mut WebGlState) -> &mut Names<T>,
+    create: impl Fn(&Gl) -> Option<T>,
+) {
+    with(|state| {
+        for index in 0..count.max(0) as usize {
+            let name = match create(&state.gl) {
+                Some(object) => table(state).insert(object),
+                None => 0,
+            };
+            *out.add(index) = name;
+        }
+    });
+}
+
+/// Deletes names with a WebGL delete function.
+unsafe fn delete<T: AsRef<JsValue>>(
+    count: GLsizei,
+    names: *const GLuint,
+    table: impl Fn(&mut WebGlState) -> &mut Names<T>,
+    destroy: impl Fn(&Gl, &T),
+) {
+    with(|state| {
+        for index in 0..count.max(0) as usize {
+            if let Some(object) = table(state).remove(*names.add(index)) {
+                destroy(&state.gl, &object);
+            }
+        }
+    });
+}
+
+/// Returns the shim for a GL function, or null if WebGL2 has no equivalent.
+fn proc_address(name: &str) -> *const c_void {
+    macro_rules! shims {
+        ($($gl_name:literal => $shim:ident,)*) => {
+            match name {
+                $($gl_name => $shim as *const c_void,)*
+                _ => ptr::null(),
+            }
+        };
+    }
+    shims! {
+        "glActiveTexture" => active_texture,
+        "glAttachShader" => attach_shader,
+        "glBeginQuery" => begin_query,
+        "glBindBuffer" => bind_buffer,
+        "glBindBufferBase" => bind_buffer_base,
+        "glBindFramebuffer" => bind_framebuffer,
+        "glBindRenderbuffer" => bind_renderbuffer,
+        "glBindTexture" => bind_texture,
+        "glBindVertexArray" => bind_vertex_array,
+        "glBlendEquation" => blend_equation,
+        "glBlendFunc" => blend_func,
+        "glBlitFramebuffer" => blit_framebuffer,
+        "glBufferData" => buffer_data,
+        "glBufferSubData" => buffer_sub_data,
+        "glCheckFramebufferStatus" => check_framebuffer_status,
+        "glClear" => clear,
+        "glClearBufferfv" => clear_bufferfv,
+        "glClearColor" => clear_color,
+        "glClearDepth" => clear_depth,
+        "glClearStencil" => clear_stencil,
+        "glColorMask" => color_mask,
+        "glCompileShader" => compile_shader,
+        "glCopyTexSubImage2D" => copy_tex_sub_image_2d,
+        "glCreateProgram" => create_program,
+        "glCreateShader" => create_shader,
+        "glCullFace" => cull_face,
+        "glDeleteBuffers" => delete_buffers,
+        "glDeleteFramebuffers" => delete_framebuffers,
+        "glDeleteProgram" => delete_program,
+        "glDeleteQueries" => delete_queries,
+        "glDeleteRenderbuffers" => delete_renderbuffers,
+        "glDeleteShader" => delete_shader,
+        "glDeleteTextures" => delete_textures,
+        "glDeleteVertexArrays" => delete_vertex_arrays,
+        "glDepthFunc" => depth_func,
+        "glDepthMask" => depth_mask,
+        "glDisable" => disable,
+        "glDisableVertexAttribArray" => disable_vertex_attrib_array,
+        "glDrawArrays" => draw_arrays,
+        "glDrawArraysInstanced" => draw_arrays_instanced,
+        "glDrawBuffer" => draw_buffer,
+        "glDrawBuffers" => draw_buffers,
+        "glDrawElements" => draw_elements,
+        "glDrawElementsInstanced" => draw_elements_instanced,
+        "glEnable" => enable,
+        "glEnableVertexAttribArray" => enable_vertex_attrib_array,
+        "glEndQuery" => end_query,
+        "glFramebufferRenderbuffer" => framebuffer_renderbuffer,
+        "glFramebufferTexture2D" => framebuffer_texture_2d,
+        "glFrontFace" => front_face,
+        "glGenBuffers" => gen_buffers,
+        "glGenFramebuffers" => gen_framebuffers,
+        "glGenQueries" => gen_queries,
+        "glGenRenderbuffers" => gen_renderbuffers,
+        "glGenTextures" => gen_textures,
+        "glGenVertexArrays" => gen_vertex_arrays,
+        "glGenerateMipmap" => generate_mipmap,
+        "glGetBooleanv" => get_booleanv,
+        "glGetBufferParameteriv" => get_buffer_parameteriv,
+        "glGetBufferSubData" => get_buffer_sub_data,
+        "glGetError" => get_error,
+        "glGetIntegerv" => get_integerv,
+        "glGetProgramInfoLog" => get_program_info_log,
+        "glGetProgramiv" => get_programiv,
+        "glGetQueryObjectui64v" => get_query_objectui64v,
+        "glGetQueryObjectuiv" => get_query_objectuiv,
+        "glGetShaderInfoLog" => get_shader_info_log,
+        "glGetShaderiv" => get_shaderiv,
+        "glGetString" => get_string,
+        "glGetStringi" => get_stringi,
+        "glGetTexImage" => get_tex_image,
+        "glGetUniformBlockIndex" => get_uniform_block_index,
+        "glGetUniformLocation" => get_uniform_location,
+        "glGetVertexAttribPointerv" => get_vertex_attrib_pointerv,
+        "glGetVertexAttribiv" => get_vertex_attribiv,
+        "glIsEnabled" => is_enabled,
+        "glLineWidth" => line_width,
+        "glLinkProgram" => link_program,
+        "glMultiDrawElements" => multi_draw_elements,
+        "glPixelStorei" => pixel_storei,
+        "glPointSize" => point_size,
+        "glPolygonOffset" => polygon_offset,
+        "glReadBuffer" => read_buffer,
+        "glReadPixels" => read_pixels,
+        "glRenderbufferStorage" => renderbuffer_storage,
+        "glRenderbufferStorageMultisample" => renderbuffer_storage_multisample,
+        "glScissor" => scissor,
+        "glShaderSource" => shader_source,
+        "glStencilMask" => stencil_mask,
+        "glTexImage2D" => tex_image_2d,
+        "glTexParameterf" => tex_parameterf,
+        "glTexParameterfv" => tex_parameterfv,
+        "glTexParameteri" => tex_parameteri,
+        "glTexStorage2D" => tex_storage_2d,
+        "glTexSubImage2D" => tex_sub_image_2d,
+        "glUniform1f" => uniform1f,
+        "glUniform1fv" => uniform1fv,
+        "glUniform1i" => uniform1i,
+        "glUniform1iv" => uniform1iv,
+        "glUniform1ui" => uniform1ui,
+        "glUniform2f" => uniform2f,
+        "glUniform2fv" => uniform2fv,
+        "glUniform2i" => uniform2i,
+        "glUniform3f" => uniform3f,
+        "glUniform3fv" => uniform3fv,
+        "glUniform3i" => uniform3i,
+        "glUniform4f" => uniform4f,
+        "glUniform4fv" => uniform4fv,
+        "glUniform4i" => uniform4i,
+        "glUniformBlockBinding" => uniform_block_binding,
+        "glUniformMatrix3fv" => uniform_matrix3fv,
+        "glUniformMatrix4fv" => uniform_matrix4fv,
+        "glUseProgram" => use_program,
+        "glVertexAttrib4f" => vertex_attrib4f,
+        "glVertexAttribDivisor" => vertex_attrib_divisor,
+        "glVertexAttribIPointer" => vertex_attrib_i_pointer,
+        "glVertexAttribPointer" => vertex_attrib_pointer,
+        "glViewport" => viewport,
+    }
+}
+
+// State
+
+extern "system" fn active_texture(texture: GLenum) {
+    with(|s| s.gl.active_texture(texture))
+}
+
+extern "system" fn blend_equation(mode: GLenum) {
+    with(|s| s.gl.blend_equation(mode))
+}
+
+extern "system" fn blend_func(source: GLenum, destination: GLenum) {
+    with(|s| s.gl.blend_func(source, destination))
+}
+
+extern "system" fn clear(mask: GLbitfield) {
+    with(|s| s.gl.clear(mask))
+}
+
+extern "system" fn clear_color(red: GLfloat, green: GLfloat, blue: GLfloat, alpha: GLfloat) {
+    with(|s| s.gl.clear_color(red, green, blue, alpha))
+}
+
+extern "system" fn clear_depth(depth: GLdouble) {
+    with(|s| s.gl.clear_depth(depth as f32))
+}
+
+extern "system" fn clear_stencil(stencil: GLint) {
+    with(|s| s.gl.clear_stencil(stencil))
+}
+
+unsafe extern "system" fn clear_bufferfv(
+    buffer: GLenum,
+    draw_buffer: GLint,
+    value: *const GLfloat,
+) {
+    let count = if buffer == gl::COLOR { 4 } else { 1 };
+    let values = std::slice::from_raw_parts(value, count);
+    with(|s| {
+        s.gl.clear_bufferfv_with_f32_array(buffer, draw_buffer, values)
+    })
+}
+
+extern "system" fn color_mask(red: GLboolean, green: GLboolean, blue: GLboolean, alpha: GLboolean) {
+    with(|s| s.gl.color_mask(red != 0, green != 0, blue != 0, alpha != 0))
+}
+
+extern "system" fn cull_face(mode: GLenum) {
+    with(|s| s.gl.cull_face(mode))
+}
+
+extern "system" fn depth_func(function: GLenum) {
+    with(|s| s.gl.depth_func(function))
+}
+
+extern "system" fn depth_mask(flag: GLboolean) {
+    with(|s| s.gl.depth_mask(flag != 0))
+}
+
+extern "system" fn disable(capability: GLenum) {
+    with(|s| s.gl.disable(capability))
+}
+
+extern "system" fn enable(capability: GLenum) {
+    with(|s| s.gl.enable(capability))
+}
+
+extern "system" fn is_enabled(capability: GLenum) -> GLboolean {
+    with(|s| s.gl.is_enabled(capability) as GLboolean)
+}
+
+extern "system" fn front_face(mode: GLenum) {
+    with(|s| s.gl.front_face(mode))
+}
+
+extern "system" fn line_width(width: GLfloat) {
+    with(|s| s.gl.line_width(width))
+}
+
+/// WebGL has no fixed point size; shaders set `gl_PointSize` instead.
+extern "system" fn point_size(_size: GLfloat) {}
+
+extern "system" fn polygon_offset(factor: GLfloat, units: GLfloat) {
+    with(|s| s.gl.polygon_offset(factor, units))
+}
+
+extern "system" fn scissor(x: GLint, y: GLint, width: GLsizei, height: GLsizei) {
+    with(|s| s.gl.scissor(x, y, width, height))
+}
+
+extern "system" fn stencil_mask(mask: GLuint) {
+    with(|s| s.gl.stencil_mask(mask))
+}
+
+extern "system" fn viewport(x: GLint, y: GLint, width: GLsizei, height: GLsizei) {
+    with(|s| s.gl.viewport(x, y, width, height))
+}
+
+extern "system" fn pixel_storei(pname: GLenum, param: GLint) {
+    with(|s| {
+        match pname {
+            gl::UNPACK_ALIGNMENT => s.unpack_alignment = param.max(1) as usize,
+            gl::PACK_ALIGNMENT => s.pack_alignment = param.max(1) as usize,
+            _ => {}
+        }
+        s.gl.pixel_storei(pname, param)
+    })
+}
+
+extern "system" fn get_error() -> GLenum {
+    with(|s| match std::mem::replace(&mut s.error, gl::NO_ERROR) {
+        gl::NO_ERROR => s.gl.get_error(),
+        error => error,
+    })
+}
+
+unsafe extern "system" fn get_integerv(pname: GLenum, data: *mut GLint) {
+    let values = with(|s| match pname {
+        gl::NUM_EXTENSIONS => vec![s.extensions.len() as GLint],
+        gl::MAJOR_VERSION => vec![3],
+        gl::MINOR_VERSION => vec![0],
+        gl::CONTEXT_PROFILE_MASK | gl::CONTEXT_FLAGS => vec![0],
+        gl::UNPACK_ALIGNMENT => vec![s.unpack_alignment as GLint],
+        gl::PACK_ALIGNMENT => vec![s.pack_alignment as GLint],
+        _ => match s.gl.get_parameter(pname) {
+            Ok(value) => match s.binding_name(pname, &value) {
+                Some(name) => vec![name as GLint],
+                None => integers(&value),
+            },
+            Err(_) => {
+                s.set_error(gl::INVALID_ENUM);
+                Vec::new()
+            }
+        },
+    });
+    for (index, value) in values.into_iter().enumerate() {
+        *data.add(index) = value;
+    }
+}
+
+unsafe extern "system" fn get_booleanv(pname: GLenum, data: *mut GLboolean) {
+    let values = with(|s| match s.gl.get_parameter(pname) {
+        Ok(value) => integers(&value),
+        Err(_) => {
+            s.set_error(gl::INVALID_ENUM);
+            Vec::new()
+        }
+    });
+    for (index, value) in values.into_iter().enumerate() {
+        *data.add(index) = (value != 0) as GLboolean;
+    }
+}
+
+extern "system" fn get_string(name: GLenum) -> *const GLubyte {
+    with(|s| {
+        if !s.strings.contains_key(&name) {
+            let value = match name {
+                gl::VERSION => "OpenGL ES 3.0 (WebGL 2.0)".to_string(),
+                gl::SHADING_LANGUAGE_VERSION => "OpenGL ES GLSL ES 3.00".to_string(),
+                _ => match s.gl.get_parameter(name).ok().and_then(|v| v.as_string()) {
+                    Some(value) => value,
+                    None => {
+                        s.set_error(gl::INVALID_ENUM);
+                        return ptr::null();
+                    }
+                },
+            };
+            s.strings
+                .insert(name, CString::new(value).unwrap_or_default());
+        }
+        s.strings[&name].as_ptr() as *const GLubyte
+    })
+}
+
+extern "system" fn get_stringi(name: GLenum, index: GLuint) -> *const GLubyte {
+    with(|s| match (name, s.extensions.get(index as usize)) {
+        (gl::EXTENSIONS, Some(extension)) => extension.as_ptr() as *const GLubyte,
+        _ => {
+            s.set_error(gl::INVALID_VALUE);
+            ptr::null()
+        }
+    })
+}
+
+// Buffers
+
+unsafe extern "system" fn gen_buffers(count: GLsizei, buffers: *mut GLuint) {
+    generate(count, buffers, |s| &mut s.buffers, Gl::create_buffer)
+}
+
+unsafe extern "system" fn delete_buffers(count: GLsizei, buffers: *const GLuint) {
+    delete(
+        count,
+        buffers,
+        |s| &mut s.buffers,
+        |gl, buffer| gl.delete_buffer(Some(buffer)),
+    )
+}
+
+extern "system" fn bind_buffer(target: GLenum, buffer: GLuint) {
+    with(|s| s.gl.bind_buffer(target, s.buffers.get(buffer)))
+}
+
+extern "system" fn bind_buffer_base(target: GLenum, index: GLuint, buffer: GLuint) {
+    with(|s| s.gl.bind_buffer_base(target, index, s.buffers.get(buffer)))
+}
+
+unsafe extern "system" fn buffer_data(
+    target: GLenum,
+    size: GLsizeiptr,
+    data: *const c_void,
+    usage: GLenum,
+) {
+    with(|s| {
+        if data.is_null() {
+            s.gl.buffer_data_with_i32(target, size as i32, usage);
+        } else {
+            let view = array_view(gl::UNSIGNED_BYTE, data, size as usize);
+            s.gl.buffer_data_with_array_buffer_view(target, &view, usage);
+        }
+    })
+}
+
+unsafe extern "system" fn buffer_sub_data(
+    target: GLenum,
+    offset: GLintptr,
+    size: GLsizeiptr,
+    data: *const c_void,
+) {
+    with(|s| {
+        let view = array_view(gl::UNSIGNED_BYTE, data, size as usize);
+        s.gl.buffer_sub_data_with_i32_and_array_buffer_view(target, offset as i32, &view);
+    })
+}
+
+unsafe extern "system" fn get_buffer_sub_data(
+    target: GLenum,
+    offset: GLintptr,
+    size: GLsizeiptr,
+    data: *mut c_void,
+) {
+    with(|s| {
+        let view = array_view(gl::UNSIGNED_BYTE, data, size as usize);
+        s.gl.get_buffer_sub_data_with_i32_and_array_buffer_view(target, offset as i32, &view);
+    })
+}
+
+unsafe extern "system" fn get_buffer_parameteriv(target: GLenum, pname: GLenum, data: *mut GLint) {
+    if let Some(&value) = integers(&with(|s| s.gl.get_buffer_parameter(target, pname))).first() {
+        *data = value;
+    }
+}
+
+// Vertex arrays
+
+unsafe extern "system" fn gen_vertex_arrays(count: GLsizei, arrays: *mut GLuint) {
+    generate(
+        count,
+        arrays,
+        |s| &mut s.vertex_arrays,
+        Gl::create_vertex_array,
+    )
+}
+
+unsafe extern "system" fn delete_vertex_arrays(count: GLsizei, arrays: *const GLuint) {
+    delete(
+        count,
+        arrays,
+        |s| &mut s.vertex_arrays,
+        |gl, array| gl.delete_vertex_array(Some(array)),
+    )
+}
+
+extern "system" fn bind_vertex_array(array: GLuint) {
+    with(|s| s.gl.bind_vertex_array(s.vertex_arrays.get(array)))
+}
+
+extern "system" fn enable_vertex_attrib_array(index: GLuint) {
+    with(|s| s.gl.enable_vertex_attrib_array(index))
+}
+
+extern "system" fn disable_vertex_attrib_array(index: GLuint) {
+    with(|s| s.gl.disable_vertex_attrib_array(index))
+}
+
+extern "system" fn vertex_attrib_pointer(
+    index: GLuint,
+    size: GLint,
+    kind: GLenum,
+    normalized: GLboolean,
+    stride: GLsizei,
+    offset: *const c_void,
+) {
+    with(|s| {
+        s.gl.vertex_attrib_pointer_with_i32(
+            index,
+            size,
+            kind,
+            normalized != 0,
+            stride,
+            offset as i32,
+        )
+    })
+}
+
+extern "system" fn vertex_attrib_i_pointer(
+    index: GLuint,
+    size: GLint,
+    kind: GLenum,
+    stride: GLsizei,
+    offset: *const c_void,
+) {
+    with(|s| {
+        s.gl.vertex_attrib_i_pointer_with_i32(index, size, kind, stride, offset as i32)
+    })
+}
+
+extern "system" fn vertex_attrib_divisor(index: GLuint, divisor: GLuint) {
+    with(|s| s.gl.vertex_attrib_divisor(index, divisor))
+}
+
+extern "system" fn vertex_attrib4f(index: GLuint, x: GLfloat, y: GLfloat, z: GLfloat, w: GLfloat) {
+    with(|s| s.gl.vertex_attrib4f(index, x, y, z, w))
+}
+
+unsafe extern "system" fn get_vertex_attribiv(index: GLuint, pname: GLenum, data: *mut GLint) {
+    let value = with(|s| {
+        let value =
+            s.gl.get_vertex_attrib(index, pname)
+                .unwrap_or(JsValue::NULL);
+        if pname == gl::VERTEX_ATTRIB_ARRAY_BUFFER_BINDING {
+            vec![s.buffers.name_of(&value) as GLint]
+        } else {
+            integers(&value)
+        }
+    });
+    for (offset, value) in value.into_iter().enumerate() {
+        *data.add(offset) = value;
+    }
+}
+
+unsafe extern "system" fn get_vertex_attrib_pointerv(
+    index: GLuint,
+    pname: GLenum,
+    pointer: *const *mut c_void,
+) {
+    let offset = with(|s| s.gl.get_vertex_attrib_offset(index, pname));
+    *(pointer as *mut *mut c_void) = offset as usize as *mut c_void;
+}
+
+// Drawing
+
+extern "system" fn draw_arrays(mode: GLenum, first: GLint, count: GLsizei) {
+    with(|s| s.gl.draw_arrays(mode, first, count))
+}
+
+extern "system" fn draw_arrays_instanced(
+    mode: GLenum,
+    first: GLint,
+    count: GLsizei,
+    instances: GLsizei,
+) {
+    with(|s| s.gl.draw_arrays_instanced(mode, first, count, instances))
+}
+
+extern "system" fn draw_elements(
+    mode: GLenum,
+    count: GLsizei,
+    kind: GLenum,
+    offset: *const c_void,
+) {
+    with(|s| {
+        s.gl.draw_elements_with_i32(mode, count, kind, offset as i32)
+    })
+}
+
+extern "system" fn draw_elements_instanced(
+    mode: GLenum,
+    count: GLsizei,
+    kind: GLenum,
+    offset: *const c_void,
+    instances: GLsizei,
+) {
+    with(|s| {
+        s.gl.draw_elements_instanced_with_i32(mode, count, kind, offset as i32, instances)
+    })
+}
+
+unsafe extern "system" fn multi_draw_elements(
+    mode: GLenum,
+    counts: *const GLsizei,
+    kind: GLenum,
+    offsets: *const *const c_void,
+    draws: GLsizei,
+) {
+    with(|s| {
+        for draw in 0..draws.max(0) as usize {
+            let offset = *offsets.add(draw) as i32;
+            s.gl.draw_elements_with_i32(mode, *counts.add(draw), kind, offset);
+        }
+    })
+}
+
+// Shaders and programs
+
+extern "system" fn create_shader(kind: GLenum) -> GLuint {
+    with(|s| match s.gl.create_shader(kind) {
+        Some(shader) => s.shader_objects.insert(ShaderObject::Shader(shader)),
+        None => 0,
+    })
+}
+
+extern "system" fn delete_shader(shader: GLuint) {
+    with(|s| {
+        if let Some(ShaderObject::Shader(handle)) = s.shader_objects.remove(shader) {
+            s.gl.delete_shader(Some(&handle));
+        }
+    })
+}
+
+unsafe extern "system" fn shader_source(
+    shader: GLuint,
+    count: GLsizei,
+    strings: *const *const GLchar,
+    lengths: *const GLint,
+) {
+    let mut source = String::new();
+    for index in 0..count.max(0) as usize {
+        let string = *strings.add(index);
+        let length = if lengths.is_null() {
+            -1
+        } else {
+            *lengths.add(index)
+        };
+        let bytes = if length < 0 {
+            CStr::from_ptr(string).to_bytes()
+        } else {
+            std::slice::from_raw_parts(string as *const u8, length as usize)
+        };
+        source.push_str(&String::from_utf8_lossy(bytes));
+    }
+    with(|s| match s.shader(shader) {
+        Some(handle) => s.gl.shader_source(handle, &to_glsl_es(&source)),
+        None => s.set_error(gl::INVALID_VALUE),
+    })
+}
+
+extern "system" fn compile_shader(shader: GLuint) {
+    with(|s| match s.shader(shader) {
+        Some(handle) => s.gl.compile_shader(handle),
+        None => s.set_error(gl::INVALID_VALUE),
+    })
+}
+
+unsafe extern "system" fn get_shaderiv(shader: GLuint, pname: GLenum, data: *mut GLint) {
+    let value = with(|s| {
+        let handle = s.shader(shader)?;
+        Some(match pname {
+            gl::INFO_LOG_LENGTH => {
+                s.gl.get_shader_info_log(handle)
+                    .map_or(0, |log| log.len() as GLint + 1)
+            }
+            _ => *integers(&s.gl.get_shader_parameter(handle, pname)).first()?,
+        })
+    });
+    if let Some(value) = value {
+        *data = value;
+    }
+}
+
+unsafe extern "system" fn get_shader_info_log(
+    shader: GLuint,
+    size: GLsizei,
+    length: *mut GLsizei,
+    log: *mut GLchar,
+) {
+    let text = with(|s| {
+        s.shader(shader)
+            .and_then(|handle| s.gl.get_shader_info_log(handle))
+    });
+    write_string(&text.unwrap_or_default(), size, length, log);
+}
+
+extern "system" fn create_program() -> GLuint {
+    with(|s| match s.gl.create_program() {
+        Some(program) => s
+            .shader_objects
+            .insert(ShaderObject::Program(Box::new(Program {
+                program,
+                uniforms: Vec::new(),
+                uniform_names: HashMap::new(),
+            }))),
+        None => 0,
+    })
+}
+
+extern "system" fn delete_program(program: GLuint) {
+    with(|s| {
+        if let Some(ShaderObject::Program(handle)) = s.shader_objects.remove(program) {
+            s.gl.delete_program(Some(&handle.program));
+        }
+    })
+}
+
+extern "system" fn attach_shader(program: GLuint, shader: GLuint) {
+    with(|s| match (s.program(program), s.shader(shader)) {
+        (Some(program), Some(shader)) => s.gl.attach_shader(&program.program, shader),
+        _ => s.set_error(gl::INVALID_VALUE),
+    })
+}
+
+extern "system" fn link_program(program: GLuint) {
+    with(|s| {
+        let gl = s.gl.clone();
+        match s.shader_objects.get_mut(program) {
+            Some(ShaderObject::Program(handle)) => {
+                gl.link_program(&handle.program);
+                // Locations from before the link are no longer valid
+                handle.uniforms.clear();
+                handle.uniform_names.clear();
+            }
+            _ => s.set_error(gl::INVALID_VALUE),
+        }
+    })
+}
+
+extern "system" fn use_program(program: GLuint) {
+    with(|s| {
+        s.current_program = program;
+        let handle = s.program(program).map(|program| &program.program);
+        s.gl.use_program(handle);
+    })
+}
+
+unsafe extern "system" fn get_programiv(program: GLuint, pname: GLenum, data: *mut GLint) {
+    let value = with(|s| {
+        let handle = &s.program(program)?.program;
+        Some(match pname {
+            gl::INFO_LOG_LENGTH => {
+                s.gl.get_program_info_log(handle)
+                    .map_or(0, |log| log.len() as GLint + 1)
+            }
+            _ => *integers(&s.gl.get_program_parameter(handle, pname)).first()?,
+        })
+    });
+    if let Some(value) = value {
+        *data = value;
+    }
+}
+
+unsafe extern "system" fn get_program_info_log(
+    program: GLuint,
+    size: GLsizei,
+    length: *mut GLsizei,
+    log: *mut GLchar,
+) {
+    let text = with(|s| {
+        s.program(program)
+            .and_then(|handle| s.gl.get_program_info_log(&handle.program))
+    });
+    write_string(&text.unwrap_or_default(), size, length, log);
+}
+
+unsafe extern "system" fn get_uniform_location(program: GLuint, name: *const GLchar) -> GLint {
+    let name = CStr::from_ptr(name).to_string_lossy().into_owned();
+    with(|s| {
+        let gl = s.gl.clone();
+        let Some(ShaderObject::Program(handle)) = s.shader_objects.get_mut(program) else {
+            s.set_error(gl::INVALID_VALUE);
+            return -1;
+        };
+        if let Some(&location) = handle.uniform_names.get(&name) {
+            return location;
+        }
+        let location = match gl.get_uniform_location(&handle.program, &name) {
+            Some(location) => {
+                handle.uniforms.push(location);
+                handle.uniforms.len() as GLint - 1
+            }
+            None => -1,
+        };
+        handle.uniform_names.insert(name, location);
+        location
+    })
+}
+
+unsafe extern "system" fn get_uniform_block_index(program: GLuint, name: *const GLchar) -> GLuint {
+    let name = CStr::from_ptr(name).to_string_lossy();
+    with(|s| match s.program(program) {
+        Some(handle) => s.gl.get_uniform_block_index(&handle.program, &name),
+        None => gl::INVALID_INDEX,
+    })
+}
+
+extern "system" fn uniform_block_binding(program: GLuint, block: GLuint, binding: GLuint) {
+    with(|s| {
+        if let Some(handle) = s.program(program) {
+            s.gl.uniform_block_binding(&handle.program, block, binding);
+        }
+    })
+}
+
+extern "system" fn uniform1f(location: GLint, x: GLfloat) {
+    with(|s| s.gl.uniform1f(s.uniform(location), x))
+}
+
+extern "system" fn uniform2f(location: GLint, x: GLfloat, y: GLfloat) {
+    with(|s| s.gl.uniform2f(s.uniform(location), x, y))
+}
+
+extern "system" fn uniform3f(location: GLint, x: GLfloat, y: GLfloat, z: GLfloat) {
+    with(|s| s.gl.uniform3f(s.uniform(location), x, y, z))
+}
+
+extern "system" fn uniform4f(location: GLint, x: GLfloat, y: GLfloat, z: GLfloat, w: GLfloat) {
+    with(|s| s.gl.uniform4f(s.uniform(location), x, y, z, w))
+}
+
+extern "system" fn uniform1i(location: GLint, x: GLint) {
+    with(|s| s.gl.uniform1i(s.uniform(location), x))
+}
+
+extern "system" fn uniform2i(location: GLint, x: GLint, y: GLint) {
+    with(|s| s.gl.uniform2i(s.uniform(location), x, y))
+}
+
+extern "system" fn uniform3i(location: GLint, x: GLint, y: GLint, z: GLint) {
+    with(|s| s.gl.uniform3i(s.uniform(location), x, y, z))
+}
+
+extern "system" fn uniform4i(location: GLint, x: GLint, y: GLint, z: GLint, w: GLint) {
+    with(|s| s.gl.uniform4i(s.uniform(location), x, y, z, w))
+}
+
+extern "system" fn uniform1ui(location: GLint, x: GLuint) {
+    with(|s| s.gl.uniform1ui(s.uniform(location), x))
+}
+
+unsafe extern "system" fn uniform1fv(location: GLint, count: GLsizei, values: *const GLfloat) {
+    let values = std::slice::from_raw_parts(values, count.max(0) as usize);
+    with(|s| s.gl.uniform1fv_with_f32_array(s.uniform(location), values))
+}
+
+unsafe extern "system" fn uniform2fv(location: GLint, count: GLsizei, values: *const GLfloat) {
+    let values = std::slice::from_raw_parts(values, count.max(0) as usize * 2);
+    with(|s| s.gl.uniform2fv_with_f32_array(s.uniform(location), values))
+}
+
+unsafe extern "system" fn uniform3fv(location: GLint, count: GLsizei, values: *const GLfloat) {
+    let values = std::slice::from_raw_parts(values, count.max(0) as usize * 3);
+    with(|s| s.gl.uniform3fv_with_f32_array(s.uniform(location), values))
+}
+
+unsafe extern "system" fn uniform4fv(location: GLint, count: GLsizei, values: *const GLfloat) {
+    let values = std::slice::from_raw_parts(values, count.max(0) as usize * 4);
+    with(|s| s.gl.uniform4fv_with_f32_array(s.uniform(location), values))
+}
+
+unsafe extern "system" fn uniform1iv(location: GLint, count: GLsizei, values: *const GLint) {
+    let values = std::slice::from_raw_parts(values, count.max(0) as usize);
+    with(|s| s.gl.uniform1iv_with_i32_array(s.uniform(location), values))
+}
+
+unsafe extern "system" fn uniform_matrix3fv(
+    location: GLint,
+    count: GLsizei,
+    transpose: GLboolean,
+    values: *const GLfloat,
+) {
+    let values = std::slice::from_raw_parts(values, count.max(0) as usize * 9);
+    with(|s| {
+        s.gl.uniform_matrix3fv_with_f32_array(s.uniform(location), transpose != 0, values)
+    })
+}
+
+unsafe extern "system" fn uniform_matrix4fv(
+    location: GLint,
+    count: GLsizei,
+    transpose: GLboolean,
+    values: *const GLfloat,
+) {
+    let values = std::slice::from_raw_parts(values, count.max(0) as usize * 16);
+    with(|s| {
+        s.gl.uniform_matrix4fv_with_f32_array(s.uniform(location), transpose != 0, values)
+    })
+}
+
+// Textures
+
+unsafe extern "system" fn gen_textures(count: GLsizei, textures: *mut GLuint) {
+    generate(count, textures, |s| &mut s.textures, Gl::create_texture)
+}
+
+unsafe extern "system" fn delete_textures(count: GLsizei, textures: *const GLuint) {
+    for index in 0..count.max(0) as usize {
+        let name = *textures.add(index);
+        with(|s| s.texture_sizes.retain(|&(texture, _), _| texture != name));
+    }
+    delete(
+        count,
+        textures,
+        |s| &mut s.textures,
+        |gl, texture| gl.delete_texture(Some(texture)),
+    )
+}
+
+extern "system" fn bind_texture(target: GLenum, texture: GLuint) {
+    with(|s| s.gl.bind_texture(target, s.textures.get(texture)))
+}
+
+extern "system" fn tex_parameteri(target: GLenum, pname: GLenum, param: GLint) {
+    with(|s| s.gl.tex_parameteri(target, pname, param))
+}
+
+extern "system" fn tex_parameterf(target: GLenum, pname: GLenum, param: GLfloat) {
+    with(|s| s.gl.tex_parameterf(target, pname, param))
+}
+
+/// WebGL has no vector texture parameters; the only one, the border color, does not exist
+/// because there is no `GL_CLAMP_TO_BORDER`.
+extern "system" fn tex_parameterfv(_target: GLenum, _pname: GLenum, _params: *const GLfloat) {
+    with(|s| s.set_error(gl::INVALID_ENUM))
+}
+
+extern "system" fn generate_mipmap(target: GLenum) {
+    with(|s| s.gl.generate_mipmap(target))
+}
+
+unsafe extern "system" fn tex_image_2d(
+    target: GLenum,
+    level: GLint,
+    internal_format: GLint,
+    width: GLsizei,
+    height: GLsizei,
+    border: GLint,
+    format: GLenum,
+    kind: GLenum,
+    pixels: *const c_void,
+) {
+    with(|s| {
+        let texture = s.bound_texture(target);
+        s.texture_sizes.insert((texture, level), (width, height));
+        let view = match (pixels.is_null(), pixel_size(format, kind)) {
+            (true, _) => None,
+            (false, Some(pixel)) => {
+                let size = image_size(width, height, pixel, s.unpack_alignment);
+                Some(array_view(kind, pixels, size))
+            }
+            (false, None) => {
+                s.set_error(gl::INVALID_ENUM);
+                return;
+            }
+        };
+        if s.gl
+            .tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_array_buffer_view(
+                target,
+                level,
+                internal_format,
+                width,
+                height,
+                border,
+                format,
+                kind,
+                view.as_ref(),
+            )
+            .is_err()
+        {
+            s.set_error(gl::INVALID_OPERATION);
+        }
+    })
+}
+
+unsafe extern "system" fn tex_sub_image_2d(
+    target: GLenum,
+    level: GLint,
+    x: GLint,
+    y: GLint,
+    width: GLsizei,
+    height: GLsizei,
+    format: GLenum,
+    kind: GLenum,
+    pixels: *const c_void,
+) {
+    with(|s| {
+        let Some(pixel) = pixel_size(format, kind) else {
+            s.set_error(gl::INVALID_ENUM);
+            return;
+        };
+        let size = image_size(width, height, pixel, s.unpack_alignment);
+        let view = array_view(kind, pixels, size);
+        if s.gl
+            .tex_sub_image_2d_with_i32_and_i32_and_u32_and_type_and_opt_array_buffer_view(
+                target,
+                level,
+                x,
+                y,
+                width,
+                height,
+                format,
+                kind,
+                Some(&view),
+            )
+            .is_err()
+        {
+            s.set_error(gl::INVALID_OPERATION);
+        }
+    })
+}
+
+extern "system" fn tex_storage_2d(
+    target: GLenum,
+    levels: GLsizei,
+    internal_format: GLenum,
+    width: GLsizei,
+    height: GLsizei,
+) {
+    with(|s| {
+        let texture = s.bound_texture(target);
+        for level in 0..levels.max(0) {
+            let size = ((width >> level).max(1), (height >> level).max(1));
+            s.texture_sizes.insert((texture, level), size);
+        }
+        s.gl.tex_storage_2d(target, levels, internal_format, width, height)
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+extern "system" fn copy_tex_sub_image_2d(
+    target: GLenum,
+    level: GLint,
+    x_offset: GLint,
+    y_offset: GLint,
+    x: GLint,
+    y: GLint,
+    width: GLsizei,
+    height: GLsizei,
+) {
+    with(|s| {
+        s.gl.copy_tex_sub_image_2d(target, level, x_offset, y_offset, x, y, width, height)
+    })
+}
+
+/// Reads a texture level back through a temporary framebuffer, since WebGL cannot read
+/// textures directly.
+unsafe extern "system" fn get_tex_image(
+    target: GLenum,
+    level: GLint,
+    format: GLenum,
+    kind: GLenum,
+    pixels: *mut c_void,
+) {
+    with(|s| {
+        let texture = s.bound_texture(target);
+        let (Some(&(width, height)), Some(handle), Some(pixel)) = (
+            s.texture_sizes.get(&(texture, level)),
+            s.textures.get(texture),
+            pixel_size(format, kind),
+        ) else {
+            s.set_error(gl::INVALID_OPERATION);
+            return;
+        };
+        let previous =
+            s.gl.get_parameter(gl::READ_FRAMEBUFFER_BINDING)
+                .ok()
+                .and_then(|framebuffer| framebuffer.dyn_into::<WebGlFramebuffer>().ok());
+        let framebuffer = s.gl.create_framebuffer();
+        s.gl.bind_framebuffer(gl::READ_FRAMEBUFFER, framebuffer.as_ref());
+        s.gl.framebuffer_texture_2d(
+            gl::READ_FRAMEBUFFER,
+            gl::COLOR_ATTACHMENT0,
+            target,
+            Some(handle),
+            level,
+        );
+        let size = image_size(width, height, pixel, s.pack_alignment);
+        let view = array_view(kind, pixels, size);
+        if s.gl
+            .read_pixels_with_opt_array_buffer_view(0, 0, width, height, format, kind, Some(&view))
+            .is_err()
+        {
+            s.set_error(gl::INVALID_OPERATION);
+        }
+        s.gl.bind_framebuffer(gl::READ_FRAMEBUFFER, previous.as_ref());
+        s.gl.delete_framebuffer(framebuffer.as_ref());
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+unsafe extern "system" fn read_pixels(
+    x: GLint,
+    y: GLint,
+    width: GLsizei,
+    height: GLsizei,
+    format: GLenum,
+    kind: GLenum,
+    pixels: *mut c_void,
+) {
+    with(|s| {
+        let Some(pixel) = pixel_size(format, kind) else {
+            s.set_error(gl::INVALID_ENUM);
+            return;
+        };
+        let size = image_size(width, height, pixel, s.pack_alignment);
+        let view = array_view(kind, pixels, size);
+        if s.gl
+            .read_pixels_with_opt_array_buffer_view(x, y, width, height, format, kind, Some(&view))
+            .is_err()
+        {
+            s.set_error(gl::INVALID_OPERATION);
+        }
+    })
+}
+
+// Framebuffers and renderbuffers
+
+unsafe extern "system" fn gen_framebuffers(count: GLsizei, framebuffers: *mut GLuint) {
+    generate(
+        count,
+        framebuffers,
+        |s| &mut s.framebuffers,
+        Gl::create_framebuffer,
+    )
+}
+
+unsafe extern "system" fn delete_framebuffers(count: GLsizei, framebuffers: *const GLuint) {
+    delete(
+        count,
+        framebuffers,
+        |s| &mut s.framebuffers,
+        |gl, framebuffer| gl.delete_framebuffer(Some(framebuffer)),
+    )
+}
+
+extern "system" fn bind_framebuffer(target: GLenum, framebuffer: GLuint) {
+    with(|s| {
+        s.gl.bind_framebuffer(target, s.framebuffers.get(framebuffer))
+    })
+}
+
+extern "system" fn check_framebuffer_status(target: GLenum) -> GLenum {
+    with(|s| s.gl.check_framebuffer_status(target))
+}
+
+extern "system" fn framebuffer_texture_2d(
+    target: GLenum,
+    attachment: GLenum,
+    texture_target: GLenum,
+    texture: GLuint,
+    level: GLint,
+) {
+    with(|s| {
+        s.gl.framebuffer_texture_2d(
+            target,
+            attachment,
+            texture_target,
+            s.textures.get(texture),
+            level,
+        )
+    })
+}
+
+extern "system" fn framebuffer_renderbuffer(
+    target: GLenum,
+    attachment: GLenum,
+    renderbuffer_target: GLenum,
+    renderbuffer: GLuint,
+) {
+    with(|s| {
+        s.gl.framebuffer_renderbuffer(
+            target,
+            attachment,
+            renderbuffer_target,
+            s.renderbuffers.get(renderbuffer),
+        )
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+extern "system" fn blit_framebuffer(
+    source_x0: GLint,
+    source_y0: GLint,
+    source_x1: GLint,
+    source_y1: GLint,
+    destination_x0: GLint,
+    destination_y0: GLint,
+    destination_x1: GLint,
+    destination_y1: GLint,
+    mask: GLbitfield,
+    filter: GLenum,
+) {
+    with(|s| {
+        s.gl.blit_framebuffer(
+            source_x0,
+            source_y0,
+            source_x1,
+            source_y1,
+            destination_x0,
+            destination_y0,
+            destination_x1,
+            destination_y1,
+            mask,
+            filter,
+        )
+    })
+}
+
+/// Selects a single draw buffer. WebGL only has `drawBuffers`.
+extern "system" fn draw_buffer(buffer: GLenum) {
+    with(|s| {
+        let buffers = js_sys::Array::of1(&JsValue::from(buffer));
+        s.gl.draw_buffers(&buffers)
+    })
+}
+
+unsafe extern "system" fn draw_buffers(count: GLsizei, buffers: *const GLenum) {
+    let buffers: js_sys::Array = std::slice::from_raw_parts(buffers, count.max(0) as usize)
+        .iter()
+        .map(|&buffer| JsValue::from(buffer))
+        .collect();
+    with(|s| s.gl.draw_buffers(&buffers))
+}
+
+extern "system" fn read_buffer(source: GLenum) {
+    with(|s| s.gl.read_buffer(source))
+}
+
+unsafe extern "system" fn gen_renderbuffers(count: GLsizei, renderbuffers: *mut GLuint) {
+    generate(
+        count,
+        renderbuffers,
+        |s| &mut s.renderbuffers,
+        Gl::create_renderbuffer,
+    )
+}
+
+unsafe extern "system" fn delete_renderbuffers(count: GLsizei, renderbuffers: *const GLuint) {
+    delete(
+        count,
+        renderbuffers,
+        |s| &mut s.renderbuffers,
+        |gl, renderbuffer| gl.delete_renderbuffer(Some(renderbuffer)),
+    )
+}
+
+extern "system" fn bind_renderbuffer(target: GLenum, renderbuffer: GLuint) {
+    with(|s| {
+        s.gl.bind_renderbuffer(target, s.renderbuffers.get(renderbuffer))
+    })
+}
+
+extern "system" fn renderbuffer_storage(
+    target: GLenum,
+    internal_format: GLenum,
+    width: GLsizei,
+    height: GLsizei,
+) {
+    with(|s| {
+        s.gl.renderbuffer_storage(target, internal_format, width, height)
+    })
+}
+
+extern "system" fn renderbuffer_storage_multisample(
+    target: GLenum,
+    samples: GLsizei,
+    internal_format: GLenum,
+    width: GLsizei,
+    height: GLsizei,
+) {
+    with(|s| {
+        s.gl.renderbuffer_storage_multisample(target, samples, internal_format, width, height)
+    })
+}
+
+// Queries
+
+unsafe extern "system" fn gen_queries(count: GLsizei, queries: *mut GLuint) {
+    generate(count, queries, |s| &mut s.queries, Gl::create_query)
+}
+
+unsafe extern "system" fn delete_queries(count: GLsizei, queries: *const GLuint) {
+    delete(
+        count,
+        queries,
+        |s| &mut s.queries,
+        |gl, query| gl.delete_query(Some(query)),
+    )
+}
+
+extern "system" fn begin_query(target: GLenum, query: GLuint) {
+    with(|s| match s.queries.get(query) {
+        Some(handle) => s.gl.begin_query(target, handle),
+        None => s.set_error(gl::INVALID_OPERATION),
+    })
+}
+
+extern "system" fn end_query(target: GLenum) {
+    with(|s| s.gl.end_query(target))
+}
+
+/// Returns a query result as a number, or `None` if the query does not exist.
+fn query_result(query: GLuint, pname: GLenum) -> Option<f64> {
+    with(|s| {
+        let handle = s.queries.get(query)?;
+        let value = s.gl.get_query_parameter(handle, pname);
+        value
+            .as_f64()
+            .or_else(|| value.as_bool().map(|value| value as u8 as f64))
+    })
+}
+
+unsafe extern "system" fn get_query_objectuiv(query: GLuint, pname: GLenum, data: *mut GLuint) {
+    if let Some(value) = query_result(query, pname) {
+        *data = value as GLuint;
+    }
+}
+
+unsafe extern "system" fn get_query_objectui64v(query: GLuint, pname: GLenum, data: *mut GLuint64) {
+    if let Some(value) = query_result(query, pname) {
+        *data = value as GLuint64;
+    }
+}
@@ -0,0 +1,560 @@
+//! # Web Window Module
+//!
+//! The browser version of `Window`, drawing into an HTML canvas with a WebGL2 context. It is
+//! available with the `web` feature and replaces the GLFW window when the `native` feature
+//! is disabled.
+//!
+//! The window uses the canvas with the id `glwfr` if the page has one, and otherwise appends
+//! a new canvas to the page body. Its size follows the canvas' size on the page; the
+//! framebuffer is resized to the canvas' size in device pixels during `update`.
+//!
+//! Browsers do not allow blocking the page in a loop, so the main loop is written with
+//! `run`, which calls the frame function on every animation frame. The same code runs on
+//! the native backend, where `run` is a plain loop.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::graphics::window::Window;
+//! use glwfr::graphics::render_state::{ClearState, RenderState};
+//!
+//! fn main() -> Result<(), glwfr::custom_errors::Errors> {
+//!     let mut window = Window::new(800, 600, "My Window")?;
+//!     window.init_gl()?;
+//!     RenderState::default().apply();
+//!
+//!     window.run(|window| {
+//!         window.clear_with(&ClearState::default());
+//!     });
+//!     Ok(())
+//! }
+//! ```
+
+use crate::custom_errors::Errors;
+use crate::graphics::render_state::{BlendState, ClearState, DepthState};
+use crate::graphics::web::events::{Action, Key, WindowEvent};
+use crate::graphics::web::webgl;
+use crate::input;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{
+    Document, Event, EventTarget, HtmlCanvasElement, KeyboardEvent, MouseEvent,
+    WebGl2RenderingContext, WheelEvent,
+};
+
+/// How often the FPS counter in the title is refreshed, in seconds.
+const FPS_TITLE_INTERVAL: f64 = 0.5;
+
+/// Smoothing factor for the frame time average used by the FPS counter.
+const FPS_SMOOTHING: f64 = 0.1;
+
+/// The id of the canvas the window draws into, if the page provides one.
+const CANVAS_ID: &str = "glwfr";
+
+/// The animation frame callback of `Window::run`, shared so it can request itself again.
+type FrameCallback = Rc<RefCell<Option<Closure<dyn FnMut()>>>>;
+
+/// An event listener registered by the window, removed again when it is dropped.
+struct Listener {
+    target: EventTarget,
+    kind: &'static str,
+    callback: Closure<dyn FnMut(Event)>,
+}
+
+pub struct Window {
+    document: Document,
+    canvas: HtmlCanvasElement,
+    events: Rc<RefCell<VecDeque<WindowEvent>>>,
+    listeners: Vec<Listener>,
+    should_close: bool,
+    title: String,
+    show_fps: bool,
+    last_frame_time: f64,
+    last_title_update: f64,
+    average_frame_time: f64,
+}
+
+impl Window {
+    /// Create a new window drawing into a canvas of the given size in CSS pixels, and set
+    /// the title of the page.
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - The width of the canvas in CSS pixels. Ignored for a canvas provided by the
+    ///   page, which keeps the size given by its style.
+    /// * `height` - The height of the canvas in CSS pixels, see `width`.
+    /// * `title` - The title of the page.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a `Window` instance if successful, or an error of type
+    /// `Errors::WindowCreationError` if there is no page or canvas to draw into.
+    pub fn new(width: u32, height: u32, title: &str) -> Result<Self, Errors> {
+        let document = web_sys::window()
+            .and_then(|window| window.document())
+            .ok_or_else(|| Errors::WindowCreationError("No document to draw into".to_string()))?;
+        let canvas = match document.get_element_by_id(CANVAS_ID) {
+            Some(element) => element.dyn_into::<HtmlCanvasElement>().map_err(|_| {
+                Errors::WindowCreationError(format!("#{} is not a canvas", CANVAS_ID))
+            })?,
+            None => {
+                let canvas = document
+                    .create_element("canvas")
+                    .map_err(js_error)?
+                    .dyn_into::<HtmlCanvasElement>()
+                    .map_err(|_| {
+                        Errors::WindowCreationError("Failed to create a canvas".to_string())
+                    })?;
+                canvas.set_id(CANVAS_ID);
+                let style = canvas.style();
+                style
+                    .set_property("width", &format!("{}px", width))
+                    .map_err(js_error)?;
+                style
+                    .set_property("height", &format!("{}px", height))
+                    .map_err(js_error)?;
+                document
+                    .body()
+                    .ok_or_else(|| Errors::WindowCreationError("The page has no body".to_string()))?
+                    .append_child(&canvas)
+                    .map_err(js_error)?;
+                canvas
+            }
+        };
+        // Make the canvas focusable, so it receives keyboard events
+        canvas.set_tab_index(0);
+        let _ = canvas.focus();
+
+        let mut window = Self {
+            document,
+            canvas,
+            events: Rc::new(RefCell::new(VecDeque::new())),
+            listeners: Vec::new(),
+            should_close: false,
+            title: title.to_string(),
+            show_fps: false,
+            last_frame_time: now(),
+            last_title_update: now(),
+            average_frame_time: 0.0,
+        };
+        window.listen_for_events();
+        window.resize_framebuffer();
+        window.refresh_title();
+        Ok(window)
+    }
+
+    /// Initialize the WebGL2 context of the canvas.
+    ///
+    /// This function creates the canvas' WebGL2 context and loads the `gl` crate's function
+    /// pointers with functions forwarding to it, see `webgl`. The default vertex color read
+    /// by meshes without a color attribute is set to white.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::WindowCreationError` if the browser does not support WebGL2, or
+    /// an error if there are any OpenGL errors during the initialization process.
+    pub fn init_gl(&mut self) -> Result<(), Errors> {
+        let context = self
+            .canvas
+            .get_context("webgl2")
+            .map_err(js_error)?
+            .and_then(|context| context.dyn_into::<WebGl2RenderingContext>().ok())
+            .ok_or_else(|| {
+                Errors::WindowCreationError("The browser does not support WebGL2".to_string())
+            })?;
+        webgl::load(context)?;
+        crate::graphics::gl_wrapper::mark_context_thread();
+        crate::graphics::standard_vertex::set_default_vertex_color([1.0; 4]);
+        let (width, height) = self.framebuffer_size();
+        unsafe { gl::Viewport(0, 0, width, height) }
+
+        // Check for OpenGL errors
+        crate::custom_errors::check_opengl_error()?;
+        Ok(())
+    }
+
+    /// Check if the window should close, i.e. escape was pressed.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the window should close, otherwise `false`.
+    pub fn should_close(&self) -> bool {
+        self.should_close
+    }
+
+    /// Returns the size of the canvas in CSS pixels as `(width, height)`, the unit mouse
+    /// positions are reported in.
+    ///
+    /// On high-DPI displays this can differ from the framebuffer size in pixels.
+    pub fn size(&self) -> (i32, i32) {
+        (self.canvas.client_width(), self.canvas.client_height())
+    }
+
+    /// Returns the size of the canvas' framebuffer in pixels as `(width, height)`.
+    ///
+    /// On high-DPI displays this can differ from the canvas size in CSS pixels.
+    pub fn framebuffer_size(&self) -> (i32, i32) {
+        (self.canvas.width() as i32, self.canvas.height() as i32)
+    }
+
+    /// Set the title of the page.
+    ///
+    /// If the FPS counter is enabled, it is appended to the new title.
+    ///
+    /// # Arguments
+    ///
+    /// * `title` - The new title of the page.
+    pub fn set_title(&mut self, title: &str) {
+        self.title = title.to_string();
+        self.refresh_title();
+    }
+
+    /// Returns the title of the page, without the FPS counter.
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// Append a smoothed FPS counter to the page title, updated by `update`.
+    ///
+    /// # Arguments
+    ///
+    /// * `show` - `true` to show the counter, `false` to restore the plain title.
+    pub fn show_fps_in_title(&mut self, show: bool) {
+        self.show_fps = show;
+        self.refresh_title();
+    }
+
+    /// Returns the smoothed frames per second measured by `update`.
+    pub fn fps(&self) -> f64 {
+        if self.average_frame_time > 0.0 {
+            1.0 / self.average_frame_time
+        } else {
+            0.0
+        }
+    }
+
+    /// Lock the canvas to the given aspect ratio when the page resizes it.
+    ///
+    /// # Arguments
+    ///
+    /// * `numerator` - The numerator of the aspect ratio, e.g. `16`.
+    /// * `denominator` - The denominator of the aspect ratio, e.g. `9`.
+    ///
+    /// # Web APIs
+    ///
+    /// This function sets the canvas' `aspect-ratio` style.
+    pub fn set_aspect_ratio(&mut self, numerator: u32, denominator: u32) {
+        let style = self.canvas.style();
+        let _ = style.set_property("aspect-ratio", &format!("{} / {}", numerator, denominator));
+        // A fixed height would override the aspect ratio
+        let _ = style.set_property("height", "auto");
+    }
+
+    /// Limit the size of the canvas in CSS pixels.
+    ///
+    /// # Arguments
+    ///
+    /// * `min` - The minimum size as `(width, height)`, or `None` for no lower limit.
+    /// * `max` - The maximum size as `(width, height)`, or `None` for no upper limit.
+    ///
+    /// # Web APIs
+    ///
+    /// This function sets the canvas' `min-width`, `min-height`, `max-width` and `max-height`
+    /// styles.
+    pub fn set_size_limits(&mut self, min: Option<(u32, u32)>, max: Option<(u32, u32)>) {
+        let style = self.canvas.style();
+        let limit = |size: Option<u32>, unlimited: &str| {
+            size.map_or_else(|| unlimited.to_string(), |size| format!("{}px", size))
+        };
+        let _ = style.set_property("min-width", &limit(min.map(|(width, _)| width), "0"));
+        let _ = style.set_property("min-height", &limit(min.map(|(_, height)| height), "0"));
+        let _ = style.set_property("max-width", &limit(max.map(|(width, _)| width), "none"));
+        let _ = style.set_property("max-height", &limit(max.map(|(_, height)| height), "none"));
+    }
+
+    /// Enable depth testing for this window.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glEnable(GL_DEPTH_TEST)`.
+    #[deprecated(note = "use `RenderState` or `DepthState::apply` instead")]
+    pub fn enable_depth_test(&self) {
+        DepthState::default().apply();
+    }
+
+    /// Enable blending for this window.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glEnable(GL_BLEND)` and
+    /// `glBlendFunc(GL_SRC_ALPHA, GL_ONE_MINUS_SRC_ALPHA)`.
+    #[deprecated(note = "use `RenderState` or `BlendState::apply` instead")]
+    pub fn enable_blend(&self) {
+        BlendState::ALPHA.apply();
+    }
+
+    /// Clear the screen with the given color.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glClearColor` and `glClear`.
+    ///
+    /// # Arguments
+    ///
+    /// * `r` - The red component of the color.
+    /// * `g` - The green component of the color.
+    /// * `b` - The blue component of the color.
+    /// * `a` - The alpha component of the color.
+    #[deprecated(note = "use `Window::clear_with` and `ClearState` instead")]
+    pub fn clear(&self, r: f32, g: f32, b: f32, a: f32) {
+        self.clear_with(&ClearState::color_and_depth([r, g, b, a]));
+    }
+
+    /// Clear the canvas' framebuffer with the given configuration.
+    ///
+    /// The default framebuffer must be bound, e.g. after rendering into a `Framebuffer`.
+    ///
+    /// # Arguments
+    ///
+    /// * `clear` - The buffers to clear and their values, or `ClearState::LOAD` to keep
+    ///   the previous contents.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `ClearState::apply`.
+    pub fn clear_with(&self, clear: &ClearState) {
+        clear.apply();
+    }
+
+    /// Process the events received since the last update and resize the framebuffer to the
+    /// canvas.
+    ///
+    /// The browser presents the frame once control returns to it, so this is called at the
+    /// end of every animation frame by `run`. It also measures the frame time used by `fps`
+    /// and the FPS counter in the title.
+    pub fn update(&mut self) {
+        self.resize_framebuffer();
+        self.process_events();
+        self.update_frame_time();
+    }
+
+    /// Same as `update`. Browsers cannot block while waiting for events, so animation
+    /// frames keep running at the display rate.
+    pub fn update_wait(&mut self) {
+        self.update();
+    }
+
+    /// Same as `update`, see `update_wait`.
+    ///
+    /// # Arguments
+    ///
+    /// * `_timeout` - Ignored.
+    pub fn update_wait_timeout(&mut self, _timeout: f64) {
+        self.update();
+    }
+
+    /// Run the main loop, calling `frame` and then `update` on every animation frame until
+    /// the window should close.
+    ///
+    /// This function returns immediately; the browser calls the loop from then on, and the
+    /// window is dropped once it stops.
+    ///
+    /// # Arguments
+    ///
+    /// * `frame` - Draws a frame.
+    pub fn run<F: FnMut(&mut Window) + 'static>(self, mut frame: F) {
+        let callback: FrameCallback = Rc::new(RefCell::new(None));
+        let next = callback.clone();
+        let mut window = Some(self);
+        *callback.borrow_mut() = Some(Closure::new(move || {
+            let Some(current) = window.as_mut() else {
+                return;
+            };
+            frame(current);
+            current.update();
+            if current.should_close() {
+                // Not requesting another frame ends the loop
+                window = None;
+            } else if let Some(next) = next.borrow().as_ref() {
+                request_animation_frame(next);
+            }
+        }));
+        let first = callback.borrow();
+        if let Some(first) = first.as_ref() {
+            request_animation_frame(first);
+        }
+    }
+
+    /// Measure the time since the last frame and refresh the FPS counter in the title.
+    fn update_frame_time(&mut self) {
+        let now = now();
+        let frame_time = now - self.last_frame_time;
+        self.last_frame_time = now;
+        self.average_frame_time = if self.average_frame_time > 0.0 {
+            self.average_frame_time + (frame_time - self.average_frame_time) * FPS_SMOOTHING
+        } else {
+            frame_time
+        };
+
+        if self.show_fps && now - self.last_title_update >= FPS_TITLE_INTERVAL {
+            self.last_title_update = now;
+            self.refresh_title();
+        }
+    }
+
+    /// Apply the stored title to the page, with the FPS counter if enabled.
+    fn refresh_title(&mut self) {
+        if self.show_fps {
+            let title = format!("{} - {:.0} FPS", self.title, self.fps());
+            self.document.set_title(&title);
+        } else {
+            self.document.set_title(&self.title);
+        }
+    }
+
+    /// Resize the framebuffer to the canvas' size in device pixels, queueing a
+    /// `FramebufferSize` event if it changed.
+    fn resize_framebuffer(&mut self) {
+        let scale = web_sys::window().map_or(1.0, |window| window.device_pixel_ratio());
+        let (width, height) = self.size();
+        let width = (width as f64 * scale).round().max(1.0) as u32;
+        let height = (height as f64 * scale).round().max(1.0) as u32;
+        if self.canvas.width() != width || self.canvas.height() != height {
+            self.canvas.set_width(width);
+            self.canvas.set_height(height);
+            self.events
+                .borrow_mut()
+                .push_back(WindowEvent::FramebufferSize(width as i32, height as i32));
+        }
+    }
+
+    /// Register the DOM event listeners that queue window events.
+    fn listen_for_events(&mut self) {
+        let canvas: EventTarget = self.canvas.clone().into();
+        self.listen(&canvas, "keydown", |event| {
+            let event = event.dyn_ref::<KeyboardEvent>()?;
+            // Keep keys such as space and the arrows from scrolling the page, but let the
+            // browser handle its own shortcuts
+            if !event.ctrl_key() && !event.meta_key() {
+                event.prevent_default();
+            }
+            Some(WindowEvent::from_keyboard_event(event, Action::Press))
+        });
+        self.listen(&canvas, "keyup", |event| {
+            let event = event.dyn_ref::<KeyboardEvent>()?;
+            Some(WindowEvent::from_keyboard_event(event, Action::Release))
+        });
+        self.listen(&canvas, "mousedown", |event| {
+            let event = event.dyn_ref::<MouseEvent>()?;
+            WindowEvent::from_mouse_button_event(event, Action::Press).map(|event| vec![event])
+        });
+        // Releases are tracked on the whole page, so buttons released outside the canvas
+        // do not stay pressed
+        if let Some(page) = web_sys::window() {
+            self.listen(&page.into(), "mouseup", |event| {
+                let event = event.dyn_ref::<MouseEvent>()?;
+                WindowEvent::from_mouse_button_event(event, Action::Release)
+                    .map(|event| vec![event])
+            });
+        }
+        self.listen(&canvas, "mousemove", |event| {
+            let event = event.dyn_ref::<MouseEvent>()?;
+            Some(vec![WindowEvent::CursorPos(
+                event.offset_x() as f64,
+                event.offset_y() as f64,
+            )])
+        });
+        self.listen(&canvas, "wheel", |event| {
+            let event = event.dyn_ref::<WheelEvent>()?;
+            event.prevent_default();
+            Some(vec![WindowEvent::from_wheel_event(event)])
+        });
+        self.listen(&canvas, "contextmenu", |event| {
+            // Let the right mouse button be used by the application
+            event.prevent_default();
+            None
+        });
+    }
+
+    /// Register a DOM event listener whose events are queued for `update`.
+    fn listen(
+        &mut self,
+        target: &EventTarget,
+        kind: &'static str,
+        convert: impl Fn(&Event) -> Option<Vec<WindowEvent>> + 'static,
+    ) {
+        let events = self.events.clone();
+        let callback = Closure::<dyn FnMut(Event)>::new(move |event: Event| {
+            if let Some(converted) = convert(&event) {
+                events.borrow_mut().extend(converted);
+            }
+        });
+        if target
+            .add_event_listener_with_callback(kind, callback.as_ref().unchecked_ref())
+            .is_ok()
+        {
+            self.listeners.push(Listener {
+                target: target.clone(),
+                kind,
+                callback,
+            });
+        }
+    }
+
+    /// Process window events and update the window state accordingly.
+    ///
+    /// This function will handle the following events:
+    ///
+    /// * `FramebufferSize`: Update the OpenGL viewport to match the new canvas dimensions.
+    /// * `Key` with the escape key: Mark the window as needing to close.
+    ///
+    /// This function also calls `input::process_event` to allow for input to be handled by the user.
+    /// Per-frame input state, such as triggered shortcuts, is cleared first.
+    fn process_events(&mut self) {
+        input::begin_frame();
+        let events: Vec<WindowEvent> = self.events.borrow_mut().drain(..).collect();
+        for event in events {
+            input::process_event(&event);
+            match event {
+                // Make sure the viewport matches the new canvas dimensions, once the context
+                // is initialized.
+                WindowEvent::FramebufferSize(width, height) if gl::Viewport::is_loaded() => unsafe {
+                    gl::Viewport(0, 0, width, height)
+                },
+                WindowEvent::Key(Key::Escape, _, Action::Press, _) => self.should_close = true,
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Drop for Window {
+    fn drop(&mut self) {
+        for listener in &self.listeners {
+            let _ = listener.target.remove_event_listener_with_callback(
+                listener.kind,
+                listener.callback.as_ref().unchecked_ref(),
+            );
+        }
+    }
+}
+
+/// Returns the time since the page was loaded, in seconds.
+fn now() -> f64 {
+    web_sys::window()
+        .and_then(|window| window.performance())
+        .map_or(0.0, |performance| performance.now() / 1000.0)
+}
+
+fn request_animation_frame(callback: &Closure<dyn FnMut()>) {
+    if let Some(window) = web_sys::window() {
+        let _ = window.request_animation_frame(callback.as_ref().unchecked_ref());
+    }
+}
+
+fn js_error(error: JsValue) -> Errors {
+    Errors::WindowCreationError(format!("{:?}", error))
+}
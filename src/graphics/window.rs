@@ -6,14 +6,15 @@
 //!
 //! ```rust
 //! use glwfr::graphics::window::Window;
+//! use glwfr::graphics::render_state::{ClearState, RenderState};
 //!
 //! fn main() -> Result<(), glwfr::custom_errors::Errors> {
 //!     let mut window = Window::new(800, 600, "My Window")?;
 //!     window.init_gl()?;
-//!     window.enable_depth_test();
+//!     RenderState::default().apply();
 //!
 //!     while !window.should_close() {
-//!         window.clear(0.0, 0.0, 0.0, 1.0);
+//!         window.clear_with(&ClearState::default());
 //!         window.update();
 //!     }
 //!     Ok(())
@@ -21,14 +22,26 @@
 //! ```
 
 use crate::custom_errors::Errors;
+use crate::graphics::render_state::{BlendState, ClearState, DepthState};
 use crate::input;
 use glfw::{Action, Context, Key, WindowEvent};
 use std::sync::mpsc::Receiver;
 
+/// How often the FPS counter in the title is refreshed, in seconds.
+const FPS_TITLE_INTERVAL: f64 = 0.5;
+
+/// Smoothing factor for the frame time average used by the FPS counter.
+const FPS_SMOOTHING: f64 = 0.1;
+
 pub struct Window {
     glfw: glfw::Glfw,
     window_handle: glfw::Window,
     events: Receiver<(f64, WindowEvent)>,
+    title: String,
+    show_fps: bool,
+    last_frame_time: f64,
+    last_title_update: f64,
+    average_frame_time: f64,
 }
 
 impl Window {
@@ -58,21 +71,29 @@ impl Window {
 
         window.set_framebuffer_size_polling(true);
         window.set_key_polling(true);
+        window.set_char_polling(true);
         window.set_mouse_button_polling(true);
         window.set_cursor_pos_polling(true);
         window.set_scroll_polling(true);
 
+        let now = glfw.get_time();
         Ok(Self {
             glfw,
             window_handle: window,
             events,
+            title: title.to_string(),
+            show_fps: false,
+            last_frame_time: now,
+            last_title_update: now,
+            average_frame_time: 0.0,
         })
     }
 
     /// Initialize the OpenGL context for the window.
     ///
     /// This function sets the current context to the window's OpenGL context
-    /// and loads the OpenGL function pointers using the `gl` crate.
+    /// and loads the OpenGL function pointers using the `gl` crate. The default vertex color
+    /// read by meshes without a color attribute is set to white.
     ///
     /// # Errors
     ///
@@ -82,6 +103,8 @@ impl Window {
     pub fn init_gl(&mut self) -> Result<(), Errors> {
         self.window_handle.make_current();
         gl::load_with(|s| self.window_handle.get_proc_address(s) as *const _);
+        crate::graphics::gl_wrapper::mark_context_thread();
+        crate::graphics::standard_vertex::set_default_vertex_color([1.0; 4]);
 
         // Check for OpenGL errors
         crate::custom_errors::check_opengl_error()?;
@@ -97,15 +120,98 @@ impl Window {
         self.window_handle.should_close()
     }
 
+    /// Returns the size of the window in screen coordinates as `(width, height)`, the unit
+    /// mouse positions are reported in.
+    ///
+    /// On high-DPI displays this can differ from the framebuffer size in pixels.
+    pub fn size(&self) -> (i32, i32) {
+        self.window_handle.get_size()
+    }
+
+    /// Returns the size of the window's framebuffer in pixels as `(width, height)`.
+    ///
+    /// On high-DPI displays this can differ from the window size in screen coordinates.
+    pub fn framebuffer_size(&self) -> (i32, i32) {
+        self.window_handle.get_framebuffer_size()
+    }
+
+    /// Set the title of the window.
+    ///
+    /// If the FPS counter is enabled, it is appended to the new title.
+    ///
+    /// # Arguments
+    ///
+    /// * `title` - The new title of the window.
+    pub fn set_title(&mut self, title: &str) {
+        self.title = title.to_string();
+        self.refresh_title();
+    }
+
+    /// Returns the title of the window, without the FPS counter.
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// Append a smoothed FPS counter to the window title, updated by `update`.
+    ///
+    /// # Arguments
+    ///
+    /// * `show` - `true` to show the counter, `false` to restore the plain title.
+    pub fn show_fps_in_title(&mut self, show: bool) {
+        self.show_fps = show;
+        self.refresh_title();
+    }
+
+    /// Returns the smoothed frames per second measured by `update`.
+    pub fn fps(&self) -> f64 {
+        if self.average_frame_time > 0.0 {
+            1.0 / self.average_frame_time
+        } else {
+            0.0
+        }
+    }
+
+    /// Lock the window to the given aspect ratio while it is resized by the user.
+    ///
+    /// # Arguments
+    ///
+    /// * `numerator` - The numerator of the aspect ratio, e.g. `16`.
+    /// * `denominator` - The denominator of the aspect ratio, e.g. `9`.
+    ///
+    /// # GLFW Functions
+    ///
+    /// This function is a wrapper around `glfwSetWindowAspectRatio`.
+    pub fn set_aspect_ratio(&mut self, numerator: u32, denominator: u32) {
+        self.window_handle.set_aspect_ratio(numerator, denominator);
+    }
+
+    /// Limit the size of the window's content area in screen coordinates.
+    ///
+    /// # Arguments
+    ///
+    /// * `min` - The minimum size as `(width, height)`, or `None` for no lower limit.
+    /// * `max` - The maximum size as `(width, height)`, or `None` for no upper limit.
+    ///
+    /// # GLFW Functions
+    ///
+    /// This function is a wrapper around `glfwSetWindowSizeLimits`.
+    pub fn set_size_limits(&mut self, min: Option<(u32, u32)>, max: Option<(u32, u32)>) {
+        self.window_handle.set_size_limits(
+            min.map(|(width, _)| width),
+            min.map(|(_, height)| height),
+            max.map(|(width, _)| width),
+            max.map(|(_, height)| height),
+        );
+    }
+
     /// Enable depth testing for this window.
     ///
     /// # OpenGL Functions
     ///
     /// This function is a wrapper around `glEnable(GL_DEPTH_TEST)`.
+    #[deprecated(note = "use `RenderState` or `DepthState::apply` instead")]
     pub fn enable_depth_test(&self) {
-        unsafe {
-            gl::Enable(gl::DEPTH_TEST);
-        }
+        DepthState::default().apply();
     }
 
     /// Enable blending for this window.
@@ -114,11 +220,9 @@ impl Window {
     ///
     /// This function is a wrapper around `glEnable(GL_BLEND)` and
     /// `glBlendFunc(GL_SRC_ALPHA, GL_ONE_MINUS_SRC_ALPHA)`.
+    #[deprecated(note = "use `RenderState` or `BlendState::apply` instead")]
     pub fn enable_blend(&self) {
-        unsafe {
-            gl::Enable(gl::BLEND);
-            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
-        }
+        BlendState::ALPHA.apply();
     }
 
     /// Clear the screen with the given color.
@@ -133,20 +237,106 @@ impl Window {
     /// * `g` - The green component of the color.
     /// * `b` - The blue component of the color.
     /// * `a` - The alpha component of the color.
+    #[deprecated(note = "use `Window::clear_with` and `ClearState` instead")]
     pub fn clear(&self, r: f32, g: f32, b: f32, a: f32) {
-        unsafe {
-            gl::ClearColor(r, g, b, a);
-            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
-        }
+        self.clear_with(&ClearState::color_and_depth([r, g, b, a]));
+    }
+
+    /// Clear the window's framebuffer with the given configuration.
+    ///
+    /// The default framebuffer must be bound, e.g. after rendering into a `Framebuffer`.
+    ///
+    /// # Arguments
+    ///
+    /// * `clear` - The buffers to clear and their values, or `ClearState::LOAD` to keep
+    ///   the previous contents.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `ClearState::apply`.
+    pub fn clear_with(&self, clear: &ClearState) {
+        clear.apply();
     }
 
     /// Process window events and swap the front and back buffers.
     ///
-    /// This must be called every frame to keep the window responsive.
+    /// This must be called every frame to keep the window responsive. It also measures the
+    /// frame time used by `fps` and the FPS counter in the title.
     pub fn update(&mut self) {
         self.process_events();
         self.glfw.poll_events();
         self.window_handle.swap_buffers();
+        self.update_frame_time();
+    }
+
+    /// Like `update`, but block until at least one event arrives instead of polling.
+    ///
+    /// The finished frame is presented before waiting and the waking events are processed
+    /// afterwards, so the input state is current when the caller draws the next frame.
+    ///
+    /// Use this for editor-style tools that only need to redraw in response to input, so the
+    /// application does not spin at full speed while idle.
+    pub fn update_wait(&mut self) {
+        self.window_handle.swap_buffers();
+        self.glfw.wait_events();
+        self.process_events();
+        self.update_frame_time();
+    }
+
+    /// Like `update_wait`, but stop waiting after the given timeout even if no event arrived.
+    ///
+    /// Useful when something has to be redrawn periodically, such as a blinking cursor.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - The maximum time to wait for an event, in seconds.
+    pub fn update_wait_timeout(&mut self, timeout: f64) {
+        self.window_handle.swap_buffers();
+        self.glfw.wait_events_timeout(timeout);
+        self.process_events();
+        self.update_frame_time();
+    }
+
+    /// Run the main loop, calling `frame` and then `update` until the window should close.
+    ///
+    /// Loops written with `run` also work with the `web` backend, where the browser calls
+    /// the frame function on every animation frame instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `frame` - Draws a frame.
+    pub fn run<F: FnMut(&mut Window) + 'static>(mut self, mut frame: F) {
+        while !self.should_close() {
+            frame(&mut self);
+            self.update();
+        }
+    }
+
+    /// Measure the time since the last frame and refresh the FPS counter in the title.
+    fn update_frame_time(&mut self) {
+        let now = self.glfw.get_time();
+        let frame_time = now - self.last_frame_time;
+        self.last_frame_time = now;
+        self.average_frame_time = if self.average_frame_time > 0.0 {
+            self.average_frame_time + (frame_time - self.average_frame_time) * FPS_SMOOTHING
+        } else {
+            frame_time
+        };
+
+        if self.show_fps && now - self.last_title_update >= FPS_TITLE_INTERVAL {
+            self.last_title_update = now;
+            self.refresh_title();
+        }
+    }
+
+    /// Apply the stored title to the window, with the FPS counter if enabled.
+    fn refresh_title(&mut self) {
+        if self.show_fps {
+            let title = format!("{} - {:.0} FPS", self.title, self.fps());
+            self.window_handle.set_title(&title);
+        } else {
+            self.window_handle.set_title(&self.title);
+        }
     }
 
     /// Process window events and update the window state accordingly.
@@ -155,9 +345,12 @@ impl Window {
     ///
     /// * `FramebufferSize`: Update the OpenGL viewport to match the new window dimensions.
     /// * `Key` with the escape key: Mark the window as needing to close.
+    /// * `Key` with the RenderDoc capture key (with the `renderdoc` feature): Capture the next frame.
     ///
     /// This function also calls `input::process_event` to allow for input to be handled by the user.
+    /// Per-frame input state, such as triggered shortcuts, is cleared first.
     fn process_events(&mut self) {
+        input::begin_frame();
         for (_, event) in glfw::flush_messages(&self.events) {
             input::process_event(&event);
             match event {
@@ -168,6 +361,10 @@ impl Window {
                 glfw::WindowEvent::Key(Key::Escape, _, Action::Press, _) => {
                     self.window_handle.set_should_close(true)
                 }
+                #[cfg(feature = "renderdoc")]
+                glfw::WindowEvent::Key(key, _, Action::Press, _) => {
+                    crate::debug::handle_key_press(key)
+                }
                 _ => {}
             }
         }
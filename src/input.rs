@@ -5,7 +5,11 @@
 //!
 //! ## Features
 //! - Track pressed keys and mouse buttons.
-//! - Get the current mouse position.
+//! - Measure how long keys have been held.
+//! - Register keyboard shortcuts such as `Ctrl+S`.
+//! - Read typed text, including text composed with an input method.
+//! - Show key names for the current keyboard layout and bind keys by their physical position.
+//! - Get the current mouse position, also in framebuffer pixels and normalized device coordinates.
 //! - Detect scroll events.
 //! - Reset the input state.
 //!
@@ -32,10 +36,24 @@
 //! }
 //! ```
 
+#[cfg(all(feature = "web", not(feature = "native")))]
+use crate::graphics::web::events::{Action, WindowEvent};
+#[cfg(all(feature = "web", not(feature = "native")))]
+pub use crate::graphics::web::events::{Key, Modifiers, MouseButton, Scancode};
+use crate::graphics::window::Window;
+#[cfg(feature = "native")]
 use glfw::{Action, WindowEvent};
-pub use glfw::{Key, MouseButton};
+#[cfg(feature = "native")]
+pub use glfw::{Key, Modifiers, MouseButton, Scancode};
 use lazy_static::lazy_static;
+use std::collections::HashSet;
+use std::fmt;
 use std::sync::Mutex;
+#[cfg(feature = "native")]
+use std::time::{Duration, Instant};
+// `std::time::Instant` is not available in the browser
+#[cfg(all(feature = "web", not(feature = "native")))]
+use web_time::{Duration, Instant};
 
 // Static variables to store input state
 lazy_static! {
@@ -43,9 +61,78 @@ lazy_static! {
     static ref MOUSE_BUTTONS_PRESSED: Mutex<[bool; 8]> = Mutex::new([false; 8]); // 8 кнопок мыши
     static ref MOUSE_POSITION: Mutex<(f64, f64)> = Mutex::new((0.0, 0.0));
     static ref MOUSE_SCROLL: Mutex<(f64, f64)> = Mutex::new((0.0, 0.0));
+    static ref KEY_PRESS_TIMES: Mutex<[Option<Instant>; 350]> = Mutex::new([None; 350]);
+    static ref KEY_LAST_HOLD_DURATIONS: Mutex<[Option<Duration>; 350]> = Mutex::new([None; 350]);
+    static ref SHORTCUTS: Mutex<Vec<Shortcut>> = Mutex::new(Vec::new());
+    static ref TRIGGERED_SHORTCUTS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+    static ref TEXT_INPUT: Mutex<String> = Mutex::new(String::new());
+    static ref SCANCODES_PRESSED: Mutex<HashSet<Scancode>> = Mutex::new(HashSet::new());
+    static ref LAST_KEY_PRESS: Mutex<Option<(Key, Scancode)>> = Mutex::new(None);
 }
 
-/// Processes a `glfw::WindowEvent` to update the input state.
+/// A registered keyboard shortcut.
+struct Shortcut {
+    name: String,
+    modifiers: Modifiers,
+    keys: Vec<Key>,
+}
+
+/// Returns the modifier a key belongs to, treating left and right variants the same.
+fn modifier_of(key: Key) -> Option<Modifiers> {
+    match key {
+        Key::LeftControl | Key::RightControl => Some(Modifiers::Control),
+        Key::LeftShift | Key::RightShift => Some(Modifiers::Shift),
+        Key::LeftAlt | Key::RightAlt => Some(Modifiers::Alt),
+        Key::LeftSuper | Key::RightSuper => Some(Modifiers::Super),
+        _ => None,
+    }
+}
+
+/// Returns the modifiers that are currently held.
+fn held_modifiers() -> Modifiers {
+    let keys = KEYS_PRESSED.lock().unwrap();
+    let mut modifiers = Modifiers::empty();
+    for key in [
+        Key::LeftControl,
+        Key::RightControl,
+        Key::LeftShift,
+        Key::RightShift,
+        Key::LeftAlt,
+        Key::RightAlt,
+        Key::LeftSuper,
+        Key::RightSuper,
+    ] {
+        if keys[key as usize] {
+            if let Some(modifier) = modifier_of(key) {
+                modifiers.insert(modifier);
+            }
+        }
+    }
+    modifiers
+}
+
+/// Records the shortcuts completed by pressing `key`.
+fn trigger_shortcuts(key: Key) {
+    let modifiers = held_modifiers();
+    let shortcuts = SHORTCUTS.lock().unwrap();
+    let mut triggered = TRIGGERED_SHORTCUTS.lock().unwrap();
+    for shortcut in shortcuts.iter() {
+        if shortcut.modifiers != modifiers {
+            continue;
+        }
+        let completed = if shortcut.keys.is_empty() {
+            modifier_of(key).is_some()
+        } else {
+            shortcut.keys.contains(&key) && shortcut.keys.iter().all(|&k| is_key_pressed(k))
+        };
+        if completed {
+            triggered.push(shortcut.name.clone());
+        }
+    }
+}
+
+/// Processes a `glfw::WindowEvent`, or a `web::events::WindowEvent` with the `web` backend,
+/// to update the input state.
 ///
 /// This function should be called for every event in your event loop.
 ///
@@ -60,11 +147,19 @@ lazy_static! {
 /// ```
 pub fn process_event(event: &WindowEvent) {
     match event {
-        WindowEvent::Key(key, _, Action::Press, _) => {
+        WindowEvent::Key(key, scancode, Action::Press, _) => {
+            SCANCODES_PRESSED.lock().unwrap().insert(*scancode);
+            *LAST_KEY_PRESS.lock().unwrap() = Some((*key, *scancode));
             KEYS_PRESSED.lock().unwrap()[*key as usize] = true;
+            KEY_PRESS_TIMES.lock().unwrap()[*key as usize] = Some(Instant::now());
+            trigger_shortcuts(*key);
         }
-        WindowEvent::Key(key, _, Action::Release, _) => {
+        WindowEvent::Key(key, scancode, Action::Release, _) => {
+            SCANCODES_PRESSED.lock().unwrap().remove(scancode);
             KEYS_PRESSED.lock().unwrap()[*key as usize] = false;
+            if let Some(pressed_at) = KEY_PRESS_TIMES.lock().unwrap()[*key as usize].take() {
+                KEY_LAST_HOLD_DURATIONS.lock().unwrap()[*key as usize] = Some(pressed_at.elapsed());
+            }
         }
         WindowEvent::MouseButton(button, Action::Press, _) => {
             MOUSE_BUTTONS_PRESSED.lock().unwrap()[*button as usize] = true;
@@ -78,6 +173,9 @@ pub fn process_event(event: &WindowEvent) {
         WindowEvent::Scroll(xoffset, yoffset) => {
             *MOUSE_SCROLL.lock().unwrap() = (*xoffset, *yoffset);
         }
+        WindowEvent::Char(character) => {
+            TEXT_INPUT.lock().unwrap().push(*character);
+        }
         _ => {}
     }
 }
@@ -102,6 +200,309 @@ pub fn is_key_pressed(key: Key) -> bool {
     KEYS_PRESSED.lock().unwrap()[key as usize]
 }
 
+/// Returns how long a key has been held down.
+///
+/// # Arguments
+/// * `key` - The key to check (e.g., `Key::Space`).
+///
+/// # Returns
+/// The time since the key was pressed, or `Duration::ZERO` if it is not pressed.
+///
+/// # Example
+/// ```rust
+/// use glwfr::input::{self, Key};
+///
+/// // Charge a jump while space is held, up to one second
+/// let charge = input::key_held_duration(Key::Space).as_secs_f32().min(1.0);
+/// ```
+pub fn key_held_duration(key: Key) -> Duration {
+    KEY_PRESS_TIMES.lock().unwrap()[key as usize]
+        .map(|pressed_at| pressed_at.elapsed())
+        .unwrap_or(Duration::ZERO)
+}
+
+/// Returns how long a key was held the last time it was released.
+///
+/// Useful for telling taps from holds once the key is released.
+///
+/// # Arguments
+/// * `key` - The key to check (e.g., `Key::E`).
+///
+/// # Returns
+/// The duration of the last completed press, or `None` if the key has not been released yet.
+///
+/// # Example
+/// ```rust
+/// use glwfr::input::{self, Key};
+/// use std::time::Duration;
+///
+/// if let Some(held) = input::last_key_hold_duration(Key::E) {
+///     let is_tap = held < Duration::from_millis(200);
+/// }
+/// ```
+pub fn last_key_hold_duration(key: Key) -> Option<Duration> {
+    KEY_LAST_HOLD_DURATIONS.lock().unwrap()[key as usize]
+}
+
+/// Registers a keyboard shortcut under the given name.
+///
+/// Modifier keys match either their left or right variant, and a shortcut only triggers if
+/// exactly its modifiers are held, so `Ctrl+S` does not trigger on `Ctrl+Shift+S`. The
+/// shortcut triggers when the last of its keys is pressed.
+///
+/// # Arguments
+/// * `keys` - The keys of the shortcut, including modifiers (e.g., `&[Key::LeftControl, Key::S]`).
+/// * `name` - The name reported by `triggered_shortcuts`.
+///
+/// # Example
+/// ```rust
+/// use glwfr::input::{self, Key};
+///
+/// input::register_shortcut(&[Key::LeftControl, Key::S], "save");
+/// input::register_shortcut(&[Key::LeftControl, Key::LeftShift, Key::S], "save_as");
+/// ```
+pub fn register_shortcut(keys: &[Key], name: &str) {
+    let mut modifiers = Modifiers::empty();
+    let mut other_keys = Vec::new();
+    for &key in keys {
+        match modifier_of(key) {
+            Some(modifier) => modifiers.insert(modifier),
+            None => other_keys.push(key),
+        }
+    }
+    SHORTCUTS.lock().unwrap().push(Shortcut {
+        name: name.to_string(),
+        modifiers,
+        keys: other_keys,
+    });
+}
+
+/// Removes all shortcuts registered under the given name.
+pub fn unregister_shortcut(name: &str) {
+    SHORTCUTS
+        .lock()
+        .unwrap()
+        .retain(|shortcut| shortcut.name != name);
+}
+
+/// Returns the names of the shortcuts triggered during the current frame.
+///
+/// # Example
+/// ```rust
+/// use glwfr::input;
+///
+/// for shortcut in input::triggered_shortcuts() {
+///     match shortcut.as_str() {
+///         "save" => println!("Saving..."),
+///         _ => {}
+///     }
+/// }
+/// ```
+pub fn triggered_shortcuts() -> Vec<String> {
+    TRIGGERED_SHORTCUTS.lock().unwrap().clone()
+}
+
+/// Checks if the shortcut with the given name was triggered during the current frame.
+pub fn is_shortcut_triggered(name: &str) -> bool {
+    TRIGGERED_SHORTCUTS
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|shortcut| shortcut == name)
+}
+
+/// Clears the per-frame input state. Called by the window before processing new events.
+pub(crate) fn begin_frame() {
+    TRIGGERED_SHORTCUTS.lock().unwrap().clear();
+    TEXT_INPUT.lock().unwrap().clear();
+    *LAST_KEY_PRESS.lock().unwrap() = None;
+}
+
+/// Returns the text typed since the last `Window::update`.
+///
+/// Unlike key presses, the text follows the keyboard layout and includes characters
+/// composed with dead keys or an input method editor (IME), such as Chinese, Japanese and
+/// Korean text. Composed text arrives once the user commits it; the intermediate preedit
+/// string is shown by the operating system's IME window, since GLFW does not report it.
+/// Control keys such as backspace and enter are not included and should be read as keys.
+///
+/// # Returns
+/// The committed text, or an empty string if nothing was typed.
+///
+/// # Example
+/// ```rust
+/// use glwfr::input;
+///
+/// let mut text_box = String::new();
+/// // Once per frame, after `Window::update`
+/// text_box.push_str(&input::text_input());
+/// ```
+pub fn text_input() -> String {
+    TEXT_INPUT.lock().unwrap().clone()
+}
+
+/// Checks if the key with the given platform scancode is currently pressed.
+///
+/// Scancodes identify the physical key regardless of the keyboard layout, see `KeyBinding`.
+///
+/// # Arguments
+/// * `scancode` - The scancode to check, e.g. from `key_scancode`.
+///
+/// # Returns
+/// `true` if the key is pressed, `false` otherwise.
+pub fn is_scancode_pressed(scancode: Scancode) -> bool {
+    SCANCODES_PRESSED.lock().unwrap().contains(&scancode)
+}
+
+/// Returns the key pressed last since the previous `Window::update`, with its scancode.
+///
+/// Rebinding menus use this to capture the next key the user presses.
+///
+/// # Returns
+/// The key and scancode, or `None` if no key was pressed.
+pub fn last_key_press() -> Option<(Key, Scancode)> {
+    *LAST_KEY_PRESS.lock().unwrap()
+}
+
+/// Returns the platform scancode of a key, or `None` if the key has no scancode on this
+/// platform.
+///
+/// In the browser, the scancode is the key itself, see `web::events`.
+///
+/// # Arguments
+/// * `key` - The key, named after its position on a US keyboard layout.
+pub fn key_scancode(key: Key) -> Option<Scancode> {
+    #[cfg(feature = "native")]
+    return glfw::get_key_scancode(Some(key));
+    #[cfg(not(feature = "native"))]
+    return Some(key as Scancode);
+}
+
+/// Returns the name of a key to show to the user, following the current keyboard layout.
+///
+/// Printable keys are named after the character they produce, e.g. `"Z"` for `Key::Y` on a
+/// German layout or `Key::W` on AZERTY. Other keys get a fixed English name, such as
+/// `"Left Shift"` or `"F5"`. Browsers do not report the layout, so there all keys get their
+/// fixed name.
+///
+/// # Arguments
+/// * `key` - The key to name.
+///
+/// # Example
+/// ```rust
+/// use glwfr::input::{self, Key};
+///
+/// println!("Press {} to jump", input::key_display_name(Key::Space));
+/// ```
+pub fn key_display_name(key: Key) -> String {
+    #[cfg(feature = "native")]
+    if let Some(name) = glfw::get_key_name(Some(key), None).filter(|name| !name.is_empty()) {
+        return name.to_uppercase();
+    }
+    fixed_key_name(key)
+}
+
+/// Returns the name of the key with the given scancode to show to the user, following the
+/// current keyboard layout, or `None` if the platform cannot name it.
+///
+/// # Arguments
+/// * `scancode` - The scancode of the key.
+pub fn scancode_display_name(scancode: Scancode) -> Option<String> {
+    #[cfg(feature = "native")]
+    return glfw::get_key_name(None, Some(scancode))
+        .filter(|name| !name.is_empty())
+        .map(|name| name.to_uppercase());
+    #[cfg(not(feature = "native"))]
+    return Key::from_scancode(scancode).map(fixed_key_name);
+}
+
+/// Returns the layout-independent name of a key.
+fn fixed_key_name(key: Key) -> String {
+    let name = match key {
+        Key::Space => "Space",
+        Key::Escape => "Escape",
+        Key::Enter => "Enter",
+        Key::Tab => "Tab",
+        Key::Backspace => "Backspace",
+        Key::Insert => "Insert",
+        Key::Delete => "Delete",
+        Key::Right => "Right",
+        Key::Left => "Left",
+        Key::Down => "Down",
+        Key::Up => "Up",
+        Key::PageUp => "Page Up",
+        Key::PageDown => "Page Down",
+        Key::Home => "Home",
+        Key::End => "End",
+        Key::CapsLock => "Caps Lock",
+        Key::ScrollLock => "Scroll Lock",
+        Key::NumLock => "Num Lock",
+        Key::PrintScreen => "Print Screen",
+        Key::Pause => "Pause",
+        Key::KpEnter => "Keypad Enter",
+        Key::LeftShift => "Left Shift",
+        Key::LeftControl => "Left Ctrl",
+        Key::LeftAlt => "Left Alt",
+        Key::LeftSuper => "Left Super",
+        Key::RightShift => "Right Shift",
+        Key::RightControl => "Right Ctrl",
+        Key::RightAlt => "Right Alt",
+        Key::RightSuper => "Right Super",
+        Key::Menu => "Menu",
+        // Function keys and the rest are named after the enum variant, e.g. "F5"
+        _ => return format!("{:?}", key),
+    };
+    name.to_string()
+}
+
+/// A key binding stored either by key or by physical key position.
+///
+/// `Key` values are named after the US layout, so `Key::W` is the key printing "W" there but
+/// "Z" on AZERTY keyboards. Movement bindings such as WASD should be stored by scancode, so
+/// they stay at the same physical position on every layout; shortcuts that refer to a
+/// letter, such as `Ctrl+S`, are better stored by key. Scancodes are platform-specific, so
+/// scancode bindings should only be saved for the machine they were captured on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyBinding {
+    /// A key, following the layout.
+    Key(Key),
+    /// The key at a physical position.
+    Scancode(Scancode),
+}
+
+impl KeyBinding {
+    /// Returns a binding to the physical position of a key on the US layout, falling back
+    /// to the key if it has no scancode.
+    pub fn physical(key: Key) -> Self {
+        key_scancode(key).map_or(KeyBinding::Key(key), KeyBinding::Scancode)
+    }
+
+    /// Checks if the bound key is currently pressed.
+    pub fn is_pressed(&self) -> bool {
+        match self {
+            KeyBinding::Key(key) => is_key_pressed(*key),
+            KeyBinding::Scancode(scancode) => is_scancode_pressed(*scancode),
+        }
+    }
+
+    /// Returns the name of the bound key to show to the user, following the current
+    /// keyboard layout.
+    pub fn display_name(&self) -> String {
+        match self {
+            KeyBinding::Key(key) => key_display_name(*key),
+            KeyBinding::Scancode(scancode) => {
+                scancode_display_name(*scancode).unwrap_or_else(|| format!("Scancode {}", scancode))
+            }
+        }
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.display_name())
+    }
+}
+
 /// Checks if a specific mouse button is currently pressed.
 ///
 /// # Arguments
@@ -138,6 +539,54 @@ pub fn get_mouse_position() -> (f64, f64) {
     *MOUSE_POSITION.lock().unwrap()
 }
 
+/// Returns the current mouse position in framebuffer pixels, e.g. for reading back the
+/// pixel under the cursor.
+///
+/// Mouse positions are reported in screen coordinates, which differ from pixels on high-DPI
+/// displays.
+///
+/// # Arguments
+/// * `window` - The window the mouse position is relative to.
+///
+/// # Returns
+/// A tuple `(x, y)` in pixels, measured from the top left corner.
+pub fn mouse_framebuffer_position(window: &Window) -> (f64, f64) {
+    let (x, y) = get_mouse_position();
+    let (width, height) = window.size();
+    let (framebuffer_width, framebuffer_height) = window.framebuffer_size();
+    (
+        x * framebuffer_width as f64 / width.max(1) as f64,
+        y * framebuffer_height as f64 / height.max(1) as f64,
+    )
+}
+
+/// Returns the current mouse position in normalized device coordinates.
+///
+/// # Arguments
+/// * `window` - The window the mouse position is relative to.
+///
+/// # Returns
+/// A tuple `(x, y)` from `-1.0` at the left and bottom edges to `1.0` at the right and top
+/// edges, matching OpenGL's clip space.
+///
+/// # Example
+/// ```rust
+/// use glwfr::input;
+///
+/// let (x, y) = input::mouse_ndc(&window);
+/// if x > 0.0 {
+///     println!("The mouse is on the right half of the window");
+/// }
+/// ```
+pub fn mouse_ndc(window: &Window) -> (f32, f32) {
+    let (x, y) = get_mouse_position();
+    let (width, height) = window.size();
+    (
+        (2.0 * x / width.max(1) as f64 - 1.0) as f32,
+        (1.0 - 2.0 * y / height.max(1) as f64) as f32,
+    )
+}
+
 /// Returns the current mouse scroll offset.
 ///
 /// # Returns
@@ -164,7 +613,13 @@ pub fn get_mouse_scroll() -> (f64, f64) {
 /// ```
 pub fn reset_state() {
     KEYS_PRESSED.lock().unwrap().fill(false);
+    KEY_PRESS_TIMES.lock().unwrap().fill(None);
+    KEY_LAST_HOLD_DURATIONS.lock().unwrap().fill(None);
+    TRIGGERED_SHORTCUTS.lock().unwrap().clear();
     MOUSE_BUTTONS_PRESSED.lock().unwrap().fill(false);
     *MOUSE_POSITION.lock().unwrap() = (0.0, 0.0);
+    TEXT_INPUT.lock().unwrap().clear();
+    SCANCODES_PRESSED.lock().unwrap().clear();
+    *LAST_KEY_PRESS.lock().unwrap() = None;
     *MOUSE_SCROLL.lock().unwrap() = (0.0, 0.0);
 }
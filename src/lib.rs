@@ -7,7 +7,25 @@
 //! - **Graphics**: Window management, OpenGL context creation, texture loading, shader management.
 //! - **Scene Management**: Cameras, lights, objects, and transformations.
 //! - **Input Handling**: Keyboard and mouse input.
+//! - **Math**: Rays, planes, bounding volumes and intersection tests.
 //! - **Audio**: Sound loading and playback.
+//! - **Debugging**: RenderDoc capture triggering (`renderdoc` feature).
+//!
+//! Windows, input and audio are built on GLFW and rodio and need the `native` feature, which
+//! is enabled by default. Without it, only the platform-independent rendering, scene and math
+//! modules are built, for use with an OpenGL context created by other means.
+//!
+//! The `web` feature adds a browser backend for `wasm32-unknown-unknown`: with `native`
+//! disabled, `Window` draws into a canvas with WebGL2, `input` reads DOM events and
+//! `AudioSystem` plays sounds with Web Audio. The `gl` crate's functions are loaded with
+//! functions forwarding to WebGL2 (see `graphics::web::webgl`), so the renderer is shared;
+//! shaders have to stay within the GLSL 3.30 features GLSL ES 3.00 has. Files are not
+//! available in the browser, so assets are embedded or loaded from memory, and features
+//! that need threads, such as level streaming, do not work there. Build with:
+//!
+//! ```text
+//! cargo build --target wasm32-unknown-unknown --no-default-features --features web
+//! ```
 //!
 //! ## Usage
 //! Add the following to your `Cargo.toml`:
@@ -19,7 +37,7 @@
 //!
 //! ## Example
 //! ```rust
-//! use glwfr::graphics::{window::Window, gl_wrapper::*};
+//! use glwfr::graphics::{window::Window, gl_wrapper::*, render_state::{ClearState, RenderState}};
 //! use glwfr::audio::*;
 //! use glwfr::gl;
 //! use glwfr::cgmath::{Matrix4, Deg, Vector3, Point3, perspective};
@@ -32,7 +50,7 @@
 //!     window.init_gl()?;
 //!
 //!     // Enable depth testing
-//!     window.enable_depth_test();
+//!     RenderState::default().apply();
 //!
 //! 	// Load sound
 //! 	let mut audio_system = AudioSystem::new()?;
@@ -41,7 +59,7 @@
 //!     // Main loop
 //!     while !window.should_close() {
 //!         // Clear the screen
-//!         window.clear(0.0, 0.0, 0.0, 1.0);
+//!         window.clear_with(&ClearState::default());
 //!
 //!         // Handle input
 //!         if window.is_key_pressed(glwfr::input::Key::Space) {
@@ -59,9 +77,14 @@
 
 pub extern crate cgmath;
 pub extern crate gl;
+#[cfg(any(feature = "native", feature = "web"))]
 pub mod audio;
 pub mod custom_errors;
+#[cfg(feature = "renderdoc")]
+pub mod debug;
 pub mod graphics;
+#[cfg(any(feature = "native", feature = "web"))]
 pub mod input;
 pub mod logger;
+pub mod math;
 pub mod scene;
@@ -0,0 +1,138 @@
+//! # AABB Module
+
+use crate::math::Sphere;
+use cgmath::*;
+
+/// An axis-aligned bounding box.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    /// The corner with the smallest coordinates.
+    pub min: Point3<f32>,
+    /// The corner with the largest coordinates.
+    pub max: Point3<f32>,
+}
+
+impl Aabb {
+    /// Creates a box from two opposite corners, in any order.
+    pub fn new(a: Point3<f32>, b: Point3<f32>) -> Self {
+        Self {
+            min: Point3::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z)),
+            max: Point3::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z)),
+        }
+    }
+
+    /// Creates a box from its center and half the size along each axis.
+    pub fn from_center(center: Point3<f32>, half_extents: Vector3<f32>) -> Self {
+        Self::new(center - half_extents, center + half_extents)
+    }
+
+    /// Returns the smallest box containing all points, or `None` if there are none.
+    pub fn from_points(points: impl IntoIterator<Item = Point3<f32>>) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+        Some(points.fold(Self::new(first, first), |aabb, point| {
+            aabb.expanded_to(point)
+        }))
+    }
+
+    /// Returns the center of the box.
+    pub fn center(&self) -> Point3<f32> {
+        self.min.midpoint(self.max)
+    }
+
+    /// Returns the size of the box along each axis.
+    pub fn size(&self) -> Vector3<f32> {
+        self.max - self.min
+    }
+
+    /// Returns half the size of the box along each axis.
+    pub fn half_extents(&self) -> Vector3<f32> {
+        self.size() * 0.5
+    }
+
+    /// Returns the eight corners of the box.
+    pub fn corners(&self) -> [Point3<f32>; 8] {
+        let (a, b) = (self.min, self.max);
+        [
+            Point3::new(a.x, a.y, a.z),
+            Point3::new(b.x, a.y, a.z),
+            Point3::new(a.x, b.y, a.z),
+            Point3::new(b.x, b.y, a.z),
+            Point3::new(a.x, a.y, b.z),
+            Point3::new(b.x, a.y, b.z),
+            Point3::new(a.x, b.y, b.z),
+            Point3::new(b.x, b.y, b.z),
+        ]
+    }
+
+    /// Returns the box grown to contain the given point.
+    pub fn expanded_to(&self, point: Point3<f32>) -> Self {
+        Self {
+            min: Point3::new(
+                self.min.x.min(point.x),
+                self.min.y.min(point.y),
+                self.min.z.min(point.z),
+            ),
+            max: Point3::new(
+                self.max.x.max(point.x),
+                self.max.y.max(point.y),
+                self.max.z.max(point.z),
+            ),
+        }
+    }
+
+    /// Returns the smallest box containing both boxes.
+    pub fn union(&self, other: &Aabb) -> Self {
+        self.expanded_to(other.min).expanded_to(other.max)
+    }
+
+    /// Returns the axis-aligned box enclosing this box after a transformation.
+    pub fn transformed(&self, matrix: &Matrix4<f32>) -> Self {
+        let corners = self.corners().map(|corner| matrix.transform_point(corner));
+        Self::from_points(corners).unwrap_or(*self)
+    }
+
+    /// Returns `true` if the point lies inside or on the box.
+    pub fn contains_point(&self, point: Point3<f32>) -> bool {
+        point.x >= self.min.x
+            && point.x <= self.max.x
+            && point.y >= self.min.y
+            && point.y <= self.max.y
+            && point.z >= self.min.z
+            && point.z <= self.max.z
+    }
+
+    /// Returns `true` if the other box lies completely inside this box.
+    pub fn contains_aabb(&self, other: &Aabb) -> bool {
+        self.contains_point(other.min) && self.contains_point(other.max)
+    }
+
+    /// Returns `true` if the boxes overlap or touch.
+    pub fn intersects_aabb(&self, other: &Aabb) -> bool {
+        self.min.x <= other.max.x
+            && self.max.x >= other.min.x
+            && self.min.y <= other.max.y
+            && self.max.y >= other.min.y
+            && self.min.z <= other.max.z
+            && self.max.z >= other.min.z
+    }
+
+    /// Returns `true` if the box and the sphere overlap or touch.
+    pub fn intersects_sphere(&self, sphere: &Sphere) -> bool {
+        sphere.intersects_aabb(self)
+    }
+
+    /// Returns the point on or inside the box closest to the given point.
+    pub fn closest_point(&self, point: Point3<f32>) -> Point3<f32> {
+        Point3::new(
+            point.x.clamp(self.min.x, self.max.x),
+            point.y.clamp(self.min.y, self.max.y),
+            point.z.clamp(self.min.z, self.max.z),
+        )
+    }
+
+    /// Returns the distance from the point to the box, or `0.0` if the point is inside.
+    pub fn distance_to_point(&self, point: Point3<f32>) -> f32 {
+        point.distance(self.closest_point(point))
+    }
+}
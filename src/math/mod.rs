@@ -0,0 +1,47 @@
+//! # Math Module
+//!
+//! This module provides geometric primitives and intersection tests built on cgmath, shared by
+//! picking, culling and collision code.
+//!
+//! ## Submodules
+//! - **aabb**: Axis-aligned bounding boxes.
+//! - **noise**: Perlin, simplex and Worley noise with fBm octaves.
+//! - **plane**: Infinite planes and signed distances.
+//! - **ray**: Rays and ray casts against the other primitives.
+//! - **simplify**: Mesh simplification and level-of-detail generation.
+//! - **sphere**: Bounding spheres.
+//! - **triangle**: Triangles with normals, areas and barycentric coordinates.
+//! - **uv**: Box, planar and spherical texture coordinate generation.
+//!
+//! ## Example
+//! ```rust
+//! use glwfr::math::{Aabb, Ray, Sphere};
+//! use glwfr::cgmath::{Point3, Vector3};
+//!
+//! let ray = Ray::new(Point3::new(0.0, 0.0, 5.0), Vector3::new(0.0, 0.0, -1.0));
+//! let crate_box = Aabb::new(Point3::new(-1.0, -1.0, -1.0), Point3::new(1.0, 1.0, 1.0));
+//!
+//! if let Some(distance) = ray.intersect_aabb(&crate_box) {
+//!     println!("Hit the crate at {:?}", ray.at(distance));
+//! }
+//!
+//! let bounds = Sphere::new(Point3::new(0.0, 0.0, 0.0), 2.0);
+//! assert!(bounds.intersects_aabb(&crate_box));
+//! ```
+
+pub mod aabb;
+pub mod noise;
+pub mod plane;
+pub mod ray;
+pub mod simplify;
+pub mod sphere;
+pub mod triangle;
+pub mod uv;
+
+pub use aabb::*;
+pub use plane::*;
+pub use ray::*;
+pub use simplify::*;
+pub use sphere::*;
+pub use triangle::*;
+pub use uv::*;
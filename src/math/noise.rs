@@ -0,0 +1,484 @@
+//! # Noise Module
+//!
+//! Seeded gradient and cellular noise for terrain, clouds and shader inputs.
+//!
+//! `Perlin` and `Simplex` return values in roughly `[-1, 1]`, `Worley` returns the distance to
+//! the closest feature point in roughly `[0, 1]`. `Fbm` sums several octaves of any of them.
+//! Noise can be sampled on the CPU or baked into a single-channel `Texture`. The seeded
+//! `Xorshift` generator behind the permutation tables is public for other code that needs
+//! cheap, reproducible random numbers.
+//!
+//! ## Example
+//! ```rust
+//! use glwfr::math::noise::{bake_texture, Fbm, Noise, Simplex};
+//!
+//! fn main() -> Result<(), glwfr::custom_errors::Errors> {
+//!     let terrain = Fbm::new(Simplex::new(42), 5);
+//!     let height = terrain.sample2(12.5, 3.0);
+//!
+//!     let clouds = bake_texture(&Fbm::new(Simplex::new(7), 4), 256, 256, 8.0)?;
+//!     Ok(())
+//! }
+//! ```
+
+use crate::custom_errors::Errors;
+use crate::graphics::texture::{Texture, TextureFormat};
+
+/// A noise function that can be sampled in two and three dimensions.
+pub trait Noise {
+    /// Returns the noise value at a 2D position.
+    fn sample2(&self, x: f32, y: f32) -> f32;
+
+    /// Returns the noise value at a 3D position.
+    fn sample3(&self, x: f32, y: f32, z: f32) -> f32;
+
+    /// Returns the approximate `(min, max)` range of the values, used to normalize baked
+    /// textures.
+    fn range(&self) -> (f32, f32) {
+        (-1.0, 1.0)
+    }
+}
+
+/// A small seeded xorshift64 pseudo-random generator, used for permutation tables, shuffles
+/// and scattering particles. It is fast and reproducible, but not suitable for cryptography.
+#[derive(Debug, Clone)]
+pub struct Xorshift {
+    state: u64,
+}
+
+impl Xorshift {
+    /// Creates a generator with the given seed. Every seed, including zero, gives a valid
+    /// sequence.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: seed << 1 | 1,
+        }
+    }
+
+    /// Returns the next value of the sequence.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    /// Returns a value in `0..bound`. `bound` must not be zero.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+
+    /// Returns a value in `[0, 1)`.
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+/// Builds a seeded, doubled permutation table shared by `Perlin` and `Simplex`.
+fn permutation(seed: u32) -> [u8; 512] {
+    let mut table = [0u8; 256];
+    for (i, value) in table.iter_mut().enumerate() {
+        *value = i as u8;
+    }
+    // Fisher-Yates shuffle
+    let mut random = Xorshift::new(seed as u64);
+    for i in (1..256).rev() {
+        table.swap(i, random.below(i as u64 + 1) as usize);
+    }
+    let mut doubled = [0u8; 512];
+    for (i, value) in doubled.iter_mut().enumerate() {
+        *value = table[i & 255];
+    }
+    doubled
+}
+
+/// Ken Perlin's improved gradient noise.
+#[derive(Clone)]
+pub struct Perlin {
+    perm: [u8; 512],
+}
+
+impl Perlin {
+    /// Creates Perlin noise with the given seed.
+    pub fn new(seed: u32) -> Self {
+        Self {
+            perm: permutation(seed),
+        }
+    }
+
+    fn fade(t: f32) -> f32 {
+        t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+    }
+
+    fn lerp(t: f32, a: f32, b: f32) -> f32 {
+        a + t * (b - a)
+    }
+
+    fn grad(hash: u8, x: f32, y: f32, z: f32) -> f32 {
+        let h = hash & 15;
+        let u = if h < 8 { x } else { y };
+        let v = match h {
+            0..=3 => y,
+            12 | 14 => x,
+            _ => z,
+        };
+        (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+    }
+}
+
+impl Noise for Perlin {
+    fn sample2(&self, x: f32, y: f32) -> f32 {
+        self.sample3(x, y, 0.0)
+    }
+
+    fn sample3(&self, x: f32, y: f32, z: f32) -> f32 {
+        let p = &self.perm;
+        let (xi, yi, zi) = (
+            (x.floor() as i32 & 255) as usize,
+            (y.floor() as i32 & 255) as usize,
+            (z.floor() as i32 & 255) as usize,
+        );
+        let (x, y, z) = (x - x.floor(), y - y.floor(), z - z.floor());
+        let (u, v, w) = (Self::fade(x), Self::fade(y), Self::fade(z));
+
+        let a = p[xi] as usize + yi;
+        let (aa, ab) = (p[a] as usize + zi, p[a + 1] as usize + zi);
+        let b = p[xi + 1] as usize + yi;
+        let (ba, bb) = (p[b] as usize + zi, p[b + 1] as usize + zi);
+
+        Self::lerp(
+            w,
+            Self::lerp(
+                v,
+                Self::lerp(
+                    u,
+                    Self::grad(p[aa], x, y, z),
+                    Self::grad(p[ba], x - 1.0, y, z),
+                ),
+                Self::lerp(
+                    u,
+                    Self::grad(p[ab], x, y - 1.0, z),
+                    Self::grad(p[bb], x - 1.0, y - 1.0, z),
+                ),
+            ),
+            Self::lerp(
+                v,
+                Self::lerp(
+                    u,
+                    Self::grad(p[aa + 1], x, y, z - 1.0),
+                    Self::grad(p[ba + 1], x - 1.0, y, z - 1.0),
+                ),
+                Self::lerp(
+                    u,
+                    Self::grad(p[ab + 1], x, y - 1.0, z - 1.0),
+                    Self::grad(p[bb + 1], x - 1.0, y - 1.0, z - 1.0),
+                ),
+            ),
+        )
+    }
+}
+
+/// The gradient directions used by `Simplex`.
+const GRAD3: [[f32; 3]; 12] = [
+    [1.0, 1.0, 0.0],
+    [-1.0, 1.0, 0.0],
+    [1.0, -1.0, 0.0],
+    [-1.0, -1.0, 0.0],
+    [1.0, 0.0, 1.0],
+    [-1.0, 0.0, 1.0],
+    [1.0, 0.0, -1.0],
+    [-1.0, 0.0, -1.0],
+    [0.0, 1.0, 1.0],
+    [0.0, -1.0, 1.0],
+    [0.0, 1.0, -1.0],
+    [0.0, -1.0, -1.0],
+];
+
+/// Simplex noise, with fewer directional artifacts than `Perlin` and cheaper in 3D.
+#[derive(Clone)]
+pub struct Simplex {
+    perm: [u8; 512],
+}
+
+impl Simplex {
+    /// Creates simplex noise with the given seed.
+    pub fn new(seed: u32) -> Self {
+        Self {
+            perm: permutation(seed),
+        }
+    }
+
+    /// Returns the contribution of one simplex corner.
+    fn corner(&self, gradient: usize, offset: [f32; 3], radius: f32) -> f32 {
+        let t = radius - offset[0] * offset[0] - offset[1] * offset[1] - offset[2] * offset[2];
+        if t < 0.0 {
+            return 0.0;
+        }
+        let g = GRAD3[gradient % 12];
+        let t2 = t * t;
+        t2 * t2 * (g[0] * offset[0] + g[1] * offset[1] + g[2] * offset[2])
+    }
+}
+
+impl Noise for Simplex {
+    fn sample2(&self, x: f32, y: f32) -> f32 {
+        const F2: f32 = 0.366_025_42; // (sqrt(3) - 1) / 2
+        const G2: f32 = 0.211_324_87; // (3 - sqrt(3)) / 6
+        let p = &self.perm;
+
+        let s = (x + y) * F2;
+        let (i, j) = ((x + s).floor(), (y + s).floor());
+        let t = (i + j) * G2;
+        let (x0, y0) = (x - (i - t), y - (j - t));
+        let (i1, j1) = if x0 > y0 { (1, 0) } else { (0, 1) };
+        let (x1, y1) = (x0 - i1 as f32 + G2, y0 - j1 as f32 + G2);
+        let (x2, y2) = (x0 - 1.0 + 2.0 * G2, y0 - 1.0 + 2.0 * G2);
+
+        let (ii, jj) = ((i as i32 & 255) as usize, (j as i32 & 255) as usize);
+        let g0 = p[ii + p[jj] as usize] as usize;
+        let g1 = p[ii + i1 + p[jj + j1] as usize] as usize;
+        let g2 = p[ii + 1 + p[jj + 1] as usize] as usize;
+
+        70.0 * (self.corner(g0, [x0, y0, 0.0], 0.5)
+            + self.corner(g1, [x1, y1, 0.0], 0.5)
+            + self.corner(g2, [x2, y2, 0.0], 0.5))
+    }
+
+    fn sample3(&self, x: f32, y: f32, z: f32) -> f32 {
+        const F3: f32 = 1.0 / 3.0;
+        const G3: f32 = 1.0 / 6.0;
+        let p = &self.perm;
+
+        let s = (x + y + z) * F3;
+        let (i, j, k) = ((x + s).floor(), (y + s).floor(), (z + s).floor());
+        let t = (i + j + k) * G3;
+        let (x0, y0, z0) = (x - (i - t), y - (j - t), z - (k - t));
+
+        // Find which of the six tetrahedra the point lies in
+        let ((i1, j1, k1), (i2, j2, k2)) = if x0 >= y0 {
+            if y0 >= z0 {
+                ((1, 0, 0), (1, 1, 0))
+            } else if x0 >= z0 {
+                ((1, 0, 0), (1, 0, 1))
+            } else {
+                ((0, 0, 1), (1, 0, 1))
+            }
+        } else if y0 < z0 {
+            ((0, 0, 1), (0, 1, 1))
+        } else if x0 < z0 {
+            ((0, 1, 0), (0, 1, 1))
+        } else {
+            ((0, 1, 0), (1, 1, 0))
+        };
+
+        let offset = |di: usize, dj: usize, dk: usize, g: f32| {
+            [x0 - di as f32 + g, y0 - dj as f32 + g, z0 - dk as f32 + g]
+        };
+        let (ii, jj, kk) = (
+            (i as i32 & 255) as usize,
+            (j as i32 & 255) as usize,
+            (k as i32 & 255) as usize,
+        );
+        let gradient = |di: usize, dj: usize, dk: usize| {
+            p[ii + di + p[jj + dj + p[kk + dk] as usize] as usize] as usize
+        };
+
+        32.0 * (self.corner(gradient(0, 0, 0), offset(0, 0, 0, 0.0), 0.6)
+            + self.corner(gradient(i1, j1, k1), offset(i1, j1, k1, G3), 0.6)
+            + self.corner(gradient(i2, j2, k2), offset(i2, j2, k2, 2.0 * G3), 0.6)
+            + self.corner(gradient(1, 1, 1), offset(1, 1, 1, 3.0 * G3), 0.6))
+    }
+}
+
+/// Cellular noise returning the distance to the closest of randomly scattered feature points,
+/// one per unit cell. Useful for stone, scales and cloud shapes.
+#[derive(Clone)]
+pub struct Worley {
+    seed: u32,
+}
+
+impl Worley {
+    /// Creates Worley noise with the given seed.
+    pub fn new(seed: u32) -> Self {
+        Self { seed }
+    }
+
+    /// Returns a pseudo-random value in `[0, 1)` for a cell and coordinate axis.
+    fn hash(&self, x: i32, y: i32, z: i32, axis: u32) -> f32 {
+        let mut h = (x as u32).wrapping_mul(0x8da6_b343)
+            ^ (y as u32).wrapping_mul(0xd816_3841)
+            ^ (z as u32).wrapping_mul(0xcb1a_b31f)
+            ^ self.seed.wrapping_add(axis).wrapping_mul(0x9e37_79b9);
+        h ^= h >> 16;
+        h = h.wrapping_mul(0x7feb_352d);
+        h ^= h >> 15;
+        h = h.wrapping_mul(0x846c_a68b);
+        h ^= h >> 16;
+        (h >> 8) as f32 / (1u32 << 24) as f32
+    }
+}
+
+impl Noise for Worley {
+    fn sample2(&self, x: f32, y: f32) -> f32 {
+        let (cx, cy) = (x.floor() as i32, y.floor() as i32);
+        let mut closest = f32::MAX;
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                let (ix, iy) = (cx + dx, cy + dy);
+                let px = ix as f32 + self.hash(ix, iy, 0, 0) - x;
+                let py = iy as f32 + self.hash(ix, iy, 0, 1) - y;
+                closest = closest.min(px * px + py * py);
+            }
+        }
+        closest.sqrt()
+    }
+
+    fn sample3(&self, x: f32, y: f32, z: f32) -> f32 {
+        let (cx, cy, cz) = (x.floor() as i32, y.floor() as i32, z.floor() as i32);
+        let mut closest = f32::MAX;
+        for dz in -1..=1 {
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    let (ix, iy, iz) = (cx + dx, cy + dy, cz + dz);
+                    let px = ix as f32 + self.hash(ix, iy, iz, 0) - x;
+                    let py = iy as f32 + self.hash(ix, iy, iz, 1) - y;
+                    let pz = iz as f32 + self.hash(ix, iy, iz, 2) - z;
+                    closest = closest.min(px * px + py * py + pz * pz);
+                }
+            }
+        }
+        closest.sqrt()
+    }
+
+    fn range(&self) -> (f32, f32) {
+        (0.0, 1.0)
+    }
+}
+
+/// Fractal Brownian motion: several octaves of a noise function at increasing frequency and
+/// decreasing amplitude, normalized to the range of the underlying noise.
+#[derive(Clone)]
+pub struct Fbm<N: Noise> {
+    /// The noise function summed for each octave.
+    pub noise: N,
+    /// The number of octaves.
+    pub octaves: u32,
+    /// The frequency multiplier between octaves.
+    pub lacunarity: f32,
+    /// The amplitude multiplier between octaves.
+    pub gain: f32,
+}
+
+impl<N: Noise> Fbm<N> {
+    /// Creates fBm with the given number of octaves, a lacunarity of `2.0` and a gain of `0.5`.
+    pub fn new(noise: N, octaves: u32) -> Self {
+        Self {
+            noise,
+            octaves: octaves.max(1),
+            lacunarity: 2.0,
+            gain: 0.5,
+        }
+    }
+
+    /// Sums the octaves of `sample`, called with the frequency of each octave.
+    fn accumulate(&self, sample: impl Fn(f32) -> f32) -> f32 {
+        let mut sum = 0.0;
+        let mut amplitude = 1.0;
+        let mut frequency = 1.0;
+        let mut total_amplitude = 0.0;
+        for _ in 0..self.octaves {
+            sum += sample(frequency) * amplitude;
+            total_amplitude += amplitude;
+            amplitude *= self.gain;
+            frequency *= self.lacunarity;
+        }
+        sum / total_amplitude
+    }
+}
+
+impl<N: Noise> Noise for Fbm<N> {
+    fn sample2(&self, x: f32, y: f32) -> f32 {
+        self.accumulate(|f| self.noise.sample2(x * f, y * f))
+    }
+
+    fn sample3(&self, x: f32, y: f32, z: f32) -> f32 {
+        self.accumulate(|f| self.noise.sample3(x * f, y * f, z * f))
+    }
+
+    fn range(&self) -> (f32, f32) {
+        self.noise.range()
+    }
+}
+
+/// Samples noise on a 2D grid, row by row.
+///
+/// # Arguments
+///
+/// * `noise` - The noise function to sample.
+/// * `width` - The number of samples per row.
+/// * `height` - The number of rows.
+/// * `frequency` - The number of noise units covered by the whole grid.
+pub fn sample_grid(noise: &impl Noise, width: u32, height: u32, frequency: f32) -> Vec<f32> {
+    let scale = frequency / width.max(height).max(1) as f32;
+    (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .map(|(x, y)| noise.sample2(x as f32 * scale, y as f32 * scale))
+        .collect()
+}
+
+/// Samples noise on a 3D grid, slice by slice, e.g. for uploading a volume texture.
+///
+/// # Arguments
+///
+/// * `noise` - The noise function to sample.
+/// * `width` - The number of samples per row.
+/// * `height` - The number of rows per slice.
+/// * `depth` - The number of slices.
+/// * `frequency` - The number of noise units covered by the whole grid.
+pub fn sample_volume(
+    noise: &impl Noise,
+    width: u32,
+    height: u32,
+    depth: u32,
+    frequency: f32,
+) -> Vec<f32> {
+    let scale = frequency / width.max(height).max(depth).max(1) as f32;
+    let mut values = Vec::with_capacity((width * height * depth) as usize);
+    for z in 0..depth {
+        for y in 0..height {
+            for x in 0..width {
+                values.push(noise.sample3(x as f32 * scale, y as f32 * scale, z as f32 * scale));
+            }
+        }
+    }
+    values
+}
+
+/// Bakes noise into a single-channel `TextureFormat::R8` texture, normalized with
+/// `Noise::range`.
+///
+/// # Arguments
+///
+/// * `noise` - The noise function to bake.
+/// * `width` - The width of the texture in pixels.
+/// * `height` - The height of the texture in pixels.
+/// * `frequency` - The number of noise units covered by the whole texture.
+///
+/// # Errors
+///
+/// Returns an `Errors::TextureLoadError` if the texture cannot be uploaded.
+pub fn bake_texture(
+    noise: &impl Noise,
+    width: u32,
+    height: u32,
+    frequency: f32,
+) -> Result<Texture, Errors> {
+    let (min, max) = noise.range();
+    let data: Vec<u8> = sample_grid(noise, width, height, frequency)
+        .into_iter()
+        .map(|value| (((value - min) / (max - min)).clamp(0.0, 1.0) * 255.0).round() as u8)
+        .collect();
+    let texture = Texture::new();
+    texture.load_from_data_with_format(width, height, TextureFormat::R8, &data)?;
+    Ok(texture)
+}
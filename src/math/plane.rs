@@ -0,0 +1,64 @@
+//! # Plane Module
+
+use cgmath::*;
+
+/// An infinite plane, stored as a unit normal and the distance from the origin along it.
+///
+/// Points `p` on the plane satisfy `normal.dot(p) + distance == 0`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Plane {
+    /// The unit normal of the plane, pointing to its positive side.
+    pub normal: Vector3<f32>,
+    /// The signed distance term of the plane equation.
+    pub distance: f32,
+}
+
+impl Plane {
+    /// Creates a plane from a normal, which is normalized, and the distance term.
+    pub fn new(normal: Vector3<f32>, distance: f32) -> Self {
+        let length = normal.magnitude();
+        Self {
+            normal: normal / length,
+            distance: distance / length,
+        }
+    }
+
+    /// Creates a plane through a point with the given normal.
+    pub fn from_point_normal(point: Point3<f32>, normal: Vector3<f32>) -> Self {
+        let normal = normal.normalize();
+        Self {
+            normal,
+            distance: -normal.dot(point.to_vec()),
+        }
+    }
+
+    /// Creates a plane through three points. The normal follows the counter-clockwise winding
+    /// of `a`, `b`, `c`.
+    pub fn from_points(a: Point3<f32>, b: Point3<f32>, c: Point3<f32>) -> Self {
+        Self::from_point_normal(a, (b - a).cross(c - a))
+    }
+
+    /// Returns the signed distance from the plane to the point, positive on the side the
+    /// normal points to.
+    pub fn signed_distance(&self, point: Point3<f32>) -> f32 {
+        self.normal.dot(point.to_vec()) + self.distance
+    }
+
+    /// Returns the distance from the plane to the point.
+    pub fn distance_to_point(&self, point: Point3<f32>) -> f32 {
+        self.signed_distance(point).abs()
+    }
+
+    /// Returns the point on the plane closest to the given point.
+    pub fn project_point(&self, point: Point3<f32>) -> Point3<f32> {
+        point - self.normal * self.signed_distance(point)
+    }
+
+    /// Returns the plane facing the opposite direction.
+    pub fn flipped(&self) -> Self {
+        Self {
+            normal: -self.normal,
+            distance: -self.distance,
+        }
+    }
+}
@@ -0,0 +1,112 @@
+//! # Ray Module
+
+use crate::math::{Aabb, Plane, Sphere, Triangle};
+use cgmath::*;
+
+/// A half-line starting at an origin, used for picking and line-of-sight tests.
+///
+/// The intersection functions return the distance along the ray to the first hit, which can be
+/// turned into a point with `Ray::at`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ray {
+    /// The starting point of the ray.
+    pub origin: Point3<f32>,
+    /// The unit direction of the ray.
+    pub direction: Vector3<f32>,
+}
+
+impl Ray {
+    /// Creates a ray from an origin and a direction, which is normalized.
+    pub fn new(origin: Point3<f32>, direction: Vector3<f32>) -> Self {
+        Self {
+            origin,
+            direction: direction.normalize(),
+        }
+    }
+
+    /// Returns the point at the given distance along the ray.
+    pub fn at(&self, distance: f32) -> Point3<f32> {
+        self.origin + self.direction * distance
+    }
+
+    /// Returns the distance to the point where the ray crosses the plane, or `None` if the ray
+    /// is parallel to it or points away from it.
+    pub fn intersect_plane(&self, plane: &Plane) -> Option<f32> {
+        let denominator = plane.normal.dot(self.direction);
+        if denominator.abs() <= f32::EPSILON {
+            return None;
+        }
+        let distance = -plane.signed_distance(self.origin) / denominator;
+        (distance >= 0.0).then_some(distance)
+    }
+
+    /// Returns the distance to where the ray enters the box, `0.0` if it starts inside, or
+    /// `None` if it misses.
+    pub fn intersect_aabb(&self, aabb: &Aabb) -> Option<f32> {
+        let mut near = 0.0f32;
+        let mut far = f32::INFINITY;
+        for axis in 0..3 {
+            let inverse = 1.0 / self.direction[axis];
+            let mut t0 = (aabb.min[axis] - self.origin[axis]) * inverse;
+            let mut t1 = (aabb.max[axis] - self.origin[axis]) * inverse;
+            if t0 > t1 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            // NaN from 0 * inf (origin on a slab boundary) is treated as inside the slab
+            near = if t0.is_nan() { near } else { near.max(t0) };
+            far = if t1.is_nan() { far } else { far.min(t1) };
+            if near > far {
+                return None;
+            }
+        }
+        Some(near)
+    }
+
+    /// Returns the distance to where the ray enters the sphere, `0.0` if it starts inside, or
+    /// `None` if it misses.
+    pub fn intersect_sphere(&self, sphere: &Sphere) -> Option<f32> {
+        let offset = self.origin - sphere.center;
+        let b = offset.dot(self.direction);
+        let c = offset.magnitude2() - sphere.radius * sphere.radius;
+        if c <= 0.0 {
+            return Some(0.0);
+        }
+        let discriminant = b * b - c;
+        if b > 0.0 || discriminant < 0.0 {
+            return None;
+        }
+        Some(-b - discriminant.sqrt())
+    }
+
+    /// Returns the distance to where the ray hits the triangle from either side, or `None` if
+    /// it misses.
+    pub fn intersect_triangle(&self, triangle: &Triangle) -> Option<f32> {
+        // Möller–Trumbore
+        let edge1 = triangle.b - triangle.a;
+        let edge2 = triangle.c - triangle.a;
+        let p = self.direction.cross(edge2);
+        let determinant = edge1.dot(p);
+        if determinant.abs() <= f32::EPSILON {
+            return None;
+        }
+        let inverse = 1.0 / determinant;
+        let t = self.origin - triangle.a;
+        let u = t.dot(p) * inverse;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+        let q = t.cross(edge1);
+        let v = self.direction.dot(q) * inverse;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+        let distance = edge2.dot(q) * inverse;
+        (distance >= 0.0).then_some(distance)
+    }
+
+    /// Returns the distance from the point to the closest point on the ray.
+    pub fn distance_to_point(&self, point: Point3<f32>) -> f32 {
+        let along = (point - self.origin).dot(self.direction).max(0.0);
+        self.at(along).distance(point)
+    }
+}
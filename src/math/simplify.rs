@@ -0,0 +1,419 @@
+//! # Simplify Module
+//!
+//! Reduces the triangle count of indexed meshes on the CPU, e.g. to generate levels of detail
+//! for imported models.
+//!
+//! Triangles are removed by collapsing edges onto one of their vertices, cheapest first, using
+//! quadric error metrics to estimate how far the surface moves. Because vertices are only
+//! removed and never moved, the simplified index lists keep referring to the original vertex
+//! buffer, so every attribute stays valid and all levels of detail can share one buffer.
+//! Open borders and attribute seams, which show up as borders in the index topology, are
+//! kept in place, and collapses that would flip triangles are skipped.
+//!
+//! ## Example
+//! ```rust
+//! use glwfr::math::simplify::{generate_lods, simplify};
+//!
+//! fn build_lods(positions: &[[f32; 3]], indices: &[u32]) {
+//!     // Halve the triangle count, as long as the surface moves less than 0.01 units
+//!     let simplified = simplify(positions, indices, indices.len() / 6, 0.01);
+//!     println!("{} -> {} triangles", indices.len() / 3, simplified.len() / 3);
+//!
+//!     // Three levels of detail with 50%, 25% and 10% of the triangles
+//!     let lods = generate_lods(positions, indices, &[0.5, 0.25, 0.1], f32::INFINITY);
+//!     assert_eq!(lods.len(), 3);
+//! }
+//! ```
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+/// The weight of the planes keeping border edges in place, relative to surface planes.
+const BORDER_WEIGHT: f64 = 1000.0;
+
+/// A symmetric 4x4 matrix summing the squared distances to a set of planes.
+#[derive(Debug, Clone, Copy, Default)]
+struct Quadric([f64; 10]);
+
+impl Quadric {
+    /// Creates the quadric of the plane `ax + by + cz + d = 0` with a unit normal.
+    fn from_plane(normal: [f64; 3], d: f64, weight: f64) -> Self {
+        let [a, b, c] = normal;
+        Self(
+            [
+                a * a,
+                a * b,
+                a * c,
+                a * d,
+                b * b,
+                b * c,
+                b * d,
+                c * c,
+                c * d,
+                d * d,
+            ]
+            .map(|value| value * weight),
+        )
+    }
+
+    fn add(&mut self, other: &Quadric) {
+        for (value, other) in self.0.iter_mut().zip(other.0) {
+            *value += other;
+        }
+    }
+
+    /// Returns the weighted sum of squared distances from the point to the planes.
+    fn error(&self, [x, y, z]: [f64; 3]) -> f64 {
+        let q = &self.0;
+        let error = q[0] * x * x
+            + 2.0 * q[1] * x * y
+            + 2.0 * q[2] * x * z
+            + 2.0 * q[3] * x
+            + q[4] * y * y
+            + 2.0 * q[5] * y * z
+            + 2.0 * q[6] * y
+            + q[7] * z * z
+            + 2.0 * q[8] * z
+            + q[9];
+        error.max(0.0)
+    }
+}
+
+/// A candidate collapse of vertex `from` onto vertex `to`.
+#[derive(Debug, Clone, Copy)]
+struct Collapse {
+    cost: f64,
+    from: u32,
+    to: u32,
+    /// The versions of both vertices when the cost was computed.
+    versions: (u32, u32),
+}
+
+impl PartialEq for Collapse {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Collapse {}
+
+impl PartialOrd for Collapse {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Collapse {
+    /// Orders cheaper collapses first in a max-heap.
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+/// The state of a mesh during simplification.
+struct Simplifier<'a> {
+    positions: &'a [[f32; 3]],
+    triangles: Vec<[u32; 3]>,
+    triangle_alive: Vec<bool>,
+    /// The triangles using each vertex.
+    adjacency: Vec<Vec<usize>>,
+    quadrics: Vec<Quadric>,
+    versions: Vec<u32>,
+    triangle_count: usize,
+}
+
+impl<'a> Simplifier<'a> {
+    fn new(positions: &'a [[f32; 3]], indices: &[u32]) -> Self {
+        let triangles: Vec<[u32; 3]> = indices
+            .chunks_exact(3)
+            .map(|triangle| [triangle[0], triangle[1], triangle[2]])
+            .filter(|&[a, b, c]| a != b && b != c && a != c)
+            .collect();
+        let mut adjacency = vec![Vec::new(); positions.len()];
+        let mut quadrics = vec![Quadric::default(); positions.len()];
+        let mut edge_uses: HashMap<(u32, u32), (u32, usize)> = HashMap::new();
+        for (index, triangle) in triangles.iter().enumerate() {
+            for &vertex in triangle {
+                adjacency[vertex as usize].push(index);
+            }
+            if let Some((normal, d)) = plane(positions, *triangle) {
+                let quadric = Quadric::from_plane(normal, d, 1.0);
+                for &vertex in triangle {
+                    quadrics[vertex as usize].add(&quadric);
+                }
+            }
+            for corner in 0..3 {
+                let (a, b) = (triangle[corner], triangle[(corner + 1) % 3]);
+                let entry = edge_uses.entry((a.min(b), a.max(b))).or_insert((0, index));
+                entry.0 += 1;
+            }
+        }
+
+        // Keep border edges in place with planes perpendicular to their triangle
+        for ((a, b), (uses, triangle)) in edge_uses {
+            if uses != 1 {
+                continue;
+            }
+            let Some((normal, _)) = plane(positions, triangles[triangle]) else {
+                continue;
+            };
+            let pa = to_f64(positions[a as usize]);
+            let pb = to_f64(positions[b as usize]);
+            let Some(border_normal) = normalize(cross(sub(pb, pa), normal)) else {
+                continue;
+            };
+            let d = -dot(border_normal, pa);
+            let quadric = Quadric::from_plane(border_normal, d, BORDER_WEIGHT);
+            quadrics[a as usize].add(&quadric);
+            quadrics[b as usize].add(&quadric);
+        }
+
+        let triangle_count = triangles.len();
+        Self {
+            positions,
+            triangle_alive: vec![true; triangle_count],
+            triangles,
+            adjacency,
+            quadrics,
+            versions: vec![0; positions.len()],
+            triangle_count,
+        }
+    }
+
+    fn position(&self, vertex: u32) -> [f64; 3] {
+        to_f64(self.positions[vertex as usize])
+    }
+
+    /// Returns the vertices sharing a triangle with the given vertex.
+    fn neighbors(&self, vertex: u32) -> HashSet<u32> {
+        self.adjacency[vertex as usize]
+            .iter()
+            .flat_map(|&triangle| self.triangles[triangle])
+            .filter(|&other| other != vertex)
+            .collect()
+    }
+
+    /// Returns the cheaper direction of collapsing the edge between two vertices.
+    fn best_collapse(&self, a: u32, b: u32) -> Collapse {
+        let mut quadric = self.quadrics[a as usize];
+        quadric.add(&self.quadrics[b as usize]);
+        let onto_b = quadric.error(self.position(b));
+        let onto_a = quadric.error(self.position(a));
+        let (from, to, cost) = if onto_b <= onto_a {
+            (a, b, onto_b)
+        } else {
+            (b, a, onto_a)
+        };
+        Collapse {
+            cost,
+            from,
+            to,
+            versions: (self.versions[from as usize], self.versions[to as usize]),
+        }
+    }
+
+    /// Checks if collapsing `from` onto `to` keeps the mesh manifold and flips no triangle.
+    fn is_valid(&self, from: u32, to: u32) -> bool {
+        let shared = self.adjacency[from as usize]
+            .iter()
+            .filter(|&&triangle| self.triangles[triangle].contains(&to))
+            .count();
+        if shared == 0 {
+            return false;
+        }
+        let common = self
+            .neighbors(from)
+            .intersection(&self.neighbors(to))
+            .count();
+        if common > shared {
+            return false;
+        }
+
+        let target = self.position(to);
+        self.adjacency[from as usize].iter().all(|&triangle| {
+            let vertices = self.triangles[triangle];
+            if vertices.contains(&to) {
+                return true;
+            }
+            let before = vertices.map(|vertex| self.position(vertex));
+            let after = vertices.map(|vertex| {
+                if vertex == from {
+                    target
+                } else {
+                    self.position(vertex)
+                }
+            });
+            let normal_before = cross(sub(before[1], before[0]), sub(before[2], before[0]));
+            let normal_after = cross(sub(after[1], after[0]), sub(after[2], after[0]));
+            dot(normal_before, normal_after) > 0.0
+        })
+    }
+
+    /// Moves the triangles of `from` onto `to` and removes the ones that degenerate.
+    fn collapse(&mut self, from: u32, to: u32) {
+        for triangle in std::mem::take(&mut self.adjacency[from as usize]) {
+            if self.triangles[triangle].contains(&to) {
+                self.triangle_alive[triangle] = false;
+                self.triangle_count -= 1;
+                for vertex in self.triangles[triangle] {
+                    if vertex != from {
+                        self.adjacency[vertex as usize].retain(|&other| other != triangle);
+                    }
+                }
+            } else {
+                for vertex in &mut self.triangles[triangle] {
+                    if *vertex == from {
+                        *vertex = to;
+                    }
+                }
+                self.adjacency[to as usize].push(triangle);
+            }
+        }
+        let quadric = self.quadrics[from as usize];
+        self.quadrics[to as usize].add(&quadric);
+        self.versions[from as usize] += 1;
+        self.versions[to as usize] += 1;
+    }
+
+    fn run(&mut self, target_triangles: usize, max_error: f32) {
+        let max_cost = (max_error as f64) * (max_error as f64);
+        let mut queue = BinaryHeap::new();
+        let mut edges = HashSet::new();
+        for triangle in &self.triangles {
+            for corner in 0..3 {
+                let (a, b) = (triangle[corner], triangle[(corner + 1) % 3]);
+                if edges.insert((a.min(b), a.max(b))) {
+                    queue.push(self.best_collapse(a, b));
+                }
+            }
+        }
+
+        while self.triangle_count > target_triangles {
+            let Some(collapse) = queue.pop() else {
+                break;
+            };
+            let current = (
+                self.versions[collapse.from as usize],
+                self.versions[collapse.to as usize],
+            );
+            if collapse.versions != current {
+                continue;
+            }
+            if collapse.cost > max_cost {
+                break;
+            }
+            if !self.is_valid(collapse.from, collapse.to) {
+                continue;
+            }
+            self.collapse(collapse.from, collapse.to);
+            for neighbor in self.neighbors(collapse.to) {
+                queue.push(self.best_collapse(collapse.to, neighbor));
+            }
+        }
+    }
+
+    fn indices(&self) -> Vec<u32> {
+        self.triangles
+            .iter()
+            .zip(&self.triangle_alive)
+            .filter(|(_, alive)| **alive)
+            .flat_map(|(triangle, _)| *triangle)
+            .collect()
+    }
+}
+
+/// Removes triangles from an indexed triangle list by collapsing edges.
+///
+/// Simplification stops when at most `target_triangles` triangles are left, or when the next
+/// collapse would move the surface by more than `max_error`. The result can have more
+/// triangles than the target if borders or flipped triangles prevent further collapses.
+///
+/// # Arguments
+///
+/// * `positions` - The vertex positions.
+/// * `indices` - The indices of the triangle list into `positions`.
+/// * `target_triangles` - The number of triangles to reduce the mesh to.
+/// * `max_error` - The largest estimated distance in world units the surface may move,
+///   or `f32::INFINITY` to only stop at the target.
+///
+/// # Returns
+///
+/// The indices of the simplified triangle list into the original `positions`.
+pub fn simplify(
+    positions: &[[f32; 3]],
+    indices: &[u32],
+    target_triangles: usize,
+    max_error: f32,
+) -> Vec<u32> {
+    let mut simplifier = Simplifier::new(positions, indices);
+    simplifier.run(target_triangles, max_error);
+    simplifier.indices()
+}
+
+/// Generates levels of detail by simplifying a mesh to fractions of its triangle count.
+///
+/// Each level is simplified from the previous one, so the levels nest and later ones are
+/// cheap to generate.
+///
+/// # Arguments
+///
+/// * `positions` - The vertex positions.
+/// * `indices` - The indices of the full-detail triangle list into `positions`.
+/// * `ratios` - The fraction of triangles to keep for each level, in decreasing order, e.g.
+///   `[0.5, 0.25]`.
+/// * `max_error` - The largest estimated distance the surface may move per level, see
+///   `simplify`.
+///
+/// # Returns
+///
+/// One index list per ratio, all referring to the original `positions`.
+pub fn generate_lods(
+    positions: &[[f32; 3]],
+    indices: &[u32],
+    ratios: &[f32],
+    max_error: f32,
+) -> Vec<Vec<u32>> {
+    let triangle_count = indices.len() / 3;
+    let mut lods: Vec<Vec<u32>> = Vec::with_capacity(ratios.len());
+    for &ratio in ratios {
+        let source = lods.last().map_or(indices, |lod| lod.as_slice());
+        let target = (triangle_count as f32 * ratio.clamp(0.0, 1.0)) as usize;
+        lods.push(simplify(positions, source, target, max_error));
+    }
+    lods
+}
+
+/// Returns the unit normal and the offset of a triangle's plane, or `None` if it is
+/// degenerate.
+fn plane(positions: &[[f32; 3]], [a, b, c]: [u32; 3]) -> Option<([f64; 3], f64)> {
+    let pa = to_f64(positions[a as usize]);
+    let pb = to_f64(positions[b as usize]);
+    let pc = to_f64(positions[c as usize]);
+    let normal = normalize(cross(sub(pb, pa), sub(pc, pa)))?;
+    Some((normal, -dot(normal, pa)))
+}
+
+fn to_f64(position: [f32; 3]) -> [f64; 3] {
+    position.map(|component| component as f64)
+}
+
+fn sub(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn normalize(vector: [f64; 3]) -> Option<[f64; 3]> {
+    let length = dot(vector, vector).sqrt();
+    (length > 1e-12).then(|| vector.map(|component| component / length))
+}
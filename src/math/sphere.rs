@@ -0,0 +1,47 @@
+//! # Sphere Module
+
+use crate::math::Aabb;
+use cgmath::*;
+
+/// A sphere, commonly used as a cheap bounding volume.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sphere {
+    /// The center of the sphere.
+    pub center: Point3<f32>,
+    /// The radius of the sphere.
+    pub radius: f32,
+}
+
+impl Sphere {
+    /// Creates a sphere from its center and radius.
+    pub fn new(center: Point3<f32>, radius: f32) -> Self {
+        Self { center, radius }
+    }
+
+    /// Returns the smallest sphere around the center of the box that contains the box.
+    pub fn from_aabb(aabb: &Aabb) -> Self {
+        Self::new(aabb.center(), aabb.half_extents().magnitude())
+    }
+
+    /// Returns `true` if the point lies inside or on the sphere.
+    pub fn contains_point(&self, point: Point3<f32>) -> bool {
+        self.center.distance2(point) <= self.radius * self.radius
+    }
+
+    /// Returns `true` if the spheres overlap or touch.
+    pub fn intersects_sphere(&self, other: &Sphere) -> bool {
+        let radii = self.radius + other.radius;
+        self.center.distance2(other.center) <= radii * radii
+    }
+
+    /// Returns `true` if the sphere and the box overlap or touch.
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.center.distance2(aabb.closest_point(self.center)) <= self.radius * self.radius
+    }
+
+    /// Returns the distance from the point to the surface of the sphere, or `0.0` if the point
+    /// is inside.
+    pub fn distance_to_point(&self, point: Point3<f32>) -> f32 {
+        (self.center.distance(point) - self.radius).max(0.0)
+    }
+}
@@ -0,0 +1,59 @@
+//! # Triangle Module
+
+use cgmath::*;
+
+/// A triangle in 3D space.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Triangle {
+    /// The first corner.
+    pub a: Point3<f32>,
+    /// The second corner.
+    pub b: Point3<f32>,
+    /// The third corner.
+    pub c: Point3<f32>,
+}
+
+impl Triangle {
+    /// Creates a triangle from its corners.
+    pub fn new(a: Point3<f32>, b: Point3<f32>, c: Point3<f32>) -> Self {
+        Self { a, b, c }
+    }
+
+    /// Returns the unit normal, following the counter-clockwise winding of the corners.
+    pub fn normal(&self) -> Vector3<f32> {
+        (self.b - self.a).cross(self.c - self.a).normalize()
+    }
+
+    /// Returns the area of the triangle.
+    pub fn area(&self) -> f32 {
+        (self.b - self.a).cross(self.c - self.a).magnitude() * 0.5
+    }
+
+    /// Returns the center of mass of the triangle.
+    pub fn centroid(&self) -> Point3<f32> {
+        Point3::centroid(&[self.a, self.b, self.c])
+    }
+
+    /// Returns the barycentric coordinates `(u, v, w)` of a point in the triangle's plane, so
+    /// that `point == a * u + b * v + c * w`.
+    ///
+    /// Returns `None` for degenerate triangles.
+    pub fn barycentric(&self, point: Point3<f32>) -> Option<Vector3<f32>> {
+        let (v0, v1, v2) = (self.b - self.a, self.c - self.a, point - self.a);
+        let (d00, d01, d11) = (v0.dot(v0), v0.dot(v1), v1.dot(v1));
+        let (d20, d21) = (v2.dot(v0), v2.dot(v1));
+        let denominator = d00 * d11 - d01 * d01;
+        if denominator.abs() <= f32::EPSILON {
+            return None;
+        }
+        let v = (d11 * d20 - d01 * d21) / denominator;
+        let w = (d00 * d21 - d01 * d20) / denominator;
+        Some(Vector3::new(1.0 - v - w, v, w))
+    }
+
+    /// Returns `true` if a point in the triangle's plane lies inside or on the triangle.
+    pub fn contains_point(&self, point: Point3<f32>) -> bool {
+        self.barycentric(point)
+            .is_some_and(|b| b.x >= 0.0 && b.y >= 0.0 && b.z >= 0.0)
+    }
+}
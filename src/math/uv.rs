@@ -0,0 +1,110 @@
+//! # UV Module
+//!
+//! Generates texture coordinates for geometry built in code, so it can be textured without
+//! authoring UVs.
+//!
+//! ## Example
+//! ```rust
+//! use glwfr::math::uv::{generate_uvs, UvMapping};
+//!
+//! let positions = [[0.0, 0.0, 0.0], [4.0, 0.0, 0.0], [4.0, 0.0, 4.0]];
+//! let normals = [[0.0, 1.0, 0.0]; 3];
+//!
+//! // Repeat the texture twice per world unit
+//! let uvs = generate_uvs(&positions, &normals, UvMapping::Box, 2.0);
+//! assert_eq!(uvs[1], [8.0, 0.0]);
+//! ```
+
+use cgmath::*;
+use std::f32::consts::PI;
+
+/// How texture coordinates are projected onto geometry.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum UvMapping {
+    /// Projects along the dominant axis of each vertex normal, so every side of a box-like
+    /// shape gets an undistorted texture.
+    #[default]
+    Box,
+    /// Projects onto the plane perpendicular to `normal`, e.g. for floors and walls.
+    Planar {
+        /// The direction the texture is projected along.
+        normal: Vector3<f32>,
+    },
+    /// Wraps longitude and latitude around `center`, for spheres and domes. `u` runs from
+    /// `0.0` to `1.0` around the Y axis, `v` from `0.0` at the bottom to `1.0` at the top.
+    Spherical {
+        /// The center of the projection.
+        center: Point3<f32>,
+    },
+}
+
+/// Generates one UV pair per vertex.
+///
+/// # Arguments
+///
+/// * `positions` - The vertex positions.
+/// * `normals` - The vertex normals, used by `UvMapping::Box`. Missing normals count as
+///   pointing up.
+/// * `mapping` - The projection to use.
+/// * `texel_density` - How often the texture repeats per world unit. For
+///   `UvMapping::Spherical` it is the number of repetitions around the sphere.
+pub fn generate_uvs(
+    positions: &[[f32; 3]],
+    normals: &[[f32; 3]],
+    mapping: UvMapping,
+    texel_density: f32,
+) -> Vec<[f32; 2]> {
+    positions
+        .iter()
+        .enumerate()
+        .map(|(i, &position)| {
+            let position = Point3::from(position);
+            let normal = normals.get(i).copied().unwrap_or([0.0, 1.0, 0.0]).into();
+            let [u, v] = match mapping {
+                UvMapping::Box => box_uv(position, normal),
+                UvMapping::Planar { normal } => planar_uv(position, normal),
+                UvMapping::Spherical { center } => spherical_uv(position, center),
+            };
+            [u * texel_density, v * texel_density]
+        })
+        .collect()
+}
+
+/// Projects along the axis the normal points most towards.
+fn box_uv(position: Point3<f32>, normal: Vector3<f32>) -> [f32; 2] {
+    let (x, y, z) = (normal.x.abs(), normal.y.abs(), normal.z.abs());
+    if x >= y && x >= z {
+        [-position.z * normal.x.signum(), position.y]
+    } else if y >= z {
+        [position.x, -position.z * normal.y.signum()]
+    } else {
+        [position.x * normal.z.signum(), position.y]
+    }
+}
+
+/// Projects onto the plane perpendicular to `normal`.
+fn planar_uv(position: Point3<f32>, normal: Vector3<f32>) -> [f32; 2] {
+    let normal = normal.normalize();
+    // Any vector not parallel to the normal works as a reference for the tangent
+    let reference = if normal.y.abs() < 0.99 {
+        Vector3::unit_y()
+    } else {
+        Vector3::unit_z()
+    };
+    let tangent = reference.cross(normal).normalize();
+    let bitangent = normal.cross(tangent);
+    let position = position.to_vec();
+    [position.dot(tangent), position.dot(bitangent)]
+}
+
+/// Maps the direction from `center` to longitude and latitude.
+fn spherical_uv(position: Point3<f32>, center: Point3<f32>) -> [f32; 2] {
+    let direction = position - center;
+    if direction.magnitude2() <= f32::EPSILON {
+        return [0.0, 0.5];
+    }
+    let direction = direction.normalize();
+    let u = 0.5 + direction.z.atan2(direction.x) / (2.0 * PI);
+    let v = 0.5 + direction.y.clamp(-1.0, 1.0).asin() / PI;
+    [u, v]
+}
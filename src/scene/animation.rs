@@ -0,0 +1,386 @@
+//! # Animation Module
+//!
+//! This module plays back keyframed translation, rotation, scale and morph target weight
+//! animations on scene objects, e.g. doors, elevators and camera flythroughs authored in a
+//! modelling tool. With the `gltf` feature, animations can be imported from glTF files with
+//! `Animation::from_gltf`.
+//!
+//! Channels follow the glTF animation model: each channel targets one property of the objects
+//! with a given tag and holds keyframe times and values with step, linear or cubic spline
+//! interpolation. Cubic spline channels store an in-tangent, value and out-tangent per
+//! keyframe, in that order.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::scene::{Animation, AnimationChannel, AnimationPlayer, ChannelValues, Interpolation, Scene};
+//! use glwfr::cgmath::Vector3;
+//!
+//! fn update(scene: &mut Scene, player: &mut AnimationPlayer, delta_time: f32) {
+//!     player.update(delta_time, scene);
+//! }
+//!
+//! fn elevator() -> AnimationPlayer {
+//!     let channel = AnimationChannel {
+//!         target: "elevator".to_string(),
+//!         times: vec![0.0, 4.0],
+//!         values: ChannelValues::Translation(vec![
+//!             Vector3::new(0.0, 0.0, 0.0),
+//!             Vector3::new(0.0, 10.0, 0.0),
+//!         ]),
+//!         interpolation: Interpolation::Linear,
+//!     };
+//!     let mut player = AnimationPlayer::new(Animation::new("ride_up", vec![channel]));
+//!     player.looping = true;
+//!     player.play();
+//!     player
+//! }
+//! ```
+
+use crate::scene::{Object, Scene};
+use cgmath::*;
+
+/// How values between keyframes are computed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Interpolation {
+    /// Keep the value of the previous keyframe.
+    Step,
+    /// Interpolate linearly; rotations use spherical interpolation.
+    #[default]
+    Linear,
+    /// Interpolate with a cubic Hermite spline using per-keyframe tangents.
+    CubicSpline,
+}
+
+/// The keyframe values of an animation channel and the property they animate.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChannelValues {
+    /// Positions of the target.
+    Translation(Vec<Vector3<f32>>),
+    /// Rotations of the target.
+    Rotation(Vec<Quaternion<f32>>),
+    /// Scales of the target.
+    Scale(Vec<Vector3<f32>>),
+    /// Morph target weights of the target's `Morph`, with the weights of all targets per
+    /// keyframe.
+    Weights(Vec<Vec<f32>>),
+}
+
+/// Animates one property of the objects with a given tag.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimationChannel {
+    /// The tag of the animated objects.
+    pub target: String,
+    /// The time of each keyframe in seconds, in ascending order.
+    pub times: Vec<f32>,
+    /// The keyframe values. Cubic spline channels hold three values per keyframe.
+    pub values: ChannelValues,
+    /// How values between keyframes are computed.
+    pub interpolation: Interpolation,
+}
+
+impl AnimationChannel {
+    /// Returns the keyframe segment containing `time` as `(index, factor, duration)`, clamped
+    /// to the first and last keyframe.
+    fn segment(&self, time: f32) -> Option<(usize, f32, f32)> {
+        let last = self.times.len().checked_sub(1)?;
+        let next = self.times.partition_point(|&t| t <= time);
+        if next == 0 {
+            return Some((0, 0.0, 0.0));
+        }
+        if next > last {
+            return Some((last, 0.0, 0.0));
+        }
+        let (t0, t1) = (self.times[next - 1], self.times[next]);
+        let duration = t1 - t0;
+        let factor = if duration > 0.0 {
+            (time - t0) / duration
+        } else {
+            0.0
+        };
+        Some((next - 1, factor, duration))
+    }
+
+    /// Samples a vector channel at the given keyframe segment.
+    fn sample_vector(
+        &self,
+        values: &[Vector3<f32>],
+        (index, factor, duration): (usize, f32, f32),
+    ) -> Option<Vector3<f32>> {
+        match self.interpolation {
+            Interpolation::Step => values.get(index).copied(),
+            Interpolation::Linear => {
+                let from = *values.get(index)?;
+                let to = values.get(index + 1).copied().unwrap_or(from);
+                Some(from.lerp(to, factor))
+            }
+            Interpolation::CubicSpline => {
+                let from = *values.get(index * 3 + 1)?;
+                if factor == 0.0 {
+                    return Some(from);
+                }
+                let out_tangent = *values.get(index * 3 + 2)?;
+                let in_tangent = *values.get(index * 3 + 3)?;
+                let to = *values.get(index * 3 + 4)?;
+                Some(hermite(
+                    from,
+                    out_tangent * duration,
+                    to,
+                    in_tangent * duration,
+                    factor,
+                ))
+            }
+        }
+    }
+
+    /// Samples a rotation channel at the given keyframe segment.
+    fn sample_rotation(
+        &self,
+        values: &[Quaternion<f32>],
+        (index, factor, duration): (usize, f32, f32),
+    ) -> Option<Quaternion<f32>> {
+        let rotation = match self.interpolation {
+            Interpolation::Step => *values.get(index)?,
+            Interpolation::Linear => {
+                let from = *values.get(index)?;
+                let to = values.get(index + 1).copied().unwrap_or(from);
+                from.slerp(to, factor)
+            }
+            Interpolation::CubicSpline => {
+                let from = *values.get(index * 3 + 1)?;
+                if factor == 0.0 {
+                    return Some(from.normalize());
+                }
+                let out_tangent = *values.get(index * 3 + 2)?;
+                let in_tangent = *values.get(index * 3 + 3)?;
+                let to = *values.get(index * 3 + 4)?;
+                let blended = hermite(
+                    Vector4::new(from.v.x, from.v.y, from.v.z, from.s),
+                    Vector4::new(
+                        out_tangent.v.x,
+                        out_tangent.v.y,
+                        out_tangent.v.z,
+                        out_tangent.s,
+                    ) * duration,
+                    Vector4::new(to.v.x, to.v.y, to.v.z, to.s),
+                    Vector4::new(in_tangent.v.x, in_tangent.v.y, in_tangent.v.z, in_tangent.s)
+                        * duration,
+                    factor,
+                );
+                Quaternion::new(blended.w, blended.x, blended.y, blended.z)
+            }
+        };
+        Some(rotation.normalize())
+    }
+
+    /// Samples a morph target weights channel at the given keyframe segment.
+    fn sample_weights(
+        &self,
+        values: &[Vec<f32>],
+        (index, factor, duration): (usize, f32, f32),
+    ) -> Option<Vec<f32>> {
+        match self.interpolation {
+            Interpolation::Step => values.get(index).cloned(),
+            Interpolation::Linear => {
+                let from = values.get(index)?;
+                let to = values.get(index + 1).unwrap_or(from);
+                Some(
+                    from.iter()
+                        .zip(to)
+                        .map(|(from, to)| from + (to - from) * factor)
+                        .collect(),
+                )
+            }
+            Interpolation::CubicSpline => {
+                let from = values.get(index * 3 + 1)?;
+                if factor == 0.0 {
+                    return Some(from.clone());
+                }
+                let out_tangent = values.get(index * 3 + 2)?;
+                let in_tangent = values.get(index * 3 + 3)?;
+                let to = values.get(index * 3 + 4)?;
+                Some(
+                    (0..from.len())
+                        .map(|i| {
+                            let weight = |values: &[f32]| {
+                                Vector1::new(values.get(i).copied().unwrap_or(0.0))
+                            };
+                            hermite(
+                                weight(from),
+                                weight(out_tangent) * duration,
+                                weight(to),
+                                weight(in_tangent) * duration,
+                                factor,
+                            )
+                            .x
+                        })
+                        .collect(),
+                )
+            }
+        }
+    }
+
+    /// Applies the channel's value at the given time to an object.
+    fn apply(&self, object: &mut Object, time: f32) {
+        let Some(segment) = self.segment(time) else {
+            return;
+        };
+        match &self.values {
+            ChannelValues::Translation(values) => {
+                if let Some(position) = self.sample_vector(values, segment) {
+                    object.transform.set_position(position);
+                }
+            }
+            ChannelValues::Rotation(values) => {
+                if let Some(rotation) = self.sample_rotation(values, segment) {
+                    object.transform.set_rotation(rotation);
+                }
+            }
+            ChannelValues::Scale(values) => {
+                if let Some(scale) = self.sample_vector(values, segment) {
+                    object.transform.set_scale(scale);
+                }
+            }
+            ChannelValues::Weights(values) => {
+                let Some(morph) = &mut object.morph else {
+                    return;
+                };
+                if let Some(weights) = self.sample_weights(values, segment) {
+                    for (target, weight) in weights.into_iter().enumerate() {
+                        morph.set_weight(target, weight);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Evaluates a cubic Hermite spline between two points with scaled tangents.
+fn hermite<V>(p0: V, m0: V, p1: V, m1: V, t: f32) -> V
+where
+    V: VectorSpace<Scalar = f32>,
+{
+    let t2 = t * t;
+    let t3 = t2 * t;
+    p0 * (2.0 * t3 - 3.0 * t2 + 1.0)
+        + m0 * (t3 - 2.0 * t2 + t)
+        + p1 * (-2.0 * t3 + 3.0 * t2)
+        + m1 * (t3 - t2)
+}
+
+/// A named set of channels played together.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Animation {
+    /// The name of the animation.
+    pub name: String,
+    /// The animated properties.
+    pub channels: Vec<AnimationChannel>,
+}
+
+impl Animation {
+    /// Creates an animation from its channels.
+    pub fn new(name: impl Into<String>, channels: Vec<AnimationChannel>) -> Self {
+        Self {
+            name: name.into(),
+            channels,
+        }
+    }
+
+    /// Returns the time of the last keyframe of all channels in seconds.
+    pub fn duration(&self) -> f32 {
+        self.channels
+            .iter()
+            .filter_map(|channel| channel.times.last().copied())
+            .fold(0.0, f32::max)
+    }
+
+    /// Poses the targeted objects of the scene at the given time.
+    pub fn apply(&self, scene: &mut Scene, time: f32) {
+        for channel in &self.channels {
+            for object in scene.objects_with_tag_mut(&channel.target) {
+                channel.apply(object, time);
+            }
+        }
+    }
+}
+
+/// Plays an `Animation` over time.
+pub struct AnimationPlayer {
+    /// The animation being played.
+    pub animation: Animation,
+    /// The playback speed, where `1.0` is normal speed and negative values play backwards.
+    pub speed: f32,
+    /// Whether playback restarts after the end of the animation.
+    pub looping: bool,
+    time: f32,
+    playing: bool,
+}
+
+impl AnimationPlayer {
+    /// Creates a paused player at the start of the animation.
+    pub fn new(animation: Animation) -> Self {
+        Self {
+            animation,
+            speed: 1.0,
+            looping: false,
+            time: 0.0,
+            playing: false,
+        }
+    }
+
+    /// Starts or resumes playback.
+    pub fn play(&mut self) {
+        self.playing = true;
+    }
+
+    /// Pauses playback at the current time.
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    /// Stops playback and rewinds to the start.
+    pub fn stop(&mut self) {
+        self.playing = false;
+        self.time = 0.0;
+    }
+
+    /// Checks if the animation is playing.
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    /// Returns the current playback time in seconds.
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    /// Jumps to the given time in seconds.
+    pub fn seek(&mut self, time: f32) {
+        self.time = time.clamp(0.0, self.animation.duration());
+    }
+
+    /// Advances playback and poses the targeted objects. Non-looping animations stop at the end.
+    ///
+    /// Call this once per frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `delta_time` - The time since the previous update in seconds.
+    /// * `scene` - The scene containing the animated objects.
+    pub fn update(&mut self, delta_time: f32, scene: &mut Scene) {
+        if !self.playing {
+            return;
+        }
+        let duration = self.animation.duration();
+        self.time += delta_time * self.speed;
+        if self.looping && duration > 0.0 {
+            self.time = self.time.rem_euclid(duration);
+        } else if (self.speed >= 0.0 && self.time >= duration)
+            || (self.speed < 0.0 && self.time <= 0.0)
+        {
+            self.time = self.time.clamp(0.0, duration);
+            self.playing = false;
+        }
+        self.animation.apply(scene, self.time);
+    }
+}
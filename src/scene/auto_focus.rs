@@ -0,0 +1,129 @@
+//! # Auto Focus Module
+//!
+//! This module provides an `AutoFocus` controller that smoothly pulls the focus of a
+//! `DepthOfField` pass towards an object or whatever the camera is aimed at, like a camera
+//! operator racking focus.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::scene::{AutoFocus, Camera};
+//! use glwfr::graphics::depth_of_field::DepthOfField;
+//! use glwfr::math::Aabb;
+//!
+//! fn update(camera: &Camera, focus: &mut AutoFocus, dof: &mut DepthOfField, walls: &[Aabb], dt: f32) {
+//!     // Focus on whatever is in the center of the screen
+//!     focus.focus_on_raycast(camera, |ray| {
+//!         walls.iter().filter_map(|wall| ray.intersect_aabb(wall)).reduce(f32::min)
+//!     });
+//!     focus.update(dt);
+//!     focus.apply(dof);
+//! }
+//! ```
+
+use crate::graphics::depth_of_field::DepthOfField;
+use crate::math::Ray;
+use crate::scene::Camera;
+use cgmath::*;
+
+/// Animates a focus distance towards a target distance.
+#[derive(Debug, Clone)]
+pub struct AutoFocus {
+    /// The time in seconds the focus needs to get most of the way to its target. `0.0`
+    /// focuses instantly.
+    pub smoothing: f32,
+    /// The closest distance the focus is pulled to.
+    pub min_distance: f32,
+    distance: f32,
+    target: f32,
+}
+
+impl AutoFocus {
+    /// Creates a controller focused at the given distance.
+    pub fn new(distance: f32) -> Self {
+        Self {
+            smoothing: 0.25,
+            min_distance: 0.1,
+            distance,
+            target: distance,
+        }
+    }
+
+    /// Returns the current, animated focus distance.
+    pub fn focus_distance(&self) -> f32 {
+        self.distance
+    }
+
+    /// Returns the distance the focus is moving towards.
+    pub fn target_distance(&self) -> f32 {
+        self.target
+    }
+
+    /// Sets the distance the focus moves towards.
+    pub fn focus_at(&mut self, distance: f32) {
+        self.target = distance.max(self.min_distance);
+    }
+
+    /// Jumps to the given focus distance without animating, e.g. after a camera cut.
+    pub fn snap_to(&mut self, distance: f32) {
+        self.focus_at(distance);
+        self.distance = self.target;
+    }
+
+    /// Moves the focus towards a point in world space, such as the position of an object.
+    ///
+    /// The focus distance is the depth of the point along the camera's view direction.
+    pub fn focus_on_point(&mut self, camera: &Camera, point: Point3<f32>) {
+        let forward = (camera.target - camera.position).normalize();
+        self.focus_at((point - camera.position).dot(forward));
+    }
+
+    /// Moves the focus towards whatever the camera is aimed at.
+    ///
+    /// # Arguments
+    ///
+    /// * `camera` - The camera, casting a ray through the center of the screen.
+    /// * `raycast` - Returns the distance along the ray to the closest hit, if any.
+    ///
+    /// # Returns
+    ///
+    /// `true` if something was hit. Otherwise the focus target is left unchanged.
+    pub fn focus_on_raycast(
+        &mut self,
+        camera: &Camera,
+        raycast: impl Fn(&Ray) -> Option<f32>,
+    ) -> bool {
+        let ray = Ray::new(camera.position, camera.target - camera.position);
+        match raycast(&ray) {
+            Some(distance) => {
+                self.focus_at(distance);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Advances the focus animation.
+    ///
+    /// # Arguments
+    ///
+    /// * `delta_time` - The time since the last update, in seconds.
+    ///
+    /// # Returns
+    ///
+    /// The new focus distance.
+    pub fn update(&mut self, delta_time: f32) -> f32 {
+        if self.smoothing <= 0.0 {
+            self.distance = self.target;
+        } else {
+            let blend = 1.0 - (-delta_time / self.smoothing).exp();
+            self.distance += (self.target - self.distance) * blend;
+        }
+        self.distance
+    }
+
+    /// Sets the focus distance of a depth-of-field pass.
+    pub fn apply(&self, depth_of_field: &mut DepthOfField) {
+        depth_of_field.focus_distance = self.distance;
+    }
+}
@@ -26,6 +26,12 @@
 //! let projection_matrix = camera.projection_matrix();
 //! ```
 
+use crate::graphics::render_state::ClearState;
+#[cfg(feature = "native")]
+use crate::graphics::window::Window;
+#[cfg(feature = "native")]
+use crate::input;
+use crate::math::Ray;
 use cgmath::*;
 
 /// Represents the type of camera projection: perspective or orthographic.
@@ -58,6 +64,11 @@ pub struct Camera {
     pub target: Point3<f32>,
     /// The up vector of the camera, defining its orientation.
     pub up: Vector3<f32>,
+    /// How `Scene::clear` clears the target before the camera renders, or `None` to clear
+    /// the color to the environment's background and the depth to `1.0`. Cameras drawing on
+    /// top of another view, such as a HUD or a picture-in-picture, use
+    /// `ClearState::depth_only()` or `ClearState::LOAD`.
+    pub clear: Option<ClearState>,
     /// The type of projection used by the camera (perspective or orthographic).
     camera_type: CameraType,
 }
@@ -85,6 +96,7 @@ impl Camera {
             position,
             target,
             up,
+            clear: None,
             camera_type,
         }
     }
@@ -98,6 +110,73 @@ impl Camera {
         Matrix4::look_at_rh(self.position, self.target, self.up)
     }
 
+    /// Returns the ray through a point on the screen, e.g. the mouse cursor.
+    ///
+    /// For orthographic cameras all rays are parallel to the view direction.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - The horizontal screen coordinate, from the left edge.
+    /// * `y` - The vertical screen coordinate, from the top edge.
+    /// * `width` - The width of the viewport, in the same units as `x`.
+    /// * `height` - The height of the viewport, in the same units as `y`.
+    pub fn screen_ray(&self, x: f32, y: f32, width: f32, height: f32) -> Ray {
+        self.ndc_ray(
+            2.0 * x / width.max(1.0) - 1.0,
+            1.0 - 2.0 * y / height.max(1.0),
+        )
+    }
+
+    /// Returns the ray through a point in normalized device coordinates, e.g. from
+    /// `input::mouse_ndc`.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - The horizontal coordinate, from `-1.0` at the left edge to `1.0` at the right.
+    /// * `y` - The vertical coordinate, from `-1.0` at the bottom edge to `1.0` at the top.
+    pub fn ndc_ray(&self, x: f32, y: f32) -> Ray {
+        let inverse = (self.projection_matrix() * self.view_matrix())
+            .invert()
+            .unwrap_or_else(Matrix4::identity);
+        let unproject = |z: f32| {
+            let point = inverse * Vector4::new(x, y, z, 1.0);
+            Point3::from_homogeneous(point)
+        };
+        let near = unproject(-1.0);
+        let far = unproject(1.0);
+        Ray::new(near, far - near)
+    }
+
+    /// Returns the ray under the mouse cursor, for a camera covering the whole window.
+    ///
+    /// # Arguments
+    ///
+    /// * `window` - The window the camera renders to.
+    #[cfg(feature = "native")]
+    pub fn cursor_ray(&self, window: &Window) -> Ray {
+        let (x, y) = input::mouse_ndc(window);
+        self.ndc_ray(x, y)
+    }
+
+    /// Returns the vertical field of view, or `None` for orthographic cameras.
+    pub fn fov(&self) -> Option<Deg<f32>> {
+        match self.camera_type {
+            CameraType::Perspective { fov, .. } => Some(fov),
+            CameraType::Orthographic { .. } => None,
+        }
+    }
+
+    /// Sets the vertical field of view. Does nothing for orthographic cameras.
+    ///
+    /// # Arguments
+    ///
+    /// * `new_fov` - The new field of view.
+    pub fn set_fov(&mut self, new_fov: Deg<f32>) {
+        if let CameraType::Perspective { fov, .. } = &mut self.camera_type {
+            *fov = new_fov;
+        }
+    }
+
     /// Returns the projection matrix for the camera.
     ///
     /// The projection matrix transforms camera coordinates into normalized device coordinates.
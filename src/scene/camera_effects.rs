@@ -0,0 +1,175 @@
+//! # Camera Effects Module
+//!
+//! This module provides procedural camera effects: trauma-based screen shake, short field of
+//! view kicks and smooth zoom transitions. The effects are applied on top of whatever a camera
+//! controller, such as `FollowCamera`, wrote into the `Camera` this frame.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::scene::{Camera, CameraEffects, FollowCamera};
+//! use glwfr::cgmath::{Deg, Point3, Quaternion};
+//!
+//! fn update(camera: &mut Camera, follow: &mut FollowCamera, effects: &mut CameraEffects, delta_time: f32) {
+//!     // On an explosion nearby
+//!     effects.add_trauma(0.6);
+//!     // When sprinting starts
+//!     effects.kick_fov(Deg(8.0));
+//!     // When aiming down sights
+//!     effects.zoom_to(Deg(30.0), 0.25);
+//!
+//!     // Once per frame, after the controller
+//!     follow.update(camera, Point3::new(0.0, 0.0, 0.0), Quaternion::new(1.0, 0.0, 0.0, 0.0), delta_time);
+//!     effects.update(delta_time);
+//!     effects.apply(camera);
+//! }
+//! ```
+
+use crate::math::noise::{Noise, Simplex};
+use crate::scene::Camera;
+use cgmath::*;
+
+/// Trauma-based camera shake, field of view kicks and zoom transitions.
+pub struct CameraEffects {
+    /// The largest positional offset along each camera axis, reached at full trauma.
+    pub max_offset: Vector3<f32>,
+    /// The largest yaw, pitch and roll, reached at full trauma.
+    pub max_rotation: Vector3<Deg<f32>>,
+    /// How much trauma is lost per second.
+    pub trauma_decay: f32,
+    /// How fast the shake moves, in noise samples per second.
+    pub frequency: f32,
+    /// How fast a field of view kick returns to zero, in seconds.
+    pub kick_recovery: f32,
+    noise: Simplex,
+    trauma: f32,
+    time: f32,
+    base_fov: Deg<f32>,
+    zoom_from: Deg<f32>,
+    zoom_to: Deg<f32>,
+    zoom_duration: f32,
+    zoom_elapsed: f32,
+    kick: Deg<f32>,
+}
+
+impl CameraEffects {
+    /// Creates camera effects for a camera with the given unzoomed field of view.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_fov` - The field of view used when no zoom or kick is active.
+    pub fn new(base_fov: Deg<f32>) -> Self {
+        Self {
+            max_offset: Vector3::new(0.3, 0.3, 0.1),
+            max_rotation: Vector3::new(Deg(2.0), Deg(2.0), Deg(4.0)),
+            trauma_decay: 1.0,
+            frequency: 20.0,
+            kick_recovery: 0.3,
+            noise: Simplex::new(0x5EED),
+            trauma: 0.0,
+            time: 0.0,
+            base_fov,
+            zoom_from: base_fov,
+            zoom_to: base_fov,
+            zoom_duration: 0.0,
+            zoom_elapsed: 0.0,
+            kick: Deg(0.0),
+        }
+    }
+
+    /// Adds trauma, clamped to `1.0`. The shake strength is the square of the trauma, so small
+    /// hits barely move the camera while large ones shake it hard.
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).clamp(0.0, 1.0);
+    }
+
+    /// Returns the current trauma in `[0, 1]`.
+    pub fn trauma(&self) -> f32 {
+        self.trauma
+    }
+
+    /// Adds a short field of view kick that eases back to zero over `kick_recovery` seconds.
+    pub fn kick_fov(&mut self, amount: Deg<f32>) {
+        self.kick += amount;
+    }
+
+    /// Smoothly changes the field of view to `fov` over `duration` seconds.
+    pub fn zoom_to(&mut self, fov: Deg<f32>, duration: f32) {
+        self.zoom_from = self.zoomed_fov();
+        self.zoom_to = fov;
+        self.zoom_duration = duration.max(0.0);
+        self.zoom_elapsed = 0.0;
+    }
+
+    /// Smoothly returns to the base field of view over `duration` seconds.
+    pub fn reset_zoom(&mut self, duration: f32) {
+        self.zoom_to(self.base_fov, duration);
+    }
+
+    /// Returns the field of view the effects currently produce.
+    pub fn fov(&self) -> Deg<f32> {
+        self.zoomed_fov() + self.kick
+    }
+
+    /// Advances the effects. Call this once per frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `delta_time` - The time since the previous update in seconds.
+    pub fn update(&mut self, delta_time: f32) {
+        let delta_time = delta_time.max(0.0);
+        self.time += delta_time;
+        self.trauma = (self.trauma - self.trauma_decay * delta_time).max(0.0);
+        self.zoom_elapsed = (self.zoom_elapsed + delta_time).min(self.zoom_duration);
+        self.kick = if self.kick_recovery > 0.0 {
+            self.kick * (-delta_time * 4.0 / self.kick_recovery).exp()
+        } else {
+            Deg(0.0)
+        };
+    }
+
+    /// Applies the shake and field of view to the camera.
+    ///
+    /// The shake offsets the camera's position and target, so the camera controller must write
+    /// them again every frame before this is called.
+    pub fn apply(&self, camera: &mut Camera) {
+        camera.set_fov(self.fov());
+
+        let shake = self.trauma * self.trauma;
+        if shake <= 0.0 {
+            return;
+        }
+        let t = self.time * self.frequency;
+        let sample = |channel: f32| self.noise.sample2(t, channel * 17.0) * shake;
+
+        let forward = camera.target - camera.position;
+        let distance = forward.magnitude();
+        if distance <= f32::EPSILON {
+            return;
+        }
+        let forward = forward / distance;
+        let right = forward.cross(camera.up).normalize();
+        let up = right.cross(forward);
+
+        let offset = right * (self.max_offset.x * sample(0.0))
+            + up * (self.max_offset.y * sample(1.0))
+            + forward * (self.max_offset.z * sample(2.0));
+        let rotation = Quaternion::from_axis_angle(up, self.max_rotation.x * sample(3.0))
+            * Quaternion::from_axis_angle(right, self.max_rotation.y * sample(4.0))
+            * Quaternion::from_axis_angle(forward, self.max_rotation.z * sample(5.0));
+
+        camera.position += offset;
+        camera.target = camera.position + rotation.rotate_vector(forward) * distance;
+        camera.up = rotation.rotate_vector(up);
+    }
+
+    /// Returns the field of view of the zoom transition, without the kick.
+    fn zoomed_fov(&self) -> Deg<f32> {
+        if self.zoom_duration <= 0.0 {
+            return self.zoom_to;
+        }
+        let t = self.zoom_elapsed / self.zoom_duration;
+        let eased = t * t * (3.0 - 2.0 * t);
+        self.zoom_from + (self.zoom_to - self.zoom_from) * eased
+    }
+}
@@ -0,0 +1,182 @@
+//! # Environment Module
+//!
+//! This module provides scene-wide environment settings: ambient light, an optional
+//! environment map, fog, wind, the wetness of surfaces and the background color.
+//!
+//! `Scene::render` uploads the settings to the shader of every object. Shaders only need to
+//! declare the uniforms they use:
+//!
+//! | Uniform                | Type        | Value                                              |
+//! |------------------------|-------------|----------------------------------------------------|
+//! | `ambientColor`         | `vec3`      | The ambient color multiplied by its intensity      |
+//! | `hasEnvironmentMap`    | `int`       | `1` if an environment map is set, otherwise `0`    |
+//! | `environmentMap`       | `sampler2D` | An equirectangular environment map                 |
+//! | `fogMode`              | `int`       | `0` none, `1` linear, `2` exponential, `3` exp²    |
+//! | `fogColor`             | `vec3`      | The fog color                                      |
+//! | `fogStart`, `fogEnd`   | `float`     | The linear fog range                               |
+//! | `fogDensity`           | `float`     | The exponential fog density                        |
+//! | `windStrength`, ...    | see `Wind`  | The wind of `WIND_GLSL`, zero strength without one |
+//! | `wetness`              | `float`     | The wetness of `WETNESS_GLSL`, `0.0` when dry      |
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::scene::{Fog, FogMode, SceneEnvironment};
+//! use glwfr::cgmath::Vector3;
+//!
+//! let mut environment = SceneEnvironment::default();
+//! environment.ambient_color = Vector3::new(0.6, 0.7, 1.0);
+//! environment.ambient_intensity = 0.3;
+//! environment.fog = Some(Fog {
+//!     mode: FogMode::Linear { start: 10.0, end: 50.0 },
+//!     color: Vector3::new(0.5, 0.5, 0.6),
+//! });
+//! environment.background_color = [0.5, 0.5, 0.6, 1.0];
+//!
+//! // scene.set_environment(environment);
+//! ```
+
+use crate::graphics::gl_wrapper::ShaderProgram;
+use crate::graphics::render_state::ClearState;
+use crate::graphics::texture::Texture;
+use crate::graphics::texture_unit::TextureUnit;
+use crate::scene::Wind;
+use cgmath::*;
+use std::rc::Rc;
+
+/// How fog density increases with distance from the camera.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FogMode {
+    /// Fog increases linearly between `start` and `end`.
+    Linear { start: f32, end: f32 },
+    /// Fog increases with `1 - exp(-density * distance)`.
+    Exponential { density: f32 },
+    /// Fog increases with `1 - exp(-(density * distance)²)`.
+    ExponentialSquared { density: f32 },
+}
+
+impl FogMode {
+    /// Returns the value of the `fogMode` uniform for this mode.
+    fn uniform_value(&self) -> i32 {
+        match self {
+            FogMode::Linear { .. } => 1,
+            FogMode::Exponential { .. } => 2,
+            FogMode::ExponentialSquared { .. } => 3,
+        }
+    }
+}
+
+/// Distance fog applied to the whole scene.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fog {
+    /// How the fog density increases with distance.
+    pub mode: FogMode,
+    /// The color of the fog.
+    pub color: Vector3<f32>,
+}
+
+/// Scene-wide lighting and background settings.
+pub struct SceneEnvironment {
+    /// The color of the ambient light.
+    pub ambient_color: Vector3<f32>,
+    /// The intensity of the ambient light.
+    pub ambient_intensity: f32,
+    /// An optional equirectangular environment map for reflections and image-based lighting.
+    pub environment_map: Option<Rc<Texture>>,
+    /// The texture unit the environment map is bound to.
+    pub environment_map_unit: TextureUnit,
+    /// The fog applied to the scene, or `None` for no fog.
+    pub fog: Option<Fog>,
+    /// The wind swaying foliage, or `None` for still air.
+    pub wind: Option<Wind>,
+    /// How wet surfaces are, from `0.0` (dry) to `1.0` (soaked). See `WETNESS_GLSL`.
+    pub wetness: f32,
+    /// The color the background is cleared to as `[r, g, b, a]`.
+    pub background_color: [f32; 4],
+}
+
+impl SceneEnvironment {
+    /// Returns the ambient color multiplied by its intensity.
+    pub fn ambient(&self) -> Vector3<f32> {
+        self.ambient_color * self.ambient_intensity
+    }
+
+    /// Upload the environment settings to the given shader program.
+    ///
+    /// The shader program must be bound before calling this function. Uniforms the shader
+    /// does not declare are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `shader_program` - The shader program to upload the settings to.
+    pub fn apply(&self, shader_program: &ShaderProgram) {
+        shader_program.try_set_uniform("ambientColor", self.ambient());
+
+        match &self.environment_map {
+            Some(texture) => {
+                texture.bind_to_unit(self.environment_map_unit);
+                shader_program.try_set_uniform("hasEnvironmentMap", 1);
+                shader_program
+                    .try_set_uniform("environmentMap", self.environment_map_unit.index() as i32);
+            }
+            None => {
+                shader_program.try_set_uniform("hasEnvironmentMap", 0);
+            }
+        }
+
+        match &self.fog {
+            Some(fog) => {
+                shader_program.try_set_uniform("fogMode", fog.mode.uniform_value());
+                shader_program.try_set_uniform("fogColor", fog.color);
+                match fog.mode {
+                    FogMode::Linear { start, end } => {
+                        shader_program.try_set_uniform("fogStart", start);
+                        shader_program.try_set_uniform("fogEnd", end);
+                    }
+                    FogMode::Exponential { density } | FogMode::ExponentialSquared { density } => {
+                        shader_program.try_set_uniform("fogDensity", density);
+                    }
+                }
+            }
+            None => {
+                shader_program.try_set_uniform("fogMode", 0);
+            }
+        }
+
+        match &self.wind {
+            Some(wind) => wind.apply(shader_program),
+            None => {
+                shader_program.try_set_uniform("windStrength", 0.0);
+                shader_program.try_set_uniform("windGustStrength", 0.0);
+            }
+        }
+
+        shader_program.try_set_uniform("wetness", self.wetness.clamp(0.0, 1.0));
+    }
+
+    /// Clear the color and depth buffers, using the background color.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glClearColor` and `glClear`.
+    pub fn clear(&self) {
+        ClearState::color_and_depth(self.background_color).apply();
+    }
+}
+
+impl Default for SceneEnvironment {
+    /// A dim white ambient light, no environment map, no fog, no wind, dry surfaces and a black
+    /// background.
+    fn default() -> Self {
+        Self {
+            ambient_color: Vector3::new(1.0, 1.0, 1.0),
+            ambient_intensity: 0.1,
+            environment_map: None,
+            environment_map_unit: TextureUnit::new(15),
+            fog: None,
+            wind: None,
+            wetness: 0.0,
+            background_color: [0.0, 0.0, 0.0, 1.0],
+        }
+    }
+}
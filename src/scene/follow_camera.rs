@@ -0,0 +1,187 @@
+//! # Follow Camera Module
+//!
+//! This module provides a third-person camera controller that smoothly follows a moving
+//! target. Position and aim are smoothed with a critically damped spring, so the camera
+//! settles as fast as possible without overshooting.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::scene::{Camera, FollowCamera};
+//! use glwfr::math::{Aabb, Ray};
+//! use glwfr::cgmath::{Point3, Quaternion, Vector3};
+//!
+//! fn update(camera: &mut Camera, follow: &mut FollowCamera, walls: &[Aabb], delta_time: f32) {
+//!     let player_position = Point3::new(0.0, 0.0, 0.0);
+//!     let player_rotation = Quaternion::new(1.0, 0.0, 0.0, 0.0);
+//!
+//!     // Pull the camera in front of walls between it and the player
+//!     follow.update_with_collision(camera, player_position, player_rotation, delta_time, |ray| {
+//!         walls.iter().filter_map(|wall| ray.intersect_aabb(wall)).reduce(f32::min)
+//!     });
+//! }
+//! ```
+
+use crate::math::Ray;
+use crate::scene::Camera;
+use cgmath::*;
+
+/// A third-person camera controller with critically damped smoothing.
+#[derive(Debug, Clone)]
+pub struct FollowCamera {
+    /// The camera position relative to the target, in the target's local space.
+    pub offset: Vector3<f32>,
+    /// The point the camera looks at relative to the target, in the target's local space.
+    pub look_offset: Vector3<f32>,
+    /// The time in seconds the camera position needs to catch up with the target. `0.0`
+    /// follows rigidly.
+    pub position_smoothing: f32,
+    /// The time in seconds the aim needs to catch up with the target. `0.0` aims rigidly.
+    pub rotation_smoothing: f32,
+    /// How many seconds ahead of the target's movement the camera looks.
+    pub look_ahead: f32,
+    /// The distance kept between the camera and obstacles when avoiding collisions.
+    pub collision_margin: f32,
+    position: Option<Point3<f32>>,
+    position_velocity: Vector3<f32>,
+    look_point: Option<Point3<f32>>,
+    look_velocity: Vector3<f32>,
+    last_target: Option<Point3<f32>>,
+}
+
+impl FollowCamera {
+    /// Creates a controller keeping the camera at `offset` behind and above the target.
+    pub fn new(offset: Vector3<f32>) -> Self {
+        Self {
+            offset,
+            look_offset: Vector3::new(0.0, 1.0, 0.0),
+            position_smoothing: 0.2,
+            rotation_smoothing: 0.1,
+            look_ahead: 0.0,
+            collision_margin: 0.2,
+            position: None,
+            position_velocity: Vector3::zero(),
+            look_point: None,
+            look_velocity: Vector3::zero(),
+            last_target: None,
+        }
+    }
+
+    /// Jumps to the desired position on the next update, e.g. after teleporting the target.
+    pub fn reset(&mut self) {
+        self.position = None;
+        self.look_point = None;
+        self.last_target = None;
+        self.position_velocity = Vector3::zero();
+        self.look_velocity = Vector3::zero();
+    }
+
+    /// Moves the camera towards its desired position behind the target.
+    ///
+    /// Call this once per frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `camera` - The camera to update.
+    /// * `target_position` - The world-space position of the followed object.
+    /// * `target_rotation` - The rotation of the followed object, which rotates the offsets.
+    /// * `delta_time` - The time since the previous update in seconds.
+    pub fn update(
+        &mut self,
+        camera: &mut Camera,
+        target_position: Point3<f32>,
+        target_rotation: Quaternion<f32>,
+        delta_time: f32,
+    ) {
+        self.update_with_collision(camera, target_position, target_rotation, delta_time, |_| {
+            None
+        });
+    }
+
+    /// Like `update`, but keeps obstacles from coming between the camera and the target.
+    ///
+    /// # Arguments
+    ///
+    /// * `raycast` - Returns the distance along the ray to the closest obstacle, if any. The
+    ///   ray starts at the look point and points towards the camera; hits further away than
+    ///   the camera are ignored.
+    pub fn update_with_collision(
+        &mut self,
+        camera: &mut Camera,
+        target_position: Point3<f32>,
+        target_rotation: Quaternion<f32>,
+        delta_time: f32,
+        raycast: impl Fn(&Ray) -> Option<f32>,
+    ) {
+        let delta_time = delta_time.max(0.0);
+        let velocity = match self.last_target {
+            Some(last) if delta_time > 0.0 => (target_position - last) / delta_time,
+            _ => Vector3::zero(),
+        };
+        self.last_target = Some(target_position);
+
+        let desired_look = target_position
+            + target_rotation.rotate_vector(self.look_offset)
+            + velocity * self.look_ahead;
+        let mut desired_position = target_position + target_rotation.rotate_vector(self.offset);
+
+        // Pull the camera in front of obstacles between it and the look point
+        let to_camera = desired_position - desired_look;
+        let distance = to_camera.magnitude();
+        if distance > f32::EPSILON {
+            let ray = Ray::new(desired_look, to_camera);
+            if let Some(hit) = raycast(&ray).filter(|&hit| hit < distance) {
+                desired_position = ray.at((hit - self.collision_margin).max(0.0));
+                // Snap inwards immediately so the camera never clips through the obstacle
+                if let Some(position) = self.position {
+                    if position.distance(desired_look) > desired_position.distance(desired_look) {
+                        self.position = Some(desired_position);
+                        self.position_velocity = Vector3::zero();
+                    }
+                }
+            }
+        }
+
+        let position = smooth_damp(
+            self.position.unwrap_or(desired_position),
+            desired_position,
+            &mut self.position_velocity,
+            self.position_smoothing,
+            delta_time,
+        );
+        let look_point = smooth_damp(
+            self.look_point.unwrap_or(desired_look),
+            desired_look,
+            &mut self.look_velocity,
+            self.rotation_smoothing,
+            delta_time,
+        );
+        self.position = Some(position);
+        self.look_point = Some(look_point);
+
+        camera.position = position;
+        camera.target = look_point;
+    }
+}
+
+/// Moves `current` towards `target` like a critically damped spring reaching it in about
+/// `smooth_time` seconds.
+fn smooth_damp(
+    current: Point3<f32>,
+    target: Point3<f32>,
+    velocity: &mut Vector3<f32>,
+    smooth_time: f32,
+    delta_time: f32,
+) -> Point3<f32> {
+    if smooth_time <= 0.0 {
+        *velocity = Vector3::zero();
+        return target;
+    }
+    let omega = 2.0 / smooth_time;
+    let x = omega * delta_time;
+    let decay = 1.0 / (1.0 + x + 0.48 * x * x + 0.235 * x * x * x);
+    let change = current - target;
+    let temp = (*velocity + change * omega) * delta_time;
+    *velocity = (*velocity - temp * omega) * decay;
+    target + (change + temp) * decay
+}
@@ -0,0 +1,578 @@
+//! # Gizmo Module
+//!
+//! This module provides manipulation gizmos for editing the transform of a selected object
+//! with the mouse: arrows for moving it along an axis, rings for rotating it around an axis
+//! and boxes for scaling it along an axis. The handles keep the same size on screen however
+//! far the object is from the camera, and are drawn on top of the scene.
+//!
+//! The gizmo is driven by rays from `Camera::screen_ray`, so it works with any input source
+//! and viewport.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::scene::{Camera, Gizmo, GizmoMode, Object};
+//!
+//! fn edit(gizmo: &mut Gizmo, camera: &Camera, selected: &mut Object, mouse: (f32, f32), pressed: bool) {
+//!     // Switch modes with keys, e.g. W, E and R
+//!     gizmo.mode = GizmoMode::Rotate;
+//!
+//!     let ray = camera.screen_ray(mouse.0, mouse.1, 1280.0, 720.0);
+//!     let consumed = gizmo.handle_mouse(camera, &ray, pressed, &mut selected.transform);
+//!     if !consumed {
+//!         // Use the click for picking objects instead
+//!     }
+//!
+//!     // After the scene was rendered
+//!     gizmo.render(camera, &selected.transform);
+//! }
+//! ```
+
+use crate::custom_errors::Errors;
+use crate::graphics::gl_wrapper::{
+    self, BufferObject, PrimitiveType, ShaderProgram, Vao, Vertex, VertexAttributeLayout,
+};
+use crate::graphics::render_state::RenderState;
+use crate::graphics::standard_vertex::{COLOR_ATTRIBUTE, POSITION_ATTRIBUTE};
+use crate::graphics::stats;
+use crate::math::{Plane, Ray};
+use crate::scene::{Camera, Transform};
+use cgmath::*;
+use std::mem;
+
+const GIZMO_VERTEX_SHADER: &str = r#"#version 330 core
+layout (location = 0) in vec3 aPosition;
+layout (location = 6) in vec4 aColor;
+uniform mat4 viewProjection;
+out vec4 color;
+
+void main() {
+    color = aColor;
+    gl_Position = viewProjection * vec4(aPosition, 1.0);
+}
+"#;
+
+const GIZMO_FRAGMENT_SHADER: &str = r#"#version 330 core
+in vec4 color;
+out vec4 fragColor;
+
+void main() {
+    fragColor = color;
+}
+"#;
+
+/// The number of line segments of a rotation ring.
+const RING_SEGMENTS: usize = 48;
+/// The length of the arrow tips and the size of the scale boxes, relative to the handle length.
+const TIP_SIZE: f32 = 0.12;
+/// The distance from a handle within which it can be grabbed, relative to the handle length.
+const PICK_TOLERANCE: f32 = 0.08;
+
+const HIGHLIGHT_COLOR: [u8; 4] = [255, 220, 40, 255];
+
+/// What dragging a handle changes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum GizmoMode {
+    /// Arrows moving the object along an axis.
+    #[default]
+    Translate,
+    /// Rings rotating the object around an axis.
+    Rotate,
+    /// Boxes scaling the object along one of its local axes.
+    Scale,
+}
+
+/// The axes handles are aligned to. Scale handles always use the local axes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum GizmoSpace {
+    /// The world axes.
+    #[default]
+    World,
+    /// The axes of the object's rotation.
+    Local,
+}
+
+/// One of the three handles of a gizmo.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GizmoAxis {
+    X,
+    Y,
+    Z,
+}
+
+impl GizmoAxis {
+    /// All axes, in order.
+    pub const ALL: [GizmoAxis; 3] = [GizmoAxis::X, GizmoAxis::Y, GizmoAxis::Z];
+
+    /// Returns the index of the axis, `0` for x.
+    pub fn index(&self) -> usize {
+        match self {
+            GizmoAxis::X => 0,
+            GizmoAxis::Y => 1,
+            GizmoAxis::Z => 2,
+        }
+    }
+
+    /// Returns the unit vector of the axis.
+    pub fn unit(&self) -> Vector3<f32> {
+        match self {
+            GizmoAxis::X => Vector3::unit_x(),
+            GizmoAxis::Y => Vector3::unit_y(),
+            GizmoAxis::Z => Vector3::unit_z(),
+        }
+    }
+
+    /// Returns the color of the handle, red, green or blue.
+    fn color(&self) -> [u8; 4] {
+        match self {
+            GizmoAxis::X => [230, 60, 60, 255],
+            GizmoAxis::Y => [80, 210, 80, 255],
+            GizmoAxis::Z => [70, 110, 240, 255],
+        }
+    }
+}
+
+/// A vertex of the gizmo lines.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+struct GizmoVertex {
+    position: [f32; 3],
+    color: [u8; 4],
+}
+
+impl Vertex for GizmoVertex {
+    fn layout() -> Vec<VertexAttributeLayout> {
+        vec![
+            VertexAttributeLayout {
+                index: POSITION_ATTRIBUTE,
+                size: 3,
+                r#type: gl::FLOAT,
+                normalized: gl::FALSE,
+                offset: mem::offset_of!(GizmoVertex, position),
+            },
+            VertexAttributeLayout {
+                index: COLOR_ATTRIBUTE,
+                size: 4,
+                r#type: gl::UNSIGNED_BYTE,
+                normalized: gl::TRUE,
+                offset: mem::offset_of!(GizmoVertex, color),
+            },
+        ]
+    }
+}
+
+/// The state captured when a drag starts.
+#[derive(Debug, Clone, Copy)]
+struct GizmoDrag {
+    axis: GizmoAxis,
+    mode: GizmoMode,
+    /// The world space direction of the handle.
+    direction: Vector3<f32>,
+    pivot: Point3<f32>,
+    handle_length: f32,
+    /// The position along the axis where a translate or scale handle was grabbed.
+    start_parameter: f32,
+    /// The direction from the pivot to where a ring was grabbed.
+    start_vector: Vector3<f32>,
+    start_position: Vector3<f32>,
+    start_rotation: Quaternion<f32>,
+    start_scale: Vector3<f32>,
+}
+
+/// Translate, rotate and scale handles for the transform of a selected object.
+pub struct Gizmo {
+    shader_program: ShaderProgram,
+    vao: Vao,
+    vbo: BufferObject,
+    hovered: Option<GizmoAxis>,
+    drag: Option<GizmoDrag>,
+    was_pressed: bool,
+    /// What dragging a handle changes.
+    pub mode: GizmoMode,
+    /// The axes translate and rotate handles are aligned to.
+    pub space: GizmoSpace,
+    /// The length of the handles as a fraction of the viewport height, e.g. `0.15`.
+    pub size: f32,
+    /// The width of the handle lines in pixels.
+    pub line_width: f32,
+}
+
+impl Gizmo {
+    /// Creates a translate gizmo in world space.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::ShaderCompilationError` if the line shader fails to compile, or an
+    /// `Errors::OpenGlError` if the buffers cannot be created.
+    pub fn new() -> Result<Self, Errors> {
+        let vao = Vao::new()?;
+        vao.bind();
+        let vbo = BufferObject::new(gl::ARRAY_BUFFER, gl::DYNAMIC_DRAW)?;
+        vbo.bind();
+        GizmoVertex::setup_attributes();
+        vao.unbind();
+        Ok(Self {
+            shader_program: ShaderProgram::from_source(GIZMO_VERTEX_SHADER, GIZMO_FRAGMENT_SHADER)?,
+            vao,
+            vbo,
+            hovered: None,
+            drag: None,
+            was_pressed: false,
+            mode: GizmoMode::Translate,
+            space: GizmoSpace::World,
+            size: 0.15,
+            line_width: 2.0,
+        })
+    }
+
+    /// Returns the handle under the mouse, as found by the last `handle_mouse`.
+    pub fn hovered_axis(&self) -> Option<GizmoAxis> {
+        self.hovered
+    }
+
+    /// Returns the handle being dragged.
+    pub fn active_axis(&self) -> Option<GizmoAxis> {
+        self.drag.map(|drag| drag.axis)
+    }
+
+    /// Returns `true` while a handle is being dragged.
+    pub fn is_dragging(&self) -> bool {
+        self.drag.is_some()
+    }
+
+    /// Returns the world space directions of the handles for a transform.
+    fn axes(&self, transform: &Transform) -> [Vector3<f32>; 3] {
+        let local = self.mode == GizmoMode::Scale || self.space == GizmoSpace::Local;
+        GizmoAxis::ALL.map(|axis| {
+            if local {
+                transform.rotation().rotate_vector(axis.unit())
+            } else {
+                axis.unit()
+            }
+        })
+    }
+
+    /// Returns the length of the handles in world units at the given position, so they cover
+    /// `size` of the viewport height.
+    fn handle_length(&self, camera: &Camera, pivot: Point3<f32>) -> f32 {
+        let projection = camera.projection_matrix();
+        let clip = projection * camera.view_matrix() * pivot.to_homogeneous();
+        let scale = projection.y.y.abs().max(f32::EPSILON);
+        (self.size * 2.0 * clip.w.abs() / scale).max(f32::EPSILON)
+    }
+
+    /// Finds the handle hit by a ray, e.g. from `Camera::screen_ray`.
+    ///
+    /// # Arguments
+    ///
+    /// * `camera` - The camera the gizmo is seen through.
+    /// * `ray` - The ray to test.
+    /// * `transform` - The transform of the selected object.
+    ///
+    /// # Returns
+    ///
+    /// The closest handle along the ray, or `None` if the ray misses all handles.
+    pub fn pick(&self, camera: &Camera, ray: &Ray, transform: &Transform) -> Option<GizmoAxis> {
+        let pivot = Point3::from_vec(transform.position());
+        let length = self.handle_length(camera, pivot);
+        let tolerance = length * PICK_TOLERANCE;
+        let axes = self.axes(transform);
+
+        let mut best: Option<(GizmoAxis, f32)> = None;
+        for axis in GizmoAxis::ALL {
+            let direction = axes[axis.index()];
+            let hit = match self.mode {
+                GizmoMode::Translate | GizmoMode::Scale => closest_points(ray, pivot, direction)
+                    .and_then(|(along_ray, along_axis)| {
+                        let on_axis = pivot + direction * along_axis;
+                        let hit = along_ray >= 0.0
+                            && (0.0..=length * (1.0 + TIP_SIZE)).contains(&along_axis)
+                            && ray.at(along_ray).distance(on_axis) <= tolerance;
+                        hit.then_some(along_ray)
+                    }),
+                GizmoMode::Rotate => ray
+                    .intersect_plane(&Plane::from_point_normal(pivot, direction))
+                    .filter(|&distance| {
+                        (ray.at(distance).distance(pivot) - length).abs() <= tolerance
+                    }),
+            };
+            if let Some(distance) = hit {
+                if best.is_none_or(|(_, best_distance)| distance < best_distance) {
+                    best = Some((axis, distance));
+                }
+            }
+        }
+        best.map(|(axis, _)| axis)
+    }
+
+    /// Starts dragging the handle hit by the ray.
+    ///
+    /// # Returns
+    ///
+    /// `true` if a handle was hit and the drag started.
+    pub fn begin_drag(&mut self, camera: &Camera, ray: &Ray, transform: &Transform) -> bool {
+        let Some(axis) = self.pick(camera, ray, transform) else {
+            return false;
+        };
+        let pivot = Point3::from_vec(transform.position());
+        let direction = self.axes(transform)[axis.index()];
+        let mut drag = GizmoDrag {
+            axis,
+            mode: self.mode,
+            direction,
+            pivot,
+            handle_length: self.handle_length(camera, pivot),
+            start_parameter: 0.0,
+            start_vector: Vector3::zero(),
+            start_position: transform.position(),
+            start_rotation: transform.rotation(),
+            start_scale: transform.scale(),
+        };
+        match self.mode {
+            GizmoMode::Translate | GizmoMode::Scale => {
+                let Some((_, along_axis)) = closest_points(ray, pivot, direction) else {
+                    return false;
+                };
+                drag.start_parameter = along_axis;
+            }
+            GizmoMode::Rotate => {
+                let Some(vector) = ring_vector(ray, pivot, direction) else {
+                    return false;
+                };
+                drag.start_vector = vector;
+            }
+        }
+        self.drag = Some(drag);
+        true
+    }
+
+    /// Applies the current drag to the transform.
+    ///
+    /// Translations move the object along the handle, scaling multiplies the scale of the
+    /// axis by the distance dragged relative to the handle length, and rotations turn the
+    /// object by the angle swept around the ring. Rays parallel to the handle leave the
+    /// transform unchanged.
+    ///
+    /// # Returns
+    ///
+    /// `true` if a drag is active.
+    pub fn drag(&mut self, ray: &Ray, transform: &mut Transform) -> bool {
+        let Some(drag) = self.drag else {
+            return false;
+        };
+        match drag.mode {
+            GizmoMode::Translate => {
+                if let Some((_, along_axis)) = closest_points(ray, drag.pivot, drag.direction) {
+                    let offset = drag.direction * (along_axis - drag.start_parameter);
+                    transform.set_position(drag.start_position + offset);
+                }
+            }
+            GizmoMode::Scale => {
+                if let Some((_, along_axis)) = closest_points(ray, drag.pivot, drag.direction) {
+                    let factor = 1.0 + (along_axis - drag.start_parameter) / drag.handle_length;
+                    let mut scale = drag.start_scale;
+                    scale[drag.axis.index()] *= factor.max(0.01);
+                    transform.set_scale(scale);
+                }
+            }
+            GizmoMode::Rotate => {
+                if let Some(vector) = ring_vector(ray, drag.pivot, drag.direction) {
+                    let angle = drag
+                        .direction
+                        .dot(drag.start_vector.cross(vector))
+                        .atan2(drag.start_vector.dot(vector));
+                    let rotation = Quaternion::from_axis_angle(drag.direction, Rad(angle));
+                    transform.set_rotation((rotation * drag.start_rotation).normalize());
+                }
+            }
+        }
+        true
+    }
+
+    /// Finishes the current drag, keeping the edited transform.
+    pub fn end_drag(&mut self) {
+        self.drag = None;
+    }
+
+    /// Aborts the current drag and restores the transform it started with.
+    pub fn cancel_drag(&mut self, transform: &mut Transform) {
+        if let Some(drag) = self.drag.take() {
+            transform.set_position(drag.start_position);
+            transform.set_rotation(drag.start_rotation);
+            transform.set_scale(drag.start_scale);
+        }
+    }
+
+    /// Updates hovering and dragging from the mouse. Call this once per frame.
+    ///
+    /// A drag starts when the button is pressed over a handle and ends when it is released.
+    ///
+    /// # Arguments
+    ///
+    /// * `camera` - The camera the gizmo is seen through.
+    /// * `ray` - The ray under the mouse, from `Camera::screen_ray`.
+    /// * `pressed` - Whether the mouse button used for dragging is held down.
+    /// * `transform` - The transform of the selected object.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the gizmo used the mouse, so the caller should not handle it, e.g. for
+    /// picking other objects.
+    pub fn handle_mouse(
+        &mut self,
+        camera: &Camera,
+        ray: &Ray,
+        pressed: bool,
+        transform: &mut Transform,
+    ) -> bool {
+        let just_pressed = pressed && !self.was_pressed;
+        self.was_pressed = pressed;
+
+        if self.is_dragging() {
+            if pressed {
+                self.drag(ray, transform);
+            } else {
+                self.end_drag();
+            }
+            return true;
+        }
+        self.hovered = self.pick(camera, ray, transform);
+        just_pressed && self.begin_drag(camera, ray, transform)
+    }
+
+    /// Draws the handles for a transform on top of the scene.
+    ///
+    /// The hovered or dragged handle is highlighted. Depth testing is disabled while drawing;
+    /// afterwards the default `RenderState` is applied.
+    ///
+    /// # Arguments
+    ///
+    /// * `camera` - The camera the scene was rendered with.
+    /// * `transform` - The transform of the selected object.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glBufferData`, `glLineWidth` and `glDrawArrays`.
+    pub fn render(&mut self, camera: &Camera, transform: &Transform) {
+        let pivot = Point3::from_vec(transform.position());
+        let length = self.handle_length(camera, pivot);
+        let axes = self.axes(transform);
+        let highlighted = self.active_axis().or(self.hovered);
+
+        let mut vertices = Vec::new();
+        for axis in GizmoAxis::ALL {
+            let color = if highlighted == Some(axis) {
+                HIGHLIGHT_COLOR
+            } else {
+                axis.color()
+            };
+            let direction = axes[axis.index()];
+            let (u, v) = perpendicular_basis(direction);
+            let mut line = |a: Point3<f32>, b: Point3<f32>| {
+                vertices.push(GizmoVertex {
+                    position: a.into(),
+                    color,
+                });
+                vertices.push(GizmoVertex {
+                    position: b.into(),
+                    color,
+                });
+            };
+            let tip_size = length * TIP_SIZE;
+            match self.mode {
+                GizmoMode::Translate => {
+                    let tip = pivot + direction * length;
+                    let base = tip - direction * tip_size;
+                    line(pivot, base);
+                    let corners = [u, v, -u, -v].map(|offset| base + offset * tip_size * 0.4);
+                    for (i, &corner) in corners.iter().enumerate() {
+                        line(corner, tip);
+                        line(corner, corners[(i + 1) % corners.len()]);
+                    }
+                }
+                GizmoMode::Rotate => {
+                    let point = |i: usize| {
+                        let angle = i as f32 / RING_SEGMENTS as f32 * std::f32::consts::TAU;
+                        pivot + (u * angle.cos() + v * angle.sin()) * length
+                    };
+                    for i in 0..RING_SEGMENTS {
+                        line(point(i), point(i + 1));
+                    }
+                }
+                GizmoMode::Scale => {
+                    let center = pivot + direction * length;
+                    line(pivot, center - direction * tip_size * 0.5);
+                    let half = [u, v, direction].map(|offset| offset * tip_size * 0.5);
+                    let corner = |i: usize| {
+                        let sign = |bit: usize| if i & bit != 0 { 1.0 } else { -1.0 };
+                        center + half[0] * sign(1) + half[1] * sign(2) + half[2] * sign(4)
+                    };
+                    for i in 0..8 {
+                        for bit in [1, 2, 4] {
+                            if i & bit == 0 {
+                                line(corner(i), corner(i | bit));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        RenderState {
+            depth: None,
+            blend: None,
+            cull: None,
+            depth_bias: None,
+        }
+        .apply();
+        self.shader_program.bind();
+        self.shader_program.try_set_uniform(
+            "viewProjection",
+            camera.projection_matrix() * camera.view_matrix(),
+        );
+        self.vao.bind();
+        self.vbo.bind();
+        self.vbo.allocate(mem::size_of_val(vertices.as_slice()));
+        self.vbo.update_data(0, &vertices);
+        gl_wrapper::set_line_width(self.line_width);
+        unsafe {
+            gl::DrawArrays(gl::LINES, 0, vertices.len() as i32);
+        }
+        stats::record_draw(PrimitiveType::Lines, vertices.len(), 1);
+        gl_wrapper::set_line_width(1.0);
+        self.vao.unbind();
+        RenderState::default().apply();
+    }
+}
+
+/// Returns two unit vectors perpendicular to `direction` and to each other.
+fn perpendicular_basis(direction: Vector3<f32>) -> (Vector3<f32>, Vector3<f32>) {
+    let helper = if direction.x.abs() < 0.9 {
+        Vector3::unit_x()
+    } else {
+        Vector3::unit_y()
+    };
+    let u = direction.cross(helper).normalize();
+    (u, direction.cross(u).normalize())
+}
+
+/// Returns the distances along the ray and along the axis line to the points where the two
+/// lines are closest, or `None` if they are parallel.
+fn closest_points(ray: &Ray, origin: Point3<f32>, direction: Vector3<f32>) -> Option<(f32, f32)> {
+    let offset = ray.origin - origin;
+    let b = ray.direction.dot(direction);
+    let d = ray.direction.dot(offset);
+    let e = direction.dot(offset);
+    let denominator = 1.0 - b * b;
+    if denominator <= 1e-6 {
+        return None;
+    }
+    Some(((b * e - d) / denominator, (e - b * d) / denominator))
+}
+
+/// Returns the unit direction from the pivot to where the ray crosses the plane of a ring.
+fn ring_vector(ray: &Ray, pivot: Point3<f32>, normal: Vector3<f32>) -> Option<Vector3<f32>> {
+    let plane = Plane::from_point_normal(pivot, normal);
+    let vector = ray.at(ray.intersect_plane(&plane)?) - pivot;
+    (vector.magnitude2() > f32::EPSILON).then(|| vector.normalize())
+}
@@ -0,0 +1,199 @@
+//! # glTF Animation Module
+//!
+//! This module imports the animations of glTF files into `Animation`s. It is available with
+//! the `gltf` feature.
+//!
+//! Every glTF channel becomes an `AnimationChannel` that targets the objects tagged with the
+//! name of the channel's node, or `node<index>` for unnamed nodes. Translation, rotation,
+//! scale and morph target weight channels are supported, with step, linear and cubic spline
+//! interpolation. Meshes and nodes are not imported; tag the objects created for the nodes
+//! to connect them with the animations.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::scene::{Animation, AnimationPlayer};
+//!
+//! fn load() -> Result<Vec<AnimationPlayer>, glwfr::custom_errors::Errors> {
+//!     let animations = Animation::from_gltf("models/door.glb")?;
+//!     Ok(animations.into_iter().map(AnimationPlayer::new).collect())
+//! }
+//! ```
+
+use crate::custom_errors::Errors;
+use crate::scene::{Animation, AnimationChannel, ChannelValues, Interpolation};
+use cgmath::{Quaternion, Vector3};
+use gltf::accessor::{DataType, Dimensions};
+use gltf::animation::util::ReadOutputs;
+use gltf::animation::{Channel, Property};
+use std::path::Path;
+
+impl Animation {
+    /// Loads all animations of a glTF (`.gltf` or `.glb`) file. External buffers are resolved
+    /// relative to the file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::AnimationLoadError` if the file cannot be read or parsed, or if a
+    /// sampler is malformed or stores its keyframes in an unsupported format.
+    pub fn from_gltf(path: &str) -> Result<Vec<Self>, Errors> {
+        let gltf = gltf::Gltf::open(path).map_err(gltf_error)?;
+        let base = Path::new(path).parent().unwrap_or(Path::new(""));
+        let buffers =
+            gltf::import_buffers(&gltf.document, Some(base), gltf.blob).map_err(gltf_error)?;
+        Self::from_gltf_document(&gltf.document, &buffers)
+    }
+
+    /// Converts the animations of a loaded glTF document, e.g. from `gltf::import`.
+    ///
+    /// # Arguments
+    ///
+    /// * `document` - The glTF document.
+    /// * `buffers` - The data of the document's buffers, in order.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::AnimationLoadError` if a sampler is malformed or stores its
+    /// keyframes in an unsupported format.
+    pub fn from_gltf_document(
+        document: &gltf::Document,
+        buffers: &[gltf::buffer::Data],
+    ) -> Result<Vec<Self>, Errors> {
+        document
+            .animations()
+            .map(|animation| {
+                let name = animation
+                    .name()
+                    .map_or_else(|| format!("animation{}", animation.index()), str::to_string);
+                let channels = animation
+                    .channels()
+                    .map(|channel| convert_channel(&channel, buffers))
+                    .collect::<Result<_, _>>()
+                    .map_err(|error| match error {
+                        Errors::AnimationLoadError(message) => {
+                            Errors::AnimationLoadError(format!("animation {}: {}", name, message))
+                        }
+                        error => error,
+                    })?;
+                Ok(Animation::new(name, channels))
+            })
+            .collect()
+    }
+}
+
+fn gltf_error(error: gltf::Error) -> Errors {
+    Errors::AnimationLoadError(error.to_string())
+}
+
+/// Converts a glTF channel, checking that its sampler can be read.
+fn convert_channel(
+    channel: &Channel<'_>,
+    buffers: &[gltf::buffer::Data],
+) -> Result<AnimationChannel, Errors> {
+    let sampler = channel.sampler();
+    let property = channel.target().property();
+    let node = channel.target().node();
+    let target = node
+        .name()
+        .map_or_else(|| format!("node{}", node.index()), str::to_string);
+    let unsupported = |what: &str| {
+        Errors::AnimationLoadError(format!(
+            "sampler {} of node {}: {}",
+            sampler.index(),
+            target,
+            what
+        ))
+    };
+
+    let input = sampler.input();
+    if input.data_type() != DataType::F32 || input.dimensions() != Dimensions::Scalar {
+        return Err(unsupported("keyframe times must be float scalars"));
+    }
+    let output = sampler.output();
+    let normalized_or_float = matches!(
+        output.data_type(),
+        DataType::F32 | DataType::I8 | DataType::U8 | DataType::I16 | DataType::U16
+    );
+    let supported = match property {
+        Property::Translation | Property::Scale => {
+            output.data_type() == DataType::F32 && output.dimensions() == Dimensions::Vec3
+        }
+        Property::Rotation => normalized_or_float && output.dimensions() == Dimensions::Vec4,
+        Property::MorphTargetWeights => {
+            normalized_or_float && output.dimensions() == Dimensions::Scalar
+        }
+    };
+    if !supported {
+        return Err(unsupported(&format!(
+            "unsupported output format {:?} {:?}",
+            output.dimensions(),
+            output.data_type()
+        )));
+    }
+
+    let reader = channel.reader(|buffer| buffers.get(buffer.index()).map(|data| &data.0[..]));
+    let times: Vec<f32> = reader
+        .read_inputs()
+        .ok_or_else(|| unsupported("keyframe times have no buffer data"))?
+        .collect();
+    if times.windows(2).any(|pair| pair[1] < pair[0]) {
+        return Err(unsupported("keyframe times are not ascending"));
+    }
+    let interpolation = match sampler.interpolation() {
+        gltf::animation::Interpolation::Step => Interpolation::Step,
+        gltf::animation::Interpolation::Linear => Interpolation::Linear,
+        gltf::animation::Interpolation::CubicSpline => Interpolation::CubicSpline,
+    };
+    let values_per_keyframe = match interpolation {
+        Interpolation::CubicSpline => 3,
+        _ => 1,
+    };
+    let expected = times.len() * values_per_keyframe;
+
+    let values = match reader
+        .read_outputs()
+        .ok_or_else(|| unsupported("keyframe values have no buffer data"))?
+    {
+        ReadOutputs::Translations(values) => {
+            ChannelValues::Translation(values.map(Vector3::from).collect())
+        }
+        ReadOutputs::Rotations(values) => ChannelValues::Rotation(
+            values
+                .into_f32()
+                .map(|[x, y, z, w]| Quaternion::new(w, x, y, z))
+                .collect(),
+        ),
+        ReadOutputs::Scales(values) => ChannelValues::Scale(values.map(Vector3::from).collect()),
+        ReadOutputs::MorphTargetWeights(values) => {
+            let weights: Vec<f32> = values.into_f32().collect();
+            if weights.is_empty() || expected == 0 || !weights.len().is_multiple_of(expected) {
+                return Err(unsupported(&format!(
+                    "{} weights cannot be split into {} keyframe values",
+                    weights.len(),
+                    expected
+                )));
+            }
+            let targets = weights.len() / expected;
+            ChannelValues::Weights(weights.chunks(targets).map(<[f32]>::to_vec).collect())
+        }
+    };
+    let count = match &values {
+        ChannelValues::Translation(values) | ChannelValues::Scale(values) => values.len(),
+        ChannelValues::Rotation(values) => values.len(),
+        ChannelValues::Weights(values) => values.len(),
+    };
+    if count != expected {
+        return Err(unsupported(&format!(
+            "{} values for {} keyframes",
+            count,
+            times.len()
+        )));
+    }
+
+    Ok(AnimationChannel {
+        target,
+        times,
+        values,
+        interpolation,
+    })
+}
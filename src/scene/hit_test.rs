@@ -0,0 +1,175 @@
+//! # Hit Test Module
+//!
+//! This module provides hit testing for 2D sprites and UI elements. Regions are rectangles on
+//! the `z = 0` plane; the mouse position is mapped through the camera that draws them, so
+//! panning and zooming a 2D camera keeps picking correct. When regions overlap, the one with
+//! the highest z-order wins.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::scene::{Camera, CameraType, HitTester};
+//! use glwfr::cgmath::{Point2, Point3, Vector3};
+//! use glwfr::input;
+//!
+//! #[derive(Clone, Copy, PartialEq)]
+//! enum Button { Play, Quit }
+//!
+//! // A HUD camera with one unit per pixel and the origin in the top-left corner
+//! let hud = Camera::new(
+//!     Point3::new(0.0, 0.0, 1.0),
+//!     Point3::new(0.0, 0.0, 0.0),
+//!     Vector3::new(0.0, 1.0, 0.0),
+//!     CameraType::Orthographic { left: 0.0, right: 800.0, bottom: 600.0, top: 0.0, near: 0.1, far: 10.0 },
+//! );
+//!
+//! let mut hits = HitTester::new();
+//! hits.add(Button::Play, Point2::new(300.0, 200.0), Point2::new(500.0, 260.0), 0);
+//! hits.add(Button::Quit, Point2::new(300.0, 300.0), Point2::new(500.0, 360.0), 0);
+//!
+//! let (x, y) = input::get_mouse_position();
+//! if hits.pick(&hud, x as f32, y as f32, 800.0, 600.0) == Some(&Button::Play) {
+//!     // Highlight the play button
+//! }
+//! ```
+
+use crate::scene::Camera;
+use cgmath::*;
+
+/// A rectangular region that can be picked, such as a sprite or a button.
+#[derive(Debug, Clone)]
+pub struct HitRegion<T> {
+    /// The identifier returned when the region is hit.
+    pub id: T,
+    /// The corner with the smallest coordinates.
+    pub min: Point2<f32>,
+    /// The corner with the largest coordinates.
+    pub max: Point2<f32>,
+    /// The drawing order. Regions with a higher z-order are on top.
+    pub z_order: i32,
+    /// Whether the region can be hit. Disabled regions are skipped, without blocking the
+    /// regions below them.
+    pub enabled: bool,
+}
+
+impl<T> HitRegion<T> {
+    /// Returns `true` if the point lies inside the region.
+    pub fn contains(&self, point: Point2<f32>) -> bool {
+        point.x >= self.min.x
+            && point.x <= self.max.x
+            && point.y >= self.min.y
+            && point.y <= self.max.y
+    }
+}
+
+/// A set of regions that can be tested against the mouse cursor.
+#[derive(Debug, Clone)]
+pub struct HitTester<T> {
+    regions: Vec<HitRegion<T>>,
+}
+
+impl<T> Default for HitTester<T> {
+    fn default() -> Self {
+        Self {
+            regions: Vec::new(),
+        }
+    }
+}
+
+impl<T> HitTester<T> {
+    /// Creates an empty hit tester.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a region. Regions added later are on top of earlier regions with the same z-order.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The identifier returned when the region is hit.
+    /// * `min` - One corner of the rectangle, in world units.
+    /// * `max` - The opposite corner of the rectangle, in world units.
+    /// * `z_order` - The drawing order. Regions with a higher z-order are on top.
+    pub fn add(&mut self, id: T, min: Point2<f32>, max: Point2<f32>, z_order: i32) {
+        self.regions.push(HitRegion {
+            id,
+            min: Point2::new(min.x.min(max.x), min.y.min(max.y)),
+            max: Point2::new(min.x.max(max.x), min.y.max(max.y)),
+            z_order,
+            enabled: true,
+        });
+    }
+
+    /// Removes all regions, e.g. before rebuilding them for the next frame.
+    pub fn clear(&mut self) {
+        self.regions.clear();
+    }
+
+    /// Returns the regions in the order they were added.
+    pub fn regions(&self) -> &[HitRegion<T>] {
+        &self.regions
+    }
+
+    /// Returns the regions mutably, e.g. to move or disable them.
+    pub fn regions_mut(&mut self) -> &mut [HitRegion<T>] {
+        &mut self.regions
+    }
+
+    /// Returns the identifier of the topmost region containing a world-space point.
+    pub fn pick_point(&self, point: Point2<f32>) -> Option<&T> {
+        self.hits_at_point(point).into_iter().next()
+    }
+
+    /// Returns the identifiers of all regions containing a world-space point, topmost first.
+    pub fn hits_at_point(&self, point: Point2<f32>) -> Vec<&T> {
+        let mut hits: Vec<(usize, &HitRegion<T>)> = self
+            .regions
+            .iter()
+            .enumerate()
+            .filter(|(_, region)| region.enabled && region.contains(point))
+            .collect();
+        hits.sort_by(|(a_index, a), (b_index, b)| {
+            b.z_order.cmp(&a.z_order).then(b_index.cmp(a_index))
+        });
+        hits.into_iter().map(|(_, region)| &region.id).collect()
+    }
+
+    /// Returns the identifier of the topmost region under a screen position.
+    ///
+    /// # Arguments
+    ///
+    /// * `camera` - The camera the regions are drawn with.
+    /// * `x` - The horizontal screen coordinate, from the left edge, e.g. the mouse position.
+    /// * `y` - The vertical screen coordinate, from the top edge.
+    /// * `width` - The width of the viewport, in the same units as `x`.
+    /// * `height` - The height of the viewport, in the same units as `y`.
+    pub fn pick(&self, camera: &Camera, x: f32, y: f32, width: f32, height: f32) -> Option<&T> {
+        self.pick_point(screen_to_plane(camera, x, y, width, height)?)
+    }
+
+    /// Returns the identifiers of all regions under a screen position, topmost first.
+    ///
+    /// See `pick` for the arguments.
+    pub fn hits_at(&self, camera: &Camera, x: f32, y: f32, width: f32, height: f32) -> Vec<&T> {
+        match screen_to_plane(camera, x, y, width, height) {
+            Some(point) => self.hits_at_point(point),
+            None => Vec::new(),
+        }
+    }
+}
+
+/// Maps a screen position to the point on the `z = 0` plane under it.
+fn screen_to_plane(
+    camera: &Camera,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+) -> Option<Point2<f32>> {
+    let ray = camera.screen_ray(x, y, width, height);
+    if ray.direction.z.abs() <= f32::EPSILON {
+        return None;
+    }
+    let point = ray.at(-ray.origin.z / ray.direction.z);
+    Some(Point2::new(point.x, point.y))
+}
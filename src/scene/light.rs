@@ -5,7 +5,7 @@
 //! ## Usage
 //!
 //! ```rust
-//! use glwfr::scene::light::{Light, LightType};
+//! use glwfr::scene::light::{Light, LightType, ShadowSettings};
 //! use glwfr::cgmath::{Point3, Vector3};
 //!
 //! // Create a point light
@@ -24,7 +24,11 @@
 //!         intensity: 0.8,
 //!     },
 //!     Vector3::new(1.0, 1.0, 0.8), // Color
-//! );
+//! )
+//! .with_shadows(ShadowSettings {
+//!     resolution: 2048,
+//!     ..ShadowSettings::default()
+//! });
 //! ```
 
 use cgmath::*;
@@ -43,12 +47,38 @@ pub enum LightType {
     },
 }
 
+/// Shadow map settings of a light.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShadowSettings {
+    /// The width and height of the shadow map in pixels.
+    pub resolution: u32,
+    /// A constant offset applied to the depth comparison to avoid shadow acne.
+    pub depth_bias: f32,
+    /// An offset along the surface normal, scaled by the slope, to avoid shadow acne on
+    /// surfaces at grazing angles.
+    pub normal_bias: f32,
+}
+
+impl Default for ShadowSettings {
+    fn default() -> Self {
+        Self {
+            resolution: 1024,
+            depth_bias: 0.005,
+            normal_bias: 0.02,
+        }
+    }
+}
+
 /// Represents a light source in a 3D scene.
 pub struct Light {
     /// The type of light (point or directional).
     pub light_type: LightType,
     /// The color of the light.
     color: Vector3<f32>,
+    /// Whether the light casts shadows.
+    pub casts_shadows: bool,
+    /// The shadow map settings, used when `casts_shadows` is `true`.
+    pub shadow_settings: ShadowSettings,
 }
 
 impl Light {
@@ -61,9 +91,25 @@ impl Light {
     ///
     /// # Returns
     ///
-    /// A new `Light` instance with the given type and color.
+    /// A new `Light` instance with the given type and color that does not cast shadows.
     pub fn new(light_type: LightType, color: Vector3<f32>) -> Self {
-        Self { light_type, color }
+        Self {
+            light_type,
+            color,
+            casts_shadows: false,
+            shadow_settings: ShadowSettings::default(),
+        }
+    }
+
+    /// Enables shadows for the light with the given settings, returning the light for chaining.
+    ///
+    /// # Arguments
+    ///
+    /// * `settings` - The shadow map resolution and bias settings.
+    pub fn with_shadows(mut self, settings: ShadowSettings) -> Self {
+        self.casts_shadows = true;
+        self.shadow_settings = settings;
+        self
     }
 
     /// Returns the light data including the direction or position, intensity, and color.
@@ -0,0 +1,335 @@
+//! # Lightmap Module
+//!
+//! This module provides precomputed lighting for static geometry. A lightmap is a texture
+//! mapped onto a mesh through a second UV set, read from vertex attribute
+//! `LIGHTMAP_UV_ATTRIBUTE`, and combined with the surface color by the fragment shader.
+//!
+//! Lit shaders can paste `LIGHTMAP_GLSL` into their fragment shader and call
+//! `applyLightmap(color, lightmapUv)`. `LightmapBaker` renders the lights of a scene into
+//! lightmaps once, e.g. at load time.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::scene::{LightmapBaker, Scene};
+//!
+//! fn bake(scene: &mut Scene) -> Result<(), glwfr::custom_errors::Errors> {
+//!     let baker = LightmapBaker::new(512)?;
+//!
+//!     // Bake all objects tagged "static"; they render with their lightmap afterwards
+//!     let baked = scene.bake_lightmaps(&baker, "static")?;
+//!     println!("Baked {} lightmaps", baked);
+//!     Ok(())
+//! }
+//! ```
+
+use crate::custom_errors::{Errors, GlErrorKind};
+use crate::graphics::gl_wrapper::{ContextBound, ShaderProgram};
+use crate::graphics::texture::{Texture, TextureFormat};
+use crate::graphics::texture_unit::TextureUnit;
+use crate::scene::{Light, LightType, Object};
+use cgmath::*;
+use gl::types::*;
+use std::rc::Rc;
+
+/// The vertex attribute index of the second UV set used for lightmaps.
+///
+/// Positions and normals are expected at indices `0` and `1`.
+pub const LIGHTMAP_UV_ATTRIBUTE: u32 = 3;
+
+/// The maximum number of lights `LightmapBaker` takes into account.
+pub const MAX_BAKED_LIGHTS: usize = 8;
+
+/// GLSL helpers for applying a `Lightmap` in a fragment shader.
+///
+/// Declares the uniforms set by `Lightmap::apply` and an `applyLightmap(color, lightmapUv)`
+/// function. Objects without a lightmap leave the color unchanged.
+pub const LIGHTMAP_GLSL: &str = r#"
+uniform sampler2D lightmap;
+uniform int hasLightmap;
+uniform int lightmapMode;
+uniform float lightmapIntensity;
+
+vec3 applyLightmap(vec3 color, vec2 lightmapUv) {
+    if (hasLightmap == 0) {
+        return color;
+    }
+    vec3 baked = texture(lightmap, lightmapUv).rgb * lightmapIntensity;
+    return lightmapMode == 0 ? color * baked : color + baked;
+}
+"#;
+
+const BAKE_VERTEX_SHADER: &str = r#"#version 330 core
+layout (location = 0) in vec3 aPos;
+layout (location = 1) in vec3 aNormal;
+layout (location = 3) in vec2 aLightmapUv;
+
+uniform mat4 model;
+
+out vec3 fragPos;
+out vec3 normal;
+
+void main() {
+    vec4 worldPos = model * vec4(aPos, 1.0);
+    fragPos = worldPos.xyz;
+    normal = mat3(transpose(inverse(model))) * aNormal;
+    gl_Position = vec4(aLightmapUv * 2.0 - 1.0, 0.0, 1.0);
+}
+"#;
+
+const BAKE_FRAGMENT_SHADER: &str = r#"#version 330 core
+#define MAX_LIGHTS 8
+
+in vec3 fragPos;
+in vec3 normal;
+
+uniform int lightCount;
+uniform vec3 lightVectors[MAX_LIGHTS];
+uniform vec3 lightColors[MAX_LIGHTS];
+uniform int lightDirectional[MAX_LIGHTS];
+uniform vec3 ambientColor;
+
+out vec4 fragColor;
+
+void main() {
+    vec3 n = normalize(normal);
+    vec3 result = ambientColor;
+    for (int i = 0; i < lightCount; i++) {
+        vec3 toLight;
+        float attenuation = 1.0;
+        if (lightDirectional[i] == 1) {
+            toLight = normalize(-lightVectors[i]);
+        } else {
+            vec3 offset = lightVectors[i] - fragPos;
+            float distance = length(offset);
+            toLight = offset / distance;
+            attenuation = 1.0 / (1.0 + distance * distance);
+        }
+        result += lightColors[i] * max(dot(n, toLight), 0.0) * attenuation;
+    }
+    fragColor = vec4(result, 1.0);
+}
+"#;
+
+/// How a lightmap is combined with the surface color.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum LightmapMode {
+    /// The surface color is multiplied by the lightmap, for fully baked lighting.
+    #[default]
+    Multiply,
+    /// The lightmap is added to the surface color, e.g. for baked bounce light on top of
+    /// dynamic lighting.
+    Add,
+}
+
+impl LightmapMode {
+    /// Returns the value of the `lightmapMode` uniform for this mode.
+    fn uniform_value(&self) -> i32 {
+        match self {
+            LightmapMode::Multiply => 0,
+            LightmapMode::Add => 1,
+        }
+    }
+}
+
+/// A lightmap texture applied to an object.
+#[derive(Clone)]
+pub struct Lightmap {
+    /// The texture containing the baked lighting.
+    pub texture: Rc<Texture>,
+    /// How the lightmap is combined with the surface color.
+    pub mode: LightmapMode,
+    /// A factor the lightmap is multiplied by.
+    pub intensity: f32,
+    /// The texture unit the lightmap is bound to.
+    pub unit: TextureUnit,
+}
+
+impl Lightmap {
+    /// Creates a multiplicative lightmap with full intensity.
+    ///
+    /// # Arguments
+    ///
+    /// * `texture` - The texture containing the baked lighting.
+    pub fn new(texture: Rc<Texture>) -> Self {
+        Self {
+            texture,
+            mode: LightmapMode::Multiply,
+            intensity: 1.0,
+            unit: TextureUnit::new(13),
+        }
+    }
+
+    /// Binds the lightmap and sets the uniforms declared by `LIGHTMAP_GLSL`.
+    ///
+    /// The shader program must be bound before calling this function. Uniforms the shader
+    /// does not declare are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `shader_program` - The shader program applying the lightmap.
+    pub fn apply(&self, shader_program: &ShaderProgram) {
+        self.texture.bind_to_unit(self.unit);
+        shader_program.try_set_uniform("lightmap", self.unit.index() as i32);
+        shader_program.try_set_uniform("hasLightmap", 1);
+        shader_program.try_set_uniform("lightmapMode", self.mode.uniform_value());
+        shader_program.try_set_uniform("lightmapIntensity", self.intensity);
+    }
+}
+
+/// Renders static lighting into lightmaps.
+///
+/// The geometry is rasterized in lightmap UV space, so every texel receives the diffuse
+/// lighting of the surface point it maps to. Shadows are not taken into account.
+pub struct LightmapBaker {
+    fbo: GLuint,
+    resolution: u32,
+    shader: ShaderProgram,
+    _context: ContextBound,
+}
+
+impl LightmapBaker {
+    /// Creates a new lightmap baker.
+    ///
+    /// # Arguments
+    ///
+    /// * `resolution` - The width and height of the baked lightmaps in pixels.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::OpenGlError` if the framebuffer cannot be created, or an
+    /// `Errors::ShaderCompilationError` if the bake shader cannot be compiled.
+    pub fn new(resolution: u32) -> Result<Self, Errors> {
+        let shader = ShaderProgram::from_source(BAKE_VERTEX_SHADER, BAKE_FRAGMENT_SHADER)?;
+        let mut fbo = 0;
+        unsafe {
+            gl::GenFramebuffers(1, &mut fbo);
+        }
+        if fbo == 0 {
+            return Err(Errors::OpenGlError(
+                "Failed to generate lightmap framebuffer".to_string(),
+                GlErrorKind::ObjectCreationFailed,
+            ));
+        }
+        Ok(Self {
+            fbo,
+            resolution,
+            shader,
+            _context: ContextBound::default(),
+        })
+    }
+
+    /// Returns the width and height of the baked lightmaps in pixels.
+    pub fn resolution(&self) -> u32 {
+        self.resolution
+    }
+
+    /// Bakes the lighting of an object into a new lightmap texture.
+    ///
+    /// The object's mesh must provide positions, normals and lightmap UVs at attribute
+    /// indices `0`, `1` and `LIGHTMAP_UV_ATTRIBUTE`. Only the first `MAX_BAKED_LIGHTS` lights
+    /// are used.
+    ///
+    /// # Arguments
+    ///
+    /// * `object` - The static object to bake.
+    /// * `lights` - The lights illuminating the object.
+    /// * `ambient` - The ambient light added to every texel.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::OpenGlError` of kind `GlErrorKind::FramebufferIncomplete` if the
+    /// lightmap framebuffer is incomplete.
+    pub fn bake(
+        &self,
+        object: &mut Object,
+        lights: &[Light],
+        ambient: Vector3<f32>,
+    ) -> Result<Texture, Errors> {
+        let texture = Texture::new();
+        texture.allocate(self.resolution, self.resolution, TextureFormat::Rgba8);
+
+        let mut previous_fbo = 0;
+        let mut previous_viewport = [0; 4];
+        let depth_test;
+        let cull_face;
+        unsafe {
+            gl::GetIntegerv(gl::DRAW_FRAMEBUFFER_BINDING, &mut previous_fbo);
+            gl::GetIntegerv(gl::VIEWPORT, previous_viewport.as_mut_ptr());
+            depth_test = gl::IsEnabled(gl::DEPTH_TEST) == gl::TRUE;
+            cull_face = gl::IsEnabled(gl::CULL_FACE) == gl::TRUE;
+
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+            gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0,
+                gl::TEXTURE_2D,
+                texture.id(),
+                0,
+            );
+            let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+            if status != gl::FRAMEBUFFER_COMPLETE {
+                gl::BindFramebuffer(gl::FRAMEBUFFER, previous_fbo as GLuint);
+                return Err(Errors::OpenGlError(
+                    "Lightmap framebuffer is incomplete".to_string(),
+                    GlErrorKind::FramebufferIncomplete(status),
+                ));
+            }
+
+            gl::Viewport(0, 0, self.resolution as i32, self.resolution as i32);
+            gl::Disable(gl::DEPTH_TEST);
+            gl::Disable(gl::CULL_FACE);
+            gl::ClearColor(0.0, 0.0, 0.0, 1.0);
+            gl::Clear(gl::COLOR_BUFFER_BIT);
+        }
+
+        self.shader.bind();
+        self.set_light_uniforms(lights, ambient);
+        object.render_with(&self.shader);
+
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, previous_fbo as GLuint);
+            gl::Viewport(
+                previous_viewport[0],
+                previous_viewport[1],
+                previous_viewport[2],
+                previous_viewport[3],
+            );
+            if depth_test {
+                gl::Enable(gl::DEPTH_TEST);
+            }
+            if cull_face {
+                gl::Enable(gl::CULL_FACE);
+            }
+        }
+
+        texture.generate_mipmaps();
+        Ok(texture)
+    }
+
+    /// Uploads the lights and ambient color to the bake shader.
+    fn set_light_uniforms(&self, lights: &[Light], ambient: Vector3<f32>) {
+        let lights = &lights[..lights.len().min(MAX_BAKED_LIGHTS)];
+        self.shader
+            .try_set_uniform("lightCount", lights.len() as i32);
+        self.shader.try_set_uniform("ambientColor", ambient);
+        for (i, light) in lights.iter().enumerate() {
+            let (vector, intensity, color) = light.get_light_data();
+            let directional = matches!(light.light_type, LightType::Directional { .. });
+            self.shader
+                .try_set_uniform(&format!("lightVectors[{}]", i), vector);
+            self.shader
+                .try_set_uniform(&format!("lightColors[{}]", i), color * intensity);
+            self.shader
+                .try_set_uniform(&format!("lightDirectional[{}]", i), directional as i32);
+        }
+    }
+}
+
+impl Drop for LightmapBaker {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteFramebuffers(1, &self.fbo);
+        }
+    }
+}
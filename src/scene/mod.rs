@@ -4,10 +4,34 @@
 //! objects, and transformations.
 //!
 //! ## Submodules
+//! - **animation**: Keyframed translation, rotation, scale and morph weight animations.
+//! - **auto_focus**: Smooth depth-of-field focus on objects and raycast hits.
 //! - **camera**: Camera implementation for 3D scenes.
+//! - **camera_effects**: Screen shake and field of view effects for cameras.
+//! - **environment**: Ambient light, fog and background settings for a scene.
+//! - **follow_camera**: A smoothly following third-person camera controller.
+//! - **gizmo**: Translate, rotate and scale handles for editing transforms with the mouse.
+//! - **gltf_animation**: Import of glTF animations (requires the `gltf` feature).
+//! - **hit_test**: Mouse picking of 2D sprites and UI regions.
 //! - **light**: Light sources for 3D scenes.
+//! - **lightmap**: Baked lighting for static geometry.
+//! - **morph**: Morph targets and keyframed morph weights.
 //! - **object**: Representation of objects in a 3D scene.
+//! - **occlusion**: Hardware occlusion culling of hidden objects.
+//! - **portal**: Portal-cell visibility for indoor scenes.
+//! - **prefab**: Object templates spawned into scenes many times.
+//! - **projector**: Projector lights casting a texture onto the scene.
+//! - **reflection_probe**: Captured cubemaps for local reflections.
+//! - **scene_manager**: Named scenes with fade and crossfade transitions between them.
+//! - **shadow**: Omnidirectional shadow maps for point lights.
+//! - **streaming**: Loading and unloading of world chunks around the camera.
+//! - **static_batch**: Merging of static objects into combined meshes.
+//! - **skinning**: Bone matrix uniform buffers and skinned vertex shader helpers.
+//! - **toon**: Toon shading with stepped lighting, ramp textures and rim light.
 //! - **transform**: Transformations in 3D space.
+//! - **vegetation**: Density-map scattering of instanced grass and rocks with culling and fade.
+//! - **weather**: Rain and snow around the camera and wet surfaces.
+//! - **wind**: Procedural wind sway for foliage.
 //!
 //! ## Example
 //! ```rust
@@ -53,14 +77,62 @@
 //! }
 //! ```
 
+pub mod animation;
+pub mod auto_focus;
 pub mod camera;
+pub mod camera_effects;
+pub mod environment;
+pub mod follow_camera;
+pub mod gizmo;
+#[cfg(feature = "gltf")]
+pub mod gltf_animation;
+pub mod hit_test;
 pub mod light;
+pub mod lightmap;
+pub mod morph;
 pub mod object;
+pub mod occlusion;
+pub mod portal;
+pub mod prefab;
+pub mod projector;
+pub mod reflection_probe;
 pub mod scene;
+pub mod scene_manager;
+pub mod shadow;
+pub mod skinning;
+pub mod static_batch;
+pub mod streaming;
+pub mod toon;
 pub mod transform;
+pub mod vegetation;
+pub mod weather;
+pub mod wind;
 
+pub use animation::*;
+pub use auto_focus::*;
 pub use camera::*;
+pub use camera_effects::*;
+pub use environment::*;
+pub use follow_camera::*;
+pub use gizmo::*;
+pub use hit_test::*;
 pub use light::*;
+pub use lightmap::*;
+pub use morph::*;
 pub use object::*;
+pub use occlusion::*;
+pub use portal::*;
+pub use prefab::*;
+pub use projector::*;
+pub use reflection_probe::*;
 pub use scene::*;
+pub use scene_manager::*;
+pub use shadow::*;
+pub use skinning::*;
+pub use static_batch::*;
+pub use streaming::*;
+pub use toon::*;
 pub use transform::*;
+pub use vegetation::*;
+pub use weather::*;
+pub use wind::*;
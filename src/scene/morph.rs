@@ -0,0 +1,264 @@
+//! # Morph Module
+//!
+//! This module provides morph targets (blend shapes), e.g. for facial animation. The position
+//! and normal deltas of all targets are stored in a float texture, which the vertex shader
+//! reads with `gl_VertexID`, so the number of targets is not limited by vertex attributes.
+//!
+//! Vertex shaders can paste `MORPH_GLSL` and call `morphPosition(aPos)` and
+//! `morphNormal(aNormal)` before applying the model matrix. Objects with a `Morph` upload their
+//! weights when rendered.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::scene::{Morph, MorphAnimation, MorphTarget, MorphTargets, Object};
+//! use std::rc::Rc;
+//!
+//! fn setup(face: &mut Object, smile: Vec<[f32; 3]>, blink: Vec<[f32; 3]>) -> Result<(), glwfr::custom_errors::Errors> {
+//!     let vertex_count = smile.len();
+//!     let targets = MorphTargets::new(
+//!         &[MorphTarget::from_positions(smile), MorphTarget::from_positions(blink)],
+//!         vertex_count,
+//!     )?;
+//!     face.morph = Some(Morph::new(Rc::new(targets)));
+//!
+//!     // Blink over a quarter of a second
+//!     let blink_animation = MorphAnimation::new(
+//!         vec![0.0, 0.125, 0.25],
+//!         vec![vec![0.0, 0.0], vec![0.0, 1.0], vec![0.0, 0.0]],
+//!     );
+//!     if let Some(morph) = &mut face.morph {
+//!         morph.weights = blink_animation.sample(0.1);
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use crate::custom_errors::Errors;
+use crate::graphics::gl_wrapper::ShaderProgram;
+use crate::graphics::texture::{Texture, TextureFormat};
+use crate::graphics::texture_unit::TextureUnit;
+use std::rc::Rc;
+
+/// The maximum number of morph targets `MORPH_GLSL` blends.
+pub const MAX_MORPH_TARGETS: usize = 32;
+
+/// The width of the morph target texture in texels.
+const MORPH_TEXTURE_WIDTH: usize = 1024;
+
+/// GLSL helpers for applying morph targets in a vertex shader.
+///
+/// Declares the uniforms set by `Morph::apply` and the functions `morphPosition(position)` and
+/// `morphNormal(normal)`. Objects without morph targets leave their inputs unchanged.
+pub const MORPH_GLSL: &str = r#"
+#define MAX_MORPH_TARGETS 32
+uniform sampler2D morphTargets;
+uniform int morphTargetCount;
+uniform int morphVertexCount;
+uniform float morphWeights[MAX_MORPH_TARGETS];
+
+vec4 morphTexel(int target, int channel) {
+    int index = (target * morphVertexCount + gl_VertexID) * 2 + channel;
+    return texelFetch(morphTargets, ivec2(index % 1024, index / 1024), 0);
+}
+
+vec3 morphPosition(vec3 position) {
+    for (int i = 0; i < morphTargetCount; i++) {
+        position += morphWeights[i] * morphTexel(i, 0).xyz;
+    }
+    return position;
+}
+
+vec3 morphNormal(vec3 normal) {
+    for (int i = 0; i < morphTargetCount; i++) {
+        normal += morphWeights[i] * morphTexel(i, 1).xyz;
+    }
+    return normalize(normal);
+}
+"#;
+
+/// The per-vertex deltas of a single morph target.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MorphTarget {
+    /// The position offset of each vertex.
+    pub positions: Vec<[f32; 3]>,
+    /// The normal offset of each vertex. May be empty if the target does not change normals.
+    pub normals: Vec<[f32; 3]>,
+}
+
+impl MorphTarget {
+    /// Creates a morph target that only moves vertices.
+    pub fn from_positions(positions: Vec<[f32; 3]>) -> Self {
+        Self {
+            positions,
+            normals: Vec::new(),
+        }
+    }
+}
+
+/// The morph targets of a mesh, stored in a texture. Can be shared between objects using the
+/// same mesh.
+pub struct MorphTargets {
+    texture: Texture,
+    vertex_count: usize,
+    target_count: usize,
+}
+
+impl MorphTargets {
+    /// Uploads the deltas of the given targets. Only the first `MAX_MORPH_TARGETS` are used.
+    ///
+    /// # Arguments
+    ///
+    /// * `targets` - The morph targets. Missing deltas count as zero.
+    /// * `vertex_count` - The number of vertices of the mesh.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::TextureLoadError` if the texture cannot be uploaded.
+    pub fn new(targets: &[MorphTarget], vertex_count: usize) -> Result<Self, Errors> {
+        let targets = &targets[..targets.len().min(MAX_MORPH_TARGETS)];
+        let texel_count = (targets.len() * vertex_count * 2).max(1);
+        let height = texel_count.div_ceil(MORPH_TEXTURE_WIDTH);
+
+        let mut texels = vec![[0.0f32; 4]; MORPH_TEXTURE_WIDTH * height];
+        for (target_index, target) in targets.iter().enumerate() {
+            for vertex in 0..vertex_count {
+                let index = (target_index * vertex_count + vertex) * 2;
+                if let Some(&[x, y, z]) = target.positions.get(vertex) {
+                    texels[index] = [x, y, z, 0.0];
+                }
+                if let Some(&[x, y, z]) = target.normals.get(vertex) {
+                    texels[index + 1] = [x, y, z, 0.0];
+                }
+            }
+        }
+        let data: Vec<u8> = texels
+            .iter()
+            .flatten()
+            .flat_map(|value| value.to_ne_bytes())
+            .collect();
+
+        let texture = Texture::new();
+        texture.set_generate_mipmaps(false);
+        texture.load_from_data_with_format(
+            MORPH_TEXTURE_WIDTH as u32,
+            height as u32,
+            TextureFormat::Rgba32F,
+            &data,
+        )?;
+        texture.set_parameteri(gl::TEXTURE_MIN_FILTER, gl::NEAREST as i32);
+        texture.set_parameteri(gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32);
+
+        Ok(Self {
+            texture,
+            vertex_count,
+            target_count: targets.len(),
+        })
+    }
+
+    /// Returns the number of vertices of the mesh.
+    pub fn vertex_count(&self) -> usize {
+        self.vertex_count
+    }
+
+    /// Returns the number of morph targets.
+    pub fn target_count(&self) -> usize {
+        self.target_count
+    }
+}
+
+/// The morph targets of an object and their current weights.
+#[derive(Clone)]
+pub struct Morph {
+    /// The morph targets of the object's mesh.
+    pub targets: Rc<MorphTargets>,
+    /// The weight of each target, usually from `0.0` to `1.0`.
+    pub weights: Vec<f32>,
+    /// The texture unit the morph target texture is bound to.
+    pub unit: TextureUnit,
+}
+
+impl Morph {
+    /// Creates a morph with all weights set to zero.
+    pub fn new(targets: Rc<MorphTargets>) -> Self {
+        let weights = vec![0.0; targets.target_count()];
+        Self {
+            targets,
+            weights,
+            unit: TextureUnit::new(12),
+        }
+    }
+
+    /// Sets the weight of a single target. Out of range indices are ignored.
+    pub fn set_weight(&mut self, target: usize, weight: f32) {
+        if let Some(w) = self.weights.get_mut(target) {
+            *w = weight;
+        }
+    }
+
+    /// Binds the morph target texture and sets the uniforms declared by `MORPH_GLSL`.
+    ///
+    /// The shader program must be bound before calling this function. Uniforms the shader
+    /// does not declare are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `shader_program` - The shader program applying the morph targets.
+    pub fn apply(&self, shader_program: &ShaderProgram) {
+        self.targets.texture.bind_to_unit(self.unit);
+        shader_program.try_set_uniform("morphTargets", self.unit.index() as i32);
+        shader_program.try_set_uniform("morphTargetCount", self.targets.target_count as i32);
+        shader_program.try_set_uniform("morphVertexCount", self.targets.vertex_count as i32);
+        for (i, weight) in self.weights.iter().take(MAX_MORPH_TARGETS).enumerate() {
+            shader_program.try_set_uniform(&format!("morphWeights[{}]", i), *weight);
+        }
+    }
+}
+
+/// Keyframed morph target weights, e.g. from a glTF `weights` animation channel.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MorphAnimation {
+    /// The time of each keyframe in seconds, in ascending order.
+    pub times: Vec<f32>,
+    /// The weights of all targets at each keyframe.
+    pub keyframes: Vec<Vec<f32>>,
+}
+
+impl MorphAnimation {
+    /// Creates an animation from keyframe times and the weights at each keyframe.
+    pub fn new(times: Vec<f32>, keyframes: Vec<Vec<f32>>) -> Self {
+        Self { times, keyframes }
+    }
+
+    /// Returns the duration of the animation in seconds.
+    pub fn duration(&self) -> f32 {
+        self.times.last().copied().unwrap_or(0.0)
+    }
+
+    /// Returns the weights at the given time, linearly interpolated between keyframes and
+    /// clamped to the first and last keyframe.
+    pub fn sample(&self, time: f32) -> Vec<f32> {
+        let count = self.times.len().min(self.keyframes.len());
+        if count == 0 {
+            return Vec::new();
+        }
+        let next = self.times[..count].partition_point(|&t| t <= time);
+        if next == 0 {
+            return self.keyframes[0].clone();
+        }
+        if next == count {
+            return self.keyframes[count - 1].clone();
+        }
+        let (t0, t1) = (self.times[next - 1], self.times[next]);
+        let factor = if t1 > t0 {
+            (time - t0) / (t1 - t0)
+        } else {
+            0.0
+        };
+        let (from, to) = (&self.keyframes[next - 1], &self.keyframes[next]);
+        from.iter()
+            .zip(to)
+            .map(|(a, b)| a + (b - a) * factor)
+            .collect()
+    }
+}
@@ -6,30 +6,83 @@
 //!
 //! ```rust
 //! use glwfr::scene::Object;
-//! use glwfr::graphics::gl_wrapper::{Vao, ShaderProgram};
+//! use glwfr::graphics::gl_wrapper::ShaderProgram;
+//! use glwfr::graphics::mesh::Mesh;
+//! use glwfr::graphics::standard_vertex::StandardVertex;
 //! use glwfr::cgmath::Matrix4;
 //!
 //! // Create a new object
-//! let vao = Vao::new().unwrap();
+//! let vertices = [StandardVertex::default(); 3];
+//! let mesh = Mesh::new(&vertices, &[0u16, 1, 2]).unwrap();
 //! let shader_program = ShaderProgram::new("vertex.glsl", "fragment.glsl").unwrap();
-//! let mut object = Object::new(vao, shader_program);
+//! let mut object = Object::new(mesh, shader_program);
 //!
 //! // Set the object's transform
 //! object.transform.set_position(Vector3::new(0.0, 0.0, 0.0));
 //! ```
 
-use crate::graphics::gl_wrapper::{ShaderProgram, Vao};
-use crate::scene::Transform;
+use crate::graphics::alpha_mode::{self, AlphaMode};
+use crate::graphics::gl_wrapper::ShaderProgram;
+use crate::graphics::mesh::Mesh;
+use crate::graphics::render_state::{CullMode, RenderState};
+use crate::math::Aabb;
+use crate::scene::{Lightmap, Morph, PoolHandle, ToonShading, Transform, WindSway};
 use cgmath::*;
+use std::collections::HashSet;
+use std::rc::Rc;
 
 /// Represents an object in a 3D scene.
 pub struct Object {
-    /// The mesh of the object, represented as a VAO.
-    mesh: Vao,
+    /// The mesh of the object, which may be shared with other objects.
+    mesh: Rc<Mesh>,
     /// The transformation matrix of the object.
     pub transform: Transform,
-    /// The shader program used to render the object.
-    pub shader_program: ShaderProgram,
+    /// The shader program used to render the object, possibly shared with other objects.
+    pub shader_program: Rc<ShaderProgram>,
+    /// The render state applied before drawing the object, or `None` to keep the current state.
+    pub render_state: Option<RenderState>,
+    /// How the alpha of the material is used, which also decides the queue the object is
+    /// drawn in by `Scene::render`.
+    pub alpha_mode: AlphaMode,
+    /// The faces hidden when drawing the object, overriding the culling of `render_state`, or
+    /// `None` to keep it.
+    pub cull_mode: Option<CullMode>,
+    /// Whether the object is rendered into shadow maps.
+    pub cast_shadows: bool,
+    /// Whether shadows are applied to the object. Uploaded as the `receiveShadows` uniform.
+    pub receive_shadows: bool,
+    /// The baked lighting of the object, or `None` if it is lit dynamically only.
+    pub lightmap: Option<Lightmap>,
+    /// The morph targets and weights of the object, or `None` if its mesh is not morphed.
+    pub morph: Option<Morph>,
+    /// The toon shading of the object, or `None` for smooth lighting.
+    pub toon: Option<ToonShading>,
+    /// The wind sway of the object, e.g. for foliage, or `None` if it is not moved by wind.
+    pub wind_sway: Option<WindSway>,
+    /// The bounding box of the mesh in local space, or `None` if it is unknown.
+    pub bounds: Option<Aabb>,
+    /// Whether the object is skipped while its bounds are hidden behind other geometry.
+    /// Needs `bounds` and a `Scene` with an `OcclusionCuller`.
+    pub occlusion_culling: bool,
+    /// Whether the object never moves, so `Scene::bake_static` may merge it with other static
+    /// objects.
+    pub is_static: bool,
+    /// Whether `Scene::render` may draw the object in a single instanced draw call together
+    /// with other objects sharing its mesh, shader program and material settings. The shader
+    /// must use `INSTANCING_GLSL`.
+    pub instanced: bool,
+    /// Whether the object is part of the scene. Inactive objects are skipped by rendering,
+    /// shadow passes, occlusion queries and `Scene::bake_static`, e.g. pooled objects waiting
+    /// to be spawned again.
+    pub active: bool,
+    /// The name of the pool the object belongs to, if it was created by `Scene::spawn_pooled`.
+    pool: Option<String>,
+    /// The handle of the current spawn, if the object was created by `Scene::spawn_pooled`.
+    pool_handle: Option<PoolHandle>,
+    /// The tags used to find the object with `Scene::objects_with_tag`.
+    tags: HashSet<String>,
+    /// The model matrix of the previous render, used for motion vectors.
+    previous_model: Option<Matrix4<f32>>,
 }
 
 impl Object {
@@ -37,53 +90,296 @@ impl Object {
     ///
     /// # Arguments
     ///
-    /// * `mesh` - The mesh of the object, represented as a VAO.
-    /// * `shader_program` - The shader program used to render the object.
+    /// * `mesh` - The mesh of the object. Pass an `Rc<Mesh>` to share it between objects; a
+    ///   bare `Vao` is accepted as well and wrapped with `Mesh::from_vao`.
+    /// * `shader_program` - The shader program used to render the object. Pass an
+    ///   `Rc<ShaderProgram>`, e.g. from a `ShaderManager`, to share it between objects.
     ///
     /// # Returns
     ///
     /// A new `Object` instance with the given mesh and shader program,
     /// and an identity transformation matrix.
 
-    pub fn new(mesh: Vao, shader_program: ShaderProgram) -> Self {
+    pub fn new(mesh: impl Into<Rc<Mesh>>, shader_program: impl Into<Rc<ShaderProgram>>) -> Self {
         Self {
-            mesh,
+            mesh: mesh.into(),
             transform: Transform::new(),
-            shader_program,
+            shader_program: shader_program.into(),
+            render_state: None,
+            alpha_mode: AlphaMode::Opaque,
+            cull_mode: None,
+            cast_shadows: true,
+            receive_shadows: true,
+            lightmap: None,
+            morph: None,
+            toon: None,
+            wind_sway: None,
+            bounds: None,
+            occlusion_culling: false,
+            is_static: false,
+            instanced: false,
+            active: true,
+            pool: None,
+            pool_handle: None,
+            tags: HashSet::new(),
+            previous_model: None,
         }
     }
 
+    /// Adds a tag to the object, returning the object for chaining.
+    ///
+    /// Tags can be any string, or an enum with an `AsRef<str>` implementation.
+    ///
+    /// # Arguments
+    ///
+    /// * `tag` - The tag to add.
+    pub fn with_tag(mut self, tag: impl AsRef<str>) -> Self {
+        self.add_tag(tag);
+        self
+    }
+
+    /// Adds a tag to the object.
+    ///
+    /// # Arguments
+    ///
+    /// * `tag` - The tag to add.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the tag was added, `false` if the object already had it.
+    pub fn add_tag(&mut self, tag: impl AsRef<str>) -> bool {
+        self.tags.insert(tag.as_ref().to_string())
+    }
+
+    /// Removes a tag from the object.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the object had the tag, otherwise `false`.
+    pub fn remove_tag(&mut self, tag: impl AsRef<str>) -> bool {
+        self.tags.remove(tag.as_ref())
+    }
+
+    /// Checks if the object has the given tag.
+    pub fn has_tag(&self, tag: impl AsRef<str>) -> bool {
+        self.tags.contains(tag.as_ref())
+    }
+
+    /// Returns an iterator over the tags of the object.
+    pub fn tags(&self) -> impl Iterator<Item = &str> {
+        self.tags.iter().map(String::as_str)
+    }
+
+    /// Returns the name of the pool the object belongs to, if it was created by
+    /// `Scene::spawn_pooled`.
+    pub fn pool(&self) -> Option<&str> {
+        self.pool.as_deref()
+    }
+
+    /// Sets the pool the object belongs to.
+    pub(crate) fn set_pool(&mut self, pool: Option<String>) {
+        self.pool = pool;
+    }
+
+    /// Returns the handle of the object's current spawn, if it was created by
+    /// `Scene::spawn_pooled`.
+    pub fn pool_handle(&self) -> Option<PoolHandle> {
+        self.pool_handle
+    }
+
+    /// Sets the handle of the object's current spawn.
+    pub(crate) fn set_pool_handle(&mut self, pool_handle: Option<PoolHandle>) {
+        self.pool_handle = pool_handle;
+    }
+
+    /// Returns the mesh of the object. Clone it to create other objects sharing the mesh.
+    pub fn mesh(&self) -> &Rc<Mesh> {
+        &self.mesh
+    }
+
+    /// Returns the mesh of the object mutably, e.g. to change its primitive type, or `None` if
+    /// the mesh is shared with other objects.
+    pub fn mesh_mut(&mut self) -> Option<&mut Mesh> {
+        Rc::get_mut(&mut self.mesh)
+    }
+
+    /// Returns the bounding box of the object in world space, or `None` if it has no bounds.
+    pub fn world_bounds(&mut self) -> Option<Aabb> {
+        let bounds = self.bounds?;
+        Some(bounds.transformed(&self.transform.matrix()))
+    }
+
+    /// Forgets the model matrix of the previous render, so the next frame has no motion blur.
+    ///
+    /// Call this after teleporting the object to avoid a blur streak across the screen.
+    pub fn reset_motion(&mut self) {
+        self.previous_model = None;
+    }
+
+    /// Returns the model matrix of the previous render, used for motion vectors.
+    pub(crate) fn previous_model(&self) -> Option<Matrix4<f32>> {
+        self.previous_model
+    }
+
+    /// Restores the model matrix of the previous render after an extra render, such as a
+    /// reflection capture, that should not count as a frame.
+    pub(crate) fn set_previous_model(&mut self, previous_model: Option<Matrix4<f32>>) {
+        self.previous_model = previous_model;
+    }
+
     /// Renders the object using the given view and projection matrices.
     ///
+    /// Applies the object's render state and cull mode, binds its shader program and sets the
+    /// "model", "previousModel", "view" and "projection" uniforms, along with its material
+    /// options such as shadows, alpha cutoff, lightmap, morph weights, toon shading and wind
+    /// sway. Uniforms the shader does not declare are skipped. It then draws the mesh with
+    /// `Mesh::draw`.
+    ///
     /// # Arguments
     ///
     /// * `view_matrix` - The view matrix to use for rendering.
     /// * `projection_matrix` - The projection matrix to use for rendering.
-    ///
-    /// This function binds the object's shader program and sets the "model", "view", and
-    /// "projection" uniforms to the object's transformation matrix, the given view matrix,
-    /// and the given projection matrix, respectively. It then binds the object's mesh and
-    /// renders it using the `gl::DrawElements` function with the `gl::TRIANGLES` primitive type.
     pub fn render(&mut self, view_matrix: Matrix4<f32>, projection_matrix: Matrix4<f32>) {
+        let model = self.transform.matrix();
+        let previous_model = self.previous_model.unwrap_or(model);
+        self.previous_model = Some(model);
+        self.render_instances(view_matrix, projection_matrix, model, previous_model, None);
+    }
+
+    /// Renders `instances` copies of the object in a single draw call, using the per-instance
+    /// data uploaded with `Mesh::set_instances`.
+    ///
+    /// Sets the same uniforms as `render`, and "useInstanceModel" to `1` so `INSTANCING_GLSL`
+    /// applies the `InstanceTransform` of every instance before the object's model matrix.
+    ///
+    /// # Arguments
+    ///
+    /// * `view_matrix` - The view matrix to use for rendering.
+    /// * `projection_matrix` - The projection matrix to use for rendering.
+    /// * `instances` - The number of instances to draw, at most `Mesh::instance_count`.
+    pub fn render_instanced(
+        &mut self,
+        view_matrix: Matrix4<f32>,
+        projection_matrix: Matrix4<f32>,
+        instances: u32,
+    ) {
+        let model = self.transform.matrix();
+        let previous_model = self.previous_model.unwrap_or(model);
+        self.previous_model = Some(model);
+        self.render_instances(
+            view_matrix,
+            projection_matrix,
+            model,
+            previous_model,
+            Some(instances),
+        );
+    }
+
+    /// Applies the object's state and uniforms with the given model matrices and draws its
+    /// mesh, instanced if `instances` is given.
+    pub(crate) fn render_instances(
+        &self,
+        view_matrix: Matrix4<f32>,
+        projection_matrix: Matrix4<f32>,
+        model: Matrix4<f32>,
+        previous_model: Matrix4<f32>,
+        instances: Option<u32>,
+    ) {
+        if let Some(render_state) = &self.render_state {
+            render_state.apply();
+        }
+        let state_cull = self.render_state.as_ref().map(|state| state.cull);
+        if let Some(cull_mode) = self.cull_mode {
+            let front_face = state_cull.flatten().unwrap_or_default().front_face;
+            cull_mode.apply(front_face);
+        }
+        let two_sided = self
+            .cull_mode
+            .or_else(|| state_cull.map(|cull| CullMode::from_state(cull.as_ref())))
+            .is_some_and(|cull_mode| cull_mode.shows_back_faces());
         self.shader_program.bind();
+        self.shader_program.try_set_uniform("model", model);
+        self.shader_program
+            .try_set_uniform("previousModel", previous_model);
+        self.shader_program
+            .try_set_uniform("useInstanceModel", instances.is_some() as i32);
+        self.shader_program.try_set_uniform("view", view_matrix);
         self.shader_program
-            .set_uniform_matrix4fv("model", &self.transform.matrix())
-            .unwrap();
+            .try_set_uniform("projection", projection_matrix);
         self.shader_program
-            .set_uniform_matrix4fv("view", &view_matrix)
-            .unwrap();
+            .try_set_uniform("receiveShadows", self.receive_shadows as i32);
         self.shader_program
-            .set_uniform_matrix4fv("projection", &projection_matrix)
-            .unwrap();
-
-        self.mesh.bind();
-        unsafe {
-            gl::DrawElements(
-                gl::TRIANGLES,
-                self.mesh.index_count() as i32,
-                gl::UNSIGNED_INT,
-                std::ptr::null(),
-            );
+            .try_set_uniform("twoSided", two_sided as i32);
+        match &self.lightmap {
+            Some(lightmap) => lightmap.apply(&self.shader_program),
+            None => {
+                self.shader_program.try_set_uniform("hasLightmap", 0);
+            }
+        }
+        match &self.morph {
+            Some(morph) => morph.apply(&self.shader_program),
+            None => {
+                self.shader_program.try_set_uniform("morphTargetCount", 0);
+            }
+        }
+        match &self.toon {
+            Some(toon) => toon.apply(&self.shader_program),
+            None => {
+                self.shader_program.try_set_uniform("useToonShading", 0);
+            }
+        }
+        match &self.wind_sway {
+            Some(wind_sway) => wind_sway.apply(&self.shader_program),
+            None => {
+                self.shader_program.try_set_uniform("useWindSway", 0);
+            }
+        }
+        let alpha_to_coverage = self.alpha_mode.apply(&self.shader_program);
+        if alpha_to_coverage {
+            alpha_mode::set_alpha_to_coverage(true);
+        }
+        match instances {
+            Some(instances) => self.mesh.draw_instanced(instances),
+            None => self.mesh.draw(),
+        }
+        if alpha_to_coverage {
+            alpha_mode::set_alpha_to_coverage(false);
         }
     }
+
+    /// Checks if `Scene::render` may draw the object in one instanced draw call with `other`.
+    ///
+    /// Both objects must opt in with `instanced`, be opaque, have no lightmap or morph targets
+    /// and share their mesh, shader program and material settings, including toon shading and
+    /// wind sway.
+    pub(crate) fn can_instance_with(&self, other: &Object) -> bool {
+        let instanceable = |object: &Object| {
+            object.instanced
+                && object.alpha_mode == AlphaMode::Opaque
+                && object.lightmap.is_none()
+                && object.morph.is_none()
+        };
+        instanceable(self)
+            && instanceable(other)
+            && Rc::ptr_eq(&self.mesh, &other.mesh)
+            && Rc::ptr_eq(&self.shader_program, &other.shader_program)
+            && self.render_state == other.render_state
+            && self.cull_mode == other.cull_mode
+            && self.receive_shadows == other.receive_shadows
+            && self.toon == other.toon
+            && self.wind_sway == other.wind_sway
+    }
+
+    /// Renders the object's geometry with another shader program, e.g. for a shadow pass.
+    ///
+    /// The shader program must be bound before calling this function. Only the "model" uniform
+    /// is set; the object's own shader program and render state are not used.
+    ///
+    /// # Arguments
+    ///
+    /// * `shader_program` - The bound shader program to render the geometry with.
+    pub fn render_with(&mut self, shader_program: &ShaderProgram) {
+        shader_program.try_set_uniform("model", self.transform.matrix());
+        self.mesh.draw();
+    }
 }
@@ -0,0 +1,259 @@
+//! # Occlusion Module
+//!
+//! This module provides an `OcclusionCuller` that skips objects hidden behind other geometry.
+//! After the visible objects were drawn, the bounding box of every candidate is rendered
+//! against the depth buffer with an occlusion `Query`. The results are read during the next
+//! frame without waiting for the GPU, so the decision for a frame is based on the depth of
+//! the previous one. An object is only hidden after its box was occluded for several frames
+//! in a row, which avoids objects popping in and out at the edges of occluders. Hidden
+//! objects are shown again as soon as their box becomes visible.
+//!
+//! Queries cost a draw call each, so only heavy objects should be candidates.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::scene::{OcclusionCuller, Object, Scene};
+//! use glwfr::math::Aabb;
+//! use glwfr::cgmath::Point3;
+//!
+//! fn setup(scene: &mut Scene, mut statue: Object) -> Result<(), glwfr::custom_errors::Errors> {
+//!     let mut culler = OcclusionCuller::new()?;
+//!     culler.hysteresis_frames = 4;
+//!     scene.set_occlusion_culler(Some(culler));
+//!
+//!     statue.bounds = Some(Aabb::new(Point3::new(-1.0, 0.0, -1.0), Point3::new(1.0, 3.0, 1.0)));
+//!     statue.occlusion_culling = true;
+//!     scene.add_object(statue);
+//!     Ok(())
+//! }
+//! ```
+
+use crate::custom_errors::Errors;
+use crate::graphics::gl_wrapper::{PrimitiveType, Query, QueryTarget, ShaderProgram, Vao};
+use crate::graphics::render_state::{CompareFunc, DepthState, RenderState};
+use crate::graphics::stats;
+use crate::math::Aabb;
+use cgmath::*;
+
+const BOX_VERTEX_SHADER: &str = r#"#version 330 core
+uniform mat4 viewProjection;
+uniform vec3 boxMin;
+uniform vec3 boxMax;
+
+// Corner indices of the twelve triangles of a box, with bit 0, 1 and 2 selecting max x, y and z
+const int BOX_INDICES[36] = int[36](
+    0, 2, 3, 0, 3, 1,
+    4, 5, 7, 4, 7, 6,
+    0, 4, 6, 0, 6, 2,
+    1, 3, 7, 1, 7, 5,
+    0, 1, 5, 0, 5, 4,
+    2, 6, 7, 2, 7, 3
+);
+
+void main() {
+    int corner = BOX_INDICES[gl_VertexID];
+    vec3 select = vec3(corner & 1, (corner >> 1) & 1, (corner >> 2) & 1);
+    gl_Position = viewProjection * vec4(mix(boxMin, boxMax, select), 1.0);
+}
+"#;
+
+const BOX_FRAGMENT_SHADER: &str = r#"#version 330 core
+void main() {
+}
+"#;
+
+/// The occlusion state of one object.
+struct OcclusionEntry {
+    query: Option<Query>,
+    pending: bool,
+    visible: bool,
+    occluded_frames: u32,
+}
+
+impl OcclusionEntry {
+    fn new() -> Self {
+        Self {
+            query: None,
+            pending: false,
+            visible: true,
+            occluded_frames: 0,
+        }
+    }
+
+    fn mark_visible(&mut self) {
+        self.visible = true;
+        self.occluded_frames = 0;
+    }
+}
+
+/// Hides objects whose bounding boxes were occluded in previous frames.
+///
+/// Objects are identified by their index in the scene, see `Scene::set_occlusion_culler`.
+pub struct OcclusionCuller {
+    shader_program: ShaderProgram,
+    vao: Vao,
+    entries: Vec<OcclusionEntry>,
+    /// The number of query results in a row that must report the box as occluded before the
+    /// object is hidden.
+    pub hysteresis_frames: u32,
+    /// The distance the bounding boxes are grown by before testing them, so objects appear
+    /// slightly before they come into view.
+    pub padding: f32,
+    /// Whether queries are issued. While disabled, every object counts as visible.
+    pub enabled: bool,
+}
+
+impl OcclusionCuller {
+    /// Creates an occlusion culler with no tracked objects.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::ShaderCompilationError` if the bounding box shader fails to
+    /// compile, or an `Errors::OpenGlError` if the VAO cannot be created.
+    pub fn new() -> Result<Self, Errors> {
+        Ok(Self {
+            shader_program: ShaderProgram::from_source(BOX_VERTEX_SHADER, BOX_FRAGMENT_SHADER)?,
+            vao: Vao::new()?,
+            entries: Vec::new(),
+            hysteresis_frames: 3,
+            padding: 0.1,
+            enabled: true,
+        })
+    }
+
+    /// Checks if the object with the given index should be drawn.
+    ///
+    /// Objects that have not been tested yet count as visible.
+    pub fn is_visible(&self, index: usize) -> bool {
+        !self.enabled || self.entries.get(index).is_none_or(|entry| entry.visible)
+    }
+
+    /// Returns the number of objects currently hidden.
+    pub fn occluded_count(&self) -> usize {
+        if !self.enabled {
+            return 0;
+        }
+        self.entries.iter().filter(|entry| !entry.visible).count()
+    }
+
+    /// Forgets all query results, so every object is drawn again, e.g. after a camera cut.
+    pub fn reset(&mut self) {
+        for entry in &mut self.entries {
+            entry.pending = false;
+            entry.mark_visible();
+        }
+    }
+
+    /// Reads the query results that are available without waiting for the GPU.
+    ///
+    /// Called by `Scene::render` before drawing. Queries the GPU has not finished yet keep
+    /// the previous decision and are read during a later frame.
+    pub fn update_results(&mut self) {
+        for entry in &mut self.entries {
+            if !entry.pending {
+                continue;
+            }
+            let Some(samples) = entry.query.as_ref().and_then(Query::try_result) else {
+                continue;
+            };
+            entry.pending = false;
+            if samples > 0 {
+                entry.mark_visible();
+            } else {
+                entry.occluded_frames = entry.occluded_frames.saturating_add(1);
+                if entry.occluded_frames >= self.hysteresis_frames.max(1) {
+                    entry.visible = false;
+                }
+            }
+        }
+    }
+
+    /// Renders the bounding boxes of candidate objects against the current depth buffer.
+    ///
+    /// Called by `Scene::render` after drawing. Objects whose previous query has not
+    /// returned yet are skipped, and boxes that cross the camera's near plane count as
+    /// visible without a query, since their front faces would be clipped. Color and depth
+    /// writes are disabled while the boxes are drawn; afterwards the default `RenderState`
+    /// is applied and color writes are enabled again.
+    ///
+    /// # Arguments
+    ///
+    /// * `view_projection` - The view-projection matrix the frame was rendered with.
+    /// * `candidates` - The index and world space bounding box of every candidate object.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glColorMask`, `glBeginQuery`, `glDrawArrays` and
+    /// `glEndQuery`.
+    pub fn issue_queries(
+        &mut self,
+        view_projection: Matrix4<f32>,
+        candidates: impl IntoIterator<Item = (usize, Aabb)>,
+    ) {
+        if !self.enabled {
+            return;
+        }
+        let near_plane = view_projection.invert().and_then(|inverse| {
+            Aabb::from_points(
+                [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)]
+                    .map(|(x, y)| inverse.transform_point(Point3::new(x, y, -1.0))),
+            )
+        });
+
+        RenderState {
+            depth: Some(DepthState {
+                func: CompareFunc::LessEqual,
+                write: false,
+            }),
+            blend: None,
+            cull: None,
+            depth_bias: None,
+        }
+        .apply();
+        unsafe {
+            gl::ColorMask(gl::FALSE, gl::FALSE, gl::FALSE, gl::FALSE);
+        }
+        self.shader_program.bind();
+        self.shader_program
+            .try_set_uniform("viewProjection", view_projection);
+        self.vao.bind();
+
+        let padding = Vector3::new(self.padding, self.padding, self.padding);
+        for (index, bounds) in candidates {
+            if self.entries.len() <= index {
+                self.entries.resize_with(index + 1, OcclusionEntry::new);
+            }
+            let entry = &mut self.entries[index];
+            if entry.pending {
+                continue;
+            }
+            let bounds = Aabb::new(bounds.min - padding, bounds.max + padding);
+            if near_plane.is_none_or(|near_plane| near_plane.intersects_aabb(&bounds)) {
+                entry.mark_visible();
+                continue;
+            }
+            if entry.query.is_none() {
+                entry.query = Query::new(QueryTarget::AnySamplesPassed).ok();
+            }
+            let Some(query) = &entry.query else {
+                continue;
+            };
+            self.shader_program.try_set_uniform("boxMin", bounds.min);
+            self.shader_program.try_set_uniform("boxMax", bounds.max);
+            query.begin();
+            unsafe {
+                gl::DrawArrays(gl::TRIANGLES, 0, 36);
+            }
+            query.end();
+            stats::record_draw(PrimitiveType::Triangles, 36, 1);
+            entry.pending = true;
+        }
+
+        self.vao.unbind();
+        unsafe {
+            gl::ColorMask(gl::TRUE, gl::TRUE, gl::TRUE, gl::TRUE);
+        }
+        RenderState::default().apply();
+    }
+}
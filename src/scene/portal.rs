@@ -0,0 +1,341 @@
+//! # Portal Module
+//!
+//! This module provides portal-cell visibility for indoor scenes. The level is divided into
+//! cells, such as rooms and corridors, connected by portals, such as doorways and windows.
+//! Starting at the cell containing the camera, the cells behind every portal on screen are
+//! visited, with the screen area narrowed to the portal each time, so rooms hidden behind
+//! walls are skipped even when they are inside the view frustum.
+//!
+//! Cells are boxes and may overlap slightly around their portals; while the camera stands
+//! in a doorway, traversal starts in every cell containing it.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::scene::{PortalSystem, Scene};
+//! use glwfr::math::Aabb;
+//! use glwfr::cgmath::Point3;
+//!
+//! fn setup(scene: &mut Scene) {
+//!     let mut portals = PortalSystem::new();
+//!     let hall = portals.add_cell(Aabb::new(Point3::new(0.0, 0.0, 0.0), Point3::new(10.0, 4.0, 10.0)));
+//!     let kitchen = portals.add_cell(Aabb::new(Point3::new(10.0, 0.0, 0.0), Point3::new(16.0, 4.0, 8.0)));
+//!     let door = portals.add_portal(
+//!         hall,
+//!         kitchen,
+//!         vec![
+//!             Point3::new(10.0, 0.0, 3.0),
+//!             Point3::new(10.0, 0.0, 5.0),
+//!             Point3::new(10.0, 2.5, 5.0),
+//!             Point3::new(10.0, 2.5, 3.0),
+//!         ],
+//!     );
+//!     // Closed doors block the view
+//!     portals.get_mut_portal(door).unwrap().open = false;
+//!     scene.set_portal_system(Some(portals));
+//! }
+//! ```
+
+use crate::math::Aabb;
+use crate::scene::Object;
+use cgmath::*;
+
+/// Identifies a cell of a `PortalSystem`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CellId(usize);
+
+/// Identifies a portal of a `PortalSystem`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PortalId(usize);
+
+/// An opening connecting two cells.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Portal {
+    /// The corners of the opening, in order around a convex polygon, in world space.
+    pub polygon: Vec<Point3<f32>>,
+    /// The cells on either side of the opening.
+    pub cells: (CellId, CellId),
+    /// Whether the cells can be seen through the portal, e.g. `false` for a closed door.
+    pub open: bool,
+}
+
+impl Portal {
+    /// Returns the cell on the other side of the portal, or `None` if the portal does not
+    /// touch the given cell.
+    pub fn other_cell(&self, cell: CellId) -> Option<CellId> {
+        match self.cells {
+            (a, b) if a == cell => Some(b),
+            (a, b) if b == cell => Some(a),
+            _ => None,
+        }
+    }
+}
+
+/// A region of the screen in normalized device coordinates.
+#[derive(Debug, Clone, Copy)]
+struct ScreenRect {
+    min: Vector2<f32>,
+    max: Vector2<f32>,
+}
+
+impl ScreenRect {
+    const FULL: ScreenRect = ScreenRect {
+        min: Vector2::new(-1.0, -1.0),
+        max: Vector2::new(1.0, 1.0),
+    };
+
+    fn intersection(&self, other: &ScreenRect) -> Option<ScreenRect> {
+        let rect = ScreenRect {
+            min: Vector2::new(self.min.x.max(other.min.x), self.min.y.max(other.min.y)),
+            max: Vector2::new(self.max.x.min(other.max.x), self.max.y.min(other.max.y)),
+        };
+        (rect.min.x < rect.max.x && rect.min.y < rect.max.y).then_some(rect)
+    }
+}
+
+/// The cells and portals of an indoor level.
+#[derive(Debug, Clone, Default)]
+pub struct PortalSystem {
+    cells: Vec<Aabb>,
+    portals: Vec<Portal>,
+    /// The portals touching each cell.
+    cell_portals: Vec<Vec<PortalId>>,
+}
+
+impl PortalSystem {
+    /// Creates an empty portal system.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a cell and returns its id.
+    ///
+    /// # Arguments
+    ///
+    /// * `bounds` - The box enclosing the cell, e.g. the walls, floor and ceiling of a room.
+    pub fn add_cell(&mut self, bounds: Aabb) -> CellId {
+        self.cells.push(bounds);
+        self.cell_portals.push(Vec::new());
+        CellId(self.cells.len() - 1)
+    }
+
+    /// Adds an open portal between two cells and returns its id.
+    ///
+    /// # Arguments
+    ///
+    /// * `a` - The cell on one side of the portal.
+    /// * `b` - The cell on the other side of the portal.
+    /// * `polygon` - The corners of the opening, in order around a convex polygon.
+    ///
+    /// # Panics
+    ///
+    /// Panics if one of the cells does not belong to this portal system.
+    pub fn add_portal(&mut self, a: CellId, b: CellId, polygon: Vec<Point3<f32>>) -> PortalId {
+        let id = PortalId(self.portals.len());
+        self.cell_portals[a.0].push(id);
+        if a != b {
+            self.cell_portals[b.0].push(id);
+        }
+        self.portals.push(Portal {
+            polygon,
+            cells: (a, b),
+            open: true,
+        });
+        id
+    }
+
+    /// Returns the bounds of a cell.
+    pub fn cell_bounds(&self, cell: CellId) -> Option<&Aabb> {
+        self.cells.get(cell.0)
+    }
+
+    /// Returns the number of cells.
+    pub fn cell_count(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// Returns a portal.
+    pub fn portal(&self, portal: PortalId) -> Option<&Portal> {
+        self.portals.get(portal.0)
+    }
+
+    /// Returns a mutable reference to a portal, e.g. to open or close it.
+    pub fn get_mut_portal(&mut self, portal: PortalId) -> Option<&mut Portal> {
+        self.portals.get_mut(portal.0)
+    }
+
+    /// Returns an iterator over the cells containing the point.
+    pub fn cells_at(&self, point: Point3<f32>) -> impl Iterator<Item = CellId> + '_ {
+        self.cells
+            .iter()
+            .enumerate()
+            .filter(move |(_, bounds)| bounds.contains_point(point))
+            .map(|(index, _)| CellId(index))
+    }
+
+    /// Finds the cells visible from the camera.
+    ///
+    /// # Arguments
+    ///
+    /// * `view_projection` - The view-projection matrix of the camera.
+    /// * `camera_position` - The position of the camera in world space.
+    ///
+    /// # Returns
+    ///
+    /// The visible cells. If the camera is outside of every cell, culling is disabled and
+    /// every object counts as visible.
+    pub fn visibility(
+        &self,
+        view_projection: Matrix4<f32>,
+        camera_position: Point3<f32>,
+    ) -> PortalVisibility {
+        let mut visibility = PortalVisibility {
+            visible: vec![false; self.cells.len()],
+            cells: self.cells.clone(),
+            inside: false,
+        };
+        let mut path = Vec::new();
+        for cell in self.cells_at(camera_position) {
+            visibility.inside = true;
+            self.visit(
+                cell,
+                ScreenRect::FULL,
+                view_projection,
+                &mut path,
+                &mut visibility,
+            );
+        }
+        visibility
+    }
+
+    /// Marks a cell as visible and visits the cells behind the portals inside `rect`.
+    fn visit(
+        &self,
+        cell: CellId,
+        rect: ScreenRect,
+        view_projection: Matrix4<f32>,
+        path: &mut Vec<CellId>,
+        visibility: &mut PortalVisibility,
+    ) {
+        visibility.visible[cell.0] = true;
+        path.push(cell);
+        for portal_id in &self.cell_portals[cell.0] {
+            let portal = &self.portals[portal_id.0];
+            let Some(next) = portal.other_cell(cell) else {
+                continue;
+            };
+            if !portal.open || path.contains(&next) {
+                continue;
+            }
+            let narrowed = project_polygon(&portal.polygon, view_projection)
+                .and_then(|portal_rect| portal_rect.intersection(&rect));
+            if let Some(narrowed) = narrowed {
+                self.visit(next, narrowed, view_projection, path, visibility);
+            }
+        }
+        path.pop();
+    }
+}
+
+/// Returns the screen area covered by a polygon, clipped against the near plane.
+fn project_polygon(polygon: &[Point3<f32>], view_projection: Matrix4<f32>) -> Option<ScreenRect> {
+    const NEAR_W: f32 = 1e-5;
+    let clip: Vec<Vector4<f32>> = polygon
+        .iter()
+        .map(|point| view_projection * point.to_homogeneous())
+        .collect();
+
+    // Keep the part of the polygon in front of the camera
+    let mut clipped = Vec::with_capacity(clip.len() + 1);
+    for (i, &current) in clip.iter().enumerate() {
+        let next = clip[(i + 1) % clip.len()];
+        if current.w >= NEAR_W {
+            clipped.push(current);
+        }
+        if (current.w >= NEAR_W) != (next.w >= NEAR_W) {
+            let t = (NEAR_W - current.w) / (next.w - current.w);
+            clipped.push(current + (next - current) * t);
+        }
+    }
+
+    let mut points = clipped
+        .iter()
+        .map(|point| Vector2::new(point.x / point.w, point.y / point.w));
+    let first = points.next()?;
+    let rect = points.fold(
+        ScreenRect {
+            min: first,
+            max: first,
+        },
+        |rect, point| ScreenRect {
+            min: Vector2::new(rect.min.x.min(point.x), rect.min.y.min(point.y)),
+            max: Vector2::new(rect.max.x.max(point.x), rect.max.y.max(point.y)),
+        },
+    );
+    Some(rect)
+}
+
+/// The result of a portal traversal, telling which cells and objects are visible.
+#[derive(Debug, Clone)]
+pub struct PortalVisibility {
+    visible: Vec<bool>,
+    cells: Vec<Aabb>,
+    inside: bool,
+}
+
+impl PortalVisibility {
+    /// Returns `true` if the camera is inside a cell, so culling is active.
+    pub fn is_active(&self) -> bool {
+        self.inside
+    }
+
+    /// Checks if a cell was reached from the camera.
+    pub fn is_cell_visible(&self, cell: CellId) -> bool {
+        !self.inside || self.visible.get(cell.0).copied().unwrap_or(false)
+    }
+
+    /// Returns an iterator over the visible cells.
+    pub fn visible_cells(&self) -> impl Iterator<Item = CellId> + '_ {
+        self.visible
+            .iter()
+            .enumerate()
+            .filter(|(_, visible)| **visible)
+            .map(|(index, _)| CellId(index))
+    }
+
+    /// Checks if a box in world space may be visible.
+    ///
+    /// Boxes touching a visible cell are visible, and so are boxes outside of every cell,
+    /// such as the sky or terrain seen through a window.
+    pub fn is_aabb_visible(&self, bounds: &Aabb) -> bool {
+        if !self.inside {
+            return true;
+        }
+        let mut in_any_cell = false;
+        for (cell, visible) in self.cells.iter().zip(&self.visible) {
+            if cell.intersects_aabb(bounds) {
+                if *visible {
+                    return true;
+                }
+                in_any_cell = true;
+            }
+        }
+        !in_any_cell
+    }
+
+    /// Checks if a point in world space may be visible. See `is_aabb_visible`.
+    pub fn is_point_visible(&self, point: Point3<f32>) -> bool {
+        self.is_aabb_visible(&Aabb::new(point, point))
+    }
+
+    /// Checks if an object may be visible, using its bounds or, without bounds, its position.
+    pub fn is_object_visible(&self, object: &mut Object) -> bool {
+        if !self.inside {
+            return true;
+        }
+        match object.world_bounds() {
+            Some(bounds) => self.is_aabb_visible(&bounds),
+            None => self.is_point_visible(Point3::from_vec(object.transform.position())),
+        }
+    }
+}
@@ -0,0 +1,305 @@
+//! # Prefab Module
+//!
+//! This module provides prefabs: object templates that are defined once and spawned into a
+//! scene many times. A `Prefab` is a list of parts, each with a mesh, a shader program,
+//! material settings and a transform relative to the prefab. Other prefabs can be nested as
+//! children, e.g. a lantern hanging from a tree.
+//!
+//! Meshes and shader programs are shared through `Rc`, so spawning a prefab only creates the
+//! objects, without uploading any geometry. `Scene::spawn_prefab` records every spawn as a
+//! `PrefabPlacement`, which can be saved as text and loaded again into a scene with the same
+//! prefabs registered.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::scene::{Prefab, PrefabPart, Scene, Transform};
+//! use glwfr::graphics::mesh::Mesh;
+//! use glwfr::graphics::gl_wrapper::ShaderProgram;
+//! use glwfr::cgmath::Vector3;
+//! use std::rc::Rc;
+//!
+//! fn setup(scene: &mut Scene, trunk: Mesh, crown: Mesh) -> Result<(), glwfr::custom_errors::Errors> {
+//!     let shader_program = Rc::new(ShaderProgram::new("vertex.glsl", "fragment.glsl")?);
+//!     let mut crown_transform = Transform::new();
+//!     crown_transform.set_position(Vector3::new(0.0, 2.0, 0.0));
+//!     let tree = Prefab::new()
+//!         .with_part(PrefabPart::new(trunk, shader_program.clone()))
+//!         .with_part(PrefabPart::new(crown, shader_program).with_transform(crown_transform))
+//!         .with_tag("tree");
+//!     scene.register_prefab("tree", tree)?;
+//!
+//!     for x in 0..10 {
+//!         let mut transform = Transform::new();
+//!         transform.set_position(Vector3::new(x as f32 * 4.0, 0.0, 0.0));
+//!         scene.spawn_prefab("tree", transform)?;
+//!     }
+//!
+//!     // Save the placements, e.g. to a level file, and load them again later
+//!     let saved = scene.save_prefab_placements();
+//!     scene.load_prefab_placements(&saved)?;
+//!     Ok(())
+//! }
+//! ```
+
+use crate::custom_errors::Errors;
+use crate::graphics::alpha_mode::AlphaMode;
+use crate::graphics::gl_wrapper::ShaderProgram;
+use crate::graphics::mesh::Mesh;
+use crate::graphics::render_state::{CullMode, RenderState};
+use crate::math::Aabb;
+use crate::scene::{Object, ToonShading, Transform, WindSway};
+use cgmath::{Quaternion, Vector3};
+use std::fmt;
+use std::rc::Rc;
+
+/// A single object of a prefab.
+#[derive(Clone)]
+pub struct PrefabPart {
+    /// The mesh of the part, shared by all spawned objects.
+    pub mesh: Rc<Mesh>,
+    /// The shader program of the part, shared by all spawned objects.
+    pub shader_program: Rc<ShaderProgram>,
+    /// The transform of the part relative to the prefab.
+    pub transform: Transform,
+    /// See `Object::render_state`.
+    pub render_state: Option<RenderState>,
+    /// See `Object::alpha_mode`.
+    pub alpha_mode: AlphaMode,
+    /// See `Object::cull_mode`.
+    pub cull_mode: Option<CullMode>,
+    /// See `Object::cast_shadows`.
+    pub cast_shadows: bool,
+    /// See `Object::receive_shadows`.
+    pub receive_shadows: bool,
+    /// See `Object::toon`.
+    pub toon: Option<ToonShading>,
+    /// See `Object::wind_sway`.
+    pub wind_sway: Option<WindSway>,
+    /// See `Object::bounds`.
+    pub bounds: Option<Aabb>,
+    /// See `Object::occlusion_culling`.
+    pub occlusion_culling: bool,
+    /// See `Object::is_static`.
+    pub is_static: bool,
+    /// See `Object::instanced`.
+    pub instanced: bool,
+    /// The tags given to the spawned object.
+    pub tags: Vec<String>,
+}
+
+impl PrefabPart {
+    /// Creates a part with the default settings of `Object::new` at the origin of the prefab.
+    ///
+    /// # Arguments
+    ///
+    /// * `mesh` - The mesh of the part. Pass an `Rc<Mesh>` to share it with other parts.
+    /// * `shader_program` - The shader program used to render the part.
+    pub fn new(mesh: impl Into<Rc<Mesh>>, shader_program: impl Into<Rc<ShaderProgram>>) -> Self {
+        Self {
+            mesh: mesh.into(),
+            shader_program: shader_program.into(),
+            transform: Transform::new(),
+            render_state: None,
+            alpha_mode: AlphaMode::Opaque,
+            cull_mode: None,
+            cast_shadows: true,
+            receive_shadows: true,
+            toon: None,
+            wind_sway: None,
+            bounds: None,
+            occlusion_culling: false,
+            is_static: false,
+            instanced: false,
+            tags: Vec::new(),
+        }
+    }
+
+    /// Returns the part with the given transform relative to the prefab.
+    pub fn with_transform(mut self, transform: Transform) -> Self {
+        self.transform = transform;
+        self
+    }
+
+    /// Returns the part with the given tag added.
+    pub fn with_tag(mut self, tag: impl AsRef<str>) -> Self {
+        self.tags.push(tag.as_ref().to_string());
+        self
+    }
+
+    /// Creates the object of the part for a prefab spawned at the given transform.
+    pub fn instantiate(&self, parent: &Transform) -> Object {
+        let mut object = Object::new(self.mesh.clone(), self.shader_program.clone());
+        object.transform = parent.combine(&self.transform);
+        object.render_state = self.render_state;
+        object.alpha_mode = self.alpha_mode;
+        object.cull_mode = self.cull_mode;
+        object.cast_shadows = self.cast_shadows;
+        object.receive_shadows = self.receive_shadows;
+        object.toon = self.toon.clone();
+        object.wind_sway = self.wind_sway;
+        object.bounds = self.bounds;
+        object.occlusion_culling = self.occlusion_culling;
+        object.is_static = self.is_static;
+        object.instanced = self.instanced;
+        for tag in &self.tags {
+            object.add_tag(tag);
+        }
+        object
+    }
+}
+
+/// A template of one or more objects that can be spawned many times.
+#[derive(Clone, Default)]
+pub struct Prefab {
+    parts: Vec<PrefabPart>,
+}
+
+impl Prefab {
+    /// Creates an empty prefab.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the prefab with the given part added.
+    pub fn with_part(mut self, part: PrefabPart) -> Self {
+        self.parts.push(part);
+        self
+    }
+
+    /// Returns the prefab with the parts of another prefab added as children.
+    ///
+    /// # Arguments
+    ///
+    /// * `transform` - The transform of the child prefab relative to this prefab.
+    /// * `child` - The prefab to nest.
+    pub fn with_child(mut self, transform: &Transform, child: &Prefab) -> Self {
+        self.parts.extend(child.parts.iter().map(|part| {
+            let mut part = part.clone();
+            part.transform = transform.combine(&part.transform);
+            part
+        }));
+        self
+    }
+
+    /// Returns the prefab with the given tag added to all of its parts.
+    pub fn with_tag(mut self, tag: impl AsRef<str>) -> Self {
+        for part in &mut self.parts {
+            part.tags.push(tag.as_ref().to_string());
+        }
+        self
+    }
+
+    /// Returns the parts of the prefab, including those of nested children.
+    pub fn parts(&self) -> &[PrefabPart] {
+        &self.parts
+    }
+
+    /// Returns the parts of the prefab mutably, e.g. to change the material of a part.
+    pub fn parts_mut(&mut self) -> &mut [PrefabPart] {
+        &mut self.parts
+    }
+
+    /// Creates the objects of all parts for the prefab spawned at the given transform.
+    pub fn instantiate(&self, transform: &Transform) -> Vec<Object> {
+        self.parts
+            .iter()
+            .map(|part| part.instantiate(transform))
+            .collect()
+    }
+}
+
+/// A prefab spawned into a scene, as recorded by `Scene::spawn_prefab`.
+#[derive(Debug, Clone)]
+pub struct PrefabPlacement {
+    /// The name the prefab is registered with.
+    pub name: String,
+    /// The transform the prefab was spawned at.
+    pub transform: Transform,
+}
+
+impl PrefabPlacement {
+    /// Parses a placement from a line written by its `Display` implementation:
+    ///
+    /// ```text
+    /// prefab name=tree position=1,0,2 rotation=1,0,0,0 scale=1,1,1
+    /// ```
+    ///
+    /// The rotation is a quaternion in `w,x,y,z` order. Missing fields keep the defaults of
+    /// `Transform::new`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::PrefabLoadError` if the line is not a placement or a field is
+    /// malformed.
+    pub fn parse(line: &str) -> Result<Self, Errors> {
+        let mut fields = line.split_whitespace();
+        if fields.next() != Some("prefab") {
+            return Err(Errors::PrefabLoadError(format!(
+                "expected a prefab line, found \"{}\"",
+                line
+            )));
+        }
+        let mut name = None;
+        let mut transform = Transform::new();
+        for field in fields {
+            let (key, value) = field.split_once('=').ok_or_else(|| {
+                Errors::PrefabLoadError(format!("field without value: {}", field))
+            })?;
+            match key {
+                "name" => name = Some(value.to_string()),
+                "position" => {
+                    let [x, y, z] = parse_numbers(key, value)?;
+                    transform.set_position(Vector3::new(x, y, z));
+                }
+                "rotation" => {
+                    let [w, x, y, z] = parse_numbers(key, value)?;
+                    transform.set_rotation(Quaternion::new(w, x, y, z));
+                }
+                "scale" => {
+                    let [x, y, z] = parse_numbers(key, value)?;
+                    transform.set_scale(Vector3::new(x, y, z));
+                }
+                _ => {}
+            }
+        }
+        let name = name.ok_or_else(|| {
+            Errors::PrefabLoadError(format!("prefab line without name: \"{}\"", line))
+        })?;
+        Ok(Self { name, transform })
+    }
+}
+
+impl fmt::Display for PrefabPlacement {
+    /// Formats the placement as a single line understood by `PrefabPlacement::parse`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let position = self.transform.position();
+        let rotation = self.transform.rotation();
+        let scale = self.transform.scale();
+        write!(
+            f,
+            "prefab name={} position={},{},{} rotation={},{},{},{} scale={},{},{}",
+            self.name,
+            position.x,
+            position.y,
+            position.z,
+            rotation.s,
+            rotation.v.x,
+            rotation.v.y,
+            rotation.v.z,
+            scale.x,
+            scale.y,
+            scale.z
+        )
+    }
+}
+
+/// Parses `N` comma-separated numbers of the field `key`.
+fn parse_numbers<const N: usize>(key: &str, value: &str) -> Result<[f32; N], Errors> {
+    let invalid = || Errors::PrefabLoadError(format!("invalid {}: {}", key, value));
+    let numbers = value
+        .split(',')
+        .map(|number| number.trim().parse::<f32>().map_err(|_| invalid()))
+        .collect::<Result<Vec<_>, _>>()?;
+    numbers.try_into().map_err(|_| invalid())
+}
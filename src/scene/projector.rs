@@ -0,0 +1,192 @@
+//! # Projector Module
+//!
+//! This module provides projector lights, which project a texture through a perspective
+//! frustum onto the geometry in front of them: flashlight cookies, stained-glass windows or
+//! video projectors.
+//!
+//! Lit shaders can paste `PROJECTOR_GLSL` into their fragment shader and add
+//! `projectorLight(fragPos, normal)` to their lighting. `Scene::render` uploads the scene's
+//! projectors together with its environment.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::scene::{Projector, Scene};
+//! use glwfr::graphics::texture::Texture;
+//! use glwfr::cgmath::{Deg, Point3, Vector3};
+//! use std::rc::Rc;
+//!
+//! fn add_flashlight(scene: &mut Scene) -> Result<(), glwfr::custom_errors::Errors> {
+//!     let cookie = Texture::new();
+//!     cookie.load_from_file("flashlight_cookie.png")?;
+//!
+//!     let mut flashlight = Projector::new(Rc::new(cookie), Deg(35.0));
+//!     flashlight.position = Point3::new(0.0, 1.6, 0.0);
+//!     flashlight.target = Point3::new(0.0, 1.0, -5.0);
+//!     flashlight.color = Vector3::new(1.0, 0.95, 0.8);
+//!     flashlight.intensity = 2.0;
+//!     scene.add_projector(flashlight);
+//!     Ok(())
+//! }
+//! ```
+
+use crate::graphics::gl_wrapper::ShaderProgram;
+use crate::graphics::texture::Texture;
+use crate::graphics::texture_unit::TextureUnit;
+use cgmath::*;
+use std::rc::Rc;
+
+/// The maximum number of projectors uploaded to a shader.
+pub const MAX_PROJECTORS: usize = 4;
+
+/// The texture unit of the first projector. Further projectors use the following units.
+pub const FIRST_PROJECTOR_UNIT: u32 = 8;
+
+/// GLSL helpers for projector lights in a fragment shader.
+///
+/// Declares the uniforms set by `apply_projectors` and a
+/// `projectorLight(worldPos, normal)` function returning the projected light reaching a
+/// surface point.
+pub const PROJECTOR_GLSL: &str = r#"
+#define MAX_PROJECTORS 4
+
+uniform int projectorCount;
+uniform mat4 projectorMatrices[MAX_PROJECTORS];
+uniform sampler2D projectorTextures[MAX_PROJECTORS];
+uniform vec3 projectorPositions[MAX_PROJECTORS];
+uniform vec3 projectorColors[MAX_PROJECTORS];
+uniform float projectorRanges[MAX_PROJECTORS];
+
+vec3 projectorLight(vec3 worldPos, vec3 normal) {
+    vec3 result = vec3(0.0);
+    vec3 n = normalize(normal);
+    for (int i = 0; i < MAX_PROJECTORS; i++) {
+        if (i >= projectorCount) {
+            break;
+        }
+        vec4 clip = projectorMatrices[i] * vec4(worldPos, 1.0);
+        if (clip.w <= 0.0) {
+            continue;
+        }
+        vec3 ndc = clip.xyz / clip.w;
+        if (any(greaterThan(abs(ndc), vec3(1.0)))) {
+            continue;
+        }
+        vec3 toProjector = projectorPositions[i] - worldPos;
+        float distance = length(toProjector);
+        float facing = max(dot(n, toProjector / distance), 0.0);
+        float attenuation = clamp(1.0 - distance / projectorRanges[i], 0.0, 1.0);
+        // Images are stored top row first, so the top of the frustum samples v = 0
+        vec2 uv = vec2(ndc.x, -ndc.y) * 0.5 + 0.5;
+        vec3 cookie;
+        // Samplers in arrays must be indexed with constants in GLSL 3.30
+        if (i == 0) cookie = texture(projectorTextures[0], uv).rgb;
+        else if (i == 1) cookie = texture(projectorTextures[1], uv).rgb;
+        else if (i == 2) cookie = texture(projectorTextures[2], uv).rgb;
+        else cookie = texture(projectorTextures[3], uv).rgb;
+        result += cookie * projectorColors[i] * facing * attenuation * attenuation;
+    }
+    return result;
+}
+"#;
+
+/// A light that projects a texture through a perspective frustum.
+pub struct Projector {
+    /// The position of the projector in world space.
+    pub position: Point3<f32>,
+    /// The point the projector is aimed at.
+    pub target: Point3<f32>,
+    /// The up direction of the projected image.
+    pub up: Vector3<f32>,
+    /// The vertical opening angle of the frustum.
+    pub fov: Deg<f32>,
+    /// The width of the frustum divided by its height.
+    pub aspect: f32,
+    /// The distance at which projection starts.
+    pub near: f32,
+    /// The distance at which the light has faded out completely.
+    pub range: f32,
+    /// The projected texture. Black texels project no light.
+    pub texture: Rc<Texture>,
+    /// The color the texture is multiplied by.
+    pub color: Vector3<f32>,
+    /// A factor the projected light is multiplied by.
+    pub intensity: f32,
+    /// Whether the projector is uploaded to shaders.
+    pub enabled: bool,
+}
+
+impl Projector {
+    /// Creates a white projector at the origin aimed down the negative z-axis.
+    ///
+    /// # Arguments
+    ///
+    /// * `texture` - The projected texture.
+    /// * `fov` - The vertical opening angle of the frustum.
+    pub fn new(texture: Rc<Texture>, fov: Deg<f32>) -> Self {
+        Self {
+            position: Point3::origin(),
+            target: Point3::new(0.0, 0.0, -1.0),
+            up: Vector3::unit_y(),
+            fov,
+            aspect: 1.0,
+            near: 0.1,
+            range: 20.0,
+            texture,
+            color: Vector3::new(1.0, 1.0, 1.0),
+            intensity: 1.0,
+            enabled: true,
+        }
+    }
+
+    /// Returns the matrix transforming world coordinates into the projector's clip space.
+    pub fn view_projection(&self) -> Matrix4<f32> {
+        let projection: Matrix4<f32> = perspective(
+            self.fov,
+            self.aspect,
+            self.near,
+            self.range.max(self.near + 0.01),
+        );
+        projection * Matrix4::look_at_rh(self.position, self.target, self.up)
+    }
+}
+
+/// Binds the projectors' textures and sets the uniforms declared by `PROJECTOR_GLSL`.
+///
+/// Disabled projectors are skipped and at most `MAX_PROJECTORS` are uploaded. The shader
+/// program must be bound before calling this function. Uniforms the shader does not declare
+/// are skipped.
+///
+/// # Arguments
+///
+/// * `shader_program` - The shader program applying the projectors.
+/// * `projectors` - The projectors to upload.
+pub fn apply_projectors(shader_program: &ShaderProgram, projectors: &[Projector]) {
+    let mut count = 0;
+    for projector in projectors.iter().filter(|projector| projector.enabled) {
+        if count == MAX_PROJECTORS {
+            break;
+        }
+        let unit = TextureUnit::new(FIRST_PROJECTOR_UNIT + count as u32);
+        projector.texture.bind_to_unit(unit);
+        shader_program.try_set_uniform(
+            &format!("projectorTextures[{}]", count),
+            unit.index() as i32,
+        );
+        shader_program.try_set_uniform(
+            &format!("projectorMatrices[{}]", count),
+            projector.view_projection(),
+        );
+        shader_program.try_set_uniform(
+            &format!("projectorPositions[{}]", count),
+            projector.position.to_vec(),
+        );
+        shader_program.try_set_uniform(
+            &format!("projectorColors[{}]", count),
+            projector.color * projector.intensity,
+        );
+        shader_program.try_set_uniform(&format!("projectorRanges[{}]", count), projector.range);
+        count += 1;
+    }
+    shader_program.try_set_uniform("projectorCount", count as i32);
+}
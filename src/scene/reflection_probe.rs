@@ -0,0 +1,519 @@
+//! # Reflection Probe Module
+//!
+//! This module provides reflection probes: points in the scene that capture a cubemap of
+//! their surroundings, so nearby objects can show local reflections instead of the distant
+//! environment map. The mip levels of the cubemap are prefiltered for increasing roughness,
+//! so rough surfaces sample blurrier reflections.
+//!
+//! Each probe has an influence box. Objects inside a box use that probe, or the smallest
+//! one when boxes overlap; objects outside all boxes use the closest probe. With box
+//! projection, reflection directions are corrected for the position within the box, which
+//! keeps reflections in rooms aligned with the walls.
+//!
+//! Shaders can paste `REFLECTION_PROBE_GLSL` into their fragment shader and use
+//! `probeReflection(fragPos, reflectDir, roughness)` as the specular environment term.
+//! `Scene::render` selects and uploads the probe of each object.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::scene::{ReflectionProbe, Scene};
+//! use glwfr::math::Aabb;
+//! use glwfr::cgmath::Point3;
+//!
+//! fn setup(scene: &mut Scene) -> Result<(), glwfr::custom_errors::Errors> {
+//!     let room = Aabb::new(Point3::new(-5.0, 0.0, -5.0), Point3::new(5.0, 3.0, 5.0));
+//!     let mut probe = ReflectionProbe::new(Point3::new(0.0, 1.5, 0.0), room, 256)?;
+//!     probe.box_projection = true;
+//!     scene.add_reflection_probe(probe);
+//!
+//!     // At load time, and again whenever the room changes
+//!     scene.capture_reflection_probes();
+//!     Ok(())
+//! }
+//! ```
+
+use crate::custom_errors::{Errors, GlErrorKind};
+use crate::graphics::gl_wrapper::{ContextBound, PrimitiveType, ShaderProgram, Vao};
+use crate::graphics::stats;
+use crate::graphics::texture_unit::TextureUnit;
+use crate::math::Aabb;
+use cgmath::*;
+use gl::types::*;
+
+/// The texture unit reflection probe cubemaps are bound to.
+pub const REFLECTION_PROBE_UNIT: u32 = 7;
+
+/// GLSL helpers for sampling reflection probes in a fragment shader.
+///
+/// Declares the uniforms set by `ReflectionProbe::apply` and a
+/// `probeReflection(worldPos, reflectDir, roughness)` function returning the reflected
+/// radiance, or black if the object has no probe. `hasReflectionProbe` can be checked to fall
+/// back to the environment map.
+pub const REFLECTION_PROBE_GLSL: &str = r#"
+uniform int hasReflectionProbe;
+uniform samplerCube reflectionProbe;
+uniform vec3 reflectionProbePosition;
+uniform vec3 reflectionProbeBoxMin;
+uniform vec3 reflectionProbeBoxMax;
+uniform int reflectionProbeBoxProjection;
+uniform float reflectionProbeMaxLod;
+
+vec3 probeReflection(vec3 worldPos, vec3 reflectDir, float roughness) {
+    if (hasReflectionProbe == 0) {
+        return vec3(0.0);
+    }
+    vec3 direction = normalize(reflectDir);
+    if (reflectionProbeBoxProjection != 0) {
+        // Intersect the reflection ray with the influence box and look up the hit point
+        // as seen from the probe
+        vec3 toMax = (reflectionProbeBoxMax - worldPos) / direction;
+        vec3 toMin = (reflectionProbeBoxMin - worldPos) / direction;
+        vec3 furthest = max(toMax, toMin);
+        float hitDistance = min(min(furthest.x, furthest.y), furthest.z);
+        direction = worldPos + direction * hitDistance - reflectionProbePosition;
+    }
+    return textureLod(reflectionProbe, direction, roughness * reflectionProbeMaxLod).rgb;
+}
+"#;
+
+const PREFILTER_VERTEX_SHADER: &str = r#"#version 330 core
+out vec2 faceCoords;
+
+void main() {
+    // A single triangle covering the cubemap face
+    vec2 position = vec2((gl_VertexID << 1) & 2, gl_VertexID & 2) * 2.0 - 1.0;
+    faceCoords = position;
+    gl_Position = vec4(position, 0.0, 1.0);
+}
+"#;
+
+const PREFILTER_FRAGMENT_SHADER: &str = r#"#version 330 core
+in vec2 faceCoords;
+out vec4 fragColor;
+
+uniform samplerCube source;
+uniform int face;
+uniform float roughness;
+
+const uint SAMPLE_COUNT = 64u;
+const float PI = 3.14159265359;
+
+vec3 faceDirection(vec2 st) {
+    if (face == 0) return vec3(1.0, -st.y, -st.x);
+    if (face == 1) return vec3(-1.0, -st.y, st.x);
+    if (face == 2) return vec3(st.x, 1.0, st.y);
+    if (face == 3) return vec3(st.x, -1.0, -st.y);
+    if (face == 4) return vec3(st.x, -st.y, 1.0);
+    return vec3(-st.x, -st.y, -1.0);
+}
+
+float radicalInverse(uint bits) {
+    bits = (bits << 16u) | (bits >> 16u);
+    bits = ((bits & 0x55555555u) << 1u) | ((bits & 0xAAAAAAAAu) >> 1u);
+    bits = ((bits & 0x33333333u) << 2u) | ((bits & 0xCCCCCCCCu) >> 2u);
+    bits = ((bits & 0x0F0F0F0Fu) << 4u) | ((bits & 0xF0F0F0F0u) >> 4u);
+    bits = ((bits & 0x00FF00FFu) << 8u) | ((bits & 0xFF00FF00u) >> 8u);
+    return float(bits) * 2.3283064365386963e-10;
+}
+
+vec3 importanceSampleGgx(vec2 xi, vec3 normal, float alpha) {
+    float phi = 2.0 * PI * xi.x;
+    float cosTheta = sqrt((1.0 - xi.y) / (1.0 + (alpha * alpha - 1.0) * xi.y));
+    float sinTheta = sqrt(1.0 - cosTheta * cosTheta);
+    vec3 halfway = vec3(cos(phi) * sinTheta, sin(phi) * sinTheta, cosTheta);
+    vec3 up = abs(normal.z) < 0.999 ? vec3(0.0, 0.0, 1.0) : vec3(1.0, 0.0, 0.0);
+    vec3 tangent = normalize(cross(up, normal));
+    vec3 bitangent = cross(normal, tangent);
+    return normalize(tangent * halfway.x + bitangent * halfway.y + normal * halfway.z);
+}
+
+void main() {
+    vec3 normal = normalize(faceDirection(faceCoords));
+    float alpha = roughness * roughness;
+    vec3 color = vec3(0.0);
+    float totalWeight = 0.0;
+    for (uint i = 0u; i < SAMPLE_COUNT; i++) {
+        vec2 xi = vec2(float(i) / float(SAMPLE_COUNT), radicalInverse(i));
+        vec3 halfway = importanceSampleGgx(xi, normal, alpha);
+        vec3 light = normalize(2.0 * dot(normal, halfway) * halfway - normal);
+        float weight = max(dot(normal, light), 0.0);
+        if (weight > 0.0) {
+            color += textureLod(source, light, 0.0).rgb * weight;
+            totalWeight += weight;
+        }
+    }
+    fragColor = vec4(color / max(totalWeight, 0.0001), 1.0);
+}
+"#;
+
+/// A cubemap capture of the surroundings of a point, used for local reflections.
+pub struct ReflectionProbe {
+    /// The point the cubemap is captured from.
+    pub position: Point3<f32>,
+    /// The volume of objects using this probe, and the box reflections are projected onto.
+    pub influence: Aabb,
+    /// Whether reflection directions are corrected for the position within `influence`.
+    /// Suited for probes in rooms; leave disabled for probes in open areas.
+    pub box_projection: bool,
+    /// The near plane used when capturing.
+    pub near: f32,
+    /// The far plane used when capturing.
+    pub far: f32,
+    /// Whether the probe is used by objects.
+    pub enabled: bool,
+    fbo: GLuint,
+    depth_buffer: GLuint,
+    cubemap: GLuint,
+    resolution: u32,
+    mip_levels: u32,
+    needs_update: bool,
+    captured: bool,
+    prefilter_shader: ShaderProgram,
+    vao: Vao,
+    _context: ContextBound,
+}
+
+impl ReflectionProbe {
+    /// Creates a new reflection probe. Its cubemap is captured by the next
+    /// `Scene::capture_reflection_probes`.
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - The point the cubemap is captured from.
+    /// * `influence` - The volume of objects using this probe.
+    /// * `resolution` - The width and height of each cubemap face in pixels.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::OpenGlError` if the cubemap or framebuffer cannot be created, or an
+    /// `Errors::ShaderCompilationError` if the prefilter shader cannot be compiled.
+    pub fn new(position: Point3<f32>, influence: Aabb, resolution: u32) -> Result<Self, Errors> {
+        let prefilter_shader =
+            ShaderProgram::from_source(PREFILTER_VERTEX_SHADER, PREFILTER_FRAGMENT_SHADER)?;
+        let vao = Vao::new()?;
+        let resolution = resolution.max(1);
+        // Stop at 4x4 faces, which are blurry enough for fully rough surfaces
+        let mip_levels = (resolution.ilog2().saturating_sub(1)).max(1);
+
+        let mut fbo = 0;
+        let mut depth_buffer = 0;
+        let mut cubemap = 0;
+        unsafe {
+            gl::GenFramebuffers(1, &mut fbo);
+            gl::GenRenderbuffers(1, &mut depth_buffer);
+            gl::GenTextures(1, &mut cubemap);
+        }
+        if fbo == 0 || depth_buffer == 0 || cubemap == 0 {
+            return Err(Errors::OpenGlError(
+                "Failed to generate reflection probe".to_string(),
+                GlErrorKind::ObjectCreationFailed,
+            ));
+        }
+
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, cubemap);
+            for level in 0..mip_levels {
+                let size = (resolution >> level).max(1) as GLsizei;
+                for face in 0..6 {
+                    gl::TexImage2D(
+                        gl::TEXTURE_CUBE_MAP_POSITIVE_X + face,
+                        level as GLint,
+                        gl::RGBA16F as GLint,
+                        size,
+                        size,
+                        0,
+                        gl::RGBA,
+                        gl::FLOAT,
+                        std::ptr::null(),
+                    );
+                }
+            }
+            let parameters = [
+                (gl::TEXTURE_MIN_FILTER, gl::LINEAR_MIPMAP_LINEAR),
+                (gl::TEXTURE_MAG_FILTER, gl::LINEAR),
+                (gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE),
+                (gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE),
+                (gl::TEXTURE_WRAP_R, gl::CLAMP_TO_EDGE),
+            ];
+            for (parameter, value) in parameters {
+                gl::TexParameteri(gl::TEXTURE_CUBE_MAP, parameter, value as GLint);
+            }
+            gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_BASE_LEVEL, 0);
+            gl::TexParameteri(
+                gl::TEXTURE_CUBE_MAP,
+                gl::TEXTURE_MAX_LEVEL,
+                mip_levels as GLint - 1,
+            );
+
+            gl::BindRenderbuffer(gl::RENDERBUFFER, depth_buffer);
+            gl::RenderbufferStorage(
+                gl::RENDERBUFFER,
+                gl::DEPTH_COMPONENT24,
+                resolution as GLsizei,
+                resolution as GLsizei,
+            );
+            gl::BindRenderbuffer(gl::RENDERBUFFER, 0);
+
+            gl::BindFramebuffer(gl::FRAMEBUFFER, fbo);
+            gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0,
+                gl::TEXTURE_CUBE_MAP_POSITIVE_X,
+                cubemap,
+                0,
+            );
+            gl::FramebufferRenderbuffer(
+                gl::FRAMEBUFFER,
+                gl::DEPTH_ATTACHMENT,
+                gl::RENDERBUFFER,
+                depth_buffer,
+            );
+            let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            if status != gl::FRAMEBUFFER_COMPLETE {
+                gl::DeleteFramebuffers(1, &fbo);
+                gl::DeleteRenderbuffers(1, &depth_buffer);
+                gl::DeleteTextures(1, &cubemap);
+                return Err(Errors::OpenGlError(
+                    "Reflection probe framebuffer is incomplete".to_string(),
+                    GlErrorKind::FramebufferIncomplete(status),
+                ));
+            }
+        }
+
+        Ok(Self {
+            position,
+            influence,
+            box_projection: false,
+            near: 0.1,
+            far: 100.0,
+            enabled: true,
+            fbo,
+            depth_buffer,
+            cubemap,
+            resolution,
+            mip_levels,
+            needs_update: true,
+            captured: false,
+            prefilter_shader,
+            vao,
+            _context: ContextBound::default(),
+        })
+    }
+
+    /// Returns the width and height of each cubemap face in pixels.
+    pub fn resolution(&self) -> u32 {
+        self.resolution
+    }
+
+    /// Returns the number of prefiltered mip levels.
+    pub fn mip_levels(&self) -> u32 {
+        self.mip_levels
+    }
+
+    /// Returns the OpenGL cubemap handle.
+    pub fn cubemap_id(&self) -> GLuint {
+        self.cubemap
+    }
+
+    /// Marks the probe to be captured again by the next `Scene::capture_reflection_probes`,
+    /// e.g. after a door was opened or the time of day changed.
+    pub fn request_update(&mut self) {
+        self.needs_update = true;
+    }
+
+    /// Returns `true` if the probe has not been captured since it was created or since
+    /// `request_update` was called.
+    pub fn needs_update(&self) -> bool {
+        self.needs_update
+    }
+
+    /// Returns `true` once the probe has been captured and can be used by objects.
+    pub fn is_captured(&self) -> bool {
+        self.captured
+    }
+
+    /// Returns the view-projection matrices of the six cubemap faces as `(view, projection)`,
+    /// in the order +X, -X, +Y, -Y, +Z, -Z.
+    pub fn face_matrices(&self) -> [(Matrix4<f32>, Matrix4<f32>); 6] {
+        let projection = perspective(Deg(90.0), 1.0, self.near, self.far);
+        let faces = [
+            (Vector3::unit_x(), -Vector3::unit_y()),
+            (-Vector3::unit_x(), -Vector3::unit_y()),
+            (Vector3::unit_y(), Vector3::unit_z()),
+            (-Vector3::unit_y(), -Vector3::unit_z()),
+            (Vector3::unit_z(), -Vector3::unit_y()),
+            (-Vector3::unit_z(), -Vector3::unit_y()),
+        ];
+        faces.map(|(direction, up)| {
+            let view = Matrix4::look_at_rh(self.position, self.position + direction, up);
+            (view, projection)
+        })
+    }
+
+    /// Captures the cubemap and prefilters its mip levels.
+    ///
+    /// The previous framebuffer binding and viewport are restored afterwards.
+    ///
+    /// # Arguments
+    ///
+    /// * `render_face` - Clears the bound framebuffer and renders the scene with the given
+    ///   view and projection matrices. Called once per face.
+    pub fn capture(&mut self, mut render_face: impl FnMut(Matrix4<f32>, Matrix4<f32>)) {
+        let mut previous_fbo = 0;
+        let mut previous_viewport = [0; 4];
+        unsafe {
+            gl::GetIntegerv(gl::DRAW_FRAMEBUFFER_BINDING, &mut previous_fbo);
+            gl::GetIntegerv(gl::VIEWPORT, previous_viewport.as_mut_ptr());
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+            gl::Viewport(0, 0, self.resolution as i32, self.resolution as i32);
+        }
+        for (face, (view, projection)) in self.face_matrices().into_iter().enumerate() {
+            unsafe {
+                gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+                gl::FramebufferTexture2D(
+                    gl::FRAMEBUFFER,
+                    gl::COLOR_ATTACHMENT0,
+                    gl::TEXTURE_CUBE_MAP_POSITIVE_X + face as u32,
+                    self.cubemap,
+                    0,
+                );
+            }
+            render_face(view, projection);
+        }
+        self.prefilter();
+        self.needs_update = false;
+        self.captured = true;
+
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, previous_fbo as GLuint);
+            gl::Viewport(
+                previous_viewport[0],
+                previous_viewport[1],
+                previous_viewport[2],
+                previous_viewport[3],
+            );
+        }
+    }
+
+    /// Renders each mip level above the base from the base level with increasing roughness.
+    fn prefilter(&self) {
+        let mut depth_test = 0;
+        unsafe {
+            gl::GetBooleanv(gl::DEPTH_TEST, &mut depth_test);
+            gl::Disable(gl::DEPTH_TEST);
+            gl::Enable(gl::TEXTURE_CUBE_MAP_SEAMLESS);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+            // Detach the depth buffer, which only matches the base level
+            gl::FramebufferRenderbuffer(gl::FRAMEBUFFER, gl::DEPTH_ATTACHMENT, gl::RENDERBUFFER, 0);
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, self.cubemap);
+            // Only sample the base level while rendering into the others
+            gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_MAX_LEVEL, 0);
+        }
+        self.prefilter_shader.bind();
+        self.prefilter_shader.try_set_uniform("source", 0);
+        self.vao.bind();
+        for level in 1..self.mip_levels {
+            let size = (self.resolution >> level).max(1) as GLsizei;
+            let roughness = level as f32 / (self.mip_levels - 1) as f32;
+            self.prefilter_shader
+                .try_set_uniform("roughness", roughness);
+            for face in 0..6 {
+                self.prefilter_shader.try_set_uniform("face", face);
+                unsafe {
+                    gl::FramebufferTexture2D(
+                        gl::FRAMEBUFFER,
+                        gl::COLOR_ATTACHMENT0,
+                        gl::TEXTURE_CUBE_MAP_POSITIVE_X + face as u32,
+                        self.cubemap,
+                        level as GLint,
+                    );
+                    gl::Viewport(0, 0, size, size);
+                    gl::DrawArrays(gl::TRIANGLES, 0, 3);
+                }
+                stats::record_draw(PrimitiveType::Triangles, 3, 1);
+            }
+        }
+        self.vao.unbind();
+        unsafe {
+            gl::TexParameteri(
+                gl::TEXTURE_CUBE_MAP,
+                gl::TEXTURE_MAX_LEVEL,
+                self.mip_levels as GLint - 1,
+            );
+            gl::FramebufferRenderbuffer(
+                gl::FRAMEBUFFER,
+                gl::DEPTH_ATTACHMENT,
+                gl::RENDERBUFFER,
+                self.depth_buffer,
+            );
+            if depth_test == gl::TRUE {
+                gl::Enable(gl::DEPTH_TEST);
+            }
+        }
+    }
+
+    /// Binds the cubemap and sets the uniforms declared by `REFLECTION_PROBE_GLSL`.
+    ///
+    /// The shader program must be bound before calling this function. Uniforms the shader
+    /// does not declare are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `shader_program` - The shader program sampling the probe.
+    /// * `unit` - The texture unit to bind the cubemap to.
+    pub fn apply(&self, shader_program: &ShaderProgram, unit: TextureUnit) {
+        unit.activate();
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, self.cubemap);
+        }
+        stats::record_texture_bind();
+        shader_program.try_set_uniform("hasReflectionProbe", 1);
+        shader_program.try_set_uniform("reflectionProbe", unit.index() as i32);
+        shader_program.try_set_uniform("reflectionProbePosition", self.position);
+        shader_program.try_set_uniform("reflectionProbeBoxMin", self.influence.min);
+        shader_program.try_set_uniform("reflectionProbeBoxMax", self.influence.max);
+        shader_program.try_set_uniform("reflectionProbeBoxProjection", self.box_projection as i32);
+        shader_program.try_set_uniform("reflectionProbeMaxLod", (self.mip_levels - 1) as f32);
+    }
+}
+
+impl Drop for ReflectionProbe {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteFramebuffers(1, &self.fbo);
+            gl::DeleteRenderbuffers(1, &self.depth_buffer);
+            gl::DeleteTextures(1, &self.cubemap);
+        }
+    }
+}
+
+/// Returns the probe an object at `point` uses: the smallest enabled probe whose influence
+/// box contains the point, or the enabled probe with the closest box otherwise. Probes that
+/// were never captured are skipped.
+pub fn select_reflection_probe(
+    probes: &[ReflectionProbe],
+    point: Point3<f32>,
+) -> Option<&ReflectionProbe> {
+    let volume = |probe: &&ReflectionProbe| {
+        let size = probe.influence.size();
+        size.x * size.y * size.z
+    };
+    let enabled = || {
+        probes
+            .iter()
+            .filter(|probe| probe.enabled && probe.captured)
+    };
+    enabled()
+        .filter(|probe| probe.influence.contains_point(point))
+        .min_by(|a, b| volume(a).total_cmp(&volume(b)))
+        .or_else(|| {
+            enabled().min_by(|a, b| {
+                let distance_a = a.influence.distance_to_point(point);
+                let distance_b = b.influence.distance_to_point(point);
+                distance_a.total_cmp(&distance_b)
+            })
+        })
+}
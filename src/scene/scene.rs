@@ -36,13 +36,36 @@
 //! // Add an object to the scene
 //! let vao = Vao::new().unwrap();
 //! let shader_program = ShaderProgram::new("vertex.glsl", "fragment.glsl").unwrap();
-//! let object = Object::new(vao, shader_program);
+//! let object = Object::new(vao, shader_program).with_tag("enemy");
 //! scene.add_object(object);
 //!
+//! // Find objects by tag
+//! for enemy in scene.objects_with_tag_mut("enemy") {
+//!     enemy.transform.set_position(Vector3::new(0.0, 1.0, 0.0));
+//! }
+//!
 //! // Render the scene
 //! scene.render();
 //!
-use super::{Camera, Light, Object};
+use super::static_batch::merge_static_objects;
+use super::{
+    apply_projectors, select_reflection_probe, Camera, Light, Lightmap, LightmapBaker, Object,
+    OcclusionCuller, PointShadowMap, PortalSystem, Prefab, PrefabPart, PrefabPlacement, Projector,
+    ReflectionProbe, SceneEnvironment, StaticBatch, Transform, REFLECTION_PROBE_UNIT,
+};
+use crate::custom_errors::Errors;
+use crate::graphics::alpha_mode::RenderQueue;
+use crate::graphics::gl_wrapper::ShaderProgram;
+use crate::graphics::renderer;
+use crate::graphics::standard_vertex::InstanceTransform;
+use crate::graphics::stats::FrameStats;
+use crate::graphics::texture_unit::TextureUnit;
+use crate::math::Plane;
+use cgmath::{EuclideanSpace, Matrix4, MetricSpace, Point3, SquareMatrix};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::rc::Rc;
 
 /// Represents a 3D scene containing a camera, lights, and objects.
 pub struct Scene {
@@ -50,8 +73,56 @@ pub struct Scene {
     camera: Camera,
     /// The lights in the scene.
     lights: Vec<Light>,
+    /// The projector lights in the scene.
+    projectors: Vec<Projector>,
     /// The objects in the scene.
     objects: Vec<Object>,
+    /// The reflection probes in the scene.
+    reflection_probes: Vec<ReflectionProbe>,
+    /// The ambient light, fog and background settings of the scene.
+    environment: SceneEnvironment,
+    /// The view-projection matrix of the previous render, used for motion vectors.
+    previous_view_projection: Option<Matrix4<f32>>,
+    /// The culler skipping occluded objects, or `None` to draw every object.
+    occlusion_culler: Option<OcclusionCuller>,
+    /// The buffers of the meshes merged by `bake_static`.
+    static_batches: Vec<StaticBatch>,
+    /// The cells and portals used to skip hidden rooms, or `None` to draw every object.
+    portal_system: Option<PortalSystem>,
+    /// The plane geometry is clipped against, or `None` to keep all geometry.
+    clip_plane: Option<Plane>,
+    /// The draw counts of the previous render.
+    last_frame_stats: FrameStats,
+    /// The prefabs that can be spawned by name.
+    prefabs: HashMap<String, Prefab>,
+    /// The prefabs spawned into the scene, in spawn order.
+    prefab_placements: Vec<PrefabPlacement>,
+    /// The object pools used by `spawn_pooled`, by name.
+    pools: HashMap<String, ObjectPool>,
+    /// The current index of every pooled object, by the id of its `PoolHandle`.
+    pooled_indices: HashMap<u64, usize>,
+    /// The id given to the next pooled object.
+    next_pool_id: u64,
+}
+
+/// The template and the inactive objects of an object pool.
+struct ObjectPool {
+    part: PrefabPart,
+    /// The indices of the despawned objects, ready to be spawned again.
+    free: Vec<usize>,
+}
+
+/// A handle to a spawn of a pooled object, returned by `Scene::spawn_pooled`.
+///
+/// The handle stays valid while the scene's objects are reordered, e.g. by `bake_static` or
+/// `remove_objects`. It becomes stale when the object is despawned, so a handle kept after
+/// `despawn` never refers to a later spawn of the same object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PoolHandle {
+    /// The id of the pooled object, unique within its scene.
+    id: u64,
+    /// The number of times the object was spawned before this spawn.
+    generation: u32,
 }
 
 impl Scene {
@@ -68,7 +139,21 @@ impl Scene {
         Self {
             camera,
             lights: Vec::new(),
+            projectors: Vec::new(),
             objects: Vec::new(),
+            reflection_probes: Vec::new(),
+            environment: SceneEnvironment::default(),
+            previous_view_projection: None,
+            occlusion_culler: None,
+            static_batches: Vec::new(),
+            portal_system: None,
+            clip_plane: None,
+            last_frame_stats: FrameStats::default(),
+            prefabs: HashMap::new(),
+            prefab_placements: Vec::new(),
+            pools: HashMap::new(),
+            pooled_indices: HashMap::new(),
+            next_pool_id: 0,
         }
     }
 
@@ -94,6 +179,74 @@ impl Scene {
         &mut self.lights
     }
 
+    /// Adds a projector light to the scene.
+    ///
+    /// # Arguments
+    ///
+    /// * `projector` - The projector to add to the scene.
+    pub fn add_projector(&mut self, projector: Projector) {
+        self.projectors.push(projector);
+    }
+
+    /// Returns a mutable reference to the vector of projector lights in the scene.
+    pub fn get_mut_projectors(&mut self) -> &mut Vec<Projector> {
+        &mut self.projectors
+    }
+
+    /// Adds a reflection probe to the scene. It is captured by the next
+    /// `capture_reflection_probes`.
+    ///
+    /// # Arguments
+    ///
+    /// * `probe` - The probe to add to the scene.
+    pub fn add_reflection_probe(&mut self, probe: ReflectionProbe) {
+        self.reflection_probes.push(probe);
+    }
+
+    /// Returns a mutable reference to the vector of reflection probes in the scene.
+    pub fn get_mut_reflection_probes(&mut self) -> &mut Vec<ReflectionProbe> {
+        &mut self.reflection_probes
+    }
+
+    /// Captures the reflection probes that were added or had `request_update` called since
+    /// their last capture.
+    ///
+    /// Each face is cleared with the environment's background color and all objects are
+    /// rendered with their own shader programs and the scene's environment and projectors,
+    /// but without reflection probes. Motion blur tracking is not affected.
+    ///
+    /// # Returns
+    ///
+    /// The number of captured probes.
+    pub fn capture_reflection_probes(&mut self) -> usize {
+        let mut captured = 0;
+        for probe in self
+            .reflection_probes
+            .iter_mut()
+            .filter(|probe| probe.needs_update())
+        {
+            probe.capture(|view_matrix, projection_matrix| {
+                self.environment.clear();
+                for object in &mut self.objects {
+                    let previous_model = object.previous_model();
+                    object.shader_program.bind();
+                    self.environment.apply(&object.shader_program);
+                    apply_projectors(&object.shader_program, &self.projectors);
+                    object
+                        .shader_program
+                        .try_set_uniform("previousViewProjection", projection_matrix * view_matrix);
+                    object
+                        .shader_program
+                        .try_set_uniform("hasReflectionProbe", 0);
+                    object.render(view_matrix, projection_matrix);
+                    object.set_previous_model(previous_model);
+                }
+            });
+            captured += 1;
+        }
+        captured
+    }
+
     /// Returns a mutable reference to the camera in the scene.
     ///
     /// # Returns
@@ -103,6 +256,34 @@ impl Scene {
         &mut self.camera
     }
 
+    /// Returns the environment settings of the scene.
+    pub fn environment(&self) -> &SceneEnvironment {
+        &self.environment
+    }
+
+    /// Returns a mutable reference to the environment settings of the scene.
+    pub fn get_mut_environment(&mut self) -> &mut SceneEnvironment {
+        &mut self.environment
+    }
+
+    /// Replaces the environment settings of the scene.
+    ///
+    /// # Arguments
+    ///
+    /// * `environment` - The new ambient light, fog and background settings.
+    pub fn set_environment(&mut self, environment: SceneEnvironment) {
+        self.environment = environment;
+    }
+
+    /// Clears the bound framebuffer with the camera's clear state, or without one, the color
+    /// and depth buffers with the environment's background color.
+    pub fn clear(&self) {
+        match &self.camera.clear {
+            Some(clear) => clear.apply(),
+            None => self.environment.clear(),
+        }
+    }
+
     /// Returns a mutable reference to the object at the specified index in the scene, or None if the index is out of bounds.
     ///
     /// # Arguments
@@ -116,6 +297,153 @@ impl Scene {
         self.objects.get_mut(index)
     }
 
+    /// Returns an iterator over all objects in the scene.
+    pub fn objects(&self) -> impl Iterator<Item = &Object> {
+        self.objects.iter()
+    }
+
+    /// Returns a mutable iterator over all objects in the scene.
+    pub fn objects_mut(&mut self) -> impl Iterator<Item = &mut Object> {
+        self.objects.iter_mut()
+    }
+
+    /// Returns an iterator over the objects that are rendered into shadow maps.
+    pub fn shadow_casters(&self) -> impl Iterator<Item = &Object> {
+        self.objects
+            .iter()
+            .filter(|object| object.active && object.cast_shadows)
+    }
+
+    /// Returns an iterator over the lights that cast shadows.
+    pub fn shadow_casting_lights(&self) -> impl Iterator<Item = &Light> {
+        self.lights.iter().filter(|light| light.casts_shadows)
+    }
+
+    /// Renders the shadow casting objects into a point light's shadow map.
+    ///
+    /// # Arguments
+    ///
+    /// * `shadow_map` - The shadow map to render into.
+    /// * `light_position` - The world-space position of the point light.
+    pub fn render_point_shadow(
+        &mut self,
+        shadow_map: &PointShadowMap,
+        light_position: Point3<f32>,
+    ) {
+        shadow_map.render(
+            light_position,
+            self.objects
+                .iter_mut()
+                .filter(|object| object.active && object.cast_shadows),
+        );
+    }
+
+    /// Bakes the scene's lights and ambient light into lightmaps for the objects with the
+    /// given tag, and assigns the lightmaps to the objects.
+    ///
+    /// # Arguments
+    ///
+    /// * `baker` - The baker rendering the lightmaps.
+    /// * `tag` - The tag marking static objects, e.g. `"static"`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the number of baked objects, or an error of type
+    /// `Errors::OpenGlError` if a lightmap cannot be rendered.
+    pub fn bake_lightmaps(&mut self, baker: &LightmapBaker, tag: &str) -> Result<usize, Errors> {
+        let ambient = self.environment.ambient();
+        let mut baked = 0;
+        for object in self.objects.iter_mut().filter(|object| object.has_tag(tag)) {
+            let texture = baker.bake(object, &self.lights, ambient)?;
+            object.lightmap = Some(Lightmap::new(Rc::new(texture)));
+            baked += 1;
+        }
+        Ok(baked)
+    }
+
+    /// Sets the cells and portals used to skip objects in rooms the camera cannot see into.
+    ///
+    /// # Arguments
+    ///
+    /// * `portal_system` - The portal system, or `None` to draw every object.
+    pub fn set_portal_system(&mut self, portal_system: Option<PortalSystem>) {
+        self.portal_system = portal_system;
+    }
+
+    /// Returns a mutable reference to the portal system, if any, e.g. to open or close doors.
+    pub fn get_mut_portal_system(&mut self) -> Option<&mut PortalSystem> {
+        self.portal_system.as_mut()
+    }
+
+    /// Sets the plane geometry is clipped against, e.g. the water surface while rendering a
+    /// planar reflection. Object shaders clip with `renderer::CLIP_PLANE_GLSL`.
+    ///
+    /// # Arguments
+    ///
+    /// * `clip_plane` - The clip plane in world space, or `None` to keep all geometry.
+    pub fn set_clip_plane(&mut self, clip_plane: Option<Plane>) {
+        self.clip_plane = clip_plane;
+    }
+
+    /// Returns the plane geometry is clipped against, if any.
+    pub fn clip_plane(&self) -> Option<Plane> {
+        self.clip_plane
+    }
+
+    /// Merges static objects that share a shader program and render state into combined
+    /// meshes, so they are drawn with one draw call per group.
+    ///
+    /// Objects with `is_static` set are merged if their meshes are indexed triangle lists
+    /// with the same vertex format. Their vertices are pre-transformed into world space, and
+    /// the merged object gets an identity transform, the union of their bounds and tags.
+    /// Objects with a lightmap, morph targets, toon shading, wind sway or a blended alpha
+    /// mode, and dynamic objects, are left untouched.
+    /// The order of the objects changes, so occlusion query results are forgotten.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the number of objects that were merged, or an error of type
+    /// `Errors::OpenGlError` if a merged mesh cannot be created. Objects that could not be
+    /// merged stay in the scene unchanged.
+    pub fn bake_static(&mut self) -> Result<usize, Errors> {
+        let merged = merge_static_objects(&mut self.objects, &mut self.static_batches);
+        self.rebuild_pools();
+        if let Some(occlusion_culler) = &mut self.occlusion_culler {
+            occlusion_culler.reset();
+        }
+        merged
+    }
+
+    /// Returns the meshes merged by `bake_static`.
+    pub fn static_batches(&self) -> &[StaticBatch] {
+        &self.static_batches
+    }
+
+    /// Returns an iterator over the objects with the given tag.
+    ///
+    /// # Arguments
+    ///
+    /// * `tag` - The tag to look for, e.g. `"enemy"`.
+    pub fn objects_with_tag<'a>(&'a self, tag: &'a str) -> impl Iterator<Item = &'a Object> + 'a {
+        self.objects
+            .iter()
+            .filter(move |object| object.has_tag(tag))
+    }
+
+    /// Returns a mutable iterator over the objects with the given tag.
+    ///
+    /// # Arguments
+    ///
+    /// * `tag` - The tag to look for, e.g. `"enemy"`.
+    pub fn objects_with_tag_mut<'a>(
+        &'a mut self,
+        tag: &'a str,
+    ) -> impl Iterator<Item = &'a mut Object> + 'a {
+        self.objects
+            .iter_mut()
+            .filter(move |object| object.has_tag(tag))
+    }
+
     /// Adds an object to the scene.
     ///
     /// # Arguments
@@ -129,24 +457,551 @@ impl Scene {
         self.objects.push(object);
     }
 
+    /// Removes the objects matching the predicate from the scene.
+    ///
+    /// The indices of the remaining objects change, so occlusion query results are
+    /// forgotten. Removed pooled objects are not recycled anymore.
+    ///
+    /// # Arguments
+    ///
+    /// * `predicate` - Returns `true` for the objects to remove.
+    ///
+    /// # Returns
+    ///
+    /// The number of removed objects.
+    pub fn remove_objects(&mut self, mut predicate: impl FnMut(&Object) -> bool) -> usize {
+        let count = self.objects.len();
+        self.objects.retain(|object| !predicate(object));
+        let removed = count - self.objects.len();
+        if removed > 0 {
+            self.rebuild_pools();
+            if let Some(occlusion_culler) = &mut self.occlusion_culler {
+                occlusion_culler.reset();
+            }
+        }
+        removed
+    }
+
+    /// Registers a prefab under the given name, replacing any prefab with the same name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name used by `spawn_prefab` and saved placements.
+    /// * `prefab` - The prefab to register.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::PrefabLoadError` if the name is empty or contains whitespace or
+    /// `=`, since such a name could not be read back by `load_prefab_placements`.
+    pub fn register_prefab(
+        &mut self,
+        name: impl Into<String>,
+        prefab: Prefab,
+    ) -> Result<(), Errors> {
+        let name = name.into();
+        if name.is_empty() || name.contains(|c: char| c.is_whitespace() || c == '=') {
+            return Err(Errors::PrefabLoadError(format!(
+                "invalid prefab name \"{}\": names must be non-empty and must not contain whitespace or '='",
+                name
+            )));
+        }
+        self.prefabs.insert(name, prefab);
+        Ok(())
+    }
+
+    /// Returns the prefab registered under the given name, if any.
+    pub fn prefab(&self, name: &str) -> Option<&Prefab> {
+        self.prefabs.get(name)
+    }
+
+    /// Adds the objects of a registered prefab to the scene and records the placement.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name the prefab was registered with.
+    /// * `transform` - The transform the parts of the prefab are placed relative to.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the indices of the new objects, or an error of type
+    /// `Errors::PrefabNotFoundError` if no prefab is registered under the name.
+    pub fn spawn_prefab(
+        &mut self,
+        name: &str,
+        transform: Transform,
+    ) -> Result<Range<usize>, Errors> {
+        let prefab = self
+            .prefabs
+            .get(name)
+            .ok_or_else(|| Errors::PrefabNotFoundError(name.to_string()))?;
+        let start = self.objects.len();
+        self.objects.extend(prefab.instantiate(&transform));
+        self.prefab_placements.push(PrefabPlacement {
+            name: name.to_string(),
+            transform,
+        });
+        Ok(start..self.objects.len())
+    }
+
+    /// Returns the prefabs spawned into the scene, in spawn order.
+    pub fn prefab_placements(&self) -> &[PrefabPlacement] {
+        &self.prefab_placements
+    }
+
+    /// Writes the prefab placements as text, one `PrefabPlacement` per line.
+    pub fn save_prefab_placements(&self) -> String {
+        self.prefab_placements
+            .iter()
+            .map(|placement| format!("{}\n", placement))
+            .collect()
+    }
+
+    /// Spawns the prefabs of placements written by `save_prefab_placements`.
+    ///
+    /// Empty lines and lines starting with `#` are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The saved placements.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the number of spawned prefabs, or an error of type
+    /// `Errors::PrefabLoadError` if a line is malformed or `Errors::PrefabNotFoundError` if a
+    /// prefab is not registered. The lines are validated before anything is spawned.
+    pub fn load_prefab_placements(&mut self, source: &str) -> Result<usize, Errors> {
+        let placements = source
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(PrefabPlacement::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        if let Some(missing) = placements
+            .iter()
+            .find(|placement| !self.prefabs.contains_key(&placement.name))
+        {
+            return Err(Errors::PrefabNotFoundError(missing.name.clone()));
+        }
+        let count = placements.len();
+        for placement in placements {
+            self.spawn_prefab(&placement.name, placement.transform)?;
+        }
+        Ok(count)
+    }
+
+    /// Creates an object pool recycling objects of the given template.
+    ///
+    /// `capacity` inactive objects are created up front, so the first spawns do not allocate.
+    /// The pool grows when more objects are spawned at once. Registering a pool with the name
+    /// of an existing pool replaces its template; objects created from the old template are
+    /// reused until they are removed with the rest of the scene.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name passed to `spawn_pooled`, e.g. `"bullet"`.
+    /// * `part` - The template of the pooled objects. Its transform is the initial transform
+    ///   of every spawned object.
+    /// * `capacity` - The number of objects to create up front.
+    pub fn register_pool(&mut self, name: impl Into<String>, part: PrefabPart, capacity: usize) {
+        let name = name.into();
+        let mut free = self
+            .pools
+            .remove(&name)
+            .map(|pool| pool.free)
+            .unwrap_or_default();
+        free.reserve(capacity);
+        for _ in 0..capacity {
+            let mut object = part.instantiate(&Transform::new());
+            object.active = false;
+            free.push(self.objects.len());
+            self.push_pooled(object, &name);
+        }
+        self.pools.insert(name, ObjectPool { part, free });
+    }
+
+    /// Activates an object of the given pool, reusing a despawned object if one is available.
+    ///
+    /// The object's transform is reset to the template's and its motion is forgotten; its
+    /// mesh, shader program and GPU resources are kept from the previous spawn.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name the pool was registered with.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the handle of the spawned object, or an error of type
+    /// `Errors::PoolNotFoundError` if no pool is registered under the name.
+    pub fn spawn_pooled(&mut self, name: &str) -> Result<PoolHandle, Errors> {
+        let pool = self
+            .pools
+            .get_mut(name)
+            .ok_or_else(|| Errors::PoolNotFoundError(name.to_string()))?;
+        let index = match pool.free.pop() {
+            Some(index) => {
+                let object = &mut self.objects[index];
+                object.transform = pool.part.transform.clone();
+                object.reset_motion();
+                object.active = true;
+                index
+            }
+            None => {
+                let object = pool.part.instantiate(&Transform::new());
+                self.push_pooled(object, name);
+                self.objects.len() - 1
+            }
+        };
+        let object = &mut self.objects[index];
+        let handle = object
+            .pool_handle()
+            .expect("pooled objects always have a handle");
+        let handle = PoolHandle {
+            generation: handle.generation.wrapping_add(1),
+            ..handle
+        };
+        object.set_pool_handle(Some(handle));
+        Ok(handle)
+    }
+
+    /// Deactivates a pooled object and returns it to its pool.
+    ///
+    /// # Arguments
+    ///
+    /// * `handle` - The handle returned by `spawn_pooled`.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the object was despawned, or `false` if the handle is stale, i.e. the object
+    /// was already despawned or removed from the scene.
+    pub fn despawn(&mut self, handle: PoolHandle) -> bool {
+        let Some(index) = self.pooled_index(handle) else {
+            return false;
+        };
+        let object = &mut self.objects[index];
+        let Some(pool) = object.pool().and_then(|name| self.pools.get_mut(name)) else {
+            return false;
+        };
+        object.active = false;
+        pool.free.push(index);
+        true
+    }
+
+    /// Returns the spawned object of the given handle, or `None` if the handle is stale.
+    pub fn pooled_object(&self, handle: PoolHandle) -> Option<&Object> {
+        self.pooled_index(handle).map(|index| &self.objects[index])
+    }
+
+    /// Returns the spawned object of the given handle mutably, or `None` if the handle is
+    /// stale.
+    pub fn pooled_object_mut(&mut self, handle: PoolHandle) -> Option<&mut Object> {
+        self.pooled_index(handle)
+            .map(|index| &mut self.objects[index])
+    }
+
+    /// Returns the number of active and inactive objects of the given pool, or `None` if no
+    /// pool is registered under the name.
+    pub fn pool_size(&self, name: &str) -> Option<(usize, usize)> {
+        self.pools.get(name)?;
+        let (active, inactive) = self
+            .objects
+            .iter()
+            .filter(|object| object.pool() == Some(name))
+            .fold((0, 0), |(active, inactive), object| {
+                if object.active {
+                    (active + 1, inactive)
+                } else {
+                    (active, inactive + 1)
+                }
+            });
+        Some((active, inactive))
+    }
+
+    /// Adds a new object to the given pool, with a fresh handle id.
+    fn push_pooled(&mut self, mut object: Object, name: &str) {
+        let id = self.next_pool_id;
+        self.next_pool_id += 1;
+        object.set_pool(Some(name.to_string()));
+        object.set_pool_handle(Some(PoolHandle { id, generation: 0 }));
+        self.pooled_indices.insert(id, self.objects.len());
+        self.objects.push(object);
+    }
+
+    /// Returns the current index of the spawned object of the given handle, or `None` if the
+    /// handle is stale.
+    fn pooled_index(&self, handle: PoolHandle) -> Option<usize> {
+        let index = *self.pooled_indices.get(&handle.id)?;
+        let object = &self.objects[index];
+        (object.active && object.pool_handle() == Some(handle)).then_some(index)
+    }
+
+    /// Collects the inactive objects of every pool and the indices of all pooled objects
+    /// again after the objects were reordered.
+    fn rebuild_pools(&mut self) {
+        for pool in self.pools.values_mut() {
+            pool.free.clear();
+        }
+        self.pooled_indices.clear();
+        for (index, object) in self.objects.iter().enumerate() {
+            if let Some(handle) = object.pool_handle() {
+                self.pooled_indices.insert(handle.id, index);
+            }
+            if object.active {
+                continue;
+            }
+            if let Some(pool) = object.pool().and_then(|name| self.pools.get_mut(name)) {
+                pool.free.push(index);
+            }
+        }
+    }
+
+    /// Forgets the camera and object matrices of the previous render, so the next frame has
+    /// no motion blur. Occlusion query results are forgotten as well, so every object is
+    /// drawn in the next frame.
+    ///
+    /// Call this after a camera cut or after loading a level.
+    pub fn reset_motion(&mut self) {
+        self.previous_view_projection = None;
+        for object in &mut self.objects {
+            object.reset_motion();
+        }
+        if let Some(occlusion_culler) = &mut self.occlusion_culler {
+            occlusion_culler.reset();
+        }
+    }
+
+    /// Sets the culler used to skip objects hidden behind other geometry.
+    ///
+    /// Only objects with `bounds` and `occlusion_culling` enabled are tested. The culler
+    /// tracks objects by their index, so replace it after rebuilding the object list.
+    ///
+    /// # Arguments
+    ///
+    /// * `occlusion_culler` - The culler, or `None` to draw every object.
+    pub fn set_occlusion_culler(&mut self, occlusion_culler: Option<OcclusionCuller>) {
+        self.occlusion_culler = occlusion_culler;
+    }
+
+    /// Returns a mutable reference to the occlusion culler, if any.
+    pub fn get_mut_occlusion_culler(&mut self) -> Option<&mut OcclusionCuller> {
+        self.occlusion_culler.as_mut()
+    }
+
     /// Renders all objects in the scene using the current camera's view and projection matrices.
     ///
     /// # Description
     ///
     /// This function iterates over all objects in the scene and calls their `render` method with the
     /// current view and projection matrices for the camera. This allows each object to render itself
-    /// using its own mesh and shader program.
+    /// using its own mesh and shader program. The scene's environment settings, projector
+    /// lights and the reflection probe selected for the object's position are uploaded to each
+    /// object's shader program first, along with the camera's
+    /// view-projection matrix of the previous render as "previousViewProjection", which the
+    /// motion blur velocity shaders use.
+    ///
+    /// With a clip plane, `GL_CLIP_DISTANCE0` is enabled while the objects are drawn and the
+    /// plane is uploaded as "clipPlaneNormal" and "clipPlaneDistance".
+    ///
+    /// Objects are drawn by the queue of their alpha mode: opaque objects first, then cutout
+    /// objects, both in the order they were added, and then blended objects from back to
+    /// front.
+    ///
+    /// Inactive objects are skipped. With a portal system, objects outside of the cells visible from the camera are
+    /// skipped. With an occlusion culler, objects whose bounds were occluded in previous frames are
+    /// skipped, and the bounds of all candidates are tested against the depth buffer after
+    /// the objects were drawn.
+    ///
+    /// Objects with `instanced` set that share their mesh, shader program and material
+    /// settings are drawn together with a single instanced draw call, at the position of the
+    /// first of them. Their model matrices are uploaded as `InstanceTransform`s, so the shader
+    /// must use `INSTANCING_GLSL`. Environment, projector and reflection probe uniforms are
+    /// those of the first object, and the instances get no per-object motion vectors.
+    ///
+    /// The draw counts of the call are available from `last_frame_stats` afterwards.
     ///
     /// # Note
     ///
     /// This function does not clear the OpenGL context or swap the front and back buffers; it is
-    /// expected that the caller will handle these tasks.
+    /// expected that the caller will handle these tasks, e.g. with `Scene::clear`.
     pub fn render(&mut self) {
+        let start_stats = FrameStats::counters();
         let view_matrix = self.camera.view_matrix();
         let projection_matrix = self.camera.projection_matrix();
+        let view_projection = projection_matrix * view_matrix;
+        let previous_view_projection = self.previous_view_projection.unwrap_or(view_projection);
 
-        for object in &mut self.objects {
-            object.render(view_matrix, projection_matrix);
+        if let Some(occlusion_culler) = &mut self.occlusion_culler {
+            occlusion_culler.update_results();
+        }
+        let portal_visibility = self
+            .portal_system
+            .as_ref()
+            .map(|portal_system| portal_system.visibility(view_projection, self.camera.position));
+
+        let camera_position = self.camera.position;
+        let mut draw_order: Vec<(usize, (RenderQueue, bool, f32))> = self
+            .objects
+            .iter_mut()
+            .enumerate()
+            .map(|(index, object)| {
+                let queue = object.alpha_mode.render_queue();
+                let distance = match queue {
+                    RenderQueue::Opaque => 0.0,
+                    RenderQueue::Transparent => {
+                        -Point3::from_vec(object.transform.matrix().w.truncate())
+                            .distance2(camera_position)
+                    }
+                };
+                (index, (queue, object.alpha_mode.is_cutout(), distance))
+            })
+            .collect();
+        draw_order.sort_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+
+        let objects = &mut self.objects;
+        let occlusion_culler = &self.occlusion_culler;
+        draw_order.retain(|&(index, _)| {
+            let object = &mut objects[index];
+            if !object.active {
+                return false;
+            }
+            if portal_visibility
+                .as_ref()
+                .is_some_and(|visibility| !visibility.is_object_visible(object))
+            {
+                return false;
+            }
+            let occluded = object.occlusion_culling
+                && object.bounds.is_some()
+                && occlusion_culler
+                    .as_ref()
+                    .is_some_and(|occlusion_culler| !occlusion_culler.is_visible(index));
+            !occluded
+        });
+
+        // Group the visible objects that can share an instanced draw call
+        let mut batches: Vec<Vec<usize>> = Vec::new();
+        for &(index, _) in &draw_order {
+            let object = &self.objects[index];
+            if !object.instanced {
+                continue;
+            }
+            match batches
+                .iter_mut()
+                .find(|batch| self.objects[batch[0]].can_instance_with(object))
+            {
+                Some(batch) => batch.push(index),
+                None => batches.push(vec![index]),
+            }
+        }
+        batches.retain(|batch| batch.len() > 1);
+        let mut batched = vec![false; self.objects.len()];
+        for &index in batches.iter().flat_map(|batch| &batch[1..]) {
+            batched[index] = true;
+        }
+
+        if self.clip_plane.is_some() {
+            renderer::set_clip_distance_enabled(true);
         }
+        for (index, _) in draw_order {
+            if batched[index] {
+                continue;
+            }
+            let object = &mut self.objects[index];
+            let shader_program = object.shader_program.clone();
+            let position = Point3::from_vec(object.transform.matrix().w.truncate());
+            shader_program.bind();
+            self.apply_object_uniforms(&shader_program, position, previous_view_projection);
+            match batches.iter().find(|batch| batch[0] == index) {
+                Some(batch) => self.render_batch(batch, view_matrix, projection_matrix),
+                None => self.objects[index].render(view_matrix, projection_matrix),
+            }
+        }
+        if self.clip_plane.is_some() {
+            renderer::set_clip_distance_enabled(false);
+        }
+
+        if let Some(occlusion_culler) = &mut self.occlusion_culler {
+            let candidates = self
+                .objects
+                .iter_mut()
+                .enumerate()
+                .filter(|(_, object)| object.active && object.occlusion_culling)
+                .filter_map(|(index, object)| {
+                    let bounds = object.world_bounds()?;
+                    portal_visibility
+                        .as_ref()
+                        .is_none_or(|visibility| visibility.is_aabb_visible(&bounds))
+                        .then_some((index, bounds))
+                });
+            occlusion_culler.issue_queries(view_projection, candidates);
+        }
+        self.previous_view_projection = Some(view_projection);
+        self.last_frame_stats = FrameStats::counters().since(&start_stats);
+    }
+
+    /// Applies the environment, projector, reflection probe and clip plane uniforms of the
+    /// scene for an object at the given position. The shader program must be bound.
+    fn apply_object_uniforms(
+        &self,
+        shader_program: &ShaderProgram,
+        position: Point3<f32>,
+        previous_view_projection: Matrix4<f32>,
+    ) {
+        self.environment.apply(shader_program);
+        apply_projectors(shader_program, &self.projectors);
+        match select_reflection_probe(&self.reflection_probes, position) {
+            Some(probe) => probe.apply(shader_program, TextureUnit::new(REFLECTION_PROBE_UNIT)),
+            None => {
+                shader_program.try_set_uniform("hasReflectionProbe", 0);
+            }
+        }
+        shader_program.try_set_uniform("previousViewProjection", previous_view_projection);
+        renderer::apply_clip_plane_uniforms(shader_program, self.clip_plane.as_ref());
+    }
+
+    /// Draws the objects at the given indices with a single instanced draw call, using the
+    /// state and uniforms of the first one and the model matrices of all of them.
+    fn render_batch(
+        &mut self,
+        batch: &[usize],
+        view_matrix: Matrix4<f32>,
+        projection_matrix: Matrix4<f32>,
+    ) {
+        let instances: Vec<InstanceTransform> = batch
+            .iter()
+            .map(|&index| {
+                let object = &mut self.objects[index];
+                let model = object.transform.matrix();
+                object.set_previous_model(Some(model));
+                InstanceTransform::from(model)
+            })
+            .collect();
+        let first = &self.objects[batch[0]];
+        match first.mesh().set_instances(&instances) {
+            Ok(()) => {
+                let identity = Matrix4::identity();
+                first.render_instances(
+                    view_matrix,
+                    projection_matrix,
+                    identity,
+                    identity,
+                    Some(instances.len() as u32),
+                );
+            }
+            Err(_) => {
+                // Without an instance buffer, fall back to one draw call per object
+                for &index in batch {
+                    self.objects[index].render(view_matrix, projection_matrix);
+                }
+            }
+        }
+    }
+
+    /// Returns the draw calls, instances, triangles, texture binds and shader switches of the
+    /// previous `render`, including the occlusion queries it issued.
+    ///
+    /// Draws outside of `render`, such as shadow passes or UI, are not included; measure
+    /// them with `FrameStats::counters`.
+    pub fn last_frame_stats(&self) -> FrameStats {
+        self.last_frame_stats
     }
 }
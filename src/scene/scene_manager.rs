@@ -0,0 +1,517 @@
+//! # Scene Manager Module
+//!
+//! This module provides a `SceneManager` that owns several named `Scene`s and switches
+//! between them, e.g. from the main menu to a level. Switches can cut immediately, fade
+//! through a color or crossfade between the two scenes.
+//!
+//! Callbacks registered with `on_enter` and `on_exit` run when a scene becomes active or
+//! stops being active, so levels can start their music, reset their objects or release
+//! resources. During a fade, the old scene is exited and the new one entered when the screen
+//! is fully covered; during a crossfade, both happen when the switch starts and the old scene
+//! keeps being rendered until the transition ends.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::scene::{Scene, SceneManager, Transition};
+//!
+//! fn setup(menu: Scene, level: Scene) -> Result<SceneManager, glwfr::custom_errors::Errors> {
+//!     let mut scenes = SceneManager::new(800, 600)?;
+//!     scenes.add_scene("menu", menu);
+//!     scenes.add_scene("level1", level);
+//!     scenes.on_enter("level1", |scene| {
+//!         scene.get_mut_camera().position = glwfr::cgmath::Point3::new(0.0, 2.0, 5.0);
+//!     });
+//!     scenes.switch_to("menu", Transition::Cut)?;
+//!     Ok(scenes)
+//! }
+//!
+//! fn frame(scenes: &mut SceneManager, start_pressed: bool, delta_time: f32) -> Result<(), glwfr::custom_errors::Errors> {
+//!     if start_pressed {
+//!         scenes.switch_to("level1", Transition::Fade { color: [0.0, 0.0, 0.0], duration: 1.0 })?;
+//!     }
+//!     scenes.update(delta_time);
+//!     scenes.render();
+//!     Ok(())
+//! }
+//! ```
+
+use crate::custom_errors::Errors;
+use crate::graphics::gl_wrapper::{Framebuffer, PrimitiveType, Renderbuffer, ShaderProgram, Vao};
+use crate::graphics::render_state::{BlendState, RenderState};
+use crate::graphics::stats;
+use crate::graphics::texture::{Texture, TextureFormat, WrapMode};
+use crate::scene::Scene;
+use cgmath::Vector3;
+use gl::types::*;
+use std::collections::HashMap;
+
+const TRANSITION_VERTEX_SHADER: &str = r#"#version 330 core
+out vec2 uv;
+
+void main() {
+    // A single triangle covering the screen
+    vec2 position = vec2((gl_VertexID << 1) & 2, gl_VertexID & 2);
+    uv = position;
+    gl_Position = vec4(position * 2.0 - 1.0, 0.0, 1.0);
+}
+"#;
+
+const TRANSITION_FRAGMENT_SHADER: &str = r#"#version 330 core
+in vec2 uv;
+out vec4 fragColor;
+
+uniform sampler2D fromTexture;
+uniform sampler2D toTexture;
+// 0: a flat overlay color, 1: a blend of the two textures
+uniform int mode;
+uniform vec3 overlayColor;
+uniform float overlayAlpha;
+uniform float factor;
+
+void main() {
+    if (mode == 0) {
+        fragColor = vec4(overlayColor, overlayAlpha);
+    } else {
+        fragColor = vec4(mix(texture(fromTexture, uv).rgb, texture(toTexture, uv).rgb, factor), 1.0);
+    }
+}
+"#;
+
+/// How `SceneManager::switch_to` changes from one scene to the next.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Transition {
+    /// Switch immediately.
+    Cut,
+    /// Fade the old scene out to a color, then fade the new scene in from it.
+    Fade {
+        /// The RGB color the screen is covered with halfway through.
+        color: [f32; 3],
+        /// The duration of the whole transition in seconds.
+        duration: f32,
+    },
+    /// Blend from the old scene to the new one, rendering both while the transition runs.
+    Crossfade {
+        /// The duration of the transition in seconds.
+        duration: f32,
+    },
+}
+
+/// Callbacks run when a scene is entered or exited, keyed by scene name.
+type SceneHooks = HashMap<String, Vec<Box<dyn FnMut(&mut Scene)>>>;
+
+/// A transition in progress.
+struct ActiveTransition {
+    transition: Transition,
+    /// The scene shown before the transition. Rendered until a fade's midpoint or until a
+    /// crossfade ends.
+    from: Option<String>,
+    /// The scene shown after the transition.
+    to: String,
+    elapsed: f32,
+}
+
+/// A render target for one side of a crossfade.
+struct TransitionTarget {
+    framebuffer: Framebuffer,
+    color: Texture,
+    depth: Renderbuffer,
+}
+
+impl TransitionTarget {
+    fn new() -> Result<Self, Errors> {
+        let color = Texture::new();
+        color.set_generate_mipmaps(false);
+        color.set_parameteri(gl::TEXTURE_MIN_FILTER, gl::LINEAR as GLint);
+        color.set_parameteri(gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
+        color.set_wrap(WrapMode::ClampToEdge);
+        let target = Self {
+            framebuffer: Framebuffer::new()?,
+            color,
+            depth: Renderbuffer::new(TextureFormat::Depth24Stencil8, 1, 1)?,
+        };
+        target.framebuffer.set_debug_label("scene_transition");
+        Ok(target)
+    }
+
+    fn resize(&mut self, width: u32, height: u32) -> Result<(), Errors> {
+        self.color.allocate(width, height, TextureFormat::Rgba8);
+        self.depth.resize(width, height);
+        self.framebuffer
+            .attach_texture(gl::COLOR_ATTACHMENT0, &self.color);
+        self.framebuffer
+            .attach_renderbuffer(self.depth.default_attachment(), &self.depth);
+        let status = self.framebuffer.check_status();
+        self.framebuffer.unbind();
+        status
+    }
+}
+
+/// Owns named scenes and renders the active one, with transitions between them.
+pub struct SceneManager {
+    scenes: HashMap<String, Scene>,
+    active: Option<String>,
+    transition: Option<ActiveTransition>,
+    enter_hooks: SceneHooks,
+    exit_hooks: SceneHooks,
+    shader_program: ShaderProgram,
+    vao: Vao,
+    targets: [TransitionTarget; 2],
+    width: u32,
+    height: u32,
+}
+
+impl SceneManager {
+    /// Creates a scene manager without scenes, rendering to a window of the given size.
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - The width of the window framebuffer in pixels.
+    /// * `height` - The height of the window framebuffer in pixels.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::OpenGlError` if the crossfade targets cannot be created or are
+    /// incomplete, or an `Errors::ShaderCompilationError` if the transition shader fails to
+    /// compile.
+    pub fn new(width: u32, height: u32) -> Result<Self, Errors> {
+        let mut scene_manager = Self {
+            scenes: HashMap::new(),
+            active: None,
+            transition: None,
+            enter_hooks: HashMap::new(),
+            exit_hooks: HashMap::new(),
+            shader_program: ShaderProgram::from_source(
+                TRANSITION_VERTEX_SHADER,
+                TRANSITION_FRAGMENT_SHADER,
+            )?,
+            vao: Vao::new()?,
+            targets: [TransitionTarget::new()?, TransitionTarget::new()?],
+            width: 0,
+            height: 0,
+        };
+        scene_manager.resize(width, height)?;
+        Ok(scene_manager)
+    }
+
+    /// Updates the size of the window, for example after it was resized.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::OpenGlError` if the resized crossfade targets are incomplete.
+    pub fn resize(&mut self, width: u32, height: u32) -> Result<(), Errors> {
+        self.width = width.max(1);
+        self.height = height.max(1);
+        for target in &mut self.targets {
+            target.resize(self.width, self.height)?;
+        }
+        Ok(())
+    }
+
+    /// Adds a scene under the given name, replacing and returning a scene with the same name.
+    ///
+    /// The scene does not become active until it is switched to.
+    pub fn add_scene(&mut self, name: impl Into<String>, scene: Scene) -> Option<Scene> {
+        self.scenes.insert(name.into(), scene)
+    }
+
+    /// Removes a scene and returns it.
+    ///
+    /// Removing the active scene runs its exit callbacks and leaves no scene active. A
+    /// transition from or to the scene ends immediately.
+    pub fn remove_scene(&mut self, name: &str) -> Option<Scene> {
+        if self.transition.as_ref().is_some_and(|transition| {
+            transition.to == name || transition.from.as_deref() == Some(name)
+        }) {
+            self.finish_transition();
+        }
+        if self.active.as_deref() == Some(name) {
+            self.run_hooks(name, false);
+            self.active = None;
+        }
+        self.scenes.remove(name)
+    }
+
+    /// Returns the scene with the given name.
+    pub fn scene(&self, name: &str) -> Option<&Scene> {
+        self.scenes.get(name)
+    }
+
+    /// Returns a mutable reference to the scene with the given name.
+    pub fn scene_mut(&mut self, name: &str) -> Option<&mut Scene> {
+        self.scenes.get_mut(name)
+    }
+
+    /// Returns the names of all scenes, in no particular order.
+    pub fn scene_names(&self) -> impl Iterator<Item = &str> {
+        self.scenes.keys().map(String::as_str)
+    }
+
+    /// Returns the name of the active scene, if any.
+    ///
+    /// During a fade this changes at the midpoint, during a crossfade when it starts.
+    pub fn active_name(&self) -> Option<&str> {
+        self.active.as_deref()
+    }
+
+    /// Returns the active scene, if any.
+    pub fn active_scene(&self) -> Option<&Scene> {
+        self.scenes.get(self.active.as_deref()?)
+    }
+
+    /// Returns a mutable reference to the active scene, if any.
+    pub fn active_scene_mut(&mut self) -> Option<&mut Scene> {
+        self.scenes.get_mut(self.active.as_deref()?)
+    }
+
+    /// Registers a callback invoked with the scene whenever the scene becomes active.
+    pub fn on_enter(
+        &mut self,
+        name: impl Into<String>,
+        callback: impl FnMut(&mut Scene) + 'static,
+    ) {
+        self.enter_hooks
+            .entry(name.into())
+            .or_default()
+            .push(Box::new(callback));
+    }
+
+    /// Registers a callback invoked with the scene whenever the scene stops being active.
+    pub fn on_exit(&mut self, name: impl Into<String>, callback: impl FnMut(&mut Scene) + 'static) {
+        self.exit_hooks
+            .entry(name.into())
+            .or_default()
+            .push(Box::new(callback));
+    }
+
+    /// Switches to the scene with the given name.
+    ///
+    /// A transition still in progress is finished immediately first. Switching to the
+    /// active scene does nothing. Without an active scene, fades only fade in and
+    /// crossfades cut.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::SceneNotFoundError` if no scene has the given name.
+    pub fn switch_to(&mut self, name: &str, transition: Transition) -> Result<(), Errors> {
+        if !self.scenes.contains_key(name) {
+            return Err(Errors::SceneNotFoundError(name.to_string()));
+        }
+        self.finish_transition();
+        if self.active.as_deref() == Some(name) {
+            return Ok(());
+        }
+        let from = self.active.clone();
+        match transition {
+            Transition::Fade { duration, .. } if duration > 0.0 => {
+                let mut active = ActiveTransition {
+                    transition,
+                    from,
+                    to: name.to_string(),
+                    elapsed: 0.0,
+                };
+                if active.from.is_none() {
+                    // Nothing to fade out, start fully covered
+                    active.elapsed = duration * 0.5;
+                    self.activate(name);
+                }
+                self.transition = Some(active);
+            }
+            Transition::Crossfade { duration } if duration > 0.0 && from.is_some() => {
+                self.activate(name);
+                self.transition = Some(ActiveTransition {
+                    transition,
+                    from,
+                    to: name.to_string(),
+                    elapsed: 0.0,
+                });
+            }
+            _ => self.activate(name),
+        }
+        Ok(())
+    }
+
+    /// Checks if a transition is in progress.
+    pub fn is_transitioning(&self) -> bool {
+        self.transition.is_some()
+    }
+
+    /// Advances the transition in progress. Call this once per frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `delta_time` - The time since the previous update in seconds.
+    pub fn update(&mut self, delta_time: f32) {
+        let Some(transition) = &mut self.transition else {
+            return;
+        };
+        let previous = transition.elapsed;
+        transition.elapsed += delta_time.max(0.0);
+        let elapsed = transition.elapsed;
+        let (duration, midpoint) = match transition.transition {
+            Transition::Fade { duration, .. } => (duration, Some(duration * 0.5)),
+            Transition::Crossfade { duration } => (duration, None),
+            Transition::Cut => (0.0, None),
+        };
+        if midpoint.is_some_and(|midpoint| previous < midpoint && elapsed >= midpoint) {
+            let to = transition.to.clone();
+            self.activate(&to);
+        }
+        if elapsed >= duration {
+            self.transition = None;
+        }
+    }
+
+    /// Clears the window and renders the active scene, including the transition in progress.
+    ///
+    /// Every scene is cleared with its own `Scene::clear` settings before it is rendered.
+    /// During a crossfade both scenes are rendered into off-screen targets and blended onto
+    /// the window. Depth testing is disabled while the transition is drawn; afterwards the
+    /// default `RenderState` is applied.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function is a wrapper around `glViewport`, `glBindFramebuffer` and
+    /// `glDrawArrays` with a screen-covering triangle.
+    pub fn render(&mut self) {
+        let (crossfade, overlay) = match &self.transition {
+            Some(ActiveTransition {
+                transition: Transition::Crossfade { duration },
+                from: Some(from),
+                elapsed,
+                ..
+            }) => (
+                Some((from.clone(), (elapsed / duration).clamp(0.0, 1.0))),
+                None,
+            ),
+            Some(ActiveTransition {
+                transition: Transition::Fade { color, duration },
+                elapsed,
+                ..
+            }) => {
+                // Rises to fully covered at the midpoint, then falls again
+                let progress = (elapsed / duration).clamp(0.0, 1.0);
+                let alpha = 1.0 - (progress * 2.0 - 1.0).abs();
+                (None, Some([color[0], color[1], color[2], alpha]))
+            }
+            _ => (None, None),
+        };
+
+        if let Some((from, factor)) = crossfade {
+            let to = self.active.clone();
+            for (target, name) in self.targets.iter().zip([Some(from), to]) {
+                target.framebuffer.bind();
+                self.set_viewport();
+                match name.and_then(|name| self.scenes.get_mut(&name)) {
+                    Some(scene) => {
+                        scene.clear();
+                        scene.render();
+                    }
+                    None => Self::clear_black(),
+                }
+            }
+            self.targets[0].framebuffer.unbind();
+            self.set_viewport();
+            self.draw_crossfade(factor);
+            return;
+        }
+
+        self.set_viewport();
+        match self.active_scene_mut() {
+            Some(scene) => {
+                scene.clear();
+                scene.render();
+            }
+            None => Self::clear_black(),
+        }
+        if let Some(color) = overlay {
+            self.draw_overlay(color);
+        }
+    }
+
+    /// Switches to the target of the transition in progress and ends it.
+    fn finish_transition(&mut self) {
+        if let Some(transition) = self.transition.take() {
+            if self.active.as_deref() != Some(transition.to.as_str()) {
+                self.activate(&transition.to);
+            }
+        }
+    }
+
+    /// Exits the active scene and enters the given one.
+    fn activate(&mut self, name: &str) {
+        if let Some(previous) = self.active.take() {
+            self.run_hooks(&previous, false);
+        }
+        self.active = Some(name.to_string());
+        self.run_hooks(name, true);
+    }
+
+    /// Runs the enter or exit callbacks of a scene.
+    fn run_hooks(&mut self, name: &str, enter: bool) {
+        let hooks = if enter {
+            &mut self.enter_hooks
+        } else {
+            &mut self.exit_hooks
+        };
+        let (Some(callbacks), Some(scene)) = (hooks.get_mut(name), self.scenes.get_mut(name))
+        else {
+            return;
+        };
+        for callback in callbacks {
+            callback(scene);
+        }
+    }
+
+    fn set_viewport(&self) {
+        unsafe {
+            gl::Viewport(0, 0, self.width as GLsizei, self.height as GLsizei);
+        }
+    }
+
+    fn clear_black() {
+        unsafe {
+            gl::ClearColor(0.0, 0.0, 0.0, 1.0);
+            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+        }
+    }
+
+    /// Draws a translucent color over the whole window.
+    fn draw_overlay(&self, color: [f32; 4]) {
+        RenderState {
+            blend: Some(BlendState::ALPHA),
+            ..RenderState::disabled()
+        }
+        .apply();
+        self.shader_program.bind();
+        self.shader_program.try_set_uniform("mode", 0);
+        self.shader_program
+            .try_set_uniform("overlayColor", Vector3::new(color[0], color[1], color[2]));
+        self.shader_program
+            .try_set_uniform("overlayAlpha", color[3]);
+        self.draw_fullscreen();
+    }
+
+    /// Draws the blend of the two crossfade targets over the whole window.
+    fn draw_crossfade(&self, factor: f32) {
+        RenderState::disabled().apply();
+        self.shader_program.bind();
+        self.targets[0].color.bind(gl::TEXTURE0);
+        self.targets[1].color.bind(gl::TEXTURE1);
+        self.shader_program.try_set_uniform("fromTexture", 0);
+        self.shader_program.try_set_uniform("toTexture", 1);
+        self.shader_program.try_set_uniform("mode", 1);
+        self.shader_program.try_set_uniform("factor", factor);
+        self.draw_fullscreen();
+    }
+
+    fn draw_fullscreen(&self) {
+        self.vao.bind();
+        unsafe {
+            gl::DrawArrays(gl::TRIANGLES, 0, 3);
+        }
+        stats::record_draw(PrimitiveType::Triangles, 3, 1);
+        self.vao.unbind();
+        RenderState::default().apply();
+    }
+}
@@ -0,0 +1,336 @@
+//! # Shadow Module
+//!
+//! This module provides omnidirectional shadow maps for point lights. The distance from the
+//! light to the closest surface is rendered into the six faces of a depth cubemap, which lit
+//! shaders sample with a distance comparison.
+//!
+//! Lit shaders can paste `POINT_SHADOW_GLSL` into their fragment shader and call
+//! `pointShadow(fragPos)`, which returns `1.0` for lit fragments and `0.0` for shadowed ones.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::scene::{PointShadowMap, Scene};
+//! use glwfr::graphics::texture_unit::TextureUnit;
+//! use glwfr::cgmath::Point3;
+//!
+//! fn render(scene: &mut Scene) -> Result<(), glwfr::custom_errors::Errors> {
+//!     let shadow_map = PointShadowMap::new(1024, 25.0)?;
+//!     let light_position = Point3::new(0.0, 5.0, 0.0);
+//!
+//!     // Shadow pass
+//!     scene.render_point_shadow(&shadow_map, light_position);
+//!
+//!     // Lighting pass
+//!     for object in scene.objects_mut() {
+//!         object.shader_program.bind();
+//!         shadow_map.apply(&object.shader_program, TextureUnit::new(14), light_position, 0.05);
+//!     }
+//!     scene.render();
+//!     Ok(())
+//! }
+//! ```
+
+use crate::custom_errors::{Errors, GlErrorKind};
+use crate::graphics::gl_wrapper::{ContextBound, ShaderProgram};
+use crate::graphics::stats;
+use crate::graphics::texture_unit::TextureUnit;
+use crate::scene::Object;
+use cgmath::*;
+use gl::types::*;
+
+/// GLSL helpers for sampling a `PointShadowMap` in a lit fragment shader.
+///
+/// Declares the uniforms set by `PointShadowMap::apply` and a `pointShadow(fragPos)` function
+/// returning `1.0` if the world-space position is lit and `0.0` if it is in shadow.
+pub const POINT_SHADOW_GLSL: &str = r#"
+uniform samplerCube pointShadowMap;
+uniform vec3 pointShadowLightPosition;
+uniform float pointShadowFarPlane;
+uniform float pointShadowBias;
+
+float pointShadow(vec3 fragPos) {
+    vec3 lightToFrag = fragPos - pointShadowLightPosition;
+    float closestDepth = texture(pointShadowMap, lightToFrag).r * pointShadowFarPlane;
+    float currentDepth = length(lightToFrag);
+    return currentDepth - pointShadowBias > closestDepth ? 0.0 : 1.0;
+}
+"#;
+
+const DEPTH_VERTEX_SHADER: &str = r#"#version 330 core
+layout (location = 0) in vec3 aPos;
+
+uniform mat4 model;
+uniform mat4 lightSpaceMatrix;
+
+out vec3 fragPos;
+
+void main() {
+    vec4 worldPos = model * vec4(aPos, 1.0);
+    fragPos = worldPos.xyz;
+    gl_Position = lightSpaceMatrix * worldPos;
+}
+"#;
+
+const DEPTH_FRAGMENT_SHADER: &str = r#"#version 330 core
+in vec3 fragPos;
+
+uniform vec3 lightPosition;
+uniform float farPlane;
+
+void main() {
+    gl_FragDepth = length(fragPos - lightPosition) / farPlane;
+}
+"#;
+
+/// The near plane used when rendering the cubemap faces.
+const NEAR_PLANE: f32 = 0.1;
+
+/// A depth cubemap storing the distance from a point light to the closest surfaces.
+pub struct PointShadowMap {
+    fbo: GLuint,
+    cubemap: GLuint,
+    resolution: u32,
+    far_plane: f32,
+    depth_shader: ShaderProgram,
+    _context: ContextBound,
+}
+
+impl PointShadowMap {
+    /// Creates a new point shadow map.
+    ///
+    /// # Arguments
+    ///
+    /// * `resolution` - The width and height of each cubemap face in pixels, e.g. from
+    ///   `ShadowSettings::resolution`.
+    /// * `far_plane` - The range of the light; surfaces further away are never shadowed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::OpenGlError` if the cubemap or framebuffer cannot be created, or an
+    /// `Errors::ShaderCompilationError` if the depth shader cannot be compiled.
+    pub fn new(resolution: u32, far_plane: f32) -> Result<Self, Errors> {
+        let depth_shader = ShaderProgram::from_source(DEPTH_VERTEX_SHADER, DEPTH_FRAGMENT_SHADER)?;
+
+        let mut fbo = 0;
+        let mut cubemap = 0;
+        unsafe {
+            gl::GenFramebuffers(1, &mut fbo);
+            gl::GenTextures(1, &mut cubemap);
+        }
+        if fbo == 0 || cubemap == 0 {
+            return Err(Errors::OpenGlError(
+                "Failed to generate point shadow map".to_string(),
+                GlErrorKind::ObjectCreationFailed,
+            ));
+        }
+
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, cubemap);
+            for face in 0..6 {
+                gl::TexImage2D(
+                    gl::TEXTURE_CUBE_MAP_POSITIVE_X + face,
+                    0,
+                    gl::DEPTH_COMPONENT24 as i32,
+                    resolution as i32,
+                    resolution as i32,
+                    0,
+                    gl::DEPTH_COMPONENT,
+                    gl::FLOAT,
+                    std::ptr::null(),
+                );
+            }
+            gl::TexParameteri(
+                gl::TEXTURE_CUBE_MAP,
+                gl::TEXTURE_MIN_FILTER,
+                gl::NEAREST as i32,
+            );
+            gl::TexParameteri(
+                gl::TEXTURE_CUBE_MAP,
+                gl::TEXTURE_MAG_FILTER,
+                gl::NEAREST as i32,
+            );
+            gl::TexParameteri(
+                gl::TEXTURE_CUBE_MAP,
+                gl::TEXTURE_WRAP_S,
+                gl::CLAMP_TO_EDGE as i32,
+            );
+            gl::TexParameteri(
+                gl::TEXTURE_CUBE_MAP,
+                gl::TEXTURE_WRAP_T,
+                gl::CLAMP_TO_EDGE as i32,
+            );
+            gl::TexParameteri(
+                gl::TEXTURE_CUBE_MAP,
+                gl::TEXTURE_WRAP_R,
+                gl::CLAMP_TO_EDGE as i32,
+            );
+
+            gl::BindFramebuffer(gl::FRAMEBUFFER, fbo);
+            gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::DEPTH_ATTACHMENT,
+                gl::TEXTURE_CUBE_MAP_POSITIVE_X,
+                cubemap,
+                0,
+            );
+            gl::DrawBuffer(gl::NONE);
+            gl::ReadBuffer(gl::NONE);
+            let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            if status != gl::FRAMEBUFFER_COMPLETE {
+                gl::DeleteFramebuffers(1, &fbo);
+                gl::DeleteTextures(1, &cubemap);
+                return Err(Errors::OpenGlError(
+                    "Point shadow map framebuffer is incomplete".to_string(),
+                    GlErrorKind::FramebufferIncomplete(status),
+                ));
+            }
+        }
+
+        Ok(Self {
+            fbo,
+            cubemap,
+            resolution,
+            far_plane,
+            depth_shader,
+            _context: ContextBound::default(),
+        })
+    }
+
+    /// Returns the width and height of each cubemap face in pixels.
+    pub fn resolution(&self) -> u32 {
+        self.resolution
+    }
+
+    /// Returns the range of the light.
+    pub fn far_plane(&self) -> f32 {
+        self.far_plane
+    }
+
+    /// Sets the range of the light. Takes effect on the next `render`.
+    pub fn set_far_plane(&mut self, far_plane: f32) {
+        self.far_plane = far_plane;
+    }
+
+    /// Returns the view-projection matrices of the six cubemap faces, in the order
+    /// +X, -X, +Y, -Y, +Z, -Z.
+    ///
+    /// # Arguments
+    ///
+    /// * `light_position` - The world-space position of the point light.
+    pub fn face_matrices(&self, light_position: Point3<f32>) -> [Matrix4<f32>; 6] {
+        let projection = perspective(Deg(90.0), 1.0, NEAR_PLANE, self.far_plane);
+        let faces = [
+            (Vector3::unit_x(), -Vector3::unit_y()),
+            (-Vector3::unit_x(), -Vector3::unit_y()),
+            (Vector3::unit_y(), Vector3::unit_z()),
+            (-Vector3::unit_y(), -Vector3::unit_z()),
+            (Vector3::unit_z(), -Vector3::unit_y()),
+            (-Vector3::unit_z(), -Vector3::unit_y()),
+        ];
+        faces.map(|(direction, up)| {
+            projection * Matrix4::look_at_rh(light_position, light_position + direction, up)
+        })
+    }
+
+    /// Renders the distance from the light to the given objects into all six cubemap faces.
+    ///
+    /// The previous framebuffer binding and viewport are restored afterwards.
+    ///
+    /// # Arguments
+    ///
+    /// * `light_position` - The world-space position of the point light.
+    /// * `objects` - The objects casting shadows, e.g. from `Scene::render_point_shadow`.
+    ///
+    /// # OpenGL Functions
+    ///
+    /// This function renders each face with `glFramebufferTexture2D` and `glDrawElements`.
+    pub fn render<'a>(
+        &self,
+        light_position: Point3<f32>,
+        objects: impl IntoIterator<Item = &'a mut Object>,
+    ) {
+        let mut previous_fbo = 0;
+        let mut previous_viewport = [0; 4];
+        unsafe {
+            gl::GetIntegerv(gl::DRAW_FRAMEBUFFER_BINDING, &mut previous_fbo);
+            gl::GetIntegerv(gl::VIEWPORT, previous_viewport.as_mut_ptr());
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+            gl::Viewport(0, 0, self.resolution as i32, self.resolution as i32);
+        }
+
+        self.depth_shader.bind();
+        self.depth_shader
+            .try_set_uniform("lightPosition", light_position);
+        self.depth_shader
+            .try_set_uniform("farPlane", self.far_plane);
+
+        let mut objects: Vec<&mut Object> = objects.into_iter().collect();
+        for (face, matrix) in self.face_matrices(light_position).into_iter().enumerate() {
+            unsafe {
+                gl::FramebufferTexture2D(
+                    gl::FRAMEBUFFER,
+                    gl::DEPTH_ATTACHMENT,
+                    gl::TEXTURE_CUBE_MAP_POSITIVE_X + face as u32,
+                    self.cubemap,
+                    0,
+                );
+                gl::Clear(gl::DEPTH_BUFFER_BIT);
+            }
+            self.depth_shader
+                .try_set_uniform("lightSpaceMatrix", matrix);
+            for object in objects.iter_mut() {
+                object.render_with(&self.depth_shader);
+            }
+        }
+
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, previous_fbo as GLuint);
+            gl::Viewport(
+                previous_viewport[0],
+                previous_viewport[1],
+                previous_viewport[2],
+                previous_viewport[3],
+            );
+        }
+    }
+
+    /// Binds the cubemap and sets the uniforms declared by `POINT_SHADOW_GLSL`.
+    ///
+    /// The shader program must be bound before calling this function. Uniforms the shader
+    /// does not declare are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `shader_program` - The lit shader program sampling the shadow map.
+    /// * `unit` - The texture unit to bind the cubemap to.
+    /// * `light_position` - The world-space position of the point light.
+    /// * `bias` - The depth bias in world units, e.g. from `ShadowSettings::depth_bias`.
+    pub fn apply(
+        &self,
+        shader_program: &ShaderProgram,
+        unit: TextureUnit,
+        light_position: Point3<f32>,
+        bias: f32,
+    ) {
+        unit.activate();
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, self.cubemap);
+        }
+        stats::record_texture_bind();
+        shader_program.try_set_uniform("pointShadowMap", unit.index() as i32);
+        shader_program.try_set_uniform("pointShadowLightPosition", light_position);
+        shader_program.try_set_uniform("pointShadowFarPlane", self.far_plane);
+        shader_program.try_set_uniform("pointShadowBias", bias);
+    }
+}
+
+impl Drop for PointShadowMap {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteFramebuffers(1, &self.fbo);
+            gl::DeleteTextures(1, &self.cubemap);
+        }
+    }
+}
@@ -0,0 +1,340 @@
+//! # Skinning Module
+//!
+//! This module provides GPU skinning for animated characters. Bone matrices are uploaded to
+//! a uniform buffer once per frame by a `BonePalette`, and the vertex shader blends each vertex
+//! between up to four bones.
+//!
+//! Vertex shaders can paste the output of `skinning_glsl` and transform their inputs with
+//! `skinPosition(aPos)` and `skinNormal(aNormal)` before applying the model matrix. Joint
+//! indices and weights are read from vertex attributes `JOINTS_ATTRIBUTE` and
+//! `WEIGHTS_ATTRIBUTE`. Joint indices are unsigned integers and have to be set up with
+//! `glVertexAttribIPointer`.
+//!
+//! Bones are blended with linear blend skinning by default. Shaders generated with
+//! `SkinningMethod::DualQuaternion` blend the bones as dual quaternions instead, which keeps
+//! the volume of twisting joints such as wrists and shoulders, at the cost of not supporting
+//! scaled bones. The method is chosen per shader, so each material can pick its own.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::scene::{skinning_glsl, BonePalette, SkinningConfig};
+//! use glwfr::graphics::gl_wrapper::ShaderProgram;
+//! use glwfr::cgmath::{Matrix4, SquareMatrix};
+//!
+//! fn main() -> Result<(), glwfr::custom_errors::Errors> {
+//!     let config = SkinningConfig::default();
+//!     let vertex_source = format!(
+//!         "#version 330 core\n{}\n{}",
+//!         skinning_glsl(&config),
+//!         "layout (location = 0) in vec3 aPos;\n\
+//!          uniform mat4 mvp;\n\
+//!          void main() { gl_Position = mvp * skinPosition(vec4(aPos, 1.0)); }"
+//!     );
+//!     let shader = ShaderProgram::from_source(&vertex_source, "...")?;
+//!
+//!     let palette = BonePalette::new(config, 2)?;
+//!     palette.bind_to(&shader)?;
+//!
+//!     // Once per frame, with the matrices from the animation system
+//!     palette.update(&[Matrix4::identity(); 16]);
+//!     Ok(())
+//! }
+//! ```
+
+use crate::custom_errors::Errors;
+use crate::graphics::gl_wrapper::{ShaderProgram, UniformBuffer};
+use cgmath::*;
+
+/// The vertex attribute index of the bone indices (`uvec4` or fewer components).
+pub const JOINTS_ATTRIBUTE: u32 = 4;
+
+/// The vertex attribute index of the bone weights (`vec4` or fewer components).
+pub const WEIGHTS_ATTRIBUTE: u32 = 5;
+
+/// The name of the uniform block holding the bone matrices.
+pub const BONE_BLOCK_NAME: &str = "BoneMatrices";
+
+/// How the bones influencing a vertex are blended.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum SkinningMethod {
+    /// Blend the bone matrices. Supports scaled bones, but joints twisting far collapse
+    /// into a "candy wrapper".
+    #[default]
+    Linear,
+    /// Blend the bones as unit dual quaternions. Keeps the volume of twisting joints, but
+    /// ignores any scale in the bone matrices.
+    DualQuaternion,
+}
+
+impl SkinningMethod {
+    /// Returns the size of one bone in the bone uniform block, in bytes.
+    pub fn bone_size(&self) -> usize {
+        match self {
+            SkinningMethod::Linear => 64,
+            SkinningMethod::DualQuaternion => 32,
+        }
+    }
+}
+
+/// Limits of a skinned shader.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SkinningConfig {
+    /// The maximum number of bones per skeleton. A uniform block must fit `64` bytes per bone
+    /// with linear blending, or `32` bytes with dual quaternions, so values above `256` may
+    /// exceed `GlInfo::max_uniform_block_size`.
+    pub max_bones: u32,
+    /// The number of bones influencing each vertex, from `1` to `4`.
+    pub weights_per_vertex: u32,
+    /// How the bones of a vertex are blended.
+    pub method: SkinningMethod,
+}
+
+impl Default for SkinningConfig {
+    fn default() -> Self {
+        Self {
+            max_bones: 64,
+            weights_per_vertex: 4,
+            method: SkinningMethod::Linear,
+        }
+    }
+}
+
+/// Returns GLSL vertex shader helpers for the given configuration.
+///
+/// Declares the joint and weight attributes, the bone matrix uniform block and the functions
+/// `vec4 skinPosition(vec4 position)` and `vec3 skinNormal(vec3 normal)`.
+pub fn skinning_glsl(config: &SkinningConfig) -> String {
+    let weights = config.weights_per_vertex.clamp(1, 4);
+    let (joint_type, weight_type) = match weights {
+        1 => ("uint", "float"),
+        2 => ("uvec2", "vec2"),
+        3 => ("uvec3", "vec3"),
+        _ => ("uvec4", "vec4"),
+    };
+    let component = |i: u32| {
+        if weights == 1 {
+            String::new()
+        } else {
+            format!("[{}]", i)
+        }
+    };
+    if config.method == SkinningMethod::DualQuaternion {
+        return dual_quaternion_glsl(config, weights, joint_type, weight_type, &component);
+    }
+    let blend: Vec<String> = (0..weights)
+        .map(|i| format!("aWeights{c} * bones[aJoints{c}]", c = component(i)))
+        .collect();
+    format!(
+        r#"
+#define MAX_BONES {max_bones}
+layout (location = {joints_location}) in {joint_type} aJoints;
+layout (location = {weights_location}) in {weight_type} aWeights;
+
+layout (std140) uniform {block} {{
+    mat4 bones[MAX_BONES];
+}};
+
+mat4 skinMatrix() {{
+    return {blend};
+}}
+
+vec4 skinPosition(vec4 position) {{
+    return skinMatrix() * position;
+}}
+
+vec3 skinNormal(vec3 normal) {{
+    return mat3(skinMatrix()) * normal;
+}}
+"#,
+        max_bones = config.max_bones.max(1),
+        joints_location = JOINTS_ATTRIBUTE,
+        weights_location = WEIGHTS_ATTRIBUTE,
+        joint_type = joint_type,
+        weight_type = weight_type,
+        block = BONE_BLOCK_NAME,
+        blend = blend.join("\n        + "),
+    )
+}
+
+/// Returns the dual quaternion variant of the skinning helpers.
+///
+/// Each bone is stored as two `vec4`s, the rotation followed by the dual part encoding the
+/// translation. Bones on the opposite hemisphere of the first bone are negated before
+/// blending so the shortest rotation is taken. The blended dual quaternion is kept in
+/// globals, so `skinPosition` and `skinNormal` share a single blend per vertex.
+fn dual_quaternion_glsl(
+    config: &SkinningConfig,
+    weights: u32,
+    joint_type: &str,
+    weight_type: &str,
+    component: &impl Fn(u32) -> String,
+) -> String {
+    let blend: String = (0..weights)
+        .map(|i| {
+            format!(
+                "    blendBone(aJoints{c}, aWeights{c});\n",
+                c = component(i)
+            )
+        })
+        .collect();
+    let first_joint = format!("aJoints{}", component(0));
+    format!(
+        r#"
+#define MAX_BONES {max_bones}
+layout (location = {joints_location}) in {joint_type} aJoints;
+layout (location = {weights_location}) in {weight_type} aWeights;
+
+layout (std140) uniform {block} {{
+    vec4 bones[MAX_BONES * 2];
+}};
+
+vec4 skinReal = vec4(0.0);
+vec4 skinDual = vec4(0.0);
+bool skinBlended = false;
+
+void blendBone(uint joint, float weight) {{
+    vec4 boneReal = bones[joint * 2u];
+    if (dot(boneReal, bones[{first_joint} * 2u]) < 0.0) {{
+        weight = -weight;
+    }}
+    skinReal += weight * boneReal;
+    skinDual += weight * bones[joint * 2u + 1u];
+}}
+
+void skinDualQuaternion() {{
+    if (skinBlended) {{
+        return;
+    }}
+{blend}    float norm = length(skinReal);
+    skinReal /= norm;
+    skinDual /= norm;
+    skinBlended = true;
+}}
+
+vec4 skinPosition(vec4 position) {{
+    skinDualQuaternion();
+    vec3 rotated = position.xyz
+        + 2.0 * cross(skinReal.xyz, cross(skinReal.xyz, position.xyz) + skinReal.w * position.xyz);
+    vec3 translation = 2.0 * (skinReal.w * skinDual.xyz - skinDual.w * skinReal.xyz
+        + cross(skinReal.xyz, skinDual.xyz));
+    return vec4(rotated + translation * position.w, position.w);
+}}
+
+vec3 skinNormal(vec3 normal) {{
+    skinDualQuaternion();
+    return normal + 2.0 * cross(skinReal.xyz, cross(skinReal.xyz, normal) + skinReal.w * normal);
+}}
+"#,
+        max_bones = config.max_bones.max(1),
+        joints_location = JOINTS_ATTRIBUTE,
+        weights_location = WEIGHTS_ATTRIBUTE,
+        joint_type = joint_type,
+        weight_type = weight_type,
+        block = BONE_BLOCK_NAME,
+        first_joint = first_joint,
+        blend = blend,
+    )
+}
+
+/// Converts a rigid bone matrix into a unit dual quaternion, stored as the rotation
+/// followed by the dual part, each as `[x, y, z, w]`. Scale is removed from the matrix.
+pub fn dual_quaternion_from_matrix(matrix: &Matrix4<f32>) -> [[f32; 4]; 2] {
+    let axes = [
+        matrix.x.truncate(),
+        matrix.y.truncate(),
+        matrix.z.truncate(),
+    ];
+    let [x, y, z] = axes.map(|axis| {
+        let length = axis.magnitude();
+        if length > 0.0 {
+            axis / length
+        } else {
+            axis
+        }
+    });
+    let real = Quaternion::from(Matrix3::from_cols(x, y, z)).normalize();
+    let translation = Quaternion::from_sv(0.0, matrix.w.truncate());
+    let dual = translation * real * 0.5;
+    [
+        [real.v.x, real.v.y, real.v.z, real.s],
+        [dual.v.x, dual.v.y, dual.v.z, dual.s],
+    ]
+}
+
+/// A uniform buffer holding the bone matrices of a skeleton.
+pub struct BonePalette {
+    buffer: UniformBuffer,
+    config: SkinningConfig,
+    binding_point: u32,
+}
+
+impl BonePalette {
+    /// Creates a bone palette with room for `config.max_bones` matrices, initialized to
+    /// identity.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The limits the skinned shaders were generated with.
+    /// * `binding_point` - The uniform buffer binding point to use.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::OpenGlError` if the uniform buffer cannot be created.
+    pub fn new(config: SkinningConfig, binding_point: u32) -> Result<Self, Errors> {
+        let buffer = UniformBuffer::new(binding_point)?;
+        buffer.bind();
+        let bones = config.max_bones.max(1) as usize;
+        match config.method {
+            SkinningMethod::Linear => {
+                buffer.store_data(&vec![Matrix4::<f32>::identity(); bones]);
+            }
+            SkinningMethod::DualQuaternion => {
+                let identity = dual_quaternion_from_matrix(&Matrix4::identity());
+                buffer.store_data(&vec![identity; bones]);
+            }
+        }
+        Ok(Self {
+            buffer,
+            config,
+            binding_point,
+        })
+    }
+
+    /// Returns the limits of the palette.
+    pub fn config(&self) -> SkinningConfig {
+        self.config
+    }
+
+    /// Connects the `BoneMatrices` block of a skinned shader to this palette.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::OpenGlError` if the shader does not declare the block.
+    pub fn bind_to(&self, shader_program: &ShaderProgram) -> Result<(), Errors> {
+        shader_program.create_uniform_block(BONE_BLOCK_NAME, self.binding_point)
+    }
+
+    /// Uploads the bone matrices, usually once per frame. Matrices beyond
+    /// `config.max_bones` are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `bones` - The final skinning matrices, i.e. each joint's world transform multiplied
+    ///   by its inverse bind matrix. With `SkinningMethod::DualQuaternion` they are converted
+    ///   to dual quaternions, dropping any scale.
+    pub fn update(&self, bones: &[Matrix4<f32>]) {
+        let count = bones.len().min(self.config.max_bones as usize);
+        self.buffer.bind();
+        match self.config.method {
+            SkinningMethod::Linear => self.buffer.update_data(0, &bones[..count]),
+            SkinningMethod::DualQuaternion => {
+                let quaternions: Vec<[[f32; 4]; 2]> = bones[..count]
+                    .iter()
+                    .map(dual_quaternion_from_matrix)
+                    .collect();
+                self.buffer.update_data(0, &quaternions);
+            }
+        }
+    }
+}
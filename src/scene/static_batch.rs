@@ -0,0 +1,481 @@
+//! # Static Batch Module
+//!
+//! This module merges the meshes of static objects that share a shader program and render
+//! state into one mesh per group, so level geometry made of many small objects is drawn
+//! with a few draw calls. The vertices are read back from the GPU and pre-transformed by
+//! the object's model matrix: attribute 0 is treated as the position and attribute 1, if
+//! present, as the normal, matching the layout the built-in shaders use. All other
+//! attributes are copied unchanged.
+//!
+//! Batches are built with `Scene::bake_static`.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::scene::{Object, Scene};
+//!
+//! fn load_level(scene: &mut Scene, walls: Vec<Object>) -> Result<(), glwfr::custom_errors::Errors> {
+//!     for mut wall in walls {
+//!         wall.is_static = true;
+//!         scene.add_object(wall);
+//!     }
+//!     let merged = scene.bake_static()?;
+//!     println!("Merged {} objects", merged);
+//!     Ok(())
+//! }
+//! ```
+
+use crate::custom_errors::Errors;
+use crate::graphics::alpha_mode::AlphaMode;
+use crate::graphics::gl_wrapper::{
+    BufferObject, Ebo, IndexType, PrimitiveType, Vao, VertexAttribute,
+};
+use crate::graphics::mesh::Mesh;
+use crate::graphics::render_state::{CullMode, RenderState};
+use crate::math::Aabb;
+use crate::scene::Object;
+use cgmath::*;
+use gl::types::*;
+use std::collections::HashMap;
+use std::os::raw::*;
+use std::rc::Rc;
+
+/// The format of one vertex attribute of a mesh.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct AttributeFormat {
+    index: u32,
+    size: i32,
+    r#type: GLenum,
+    normalized: bool,
+    integer: bool,
+}
+
+impl AttributeFormat {
+    /// Returns the size of one element in bytes, or `None` for unsupported types.
+    fn element_size(&self) -> Option<usize> {
+        let component_size = match self.r#type {
+            gl::BYTE | gl::UNSIGNED_BYTE => 1,
+            gl::SHORT | gl::UNSIGNED_SHORT | gl::HALF_FLOAT => 2,
+            gl::INT | gl::UNSIGNED_INT | gl::FLOAT => 4,
+            gl::INT_2_10_10_10_REV | gl::UNSIGNED_INT_2_10_10_10_REV => return Some(4),
+            _ => return None,
+        };
+        Some(component_size * self.size as usize)
+    }
+
+    /// Checks if the attribute is three 32-bit floats, as required for positions and normals.
+    fn is_vec3(&self) -> bool {
+        self.r#type == gl::FLOAT && self.size == 3 && !self.integer
+    }
+}
+
+/// The geometry of a mesh, read back from the GPU with tightly packed attributes.
+struct MeshGeometry {
+    attributes: Vec<(AttributeFormat, Vec<u8>)>,
+    indices: Vec<u32>,
+    vertex_count: usize,
+}
+
+impl MeshGeometry {
+    /// Reads the vertices and indices of an indexed triangle mesh.
+    ///
+    /// Returns `None` if the mesh cannot be batched, e.g. because it is not a triangle list,
+    /// uses instanced attributes or has no position attribute.
+    fn read(vao: &Vao) -> Option<Self> {
+        if vao.primitive_type() != PrimitiveType::Triangles || vao.index_count() == 0 {
+            return None;
+        }
+        vao.bind();
+        let geometry = Self::read_bound(vao);
+        vao.unbind();
+        unsafe {
+            gl::BindBuffer(gl::COPY_READ_BUFFER, 0);
+        }
+        geometry
+    }
+
+    fn read_bound(vao: &Vao) -> Option<Self> {
+        let mut element_buffer = 0;
+        unsafe {
+            gl::GetIntegerv(gl::ELEMENT_ARRAY_BUFFER_BINDING, &mut element_buffer);
+        }
+        if element_buffer == 0 {
+            return None;
+        }
+        let index_bytes = read_buffer(
+            element_buffer as GLuint,
+            0,
+            vao.index_count() * vao.index_type().size(),
+        )?;
+        let indices: Vec<u32> = match vao.index_type() {
+            IndexType::U8 => index_bytes.iter().map(|&index| index as u32).collect(),
+            IndexType::U16 => index_bytes
+                .chunks_exact(2)
+                .map(|bytes| u16::from_ne_bytes([bytes[0], bytes[1]]) as u32)
+                .collect(),
+            IndexType::U32 => index_bytes
+                .chunks_exact(4)
+                .map(|bytes| u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+                .collect(),
+        };
+        let vertex_count = indices.iter().max().map_or(0, |&max| max as usize + 1);
+
+        let mut max_attributes = 0;
+        unsafe {
+            gl::GetIntegerv(gl::MAX_VERTEX_ATTRIBS, &mut max_attributes);
+        }
+        let mut attributes = Vec::new();
+        for index in 0..max_attributes as GLuint {
+            let parameter = |name: GLenum| {
+                let mut value = 0;
+                unsafe {
+                    gl::GetVertexAttribiv(index, name, &mut value);
+                }
+                value
+            };
+            if parameter(gl::VERTEX_ATTRIB_ARRAY_ENABLED) == 0 {
+                continue;
+            }
+            let buffer = parameter(gl::VERTEX_ATTRIB_ARRAY_BUFFER_BINDING) as GLuint;
+            if buffer == 0 || parameter(gl::VERTEX_ATTRIB_ARRAY_DIVISOR) != 0 {
+                return None;
+            }
+            let format = AttributeFormat {
+                index,
+                size: parameter(gl::VERTEX_ATTRIB_ARRAY_SIZE),
+                r#type: parameter(gl::VERTEX_ATTRIB_ARRAY_TYPE) as GLenum,
+                normalized: parameter(gl::VERTEX_ATTRIB_ARRAY_NORMALIZED) != 0,
+                integer: parameter(gl::VERTEX_ATTRIB_ARRAY_INTEGER) != 0,
+            };
+            let element_size = format.element_size()?;
+            let stride = match parameter(gl::VERTEX_ATTRIB_ARRAY_STRIDE) as usize {
+                0 => element_size,
+                stride => stride,
+            };
+            let mut pointer: *mut c_void = std::ptr::null_mut();
+            unsafe {
+                gl::GetVertexAttribPointerv(
+                    index,
+                    gl::VERTEX_ATTRIB_ARRAY_POINTER,
+                    std::ptr::addr_of_mut!(pointer),
+                );
+            }
+            let offset = pointer as usize;
+            let length = match vertex_count {
+                0 => 0,
+                count => (count - 1) * stride + element_size,
+            };
+            let source = read_buffer(buffer, offset, length)?;
+            let mut data = Vec::with_capacity(vertex_count * element_size);
+            for vertex in 0..vertex_count {
+                let start = vertex * stride;
+                data.extend_from_slice(&source[start..start + element_size]);
+            }
+            attributes.push((format, data));
+        }
+
+        let formats = |index| attributes.iter().find(|(format, _)| format.index == index);
+        let has_position = formats(0).is_some_and(|(format, _)| format.is_vec3());
+        let valid_normal = formats(1).is_none_or(|(format, _)| format.is_vec3());
+        (has_position && valid_normal).then_some(Self {
+            attributes,
+            indices,
+            vertex_count,
+        })
+    }
+
+    /// Returns the attribute formats, used to check if meshes can be merged.
+    fn formats(&self) -> Vec<AttributeFormat> {
+        self.attributes.iter().map(|(format, _)| *format).collect()
+    }
+
+    /// Returns the vertex positions.
+    fn positions(&self) -> impl Iterator<Item = Point3<f32>> + '_ {
+        self.attributes
+            .iter()
+            .filter(|(format, _)| format.index == 0)
+            .flat_map(|(_, data)| data.chunks_exact(12))
+            .map(|element| {
+                let component = |i: usize| {
+                    f32::from_ne_bytes([
+                        element[i * 4],
+                        element[i * 4 + 1],
+                        element[i * 4 + 2],
+                        element[i * 4 + 3],
+                    ])
+                };
+                Point3::new(component(0), component(1), component(2))
+            })
+    }
+
+    /// Moves the vertices into world space.
+    fn transform(&mut self, model: Matrix4<f32>) {
+        let normal_matrix =
+            Matrix3::from_cols(model.x.truncate(), model.y.truncate(), model.z.truncate())
+                .invert()
+                .map(|inverse| inverse.transpose())
+                .unwrap_or_else(Matrix3::identity);
+        for (format, data) in &mut self.attributes {
+            match format.index {
+                0 => transform_vec3(data, |position| {
+                    model.transform_point(Point3::from_vec(position)).to_vec()
+                }),
+                1 => transform_vec3(data, |normal| {
+                    let normal = normal_matrix * normal;
+                    if normal.magnitude2() > 0.0 {
+                        normal.normalize()
+                    } else {
+                        normal
+                    }
+                }),
+                _ => {}
+            }
+        }
+        // Mirroring transforms flip the winding order of the triangles
+        if model.determinant() < 0.0 {
+            for triangle in self.indices.chunks_exact_mut(3) {
+                triangle.swap(1, 2);
+            }
+        }
+    }
+}
+
+/// Reads a range of a buffer object through the copy read binding point.
+fn read_buffer(buffer: GLuint, offset: usize, length: usize) -> Option<Vec<u8>> {
+    let mut size = 0;
+    unsafe {
+        gl::BindBuffer(gl::COPY_READ_BUFFER, buffer);
+        gl::GetBufferParameteriv(gl::COPY_READ_BUFFER, gl::BUFFER_SIZE, &mut size);
+    }
+    if offset + length > size as usize {
+        return None;
+    }
+    let mut data = vec![0u8; length];
+    unsafe {
+        gl::GetBufferSubData(
+            gl::COPY_READ_BUFFER,
+            offset as GLintptr,
+            length as GLsizeiptr,
+            data.as_mut_ptr() as *mut c_void,
+        );
+    }
+    Some(data)
+}
+
+fn transform_vec3(data: &mut [u8], transform: impl Fn(Vector3<f32>) -> Vector3<f32>) {
+    for element in data.chunks_exact_mut(12) {
+        let component = |i: usize| {
+            f32::from_ne_bytes([
+                element[i * 4],
+                element[i * 4 + 1],
+                element[i * 4 + 2],
+                element[i * 4 + 3],
+            ])
+        };
+        let value = transform(Vector3::new(component(0), component(1), component(2)));
+        for (i, component) in [value.x, value.y, value.z].into_iter().enumerate() {
+            element[i * 4..i * 4 + 4].copy_from_slice(&component.to_ne_bytes());
+        }
+    }
+}
+
+/// Information about a merged mesh. Its buffers are owned by the `Mesh` of the merged object.
+pub struct StaticBatch {
+    object_count: usize,
+}
+
+impl StaticBatch {
+    /// Returns the number of objects merged into the batch.
+    pub fn object_count(&self) -> usize {
+        self.object_count
+    }
+}
+
+/// The properties objects must share to be merged.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct BatchKey {
+    shader_program: *const (),
+    render_state: Option<RenderState>,
+    /// The cutoff bits and alpha-to-coverage flag of cutout materials.
+    cutout: Option<(u32, bool)>,
+    cull_mode: Option<CullMode>,
+    cast_shadows: bool,
+    receive_shadows: bool,
+    formats: Vec<AttributeFormat>,
+}
+
+/// Merges the static objects of a list in place, keeping the order of the objects that
+/// stay separate. Information about every merged object is added to `batches`.
+///
+/// If a merged mesh cannot be created, the groups that were not merged yet are put back
+/// unchanged before the error is returned.
+///
+/// # Returns
+///
+/// The number of objects that were merged away.
+pub(crate) fn merge_static_objects(
+    objects: &mut Vec<Object>,
+    batches: &mut Vec<StaticBatch>,
+) -> Result<usize, Errors> {
+    let mut groups: HashMap<BatchKey, Vec<(Object, MeshGeometry)>> = HashMap::new();
+    let mut group_order = Vec::new();
+    for object in std::mem::take(objects) {
+        // Blended objects are sorted by distance, so they stay separate
+        let batchable = object.is_static
+            && object.active
+            && object.lightmap.is_none()
+            && object.morph.is_none()
+            && object.toon.is_none()
+            && object.wind_sway.is_none()
+            && object.alpha_mode != AlphaMode::Blend;
+        let geometry = batchable
+            .then(|| MeshGeometry::read(object.mesh().vao()))
+            .flatten();
+        let Some(geometry) = geometry else {
+            objects.push(object);
+            continue;
+        };
+        let key = BatchKey {
+            shader_program: Rc::as_ptr(&object.shader_program) as *const (),
+            render_state: object.render_state,
+            cutout: match object.alpha_mode {
+                AlphaMode::Cutout {
+                    cutoff,
+                    alpha_to_coverage,
+                } => Some((cutoff.to_bits(), alpha_to_coverage)),
+                _ => None,
+            },
+            cull_mode: object.cull_mode,
+            cast_shadows: object.cast_shadows,
+            receive_shadows: object.receive_shadows,
+            formats: geometry.formats(),
+        };
+        if !groups.contains_key(&key) {
+            group_order.push(key.clone());
+        }
+        groups.entry(key).or_default().push((object, geometry));
+    }
+
+    let mut merged = 0;
+    let mut result = Ok(());
+    for key in group_order {
+        let Some(mut group) = groups.remove(&key) else {
+            continue;
+        };
+        let vertex_count: usize = group
+            .iter()
+            .map(|(_, geometry)| geometry.vertex_count)
+            .sum();
+        if result.is_err() || group.len() < 2 || vertex_count > u32::MAX as usize {
+            objects.extend(group.into_iter().map(|(object, _)| object));
+            continue;
+        }
+        match merge_group(&mut group) {
+            Ok((object, batch)) => {
+                merged += batch.object_count;
+                objects.push(object);
+                batches.push(batch);
+            }
+            Err(error) => {
+                objects.extend(group.into_iter().map(|(object, _)| object));
+                result = Err(error);
+            }
+        }
+    }
+    result.map(|_| merged)
+}
+
+/// Merges a group of objects with the same shader program, render state and vertex format.
+fn merge_group(group: &mut [(Object, MeshGeometry)]) -> Result<(Object, StaticBatch), Errors> {
+    for (object, geometry) in group.iter_mut() {
+        geometry.transform(object.transform.matrix());
+    }
+    // Measured from the world-space vertices, so members without bounds are covered too
+    let bounds = Aabb::from_points(group.iter().flat_map(|(_, geometry)| geometry.positions()));
+
+    let formats = group[0].1.formats();
+    let mut vertex_data = Vec::new();
+    let mut offsets = Vec::with_capacity(formats.len());
+    for (attribute, _) in formats.iter().enumerate() {
+        offsets.push(vertex_data.len());
+        for (_, geometry) in group.iter() {
+            vertex_data.extend_from_slice(&geometry.attributes[attribute].1);
+        }
+    }
+    let mut indices = Vec::new();
+    let mut base_vertex = 0;
+    for (_, geometry) in group.iter() {
+        indices.extend(geometry.indices.iter().map(|index| index + base_vertex));
+        base_vertex += geometry.vertex_count as u32;
+    }
+
+    let mut vao = Vao::new()?;
+    vao.bind();
+    let vbo = BufferObject::new(gl::ARRAY_BUFFER, gl::STATIC_DRAW)?;
+    vbo.bind();
+    vbo.allocate(vertex_data.len());
+    vbo.update_data(0, &vertex_data);
+    let ebo = Ebo::new()?;
+    ebo.bind();
+    let index_type = if base_vertex <= u16::MAX as u32 + 1 {
+        ebo.store_indices(
+            &indices
+                .iter()
+                .map(|&index| index as u16)
+                .collect::<Vec<_>>(),
+        )
+    } else {
+        ebo.store_indices(&indices)
+    };
+    for (format, offset) in formats.iter().zip(offsets) {
+        let pointer = offset as *const c_void;
+        if format.integer {
+            unsafe {
+                gl::VertexAttribIPointer(format.index, format.size, format.r#type, 0, pointer);
+                gl::EnableVertexAttribArray(format.index);
+            }
+        } else {
+            let normalized = if format.normalized {
+                gl::TRUE
+            } else {
+                gl::FALSE
+            };
+            VertexAttribute::new(
+                format.index,
+                format.size,
+                format.r#type,
+                normalized,
+                0,
+                pointer,
+            )
+            .enable();
+        }
+    }
+    vao.unbind();
+    vao.set_index_count(indices.len());
+    vao.set_index_type(index_type);
+
+    let first = &group[0].0;
+    let mut object = Object::new(
+        Mesh::from_parts(vao, Some(vbo), Some(ebo)),
+        first.shader_program.clone(),
+    );
+    object.render_state = first.render_state;
+    object.alpha_mode = first.alpha_mode;
+    object.cull_mode = first.cull_mode;
+    object.cast_shadows = first.cast_shadows;
+    object.receive_shadows = first.receive_shadows;
+    object.is_static = true;
+    object.bounds = bounds;
+    for (source, _) in group.iter() {
+        for tag in source.tags() {
+            object.add_tag(tag);
+        }
+    }
+    Ok((
+        object,
+        StaticBatch {
+            object_count: group.len(),
+        },
+    ))
+}
@@ -0,0 +1,446 @@
+//! # Streaming Module
+//!
+//! This module provides a `LevelStreamer` that divides a large world into square chunks on
+//! the XZ plane and keeps only the chunks around the camera in the scene.
+//!
+//! Loading happens in two steps. The loader runs on a background thread and does the slow
+//! work that does not need OpenGL: reading files, decoding images and parsing meshes into
+//! CPU-side data. The builder runs on the context thread inside `LevelStreamer::update` and
+//! turns that data into objects, uploading meshes and textures. Chunks are requested nearest
+//! first, and results for chunks the camera has left in the meantime are discarded. A chunk
+//! whose loader or builder fails is marked as failed and requested again after a retry
+//! delay, so a missing file does not reload every frame.
+//!
+//! Chunks unload once they are further away than the unload radius, which is larger than
+//! the load radius so chunks at the border do not load and unload repeatedly. Freshly built
+//! chunks can fade in through the `on_fade` hook, e.g. by scaling their objects or setting a
+//! dissolve uniform.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::scene::{ChunkCoord, LevelStreamer, Object, Scene};
+//! use glwfr::graphics::mesh::Mesh;
+//! use glwfr::graphics::standard_vertex::StandardVertex;
+//! use glwfr::graphics::gl_wrapper::ShaderProgram;
+//! use std::rc::Rc;
+//!
+//! struct TerrainData {
+//!     vertices: Vec<StandardVertex>,
+//!     indices: Vec<u32>,
+//! }
+//!
+//! fn load_terrain(coord: ChunkCoord) -> Result<TerrainData, glwfr::custom_errors::Errors> {
+//!     // Read and parse "terrain/{x}_{z}.bin" here
+//!     Ok(TerrainData { vertices: Vec::new(), indices: Vec::new() })
+//! }
+//!
+//! fn frame(scene: &mut Scene, streamer: &mut LevelStreamer<TerrainData>, delta_time: f32) -> Result<(), glwfr::custom_errors::Errors> {
+//!     let camera_position = scene.get_mut_camera().position;
+//!     streamer.update(scene, camera_position, delta_time)?;
+//!     scene.render();
+//!     Ok(())
+//! }
+//!
+//! fn setup() -> Result<LevelStreamer<TerrainData>, glwfr::custom_errors::Errors> {
+//!     let shader_program = Rc::new(ShaderProgram::new("vertex.glsl", "fragment.glsl")?);
+//!     let mut streamer = LevelStreamer::new(64.0, 3, load_terrain, move |_, data: TerrainData| {
+//!         let mesh = Mesh::new(&data.vertices, &data.indices)?;
+//!         Ok(vec![Object::new(mesh, shader_program.clone())])
+//!     });
+//!     streamer.set_fade_duration(0.5);
+//!     streamer.on_fade(|_, progress, object| {
+//!         object.transform.set_scale(glwfr::cgmath::Vector3::new(1.0, progress, 1.0));
+//!     });
+//!     Ok(streamer)
+//! }
+//! ```
+
+use crate::custom_errors::Errors;
+use crate::scene::{Object, Scene};
+use cgmath::Point3;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::thread;
+
+/// The position of a chunk in the chunk grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ChunkCoord {
+    pub x: i32,
+    pub z: i32,
+}
+
+impl ChunkCoord {
+    /// Creates a chunk coordinate.
+    pub fn new(x: i32, z: i32) -> Self {
+        Self { x, z }
+    }
+
+    /// Returns the chunk containing the given world position.
+    pub fn containing(position: Point3<f32>, chunk_size: f32) -> Self {
+        Self {
+            x: (position.x / chunk_size).floor() as i32,
+            z: (position.z / chunk_size).floor() as i32,
+        }
+    }
+
+    /// Returns the world position of the chunk's minimum corner at height zero.
+    pub fn origin(&self, chunk_size: f32) -> Point3<f32> {
+        Point3::new(self.x as f32 * chunk_size, 0.0, self.z as f32 * chunk_size)
+    }
+
+    /// Returns the squared distance to another chunk in chunks.
+    pub fn distance2(&self, other: ChunkCoord) -> i64 {
+        let dx = (self.x - other.x) as i64;
+        let dz = (self.z - other.z) as i64;
+        dx * dx + dz * dz
+    }
+
+    /// Returns the tag given to the objects of the chunk, e.g. `"chunk:3,-1"`.
+    pub fn tag(&self) -> String {
+        format!("chunk:{}", self)
+    }
+}
+
+impl fmt::Display for ChunkCoord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{}", self.x, self.z)
+    }
+}
+
+/// The loading state of a chunk.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChunkState {
+    /// The loader is reading the chunk on the background thread.
+    Loading,
+    /// The chunk's objects are in the scene and fading in, with the progress in `0.0..1.0`.
+    FadingIn(f32),
+    /// The chunk's objects are in the scene.
+    Loaded,
+    /// The chunk's loader or builder failed. It is requested again once the remaining retry
+    /// delay in seconds runs out.
+    Failed(f32),
+}
+
+impl ChunkState {
+    /// Returns whether the chunk's objects are in the scene.
+    fn has_objects(&self) -> bool {
+        matches!(self, ChunkState::FadingIn(_) | ChunkState::Loaded)
+    }
+}
+
+type LoadResult<T> = (ChunkCoord, Result<T, Errors>);
+type ChunkHook = Box<dyn FnMut(ChunkCoord)>;
+type FadeHook = Box<dyn FnMut(ChunkCoord, f32, &mut Object)>;
+type ChunkBuilder<T> = Box<dyn FnMut(ChunkCoord, T) -> Result<Vec<Object>, Errors>>;
+
+/// Loads and unloads the chunks of a scene around the camera.
+///
+/// `T` is the CPU-side data of a chunk produced by the loader on the background thread.
+pub struct LevelStreamer<T: Send + 'static> {
+    chunk_size: f32,
+    load_radius: u32,
+    unload_radius: u32,
+    fade_duration: f32,
+    retry_delay: f32,
+    states: HashMap<ChunkCoord, ChunkState>,
+    builder: ChunkBuilder<T>,
+    requests: Sender<ChunkCoord>,
+    results: Receiver<LoadResult<T>>,
+    load_hooks: Vec<ChunkHook>,
+    unload_hooks: Vec<ChunkHook>,
+    fade_hooks: Vec<FadeHook>,
+}
+
+impl<T: Send + 'static> LevelStreamer<T> {
+    /// Creates a streamer and starts its background loading thread.
+    ///
+    /// # Arguments
+    ///
+    /// * `chunk_size` - The edge length of a chunk in world units.
+    /// * `load_radius` - The distance in chunks around the camera's chunk within which chunks
+    ///   are loaded. The unload radius defaults to one more chunk.
+    /// * `loader` - Produces the data of a chunk on the background thread. It must not use
+    ///   OpenGL.
+    /// * `builder` - Creates the objects of a chunk from its data on the context thread. The
+    ///   objects are tagged with `ChunkCoord::tag` and added to the scene.
+    pub fn new<L, B>(chunk_size: f32, load_radius: u32, loader: L, builder: B) -> Self
+    where
+        L: Fn(ChunkCoord) -> Result<T, Errors> + Send + 'static,
+        B: FnMut(ChunkCoord, T) -> Result<Vec<Object>, Errors> + 'static,
+    {
+        let (request_sender, request_receiver) = mpsc::channel::<ChunkCoord>();
+        let (result_sender, result_receiver) = mpsc::channel();
+        // The thread ends once the streamer is dropped and a result cannot be sent
+        thread::spawn(move || {
+            for coord in request_receiver {
+                if result_sender.send((coord, loader(coord))).is_err() {
+                    break;
+                }
+            }
+        });
+        Self {
+            chunk_size,
+            load_radius,
+            unload_radius: load_radius + 1,
+            fade_duration: 0.0,
+            retry_delay: 5.0,
+            states: HashMap::new(),
+            builder: Box::new(builder),
+            requests: request_sender,
+            results: result_receiver,
+            load_hooks: Vec::new(),
+            unload_hooks: Vec::new(),
+            fade_hooks: Vec::new(),
+        }
+    }
+
+    /// Returns the edge length of a chunk in world units.
+    pub fn chunk_size(&self) -> f32 {
+        self.chunk_size
+    }
+
+    /// Sets the distance in chunks within which chunks are loaded.
+    ///
+    /// The unload radius is raised to at least the load radius.
+    pub fn set_load_radius(&mut self, load_radius: u32) {
+        self.load_radius = load_radius;
+        self.unload_radius = self.unload_radius.max(load_radius);
+    }
+
+    /// Sets the distance in chunks beyond which loaded chunks are unloaded.
+    ///
+    /// Values below the load radius are raised to the load radius.
+    pub fn set_unload_radius(&mut self, unload_radius: u32) {
+        self.unload_radius = unload_radius.max(self.load_radius);
+    }
+
+    /// Sets how long freshly built chunks fade in, in seconds. `0.0` disables fading.
+    pub fn set_fade_duration(&mut self, fade_duration: f32) {
+        self.fade_duration = fade_duration.max(0.0);
+    }
+
+    /// Sets how long a chunk whose loader or builder failed waits before it is requested
+    /// again, in seconds. Defaults to `5.0`.
+    pub fn set_retry_delay(&mut self, retry_delay: f32) {
+        self.retry_delay = retry_delay.max(0.0);
+    }
+
+    /// Registers a callback run after the objects of a chunk were added to the scene.
+    pub fn on_load(&mut self, callback: impl FnMut(ChunkCoord) + 'static) {
+        self.load_hooks.push(Box::new(callback));
+    }
+
+    /// Registers a callback run before the objects of a chunk are removed from the scene.
+    pub fn on_unload(&mut self, callback: impl FnMut(ChunkCoord) + 'static) {
+        self.unload_hooks.push(Box::new(callback));
+    }
+
+    /// Registers a callback run for every object of a fading chunk on every update.
+    ///
+    /// The progress rises from `0.0` when the chunk is built to `1.0`, with which the callback
+    /// is called once more when the fade ends. Without a fade duration, it is called once
+    /// with `1.0`.
+    pub fn on_fade(&mut self, callback: impl FnMut(ChunkCoord, f32, &mut Object) + 'static) {
+        self.fade_hooks.push(Box::new(callback));
+    }
+
+    /// Returns the state of a chunk, or `None` if it is neither loading, loaded nor waiting to
+    /// be retried.
+    pub fn chunk_state(&self, coord: ChunkCoord) -> Option<ChunkState> {
+        self.states.get(&coord).copied()
+    }
+
+    /// Returns the chunks whose objects are in the scene.
+    pub fn loaded_chunks(&self) -> impl Iterator<Item = ChunkCoord> + '_ {
+        self.states
+            .iter()
+            .filter(|(_, state)| state.has_objects())
+            .map(|(coord, _)| *coord)
+    }
+
+    /// Returns the number of chunks waiting for the background thread.
+    pub fn pending_count(&self) -> usize {
+        self.states
+            .values()
+            .filter(|state| **state == ChunkState::Loading)
+            .count()
+    }
+
+    /// Requests the chunks around the camera, builds finished chunks, advances fades and
+    /// unloads distant chunks.
+    ///
+    /// Call this once per frame on the context thread.
+    ///
+    /// # Arguments
+    ///
+    /// * `scene` - The scene the chunk objects are added to and removed from.
+    /// * `camera_position` - The world position the chunks are loaded around.
+    /// * `delta_time` - The time since the last update in seconds.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the first chunk whose loader or builder failed. The chunk is
+    /// marked as `ChunkState::Failed` and requested again after the retry delay.
+    ///
+    /// Returns an `Errors::StreamingError` if the background thread has stopped, e.g.
+    /// because the loader panicked. No further chunks are loaded in that case.
+    pub fn update(
+        &mut self,
+        scene: &mut Scene,
+        camera_position: Point3<f32>,
+        delta_time: f32,
+    ) -> Result<(), Errors> {
+        let center = ChunkCoord::containing(camera_position, self.chunk_size);
+
+        // Unload chunks that are too far away
+        let unload_distance2 = (self.unload_radius as i64).pow(2);
+        let distant: Vec<ChunkCoord> = self
+            .states
+            .keys()
+            .copied()
+            .filter(|coord| coord.distance2(center) > unload_distance2)
+            .collect();
+        for coord in distant {
+            if self
+                .states
+                .remove(&coord)
+                .is_some_and(|state| state.has_objects())
+            {
+                for hook in &mut self.unload_hooks {
+                    hook(coord);
+                }
+                let tag = coord.tag();
+                scene.remove_objects(|object| object.has_tag(&tag));
+            }
+        }
+
+        // Forget failed chunks whose retry delay ran out, so they are requested again
+        self.states.retain(|_, state| match state {
+            ChunkState::Failed(remaining) => {
+                *remaining -= delta_time;
+                *remaining > 0.0
+            }
+            _ => true,
+        });
+
+        // Request missing chunks, nearest first
+        let radius = self.load_radius as i32;
+        let load_distance2 = (self.load_radius as i64).pow(2);
+        let mut missing: Vec<ChunkCoord> = (-radius..=radius)
+            .flat_map(|dz| (-radius..=radius).map(move |dx| (dx, dz)))
+            .map(|(dx, dz)| ChunkCoord::new(center.x + dx, center.z + dz))
+            .filter(|coord| coord.distance2(center) <= load_distance2)
+            .filter(|coord| !self.states.contains_key(coord))
+            .collect();
+        missing.sort_by_key(|coord| coord.distance2(center));
+        let mut result = Ok(());
+        for coord in missing {
+            if self.requests.send(coord).is_err() {
+                result = Err(loader_stopped());
+                break;
+            }
+            self.states.insert(coord, ChunkState::Loading);
+        }
+
+        // Advance fades before building, so new chunks start at zero
+        let mut fading = HashSet::new();
+        if self.fade_duration > 0.0 {
+            for (coord, state) in self.states.iter_mut() {
+                if let ChunkState::FadingIn(progress) = state {
+                    let progress = *progress + delta_time / self.fade_duration;
+                    *state = if progress >= 1.0 {
+                        ChunkState::Loaded
+                    } else {
+                        ChunkState::FadingIn(progress)
+                    };
+                    fading.insert(*coord);
+                }
+            }
+        }
+
+        loop {
+            let (coord, data) = match self.results.try_recv() {
+                Ok(loaded) => loaded,
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    if result.is_ok() && self.pending_count() > 0 {
+                        result = Err(loader_stopped());
+                    }
+                    break;
+                }
+            };
+            // Skip chunks unloaded while they were loading
+            if self.states.get(&coord) != Some(&ChunkState::Loading) {
+                continue;
+            }
+            let objects = data.and_then(|data| (self.builder)(coord, data));
+            match objects {
+                Ok(objects) => {
+                    let tag = coord.tag();
+                    for mut object in objects {
+                        object.add_tag(&tag);
+                        scene.add_object(object);
+                    }
+                    let state = if self.fade_duration > 0.0 {
+                        ChunkState::FadingIn(0.0)
+                    } else {
+                        ChunkState::Loaded
+                    };
+                    self.states.insert(coord, state);
+                    fading.insert(coord);
+                    for hook in &mut self.load_hooks {
+                        hook(coord);
+                    }
+                }
+                Err(error) => {
+                    self.states
+                        .insert(coord, ChunkState::Failed(self.retry_delay));
+                    if result.is_ok() {
+                        result = Err(error);
+                    }
+                }
+            }
+        }
+
+        if !self.fade_hooks.is_empty() {
+            for coord in fading {
+                let progress = match self.states.get(&coord) {
+                    Some(ChunkState::FadingIn(progress)) => *progress,
+                    Some(ChunkState::Loaded) => 1.0,
+                    _ => continue,
+                };
+                let tag = coord.tag();
+                for object in scene.objects_with_tag_mut(&tag) {
+                    for hook in &mut self.fade_hooks {
+                        hook(coord, progress, object);
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    /// Removes the objects of all loaded chunks from the scene and forgets all chunks.
+    ///
+    /// Chunks still loading are discarded when they arrive.
+    pub fn unload_all(&mut self, scene: &mut Scene) {
+        for (coord, state) in std::mem::take(&mut self.states) {
+            if state.has_objects() {
+                for hook in &mut self.unload_hooks {
+                    hook(coord);
+                }
+                let tag = coord.tag();
+                scene.remove_objects(|object| object.has_tag(&tag));
+            }
+        }
+    }
+}
+
+/// The error reported once the background thread has stopped.
+fn loader_stopped() -> Errors {
+    Errors::StreamingError(
+        "the loader thread has stopped, e.g. because the loader panicked".to_string(),
+    )
+}
@@ -0,0 +1,174 @@
+//! # Toon Module
+//!
+//! This module provides toon (cel) shading for stylized objects. Instead of replacing the
+//! lit shaders, it changes how they turn light into brightness: the diffuse term is snapped
+//! to a few bands, or looked up in a ramp texture, and a rim light brightens the silhouette.
+//! Shadows, lightmaps, projectors and fog keep working as before.
+//!
+//! Lit shaders can paste `TOON_SHADING_GLSL` into their fragment shader, pass their diffuse
+//! term through `toonDiffuse(diffuse)` and add `toonRim(normal, viewDir)` to the lit color.
+//! Objects without `ToonShading` are shaded smoothly and get no rim light.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::scene::{Object, ToonShading};
+//! use glwfr::cgmath::Vector3;
+//!
+//! fn make_toon(object: &mut Object) {
+//!     let mut toon = ToonShading::new(3);
+//!     toon.rim_color = Vector3::new(1.0, 0.9, 0.8);
+//!     toon.rim_strength = 0.6;
+//!     object.toon = Some(toon);
+//! }
+//! ```
+//!
+//! A matching fragment shader:
+//!
+//! ```glsl
+//! float diffuse = toonDiffuse(max(dot(normal, lightDir), 0.0));
+//! vec3 color = albedo * (ambientColor + diffuse * lightColor);
+//! color += toonRim(normal, viewDir);
+//! ```
+
+use crate::graphics::gl_wrapper::ShaderProgram;
+use crate::graphics::texture::Texture;
+use crate::graphics::texture_unit::TextureUnit;
+use cgmath::Vector3;
+use std::rc::Rc;
+
+/// GLSL helpers for toon shading in a fragment shader.
+///
+/// Declares the uniforms set by `ToonShading::apply`, a `toonDiffuse(diffuse)` function
+/// quantizing a diffuse term in `[0, 1]` and a `toonRim(normal, viewDir)` function returning
+/// the rim light to add to the color. Both leave the shading unchanged for objects without
+/// toon shading.
+pub const TOON_SHADING_GLSL: &str = r#"
+uniform int useToonShading;
+uniform int toonSteps;
+uniform int hasToonRamp;
+uniform sampler2D toonRamp;
+uniform float toonSmoothness;
+uniform vec3 toonRimColor;
+uniform float toonRimStrength;
+uniform float toonRimPower;
+
+float toonDiffuse(float diffuse) {
+    if (useToonShading == 0) {
+        return diffuse;
+    }
+    diffuse = clamp(diffuse, 0.0, 1.0);
+    if (hasToonRamp == 1) {
+        return texture(toonRamp, vec2(diffuse, 0.5)).r;
+    }
+    float steps = float(max(toonSteps, 2));
+    float scaled = diffuse * steps;
+    // Blend into the next band over the last toonSmoothness of each band
+    float blend = smoothstep(1.0 - max(toonSmoothness, 0.0001), 1.0, fract(scaled));
+    return min((floor(scaled) + blend) / (steps - 1.0), 1.0);
+}
+
+vec3 toonRim(vec3 normal, vec3 viewDir) {
+    if (useToonShading == 0) {
+        return vec3(0.0);
+    }
+    float rim = 1.0 - max(dot(normalize(normal), normalize(viewDir)), 0.0);
+    return toonRimColor * pow(rim, toonRimPower) * toonRimStrength;
+}
+"#;
+
+/// The toon shading settings of an object.
+#[derive(Clone)]
+pub struct ToonShading {
+    /// The number of brightness levels, from unlit to fully lit, the diffuse lighting is
+    /// snapped to. At least `2`.
+    pub steps: u32,
+    /// A texture mapping the diffuse term, along its width, to the shaded brightness in its
+    /// red channel. Overrides `steps` when set.
+    pub ramp: Option<Rc<Texture>>,
+    /// The texture unit the ramp is bound to.
+    pub ramp_unit: TextureUnit,
+    /// How soft the edges between bands are, from `0.0` (hard) to `1.0` (smooth).
+    pub smoothness: f32,
+    /// The color of the rim light.
+    pub rim_color: Vector3<f32>,
+    /// A factor the rim light is multiplied by. `0.0` disables it.
+    pub rim_strength: f32,
+    /// How tightly the rim light hugs the silhouette. Higher values give thinner rims.
+    pub rim_power: f32,
+}
+
+impl ToonShading {
+    /// Creates toon shading with the given number of brightness levels and a white rim light.
+    ///
+    /// # Arguments
+    ///
+    /// * `steps` - The number of brightness levels, e.g. `2` for a lit and an unlit side.
+    pub fn new(steps: u32) -> Self {
+        Self {
+            steps: steps.max(2),
+            ramp: None,
+            ramp_unit: TextureUnit::new(6),
+            smoothness: 0.05,
+            rim_color: Vector3::new(1.0, 1.0, 1.0),
+            rim_strength: 0.3,
+            rim_power: 4.0,
+        }
+    }
+
+    /// Returns the settings using a ramp texture instead of evenly spaced bands.
+    ///
+    /// # Arguments
+    ///
+    /// * `ramp` - A texture mapping the diffuse term to the shaded brightness. Clamp it to
+    ///   the edge and use nearest filtering for hard bands.
+    pub fn with_ramp(mut self, ramp: Rc<Texture>) -> Self {
+        self.ramp = Some(ramp);
+        self
+    }
+
+    /// Binds the ramp and sets the uniforms declared by `TOON_SHADING_GLSL`.
+    ///
+    /// The shader program must be bound before calling this function. Uniforms the shader
+    /// does not declare are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `shader_program` - The shader program applying the toon shading.
+    pub fn apply(&self, shader_program: &ShaderProgram) {
+        shader_program.try_set_uniform("useToonShading", 1);
+        shader_program.try_set_uniform("toonSteps", self.steps.max(2) as i32);
+        match &self.ramp {
+            Some(ramp) => {
+                ramp.bind_to_unit(self.ramp_unit);
+                shader_program.try_set_uniform("hasToonRamp", 1);
+                shader_program.try_set_uniform("toonRamp", self.ramp_unit.index() as i32);
+            }
+            None => {
+                shader_program.try_set_uniform("hasToonRamp", 0);
+            }
+        }
+        shader_program.try_set_uniform("toonSmoothness", self.smoothness.clamp(0.0, 1.0));
+        shader_program.try_set_uniform("toonRimColor", self.rim_color);
+        shader_program.try_set_uniform("toonRimStrength", self.rim_strength.max(0.0));
+        shader_program.try_set_uniform("toonRimPower", self.rim_power.max(0.0));
+    }
+}
+
+impl PartialEq for ToonShading {
+    /// Compares the settings, with ramps equal if they are the same texture.
+    fn eq(&self, other: &Self) -> bool {
+        let same_ramp = match (&self.ramp, &other.ramp) {
+            (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        };
+        same_ramp
+            && self.steps == other.steps
+            && self.ramp_unit == other.ramp_unit
+            && self.smoothness == other.smoothness
+            && self.rim_color == other.rim_color
+            && self.rim_strength == other.rim_strength
+            && self.rim_power == other.rim_power
+    }
+}
@@ -158,6 +158,28 @@ impl Transform {
         self.matrix
     }
 
+    /// Returns the transform of a child placed at `local` relative to this transform.
+    ///
+    /// Positions are scaled and rotated by this transform, rotations and scales are
+    /// multiplied. The result is exact for uniform scales; a non-uniform parent scale is
+    /// applied along the child's own axes instead of shearing it.
+    ///
+    /// # Arguments
+    ///
+    /// * `local` - The transform of the child relative to this transform.
+    pub fn combine(&self, local: &Transform) -> Transform {
+        let mut combined = Transform::new();
+        combined.set_position(
+            self.position
+                + self
+                    .rotation
+                    .rotate_vector(self.scale.mul_element_wise(local.position)),
+        );
+        combined.set_rotation(self.rotation * local.rotation);
+        combined.set_scale(self.scale.mul_element_wise(local.scale));
+        combined
+    }
+
     /// Updates the transformation matrix by multiplying the translation, rotation, and scale matrices
     /// and sets the `dirty` flag to false. This function is called lazily when the transformation
     /// matrix is requested.
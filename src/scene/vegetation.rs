@@ -0,0 +1,382 @@
+//! # Vegetation Module
+//!
+//! This module scatters instanced meshes such as grass and rocks over the ground. A
+//! `DensityMap` decides how much vegetation grows where, e.g. painted by hand or derived from
+//! noise, and a height function places every instance on the terrain. The instances are
+//! grouped into square cells; each frame, cells outside of the view or beyond the fade
+//! distance are skipped and the transforms of the rest are uploaded to the instance buffer of
+//! the mesh and drawn in a single instanced draw call.
+//!
+//! Instances fade out with a dither pattern instead of popping. Fragment shaders paste
+//! `VEGETATION_FADE_GLSL` and call `vegetationFade(worldPos)`; vertex shaders use
+//! `INSTANCING_GLSL` for the instance transforms, and may use `WIND_GLSL` for sway.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::scene::{DensityMap, Object, ScatterSettings, Vegetation};
+//! use glwfr::cgmath::Point2;
+//!
+//! fn setup(grass: Object) -> Result<Vegetation, glwfr::custom_errors::Errors> {
+//!     let density = DensityMap::from_image("assets/grass_density.png")?;
+//!     let settings = ScatterSettings {
+//!         min: Point2::new(-100.0, -100.0),
+//!         max: Point2::new(100.0, 100.0),
+//!         density: 4.0,
+//!         ..ScatterSettings::default()
+//!     };
+//!     // Flat ground; use the terrain height instead
+//!     let mut vegetation = Vegetation::scatter(grass, &settings, &density, |_x, _z| 0.0);
+//!     vegetation.fade_start = 40.0;
+//!     vegetation.fade_end = 60.0;
+//!     Ok(vegetation)
+//! }
+//!
+//! // Each frame: vegetation.render(scene.get_mut_camera())?;
+//! ```
+
+use crate::custom_errors::Errors;
+use crate::graphics::standard_vertex::InstanceTransform;
+use crate::math::Aabb;
+use crate::scene::{Camera, Object};
+use cgmath::*;
+use std::f32::consts::TAU;
+use std::path::Path;
+
+/// GLSL helpers for the dithered distance fade of vegetation in a fragment shader.
+///
+/// Declares the uniforms set by `Vegetation::render` and a `vegetationFade(worldPos)` function
+/// discarding a growing share of the fragments between the fade start and end distances,
+/// in an ordered 4x4 dither pattern.
+pub const VEGETATION_FADE_GLSL: &str = r#"
+uniform vec3 vegetationCameraPosition;
+uniform float vegetationFadeStart;
+uniform float vegetationFadeEnd;
+
+const float VEGETATION_DITHER[16] = float[16](
+    0.0, 8.0, 2.0, 10.0,
+    12.0, 4.0, 14.0, 6.0,
+    3.0, 11.0, 1.0, 9.0,
+    15.0, 7.0, 13.0, 5.0
+);
+
+void vegetationFade(vec3 worldPos) {
+    float distance = length(worldPos - vegetationCameraPosition);
+    float range = max(vegetationFadeEnd - vegetationFadeStart, 0.0001);
+    float visibility = 1.0 - clamp((distance - vegetationFadeStart) / range, 0.0, 1.0);
+    ivec2 pixel = ivec2(gl_FragCoord.xy) & 3;
+    float threshold = (VEGETATION_DITHER[pixel.y * 4 + pixel.x] + 0.5) / 16.0;
+    if (visibility < threshold) {
+        discard;
+    }
+}
+"#;
+
+/// How densely vegetation grows over an area, from `0.0` (none) to `1.0` (full density).
+#[derive(Debug, Clone, PartialEq)]
+pub struct DensityMap {
+    width: u32,
+    height: u32,
+    values: Vec<f32>,
+}
+
+impl DensityMap {
+    /// Creates a density map from row-major values. Missing values are treated as `0.0`.
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - The number of values per row, along the x axis of the scattered area.
+    /// * `height` - The number of rows, along the z axis of the scattered area.
+    /// * `values` - The densities, clamped to `[0, 1]`.
+    pub fn new(width: u32, height: u32, mut values: Vec<f32>) -> Self {
+        let width = width.max(1);
+        let height = height.max(1);
+        values.resize((width * height) as usize, 0.0);
+        for value in &mut values {
+            *value = value.clamp(0.0, 1.0);
+        }
+        Self {
+            width,
+            height,
+            values,
+        }
+    }
+
+    /// Creates a density map with the same density everywhere.
+    pub fn uniform(density: f32) -> Self {
+        Self::new(1, 1, vec![density])
+    }
+
+    /// Loads a density map from the brightness of an image.
+    ///
+    /// The top row of the image maps to the lowest z of the scattered area.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::TextureLoadError` if the image cannot be loaded.
+    pub fn from_image(path: impl AsRef<Path>) -> Result<Self, Errors> {
+        let image = image::open(path)
+            .map_err(|e| Errors::TextureLoadError(format!("Failed to load density map: {}", e)))?
+            .to_luma32f();
+        let (width, height) = image.dimensions();
+        Ok(Self::new(width, height, image.into_raw()))
+    }
+
+    /// Returns the size of the map as `(width, height)`.
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Returns the bilinearly filtered density at the given coordinates in `[0, 1]`.
+    pub fn sample(&self, u: f32, v: f32) -> f32 {
+        let x = (u.clamp(0.0, 1.0) * self.width as f32 - 0.5).max(0.0);
+        let y = (v.clamp(0.0, 1.0) * self.height as f32 - 0.5).max(0.0);
+        let (x0, y0) = (x as u32, y as u32);
+        let x1 = (x0 + 1).min(self.width - 1);
+        let y1 = (y0 + 1).min(self.height - 1);
+        let (tx, ty) = (x - x0 as f32, y - y0 as f32);
+        let value = |x: u32, y: u32| self.values[(y * self.width + x) as usize];
+        let top = value(x0, y0) + (value(x1, y0) - value(x0, y0)) * tx;
+        let bottom = value(x0, y1) + (value(x1, y1) - value(x0, y1)) * tx;
+        top + (bottom - top) * ty
+    }
+}
+
+/// How instances are distributed by `Vegetation::scatter`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScatterSettings {
+    /// The corner of the scattered area with the lowest x and z.
+    pub min: Point2<f32>,
+    /// The corner of the scattered area with the highest x and z.
+    pub max: Point2<f32>,
+    /// The number of instances per square unit where the density map is `1.0`.
+    pub density: f32,
+    /// The width and depth of the cells culled together.
+    pub cell_size: f32,
+    /// The smallest and largest random uniform scale of the instances.
+    pub scale: (f32, f32),
+    /// Whether the instances are rotated randomly around the y axis.
+    pub random_rotation: bool,
+    /// The seed of the random placement; the same seed gives the same vegetation.
+    pub seed: u32,
+}
+
+impl Default for ScatterSettings {
+    fn default() -> Self {
+        Self {
+            min: Point2::new(-50.0, -50.0),
+            max: Point2::new(50.0, 50.0),
+            density: 1.0,
+            cell_size: 16.0,
+            scale: (0.8, 1.2),
+            random_rotation: true,
+            seed: 0,
+        }
+    }
+}
+
+/// A square cell of scattered instances.
+struct VegetationCell {
+    bounds: Aabb,
+    instances: Vec<InstanceTransform>,
+}
+
+/// Instances of an object scattered over an area and drawn with per-cell culling.
+pub struct Vegetation {
+    object: Object,
+    cells: Vec<VegetationCell>,
+    visible_instances: usize,
+    /// The distance from the camera where instances start to fade out.
+    pub fade_start: f32,
+    /// The distance from the camera where instances are gone. Cells beyond it are skipped.
+    pub fade_end: f32,
+}
+
+impl Vegetation {
+    /// Scatters instances of an object over the area of the settings.
+    ///
+    /// The instance transforms are applied before the object's own transform, which is
+    /// usually left at the identity. Set `Object::bounds` for tight cell culling; without
+    /// it, instances are assumed to fit into a unit cube scaled with them.
+    ///
+    /// # Arguments
+    ///
+    /// * `object` - The object drawn for every instance. Its shader must use
+    ///   `INSTANCING_GLSL`, and `VEGETATION_FADE_GLSL` for the distance fade.
+    /// * `settings` - How the instances are distributed.
+    /// * `density_map` - How densely the instances grow, stretched over the area.
+    /// * `height` - Returns the ground height at a world-space `(x, z)` position.
+    pub fn scatter(
+        object: Object,
+        settings: &ScatterSettings,
+        density_map: &DensityMap,
+        height: impl Fn(f32, f32) -> f32,
+    ) -> Self {
+        let size = settings.max - settings.min;
+        let cell_size = settings.cell_size.max(0.001);
+        let columns = (size.x / cell_size).ceil().max(1.0) as i32;
+        let rows = (size.y / cell_size).ceil().max(1.0) as i32;
+        let local_bounds = object
+            .bounds
+            .unwrap_or_else(|| Aabb::from_center(Point3::origin(), Vector3::new(0.5, 0.5, 0.5)));
+
+        let mut cells = Vec::new();
+        for row in 0..rows {
+            for column in 0..columns {
+                let cell_min =
+                    settings.min + Vector2::new(column as f32 * cell_size, row as f32 * cell_size);
+                let cell_max = Point2::new(
+                    (cell_min.x + cell_size).min(settings.max.x),
+                    (cell_min.y + cell_size).min(settings.max.y),
+                );
+                let area = (cell_max.x - cell_min.x) * (cell_max.y - cell_min.y);
+                let expected = (area * settings.density.max(0.0)).max(0.0);
+                let random = |index: u32, axis: u32| {
+                    hash(
+                        settings.seed,
+                        column,
+                        row,
+                        index.wrapping_mul(8).wrapping_add(axis),
+                    )
+                };
+                // Round the candidate count randomly so fractional densities average out
+                let candidates =
+                    expected.floor() as u32 + (random(u32::MAX, 0) < expected.fract()) as u32;
+
+                let mut bounds: Option<Aabb> = None;
+                let mut instances = Vec::new();
+                for index in 0..candidates {
+                    let x = cell_min.x + (cell_max.x - cell_min.x) * random(index, 0);
+                    let z = cell_min.y + (cell_max.y - cell_min.y) * random(index, 1);
+                    let u = (x - settings.min.x) / size.x.max(0.001);
+                    let v = (z - settings.min.y) / size.y.max(0.001);
+                    if random(index, 2) >= density_map.sample(u, v) {
+                        continue;
+                    }
+                    let (min_scale, max_scale) = settings.scale;
+                    let scale = min_scale + (max_scale - min_scale) * random(index, 3);
+                    let yaw = if settings.random_rotation {
+                        random(index, 4) * TAU
+                    } else {
+                        0.0
+                    };
+                    let model = Matrix4::from_translation(Vector3::new(x, height(x, z), z))
+                        * Matrix4::from_angle_y(Rad(yaw))
+                        * Matrix4::from_scale(scale);
+                    let instance_bounds = local_bounds.transformed(&model);
+                    bounds = Some(match bounds {
+                        Some(bounds) => bounds.union(&instance_bounds),
+                        None => instance_bounds,
+                    });
+                    instances.push(InstanceTransform::from(model));
+                }
+                if let Some(bounds) = bounds {
+                    cells.push(VegetationCell { bounds, instances });
+                }
+            }
+        }
+
+        Self {
+            object,
+            cells,
+            visible_instances: 0,
+            fade_start: 50.0,
+            fade_end: 70.0,
+        }
+    }
+
+    /// Returns the object drawn for every instance.
+    pub fn object(&self) -> &Object {
+        &self.object
+    }
+
+    /// Returns the object drawn for every instance mutably, e.g. to change its material.
+    pub fn object_mut(&mut self) -> &mut Object {
+        &mut self.object
+    }
+
+    /// Returns the total number of scattered instances.
+    pub fn instance_count(&self) -> usize {
+        self.cells.iter().map(|cell| cell.instances.len()).sum()
+    }
+
+    /// Returns the number of cells holding instances.
+    pub fn cell_count(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// Returns the number of instances drawn by the last `render`.
+    pub fn visible_instances(&self) -> usize {
+        self.visible_instances
+    }
+
+    /// Draws the instances of the cells in view and within the fade distance.
+    ///
+    /// The fade distances and camera position are uploaded as the uniforms of
+    /// `VEGETATION_FADE_GLSL`, along with the uniforms set by `Object::render_instanced`.
+    ///
+    /// # Arguments
+    ///
+    /// * `camera` - The camera to render with.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::OpenGlError` if the instance buffer cannot be created.
+    pub fn render(&mut self, camera: &Camera) -> Result<(), Errors> {
+        let view_matrix = camera.view_matrix();
+        let projection_matrix = camera.projection_matrix();
+        let view_projection = projection_matrix * view_matrix;
+        let model = self.object.transform.matrix();
+        let max_distance = self.fade_end.max(self.fade_start);
+
+        let mut instances = Vec::new();
+        for cell in &self.cells {
+            let bounds = cell.bounds.transformed(&model);
+            if bounds.distance_to_point(camera.position) > max_distance
+                || is_outside_view(&bounds, view_projection)
+            {
+                continue;
+            }
+            instances.extend_from_slice(&cell.instances);
+        }
+        self.visible_instances = instances.len();
+        if instances.is_empty() {
+            return Ok(());
+        }
+
+        self.object.mesh().set_instances(&instances)?;
+        let shader_program = &self.object.shader_program;
+        shader_program.bind();
+        shader_program.try_set_uniform("vegetationCameraPosition", camera.position);
+        shader_program.try_set_uniform("vegetationFadeStart", self.fade_start);
+        shader_program.try_set_uniform("vegetationFadeEnd", max_distance);
+        self.object
+            .render_instanced(view_matrix, projection_matrix, instances.len() as u32);
+        Ok(())
+    }
+}
+
+/// Checks if all corners of a box are outside of the same clip plane.
+fn is_outside_view(bounds: &Aabb, view_projection: Matrix4<f32>) -> bool {
+    let corners = bounds
+        .corners()
+        .map(|corner| view_projection * corner.to_homogeneous());
+    (0..3).any(|axis| {
+        corners.iter().all(|corner| corner[axis] < -corner.w)
+            || corners.iter().all(|corner| corner[axis] > corner.w)
+    })
+}
+
+/// Returns a pseudo-random value in `[0, 1)` for a cell, candidate and value index.
+fn hash(seed: u32, x: i32, y: i32, index: u32) -> f32 {
+    let mut h = (x as u32).wrapping_mul(0x8da6_b343)
+        ^ (y as u32).wrapping_mul(0xd816_3841)
+        ^ index.wrapping_mul(0xcb1a_b31f)
+        ^ seed.wrapping_mul(0x9e37_79b9);
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x7feb_352d);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x846c_a68b);
+    h ^= h >> 16;
+    (h >> 8) as f32 / (1u32 << 24) as f32
+}
@@ -0,0 +1,334 @@
+//! # Weather Module
+//!
+//! This module provides rain and snow, and wet surfaces. A `WeatherEmitter` keeps a fixed
+//! number of drops or flakes falling inside a box around the camera, wrapping them around the
+//! box as they leave it, so precipitation looks endless at a constant cost. The particles are
+//! simulated on the CPU, drift with the wind of the scene environment and are drawn in a
+//! single instanced draw call, stretched into streaks for rain and facing the camera for snow.
+//!
+//! Wet surfaces are controlled by `SceneEnvironment::wetness`, from `0.0` (dry, the default)
+//! to `1.0` (soaked). Lit fragment shaders paste `WETNESS_GLSL`, darken their albedo with
+//! `wetAlbedo(albedo, normal)` and raise their reflectivity with
+//! `wetReflectivity(reflectivity, normal)`.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::scene::{Object, Scene, WeatherEmitter};
+//!
+//! // `streak` is an object with a unit quad in the XY plane and a blended material whose
+//! // vertex shader uses `INSTANCING_GLSL`
+//! fn setup(streak: Object) -> WeatherEmitter {
+//!     let mut rain = WeatherEmitter::rain(streak, 4000);
+//!     rain.intensity = 0.7;
+//!     rain
+//! }
+//!
+//! fn frame(scene: &mut Scene, rain: &mut WeatherEmitter, delta_time: f32) {
+//!     let camera_position = scene.get_mut_camera().position;
+//!     let environment = scene.get_mut_environment();
+//!     rain.update(delta_time, camera_position, environment.wind.as_ref());
+//!     environment.wetness = rain.update_wetness(environment.wetness, delta_time);
+//!
+//!     scene.render();
+//!     // rain.render(scene.get_mut_camera())?;
+//! }
+//! ```
+
+use crate::custom_errors::Errors;
+use crate::graphics::standard_vertex::InstanceTransform;
+use crate::math::noise::Xorshift;
+use crate::scene::{Camera, Object, Wind};
+use cgmath::*;
+
+/// GLSL helpers for wet surfaces in a fragment shader.
+///
+/// Declares the `wetness` uniform set by `SceneEnvironment::apply`, a
+/// `wetAlbedo(albedo, normal)` function darkening the albedo and a
+/// `wetReflectivity(reflectivity, normal)` function making the surface more mirror-like.
+/// Surfaces facing up, where water collects, get wetter than walls. Both leave the surface
+/// unchanged when the scene is dry.
+pub const WETNESS_GLSL: &str = r#"
+uniform float wetness;
+
+float surfaceWetness(vec3 normal) {
+    float facingUp = clamp(normalize(normal).y, 0.0, 1.0);
+    return clamp(wetness, 0.0, 1.0) * mix(0.5, 1.0, facingUp);
+}
+
+vec3 wetAlbedo(vec3 albedo, vec3 normal) {
+    // Water fills the pores of a surface, so less light scatters back out of it
+    return albedo * mix(1.0, 0.45, surfaceWetness(normal));
+}
+
+float wetReflectivity(float reflectivity, vec3 normal) {
+    return mix(reflectivity, max(reflectivity, 0.7), surfaceWetness(normal));
+}
+"#;
+
+/// The kind of precipitation of a `WeatherEmitter`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WeatherKind {
+    /// Fast drops, drawn as streaks along their velocity.
+    Rain,
+    /// Slow flakes fluttering sideways, drawn facing the camera.
+    Snow,
+}
+
+/// A falling drop or flake.
+#[derive(Debug, Clone, Copy)]
+struct WeatherParticle {
+    position: Point3<f32>,
+    /// A factor the fall speed is multiplied by, so particles do not fall in lockstep.
+    speed: f32,
+    /// The offset of the flutter of snow flakes in radians.
+    phase: f32,
+}
+
+/// Rain or snow falling around the camera.
+pub struct WeatherEmitter {
+    object: Object,
+    particles: Vec<WeatherParticle>,
+    center: Point3<f32>,
+    velocity: Vector3<f32>,
+    time: f32,
+    visible_particles: usize,
+    /// The kind of precipitation.
+    pub kind: WeatherKind,
+    /// The share of the particles that are drawn, from `0.0` (stopped) to `1.0` (heaviest).
+    pub intensity: f32,
+    /// The half extents of the box around the camera the particles fall in.
+    pub area: Vector3<f32>,
+    /// How fast the particles fall in world units per second.
+    pub fall_speed: f32,
+    /// The width and length of a particle in world units.
+    pub particle_size: Vector2<f32>,
+    /// How strongly the wind pushes the particles, in world units per second at a wind
+    /// strength of `1.0`.
+    pub wind_drift: f32,
+    /// How far snow flakes flutter sideways in world units per second.
+    pub flutter: f32,
+    /// How fast rain wets dry surfaces, as the wetness gained per second at full intensity.
+    pub wetting_rate: f32,
+    /// How fast surfaces dry without rain, as the wetness lost per second.
+    pub drying_rate: f32,
+}
+
+impl WeatherEmitter {
+    /// Creates an emitter of the given kind with a fixed number of particles.
+    ///
+    /// The mesh of the object is drawn for every particle. It should be a unit quad in the XY
+    /// plane centered on the origin, which is scaled to the particle size, and its vertex
+    /// shader should use `INSTANCING_GLSL`.
+    ///
+    /// # Arguments
+    ///
+    /// * `object` - The object drawn for every particle.
+    /// * `kind` - The kind of precipitation.
+    /// * `particle_count` - The number of particles at full intensity.
+    pub fn new(object: Object, kind: WeatherKind, particle_count: usize) -> Self {
+        let area = Vector3::new(15.0, 10.0, 15.0);
+        let mut generator = Xorshift::new(0x2545_f491);
+        let mut random = move || generator.next_f32();
+        let particles = (0..particle_count)
+            .map(|_| WeatherParticle {
+                position: Point3::new(
+                    (random() * 2.0 - 1.0) * area.x,
+                    (random() * 2.0 - 1.0) * area.y,
+                    (random() * 2.0 - 1.0) * area.z,
+                ),
+                speed: 0.8 + random() * 0.4,
+                phase: random() * std::f32::consts::TAU,
+            })
+            .collect();
+
+        let (fall_speed, particle_size, wind_drift) = match kind {
+            WeatherKind::Rain => (12.0, Vector2::new(0.02, 0.5), 2.0),
+            WeatherKind::Snow => (1.0, Vector2::new(0.05, 0.05), 3.0),
+        };
+        Self {
+            object,
+            particles,
+            center: Point3::origin(),
+            velocity: Vector3::new(0.0, -fall_speed, 0.0),
+            time: 0.0,
+            visible_particles: 0,
+            kind,
+            intensity: 1.0,
+            area,
+            fall_speed,
+            particle_size,
+            wind_drift,
+            flutter: 0.3,
+            wetting_rate: 0.05,
+            drying_rate: 0.02,
+        }
+    }
+
+    /// Creates a rain emitter with fast, thin streaks.
+    ///
+    /// # Arguments
+    ///
+    /// * `object` - The object drawn for every drop.
+    /// * `particle_count` - The number of drops at full intensity.
+    pub fn rain(object: Object, particle_count: usize) -> Self {
+        Self::new(object, WeatherKind::Rain, particle_count)
+    }
+
+    /// Creates a snow emitter with slow, fluttering flakes.
+    ///
+    /// # Arguments
+    ///
+    /// * `object` - The object drawn for every flake.
+    /// * `particle_count` - The number of flakes at full intensity.
+    pub fn snow(object: Object, particle_count: usize) -> Self {
+        Self::new(object, WeatherKind::Snow, particle_count)
+    }
+
+    /// Returns the object drawn for every particle.
+    pub fn object(&self) -> &Object {
+        &self.object
+    }
+
+    /// Returns the object drawn for every particle mutably, e.g. to change its material.
+    pub fn object_mut(&mut self) -> &mut Object {
+        &mut self.object
+    }
+
+    /// Returns the number of particles at full intensity.
+    pub fn particle_count(&self) -> usize {
+        self.particles.len()
+    }
+
+    /// Returns the number of particles drawn by the last `render`.
+    pub fn visible_particles(&self) -> usize {
+        self.visible_particles
+    }
+
+    /// Moves the particles and the box they fall in.
+    ///
+    /// # Arguments
+    ///
+    /// * `delta_time` - The time since the last update in seconds.
+    /// * `camera_position` - The position the box is centered on.
+    /// * `wind` - The wind pushing the particles, e.g. `SceneEnvironment::wind`.
+    pub fn update(&mut self, delta_time: f32, camera_position: Point3<f32>, wind: Option<&Wind>) {
+        self.time += delta_time;
+        self.center = camera_position;
+
+        let mut drift = Vector3::zero();
+        if let Some(wind) = wind {
+            let horizontal = Vector3::new(wind.direction.x, 0.0, wind.direction.z);
+            if horizontal.magnitude2() > 0.0 {
+                drift = horizontal.normalize() * wind.strength * self.wind_drift;
+            }
+        }
+        self.velocity = drift - Vector3::unit_y() * self.fall_speed;
+
+        let area = self.area.map(|extent| extent.max(0.001));
+        for particle in &mut self.particles {
+            let mut velocity = drift - Vector3::unit_y() * self.fall_speed * particle.speed;
+            if self.kind == WeatherKind::Snow {
+                let angle = self.time * 1.3 + particle.phase;
+                velocity += Vector3::new(angle.cos(), 0.0, (angle * 0.7).sin()) * self.flutter;
+            }
+            let position = particle.position + velocity * delta_time;
+
+            // Wrap around the box, so particles leaving it re-enter on the opposite side
+            let offset = position - self.center;
+            particle.position = self.center
+                + Vector3::new(
+                    (offset.x + area.x).rem_euclid(2.0 * area.x) - area.x,
+                    (offset.y + area.y).rem_euclid(2.0 * area.y) - area.y,
+                    (offset.z + area.z).rem_euclid(2.0 * area.z) - area.z,
+                );
+        }
+    }
+
+    /// Returns the wetness of the scene after a frame of this weather.
+    ///
+    /// Rain moves the wetness up towards its intensity at the wetting rate; snow, or rain
+    /// with a lower intensity, lets surfaces dry at the drying rate.
+    ///
+    /// # Arguments
+    ///
+    /// * `wetness` - The current wetness, e.g. `SceneEnvironment::wetness`.
+    /// * `delta_time` - The time since the last update in seconds.
+    pub fn update_wetness(&self, wetness: f32, delta_time: f32) -> f32 {
+        let intensity = self.intensity.clamp(0.0, 1.0);
+        let target = match self.kind {
+            WeatherKind::Rain => intensity,
+            WeatherKind::Snow => 0.0,
+        };
+        if wetness < target {
+            (wetness + self.wetting_rate * intensity * delta_time).min(target)
+        } else {
+            (wetness - self.drying_rate * delta_time).max(target)
+        }
+    }
+
+    /// Draws the particles for the current intensity.
+    ///
+    /// # Arguments
+    ///
+    /// * `camera` - The camera to render with, which the particles are turned towards.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Errors::OpenGlError` if the instance buffer cannot be created.
+    pub fn render(&mut self, camera: &Camera) -> Result<(), Errors> {
+        let view_matrix = camera.view_matrix();
+        let projection_matrix = camera.projection_matrix();
+        let count = (self.particles.len() as f32 * self.intensity.clamp(0.0, 1.0)).round();
+        let count = (count as usize).min(self.particles.len());
+        self.visible_particles = count;
+        if count == 0 {
+            return Ok(());
+        }
+
+        // The rows of the view matrix are the camera axes in world space
+        let camera_right = Vector3::new(view_matrix.x.x, view_matrix.y.x, view_matrix.z.x);
+        let camera_up = Vector3::new(view_matrix.x.y, view_matrix.y.y, view_matrix.z.y);
+        let fall_direction = if self.velocity.magnitude2() > 0.0 {
+            -self.velocity.normalize()
+        } else {
+            Vector3::unit_y()
+        };
+        let Vector2 {
+            x: width,
+            y: length,
+        } = self.particle_size;
+
+        let instances: Vec<InstanceTransform> = self.particles[..count]
+            .iter()
+            .map(|particle| {
+                let (right, up) = match self.kind {
+                    WeatherKind::Rain => {
+                        // Turn the streak around its fall direction to face the camera
+                        let to_camera = camera.position - particle.position;
+                        let right = fall_direction.cross(to_camera);
+                        let right = if right.magnitude2() > 0.0 {
+                            right.normalize()
+                        } else {
+                            camera_right
+                        };
+                        (right, fall_direction)
+                    }
+                    WeatherKind::Snow => (camera_right, camera_up),
+                };
+                let forward = right.cross(up);
+                InstanceTransform::from(Matrix4::from_cols(
+                    (right * width).extend(0.0),
+                    (up * length).extend(0.0),
+                    forward.extend(0.0),
+                    particle.position.to_homogeneous(),
+                ))
+            })
+            .collect();
+
+        self.object.mesh().set_instances(&instances)?;
+        self.object
+            .render_instanced(view_matrix, projection_matrix, count as u32);
+        Ok(())
+    }
+}
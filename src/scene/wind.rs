@@ -0,0 +1,202 @@
+//! # Wind Module
+//!
+//! This module provides procedural wind sway for foliage such as grass, bushes and trees.
+//! The scene-wide `Wind` sets the direction, strength and gusts, and advances its time every
+//! frame. Objects with a `WindSway` are bent along the wind in the vertex shader, weighted
+//! per vertex so roots and trunks stay in place while tips and leaves move.
+//!
+//! Vertex shaders paste `WIND_GLSL` and call `applyWind(worldPos, localPos, color)` after
+//! applying the model matrix. `Scene::render` uploads the wind with the environment, and
+//! objects upload their sway settings when rendered.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use glwfr::scene::{Object, Scene, Wind, WindSway, WindWeight};
+//! use glwfr::cgmath::Vector3;
+//!
+//! fn setup(scene: &mut Scene, grass: &mut Object) {
+//!     let mut wind = Wind::new(Vector3::new(1.0, 0.0, 0.3), 0.4);
+//!     wind.gust_strength = 0.3;
+//!     scene.get_mut_environment().wind = Some(wind);
+//!
+//!     // Bend the grass more towards its tips, which are 0.5 units above its origin
+//!     grass.wind_sway = Some(WindSway::new(WindWeight::Height { height: 0.5 }));
+//! }
+//!
+//! fn frame(scene: &mut Scene, delta_time: f32) {
+//!     if let Some(wind) = &mut scene.get_mut_environment().wind {
+//!         wind.update(delta_time);
+//!     }
+//!     scene.render();
+//! }
+//! ```
+
+use crate::graphics::gl_wrapper::ShaderProgram;
+use cgmath::*;
+
+/// GLSL helpers for wind sway in a vertex shader.
+///
+/// Declares the uniforms set by `Wind::apply` and `WindSway::apply` and an
+/// `applyWind(worldPos, localPos, color)` function returning the displaced world-space
+/// position. `color` is the vertex color, used by `WindWeight::VertexColor`; pass
+/// `vec4(1.0)` for meshes without vertex colors. Objects without a `WindSway` are not moved.
+pub const WIND_GLSL: &str = r#"
+uniform vec3 windDirection;
+uniform float windStrength;
+uniform float windFrequency;
+uniform float windGustStrength;
+uniform float windGustFrequency;
+uniform float windTime;
+uniform int useWindSway;
+uniform int windWeightSource;
+uniform float windHeight;
+uniform float windAmplitude;
+
+vec3 applyWind(vec3 worldPos, vec3 localPos, vec4 color) {
+    if (useWindSway == 0) {
+        return worldPos;
+    }
+    float weight = windWeightSource == 0
+        ? color.r
+        : clamp(localPos.y / max(windHeight, 0.0001), 0.0, 1.0);
+    // Bending grows quadratically, so stems curve instead of shearing
+    weight *= weight;
+
+    // Neighbouring plants sway slightly out of phase
+    float phase = dot(worldPos.xz, vec2(0.37, 0.21));
+    float sway = sin(windTime * windFrequency + phase) * 0.5 + 0.5;
+    float flutter = sin(windTime * windFrequency * 2.7 + phase * 3.1) * 0.15;
+    float gust = max(sin(windTime * windGustFrequency + phase * 0.1), 0.0) * windGustStrength;
+
+    float bend = (windStrength * sway + gust + flutter * windStrength) * windAmplitude * weight;
+    vec3 offset = windDirection * bend;
+    // Keep the length of the stem roughly constant by lowering bent tips
+    offset.y -= bend * bend * 0.5;
+    return worldPos + offset;
+}
+"#;
+
+/// Scene-wide wind, uploaded by `SceneEnvironment::apply`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Wind {
+    /// The horizontal direction the wind blows towards.
+    pub direction: Vector3<f32>,
+    /// The steady bend of foliage in world units at full weight.
+    pub strength: f32,
+    /// How fast foliage sways, in radians per second.
+    pub frequency: f32,
+    /// The additional bend of gusts in world units.
+    pub gust_strength: f32,
+    /// How often gusts come, in radians per second.
+    pub gust_frequency: f32,
+    /// The time driving the animation in seconds, advanced by `update`.
+    pub time: f32,
+}
+
+impl Wind {
+    /// Creates a wind blowing in the given direction, with gentle gusts.
+    ///
+    /// # Arguments
+    ///
+    /// * `direction` - The direction the wind blows towards. The vertical part is ignored.
+    /// * `strength` - The steady bend of foliage in world units at full weight.
+    pub fn new(direction: Vector3<f32>, strength: f32) -> Self {
+        Self {
+            direction,
+            strength,
+            frequency: 1.5,
+            gust_strength: strength * 0.5,
+            gust_frequency: 0.3,
+            time: 0.0,
+        }
+    }
+
+    /// Advances the wind animation.
+    ///
+    /// # Arguments
+    ///
+    /// * `delta_time` - The time since the last update in seconds.
+    pub fn update(&mut self, delta_time: f32) {
+        self.time += delta_time;
+    }
+
+    /// Sets the uniforms of the wind declared by `WIND_GLSL`.
+    ///
+    /// The shader program must be bound before calling this function. Uniforms the shader
+    /// does not declare are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `shader_program` - The shader program applying the wind.
+    pub fn apply(&self, shader_program: &ShaderProgram) {
+        let horizontal = Vector3::new(self.direction.x, 0.0, self.direction.z);
+        let direction = if horizontal.magnitude2() > 0.0 {
+            horizontal.normalize()
+        } else {
+            Vector3::zero()
+        };
+        shader_program.try_set_uniform("windDirection", direction);
+        shader_program.try_set_uniform("windStrength", self.strength);
+        shader_program.try_set_uniform("windFrequency", self.frequency);
+        shader_program.try_set_uniform("windGustStrength", self.gust_strength);
+        shader_program.try_set_uniform("windGustFrequency", self.gust_frequency);
+        shader_program.try_set_uniform("windTime", self.time);
+    }
+}
+
+/// Where the per-vertex weight of the wind sway comes from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WindWeight {
+    /// The red channel of the vertex color, e.g. painted black at the roots and white at
+    /// the tips.
+    VertexColor,
+    /// The height of the vertex above the origin of the mesh, reaching full weight at
+    /// `height`.
+    Height { height: f32 },
+}
+
+/// The wind sway settings of an object.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindSway {
+    /// Where the per-vertex weight comes from.
+    pub weight: WindWeight,
+    /// A factor the bend is multiplied by, e.g. lower for stiff bushes than for grass.
+    pub amplitude: f32,
+}
+
+impl WindSway {
+    /// Creates a wind sway with full amplitude.
+    ///
+    /// # Arguments
+    ///
+    /// * `weight` - Where the per-vertex weight comes from.
+    pub fn new(weight: WindWeight) -> Self {
+        Self {
+            weight,
+            amplitude: 1.0,
+        }
+    }
+
+    /// Sets the uniforms of the sway declared by `WIND_GLSL`.
+    ///
+    /// The shader program must be bound before calling this function. Uniforms the shader
+    /// does not declare are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `shader_program` - The shader program applying the sway.
+    pub fn apply(&self, shader_program: &ShaderProgram) {
+        shader_program.try_set_uniform("useWindSway", 1);
+        match self.weight {
+            WindWeight::VertexColor => {
+                shader_program.try_set_uniform("windWeightSource", 0);
+            }
+            WindWeight::Height { height } => {
+                shader_program.try_set_uniform("windWeightSource", 1);
+                shader_program.try_set_uniform("windHeight", height);
+            }
+        }
+        shader_program.try_set_uniform("windAmplitude", self.amplitude);
+    }
+}